@@ -287,6 +287,16 @@ fn bench_hamming_dist_diverse_str_1000iter(b: &mut Bencher) {
     });
 }
 
+#[bench]
+fn bench_quality_hamming_dist_diverse_str_1000iter(b: &mut Bencher) {
+    let qual = vec![30; STR_1.len()];
+    b.iter(|| {
+        for _ in 0..1000 {
+            black_box(quality_hamming(STR_1, STR_2, &qual));
+        }
+    });
+}
+
 #[bench]
 fn bench_levenshtein_dist_equal_str(b: &mut Bencher) {
     b.iter(|| levenshtein(STR_1, STR_1));
@@ -322,6 +332,16 @@ fn bench_simd_hamming_dist_diverse_str_1000iter(b: &mut Bencher) {
     });
 }
 
+#[bench]
+fn bench_simd_quality_hamming_dist_diverse_str_1000iter(b: &mut Bencher) {
+    let qual = vec![30; STR_1.len()];
+    b.iter(|| {
+        for _ in 0..1000 {
+            black_box(simd::quality_hamming(STR_1, STR_2, &qual));
+        }
+    });
+}
+
 #[bench]
 fn bench_simd_levenshtein_dist_equal_str(b: &mut Bencher) {
     b.iter(|| simd::levenshtein(STR_1, STR_1));
@@ -0,0 +1,78 @@
+#![feature(test)]
+
+extern crate test;
+
+use bio::alignment::pairwise::striped::StripedAligner;
+use bio::alignment::pairwise::Aligner;
+use bio::scores::blosum62;
+use rand::prelude::*;
+use test::Bencher;
+
+fn random_seq(rng: &mut StdRng, alphabet: &[u8], len: usize) -> Vec<u8> {
+    (0..len).map(|_| *alphabet.choose(rng).unwrap()).collect()
+}
+
+/// A query and targets containing a mutated copy of it.
+fn dna() -> (Vec<u8>, Vec<Vec<u8>>) {
+    let mut rng = StdRng::seed_from_u64(42);
+    let query = random_seq(&mut rng, b"ACGT", 150);
+    let targets = (0..50)
+        .map(|_| {
+            let mut target = random_seq(&mut rng, b"ACGT", 500);
+            target.extend(query.iter().step_by(2));
+            target.extend(random_seq(&mut rng, b"ACGT", 500));
+            target
+        })
+        .collect();
+    (query, targets)
+}
+
+fn protein() -> (Vec<u8>, Vec<Vec<u8>>) {
+    let mut rng = StdRng::seed_from_u64(42);
+    let alphabet = b"ARNDCQEGHILKMFPSTWYV";
+    let query = random_seq(&mut rng, alphabet, 300);
+    let targets = (0..50)
+        .map(|_| random_seq(&mut rng, alphabet, 400))
+        .collect();
+    (query, targets)
+}
+
+#[bench]
+fn bench_striped_dna(b: &mut Bencher) {
+    let (query, targets) = dna();
+    let score = |a: u8, b: u8| if a == b { 2i32 } else { -3i32 };
+    let mut striped = StripedAligner::new(&query, -5, -1, score);
+    b.iter(|| striped.scores(targets.iter().map(|target| &target[..])));
+}
+
+#[bench]
+fn bench_aligner_local_dna(b: &mut Bencher) {
+    let (query, targets) = dna();
+    let score = |a: u8, b: u8| if a == b { 2i32 } else { -3i32 };
+    let mut aligner = Aligner::new(-5, -1, score);
+    b.iter(|| {
+        targets
+            .iter()
+            .map(|target| aligner.local(&query, target).score)
+            .collect::<Vec<_>>()
+    });
+}
+
+#[bench]
+fn bench_striped_protein(b: &mut Bencher) {
+    let (query, targets) = protein();
+    let mut striped = StripedAligner::new(&query, -10, -1, &blosum62);
+    b.iter(|| striped.scores(targets.iter().map(|target| &target[..])));
+}
+
+#[bench]
+fn bench_aligner_local_protein(b: &mut Bencher) {
+    let (query, targets) = protein();
+    let mut aligner = Aligner::new(-10, -1, &blosum62);
+    b.iter(|| {
+        targets
+            .iter()
+            .map(|target| aligner.local(&query, target).score)
+            .collect::<Vec<_>>()
+    });
+}
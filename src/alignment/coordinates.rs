@@ -0,0 +1,614 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Mapping of positions between the two sequences of a pairwise alignment, slicing of
+//! alignments to an interval of the reference and swapping of query and reference, e.g. for
+//! variant normalization or lifting annotations between sequences.
+//!
+//! The [`AlignmentCoordinates`](trait.AlignmentCoordinates.html) trait is implemented for
+//! [`Alignment`](../struct.Alignment.html), with `x` being the query and `y` being the
+//! reference. All positions are 0-based and refer to the full sequences, i.e. they include
+//! unaligned (clipped) prefixes.
+//!
+//! For alignments given as a SAM CIGAR string, the trait is implemented for
+//! [`CigarStringView`](struct.CigarStringView.html), i.e. a parsed
+//! [`CigarString`](struct.CigarString.html) together with the reference position of its first
+//! aligned base.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::alignment::coordinates::AlignmentCoordinates;
+//! use bio::alignment::pairwise::Aligner;
+//!
+//! let x = b"ACGTTTGCA";
+//! let y = b"GGACGTGCA";
+//! let score = |a: u8, b: u8| if a == b { 1i32 } else { -1i32 };
+//! let mut aligner = Aligner::new(-2, -1, score);
+//! let alignment = aligner.semiglobal(x, y);
+//! //   ACGTTTGCA
+//! //   |||++||||
+//! // GGACG--TGCA
+//! assert_eq!(alignment.x_to_y(2), Some(4));
+//! assert_eq!(alignment.x_to_y(3), None);
+//! assert_eq!(alignment.y_to_x(6), Some(6));
+//!
+//! let sliced = alignment.slice_y(5, 9).unwrap();
+//! assert_eq!((sliced.xstart, sliced.xend), (5, 9));
+//! assert_eq!(alignment.inverted().x_to_y(6), Some(6));
+//! ```
+//!
+//! The same mapping for a read aligned at reference position 100:
+//!
+//! ```
+//! use bio::alignment::coordinates::{AlignmentCoordinates, CigarString};
+//!
+//! let cigar: CigarString = "2S3M2D2M1I2M".parse().unwrap();
+//! let view = cigar.into_view(100);
+//! assert_eq!(view.x_to_y(2), Some(100));
+//! assert_eq!(view.x_to_y(7), None);
+//! assert_eq!(view.y_to_x(105), Some(5));
+//! assert_eq!(view.y_to_x(103), None);
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use crate::alignment::{Alignment, AlignmentMode, AlignmentOperation};
+
+#[derive(Error, Debug, PartialEq)]
+pub enum Error {
+    #[error("invalid CIGAR string {cigar}")]
+    InvalidCigar { cigar: String },
+}
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Coordinate mapping between the sequences of an alignment.
+pub trait AlignmentCoordinates {
+    /// Position in `y` aligned to position `pos` of `x`, or `None` if `pos` is inserted or
+    /// not part of the aligned region.
+    fn x_to_y(&self, pos: usize) -> Option<usize>;
+
+    /// Position in `x` aligned to position `pos` of `y`, or `None` if `pos` is deleted or
+    /// not part of the aligned region.
+    fn y_to_x(&self, pos: usize) -> Option<usize>;
+
+    /// Restrict the alignment to the interval `start..end` of `y`. Insertions are kept if
+    /// they are surrounded by aligned positions of the interval. The result is a local
+    /// alignment without clip operations. Its score is set to zero, since it cannot be
+    /// computed without the scoring. Returns `None` if no position of the interval is
+    /// covered by the alignment.
+    fn slice_y(&self, start: usize, end: usize) -> Option<Alignment>;
+
+    /// The alignment of `y` against `x`, i.e. with insertions and deletions swapped.
+    fn inverted(&self) -> Alignment;
+}
+
+/// Walk the aligned (non-clip) operations, together with the position in `x` and `y` before
+/// each operation.
+fn steps(alignment: &Alignment) -> impl Iterator<Item = (usize, usize, AlignmentOperation)> + '_ {
+    let (mut i, mut j) = (alignment.xstart, alignment.ystart);
+    alignment
+        .operations
+        .iter()
+        .filter(|op| {
+            !matches!(
+                op,
+                AlignmentOperation::Xclip(_) | AlignmentOperation::Yclip(_)
+            )
+        })
+        .map(move |&op| {
+            let step = (i, j, op);
+            match op {
+                AlignmentOperation::Match | AlignmentOperation::Subst => {
+                    i += 1;
+                    j += 1;
+                }
+                AlignmentOperation::Ins => i += 1,
+                AlignmentOperation::Del => j += 1,
+                _ => unreachable!(),
+            }
+            step
+        })
+}
+
+fn is_aligned(op: AlignmentOperation) -> bool {
+    op == AlignmentOperation::Match || op == AlignmentOperation::Subst
+}
+
+impl AlignmentCoordinates for Alignment {
+    fn x_to_y(&self, pos: usize) -> Option<usize> {
+        if pos < self.xstart || pos >= self.xend {
+            return None;
+        }
+        steps(self)
+            .find(|&(i, _, op)| i == pos && op != AlignmentOperation::Del)
+            .filter(|&(_, _, op)| is_aligned(op))
+            .map(|(_, j, _)| j)
+    }
+
+    fn y_to_x(&self, pos: usize) -> Option<usize> {
+        if pos < self.ystart || pos >= self.yend {
+            return None;
+        }
+        steps(self)
+            .find(|&(_, j, op)| j == pos && op != AlignmentOperation::Ins)
+            .filter(|&(_, _, op)| is_aligned(op))
+            .map(|(i, _, _)| i)
+    }
+
+    fn slice_y(&self, start: usize, end: usize) -> Option<Alignment> {
+        let inside: Vec<_> = steps(self)
+            .filter(|&(_, j, op)| j >= start && (j < end || op == AlignmentOperation::Ins))
+            .collect();
+        // insertions at the borders are not surrounded by positions of the interval
+        let first = inside
+            .iter()
+            .position(|&(_, _, op)| op != AlignmentOperation::Ins)?;
+        let last = inside
+            .iter()
+            .rposition(|&(_, j, op)| op != AlignmentOperation::Ins && j < end)?;
+        let inside = &inside[first..=last];
+
+        let (xstart, ystart, _) = inside[0];
+        let (xlast, ylast, op) = inside[inside.len() - 1];
+        let (xend, yend) = if is_aligned(op) {
+            (xlast + 1, ylast + 1)
+        } else {
+            (xlast, ylast + 1)
+        };
+        Some(Alignment {
+            score: 0,
+            xstart,
+            ystart,
+            xend,
+            yend,
+            xlen: self.xlen,
+            ylen: self.ylen,
+            operations: inside.iter().map(|&(_, _, op)| op).collect(),
+            mode: AlignmentMode::Local,
+        })
+    }
+
+    fn inverted(&self) -> Alignment {
+        Alignment {
+            score: self.score,
+            xstart: self.ystart,
+            ystart: self.xstart,
+            xend: self.yend,
+            yend: self.xend,
+            xlen: self.ylen,
+            ylen: self.xlen,
+            operations: self
+                .operations
+                .iter()
+                .map(|&op| match op {
+                    AlignmentOperation::Ins => AlignmentOperation::Del,
+                    AlignmentOperation::Del => AlignmentOperation::Ins,
+                    AlignmentOperation::Xclip(len) => AlignmentOperation::Yclip(len),
+                    AlignmentOperation::Yclip(len) => AlignmentOperation::Xclip(len),
+                    op => op,
+                })
+                .collect(),
+            mode: self.mode,
+        }
+    }
+}
+
+/// An operation of a SAM CIGAR string, with its length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Cigar {
+    /// Alignment match, either a match or a substitution (`M`).
+    Match(u32),
+    /// Insertion into the reference (`I`).
+    Ins(u32),
+    /// Deletion from the reference (`D`).
+    Del(u32),
+    /// Skipped region of the reference, e.g. an intron (`N`).
+    RefSkip(u32),
+    /// Soft clip, i.e. query bases that are not aligned but present in the record (`S`).
+    SoftClip(u32),
+    /// Hard clip, i.e. query bases that are not present in the record (`H`).
+    HardClip(u32),
+    /// Padding (`P`).
+    Pad(u32),
+    /// Sequence match (`=`).
+    Equal(u32),
+    /// Sequence mismatch (`X`).
+    Diff(u32),
+}
+
+impl Cigar {
+    pub fn len(self) -> u32 {
+        match self {
+            Cigar::Match(len)
+            | Cigar::Ins(len)
+            | Cigar::Del(len)
+            | Cigar::RefSkip(len)
+            | Cigar::SoftClip(len)
+            | Cigar::HardClip(len)
+            | Cigar::Pad(len)
+            | Cigar::Equal(len)
+            | Cigar::Diff(len) => len,
+        }
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn char(self) -> char {
+        match self {
+            Cigar::Match(_) => 'M',
+            Cigar::Ins(_) => 'I',
+            Cigar::Del(_) => 'D',
+            Cigar::RefSkip(_) => 'N',
+            Cigar::SoftClip(_) => 'S',
+            Cigar::HardClip(_) => 'H',
+            Cigar::Pad(_) => 'P',
+            Cigar::Equal(_) => '=',
+            Cigar::Diff(_) => 'X',
+        }
+    }
+
+    fn is_clip(self) -> bool {
+        matches!(self, Cigar::SoftClip(_) | Cigar::HardClip(_))
+    }
+}
+
+impl fmt::Display for Cigar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.len(), self.char())
+    }
+}
+
+/// A parsed SAM CIGAR string. Clips are only allowed at the ends, with hard clips outside of
+/// soft clips.
+///
+/// # Example
+///
+/// ```
+/// use bio::alignment::coordinates::{Cigar, CigarString};
+///
+/// let cigar: CigarString = "5H2S10M".parse().unwrap();
+/// assert_eq!(cigar.0[1], Cigar::SoftClip(2));
+/// assert_eq!(cigar.to_string(), "5H2S10M");
+/// assert!("2M3S2M".parse::<CigarString>().is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct CigarString(pub Vec<Cigar>);
+
+impl CigarString {
+    /// Length of the query sequence as stored in the record, i.e. including soft clips but
+    /// not hard clips.
+    pub fn query_len(&self) -> usize {
+        self.0
+            .iter()
+            .map(|&op| match op {
+                Cigar::Match(len)
+                | Cigar::Ins(len)
+                | Cigar::SoftClip(len)
+                | Cigar::Equal(len)
+                | Cigar::Diff(len) => len as usize,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    /// Number of reference bases covered by the alignment.
+    pub fn reference_len(&self) -> usize {
+        self.0
+            .iter()
+            .map(|&op| match op {
+                Cigar::Match(len)
+                | Cigar::Del(len)
+                | Cigar::RefSkip(len)
+                | Cigar::Equal(len)
+                | Cigar::Diff(len) => len as usize,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    /// Anchor the CIGAR string at the 0-based reference position `pos` of its first aligned
+    /// base.
+    pub fn into_view(self, pos: usize) -> CigarStringView {
+        CigarStringView { cigar: self, pos }
+    }
+}
+
+impl FromStr for CigarString {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::InvalidCigar {
+            cigar: s.to_owned(),
+        };
+        let mut ops = Vec::new();
+        let mut len: Option<u32> = None;
+        for c in s.chars() {
+            if let Some(digit) = c.to_digit(10) {
+                len = Some(
+                    len.unwrap_or(0)
+                        .checked_mul(10)
+                        .and_then(|len| len.checked_add(digit))
+                        .ok_or_else(invalid)?,
+                );
+                continue;
+            }
+            let len = len.take().ok_or_else(invalid)?;
+            ops.push(match c {
+                'M' => Cigar::Match(len),
+                'I' => Cigar::Ins(len),
+                'D' => Cigar::Del(len),
+                'N' => Cigar::RefSkip(len),
+                'S' => Cigar::SoftClip(len),
+                'H' => Cigar::HardClip(len),
+                'P' => Cigar::Pad(len),
+                '=' => Cigar::Equal(len),
+                'X' => Cigar::Diff(len),
+                _ => return Err(invalid()),
+            });
+        }
+        if len.is_some() || ops.is_empty() {
+            return Err(invalid());
+        }
+
+        // clips may only occur at the ends, with hard clips outermost
+        let is_hard = |op: &Cigar| matches!(op, Cigar::HardClip(_));
+        let is_soft = |op: &Cigar| matches!(op, Cigar::SoftClip(_));
+        let start = ops.iter().take_while(|op| is_hard(op)).count();
+        let start = start + ops[start..].iter().take_while(|op| is_soft(op)).count();
+        let inner = &ops[start..];
+        let end = inner.len() - inner.iter().rev().take_while(|op| is_hard(op)).count();
+        let end = end
+            - inner[..end]
+                .iter()
+                .rev()
+                .take_while(|op| is_soft(op))
+                .count();
+        if inner[..end].iter().any(|op| op.is_clip()) {
+            return Err(invalid());
+        }
+        Ok(CigarString(ops))
+    }
+}
+
+impl fmt::Display for CigarString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for op in &self.0 {
+            write!(f, "{}", op)?;
+        }
+        Ok(())
+    }
+}
+
+/// A CIGAR string anchored at the 0-based reference position of its first aligned base, as
+/// given by a SAM record. Query positions include soft clipped bases, but not hard clipped ones.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CigarStringView {
+    pub cigar: CigarString,
+    pub pos: usize,
+}
+
+impl CigarStringView {
+    /// The alignment of the query (`x`) against the reference (`y`). Soft clips are given by
+    /// the alignment start and end, skipped reference regions become deletions and hard clips
+    /// and padding are ignored. Since the reference length is unknown, `ylen` is set to the end
+    /// of the aligned region, and the score is set to zero.
+    pub fn to_alignment(&self) -> Alignment {
+        let ops = &self.cigar.0;
+        let soft_clip = |op: Option<&Cigar>| match op {
+            Some(&Cigar::SoftClip(len)) => len as usize,
+            _ => 0,
+        };
+        let mut unclipped = ops.iter().filter(|op| !matches!(op, Cigar::HardClip(_)));
+        let xstart = soft_clip(unclipped.next());
+        let xlen = self.cigar.query_len();
+        let xend = if ops.iter().all(|op| op.is_clip()) {
+            xstart
+        } else {
+            xlen - soft_clip(unclipped.next_back())
+        };
+        let yend = self.pos + self.cigar.reference_len();
+        let operations = ops
+            .iter()
+            .flat_map(|&op| {
+                let aln_op = match op {
+                    Cigar::Match(_) | Cigar::Equal(_) => Some(AlignmentOperation::Match),
+                    Cigar::Diff(_) => Some(AlignmentOperation::Subst),
+                    Cigar::Ins(_) => Some(AlignmentOperation::Ins),
+                    Cigar::Del(_) | Cigar::RefSkip(_) => Some(AlignmentOperation::Del),
+                    _ => None,
+                };
+                aln_op
+                    .map(|aln_op| vec![aln_op; op.len() as usize])
+                    .unwrap_or_default()
+            })
+            .collect();
+        Alignment {
+            score: 0,
+            xstart,
+            ystart: self.pos,
+            xend,
+            yend,
+            xlen,
+            ylen: yend,
+            operations,
+            mode: AlignmentMode::Local,
+        }
+    }
+}
+
+impl AlignmentCoordinates for CigarStringView {
+    fn x_to_y(&self, pos: usize) -> Option<usize> {
+        self.to_alignment().x_to_y(pos)
+    }
+
+    fn y_to_x(&self, pos: usize) -> Option<usize> {
+        self.to_alignment().y_to_x(pos)
+    }
+
+    fn slice_y(&self, start: usize, end: usize) -> Option<Alignment> {
+        self.to_alignment().slice_y(start, end)
+    }
+
+    fn inverted(&self) -> Alignment {
+        self.to_alignment().inverted()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alignment::AlignmentOperation::*;
+
+    fn alignment() -> Alignment {
+        // x:   AC-GTTA
+        //      || | ||
+        // y: GGACTG--A
+        Alignment {
+            score: 0,
+            xstart: 0,
+            ystart: 2,
+            xend: 6,
+            yend: 7,
+            xlen: 6,
+            ylen: 7,
+            operations: vec![Match, Match, Del, Match, Ins, Ins, Match],
+            mode: AlignmentMode::Semiglobal,
+        }
+    }
+
+    #[test]
+    fn test_mapping() {
+        let alignment = alignment();
+        let x_to_y: Vec<_> = (0..7).map(|i| alignment.x_to_y(i)).collect();
+        assert_eq!(
+            x_to_y,
+            [Some(2), Some(3), Some(5), None, None, Some(6), None]
+        );
+        let y_to_x: Vec<_> = (0..8).map(|j| alignment.y_to_x(j)).collect();
+        assert_eq!(
+            y_to_x,
+            [None, None, Some(0), Some(1), None, Some(2), Some(5), None]
+        );
+        // mapping is symmetric under inversion
+        let inverted = alignment.inverted();
+        for i in 0..7 {
+            assert_eq!(alignment.x_to_y(i), inverted.y_to_x(i));
+        }
+        assert_eq!(inverted.inverted(), alignment);
+    }
+
+    #[test]
+    fn test_slice_y() {
+        let alignment = alignment();
+        let sliced = alignment.slice_y(3, 7).unwrap();
+        assert_eq!(sliced.operations, vec![Match, Del, Match, Ins, Ins, Match]);
+        assert_eq!((sliced.xstart, sliced.xend), (1, 6));
+        assert_eq!((sliced.ystart, sliced.yend), (3, 7));
+        // the insertion is not surrounded by positions of the interval
+        let sliced = alignment.slice_y(4, 6).unwrap();
+        assert_eq!(sliced.operations, vec![Del, Match]);
+        assert_eq!((sliced.xstart, sliced.xend), (2, 3));
+        assert_eq!((sliced.ystart, sliced.yend), (4, 6));
+        assert_eq!(alignment.slice_y(0, 2), None);
+    }
+
+    #[test]
+    fn test_cigar_parse() {
+        let cigar: CigarString = "3H2S4M1I2=1X3N1D2S".parse().unwrap();
+        assert_eq!(
+            cigar.0,
+            vec![
+                Cigar::HardClip(3),
+                Cigar::SoftClip(2),
+                Cigar::Match(4),
+                Cigar::Ins(1),
+                Cigar::Equal(2),
+                Cigar::Diff(1),
+                Cigar::RefSkip(3),
+                Cigar::Del(1),
+                Cigar::SoftClip(2),
+            ]
+        );
+        assert_eq!(cigar.to_string(), "3H2S4M1I2=1X3N1D2S");
+        assert_eq!(cigar.query_len(), 12);
+        assert_eq!(cigar.reference_len(), 11);
+        for invalid in &[
+            "",
+            "*",
+            "M",
+            "4M3",
+            "4Q",
+            "2M2S2M",
+            "2S2H3M",
+            "99999999999M",
+        ] {
+            assert_eq!(
+                invalid.parse::<CigarString>(),
+                Err(Error::InvalidCigar {
+                    cigar: invalid.to_string()
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_cigar_view() {
+        // the fixture alignment, with one soft clipped base on each side of the query
+        let view = "1H1S2M1D1M2I1M1S"
+            .parse::<CigarString>()
+            .unwrap()
+            .into_view(2);
+        let mut expected = alignment();
+        expected.xstart += 1;
+        expected.xend += 1;
+        expected.xlen += 2;
+        expected.mode = AlignmentMode::Local;
+        assert_eq!(view.to_alignment(), expected);
+
+        let x_to_y: Vec<_> = (0..9).map(|i| view.x_to_y(i)).collect();
+        assert_eq!(
+            x_to_y,
+            [
+                None,
+                Some(2),
+                Some(3),
+                Some(5),
+                None,
+                None,
+                Some(6),
+                None,
+                None
+            ]
+        );
+        let y_to_x: Vec<_> = (0..8).map(|j| view.y_to_x(j)).collect();
+        assert_eq!(
+            y_to_x,
+            [None, None, Some(1), Some(2), None, Some(3), Some(6), None]
+        );
+        assert_eq!(view.slice_y(4, 6), expected.slice_y(4, 6));
+        assert_eq!(view.inverted(), expected.inverted());
+    }
+
+    #[test]
+    fn test_cigar_round_trip() {
+        use crate::alignment::export::{AlignmentExport, CigarStyle};
+
+        for cigar in &["2S3M2D2M1I2M", "4=1X2I3=3S", "10M"] {
+            let view = cigar.parse::<CigarString>().unwrap().into_view(7);
+            assert_eq!(
+                &view
+                    .to_alignment()
+                    .cigar_string(CigarStyle::Extended, false),
+                &cigar.replace('M', "=")
+            );
+        }
+    }
+}
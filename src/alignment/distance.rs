@@ -6,11 +6,12 @@
 //! Various subroutines for computing a distance between sequences. Features
 //! both scalar and efficient vectorized distance functions with SIMD.
 
-use std::cmp::min;
+use std::convert::TryInto;
 
 use crate::utils::TextSlice;
 
-/// Compute the Hamming distance between two strings. Complexity: O(n).
+/// Compute the Hamming distance between two strings. Complexity: O(n), comparing eight
+/// symbols at a time.
 ///
 /// # Example
 ///
@@ -33,16 +34,60 @@ pub fn hamming(alpha: TextSlice<'_>, beta: TextSlice<'_>) -> u64 {
         beta.len()
     );
     let mut dist = 0;
-    for (a, b) in alpha.iter().zip(beta) {
+    let (chunks_a, chunks_b) = (alpha.chunks_exact(8), beta.chunks_exact(8));
+    for (a, b) in chunks_a.remainder().iter().zip(chunks_b.remainder()) {
         if a != b {
             dist += 1;
         }
     }
+    for (a, b) in chunks_a.zip(chunks_b) {
+        let mut x =
+            u64::from_ne_bytes(a.try_into().unwrap()) ^ u64::from_ne_bytes(b.try_into().unwrap());
+        // fold each differing byte into its lowest bit
+        x |= x >> 4;
+        x |= x >> 2;
+        x |= x >> 1;
+        dist += u64::from((x & 0x0101_0101_0101_0101).count_ones());
+    }
     dist
 }
 
-/// Compute the Levenshtein (or Edit) distance between two strings. Complexity: O(n * m) with
-/// n and m being the length of the given texts.
+/// Compute the sum of the Phred qualities at mismatching positions of a read `alpha` and a
+/// reference `beta`, where `qual` holds the Phred qualities of `alpha` (i.e. without the
+/// offset of 33 used in FASTQ files). Mismatches at low quality bases hence count less than
+/// those at high quality bases, as in the mapping quality model of MAQ. Complexity: O(n).
+///
+/// # Example
+///
+/// ```
+/// use bio::alignment::distance::*;
+///
+/// let x = b"ACGTACGT";
+/// let y = b"ACGAACGA";
+/// let qual = [40, 40, 40, 5, 40, 40, 40, 30];
+/// assert_eq!(quality_hamming(x, y, &qual), 35);
+/// ```
+pub fn quality_hamming(alpha: TextSlice<'_>, beta: TextSlice<'_>, qual: &[u8]) -> u64 {
+    assert!(
+        alpha.len() == beta.len() && alpha.len() == qual.len(),
+        "quality hamming distance cannot be calculated for texts or qualities of different length \
+         ({}!={}!={})",
+        alpha.len(),
+        beta.len(),
+        qual.len()
+    );
+    alpha
+        .iter()
+        .zip(beta)
+        .zip(qual)
+        .filter(|((a, b), _)| a != b)
+        .map(|(_, &q)| u64::from(q))
+        .sum()
+}
+
+/// Compute the Levenshtein (or Edit) distance between two strings. Uses the bit-parallel
+/// algorithm of Myers (1999) with the block-based extension of Hyyrö (2003).
+/// Complexity: O(n * ceil(m / 64)) with n and m being the length of the given texts.
 ///
 /// # Example
 ///
@@ -57,32 +102,57 @@ pub fn hamming(alpha: TextSlice<'_>, beta: TextSlice<'_>) -> u64 {
 /// let ldist = levenshtein(x, y); // Distance is 5
 /// assert_eq!(ldist, 5);
 /// ```
-#[allow(unused_assignments)]
 pub fn levenshtein(alpha: TextSlice<'_>, beta: TextSlice<'_>) -> u32 {
-    let mut columns = [vec![0u32; alpha.len() + 1], vec![0u32; alpha.len() + 1]];
-    let mut i_prev = 0;
-    let mut i_cur = 1;
-
-    for i in 0..columns[0].len() {
-        columns[0][i] = i as u32;
+    let m = alpha.len();
+    if m == 0 {
+        return beta.len() as u32;
     }
+    let blocks = m.div_ceil(64);
+    // match masks of the symbols in alpha, for each block of 64 positions
+    let mut peq = vec![[0u64; 256]; blocks];
+    for (i, &a) in alpha.iter().enumerate() {
+        peq[i / 64][a as usize] |= 1 << (i % 64);
+    }
+    // vertical deltas of the current column: initially, D[i][0] = i
+    let mut pv = vec![!0u64; blocks];
+    let mut mv = vec![0u64; blocks];
+    let last = 1 << ((m - 1) % 64);
+    let mut dist = m as u32;
 
-    for (j, item) in beta.iter().enumerate() {
-        i_cur %= 2;
-        i_prev = 1 - i_cur;
-
-        columns[i_cur][0] = 1 + j as u32;
-        for i in 1..columns[0].len() {
-            columns[i_cur][i] = min(
-                columns[i_prev][i - 1] + if alpha[i - 1] == *item { 0 } else { 1 },
-                min(columns[i_cur][i - 1] + 1, columns[i_prev][i] + 1),
-            );
+    for &b in beta {
+        // the first row is D[0][j] = j, i.e. its horizontal delta is +1
+        let mut hin = 1i32;
+        for (block, (pv, mv)) in pv.iter_mut().zip(mv.iter_mut()).enumerate() {
+            let high = if block == blocks - 1 { last } else { 1 << 63 };
+            let mut eq = peq[block][b as usize];
+            let xv = eq | *mv;
+            if hin < 0 {
+                eq |= 1;
+            }
+            let xh = (((eq & *pv).wrapping_add(*pv)) ^ *pv) | eq;
+            let mut ph = *mv | !(xh | *pv);
+            let mut mh = *pv & xh;
+            let hout = if ph & high != 0 {
+                1
+            } else if mh & high != 0 {
+                -1
+            } else {
+                0
+            };
+            ph <<= 1;
+            mh <<= 1;
+            if hin < 0 {
+                mh |= 1;
+            } else if hin > 0 {
+                ph |= 1;
+            }
+            *pv = mh | !(xv | ph);
+            *mv = ph & xv;
+            hin = hout;
         }
-
-        i_cur += 1;
+        dist = (dist as i32 + hin) as u32;
     }
-
-    columns[i_cur - 1][columns[0].len() - 1]
+    dist
 }
 
 pub mod simd {
@@ -104,6 +174,9 @@ pub mod simd {
     //!
     //! If AVX2 support is not available, there is a speed penalty for using SSE4.1 with
     //! smaller vectors.
+    //!
+    //! The quality-weighted Hamming distance is vectorized with the `simd` feature, using
+    //! AVX2 or SSE2 on x86_64. Otherwise, it falls back to the scalar version.
 
     use crate::utils::TextSlice;
 
@@ -134,6 +207,96 @@ pub mod simd {
         triple_accel::hamming(alpha, beta) as u64
     }
 
+    /// SIMD-accelerated sum of the Phred qualities at mismatching positions, see
+    /// [`quality_hamming`](../fn.quality_hamming.html). Complexity: O(n / w), for SIMD vectors
+    /// of length w (w = 16 or w = 32).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::alignment::distance::simd::*;
+    ///
+    /// let x = b"ACGTACGT";
+    /// let y = b"ACGAACGA";
+    /// let qual = [40, 40, 40, 5, 40, 40, 40, 30];
+    /// assert_eq!(quality_hamming(x, y, &qual), 35);
+    /// ```
+    pub fn quality_hamming(alpha: TextSlice<'_>, beta: TextSlice<'_>, qual: &[u8]) -> u64 {
+        assert!(
+            alpha.len() == beta.len() && alpha.len() == qual.len(),
+            "simd quality hamming distance cannot be calculated for texts or qualities of \
+             different length ({}!={}!={})",
+            alpha.len(),
+            beta.len(),
+            qual.len()
+        );
+        #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+        {
+            // Safety: the CPU supports the instructions, and the slices have equal lengths.
+            if is_x86_feature_detected!("avx2") {
+                return unsafe { x86::quality_hamming_avx2(alpha, beta, qual) };
+            }
+            if is_x86_feature_detected!("sse2") {
+                return unsafe { x86::quality_hamming_sse2(alpha, beta, qual) };
+            }
+        }
+        super::quality_hamming(alpha, beta, qual)
+    }
+
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    mod x86 {
+        use std::arch::x86_64::*;
+
+        use crate::utils::TextSlice;
+
+        /// Sum the qualities at mismatches, 32 positions at a time.
+        #[target_feature(enable = "avx2")]
+        pub(super) unsafe fn quality_hamming_avx2(
+            alpha: TextSlice<'_>,
+            beta: TextSlice<'_>,
+            qual: &[u8],
+        ) -> u64 {
+            let n = alpha.len() - alpha.len() % 32;
+            let zero = _mm256_setzero_si256();
+            let mut sums = zero;
+            for i in (0..n).step_by(32) {
+                let a = _mm256_loadu_si256(alpha.as_ptr().add(i) as *const __m256i);
+                let b = _mm256_loadu_si256(beta.as_ptr().add(i) as *const __m256i);
+                let q = _mm256_loadu_si256(qual.as_ptr().add(i) as *const __m256i);
+                // qualities at mismatches, summed into four 64-bit lanes
+                let mismatch_qual = _mm256_andnot_si256(_mm256_cmpeq_epi8(a, b), q);
+                sums = _mm256_add_epi64(sums, _mm256_sad_epu8(mismatch_qual, zero));
+            }
+            let mut lanes = [0u64; 4];
+            _mm256_storeu_si256(lanes.as_mut_ptr() as *mut __m256i, sums);
+            lanes.iter().sum::<u64>()
+                + super::super::quality_hamming(&alpha[n..], &beta[n..], &qual[n..])
+        }
+
+        /// Sum the qualities at mismatches, 16 positions at a time.
+        #[target_feature(enable = "sse2")]
+        pub(super) unsafe fn quality_hamming_sse2(
+            alpha: TextSlice<'_>,
+            beta: TextSlice<'_>,
+            qual: &[u8],
+        ) -> u64 {
+            let n = alpha.len() - alpha.len() % 16;
+            let zero = _mm_setzero_si128();
+            let mut sums = zero;
+            for i in (0..n).step_by(16) {
+                let a = _mm_loadu_si128(alpha.as_ptr().add(i) as *const __m128i);
+                let b = _mm_loadu_si128(beta.as_ptr().add(i) as *const __m128i);
+                let q = _mm_loadu_si128(qual.as_ptr().add(i) as *const __m128i);
+                let mismatch_qual = _mm_andnot_si128(_mm_cmpeq_epi8(a, b), q);
+                sums = _mm_add_epi64(sums, _mm_sad_epu8(mismatch_qual, zero));
+            }
+            let mut lanes = [0u64; 2];
+            _mm_storeu_si128(lanes.as_mut_ptr() as *mut __m128i, sums);
+            lanes.iter().sum::<u64>()
+                + super::super::quality_hamming(&alpha[n..], &beta[n..], &qual[n..])
+        }
+    }
+
     /// SIMD-accelerated Levenshtein (or Edit) distance between two strings. Complexity:
     /// O(k / w * (n + m)), with n and m being the length of the given texts, k being the
     /// number of edits, and w being the length of the SIMD vectors (usually w = 16 or
@@ -194,6 +357,8 @@ pub mod simd {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::testing::random_seq;
+    use rand::prelude::*;
 
     use std::u32;
 
@@ -250,6 +415,70 @@ mod tests {
         assert_eq!(levenshtein(b"TTTT", b"AAA"), 4);
     }
 
+    #[test]
+    fn test_levenshtein_against_full_dp() {
+        fn full_dp(alpha: &[u8], beta: &[u8]) -> u32 {
+            let mut prev: Vec<u32> = (0..=alpha.len() as u32).collect();
+            for (j, &b) in beta.iter().enumerate() {
+                let mut cur = vec![j as u32 + 1; alpha.len() + 1];
+                for (i, &a) in alpha.iter().enumerate() {
+                    cur[i + 1] = (prev[i] + u32::from(a != b))
+                        .min(prev[i + 1] + 1)
+                        .min(cur[i] + 1);
+                }
+                prev = cur;
+            }
+            prev[alpha.len()]
+        }
+
+        let mut rng = StdRng::seed_from_u64(11);
+        for _ in 0..200 {
+            let m = rng.gen_range(0..200);
+            let n = rng.gen_range(0..200);
+            let x = random_seq(&mut rng, b"ACGT", m);
+            let mut y: Vec<u8> = x.clone();
+            y.truncate(n);
+            for a in y.iter_mut() {
+                if rng.gen_bool(0.2) {
+                    *a = b"ACGT"[rng.gen_range(0..4)];
+                }
+            }
+            assert_eq!(levenshtein(&x, &y), full_dp(&x, &y));
+            assert_eq!(levenshtein(&y, &x), full_dp(&x, &y));
+        }
+    }
+
+    #[test]
+    fn test_hamming_dist_long() {
+        let x = b"ACGTACGTACGTACGTACGTAC";
+        let y = b"ACGAACGTACGTTCGTACGTAG";
+        assert_eq!(hamming(x, y), 3);
+        assert_eq!(hamming(x, y), simd::hamming(x, y));
+    }
+
+    #[test]
+    fn test_quality_hamming() {
+        let x = b"ACGTACGTAC";
+        let y = b"TCGTACGTAG";
+        let qual = [10, 40, 40, 40, 40, 40, 40, 40, 40, 20];
+        assert_eq!(quality_hamming(x, y, &qual), 30);
+        assert_eq!(quality_hamming(x, x, &qual), 0);
+    }
+
+    #[test]
+    fn test_simd_quality_hamming() {
+        let mut rng = StdRng::seed_from_u64(42);
+        for len in (0..100).chain(vec![1000, 1033]) {
+            let x = random_seq(&mut rng, b"ACGT", len);
+            let y = random_seq(&mut rng, b"ACGT", len);
+            let qual: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+            assert_eq!(
+                simd::quality_hamming(&x, &y, &qual),
+                quality_hamming(&x, &y, &qual)
+            );
+        }
+    }
+
     #[test]
     fn test_simd_levenshtein_dist() {
         let x = b"ACCGTGGAT";
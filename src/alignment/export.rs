@@ -0,0 +1,216 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Export of pairwise alignments as CIGAR and BLAST traceback operation (BTOP) strings, e.g.
+//! for writing SAM or PAF records.
+//!
+//! The [`AlignmentExport`](trait.AlignmentExport.html) trait is implemented for
+//! [`Alignment`](../struct.Alignment.html), with `x` being the query and `y` being the
+//! reference. In contrast to `Alignment::cigar`, CIGAR strings can be obtained for all
+//! alignment modes, and either with `=`/`X` or with `M` operations. A three-line pretty
+//! alignment with configurable width is provided by `Alignment::pretty`.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::alignment::export::{AlignmentExport, CigarStyle};
+//! use bio::alignment::pairwise::Aligner;
+//!
+//! let x = b"ACCGTGCATGGG";
+//! let y = b"GTTACGTGGATGGCC";
+//! let score = |a: u8, b: u8| if a == b { 1i32 } else { -1i32 };
+//! let mut aligner = Aligner::new(-5, -1, score);
+//! let alignment = aligner.local(x, y);
+//!
+//! assert_eq!(alignment.cigar_string(CigarStyle::Extended, false), "2S4=1X4=1S");
+//! assert_eq!(alignment.cigar_string(CigarStyle::Sam, true), "2H9M1H");
+//! assert_eq!(alignment.btop(x, y), "4CG4");
+//! // query, match line and reference in blocks of 80 columns
+//! println!("{}", alignment.pretty(x, y, 80));
+//! ```
+
+use std::fmt::Write;
+
+use crate::alignment::{Alignment, AlignmentOperation};
+use crate::utils::TextSlice;
+
+/// Operations used for aligned residues in CIGAR strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CigarStyle {
+    /// Matches as `=` and substitutions as `X`.
+    Extended,
+    /// Matches and substitutions as `M`, as commonly used in SAM files.
+    Sam,
+}
+
+/// Export of alignments as CIGAR and BTOP strings.
+pub trait AlignmentExport {
+    /// CIGAR string of the alignment. Unaligned prefixes and suffixes of the query `x` are
+    /// reported as soft (`S`) or hard (`H`) clips. Clipping of the reference `y` is not part of
+    /// a CIGAR string, but given by the reference start position.
+    fn cigar_string(&self, style: CigarStyle, hard_clip: bool) -> String;
+
+    /// BLAST traceback operations of the alignment of `x` against `y`, i.e. the number of
+    /// identical residues, alternating with a pair of query and reference residue for each
+    /// substitution and `-` for the gapped sequence of an indel (e.g. `A-` for a residue
+    /// inserted in the query).
+    ///
+    /// # Panics
+    ///
+    /// If `x` and `y` are not the sequences the alignment was computed from.
+    fn btop(&self, x: TextSlice<'_>, y: TextSlice<'_>) -> String;
+}
+
+impl AlignmentExport for Alignment {
+    fn cigar_string(&self, style: CigarStyle, hard_clip: bool) -> String {
+        let clip = if hard_clip { 'H' } else { 'S' };
+        let mut cigar = String::new();
+        if self.xstart > 0 {
+            write!(cigar, "{}{}", self.xstart, clip).unwrap();
+        }
+        let mut last: Option<(char, usize)> = None;
+        for op in &self.operations {
+            let c = match (op, style) {
+                (AlignmentOperation::Match, CigarStyle::Extended) => '=',
+                (AlignmentOperation::Subst, CigarStyle::Extended) => 'X',
+                (AlignmentOperation::Match, CigarStyle::Sam)
+                | (AlignmentOperation::Subst, CigarStyle::Sam) => 'M',
+                (AlignmentOperation::Ins, _) => 'I',
+                (AlignmentOperation::Del, _) => 'D',
+                // clips are given by the alignment start and end
+                (AlignmentOperation::Xclip(_), _) | (AlignmentOperation::Yclip(_), _) => continue,
+            };
+            last = match last {
+                Some((l, k)) if l == c => Some((c, k + 1)),
+                Some((l, k)) => {
+                    write!(cigar, "{}{}", k, l).unwrap();
+                    Some((c, 1))
+                }
+                None => Some((c, 1)),
+            };
+        }
+        if let Some((l, k)) = last {
+            write!(cigar, "{}{}", k, l).unwrap();
+        }
+        if self.xlen > self.xend {
+            write!(cigar, "{}{}", self.xlen - self.xend, clip).unwrap();
+        }
+        cigar
+    }
+
+    fn btop(&self, x: TextSlice<'_>, y: TextSlice<'_>) -> String {
+        let mut btop = String::new();
+        let (mut i, mut j) = (self.xstart, self.ystart);
+        let mut identical = 0;
+        for op in &self.operations {
+            let (a, b) = match op {
+                AlignmentOperation::Match => {
+                    identical += 1;
+                    i += 1;
+                    j += 1;
+                    continue;
+                }
+                AlignmentOperation::Subst => {
+                    i += 1;
+                    j += 1;
+                    (x[i - 1], y[j - 1])
+                }
+                AlignmentOperation::Ins => {
+                    i += 1;
+                    (x[i - 1], b'-')
+                }
+                AlignmentOperation::Del => {
+                    j += 1;
+                    (b'-', y[j - 1])
+                }
+                AlignmentOperation::Xclip(_) | AlignmentOperation::Yclip(_) => continue,
+            };
+            if identical > 0 {
+                write!(btop, "{}", identical).unwrap();
+                identical = 0;
+            }
+            btop.push(a as char);
+            btop.push(b as char);
+        }
+        if identical > 0 {
+            write!(btop, "{}", identical).unwrap();
+        }
+        btop
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alignment::pairwise::Aligner;
+    use crate::alignment::AlignmentMode;
+
+    #[test]
+    fn test_global_with_indels() {
+        let x = b"ACGTTACGT";
+        let y = b"ACGTACCGTA";
+        let score = |a: u8, b: u8| if a == b { 2i32 } else { -3i32 };
+        let alignment = Aligner::new(-4, -1, score).global(x, y);
+        assert_eq!(alignment.mode, AlignmentMode::Global);
+        let cigar = alignment.cigar_string(CigarStyle::Sam, false);
+        let btop = alignment.btop(x, y);
+
+        // query and reference lengths are consistent with both strings
+        let count = |ops: &str| {
+            let mut n = 0;
+            let mut counts = std::collections::HashMap::new();
+            for c in cigar.chars() {
+                match c.to_digit(10) {
+                    Some(d) => n = 10 * n + d as usize,
+                    None => {
+                        *counts.entry(c).or_insert(0) += n;
+                        n = 0;
+                    }
+                }
+            }
+            ops.chars()
+                .map(|c| counts.get(&c).unwrap_or(&0))
+                .sum::<usize>()
+        };
+        assert_eq!(count("MI"), x.len());
+        assert_eq!(count("MD"), y.len());
+        assert_eq!(btop.matches('-').count(), count("ID"));
+    }
+
+    #[test]
+    fn test_clips_and_empty() {
+        let alignment = Alignment {
+            score: 2,
+            xstart: 2,
+            ystart: 0,
+            xend: 5,
+            yend: 3,
+            xlen: 7,
+            ylen: 4,
+            operations: vec![
+                AlignmentOperation::Xclip(2),
+                AlignmentOperation::Match,
+                AlignmentOperation::Del,
+                AlignmentOperation::Subst,
+                AlignmentOperation::Ins,
+                AlignmentOperation::Xclip(2),
+            ],
+            mode: AlignmentMode::Custom,
+        };
+        assert_eq!(
+            alignment.cigar_string(CigarStyle::Extended, false),
+            "2S1=1D1X1I2S"
+        );
+        assert_eq!(
+            alignment.cigar_string(CigarStyle::Sam, true),
+            "2H1M1D1M1I2H"
+        );
+        assert_eq!(alignment.btop(b"TTATCAA", b"ACGT"), "1-CTGC-");
+
+        let empty = Alignment::default();
+        assert_eq!(empty.cigar_string(CigarStyle::Sam, false), "");
+        assert_eq!(empty.btop(b"", b""), "");
+    }
+}
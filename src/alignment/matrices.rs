@@ -0,0 +1,183 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Amino acid substitution matrices for use as scoring functions of the pairwise aligners.
+//!
+//! [`Matrix`](enum.Matrix.html) bundles the commonly used BLOSUM and PAM matrices (see
+//! [`scores`](../../scores/index.html) for the underlying tables) and implements
+//! [`MatchFunc`](../pairwise/trait.MatchFunc.html). Symbols are looked up case-insensitively,
+//! and symbols without a row in the matrix are scored like `X`. Lower BLOSUM and higher PAM
+//! numbers are suited for more divergent sequences.
+//!
+//! Any closure `Fn(u8, u8) -> i32` can be used as scoring function as well, e.g. to score
+//! IUPAC ambiguity codes or to wrap a matrix.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::alignment::matrices::Matrix;
+//! use bio::alignment::pairwise::Aligner;
+//! use bio::alphabets::iupac;
+//!
+//! let x = b"MEEPQSDPSV";
+//! let y = b"MEEPQSDLSV";
+//! let mut aligner = Aligner::new(-10, -1, Matrix::Blosum80);
+//! assert_eq!(aligner.global(x, y).score, 49);
+//! let matrix: Matrix = "pam30".parse().unwrap();
+//! assert_eq!(matrix, Matrix::Pam30);
+//!
+//! // a closure accepting a base if it is denoted by an ambiguity code of the other sequence
+//! let ambiguous = |a: u8, b: u8| {
+//!     if iupac::contains(a, b) || iupac::contains(b, a) {
+//!         1
+//!     } else {
+//!         -1
+//!     }
+//! };
+//! let mut aligner = Aligner::new(-5, -1, ambiguous);
+//! assert_eq!(aligner.global(b"ACGTRA", b"ACGTGA").score, 6);
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use crate::alignment::pairwise::MatchFunc;
+use crate::scores;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum Error {
+    #[error("unknown substitution matrix {name}")]
+    UnknownMatrix { name: String },
+}
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// A substitution matrix for amino acids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Matrix {
+    Blosum45,
+    Blosum62,
+    Blosum80,
+    Pam30,
+    Pam70,
+    Pam250,
+}
+
+impl Matrix {
+    /// All bundled matrices.
+    pub const ALL: [Matrix; 6] = [
+        Matrix::Blosum45,
+        Matrix::Blosum62,
+        Matrix::Blosum80,
+        Matrix::Pam30,
+        Matrix::Pam70,
+        Matrix::Pam250,
+    ];
+
+    /// The name of the matrix, e.g. `BLOSUM62`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Matrix::Blosum45 => "BLOSUM45",
+            Matrix::Blosum62 => "BLOSUM62",
+            Matrix::Blosum80 => "BLOSUM80",
+            Matrix::Pam30 => "PAM30",
+            Matrix::Pam70 => "PAM70",
+            Matrix::Pam250 => "PAM250",
+        }
+    }
+
+    /// Substitution score of the given symbols.
+    pub fn score(&self, a: u8, b: u8) -> i32 {
+        let (a, b) = (normalize(a), normalize(b));
+        match self {
+            Matrix::Blosum45 => scores::blosum45(a, b),
+            Matrix::Blosum62 => scores::blosum62(a, b),
+            Matrix::Blosum80 => scores::blosum80(a, b),
+            Matrix::Pam30 => scores::pam30(a, b),
+            Matrix::Pam70 => scores::pam70(a, b),
+            Matrix::Pam250 => scores::pam250(a, b),
+        }
+    }
+}
+
+/// Map a symbol to the rows of the tables in `scores`.
+fn normalize(a: u8) -> u8 {
+    match a.to_ascii_uppercase() {
+        a @ b'A'..=b'Z' | a @ b'*' => a,
+        _ => b'X',
+    }
+}
+
+impl MatchFunc for Matrix {
+    #[inline]
+    fn score(&self, a: u8, b: u8) -> i32 {
+        Matrix::score(self, a, b)
+    }
+}
+
+impl fmt::Display for Matrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Matrix {
+    type Err = Error;
+
+    /// Parse a matrix name, ignoring case.
+    fn from_str(name: &str) -> Result<Self> {
+        Matrix::ALL
+            .iter()
+            .copied()
+            .find(|matrix| matrix.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| Error::UnknownMatrix {
+                name: name.to_owned(),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AMINO_ACIDS: &[u8] = b"ARNDCQEGHILKMFPSTWYVBZX*";
+
+    #[test]
+    fn test_symmetric_and_case_insensitive() {
+        for matrix in Matrix::ALL.iter() {
+            for &a in AMINO_ACIDS {
+                for &b in AMINO_ACIDS {
+                    assert_eq!(matrix.score(a, b), matrix.score(b, a));
+                    assert_eq!(matrix.score(a.to_ascii_lowercase(), b), matrix.score(a, b));
+                }
+            }
+            assert_eq!(matrix.score(b'-', b'A'), matrix.score(b'X', b'A'));
+        }
+    }
+
+    #[test]
+    fn test_known_scores() {
+        assert_eq!(Matrix::Blosum45.score(b'W', b'W'), 15);
+        assert_eq!(Matrix::Blosum62.score(b'W', b'W'), 11);
+        assert_eq!(Matrix::Blosum80.score(b'C', b'C'), 9);
+        assert_eq!(Matrix::Pam30.score(b'W', b'C'), -15);
+        assert_eq!(Matrix::Pam70.score(b'M', b'M'), 10);
+        assert_eq!(Matrix::Pam250.score(b'W', b'W'), 17);
+    }
+
+    #[test]
+    fn test_parse() {
+        for matrix in Matrix::ALL.iter() {
+            assert_eq!(matrix.to_string().parse::<Matrix>(), Ok(*matrix));
+        }
+        assert_eq!(
+            "BLOSUM50".parse::<Matrix>(),
+            Err(Error::UnknownMatrix {
+                name: "BLOSUM50".to_owned()
+            })
+        );
+    }
+}
@@ -5,10 +5,16 @@
 
 //! Various alignment and distance computing algorithms.
 
+pub mod coordinates;
 pub mod distance;
+pub mod export;
+pub mod matrices;
+pub mod msa;
 pub mod pairwise;
 pub mod poa;
 pub mod sparse;
+pub mod summary;
+pub mod wfa;
 
 // Re-export the alignment types.
 pub use bio_types::alignment::*;
@@ -0,0 +1,310 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Consensus sequences, column conservation and percent identity of aligned sequences.
+//!
+//! Symbols are compared case-insensitively, and `-` and `.` both denote gaps.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::alignment::msa::consensus::{self, Consensus, Identity};
+//!
+//! let rows = [b"ACGT-A", b"ACCTTA", b"AC-TTA", b"TCGT-A"];
+//!
+//! assert_eq!(consensus::majority(&rows).unwrap(), b"ACGTTA");
+//! // report N where no symbol reaches 75%, and keep gap columns
+//! let strict = Consensus::new().threshold(0.75).keep_gaps(true);
+//! assert_eq!(strict.compute(&rows).unwrap(), b"ACNTNA");
+//!
+//! assert_eq!(consensus::conservation(&rows).unwrap()[2], 0.5);
+//! let identity = consensus::percent_identity(b"ACGT-A", b"ACCTTA", Identity::AlignedPositions);
+//! assert_eq!(identity.unwrap(), 80.0);
+//! ```
+
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum Error {
+    #[error("aligned row {row} has length {len}, expected {expected}")]
+    UnequalLength {
+        row: usize,
+        len: usize,
+        expected: usize,
+    },
+}
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The gap symbol used in consensus sequences.
+pub const GAP: u8 = b'-';
+
+fn is_gap(a: u8) -> bool {
+    a == b'-' || a == b'.'
+}
+
+/// Normalize a symbol for comparison.
+fn normalize(a: u8) -> u8 {
+    if is_gap(a) {
+        GAP
+    } else {
+        a.to_ascii_uppercase()
+    }
+}
+
+/// Check that all rows have the same length, and return it.
+fn columns<T: AsRef<[u8]>>(rows: &[T]) -> Result<usize> {
+    let expected = rows.first().map_or(0, |row| row.as_ref().len());
+    for (row, seq) in rows.iter().enumerate() {
+        let len = seq.as_ref().len();
+        if len != expected {
+            return Err(Error::UnequalLength { row, len, expected });
+        }
+    }
+    Ok(expected)
+}
+
+/// Symbol counts of a single column.
+struct Column {
+    counts: [usize; 256],
+}
+
+impl Column {
+    fn new<T: AsRef<[u8]>>(rows: &[T], col: usize) -> Self {
+        let mut counts = [0; 256];
+        for row in rows {
+            counts[normalize(row.as_ref()[col]) as usize] += 1;
+        }
+        Column { counts }
+    }
+
+    /// The most frequent symbol and its count. Ties are broken in favor of residues over
+    /// gaps, and then in favor of the smaller symbol.
+    fn most_frequent(&self, include_gaps: bool) -> Option<(u8, usize)> {
+        let residues = self
+            .counts
+            .iter()
+            .enumerate()
+            .filter(|&(a, &count)| count > 0 && a as u8 != GAP)
+            // max_by_key returns the last maximum, so iterate in reverse
+            .rev()
+            .max_by_key(|&(_, &count)| count)
+            .map(|(a, &count)| (a as u8, count));
+        let gaps = self.counts[GAP as usize];
+        match residues {
+            Some((_, count)) if include_gaps && gaps > count => Some((GAP, gaps)),
+            None if include_gaps && gaps > 0 => Some((GAP, gaps)),
+            residues => residues,
+        }
+    }
+}
+
+/// A configurable consensus calculation. By default, the most frequent symbol of each
+/// column is reported (plurality consensus), and columns in which gaps are most frequent
+/// are omitted.
+#[derive(Debug, Clone, Copy)]
+pub struct Consensus {
+    threshold: f64,
+    ambiguous: u8,
+    keep_gaps: bool,
+}
+
+impl Default for Consensus {
+    fn default() -> Self {
+        Consensus {
+            threshold: 0.0,
+            ambiguous: b'N',
+            keep_gaps: false,
+        }
+    }
+}
+
+impl Consensus {
+    /// Create a new plurality consensus calculation.
+    pub fn new() -> Self {
+        Consensus::default()
+    }
+
+    /// Require the most frequent symbol to occur in at least the given fraction of the rows
+    /// (gaps included). Otherwise, the ambiguity symbol is reported. E.g., a threshold
+    /// above 0.5 yields a strict majority consensus.
+    pub fn threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Set the symbol reported for columns without sufficiently frequent symbol (`N` by
+    /// default, use `X` for proteins).
+    pub fn ambiguous(mut self, ambiguous: u8) -> Self {
+        self.ambiguous = ambiguous;
+        self
+    }
+
+    /// Report gaps in columns in which gaps are most frequent, instead of omitting them,
+    /// so that the consensus has the length of the alignment.
+    pub fn keep_gaps(mut self, keep_gaps: bool) -> Self {
+        self.keep_gaps = keep_gaps;
+        self
+    }
+
+    /// Compute the consensus of the given aligned rows.
+    pub fn compute<T: AsRef<[u8]>>(&self, rows: &[T]) -> Result<Vec<u8>> {
+        let n = rows.len() as f64;
+        let len = columns(rows)?;
+        let mut consensus = Vec::with_capacity(len);
+        for col in 0..len {
+            let (symbol, count) = match Column::new(rows, col).most_frequent(true) {
+                Some(most_frequent) => most_frequent,
+                None => continue,
+            };
+            if symbol == GAP && !self.keep_gaps {
+                continue;
+            }
+            if (count as f64) < self.threshold * n {
+                consensus.push(self.ambiguous);
+            } else {
+                consensus.push(symbol);
+            }
+        }
+        Ok(consensus)
+    }
+}
+
+/// Plurality consensus of the given aligned rows, omitting columns in which gaps are most
+/// frequent. See [`Consensus`](struct.Consensus.html) for more options.
+pub fn majority<T: AsRef<[u8]>>(rows: &[T]) -> Result<Vec<u8>> {
+    Consensus::new().compute(rows)
+}
+
+/// Conservation of each column, i.e. the fraction of rows carrying the most frequent
+/// residue. Gaps are never considered conserved.
+pub fn conservation<T: AsRef<[u8]>>(rows: &[T]) -> Result<Vec<f64>> {
+    let n = rows.len() as f64;
+    Ok((0..columns(rows)?)
+        .map(|col| {
+            Column::new(rows, col)
+                .most_frequent(false)
+                .map_or(0.0, |(_, count)| count as f64 / n)
+        })
+        .collect())
+}
+
+/// Denominator used for percent identity, following Raghava and Barton (2006).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Identity {
+    /// Identical positions over aligned residue pairs, i.e. columns without gaps.
+    AlignedPositions,
+    /// Identical positions over the alignment length, i.e. columns not consisting of
+    /// gaps only.
+    AlignmentLength,
+    /// Identical positions over the length of the shorter (ungapped) sequence.
+    ShorterSequence,
+}
+
+/// Percent identity of two aligned sequences. Returns 0 if the denominator is zero.
+pub fn percent_identity(a: &[u8], b: &[u8], identity: Identity) -> Result<f64> {
+    columns(&[a, b])?;
+    let mut identical = 0;
+    let mut aligned = 0;
+    let mut length = 0;
+    let (mut len_a, mut len_b) = (0, 0);
+    for (a, b) in a
+        .iter()
+        .map(|&a| normalize(a))
+        .zip(b.iter().map(|&b| normalize(b)))
+    {
+        let (gap_a, gap_b) = (a == GAP, b == GAP);
+        len_a += !gap_a as usize;
+        len_b += !gap_b as usize;
+        if !(gap_a && gap_b) {
+            length += 1;
+        }
+        if !gap_a && !gap_b {
+            aligned += 1;
+            identical += (a == b) as usize;
+        }
+    }
+    let denominator = match identity {
+        Identity::AlignedPositions => aligned,
+        Identity::AlignmentLength => length,
+        Identity::ShorterSequence => len_a.min(len_b),
+    };
+    if denominator == 0 {
+        Ok(0.0)
+    } else {
+        Ok(100.0 * identical as f64 / denominator as f64)
+    }
+}
+
+/// Pairwise percent identities of all rows, as a symmetric matrix with 100 on the diagonal.
+pub fn identity_matrix<T: AsRef<[u8]>>(rows: &[T], identity: Identity) -> Result<Vec<Vec<f64>>> {
+    columns(rows)?;
+    let mut matrix = vec![vec![100.0; rows.len()]; rows.len()];
+    for i in 0..rows.len() {
+        for j in i + 1..rows.len() {
+            let pid = percent_identity(rows[i].as_ref(), rows[j].as_ref(), identity)?;
+            matrix[i][j] = pid;
+            matrix[j][i] = pid;
+        }
+    }
+    Ok(matrix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_consensus_gaps_and_ties() {
+        let rows = [b"a-C.", b"A-g-", b"T.G-"];
+        assert_eq!(majority(&rows).unwrap(), b"AG");
+        assert_eq!(
+            Consensus::new().keep_gaps(true).compute(&rows).unwrap(),
+            b"A-G-"
+        );
+        // a tie between a residue and gaps is resolved in favor of the residue
+        assert_eq!(majority(&[b"A", b"-"]).unwrap(), b"A");
+        assert_eq!(majority(&[b"T", b"A"]).unwrap(), b"A");
+        assert_eq!(majority::<&[u8]>(&[]).unwrap(), b"");
+    }
+
+    #[test]
+    fn test_unequal_length() {
+        assert_eq!(
+            majority(&[&b"ACGT"[..], &b"ACG"[..]]),
+            Err(Error::UnequalLength {
+                row: 1,
+                len: 3,
+                expected: 4
+            })
+        );
+        assert!(percent_identity(b"AC", b"A", Identity::AlignedPositions).is_err());
+    }
+
+    #[test]
+    fn test_identity_denominators() {
+        let (a, b) = (b"AC-GTT-", b"ACAG-A-");
+        assert_eq!(
+            percent_identity(a, b, Identity::AlignedPositions).unwrap(),
+            75.0
+        );
+        assert_eq!(
+            percent_identity(a, b, Identity::AlignmentLength).unwrap(),
+            50.0
+        );
+        assert_eq!(
+            percent_identity(a, b, Identity::ShorterSequence).unwrap(),
+            60.0
+        );
+        assert_eq!(
+            percent_identity(b"--", b"AC", Identity::AlignedPositions).unwrap(),
+            0.0
+        );
+
+        let matrix = identity_matrix(&[a, b, a], Identity::AlignedPositions).unwrap();
+        assert_eq!(matrix[0][1], 75.0);
+        assert_eq!(matrix[2][1], 75.0);
+        assert_eq!(matrix[0][2], 100.0);
+    }
+}
@@ -0,0 +1,148 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Utilities for multiple sequence alignments, given as rows of equal length in which
+//! gaps are denoted by `-` or `.`.
+
+use std::io;
+
+use crate::io::fasta;
+
+pub mod consensus;
+pub mod progressive;
+
+/// A multiple sequence alignment, consisting of named rows of equal length, with gaps
+/// denoted by `-`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MultipleSequenceAlignment {
+    ids: Vec<String>,
+    rows: Vec<Vec<u8>>,
+}
+
+impl MultipleSequenceAlignment {
+    /// Create a new alignment from ids and aligned rows.
+    ///
+    /// # Panics
+    ///
+    /// If the number of ids and rows differs, or if the rows have different lengths.
+    pub fn new(ids: Vec<String>, rows: Vec<Vec<u8>>) -> Self {
+        assert_eq!(ids.len(), rows.len(), "expecting one id per row");
+        assert!(
+            rows.windows(2).all(|w| w[0].len() == w[1].len()),
+            "rows have to be of equal length"
+        );
+        MultipleSequenceAlignment { ids, rows }
+    }
+
+    /// The ids of the rows.
+    pub fn ids(&self) -> &[String] {
+        &self.ids
+    }
+
+    /// The aligned rows.
+    pub fn rows(&self) -> &[Vec<u8>] {
+        &self.rows
+    }
+
+    /// Number of columns.
+    pub fn len(&self) -> usize {
+        self.rows.first().map_or(0, |row| row.len())
+    }
+
+    /// Whether the alignment has no columns.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Write the rows as FASTA records.
+    pub fn write_fasta<W: io::Write>(&self, writer: W) -> io::Result<()> {
+        let mut writer = fasta::Writer::new(writer);
+        for (id, row) in self.ids.iter().zip(&self.rows) {
+            writer.write(id, None, row)?;
+        }
+        writer.flush()
+    }
+
+    /// Write the alignment in Clustal format, in blocks of `width` columns. Fully conserved
+    /// columns are marked with `*`.
+    pub fn write_clustal<W: io::Write>(&self, mut writer: W, width: usize) -> io::Result<()> {
+        assert!(width > 0, "width has to be positive");
+        let pad = self.ids.iter().map(|id| id.len()).max().unwrap_or(0) + 6;
+        writeln!(writer, "CLUSTAL W multiple sequence alignment\n")?;
+        for start in (0..self.len()).step_by(width) {
+            let end = (start + width).min(self.len());
+            writeln!(writer)?;
+            for (id, row) in self.ids.iter().zip(&self.rows) {
+                writeln!(
+                    writer,
+                    "{:pad$}{}",
+                    id,
+                    String::from_utf8_lossy(&row[start..end]),
+                    pad = pad
+                )?;
+            }
+            let conservation: String = (start..end)
+                .map(|col| {
+                    let first = self.rows[0][col];
+                    if first != b'-' && self.rows.iter().all(|row| row[col] == first) {
+                        '*'
+                    } else {
+                        ' '
+                    }
+                })
+                .collect();
+            writeln!(writer, "{:pad$}{}", "", conservation, pad = pad)?;
+        }
+        Ok(())
+    }
+
+    /// Write the alignment in Stockholm format.
+    pub fn write_stockholm<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        let pad = self.ids.iter().map(|id| id.len()).max().unwrap_or(0) + 1;
+        writeln!(writer, "# STOCKHOLM 1.0")?;
+        for (id, row) in self.ids.iter().zip(&self.rows) {
+            writeln!(
+                writer,
+                "{:pad$}{}",
+                id,
+                String::from_utf8_lossy(row),
+                pad = pad
+            )?;
+        }
+        writeln!(writer, "//")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_writers() {
+        let msa = MultipleSequenceAlignment::new(
+            vec!["a".to_owned(), "seq2".to_owned()],
+            vec![b"AC-GT".to_vec(), b"ACTGA".to_vec()],
+        );
+        let mut fasta = Vec::new();
+        msa.write_fasta(&mut fasta).unwrap();
+        assert_eq!(fasta, b">a\nAC-GT\n>seq2\nACTGA\n");
+
+        let mut stockholm = Vec::new();
+        msa.write_stockholm(&mut stockholm).unwrap();
+        assert_eq!(
+            String::from_utf8(stockholm).unwrap(),
+            "# STOCKHOLM 1.0\na    AC-GT\nseq2 ACTGA\n//\n"
+        );
+
+        let mut clustal = Vec::new();
+        msa.write_clustal(&mut clustal, 3).unwrap();
+        assert_eq!(
+            String::from_utf8(clustal).unwrap(),
+            "CLUSTAL W multiple sequence alignment\n\n\n\
+             a         AC-\nseq2      ACT\n          ** \n\n\
+             a         GT\nseq2      GA\n          * \n"
+        );
+    }
+}
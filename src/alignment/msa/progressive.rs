@@ -0,0 +1,377 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Progressive multiple sequence alignment for small alignment tasks.
+//!
+//! Sequences are compared by their k-mer content, a guide tree is built from the distances
+//! with neighbor joining, and the sequences are aligned following the order in which the
+//! guide tree joins them. Each join aligns two profiles (i.e. already aligned groups of
+//! sequences) with affine gap costs, scoring two columns by the average score of all
+//! pairs of residues they contain. As always with progressive alignment, gaps introduced
+//! in early joins are never revised.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::alignment::msa::progressive::ProgressiveAligner;
+//!
+//! let score = |a: u8, b: u8| if a == b { 1i32 } else { -1i32 };
+//! let aligner = ProgressiveAligner::new(-3, -1, score);
+//! let msa = aligner.align(&[
+//!     ("a", &b"ACGTTGCAAGT"[..]),
+//!     ("b", &b"ACGTGCAAGT"[..]),
+//!     ("c", &b"ACGTTGCAGT"[..]),
+//! ]);
+//! assert_eq!(
+//!     msa.rows(),
+//!     &[
+//!         b"ACGTTGCAAGT".to_vec(),
+//!         b"ACG-TGCAAGT".to_vec(),
+//!         b"ACGTTGC-AGT".to_vec()
+//!     ]
+//! );
+//!
+//! let mut out = Vec::new();
+//! msa.write_stockholm(&mut out).unwrap();
+//! ```
+
+use std::collections::HashMap;
+
+use crate::alignment::msa::MultipleSequenceAlignment;
+use crate::alignment::pairwise::{MatchFunc, Scoring};
+
+/// The gap symbol inserted into the rows.
+const GAP: u8 = b'-';
+
+/// Input indices and aligned rows of a group of sequences.
+type Profile = (Vec<usize>, Vec<Vec<u8>>);
+
+/// Aligned columns of two profiles, `None` denoting a gap column.
+type Column = (Option<usize>, Option<usize>);
+
+/// k-mer distance of two sequences, i.e. one minus the fraction of shared k-mers
+/// (Edgar 2004). Sequences shorter than `k` have distance one.
+pub fn kmer_distance(a: &[u8], b: &[u8], k: usize) -> f64 {
+    fn counts(seq: &[u8], k: usize) -> HashMap<&[u8], usize> {
+        let mut counts = HashMap::new();
+        for kmer in seq.windows(k) {
+            *counts.entry(kmer).or_insert(0) += 1;
+        }
+        counts
+    }
+    let n = a.len().min(b.len());
+    if n < k {
+        return 1.0;
+    }
+    let (counts_a, counts_b) = (counts(a, k), counts(b, k));
+    let shared: usize = counts_a
+        .iter()
+        .map(|(kmer, count)| (*count).min(*counts_b.get(kmer).unwrap_or(&0)))
+        .sum();
+    1.0 - shared as f64 / (n - k + 1) as f64
+}
+
+/// Order in which neighbor joining on the given distance matrix joins the clusters. The
+/// initial clusters are numbered `0..n`, and the cluster created by the i-th join gets
+/// number `n + i`.
+pub fn neighbor_joining_order(distances: &[Vec<f64>]) -> Vec<(usize, usize)> {
+    let n = distances.len();
+    let mut dist: Vec<Vec<f64>> = distances.to_vec();
+    // cluster number of each row of the (shrinking) distance matrix
+    let mut clusters: Vec<usize> = (0..n).collect();
+    let mut joins = Vec::new();
+    while clusters.len() > 1 {
+        let r = clusters.len();
+        let (i, j) = if r == 2 {
+            (0, 1)
+        } else {
+            let sums: Vec<f64> = dist.iter().map(|row| row.iter().sum()).collect();
+            let mut best = (f64::INFINITY, 0, 1);
+            for i in 0..r {
+                for j in i + 1..r {
+                    let q = (r - 2) as f64 * dist[i][j] - sums[i] - sums[j];
+                    if q < best.0 {
+                        best = (q, i, j);
+                    }
+                }
+            }
+            (best.1, best.2)
+        };
+        joins.push((clusters[i], clusters[j]));
+
+        let mut new: Vec<f64> = (0..r)
+            .map(|k| (dist[i][k] + dist[j][k] - dist[i][j]) / 2.0)
+            .collect();
+        new[i] = 0.0;
+        // replace i by the new cluster and remove j (j > i)
+        for (row, &d) in dist.iter_mut().zip(&new) {
+            row[i] = d;
+            row.remove(j);
+        }
+        new.remove(j);
+        dist[i] = new;
+        dist.remove(j);
+        clusters[i] = n + joins.len() - 1;
+        clusters.remove(j);
+    }
+    joins
+}
+
+/// Progressive multiple sequence aligner.
+#[derive(Debug, Clone)]
+pub struct ProgressiveAligner<F: MatchFunc> {
+    scoring: Scoring<F>,
+    k: usize,
+}
+
+impl<F: MatchFunc> ProgressiveAligner<F> {
+    /// Create a new aligner with the given gap open and gap extend penalties (both should
+    /// not be positive) and the score function. Distances are computed from 3-mers.
+    pub fn new(gap_open: i32, gap_extend: i32, match_fn: F) -> Self {
+        ProgressiveAligner::with_scoring(Scoring::new(gap_open, gap_extend, match_fn))
+    }
+
+    /// Create a new aligner with the given scoring. Clip penalties are ignored.
+    pub fn with_scoring(scoring: Scoring<F>) -> Self {
+        ProgressiveAligner { scoring, k: 3 }
+    }
+
+    /// Set the k-mer length used for the distances (3 by default, smaller values are
+    /// better suited for short or divergent sequences).
+    pub fn k(mut self, k: usize) -> Self {
+        assert!(k > 0, "k has to be positive");
+        self.k = k;
+        self
+    }
+
+    /// Align the given pairs of id and sequence. Rows are reported in input order.
+    pub fn align<I, S>(&self, sequences: &[(I, S)]) -> MultipleSequenceAlignment
+    where
+        I: AsRef<str>,
+        S: AsRef<[u8]>,
+    {
+        let n = sequences.len();
+        let ids = sequences
+            .iter()
+            .map(|(id, _)| id.as_ref().to_owned())
+            .collect();
+        let seqs: Vec<&[u8]> = sequences.iter().map(|(_, seq)| seq.as_ref()).collect();
+
+        let mut distances = vec![vec![0.0; n]; n];
+        for i in 0..n {
+            for j in i + 1..n {
+                let d = kmer_distance(seqs[i], seqs[j], self.k);
+                distances[i][j] = d;
+                distances[j][i] = d;
+            }
+        }
+
+        let mut profiles: Vec<Option<Profile>> = seqs
+            .iter()
+            .enumerate()
+            .map(|(i, seq)| Some((vec![i], vec![seq.to_vec()])))
+            .collect();
+        for (a, b) in neighbor_joining_order(&distances) {
+            let (mut indices_a, rows_a) = profiles[a].take().unwrap();
+            let (indices_b, rows_b) = profiles[b].take().unwrap();
+            let mut rows = self.align_profiles(rows_a, rows_b);
+            let rows_b = rows.split_off(indices_a.len());
+            indices_a.extend(indices_b);
+            profiles.push(Some((indices_a, rows.into_iter().chain(rows_b).collect())));
+        }
+
+        let mut rows = vec![Vec::new(); n];
+        if let Some(Some((indices, aligned))) = profiles.pop() {
+            for (i, row) in indices.into_iter().zip(aligned) {
+                rows[i] = row;
+            }
+        }
+        MultipleSequenceAlignment::new(ids, rows)
+    }
+
+    /// Symbol frequencies of each column of a profile, excluding gaps.
+    fn columns(rows: &[Vec<u8>]) -> Vec<Vec<(u8, f64)>> {
+        let len = rows.first().map_or(0, |row| row.len());
+        (0..len)
+            .map(|col| {
+                let mut counts: Vec<(u8, f64)> = Vec::new();
+                for row in rows {
+                    let a = row[col];
+                    if a == GAP {
+                        continue;
+                    }
+                    match counts.iter_mut().find(|(b, _)| *b == a) {
+                        Some((_, count)) => *count += 1.0,
+                        None => counts.push((a, 1.0)),
+                    }
+                }
+                for (_, count) in counts.iter_mut() {
+                    *count /= rows.len() as f64;
+                }
+                counts
+            })
+            .collect()
+    }
+
+    /// Align two profiles with affine gap costs, returning the rows of both, in order.
+    fn align_profiles(&self, rows_a: Vec<Vec<u8>>, rows_b: Vec<Vec<u8>>) -> Vec<Vec<u8>> {
+        let (cols_a, cols_b) = (Self::columns(&rows_a), Self::columns(&rows_b));
+        let (m, n) = (cols_a.len(), cols_b.len());
+        let (gap_open, gap_extend) = (
+            f64::from(self.scoring.gap_open),
+            f64::from(self.scoring.gap_extend),
+        );
+        let score = |i: usize, j: usize| -> f64 {
+            let mut score = 0.0;
+            for &(a, fa) in &cols_a[i] {
+                for &(b, fb) in &cols_b[j] {
+                    score += fa * fb * f64::from(self.scoring.match_fn.score(a, b));
+                }
+            }
+            score
+        };
+
+        // best scores ending with aligned columns (0), a gap in b (1) or a gap in a (2)
+        let neg = f64::NEG_INFINITY;
+        let mut s = vec![vec![[neg; 3]; n + 1]; m + 1];
+        s[0][0][0] = 0.0;
+        for (i, row) in s.iter_mut().enumerate().skip(1) {
+            row[0][1] = gap_open + gap_extend * i as f64;
+        }
+        for (j, cell) in s[0].iter_mut().enumerate().skip(1) {
+            cell[2] = gap_open + gap_extend * j as f64;
+        }
+        let best = |cell: &[f64; 3]| cell.iter().cloned().fold(neg, f64::max);
+        for i in 1..=m {
+            for j in 1..=n {
+                let diag = best(&s[i - 1][j - 1]) + score(i - 1, j - 1);
+                let up = &s[i - 1][j];
+                let left = &s[i][j - 1];
+                let gap_b = (up[0].max(up[2]) + gap_open).max(up[1]) + gap_extend;
+                let gap_a = (left[0].max(left[1]) + gap_open).max(left[2]) + gap_extend;
+                s[i][j] = [diag, gap_b, gap_a];
+            }
+        }
+
+        // traceback, collecting the columns of a and b (None for gaps) in reverse
+        let mut columns: Vec<Column> = Vec::new();
+        let (mut i, mut j) = (m, n);
+        let argmax = |cell: &[f64; 3]| {
+            (0..3)
+                .rev()
+                .max_by(|&x, &y| cell[x].partial_cmp(&cell[y]).unwrap())
+                .unwrap()
+        };
+        let mut state = argmax(&s[m][n]);
+        while i > 0 || j > 0 {
+            let value = s[i][j][state];
+            match state {
+                0 => {
+                    columns.push((Some(i - 1), Some(j - 1)));
+                    state = argmax(&s[i - 1][j - 1]);
+                    i -= 1;
+                    j -= 1;
+                }
+                1 => {
+                    columns.push((Some(i - 1), None));
+                    let up = &s[i - 1][j];
+                    state = if up[1] + gap_extend == value {
+                        1
+                    } else if up[0] + gap_open + gap_extend == value {
+                        0
+                    } else {
+                        2
+                    };
+                    i -= 1;
+                }
+                _ => {
+                    columns.push((None, Some(j - 1)));
+                    let left = &s[i][j - 1];
+                    state = if left[2] + gap_extend == value {
+                        2
+                    } else if left[0] + gap_open + gap_extend == value {
+                        0
+                    } else {
+                        1
+                    };
+                    j -= 1;
+                }
+            }
+        }
+        columns.reverse();
+
+        let expand = |rows: &[Vec<u8>], pick: &dyn Fn(&Column) -> Option<usize>| {
+            rows.iter()
+                .map(|row| {
+                    columns
+                        .iter()
+                        .map(|column| pick(column).map_or(GAP, |col| row[col]))
+                        .collect::<Vec<u8>>()
+                })
+                .collect::<Vec<_>>()
+        };
+        let mut rows = expand(&rows_a, &|column| column.0);
+        rows.extend(expand(&rows_b, &|column| column.1));
+        rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kmer_distance() {
+        assert_eq!(kmer_distance(b"ACGTACGT", b"ACGTACGT", 3), 0.0);
+        assert_eq!(kmer_distance(b"AAAA", b"CCCC", 2), 1.0);
+        assert_eq!(kmer_distance(b"AC", b"ACGT", 3), 1.0);
+        assert!((kmer_distance(b"ACGTT", b"ACGTA", 2) - 0.25).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_neighbor_joining_order() {
+        // a and b, as well as c and d, are close to each other
+        let d = vec![
+            vec![0.0, 0.1, 0.8, 0.9],
+            vec![0.1, 0.0, 0.9, 0.8],
+            vec![0.8, 0.9, 0.0, 0.1],
+            vec![0.9, 0.8, 0.1, 0.0],
+        ];
+        let order = neighbor_joining_order(&d);
+        assert_eq!(order.len(), 3);
+        assert_eq!(order[0], (0, 1));
+        // with three clusters left, all joins are equivalent
+        assert_eq!(order[1], (4, 2));
+        assert_eq!(order[2], (5, 3));
+    }
+
+    #[test]
+    fn test_align_preserves_sequences() {
+        let seqs = [
+            ("s1", &b"MKVLAAGIVGLLLAQ"[..]),
+            ("s2", &b"MKVLAGIVGLLLAQ"[..]),
+            ("s3", &b"MRVLAAGIVGLLAQ"[..]),
+            ("s4", &b"MKVLAAGIIGLLLAQW"[..]),
+        ];
+        let msa = ProgressiveAligner::new(-5, -1, crate::scores::blosum62)
+            .k(2)
+            .align(&seqs);
+        assert_eq!(msa.ids(), &["s1", "s2", "s3", "s4"]);
+        for ((_, seq), row) in seqs.iter().zip(msa.rows()) {
+            let ungapped: Vec<u8> = row.iter().cloned().filter(|&a| a != GAP).collect();
+            assert_eq!(&ungapped[..], *seq);
+            assert_eq!(row.len(), msa.len());
+        }
+        assert_eq!(msa.rows()[0], b"MKVLAAGIVGLLLAQ-".to_vec());
+    }
+
+    #[test]
+    fn test_trivial_inputs() {
+        let aligner = ProgressiveAligner::new(-5, -1, crate::scores::blosum62);
+        assert!(aligner.align::<&str, &[u8]>(&[]).is_empty());
+        let msa = aligner.align(&[("a", b"MKV")]);
+        assert_eq!(msa.rows(), &[b"MKV".to_vec()]);
+    }
+}
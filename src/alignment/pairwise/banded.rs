@@ -68,7 +68,7 @@
 //! let y = b"AAAAACGTACGTACGTGTGCATCATCATGTGCGTATCATAGATAGATGTAGATGATCCACAGTAAAA";
 //! let mut aligner = Aligner::with_capacity_and_scoring(x.len(), y.len(), scoring, k, w);
 //! let alignment = aligner.custom(x, y);
-//! println!("{}", alignment.pretty(x, y));
+//! println!("{}", alignment.pretty(x, y, 80));
 //! assert_eq!(alignment.score, 49);
 //! let mut correct_ops = Vec::new();
 //! correct_ops.push(Yclip(4));
@@ -389,6 +389,25 @@ impl<F: MatchFunc> Aligner<F> {
         self.compute_alignment(x, y)
     }
 
+    /// Compute the alignment with custom clip penalties within a fixed band around the
+    /// straight line from the start to the end of both sequences, i.e. around the main
+    /// diagonal for sequences of equal length. Cells within a distance of `w` in x or y
+    /// direction are computed. No k-mer matches are searched, so this is suited for
+    /// sequences that are known to be similar over their entire length (e.g. when polishing
+    /// a read against a draft), with at most about `w` net insertions or deletions at any
+    /// point.
+    ///
+    /// Complexity: O(max(m, n) * w)
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - Textslice
+    /// * `y` - Textslice
+    pub fn custom_with_diagonal_band(&mut self, x: TextSlice<'_>, y: TextSlice<'_>) -> Alignment {
+        self.band = Band::create_diagonal(x.len(), y.len(), self.w);
+        self.compute_alignment(x, y)
+    }
+
     // Computes the alignment. The band needs to be populated prior
     // to calling this function
     #[inline(never)]
@@ -889,6 +908,36 @@ impl<F: MatchFunc> Aligner<F> {
         alignment
     }
 
+    /// Calculate global alignment of x against y within a fixed band around the diagonal,
+    /// see [`custom_with_diagonal_band`](#method.custom_with_diagonal_band).
+    pub fn global_with_diagonal_band(&mut self, x: TextSlice<'_>, y: TextSlice<'_>) -> Alignment {
+        // Store the current clip penalties
+        let clip_penalties = [
+            self.scoring.xclip_prefix,
+            self.scoring.xclip_suffix,
+            self.scoring.yclip_prefix,
+            self.scoring.yclip_suffix,
+        ];
+
+        // Temporarily Over-write the clip penalties
+        self.scoring.xclip_prefix = MIN_SCORE;
+        self.scoring.xclip_suffix = MIN_SCORE;
+        self.scoring.yclip_prefix = MIN_SCORE;
+        self.scoring.yclip_suffix = MIN_SCORE;
+
+        // Compute the alignment
+        let mut alignment = self.custom_with_diagonal_band(x, y);
+        alignment.mode = AlignmentMode::Global;
+
+        // Set the clip penalties to the original values
+        self.scoring.xclip_prefix = clip_penalties[0];
+        self.scoring.xclip_suffix = clip_penalties[1];
+        self.scoring.yclip_prefix = clip_penalties[2];
+        self.scoring.yclip_suffix = clip_penalties[3];
+
+        alignment
+    }
+
     /// Calculate semiglobal alignment of x against y (x is global, y is local).
     pub fn semiglobal(&mut self, x: TextSlice<'_>, y: TextSlice<'_>) -> Alignment {
         // Store the current clip penalties
@@ -1358,6 +1407,14 @@ impl Band {
         band
     }
 
+    // Create a band of width 'w' around the line from (0, 0) to (m, n)
+    fn create_diagonal(m: usize, n: usize, w: usize) -> Band {
+        let mut band = Band::new(m, n);
+        band.add_gap((0, 0), (m as u32, n as u32), w);
+        band.add_entry((m as u32, n as u32), w);
+        band
+    }
+
     fn full_matrix(&mut self) {
         self.ranges.clear();
         self.ranges.resize(self.cols, 0..self.rows);
@@ -1609,6 +1666,27 @@ mod banded {
         compare_add_kmer_and_add_entry((5u32, 0u32), 6, 3, 10, 10);
     }
 
+    #[test]
+    fn test_diagonal_band() {
+        let x = b"AGCACACGTGTGCGCTATACAGTAAGTAGTAGTACACGTGTCACAGTTGTACTAGCATGAC";
+        let y = b"AGCACACGTGTGCCGCTATACAGTAAGTAGTAGTACACGTTCACAGTTGTACTAGGCATGAC";
+        let score = |a: u8, b: u8| if a == b { 1i32 } else { -1i32 };
+
+        let mut banded_aligner = banded::Aligner::new(-5, -1, &score, 10, 3);
+        let banded_alignment = banded_aligner.global_with_diagonal_band(x, y);
+        assert!(banded_aligner.band.num_cells() < (x.len() + 1) * (y.len() + 1) / 4);
+
+        let mut full_aligner = pairwise::Aligner::new(-5, -1, &score);
+        assert_eq!(banded_alignment, full_aligner.global(x, y));
+
+        // sequences of different length and empty sequences
+        let banded_alignment = banded_aligner.global_with_diagonal_band(&x[..40], y);
+        assert_eq!(banded_alignment.operations.len(), y.len());
+        assert_eq!(banded_alignment.xend, 40);
+        let banded_alignment = banded_aligner.global_with_diagonal_band(b"", b"ACG");
+        assert_eq!(banded_alignment.operations, [Del, Del, Del]);
+    }
+
     #[test]
     fn test_same() {
         let x =
@@ -1794,7 +1872,7 @@ mod banded {
         let mut aligner = banded::Aligner::with_capacity(x.len(), y.len(), -5, -1, &score, 10, 10);
         let alignment = aligner.global(x, y);
 
-        println!("aln:\n{}", alignment.pretty(x, y));
+        println!("aln:\n{}", alignment.pretty(x, y, 80));
         assert_eq!(
             alignment.operations,
             [Match, Match, Match, Ins, Ins, Ins, Match, Match, Match]
@@ -1809,7 +1887,7 @@ mod banded {
         let mut aligner = banded::Aligner::with_capacity(x.len(), y.len(), -5, -1, &score, 10, 10);
         let alignment = aligner.local(x, y);
 
-        println!("aln:\n{}", alignment.pretty(x, y));
+        println!("aln:\n{}", alignment.pretty(x, y, 80));
         assert_eq!(alignment.x_aln_len(), 0);
         assert_eq!(alignment.y_aln_len(), 0);
     }
@@ -1822,7 +1900,7 @@ mod banded {
         let mut aligner = banded::Aligner::with_capacity(x.len(), y.len(), -5, -1, &score, 10, 10);
         let alignment = aligner.global(x, y);
 
-        println!("aln:\n{}", alignment.pretty(x, y));
+        println!("aln:\n{}", alignment.pretty(x, y, 80));
 
         let mut correct = Vec::new();
         correct.extend(repeat(Match).take(11));
@@ -1866,7 +1944,7 @@ mod banded {
         let mut aligner = banded::Aligner::with_capacity(x.len(), y.len(), -5, -1, &score, 10, 10);
         let alignment = aligner.global(x, y);
 
-        println!("\naln:\n{}", alignment.pretty(x, y));
+        println!("\naln:\n{}", alignment.pretty(x, y, 80));
         assert_eq!(alignment.ystart, 0);
         assert_eq!(alignment.xstart, 0);
         assert_eq!(
@@ -1968,7 +2046,7 @@ mod banded {
         let score = |a: u8, b: u8| if a == b { 1i32 } else { -1i32 };
         let mut aligner = banded::Aligner::with_capacity(x.len(), y.len(), -5, -1, &score, 10, 10);
         let alignment = aligner.global(x, y);
-        println!("\naln:\n{}", alignment.pretty(x, y));
+        println!("\naln:\n{}", alignment.pretty(x, y, 80));
 
         assert_eq!(alignment.ystart, 0);
         assert_eq!(alignment.xstart, 0);
@@ -1992,7 +2070,7 @@ mod banded {
         let mut aligner = banded::Aligner::with_capacity(x.len(), y.len(), -5, -1, &score, 10, 10);
         let alignment = aligner.global(x, y);
 
-        println!("\naln:\n{}", alignment.pretty(x, y));
+        println!("\naln:\n{}", alignment.pretty(x, y, 80));
 
         println!("score:{}", alignment.score);
         assert_eq!(alignment.score, -9);
@@ -2014,7 +2092,7 @@ mod banded {
         let score = |a: u8, b: u8| if a == b { 1i32 } else { -1i32 };
         let mut aligner = banded::Aligner::with_capacity(x.len(), y.len(), -5, -1, &score, 10, 10);
         let alignment = aligner.global(x, y);
-        println!("\naln:\n{}", alignment.pretty(x, y));
+        println!("\naln:\n{}", alignment.pretty(x, y, 80));
 
         assert_eq!(alignment.ystart, 0);
         assert_eq!(alignment.xstart, 0);
@@ -2168,7 +2246,7 @@ mod banded {
         let mut aligner = banded::Aligner::with_scoring(scoring, 10, 10);
         let alignment = aligner.custom(x, y);
 
-        println!("{}", alignment.pretty(x, y));
+        println!("{}", alignment.pretty(x, y, 80));
         assert_eq!(alignment.score, 7);
     }
 
@@ -0,0 +1,354 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Alignment with context-dependent scores, as suited for long reads of nanopore or PacBio
+//! sequencing, whose errors are dominated by indels in homopolymers and by substitutions at
+//! low quality bases.
+//!
+//! Two extensions of the usual affine gap scoring are supported:
+//!
+//! * Gaps of residues inside homopolymers (runs of at least `min_len` identical residues in
+//!   the sequence the residue belongs to) are scored with separate, usually cheaper, gap open
+//!   and gap extend scores.
+//! * Given the Phred qualities of `x`, the score of a mismatch at a base with quality q is
+//!   scaled by the probability that the base is correct, i.e. by 1 - 10^(-q/10). Mismatches
+//!   at bases of quality zero hence come for free.
+//!
+//! Since scaled scores are fractional, the dynamic programming is done with floating point
+//! scores, and the reported score is rounded to the closest integer. Both the time and the
+//! space complexity are O(n * m) for sequences of length m and n.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::alignment::pairwise::context::Aligner;
+//! use bio::alignment::AlignmentOperation::*;
+//!
+//! let x = b"ACGTTTTTTCA";
+//! let y = b"ACGTTTTTCA";
+//! let score = |a: u8, b: u8| if a == b { 2i32 } else { -4i32 };
+//! let aligner = Aligner::new(-6, -2, score).homopolymer_gaps(-1, -1, 4);
+//! let alignment = aligner.global(x, y, None);
+//! // the additional T in x costs 2 instead of 8
+//! assert_eq!(alignment.score, 10 * 2 - 2);
+//! assert_eq!(alignment.operations.iter().filter(|&&op| op == Ins).count(), 1);
+//!
+//! // a mismatch at a base of quality 3 only costs about half the usual penalty
+//! let qual = [30, 30, 30, 3, 30, 30, 30, 30, 30, 30, 30];
+//! let alignment = aligner.global(b"ACGATTTTTCA", b"ACGTTTTTTCA", Some(&qual));
+//! assert_eq!(alignment.score, 10 * 2 - 2);
+//! ```
+
+use crate::alignment::pairwise::{MatchFunc, Scoring};
+use crate::alignment::{Alignment, AlignmentMode, AlignmentOperation};
+use crate::utils::TextSlice;
+
+const NEG: f64 = f64::NEG_INFINITY;
+
+/// Gap scores applied to residues inside homopolymers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct HomopolymerGaps {
+    gap_open: i32,
+    gap_extend: i32,
+    min_len: usize,
+}
+
+/// Aligner with homopolymer-aware gap scores and quality-scaled mismatch scores.
+#[derive(Debug, Clone)]
+pub struct Aligner<F: MatchFunc> {
+    scoring: Scoring<F>,
+    homopolymer: Option<HomopolymerGaps>,
+}
+
+/// Matrices of best scores ending with a match/substitution (S), an insertion (I) or a
+/// deletion (D).
+#[allow(non_snake_case)]
+struct Matrices {
+    S: Vec<Vec<f64>>,
+    I: Vec<Vec<f64>>,
+    D: Vec<Vec<f64>>,
+}
+
+impl<F: MatchFunc> Aligner<F> {
+    /// Create a new aligner with the given gap open and gap extend scores (both should not be
+    /// positive) and the score function.
+    pub fn new(gap_open: i32, gap_extend: i32, match_fn: F) -> Self {
+        Aligner::with_scoring(Scoring::new(gap_open, gap_extend, match_fn))
+    }
+
+    /// Create a new aligner with the given scoring. Clip penalties are ignored.
+    pub fn with_scoring(scoring: Scoring<F>) -> Self {
+        assert!(scoring.gap_open <= 0, "gap_open can't be positive");
+        assert!(scoring.gap_extend <= 0, "gap_extend can't be positive");
+        Aligner {
+            scoring,
+            homopolymer: None,
+        }
+    }
+
+    /// Score gaps of residues that belong to a run of at least `min_len` identical residues
+    /// with the given gap open and gap extend scores. Whether a gap is opened inside a
+    /// homopolymer is decided by its first residue.
+    pub fn homopolymer_gaps(mut self, gap_open: i32, gap_extend: i32, min_len: usize) -> Self {
+        assert!(gap_open <= 0, "gap_open can't be positive");
+        assert!(gap_extend <= 0, "gap_extend can't be positive");
+        assert!(min_len > 1, "homopolymers consist of at least two residues");
+        self.homopolymer = Some(HomopolymerGaps {
+            gap_open,
+            gap_extend,
+            min_len,
+        });
+        self
+    }
+
+    /// Calculate the global alignment of `x` against `y`, with `qual` optionally holding the
+    /// Phred qualities of `x` (without the offset of 33 used in FASTQ files).
+    pub fn global(&self, x: TextSlice<'_>, y: TextSlice<'_>, qual: Option<&[u8]>) -> Alignment {
+        self.align(x, y, qual, AlignmentMode::Global)
+    }
+
+    /// Calculate the semiglobal alignment of `x` against `y` (`x` is global, `y` is local),
+    /// with `qual` optionally holding the Phred qualities of `x`.
+    pub fn semiglobal(&self, x: TextSlice<'_>, y: TextSlice<'_>, qual: Option<&[u8]>) -> Alignment {
+        self.align(x, y, qual, AlignmentMode::Semiglobal)
+    }
+
+    /// For each residue, whether it belongs to a homopolymer.
+    fn homopolymers(&self, seq: TextSlice<'_>) -> Vec<bool> {
+        let mut inside = vec![false; seq.len()];
+        if let Some(homopolymer) = self.homopolymer {
+            let mut start = 0;
+            while start < seq.len() {
+                let len = seq[start..]
+                    .iter()
+                    .take_while(|&&a| a == seq[start])
+                    .count();
+                if len >= homopolymer.min_len {
+                    for flag in &mut inside[start..start + len] {
+                        *flag = true;
+                    }
+                }
+                start += len;
+            }
+        }
+        inside
+    }
+
+    /// Gap open and extend scores for each residue of a sequence.
+    fn gap_scores(&self, seq: TextSlice<'_>) -> Vec<(f64, f64)> {
+        let regular = (
+            f64::from(self.scoring.gap_open),
+            f64::from(self.scoring.gap_extend),
+        );
+        self.homopolymers(seq)
+            .into_iter()
+            .map(|inside| match self.homopolymer {
+                Some(homopolymer) if inside => (
+                    f64::from(homopolymer.gap_open),
+                    f64::from(homopolymer.gap_extend),
+                ),
+                _ => regular,
+            })
+            .collect()
+    }
+
+    fn align(
+        &self,
+        x: TextSlice<'_>,
+        y: TextSlice<'_>,
+        qual: Option<&[u8]>,
+        mode: AlignmentMode,
+    ) -> Alignment {
+        let (m, n) = (x.len(), y.len());
+        if let Some(qual) = qual {
+            assert_eq!(qual.len(), m, "expecting one quality per residue of x");
+        }
+        let semiglobal = mode == AlignmentMode::Semiglobal;
+        let (gaps_x, gaps_y) = (self.gap_scores(x), self.gap_scores(y));
+        let score = |i: usize, j: usize| {
+            let (a, b) = (x[i], y[j]);
+            let s = f64::from(self.scoring.match_fn.score(a, b));
+            match qual {
+                Some(qual) if a != b => s * (1.0 - 10f64.powf(-f64::from(qual[i]) / 10.0)),
+                _ => s,
+            }
+        };
+
+        let mut mat = Matrices {
+            S: vec![vec![NEG; n + 1]; m + 1],
+            I: vec![vec![NEG; n + 1]; m + 1],
+            D: vec![vec![NEG; n + 1]; m + 1],
+        };
+        let best =
+            |mat: &Matrices, i: usize, j: usize| mat.S[i][j].max(mat.I[i][j]).max(mat.D[i][j]);
+        mat.S[0][0] = 0.0;
+        for j in 1..=n {
+            if semiglobal {
+                mat.S[0][j] = 0.0;
+            } else {
+                let (open, extend) = gaps_y[j - 1];
+                mat.D[0][j] = if j == 1 {
+                    open + extend
+                } else {
+                    mat.D[0][j - 1] + extend
+                };
+            }
+        }
+        for i in 1..=m {
+            let (open, extend) = gaps_x[i - 1];
+            for j in 0..=n {
+                mat.I[i][j] = (best(&mat, i - 1, j) + open).max(mat.I[i - 1][j]) + extend;
+                if j > 0 {
+                    mat.S[i][j] = best(&mat, i - 1, j - 1) + score(i - 1, j - 1);
+                    let (open, extend) = gaps_y[j - 1];
+                    mat.D[i][j] = (best(&mat, i, j - 1) + open).max(mat.D[i][j - 1]) + extend;
+                }
+            }
+        }
+
+        let yend = if semiglobal {
+            (0..=n)
+                .rev()
+                .max_by(|&a, &b| best(&mat, m, a).partial_cmp(&best(&mat, m, b)).unwrap())
+                .unwrap()
+        } else {
+            n
+        };
+
+        // traceback
+        let mut operations = Vec::new();
+        let (mut i, mut j) = (m, yend);
+        let mut state = Self::state(&mat, i, j);
+        while i > 0 || (j > 0 && !(semiglobal && state == AlignmentOperation::Match)) {
+            match state {
+                AlignmentOperation::Ins => {
+                    operations.push(AlignmentOperation::Ins);
+                    let extend = gaps_x[i - 1].1;
+                    let value = mat.I[i][j];
+                    i -= 1;
+                    state = if i > 0 && mat.I[i][j] + extend == value {
+                        AlignmentOperation::Ins
+                    } else {
+                        Self::state(&mat, i, j)
+                    };
+                }
+                AlignmentOperation::Del => {
+                    operations.push(AlignmentOperation::Del);
+                    let extend = gaps_y[j - 1].1;
+                    let value = mat.D[i][j];
+                    j -= 1;
+                    state = if j > 0 && mat.D[i][j] + extend == value {
+                        AlignmentOperation::Del
+                    } else {
+                        Self::state(&mat, i, j)
+                    };
+                }
+                _ => {
+                    if i == 0 {
+                        // semiglobal alignments start at the first row
+                        break;
+                    }
+                    operations.push(if x[i - 1] == y[j - 1] {
+                        AlignmentOperation::Match
+                    } else {
+                        AlignmentOperation::Subst
+                    });
+                    i -= 1;
+                    j -= 1;
+                    state = Self::state(&mat, i, j);
+                }
+            }
+        }
+        operations.reverse();
+
+        Alignment {
+            score: best(&mat, m, yend).round() as i32,
+            xstart: 0,
+            ystart: j,
+            xend: m,
+            yend,
+            xlen: m,
+            ylen: n,
+            operations,
+            mode,
+        }
+    }
+
+    /// The matrix holding the best score of cell (i, j), preferring matches.
+    fn state(mat: &Matrices, i: usize, j: usize) -> AlignmentOperation {
+        if mat.S[i][j] >= mat.I[i][j] && mat.S[i][j] >= mat.D[i][j] {
+            AlignmentOperation::Match
+        } else if mat.I[i][j] >= mat.D[i][j] {
+            AlignmentOperation::Ins
+        } else {
+            AlignmentOperation::Del
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alignment::pairwise;
+    use crate::utils::testing::random_seq;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn score(a: u8, b: u8) -> i32 {
+        if a == b {
+            2
+        } else {
+            -3
+        }
+    }
+
+    #[test]
+    fn test_without_context_like_pairwise() {
+        let mut rng = StdRng::seed_from_u64(5);
+        for _ in 0..100 {
+            let m = rng.gen_range(0..20);
+            let n = rng.gen_range(0..20);
+            let x = random_seq(&mut rng, b"ACGT", m);
+            let y = random_seq(&mut rng, b"ACGT", n);
+            let aligner = Aligner::new(-5, -1, score);
+            let mut expected = pairwise::Aligner::new(-5, -1, score);
+            let alignment = aligner.global(&x, &y, None);
+            assert_eq!(alignment.score, expected.global(&x, &y).score);
+            assert_eq!(alignment.x_aln_len(), m);
+            assert_eq!(alignment.y_aln_len(), n);
+            let alignment = aligner.semiglobal(&x, &y, None);
+            let expected = expected.semiglobal(&x, &y);
+            assert_eq!(alignment.score, expected.score);
+            assert_eq!(alignment.x_aln_len(), m);
+            assert_eq!(alignment.y_aln_len(), alignment.yend - alignment.ystart);
+        }
+    }
+
+    #[test]
+    fn test_homopolymer_gaps() {
+        let aligner = Aligner::new(-6, -2, score).homopolymer_gaps(-1, -1, 3);
+        // a deletion in a homopolymer of y
+        let alignment = aligner.global(b"ACGGGGTA", b"ACGGGGGTA", None);
+        assert_eq!(alignment.score, 8 * 2 - 2);
+        // the same deletion outside of a homopolymer is expensive
+        let alignment = aligner.global(b"ACGTGTA", b"ACGTAGTA", None);
+        assert_eq!(alignment.score, 7 * 2 - 8);
+        // homopolymers of length two are below the minimum length
+        let alignment = aligner.global(b"ACGTA", b"ACGGTA", None);
+        assert_eq!(alignment.score, 5 * 2 - 8);
+    }
+
+    #[test]
+    fn test_quality_scaled_mismatches() {
+        let aligner = Aligner::new(-6, -2, score);
+        let x = b"ACGTACGT";
+        let y = b"ACGAACGT";
+        let alignment = aligner.global(x, y, Some(&[40; 8]));
+        assert_eq!(alignment.score, 7 * 2 - 3);
+        let alignment = aligner.global(x, y, Some(&[40, 40, 40, 0, 40, 40, 40, 40]));
+        assert_eq!(alignment.score, 7 * 2);
+        assert_eq!(alignment.operations[3], AlignmentOperation::Subst);
+    }
+}
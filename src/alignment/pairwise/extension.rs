@@ -0,0 +1,287 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Gapped seed extension with X-drop and Z-drop termination, as used by seed-and-extend
+//! mappers (e.g. on top of q-gram index or FM-index hits).
+//!
+//! Starting from a seed, i.e. an exact or approximate match of `x` and `y` at known
+//! positions, the alignment is grown to both sides with affine gap scores. Each side ends at
+//! its best scoring cell, while the computation of a side is restricted to cells scoring at
+//! least the best score so far minus X (X-drop, as in BLAST). Optionally, a side is
+//! terminated early if the score drops by more than Z plus the score of the diagonal shift
+//! from the best cell (Z-drop, as in minimap2), which avoids extending through long gaps or
+//! poorly aligning regions.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::alignment::pairwise::extension::Extender;
+//!
+//! let x = b"CCCCGATTACAGATTACAGGGG";
+//! let y = b"TTGATTACAGATTTCAGAAAAA";
+//! let score = |a: u8, b: u8| if a == b { 1i32 } else { -2i32 };
+//! let extender = Extender::new(-3, -1, score, 5);
+//! // seed ATTACA at x[5..11] and y[3..9]
+//! let extension = extender.extend(x, y, 5, 3, 6);
+//! assert_eq!((extension.xstart, extension.xend), (4, 19));
+//! assert_eq!((extension.ystart, extension.yend), (2, 17));
+//! assert_eq!(extension.score, 12);
+//! ```
+
+use std::cmp::max;
+
+use crate::alignment::pairwise::{MatchFunc, Scoring};
+use crate::utils::TextSlice;
+
+/// Score of pruned or unreachable cells. Far from `i32::MIN`, so that penalties can be
+/// added.
+const NEG: i32 = i32::MIN / 2;
+
+/// An extended seed, with half-open coordinates on `x` and `y`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Extension {
+    pub score: i32,
+    pub xstart: usize,
+    pub xend: usize,
+    pub ystart: usize,
+    pub yend: usize,
+}
+
+/// Best cell of the extension of one side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Best {
+    score: i32,
+    i: usize,
+    j: usize,
+}
+
+/// Seed extension aligner.
+#[derive(Debug, Clone)]
+pub struct Extender<F: MatchFunc> {
+    scoring: Scoring<F>,
+    xdrop: i32,
+    zdrop: Option<i32>,
+}
+
+impl<F: MatchFunc> Extender<F> {
+    /// Create a new extender with the given gap open and gap extend penalties (both should
+    /// not be positive), the score function and the X-drop value (should not be negative).
+    pub fn new(gap_open: i32, gap_extend: i32, match_fn: F, xdrop: i32) -> Self {
+        Extender::with_scoring(Scoring::new(gap_open, gap_extend, match_fn), xdrop)
+    }
+
+    /// Create a new extender with the given scoring and X-drop value. Clip penalties are
+    /// ignored.
+    pub fn with_scoring(scoring: Scoring<F>, xdrop: i32) -> Self {
+        assert!(xdrop >= 0, "xdrop can't be negative");
+        Extender {
+            scoring,
+            xdrop,
+            zdrop: None,
+        }
+    }
+
+    /// Additionally terminate the extension of a side once the score drops by more than
+    /// `zdrop` below the best one, not counting gap extensions needed to return to the
+    /// diagonal of the best cell.
+    pub fn zdrop(mut self, zdrop: i32) -> Self {
+        assert!(zdrop >= 0, "zdrop can't be negative");
+        self.zdrop = Some(zdrop);
+        self
+    }
+
+    /// Extend the seed `x[xpos..xpos + len]`, `y[ypos..ypos + len]` to both sides. The seed
+    /// itself is scored with the score function, and is always part of the extension.
+    ///
+    /// # Panics
+    ///
+    /// If the seed does not fit into `x` or `y`.
+    pub fn extend(
+        &self,
+        x: TextSlice<'_>,
+        y: TextSlice<'_>,
+        xpos: usize,
+        ypos: usize,
+        len: usize,
+    ) -> Extension {
+        assert!(
+            xpos + len <= x.len() && ypos + len <= y.len(),
+            "seed exceeds the sequences"
+        );
+        let seed: i32 = x[xpos..xpos + len]
+            .iter()
+            .zip(&y[ypos..ypos + len])
+            .map(|(&a, &b)| self.scoring.match_fn.score(a, b))
+            .sum();
+        let left = self.extend_side(&x[..xpos], &y[..ypos], true);
+        let right = self.extend_side(&x[xpos + len..], &y[ypos + len..], false);
+        Extension {
+            score: left.score + seed + right.score,
+            xstart: xpos - left.i,
+            xend: xpos + len + right.i,
+            ystart: ypos - left.j,
+            yend: ypos + len + right.j,
+        }
+    }
+
+    /// Find the best scoring alignment of prefixes of `x` and `y` (or of suffixes, if
+    /// `reverse` is true), computing one row of the dynamic programming matrix at a time.
+    fn extend_side(&self, x: TextSlice<'_>, y: TextSlice<'_>, reverse: bool) -> Best {
+        let (m, n) = (x.len(), y.len());
+        let (gap_open, gap_extend) = (self.scoring.gap_open, self.scoring.gap_extend);
+        let x_at = |i: usize| if reverse { x[m - i] } else { x[i - 1] };
+        let y_at = |j: usize| if reverse { y[n - j] } else { y[j - 1] };
+
+        let mut best = Best {
+            score: 0,
+            i: 0,
+            j: 0,
+        };
+        // scores of the previous and current row, and of paths ending with a gap in y
+        let mut prev_h = vec![NEG; n + 1];
+        let mut prev_f = vec![NEG; n + 1];
+        let mut cur_h = vec![NEG; n + 1];
+        let mut cur_f = vec![NEG; n + 1];
+
+        prev_h[0] = 0;
+        let mut hi = 0;
+        for (j, h) in prev_h.iter_mut().enumerate().skip(1) {
+            let score = gap_open + gap_extend * j as i32;
+            if score < -self.xdrop {
+                break;
+            }
+            *h = score;
+            hi = j;
+        }
+        let mut lo = 0;
+        // cells of both row buffers that hold values of earlier rows
+        let mut stale = 0..0;
+        let mut written = 0..hi + 1;
+
+        for i in 1..=m {
+            for j in stale.clone() {
+                cur_h[j] = NEG;
+                cur_f[j] = NEG;
+            }
+            let mut e = NEG;
+            let (mut new_lo, mut new_hi) = (None, 0);
+            let mut row_best: Option<(i32, usize)> = None;
+            let mut j = lo;
+            while j <= n {
+                let diag = if j > 0 {
+                    prev_h[j - 1] + self.scoring.match_fn.score(x_at(i), y_at(j))
+                } else {
+                    NEG
+                };
+                let f = max(prev_h[j] + gap_open, prev_f[j]) + gap_extend;
+                if j > 0 {
+                    e = max(cur_h[j - 1] + gap_open, e) + gap_extend;
+                }
+                let h = max(diag, max(e, f));
+                if h < best.score - self.xdrop {
+                    cur_h[j] = NEG;
+                    cur_f[j] = NEG;
+                    e = NEG;
+                    if j > hi {
+                        // only the pruned left neighbor could reach further cells
+                        j += 1;
+                        break;
+                    }
+                } else {
+                    cur_h[j] = h;
+                    cur_f[j] = f;
+                    new_lo.get_or_insert(j);
+                    new_hi = j;
+                    if row_best.is_none_or(|(score, _)| h > score) {
+                        row_best = Some((h, j));
+                    }
+                    if h > best.score {
+                        best = Best { score: h, i, j };
+                    }
+                }
+                j += 1;
+            }
+            stale = std::mem::replace(&mut written, lo..j);
+
+            let (row_score, row_j) = match (new_lo, row_best) {
+                (Some(new_lo), Some(row_best)) => {
+                    lo = new_lo;
+                    hi = new_hi;
+                    row_best
+                }
+                _ => break,
+            };
+            if let Some(zdrop) = self.zdrop {
+                let shift = (i as i64 - best.i as i64 - (row_j as i64 - best.j as i64)).abs();
+                if (best.score - row_score) as i64 > zdrop as i64 - gap_extend as i64 * shift {
+                    break;
+                }
+            }
+            std::mem::swap(&mut prev_h, &mut cur_h);
+            std::mem::swap(&mut prev_f, &mut cur_f);
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alignment::pairwise::Aligner;
+    use crate::utils::testing::random_seq;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn score(a: u8, b: u8) -> i32 {
+        if a == b {
+            2
+        } else {
+            -3
+        }
+    }
+
+    #[test]
+    fn test_unbounded_xdrop_is_optimal() {
+        let mut rng = StdRng::seed_from_u64(3);
+        for _ in 0..100 {
+            let m = rng.gen_range(0..25);
+            let n = rng.gen_range(0..25);
+            let x = random_seq(&mut rng, b"ACG", m);
+            let y = random_seq(&mut rng, b"ACG", n);
+            // best alignment of prefixes, i.e. free suffixes
+            let scoring = Scoring::new(-5, -1, score).xclip_suffix(0).yclip_suffix(0);
+            let expected = Aligner::with_scoring(scoring).custom(&x, &y).score;
+            let extension = Extender::new(-5, -1, score, 1000).extend(&x, &y, 0, 0, 0);
+            assert_eq!(extension.score, max(expected, 0));
+            assert_eq!((extension.xstart, extension.ystart), (0, 0));
+        }
+    }
+
+    #[test]
+    fn test_xdrop_stops_at_divergence() {
+        let x = b"ACGTACGTACGTTTTTACGTACGTACGTACGTACGT";
+        let y = b"ACGTACGTACGTGGGGACGTACGTACGTACGTACGT";
+        let extension = Extender::new(-5, -1, score, 10).extend(x, y, 0, 0, 4);
+        assert_eq!((extension.xend, extension.yend), (12, 12));
+        assert_eq!(extension.score, 24);
+        // with a large X-drop, the extension crosses the divergent region
+        let extension = Extender::new(-5, -1, score, 100).extend(x, y, 0, 0, 4);
+        assert_eq!((extension.xend, extension.yend), (36, 36));
+        assert_eq!(extension.score, 52);
+    }
+
+    #[test]
+    fn test_zdrop_stops_at_long_gap() {
+        let x = b"ACGTTGCAGGCTAAAAAAAAAAAAAAAAAAAATACGCCGATAAGGTCATCGA";
+        let y = b"ACGTTGCAGGCTTACGCCGATAAGGTCATCGA";
+        // crossing the gap of length 20 pays off
+        let extender = Extender::new(-4, -1, score, 100);
+        let extension = extender.extend(x, y, 0, 0, 12);
+        assert_eq!((extension.xend, extension.yend), (x.len(), y.len()));
+        // but not with a Z-drop, since the score drops by more than 10 off the diagonal
+        let extension = extender.zdrop(10).extend(x, y, 0, 0, 12);
+        assert_eq!((extension.xend, extension.yend), (12, 12));
+    }
+}
@@ -0,0 +1,341 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Global alignment in linear space, using the divide-and-conquer algorithm of Hirschberg
+//! extended to affine gap scores by Myers and Miller.
+//!
+//! The aligner computes an optimal global alignment under the same scoring model as
+//! [`pairwise::Aligner::global`](../struct.Aligner.html#method.global), but only needs
+//! O(n) memory for sequences of length m and n instead of O(mn), at the cost of roughly
+//! doubling the running time. This makes the alignment of whole genes or plasmids feasible.
+//! If there are several optimal alignments, the reported one may differ from the one of
+//! `pairwise::Aligner`.
+//!
+//! *Myers, E. W., and Miller, W. (1988). Optimal alignments in linear space. Bioinformatics 4,
+//! 11–17.*
+//!
+//! # Example
+//!
+//! ```
+//! use bio::alignment::pairwise::hirschberg::Aligner;
+//!
+//! let x = b"ACCGTGGAT";
+//! let y = b"AAAAACCGTTGAT";
+//! let score = |a: u8, b: u8| if a == b { 1i32 } else { -1i32 };
+//! let mut aligner = Aligner::new(-5, -1, score);
+//! let alignment = aligner.global(x, y);
+//! assert_eq!(alignment.score, -2);
+//! assert_eq!((alignment.xend, alignment.yend), (9, 13));
+//! ```
+
+use std::cmp::min;
+
+use crate::alignment::pairwise::{MatchFunc, Scoring};
+use crate::alignment::{Alignment, AlignmentMode, AlignmentOperation};
+use crate::utils::TextSlice;
+
+/// A linear space global aligner. Internally, scores are negated, i.e. gaps and
+/// substitutions have positive costs that are minimized.
+#[allow(non_snake_case)]
+#[derive(Debug, Clone)]
+pub struct Aligner<F: MatchFunc> {
+    scoring: Scoring<F>,
+    /// Forward costs of the last row, ending with anything or with a gap in `y`.
+    CC: Vec<i32>,
+    DD: Vec<i32>,
+    /// Reverse costs of the last row, ending with anything or with a gap in `y`.
+    RR: Vec<i32>,
+    SS: Vec<i32>,
+    operations: Vec<AlignmentOperation>,
+}
+
+impl<F: MatchFunc> Aligner<F> {
+    /// Create a new aligner with the given gap open and gap extend penalties (both should
+    /// not be positive) and the score function.
+    pub fn new(gap_open: i32, gap_extend: i32, match_fn: F) -> Self {
+        Aligner::with_scoring(Scoring::new(gap_open, gap_extend, match_fn))
+    }
+
+    /// Create a new aligner with the given scoring. Clip penalties are ignored, since the
+    /// alignment is always global.
+    pub fn with_scoring(scoring: Scoring<F>) -> Self {
+        Aligner {
+            scoring,
+            CC: Vec::new(),
+            DD: Vec::new(),
+            RR: Vec::new(),
+            SS: Vec::new(),
+            operations: Vec::new(),
+        }
+    }
+
+    /// Calculate an optimal global alignment of `x` against `y`.
+    pub fn global(&mut self, x: TextSlice<'_>, y: TextSlice<'_>) -> Alignment {
+        let n = y.len() + 1;
+        for buffer in [&mut self.CC, &mut self.DD, &mut self.RR, &mut self.SS].iter_mut() {
+            buffer.clear();
+            buffer.resize(n, 0);
+        }
+        self.operations.clear();
+
+        let g = -self.scoring.gap_open;
+        self.diff(x, y, g, g);
+
+        let operations = self.operations.clone();
+        Alignment {
+            score: self.score(x, y, &operations),
+            xstart: 0,
+            ystart: 0,
+            xend: x.len(),
+            yend: y.len(),
+            xlen: x.len(),
+            ylen: y.len(),
+            operations,
+            mode: AlignmentMode::Global,
+        }
+    }
+
+    /// Cost of a gap of length `k`.
+    fn gap(&self, k: usize) -> i32 {
+        if k == 0 {
+            0
+        } else {
+            -self.scoring.gap_open - self.scoring.gap_extend * k as i32
+        }
+    }
+
+    /// Cost of aligning `a` to `b`.
+    fn cost(&self, a: u8, b: u8) -> i32 {
+        -self.scoring.match_fn.score(a, b)
+    }
+
+    fn push(&mut self, op: AlignmentOperation, k: usize) {
+        self.operations.resize(self.operations.len() + k, op);
+    }
+
+    /// Align `a` to `b`, appending the operations. `tb` and `te` are the costs for opening a
+    /// gap in `b` at the beginning and at the end, which is zero if the gap continues a gap
+    /// of the surrounding alignment.
+    fn diff(&mut self, a: &[u8], b: &[u8], tb: i32, te: i32) {
+        let (m, n) = (a.len(), b.len());
+        if n == 0 {
+            self.push(AlignmentOperation::Ins, m);
+            return;
+        }
+        if m == 0 {
+            self.push(AlignmentOperation::Del, n);
+            return;
+        }
+        let (g, h) = (-self.scoring.gap_open, -self.scoring.gap_extend);
+
+        if m == 1 {
+            // either gap a and b completely, or align a to one symbol of b
+            let mut best = min(tb, te) + h + self.gap(n);
+            let mut best_j = 0;
+            for (j, &bj) in b.iter().enumerate() {
+                let c = self.gap(j) + self.cost(a[0], bj) + self.gap(n - j - 1);
+                if c < best {
+                    best = c;
+                    best_j = j + 1;
+                }
+            }
+            if best_j == 0 {
+                // put the gap of a next to the adjacent one
+                if tb < te {
+                    self.push(AlignmentOperation::Ins, 1);
+                    self.push(AlignmentOperation::Del, n);
+                } else {
+                    self.push(AlignmentOperation::Del, n);
+                    self.push(AlignmentOperation::Ins, 1);
+                }
+            } else {
+                self.push(AlignmentOperation::Del, best_j - 1);
+                self.push(
+                    if a[0] == b[best_j - 1] {
+                        AlignmentOperation::Match
+                    } else {
+                        AlignmentOperation::Subst
+                    },
+                    1,
+                );
+                self.push(AlignmentOperation::Del, n - best_j);
+            }
+            return;
+        }
+
+        let mid_i = m / 2;
+        self.costs(&a[..mid_i], b, tb, false);
+        self.costs(&a[mid_i..], b, te, true);
+
+        // find the column where the optimal path crosses the middle row
+        let mut best = self.CC[0] + self.RR[n];
+        let mut best_j = 0;
+        let mut gap_crossing = false;
+        for j in 0..=n {
+            let c = self.CC[j] + self.RR[n - j];
+            if c < best {
+                best = c;
+                best_j = j;
+                gap_crossing = false;
+            }
+            let c = self.DD[j] + self.SS[n - j] - g;
+            if c < best {
+                best = c;
+                best_j = j;
+                gap_crossing = true;
+            }
+        }
+
+        if gap_crossing {
+            // a gap in b covers a[mid_i - 1] and a[mid_i]
+            self.diff(&a[..mid_i - 1], &b[..best_j], tb, 0);
+            self.push(AlignmentOperation::Ins, 2);
+            self.diff(&a[mid_i + 1..], &b[best_j..], 0, te);
+        } else {
+            self.diff(&a[..mid_i], &b[..best_j], tb, g);
+            self.diff(&a[mid_i..], &b[best_j..], g, te);
+        }
+    }
+
+    /// Compute the costs of aligning `a` to all prefixes of `b` (or, if `reverse` is true,
+    /// the reversed `a` to all reversed suffixes of `b`) into `CC` and `DD` (or `RR` and `SS`).
+    #[allow(non_snake_case)]
+    fn costs(&mut self, a: &[u8], b: &[u8], tb: i32, reverse: bool) {
+        let (g, h) = (-self.scoring.gap_open, -self.scoring.gap_extend);
+        let (m, n) = (a.len(), b.len());
+        let (mut CC, mut DD) = if reverse {
+            (std::mem::take(&mut self.RR), std::mem::take(&mut self.SS))
+        } else {
+            (std::mem::take(&mut self.CC), std::mem::take(&mut self.DD))
+        };
+
+        CC[0] = 0;
+        let mut t = g;
+        for j in 1..=n {
+            t += h;
+            CC[j] = t;
+            DD[j] = t + g;
+        }
+        let mut t = tb;
+        for i in 1..=m {
+            let ai = if reverse { a[m - i] } else { a[i - 1] };
+            let mut s = CC[0];
+            t += h;
+            let mut c = t;
+            CC[0] = c;
+            let mut e = t + g;
+            for j in 1..=n {
+                let bj = if reverse { b[n - j] } else { b[j - 1] };
+                e = min(e, c + g) + h;
+                let d = min(DD[j], CC[j] + g) + h;
+                c = min(min(d, e), s + self.cost(ai, bj));
+                s = CC[j];
+                CC[j] = c;
+                DD[j] = d;
+            }
+        }
+        DD[0] = CC[0];
+
+        if reverse {
+            self.RR = CC;
+            self.SS = DD;
+        } else {
+            self.CC = CC;
+            self.DD = DD;
+        }
+    }
+
+    /// Score of the given global alignment operations.
+    fn score(&self, x: TextSlice<'_>, y: TextSlice<'_>, operations: &[AlignmentOperation]) -> i32 {
+        let (mut i, mut j) = (0, 0);
+        let mut score = 0;
+        let mut last = None;
+        for &op in operations {
+            match op {
+                AlignmentOperation::Match | AlignmentOperation::Subst => {
+                    score += self.scoring.match_fn.score(x[i], y[j]);
+                    i += 1;
+                    j += 1;
+                }
+                AlignmentOperation::Ins | AlignmentOperation::Del => {
+                    if last != Some(op) {
+                        score += self.scoring.gap_open;
+                    }
+                    score += self.scoring.gap_extend;
+                    if op == AlignmentOperation::Ins {
+                        i += 1;
+                    } else {
+                        j += 1;
+                    }
+                }
+                _ => unreachable!(),
+            }
+            last = Some(op);
+        }
+        score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alignment::pairwise;
+    use crate::scores::blosum62;
+    use crate::utils::testing::random_seq;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn check(x: &[u8], y: &[u8], gap_open: i32, gap_extend: i32) {
+        let score = |a: u8, b: u8| if a == b { 2i32 } else { -3i32 };
+        let expected = pairwise::Aligner::new(gap_open, gap_extend, score).global(x, y);
+        let alignment = Aligner::new(gap_open, gap_extend, score).global(x, y);
+        assert_eq!(alignment.score, expected.score, "{:?} {:?}", x, y);
+        let consumed = |skipped: AlignmentOperation| {
+            alignment
+                .operations
+                .iter()
+                .filter(|&&op| op != skipped)
+                .count()
+        };
+        assert_eq!(consumed(AlignmentOperation::Del), x.len());
+        assert_eq!(consumed(AlignmentOperation::Ins), y.len());
+    }
+
+    #[test]
+    fn test_random_against_full_dp() {
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..200 {
+            let m = rng.gen_range(0..30);
+            let n = rng.gen_range(0..30);
+            let x = random_seq(&mut rng, b"ACGT", m);
+            let mut y: Vec<u8> = x.clone();
+            y.truncate(n);
+            for a in y.iter_mut() {
+                if rng.gen_bool(0.3) {
+                    *a = b"ACGT"[rng.gen_range(0..4)];
+                }
+            }
+            check(&x, &y, -5, -1);
+            check(&x, &y, 0, -2);
+            check(&y, &x, -8, -1);
+        }
+    }
+
+    #[test]
+    fn test_long_gap() {
+        check(b"AAAACCCCCCCCCCCCGGGG", b"AAAAGGGG", -10, -1);
+        check(b"A", b"CCCCCCCCACCC", -3, -1);
+        check(b"", b"", -3, -1);
+    }
+
+    #[test]
+    fn test_protein() {
+        let x = b"MEEPQSDPSVEPPLSQETFSDLWKLLPENNVLSPLPSQAMDDLMLSPDDIEQWFTEDPGP";
+        let y = b"MTAMEESQSDISLELPLSQETFSGLWKLLPPEDILPSPHCMDDLLLPQDVEEFFEGPSEALRVS";
+        let expected = pairwise::Aligner::new(-11, -1, blosum62).global(x, y);
+        let alignment = Aligner::new(-11, -1, blosum62).global(x, y);
+        assert_eq!(alignment.score, expected.score);
+    }
+}
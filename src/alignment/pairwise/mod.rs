@@ -126,7 +126,7 @@
 //! let y = b"AAAAACGTACGTACGTAAAA";
 //! let mut aligner = Aligner::with_capacity_and_scoring(x.len(), y.len(), scoring);
 //! let alignment = aligner.custom(x, y);
-//! println!("{}", alignment.pretty(x, y));
+//! println!("{}", alignment.pretty(x, y, 80));
 //! assert_eq!(alignment.score, 2);
 //! assert_eq!(
 //!     alignment.operations,
@@ -157,7 +157,14 @@ use std::iter::repeat;
 use crate::alignment::{Alignment, AlignmentMode, AlignmentOperation};
 use crate::utils::TextSlice;
 
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
 pub mod banded;
+pub mod context;
+pub mod extension;
+pub mod hirschberg;
+pub mod striped;
 
 /// Value to use as a 'negative infinity' score. Should be close to `i32::MIN`,
 /// but avoid underflow when used with reasonable scoring parameters or even
@@ -568,15 +575,13 @@ impl<F: MatchFunc> Aligner<F> {
         }
     }
 
-    /// The core function to compute the alignment
-    ///
-    /// # Arguments
-    ///
-    /// * `x` - Textslice
-    /// * `y` - Textslice
-    pub fn custom(&mut self, x: TextSlice<'_>, y: TextSlice<'_>) -> Alignment {
+    /// Fill the dynamic programming matrices, recording the traceback only if `TRACEBACK` is
+    /// set. The score of the alignment is left in `self.S[n % 2][m]`.
+    fn fill<const TRACEBACK: bool>(&mut self, x: TextSlice<'_>, y: TextSlice<'_>) {
         let (m, n) = (x.len(), y.len());
-        self.traceback.init(m, n);
+        if TRACEBACK {
+            self.traceback.init(m, n);
+        }
 
         // Set the initial conditions
         // We are repeating some work, but that's okay!
@@ -594,7 +599,9 @@ impl<F: MatchFunc> Aligner<F> {
             if k == 0 {
                 let mut tb = TracebackCell::new();
                 tb.set_all(TB_START);
-                self.traceback.set(0, 0, tb);
+                if TRACEBACK {
+                    self.traceback.set(0, 0, tb);
+                }
                 self.Lx.clear();
                 self.Lx.extend(repeat(0usize).take(n + 1));
                 self.Ly.clear();
@@ -647,7 +654,7 @@ impl<F: MatchFunc> Aligner<F> {
                     self.Lx[0] = m - i;
                 }
 
-                if k == 0 {
+                if TRACEBACK && k == 0 {
                     self.traceback.set(i, 0, tb);
                 }
                 // Track the score if we do suffix clip (y) from here
@@ -701,7 +708,9 @@ impl<F: MatchFunc> Aligner<F> {
                     self.Ly[0] = n - j;
                 }
 
-                self.traceback.set(0, j, tb);
+                if TRACEBACK {
+                    self.traceback.set(0, j, tb);
+                }
             }
 
             for i in 1..=m {
@@ -728,7 +737,9 @@ impl<F: MatchFunc> Aligner<F> {
                     tb.set_i_bits(TB_INS);
                 } else {
                     best_i_score = s_score;
-                    tb.set_i_bits(self.traceback.get(i - 1, j).get_s_bits());
+                    if TRACEBACK {
+                        tb.set_i_bits(self.traceback.get(i - 1, j).get_s_bits());
+                    }
                 }
 
                 let d_score = self.D[prev][i] + self.scoring.gap_extend;
@@ -739,7 +750,9 @@ impl<F: MatchFunc> Aligner<F> {
                     tb.set_d_bits(TB_DEL);
                 } else {
                     best_d_score = s_score;
-                    tb.set_d_bits(self.traceback.get(i, j - 1).get_s_bits());
+                    if TRACEBACK {
+                        tb.set_d_bits(self.traceback.get(i, j - 1).get_s_bits());
+                    }
                 }
 
                 tb.set_s_bits(TB_XCLIP_SUFFIX);
@@ -789,7 +802,9 @@ impl<F: MatchFunc> Aligner<F> {
                     self.Ly[i] = n - j;
                 }
 
-                self.traceback.set(i, j, tb);
+                if TRACEBACK {
+                    self.traceback.set(i, j, tb);
+                }
             }
         }
 
@@ -799,12 +814,16 @@ impl<F: MatchFunc> Aligner<F> {
             let curr = j % 2;
             if self.Sn[i] > self.S[curr][i] {
                 self.S[curr][i] = self.Sn[i];
-                self.traceback.get_mut(i, j).set_s_bits(TB_YCLIP_SUFFIX);
+                if TRACEBACK {
+                    self.traceback.get_mut(i, j).set_s_bits(TB_YCLIP_SUFFIX);
+                }
             }
             if self.S[curr][i] + self.scoring.xclip_suffix > self.S[curr][m] {
                 self.S[curr][m] = self.S[curr][i] + self.scoring.xclip_suffix;
                 self.Lx[j] = m - i;
-                self.traceback.get_mut(m, j).set_s_bits(TB_XCLIP_SUFFIX);
+                if TRACEBACK {
+                    self.traceback.get_mut(m, j).set_s_bits(TB_XCLIP_SUFFIX);
+                }
             }
         }
 
@@ -816,19 +835,36 @@ impl<F: MatchFunc> Aligner<F> {
             let s_score = self.S[curr][i - 1] + self.scoring.gap_open + self.scoring.gap_extend;
             if s_score > self.I[curr][i] {
                 self.I[curr][i] = s_score;
-                let s_bit = self.traceback.get(i - 1, j).get_s_bits();
-                self.traceback.get_mut(i, j).set_i_bits(s_bit);
+                if TRACEBACK {
+                    let s_bit = self.traceback.get(i - 1, j).get_s_bits();
+                    self.traceback.get_mut(i, j).set_i_bits(s_bit);
+                }
             }
             if s_score > self.S[curr][i] {
                 self.S[curr][i] = s_score;
-                self.traceback.get_mut(i, j).set_s_bits(TB_INS);
+                if TRACEBACK {
+                    self.traceback.get_mut(i, j).set_s_bits(TB_INS);
+                }
                 if self.S[curr][i] + self.scoring.xclip_suffix > self.S[curr][m] {
                     self.S[curr][m] = self.S[curr][i] + self.scoring.xclip_suffix;
                     self.Lx[j] = m - i;
-                    self.traceback.get_mut(m, j).set_s_bits(TB_XCLIP_SUFFIX);
+                    if TRACEBACK {
+                        self.traceback.get_mut(m, j).set_s_bits(TB_XCLIP_SUFFIX);
+                    }
                 }
             }
         }
+    }
+
+    /// The core function to compute the alignment
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - Textslice
+    /// * `y` - Textslice
+    pub fn custom(&mut self, x: TextSlice<'_>, y: TextSlice<'_>) -> Alignment {
+        let (m, n) = (x.len(), y.len());
+        self.fill::<true>(x, y);
 
         let mut i = m;
         let mut j = n;
@@ -909,7 +945,45 @@ impl<F: MatchFunc> Aligner<F> {
         }
     }
 
-    /// Calculate global alignment of x against y.
+    /// Calculate the score of the alignment returned by [`custom`](#method.custom), without
+    /// recording the traceback. This needs O(n + m) instead of O(n * m) memory and is
+    /// considerably faster, e.g. for screening many targets before aligning the best ones.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::alignment::pairwise::*;
+    ///
+    /// let scoring = Scoring::from_scores(-5, -1, 1, -1).xclip(-2);
+    /// let mut aligner = Aligner::with_scoring(scoring);
+    /// assert_eq!(aligner.custom_score(b"TTGATTACA", b"GATTACA"), 7 - 2);
+    /// ```
+    pub fn custom_score(&mut self, x: TextSlice<'_>, y: TextSlice<'_>) -> i32 {
+        self.fill::<false>(x, y);
+        self.S[y.len() % 2][x.len()]
+    }
+
+    /// Calculate global alignment of x against y (Needleman-Wunsch with affine gap
+    /// penalties, after Gotoh). A gap of length `k` is scored as `gap_open + k * gap_extend`,
+    /// so that a single long gap is preferred over several short ones.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::alignment::pairwise::*;
+    /// use bio::alignment::AlignmentOperation::*;
+    ///
+    /// let x = b"ACGTTTTACG";
+    /// let y = b"ACGACG";
+    /// let mut aligner = Aligner::new(-5, -1, |a: u8, b: u8| if a == b { 1 } else { -1 });
+    /// let alignment = aligner.global(x, y);
+    /// // six matches and one gap of length four
+    /// assert_eq!(alignment.score, 6 - 5 - 4);
+    /// assert_eq!(
+    ///     alignment.operations,
+    ///     [Match, Match, Match, Ins, Ins, Ins, Ins, Match, Match, Match]
+    /// );
+    /// ```
     pub fn global(&mut self, x: TextSlice<'_>, y: TextSlice<'_>) -> Alignment {
         // Store the current clip penalties
         let clip_penalties = [
@@ -938,7 +1012,9 @@ impl<F: MatchFunc> Aligner<F> {
         alignment
     }
 
-    /// Calculate semiglobal alignment of x against y (x is global, y is local).
+    /// Calculate semiglobal alignment of x against y (x is global, y is local), also known as
+    /// fit alignment. See [`alignment::summary`](../summary/index.html) for coverage and
+    /// identity of the result.
     pub fn semiglobal(&mut self, x: TextSlice<'_>, y: TextSlice<'_>) -> Alignment {
         // Store the current clip penalties
         let clip_penalties = [
@@ -1001,6 +1077,394 @@ impl<F: MatchFunc> Aligner<F> {
 
         alignment
     }
+
+    /// Calculate overlap alignment of x against y, i.e. an alignment with free leading and
+    /// trailing gaps in both sequences (also known as dovetail or end-gap free alignment).
+    /// The alignment starts at the beginning of x or y, and ends at the end of x or y,
+    /// which covers suffix-prefix overlaps (e.g. of contigs or paired reads) as well as
+    /// containment of one sequence in the other.
+    ///
+    /// The skipped prefix and suffix are reported as clip operations, and the alignment mode
+    /// is `Custom`. This runs the alignment for the four possible combinations of free ends.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::alignment::pairwise::*;
+    /// use bio::alignment::AlignmentOperation::*;
+    ///
+    /// let x = b"TTTTACGTACGA";
+    /// let y = b"ACGTACGTGGGG";
+    /// let mut aligner = Aligner::new(-5, -1, |a: u8, b: u8| if a == b { 1 } else { -1 });
+    /// let alignment = aligner.overlap(x, y);
+    /// assert_eq!(alignment.score, 6);
+    /// assert_eq!(
+    ///     alignment.operations,
+    ///     [Xclip(4), Match, Match, Match, Match, Match, Match, Match, Subst, Yclip(4)]
+    /// );
+    /// ```
+    pub fn overlap(&mut self, x: TextSlice<'_>, y: TextSlice<'_>) -> Alignment {
+        // Store the current clip penalties
+        let clip_penalties = [
+            self.scoring.xclip_prefix,
+            self.scoring.xclip_suffix,
+            self.scoring.yclip_prefix,
+            self.scoring.yclip_suffix,
+        ];
+
+        // Either the prefix of x or the prefix of y may be skipped, and likewise for the
+        // suffixes
+        let free_ends = [(0, MIN_SCORE), (MIN_SCORE, 0)];
+        let mut best: Option<Alignment> = None;
+        for &(xclip_prefix, yclip_prefix) in free_ends.iter() {
+            for &(xclip_suffix, yclip_suffix) in free_ends.iter() {
+                self.scoring.xclip_prefix = xclip_prefix;
+                self.scoring.xclip_suffix = xclip_suffix;
+                self.scoring.yclip_prefix = yclip_prefix;
+                self.scoring.yclip_suffix = yclip_suffix;
+                let alignment = self.custom(x, y);
+                if best
+                    .as_ref()
+                    .is_none_or(|best| alignment.score > best.score)
+                {
+                    best = Some(alignment);
+                }
+            }
+        }
+
+        // Set the clip penalties to the original values
+        self.scoring.xclip_prefix = clip_penalties[0];
+        self.scoring.xclip_suffix = clip_penalties[1];
+        self.scoring.yclip_prefix = clip_penalties[2];
+        self.scoring.yclip_suffix = clip_penalties[3];
+
+        best.unwrap()
+    }
+
+    /// Align the query x against each of the given targets in the given mode, e.g. for
+    /// screening a read against a set of adapters or amplicons. The dynamic programming
+    /// buffers of the aligner are reused, such that no allocations are necessary once the
+    /// aligner has seen the largest target. If only the scores are of interest, use
+    /// [`score_many`](#method.score_many), which skips the traceback. With the `rayon`
+    /// feature, [`par_align_many`](#method.par_align_many) aligns the targets in parallel.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::alignment::pairwise::*;
+    /// use bio::alignment::AlignmentMode;
+    ///
+    /// let x = b"GATTACA";
+    /// let targets = [&b"TTGATTACATT"[..], b"GATCACA", b"CCCCCC"];
+    /// let mut aligner = Aligner::new(-5, -1, |a: u8, b: u8| if a == b { 1 } else { -1 });
+    /// let scores: Vec<i32> = aligner
+    ///     .align_many(x, targets.iter().cloned(), AlignmentMode::Semiglobal)
+    ///     .iter()
+    ///     .map(|alignment| alignment.score)
+    ///     .collect();
+    /// assert_eq!(scores, [7, 5, -10]);
+    /// ```
+    pub fn align_many<'a, I>(
+        &mut self,
+        x: TextSlice<'_>,
+        targets: I,
+        mode: AlignmentMode,
+    ) -> Vec<Alignment>
+    where
+        I: IntoIterator<Item = TextSlice<'a>>,
+    {
+        targets
+            .into_iter()
+            .map(|y| self.align_with_mode(x, y, mode))
+            .collect()
+    }
+
+    fn align_with_mode(
+        &mut self,
+        x: TextSlice<'_>,
+        y: TextSlice<'_>,
+        mode: AlignmentMode,
+    ) -> Alignment {
+        match mode {
+            AlignmentMode::Global => self.global(x, y),
+            AlignmentMode::Semiglobal => self.semiglobal(x, y),
+            AlignmentMode::Local => self.local(x, y),
+            AlignmentMode::Custom => self.custom(x, y),
+        }
+    }
+
+    /// The clip penalties used by the alignment method of the given mode.
+    fn mode_clip_penalties(&self, mode: AlignmentMode) -> [i32; 4] {
+        match mode {
+            AlignmentMode::Global => [MIN_SCORE; 4],
+            AlignmentMode::Semiglobal => [MIN_SCORE, MIN_SCORE, 0, 0],
+            AlignmentMode::Local => [0; 4],
+            AlignmentMode::Custom => [
+                self.scoring.xclip_prefix,
+                self.scoring.xclip_suffix,
+                self.scoring.yclip_prefix,
+                self.scoring.yclip_suffix,
+            ],
+        }
+    }
+
+    /// Calculate the scores of the alignments of the query x against each of the given targets
+    /// in the given mode, without computing the alignments themselves (see
+    /// [`custom_score`](#method.custom_score)). The targets are scored sequentially; with the
+    /// `rayon` feature, [`par_score_many`](#method.par_score_many) scores them in parallel.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::alignment::pairwise::*;
+    /// use bio::alignment::AlignmentMode;
+    ///
+    /// let x = b"GATTACA";
+    /// let targets = [&b"TTGATTACATT"[..], b"GATCACA", b"CCCCCC"];
+    /// let mut aligner = Aligner::new(-5, -1, |a: u8, b: u8| if a == b { 1 } else { -1 });
+    /// let scores = aligner.score_many(x, targets.iter().cloned(), AlignmentMode::Semiglobal);
+    /// assert_eq!(scores, [7, 5, -10]);
+    /// ```
+    pub fn score_many<'a, I>(
+        &mut self,
+        x: TextSlice<'_>,
+        targets: I,
+        mode: AlignmentMode,
+    ) -> Vec<i32>
+    where
+        I: IntoIterator<Item = TextSlice<'a>>,
+    {
+        // Store the current clip penalties
+        let clip_penalties = [
+            self.scoring.xclip_prefix,
+            self.scoring.xclip_suffix,
+            self.scoring.yclip_prefix,
+            self.scoring.yclip_suffix,
+        ];
+
+        // Temporarily over-write the clip penalties as in the respective alignment method
+        let [xclip_prefix, xclip_suffix, yclip_prefix, yclip_suffix] =
+            self.mode_clip_penalties(mode);
+        self.scoring.xclip_prefix = xclip_prefix;
+        self.scoring.xclip_suffix = xclip_suffix;
+        self.scoring.yclip_prefix = yclip_prefix;
+        self.scoring.yclip_suffix = yclip_suffix;
+
+        let scores = targets
+            .into_iter()
+            .map(|y| self.custom_score(x, y))
+            .collect();
+
+        // Set the clip penalties to the original values
+        self.scoring.xclip_prefix = clip_penalties[0];
+        self.scoring.xclip_suffix = clip_penalties[1];
+        self.scoring.yclip_prefix = clip_penalties[2];
+        self.scoring.yclip_suffix = clip_penalties[3];
+
+        scores
+    }
+
+    /// Calculate all non-overlapping local alignments of x against y with a score of at
+    /// least `min_score`, in order of decreasing score (Waterman-Eggert). Alignments are
+    /// non-overlapping if they do not align the same pair of positions, so that e.g. a
+    /// repeated region of y yields one alignment per copy.
+    ///
+    /// Each alignment requires a complete recomputation of the dynamic programming matrices,
+    /// stored in full, i.e. complexity is O(k * n * m) time and O(n * m) space for k reported
+    /// alignments.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::alignment::pairwise::*;
+    ///
+    /// let x = b"GATTACA";
+    /// let y = b"TTGATTACACCCCGATTACATT";
+    /// let mut aligner = Aligner::new(-5, -1, |a: u8, b: u8| if a == b { 1 } else { -1 });
+    /// let alignments = aligner.local_all(x, y, 5);
+    /// assert_eq!(alignments.len(), 2);
+    /// assert_eq!((alignments[0].ystart, alignments[0].yend), (2, 9));
+    /// assert_eq!((alignments[1].ystart, alignments[1].yend), (13, 20));
+    /// assert!(alignments.iter().all(|alignment| alignment.score == 7));
+    /// ```
+    pub fn local_all(
+        &mut self,
+        x: TextSlice<'_>,
+        y: TextSlice<'_>,
+        min_score: i32,
+    ) -> Vec<Alignment> {
+        let (m, n) = (x.len(), y.len());
+        let cols = n + 1;
+        let (gap_open, gap_extend) = (self.scoring.gap_open, self.scoring.gap_extend);
+        // pairs of positions (i, j) already aligned by a reported alignment
+        let mut used = vec![false; (m + 1) * cols];
+        let mut alignments = Vec::new();
+        // H: best local score ending at (i, j), E: ending with a deletion,
+        // F: ending with an insertion. Each round recomputes all cells except the first row
+        // and column, which keep their initial values.
+        let mut h = vec![0; (m + 1) * cols];
+        let mut e = vec![MIN_SCORE; (m + 1) * cols];
+        let mut f = vec![MIN_SCORE; (m + 1) * cols];
+
+        loop {
+            let mut best = (0, 0, 0);
+            for i in 1..=m {
+                for j in 1..=n {
+                    let k = i * cols + j;
+                    e[k] = max(e[k - 1] + gap_extend, h[k - 1] + gap_open + gap_extend);
+                    f[k] = max(
+                        f[k - cols] + gap_extend,
+                        h[k - cols] + gap_open + gap_extend,
+                    );
+                    let m_score = if used[k] {
+                        MIN_SCORE
+                    } else {
+                        h[k - cols - 1] + self.scoring.match_fn.score(x[i - 1], y[j - 1])
+                    };
+                    h[k] = max(0, max(m_score, max(e[k], f[k])));
+                    if h[k] > best.0 {
+                        best = (h[k], i, j);
+                    }
+                }
+            }
+            let (score, mut i, mut j) = best;
+            if score <= 0 || score < min_score {
+                break;
+            }
+
+            let (xend, yend) = (i, j);
+            let mut operations = Vec::new();
+            // 0: H, 1: E, 2: F
+            let mut layer = 0;
+            loop {
+                let k = i * cols + j;
+                match layer {
+                    0 => {
+                        if h[k] == 0 {
+                            break;
+                        } else if !used[k]
+                            && h[k]
+                                == h[k - cols - 1] + self.scoring.match_fn.score(x[i - 1], y[j - 1])
+                        {
+                            operations.push(if x[i - 1] == y[j - 1] {
+                                AlignmentOperation::Match
+                            } else {
+                                AlignmentOperation::Subst
+                            });
+                            used[k] = true;
+                            i -= 1;
+                            j -= 1;
+                        } else if h[k] == e[k] {
+                            layer = 1;
+                        } else {
+                            layer = 2;
+                        }
+                    }
+                    1 => {
+                        operations.push(AlignmentOperation::Del);
+                        if e[k] == h[k - 1] + gap_open + gap_extend {
+                            layer = 0;
+                        }
+                        j -= 1;
+                    }
+                    _ => {
+                        operations.push(AlignmentOperation::Ins);
+                        if f[k] == h[k - cols] + gap_open + gap_extend {
+                            layer = 0;
+                        }
+                        i -= 1;
+                    }
+                }
+            }
+            operations.reverse();
+            alignments.push(Alignment {
+                score,
+                xstart: i,
+                ystart: j,
+                xend,
+                yend,
+                xlen: m,
+                ylen: n,
+                operations,
+                mode: AlignmentMode::Local,
+            });
+        }
+        alignments
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<F: MatchFunc + Clone + Sync> Aligner<F> {
+    /// Align the query x against each of the given targets in the given mode, in parallel
+    /// using rayon. Like [`align_many`](#method.align_many), but each rayon job works with its
+    /// own aligner, whose buffers are reused for all targets of the job.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::alignment::pairwise::*;
+    /// use bio::alignment::AlignmentMode;
+    ///
+    /// let x = b"GATTACA";
+    /// let targets = [&b"TTGATTACATT"[..], b"GATCACA", b"CCCCCC"];
+    /// let aligner = Aligner::new(-5, -1, |a: u8, b: u8| if a == b { 1 } else { -1 });
+    /// let alignments = aligner.par_align_many(x, &targets, AlignmentMode::Semiglobal);
+    /// let scores: Vec<i32> = alignments.iter().map(|alignment| alignment.score).collect();
+    /// assert_eq!(scores, [7, 5, -10]);
+    /// ```
+    pub fn par_align_many(
+        &self,
+        x: TextSlice<'_>,
+        targets: &[TextSlice<'_>],
+        mode: AlignmentMode,
+    ) -> Vec<Alignment> {
+        targets
+            .par_iter()
+            .map_init(
+                || Aligner::with_scoring(self.scoring.clone()),
+                |aligner, y| aligner.align_with_mode(x, y, mode),
+            )
+            .collect()
+    }
+
+    /// Calculate the scores of the alignments of the query x against each of the given targets
+    /// in the given mode, in parallel using rayon. Like [`score_many`](#method.score_many),
+    /// but each rayon job works with its own aligner, whose buffers are reused for all
+    /// targets of the job.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::alignment::pairwise::*;
+    /// use bio::alignment::AlignmentMode;
+    ///
+    /// let x = b"GATTACA";
+    /// let targets = [&b"TTGATTACATT"[..], b"GATCACA", b"CCCCCC"];
+    /// let aligner = Aligner::new(-5, -1, |a: u8, b: u8| if a == b { 1 } else { -1 });
+    /// let scores = aligner.par_score_many(x, &targets, AlignmentMode::Semiglobal);
+    /// assert_eq!(scores, [7, 5, -10]);
+    /// ```
+    pub fn par_score_many(
+        &self,
+        x: TextSlice<'_>,
+        targets: &[TextSlice<'_>],
+        mode: AlignmentMode,
+    ) -> Vec<i32> {
+        let mut scoring = self.scoring.clone();
+        let [xclip_prefix, xclip_suffix, yclip_prefix, yclip_suffix] =
+            self.mode_clip_penalties(mode);
+        scoring.xclip_prefix = xclip_prefix;
+        scoring.xclip_suffix = xclip_suffix;
+        scoring.yclip_prefix = yclip_prefix;
+        scoring.yclip_suffix = yclip_suffix;
+        targets
+            .par_iter()
+            .map_init(
+                || Aligner::with_scoring(scoring.clone()),
+                |aligner, y| aligner.custom_score(x, y),
+            )
+            .collect()
+    }
 }
 
 /// Packed representation of one cell of a Smith-Waterman traceback matrix.
@@ -1229,7 +1693,7 @@ mod tests {
         let mut aligner = Aligner::with_capacity(x.len(), y.len(), -5, -1, score);
         let alignment = aligner.global(x, y);
 
-        println!("aln:\n{}", alignment.pretty(x, y));
+        println!("aln:\n{}", alignment.pretty(x, y, 80));
         assert_eq!(
             alignment.operations,
             [Match, Match, Match, Ins, Ins, Ins, Match, Match, Match]
@@ -1244,7 +1708,7 @@ mod tests {
         let mut aligner = Aligner::with_capacity(x.len(), y.len(), -5, -1, score);
         let alignment = aligner.global(x, y);
 
-        println!("aln:\n{}", alignment.pretty(x, y));
+        println!("aln:\n{}", alignment.pretty(x, y, 80));
 
         let mut correct = Vec::new();
         correct.extend(repeat(Match).take(11));
@@ -1265,6 +1729,130 @@ mod tests {
         assert_eq!(alignment.ystart, 0);
     }
 
+    #[test]
+    fn test_overlap() {
+        let score = |a: u8, b: u8| if a == b { 1i32 } else { -1i32 };
+        let mut aligner = Aligner::new(-5, -1, score);
+
+        // y contained in x
+        let alignment = aligner.overlap(b"GGGGACGTACGTCC", b"ACGTACGT");
+        assert_eq!(alignment.score, 8);
+        assert_eq!((alignment.xstart, alignment.xend), (4, 12));
+        assert_eq!((alignment.ystart, alignment.yend), (0, 8));
+
+        // prefix of x overlaps suffix of y
+        let alignment = aligner.overlap(b"ACGTACGTTTTT", b"CCCCCACGTACGT");
+        assert_eq!(alignment.score, 8);
+        assert_eq!(alignment.operations[0], Yclip(5));
+        assert_eq!(alignment.operations[9], Xclip(4));
+
+        // the scoring is left untouched
+        assert_eq!(aligner.global(b"ACGT", b"ACGT").operations, [Match; 4]);
+    }
+
+    #[test]
+    fn test_local_all() {
+        let score = |a: u8, b: u8| if a == b { 1i32 } else { -1i32 };
+        let mut aligner = Aligner::new(-5, -1, score);
+
+        let x = b"ACCGTGGATGGGCGTAAA";
+        let y = b"AAAAACCGTTGATTTTCGTAA";
+        let best = aligner.local(x, y);
+        let alignments = aligner.local_all(x, y, 3);
+        assert_eq!(alignments[0].score, best.score);
+        assert_eq!(alignments[0].operations, best.operations);
+        assert!(alignments.len() > 1);
+        for pair in alignments.windows(2) {
+            assert!(pair[0].score >= pair[1].score);
+        }
+        assert!(alignments.iter().all(|alignment| alignment.score >= 3));
+
+        // x matches twice in y, with a substitution in the second copy
+        let x = b"ACGTACGT";
+        let y = b"ACGTACGTCCCCCACGAACGT";
+        let alignments = aligner.local_all(x, y, 5);
+        assert_eq!(alignments.len(), 2);
+        assert_eq!(alignments[0].score, 8);
+        assert_eq!((alignments[1].ystart, alignments[1].yend), (13, 21));
+        assert_eq!(alignments[1].score, 7 - 1);
+        assert!(aligner.local_all(b"AAAA", b"TTTT", 1).is_empty());
+    }
+
+    #[test]
+    fn test_align_many() {
+        let score = |a: u8, b: u8| if a == b { 1i32 } else { -1i32 };
+        let x = b"ACCGTGGAT";
+        let targets = [
+            &b"AAAAACCGTTGAT"[..],
+            b"ACCGTGGAT",
+            b"",
+            b"TTTTTTTTTTTTTTTTTTTTACCG",
+        ];
+        for &mode in &[
+            AlignmentMode::Global,
+            AlignmentMode::Semiglobal,
+            AlignmentMode::Local,
+        ] {
+            let mut aligner = Aligner::new(-5, -1, score);
+            let alignments = aligner.align_many(x, targets.iter().cloned(), mode);
+            assert_eq!(alignments.len(), targets.len());
+            for (alignment, y) in alignments.iter().zip(&targets) {
+                let mut fresh = Aligner::new(-5, -1, score);
+                let expected = match mode {
+                    AlignmentMode::Global => fresh.global(x, y),
+                    AlignmentMode::Semiglobal => fresh.semiglobal(x, y),
+                    _ => fresh.local(x, y),
+                };
+                assert_eq!(*alignment, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_score_many() {
+        let score = |a: u8, b: u8| if a == b { 1i32 } else { -1i32 };
+        let x = b"ACCGTGGAT";
+        let targets = [
+            &b"AAAAACCGTTGAT"[..],
+            b"ACCGTGGAT",
+            b"",
+            b"TTTTTTTTTTTTTTTTTTTTACCG",
+            b"GGATTTTTTTTTTTTTTTTTTACCG",
+        ];
+        let scoring = Scoring::new(-5, -1, score)
+            .xclip_prefix(-3)
+            .xclip_suffix(-2)
+            .yclip(-1);
+        let mut aligner = Aligner::with_scoring(scoring);
+        for &mode in &[
+            AlignmentMode::Global,
+            AlignmentMode::Semiglobal,
+            AlignmentMode::Local,
+            AlignmentMode::Custom,
+        ] {
+            let expected: Vec<_> = aligner
+                .align_many(x, targets.iter().cloned(), mode)
+                .iter()
+                .map(|alignment| alignment.score)
+                .collect();
+            assert_eq!(
+                aligner.score_many(x, targets.iter().cloned(), mode),
+                expected
+            );
+            #[cfg(feature = "rayon")]
+            {
+                assert_eq!(aligner.par_score_many(x, &targets, mode), expected);
+                assert_eq!(
+                    aligner.par_align_many(x, &targets, mode),
+                    aligner.align_many(x, targets.iter().cloned(), mode)
+                );
+            }
+        }
+        // the clip penalties are restored
+        assert_eq!(aligner.scoring.xclip_prefix, -3);
+        assert_eq!(aligner.scoring.yclip_suffix, -1);
+    }
+
     #[test]
     fn test_local() {
         let x = b"ACCGTGGAT";
@@ -1288,7 +1876,7 @@ mod tests {
         let mut aligner = Aligner::with_capacity(x.len(), y.len(), -5, -1, score);
         let alignment = aligner.global(x, y);
 
-        println!("\naln:\n{}", alignment.pretty(x, y));
+        println!("\naln:\n{}", alignment.pretty(x, y, 80));
         assert_eq!(alignment.ystart, 0);
         assert_eq!(alignment.xstart, 0);
         assert_eq!(
@@ -1407,7 +1995,7 @@ mod tests {
         let score = |a: u8, b: u8| if a == b { 1i32 } else { -1i32 };
         let mut aligner = Aligner::with_capacity(x.len(), y.len(), -5, -1, &score);
         let alignment = aligner.global(x, y);
-        println!("\naln:\n{}", alignment.pretty(x, y));
+        println!("\naln:\n{}", alignment.pretty(x, y, 80));
 
         assert_eq!(alignment.ystart, 0);
         assert_eq!(alignment.xstart, 0);
@@ -1431,7 +2019,7 @@ mod tests {
         let mut aligner = Aligner::with_capacity(x.len(), y.len(), -5, -1, &score);
         let alignment = aligner.global(x, y);
 
-        println!("\naln:\n{}", alignment.pretty(x, y));
+        println!("\naln:\n{}", alignment.pretty(x, y, 80));
 
         println!("score:{}", alignment.score);
         assert_eq!(alignment.score, -9);
@@ -1453,7 +2041,7 @@ mod tests {
         let score = |a: u8, b: u8| if a == b { 1i32 } else { -1i32 };
         let mut aligner = Aligner::with_capacity(x.len(), y.len(), -5, -1, &score);
         let alignment = aligner.global(x, y);
-        println!("\naln:\n{}", alignment.pretty(x, y));
+        println!("\naln:\n{}", alignment.pretty(x, y, 80));
 
         assert_eq!(alignment.ystart, 0);
         assert_eq!(alignment.xstart, 0);
@@ -1631,7 +2219,7 @@ mod tests {
         let mut aligner = Aligner::with_scoring(scoring);
         let alignment = aligner.custom(x, y);
 
-        println!("{}", alignment.pretty(x, y));
+        println!("{}", alignment.pretty(x, y, 80));
         assert_eq!(alignment.score, 7);
     }
 
@@ -0,0 +1,625 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Striped Smith-Waterman (Farrar 2007) for fast computation of local alignment scores of
+//! one query against many targets using SIMD instructions.
+//!
+//! The query is preprocessed once into a striped profile, such that the dynamic programming
+//! of each target column is computed with saturating 16-bit vector operations. The vectorized
+//! implementations are enabled with the `simd` feature. On x86_64, AVX2 is used if the CPU
+//! supports it (detected at runtime), and SSE2 otherwise. On other architectures, e.g.
+//! aarch64, a portable implementation on arrays of 16 lanes is used, which the compiler
+//! vectorizes for the target (e.g. with NEON). Without the feature, or if scores do not fit
+//! into 16 bits, a scalar implementation is used.
+//! Scores are equal to those of
+//! [`Aligner::local`](../struct.Aligner.html#method.local), but no traceback is computed.
+//! Compute the full alignment with the latter for the targets of interest.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::alignment::pairwise::striped::StripedAligner;
+//! use bio::alignment::pairwise::Aligner;
+//!
+//! let score = |a: u8, b: u8| if a == b { 2i32 } else { -1i32 };
+//! let query = b"ACCGTGGATGGA";
+//! let targets: Vec<&[u8]> = vec![b"TTACCGTGGATTTGA", b"GGGGGG", b"CGTGGATGG"];
+//!
+//! let mut striped = StripedAligner::new(query, -5, -1, score);
+//! let scores = striped.scores(targets.iter().copied());
+//!
+//! let mut aligner = Aligner::new(-5, -1, score);
+//! for (target, striped_score) in targets.iter().zip(scores) {
+//!     assert_eq!(striped_score, aligner.local(query, target).score);
+//! }
+//! ```
+
+use std::cmp::max;
+
+use crate::alignment::pairwise::MatchFunc;
+use crate::utils::TextSlice;
+
+/// Profile score of padding positions beyond the end of the query.
+const PADDING: i16 = i16::MIN / 2;
+
+/// Bound on the absolute value of substitution scores and gap penalties for using 16 bits.
+const SCORE_LIMIT: i32 = (i16::MAX / 4) as i32;
+
+/// The implementation used for computing scores.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Backend {
+    Scalar,
+    #[cfg(feature = "simd")]
+    Portable,
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    Sse2,
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    Avx2,
+}
+
+impl Backend {
+    /// The fastest implementation supported by the CPU.
+    fn detect() -> Self {
+        #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+        {
+            if is_x86_feature_detected!("avx2") {
+                return Backend::Avx2;
+            }
+            if is_x86_feature_detected!("sse2") {
+                return Backend::Sse2;
+            }
+        }
+        #[cfg(feature = "simd")]
+        let backend = Backend::Portable;
+        #[cfg(not(feature = "simd"))]
+        let backend = Backend::Scalar;
+        backend
+    }
+
+    fn lanes(self) -> usize {
+        match self {
+            Backend::Scalar => 1,
+            #[cfg(feature = "simd")]
+            Backend::Portable => portable::Portable::LANES,
+            #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+            Backend::Sse2 => 8,
+            #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+            Backend::Avx2 => 16,
+        }
+    }
+}
+
+/// Local alignment scorer for a fixed query, see the [module documentation](index.html).
+pub struct StripedAligner<'a, F: MatchFunc> {
+    query: TextSlice<'a>,
+    /// Score of a gap of length one, as a (positive) penalty.
+    gap_open: i32,
+    /// Score of extending a gap by one, as a (positive) penalty.
+    gap_extend: i32,
+    match_fn: F,
+    backend: Backend,
+    seg_len: usize,
+    /// Striped query profile for each target symbol, indexed by segment and lane. Profiles
+    /// are computed when the symbol first occurs in a target, such that the score function
+    /// is never called with symbols that do not occur.
+    profile: Vec<Vec<i16>>,
+    /// Maximal substitution score in the profile.
+    max_score: i32,
+}
+
+impl<'a, F: MatchFunc> StripedAligner<'a, F> {
+    /// Create a new striped aligner for the given query, gap open and gap extend penalties
+    /// and score function, with the same semantics as
+    /// [`Aligner::new`](../struct.Aligner.html#method.new).
+    pub fn new(query: TextSlice<'a>, gap_open: i32, gap_extend: i32, match_fn: F) -> Self {
+        StripedAligner::with_backend(query, gap_open, gap_extend, match_fn, Backend::detect())
+    }
+
+    fn with_backend(
+        query: TextSlice<'a>,
+        gap_open: i32,
+        gap_extend: i32,
+        match_fn: F,
+        backend: Backend,
+    ) -> Self {
+        assert!(gap_open <= 0, "gap_open can't be positive");
+        assert!(gap_extend <= 0, "gap_extend can't be positive");
+
+        let backend = if -(gap_open + gap_extend) < SCORE_LIMIT && !query.is_empty() {
+            backend
+        } else {
+            Backend::Scalar
+        };
+        StripedAligner {
+            query,
+            gap_open: -(gap_open + gap_extend),
+            gap_extend: -gap_extend,
+            match_fn,
+            backend,
+            seg_len: query.len().div_ceil(backend.lanes()),
+            profile: vec![Vec::new(); 256],
+            max_score: 0,
+        }
+    }
+
+    /// Compute the profiles of the symbols of the given target that have not been seen
+    /// before. Falls back to the scalar implementation if scores do not fit into 16 bits.
+    fn update_profile(&mut self, target: TextSlice<'_>) {
+        let lanes = self.backend.lanes();
+        for &b in target {
+            if !self.profile[b as usize].is_empty() {
+                continue;
+            }
+            let mut profile = Vec::with_capacity(self.seg_len * lanes);
+            for j in 0..self.seg_len {
+                for k in 0..lanes {
+                    profile.push(match self.query.get(k * self.seg_len + j) {
+                        Some(&a) => {
+                            let score = self.match_fn.score(a, b);
+                            if score.abs() >= SCORE_LIMIT {
+                                self.backend = Backend::Scalar;
+                                return;
+                            }
+                            self.max_score = max(self.max_score, score);
+                            score as i16
+                        }
+                        None => PADDING,
+                    });
+                }
+            }
+            self.profile[b as usize] = profile;
+        }
+    }
+
+    /// Compute the local alignment score of the query against the given target.
+    pub fn score(&mut self, target: TextSlice<'_>) -> i32 {
+        if self.backend != Backend::Scalar {
+            self.update_profile(target);
+        }
+        let score = match self.vector_score(target) {
+            Some(score) => score as i32,
+            None => return self.scalar_score(target),
+        };
+        if score >= i16::MAX as i32 - self.max_score {
+            // saturated, recompute with 32 bits
+            self.scalar_score(target)
+        } else {
+            score
+        }
+    }
+
+    /// Compute the score with the vectorized implementation of the backend, if any.
+    #[cfg_attr(not(feature = "simd"), allow(unused_variables))]
+    fn vector_score(&self, target: TextSlice<'_>) -> Option<i16> {
+        match self.backend {
+            Backend::Scalar => None,
+            // Safety: the profile has been computed for all symbols of the target.
+            #[cfg(feature = "simd")]
+            Backend::Portable => Some(unsafe { self.striped_portable(target) }),
+            // Safety: the backend is only selected if the CPU supports it.
+            #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+            Backend::Sse2 => Some(unsafe { self.striped_sse2(target) }),
+            #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+            Backend::Avx2 => Some(unsafe { self.striped_avx2(target) }),
+        }
+    }
+
+    /// Compute the local alignment scores of the query against each of the given targets.
+    pub fn scores<'b, I>(&mut self, targets: I) -> Vec<i32>
+    where
+        I: IntoIterator<Item = TextSlice<'b>>,
+    {
+        targets
+            .into_iter()
+            .map(|target| self.score(target))
+            .collect()
+    }
+
+    /// Gotoh's algorithm in linear space.
+    fn scalar_score(&self, target: TextSlice<'_>) -> i32 {
+        let neg_inf = i32::MIN / 2;
+        let m = self.query.len();
+        // H and E of the previous target column
+        let mut h = vec![0; m + 1];
+        let mut e = vec![neg_inf; m + 1];
+        let mut best = 0;
+        for &b in target {
+            let mut diag = 0;
+            let mut h_up = 0;
+            let mut f = neg_inf;
+            for i in 1..=m {
+                e[i] = max(e[i] - self.gap_extend, h[i] - self.gap_open);
+                f = max(f - self.gap_extend, h_up - self.gap_open);
+                let score = max(
+                    max(0, diag + self.match_fn.score(self.query[i - 1], b)),
+                    max(e[i], f),
+                );
+                diag = h[i];
+                h[i] = score;
+                h_up = score;
+                best = max(best, score);
+            }
+        }
+        best
+    }
+
+    #[cfg(feature = "simd")]
+    unsafe fn striped_portable(&self, target: TextSlice<'_>) -> i16 {
+        striped::<portable::Portable>(
+            &self.profile,
+            self.seg_len,
+            target,
+            self.gap_open as i16,
+            self.gap_extend as i16,
+        )
+    }
+
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    #[target_feature(enable = "sse2")]
+    unsafe fn striped_sse2(&self, target: TextSlice<'_>) -> i16 {
+        striped::<x86::Sse2>(
+            &self.profile,
+            self.seg_len,
+            target,
+            self.gap_open as i16,
+            self.gap_extend as i16,
+        )
+    }
+
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    #[target_feature(enable = "avx2")]
+    unsafe fn striped_avx2(&self, target: TextSlice<'_>) -> i16 {
+        striped::<x86::Avx2>(
+            &self.profile,
+            self.seg_len,
+            target,
+            self.gap_open as i16,
+            self.gap_extend as i16,
+        )
+    }
+}
+
+/// Operations on vectors of 16-bit lanes needed by the striped algorithm.
+#[cfg(feature = "simd")]
+trait Lanes: Copy {
+    const LANES: usize;
+    unsafe fn splat(value: i16) -> Self;
+    unsafe fn load(ptr: *const i16) -> Self;
+    unsafe fn store(self, ptr: *mut i16);
+    unsafe fn adds(self, other: Self) -> Self;
+    unsafe fn subs(self, other: Self) -> Self;
+    unsafe fn max(self, other: Self) -> Self;
+    /// Move each lane to the next higher one, shifting in zero.
+    unsafe fn shift(self) -> Self;
+    /// Whether any lane of self is greater than the corresponding lane of other.
+    unsafe fn any_gt(self, other: Self) -> bool;
+    unsafe fn hmax(self) -> i16 {
+        let mut lanes = [0i16; 16];
+        self.store(lanes.as_mut_ptr());
+        *lanes[..Self::LANES].iter().max().unwrap()
+    }
+}
+
+/// Lanes stored in a plain array, with element-wise operations that the compiler vectorizes
+/// for the target architecture.
+#[cfg(feature = "simd")]
+mod portable {
+    use super::Lanes;
+    use std::ptr;
+
+    #[derive(Clone, Copy)]
+    pub(super) struct Portable([i16; 16]);
+
+    impl Portable {
+        #[inline(always)]
+        fn zip_with(mut self, other: Self, f: impl Fn(i16, i16) -> i16) -> Self {
+            for (a, &b) in self.0.iter_mut().zip(other.0.iter()) {
+                *a = f(*a, b);
+            }
+            self
+        }
+    }
+
+    impl Lanes for Portable {
+        const LANES: usize = 16;
+
+        #[inline(always)]
+        unsafe fn splat(value: i16) -> Self {
+            Portable([value; 16])
+        }
+
+        #[inline(always)]
+        unsafe fn load(ptr: *const i16) -> Self {
+            Portable(ptr::read_unaligned(ptr as *const [i16; 16]))
+        }
+
+        #[inline(always)]
+        unsafe fn store(self, ptr: *mut i16) {
+            ptr::write_unaligned(ptr as *mut [i16; 16], self.0)
+        }
+
+        #[inline(always)]
+        unsafe fn adds(self, other: Self) -> Self {
+            self.zip_with(other, i16::saturating_add)
+        }
+
+        #[inline(always)]
+        unsafe fn subs(self, other: Self) -> Self {
+            self.zip_with(other, i16::saturating_sub)
+        }
+
+        #[inline(always)]
+        unsafe fn max(self, other: Self) -> Self {
+            self.zip_with(other, std::cmp::max)
+        }
+
+        #[inline(always)]
+        unsafe fn shift(self) -> Self {
+            let mut lanes = [0; 16];
+            lanes[1..].copy_from_slice(&self.0[..15]);
+            Portable(lanes)
+        }
+
+        #[inline(always)]
+        unsafe fn any_gt(self, other: Self) -> bool {
+            self.0.iter().zip(other.0.iter()).any(|(a, b)| a > b)
+        }
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod x86 {
+    use super::Lanes;
+    use std::arch::x86_64::*;
+
+    #[derive(Clone, Copy)]
+    pub(super) struct Sse2(__m128i);
+
+    impl Lanes for Sse2 {
+        const LANES: usize = 8;
+
+        #[inline(always)]
+        unsafe fn splat(value: i16) -> Self {
+            Sse2(_mm_set1_epi16(value))
+        }
+
+        #[inline(always)]
+        unsafe fn load(ptr: *const i16) -> Self {
+            Sse2(_mm_loadu_si128(ptr as *const __m128i))
+        }
+
+        #[inline(always)]
+        unsafe fn store(self, ptr: *mut i16) {
+            _mm_storeu_si128(ptr as *mut __m128i, self.0)
+        }
+
+        #[inline(always)]
+        unsafe fn adds(self, other: Self) -> Self {
+            Sse2(_mm_adds_epi16(self.0, other.0))
+        }
+
+        #[inline(always)]
+        unsafe fn subs(self, other: Self) -> Self {
+            Sse2(_mm_subs_epi16(self.0, other.0))
+        }
+
+        #[inline(always)]
+        unsafe fn max(self, other: Self) -> Self {
+            Sse2(_mm_max_epi16(self.0, other.0))
+        }
+
+        #[inline(always)]
+        unsafe fn shift(self) -> Self {
+            Sse2(_mm_slli_si128(self.0, 2))
+        }
+
+        #[inline(always)]
+        unsafe fn any_gt(self, other: Self) -> bool {
+            _mm_movemask_epi8(_mm_cmpgt_epi16(self.0, other.0)) != 0
+        }
+    }
+
+    #[derive(Clone, Copy)]
+    pub(super) struct Avx2(__m256i);
+
+    impl Lanes for Avx2 {
+        const LANES: usize = 16;
+
+        #[inline(always)]
+        unsafe fn splat(value: i16) -> Self {
+            Avx2(_mm256_set1_epi16(value))
+        }
+
+        #[inline(always)]
+        unsafe fn load(ptr: *const i16) -> Self {
+            Avx2(_mm256_loadu_si256(ptr as *const __m256i))
+        }
+
+        #[inline(always)]
+        unsafe fn store(self, ptr: *mut i16) {
+            _mm256_storeu_si256(ptr as *mut __m256i, self.0)
+        }
+
+        #[inline(always)]
+        unsafe fn adds(self, other: Self) -> Self {
+            Avx2(_mm256_adds_epi16(self.0, other.0))
+        }
+
+        #[inline(always)]
+        unsafe fn subs(self, other: Self) -> Self {
+            Avx2(_mm256_subs_epi16(self.0, other.0))
+        }
+
+        #[inline(always)]
+        unsafe fn max(self, other: Self) -> Self {
+            Avx2(_mm256_max_epi16(self.0, other.0))
+        }
+
+        #[inline(always)]
+        unsafe fn shift(self) -> Self {
+            // shifting across the two 128-bit halves needs the low half in the high half
+            let low = _mm256_permute2x128_si256(self.0, self.0, 0x08);
+            Avx2(_mm256_alignr_epi8(self.0, low, 14))
+        }
+
+        #[inline(always)]
+        unsafe fn any_gt(self, other: Self) -> bool {
+            _mm256_movemask_epi8(_mm256_cmpgt_epi16(self.0, other.0)) != 0
+        }
+    }
+}
+
+/// The striped algorithm, returning the best local alignment score (saturated at
+/// `i16::MAX`). Query position `k * seg_len + j` is stored in lane `k` of segment `j`.
+#[cfg(feature = "simd")]
+#[inline(always)]
+unsafe fn striped<V: Lanes>(
+    profile: &[Vec<i16>],
+    seg_len: usize,
+    target: TextSlice<'_>,
+    gap_open: i16,
+    gap_extend: i16,
+) -> i16 {
+    let lanes = V::LANES;
+    let zero = V::splat(0);
+    let gap_open = V::splat(gap_open);
+    let gap_extend = V::splat(gap_extend);
+    let mut h_store = vec![0i16; seg_len * lanes];
+    let mut h_load = vec![0i16; seg_len * lanes];
+    let mut e = vec![0i16; seg_len * lanes];
+    let mut best = zero;
+
+    for &b in target {
+        let profile = profile[b as usize].as_ptr();
+        // F is initialized with zero, errors are corrected in the lazy F loop below
+        let mut f = zero;
+        // H of the previous column, shifted to the diagonal predecessor
+        let mut h = V::load(h_store.as_ptr().add((seg_len - 1) * lanes)).shift();
+        std::mem::swap(&mut h_load, &mut h_store);
+
+        for j in 0..seg_len {
+            let offset = j * lanes;
+            h = h.adds(V::load(profile.add(offset)));
+            let mut e_j = V::load(e.as_ptr().add(offset));
+            h = h.max(e_j).max(f).max(zero);
+            h.store(h_store.as_mut_ptr().add(offset));
+            best = best.max(h);
+
+            let h_gap = h.subs(gap_open);
+            e_j = e_j.subs(gap_extend).max(h_gap);
+            e_j.store(e.as_mut_ptr().add(offset));
+            f = f.subs(gap_extend).max(h_gap);
+            h = V::load(h_load.as_ptr().add(offset));
+        }
+
+        // Lazy F loop: propagate F values across segment boundaries, as long as they can
+        // improve H. Since H is non-negative, F values of at most zero can be ignored.
+        f = f.shift();
+        let mut j = 0;
+        loop {
+            let offset = j * lanes;
+            let h = V::load(h_store.as_ptr().add(offset));
+            if !f.any_gt(h.subs(gap_open).max(zero)) {
+                break;
+            }
+            let h = h.max(f);
+            h.store(h_store.as_mut_ptr().add(offset));
+            best = best.max(h);
+            let e_j = V::load(e.as_ptr().add(offset)).max(h.subs(gap_open));
+            e_j.store(e.as_mut_ptr().add(offset));
+
+            f = f.subs(gap_extend);
+            j += 1;
+            if j == seg_len {
+                j = 0;
+                f = f.shift();
+            }
+        }
+    }
+    best.hmax()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alignment::pairwise::Aligner;
+    use crate::scores::blosum62;
+    use crate::utils::testing::random_seq;
+    use rand::prelude::*;
+
+    #[cfg_attr(not(feature = "simd"), allow(unused_mut))]
+    fn backends() -> Vec<Backend> {
+        let mut backends = vec![Backend::Scalar];
+        #[cfg(feature = "simd")]
+        backends.push(Backend::Portable);
+        #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+        {
+            if is_x86_feature_detected!("sse2") {
+                backends.push(Backend::Sse2);
+            }
+            if is_x86_feature_detected!("avx2") {
+                backends.push(Backend::Avx2);
+            }
+        }
+        backends
+    }
+
+    #[test]
+    fn test_matches_full_dp() {
+        let score = |a: u8, b: u8| if a == b { 2i32 } else { -3i32 };
+        let mut rng = StdRng::seed_from_u64(42);
+        for &(gap_open, gap_extend) in &[(-5, -1), (0, -2), (-3, 0)] {
+            let mut aligner = Aligner::new(gap_open, gap_extend, score);
+            for &len in &[1, 7, 8, 33, 100] {
+                let query = random_seq(&mut rng, b"ACGT", len);
+                for backend in backends() {
+                    let mut striped =
+                        StripedAligner::with_backend(&query, gap_open, gap_extend, score, backend);
+                    for _ in 0..5 {
+                        let mut target = random_seq(&mut rng, b"ACGT", 80);
+                        // plant a mutated copy of the query
+                        target.extend(query.iter().step_by(2));
+                        target.extend(random_seq(&mut rng, b"ACGT", 10));
+                        assert_eq!(
+                            striped.score(&target),
+                            aligner.local(&query, &target).score,
+                            "{:?} {} {}",
+                            backend,
+                            gap_open,
+                            gap_extend
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_protein() {
+        let query = b"LSPADKTNVKAAWGKVGAHAGEYGAEALERMFLSFPTTKTYFPHF";
+        let target = b"MHLTPEEKSAVTALWGKVNVDEVGGEALGRLLVVYPWTQRFFESFGDL";
+        let mut aligner = Aligner::new(-10, -1, &blosum62);
+        let expected = aligner.local(query, target).score;
+        for backend in backends() {
+            let mut striped = StripedAligner::with_backend(query, -10, -1, &blosum62, backend);
+            assert_eq!(striped.score(target), expected);
+            assert_eq!(striped.scores(vec![&b""[..], b"W"]), vec![0, 11]);
+        }
+    }
+
+    #[test]
+    fn test_saturation() {
+        let score = |a: u8, b: u8| if a == b { 500i32 } else { -500i32 };
+        let query = vec![b'A'; 200];
+        let mut striped = StripedAligner::new(&query, -5, -1, score);
+        assert_eq!(striped.score(&query), 100_000);
+
+        let score = |a: u8, b: u8| if a == b { 100i32 } else { -100i32 };
+        let mut striped = StripedAligner::new(&query, -5, -1, score);
+        assert_eq!(striped.score(&query), 20_000);
+        let mut empty = StripedAligner::new(b"", -5, -1, score);
+        assert_eq!(empty.score(&query), 0);
+    }
+}
@@ -14,6 +14,10 @@
 //! For a modern reference implementation, see poapy:
 //! https://github.com/ljdursi/poapy
 //!
+//! Sequences can be aligned globally or locally against the graph and added to it, where only
+//! the aligned part of locally aligned sequences is added. The consensus of the added sequences is given by the
+//! heaviest path through the graph.
+//!
 //! # Example
 //!
 //! ```
@@ -42,7 +46,8 @@ use crate::alignment::pairwise::{MatchFunc, Scoring};
 use petgraph::graph::NodeIndex;
 use petgraph::visit::Topo;
 
-use petgraph::{Directed, Graph, Incoming};
+use petgraph::visit::EdgeRef;
+use petgraph::{Directed, Graph, Incoming, Outgoing};
 
 pub const MIN_SCORE: i32 = -858_993_459; // negative infinity; see alignment/pairwise/mod.rs
 pub type POAGraph = Graph<u8, i32, Directed, usize>;
@@ -60,8 +65,12 @@ pub enum AlignmentOperation {
 
 pub struct Alignment {
     pub score: i32,
-    //    xstart: Edge,
+    /// Start of the aligned part of the query.
+    pub xstart: usize,
+    /// End of the aligned part of the query (exclusive).
+    pub xend: usize,
     operations: Vec<AlignmentOperation>,
+    local: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -99,6 +108,10 @@ pub struct Traceback {
     // store the last visited node in topological order so that
     // we can index into the end of the alignment when we backtrack
     last: NodeIndex<usize>,
+    // the query position the alignment ends at, which is the end of the query unless
+    // the alignment is local
+    last_col: usize,
+    local: bool,
     matrix: Vec<Vec<TracebackCell>>,
 }
 
@@ -124,6 +137,8 @@ impl Traceback {
             rows: m,
             cols: n,
             last: NodeIndex::new(0),
+            last_col: n,
+            local: false,
             matrix,
         }
     }
@@ -156,6 +171,8 @@ impl Traceback {
             rows: 0,
             cols: 0,
             last: NodeIndex::new(0),
+            last_col: 0,
+            local: false,
             matrix: Vec::new(),
         }
     }
@@ -189,9 +206,13 @@ impl Traceback {
 
         // Now backtrack through the matrix to construct an optimal path
         let mut i = self.last.index() + 1;
-        let mut j = self.cols;
+        let mut j = self.last_col;
 
         while i > 0 && j > 0 {
+            // local alignments start after the last cell without positive score
+            if self.local && self.matrix[i][j].score <= 0 {
+                break;
+            }
             // push operation and edge corresponding to (one of the) optimal
             // routes
             ops.push(self.matrix[i][j].op.clone());
@@ -208,6 +229,7 @@ impl Traceback {
                     j -= 1;
                 }
                 AlignmentOperation::Match(None) => {
+                    j -= 1;
                     break;
                 }
                 AlignmentOperation::Del(None) => {
@@ -222,8 +244,11 @@ impl Traceback {
         ops.reverse();
 
         Alignment {
-            score: self.matrix[self.last.index() + 1][self.cols].score,
+            score: self.matrix[self.last.index() + 1][self.last_col].score,
+            xstart: j,
+            xend: self.last_col,
             operations: ops,
+            local: self.local,
         }
     }
 }
@@ -266,10 +291,24 @@ impl<F: MatchFunc> Aligner<F> {
         self
     }
 
+    /// Locally align a given query against the graph. If the alignment is added to the graph,
+    /// only the aligned part of the query is incorporated.
+    pub fn local(&mut self, query: TextSlice) -> &mut Self {
+        self.query = query.to_vec();
+        self.traceback = self.poa.local(query);
+        self
+    }
+
     /// Return alignment graph.
     pub fn graph(&self) -> &POAGraph {
         &self.poa.graph
     }
+
+    /// Return the consensus sequence of the sequences added to the graph, see
+    /// [`Poa::consensus`](struct.Poa.html#method.consensus).
+    pub fn consensus(&self) -> Vec<u8> {
+        self.poa.consensus()
+    }
 }
 
 /// A partially ordered alignment graph
@@ -317,12 +356,29 @@ impl<F: MatchFunc> Poa<F> {
     /// # Arguments
     /// * `query` - the query TextSlice to align against the internal graph member
     pub fn global(&self, query: TextSlice) -> Traceback {
+        self.align(query, false)
+    }
+
+    /// A local Smith-Waterman aligner on partially ordered graphs. The alignment ends at
+    /// the best scoring pair of node and query position.
+    ///
+    /// # Arguments
+    /// * `query` - the query TextSlice to align against the internal graph member
+    pub fn local(&self, query: TextSlice) -> Traceback {
+        self.align(query, true)
+    }
+
+    fn align(&self, query: TextSlice, local: bool) -> Traceback {
         assert!(self.graph.node_count() != 0);
 
         // dimensions of the traceback matrix
         let (m, n) = (self.graph.node_count(), query.len());
         let mut traceback = Traceback::with_capacity(m, n);
-        traceback.initialize_scores(self.scoring.gap_open);
+        traceback.local = local;
+        if !local {
+            traceback.initialize_scores(self.scoring.gap_open);
+        }
+        let mut best = (0, NodeIndex::new(0), 0);
 
         traceback.set(
             0,
@@ -377,20 +433,76 @@ impl<F: MatchFunc> Poa<F> {
                     max_cell
                 };
 
-                let score = max(
+                let mut score = max(
                     max_cell,
                     TracebackCell {
                         score: traceback.get(i, j - 1).score + self.scoring.gap_open,
                         op: AlignmentOperation::Ins(Some(i - 1)),
                     },
                 );
+                if local {
+                    if score.score <= 0 {
+                        score = TracebackCell {
+                            score: 0,
+                            op: AlignmentOperation::Match(None),
+                        };
+                    } else if score.score > best.0 {
+                        best = (score.score, node, j);
+                    }
+                }
                 traceback.set(i, j, score);
             }
         }
+        if local {
+            traceback.last = best.1;
+            traceback.last_col = best.2;
+        }
 
         traceback
     }
 
+    /// Compute the consensus sequence as the heaviest path through the graph (heaviest
+    /// bundle, Lee 2003), where the weight of an edge is the number of sequences traversing
+    /// it. Ties are broken in favor of the heavier continuation.
+    pub fn consensus(&self) -> Vec<u8> {
+        let mut order = Vec::with_capacity(self.graph.node_count());
+        let mut topo = Topo::new(&self.graph);
+        while let Some(node) = topo.next(&self.graph) {
+            order.push(node);
+        }
+
+        // best path score starting at each node, and the next node on that path
+        let mut scores = vec![0; self.graph.node_count()];
+        let mut next: Vec<Option<NodeIndex<usize>>> = vec![None; self.graph.node_count()];
+        for &node in order.iter().rev() {
+            let mut best: Option<(i32, i32, NodeIndex<usize>)> = None;
+            for edge in self.graph.edges_directed(node, Outgoing) {
+                let target = edge.target();
+                let candidate = (*edge.weight(), scores[target.index()], target);
+                if best
+                    .is_none_or(|(weight, score, _)| (candidate.0, candidate.1) > (weight, score))
+                {
+                    best = Some(candidate);
+                }
+            }
+            if let Some((weight, score, target)) = best {
+                scores[node.index()] = weight + score;
+                next[node.index()] = Some(target);
+            }
+        }
+
+        let mut consensus = Vec::new();
+        let mut node = order
+            .iter()
+            .copied()
+            .max_by_key(|node| (scores[node.index()], std::cmp::Reverse(node.index())));
+        while let Some(n) = node {
+            consensus.push(self.graph.raw_nodes()[n.index()].weight);
+            node = next[n.index()];
+        }
+        consensus
+    }
+
     /// Experimental: return sequence of traversed edges
     ///
     /// Only supports alignments for sequences that have already been added,
@@ -419,38 +531,49 @@ impl<F: MatchFunc> Poa<F> {
         path
     }
 
-    /// Incorporate a new sequence into a graph from an alignment
+    /// Incorporate a new sequence into a graph from an alignment. For local alignments, only
+    /// the aligned part of the sequence is incorporated, and it is not connected to the
+    /// preceding nodes of the graph.
     ///
     /// # Arguments
     ///
     /// * `aln` - The alignment of the new sequence to the graph
     /// * `seq` - The sequence being incorporated
     pub fn add_alignment(&mut self, aln: &Alignment, seq: TextSlice) {
-        let mut prev: NodeIndex<usize> = NodeIndex::new(0);
-        let mut i: usize = 0;
+        let mut prev: Option<NodeIndex<usize>> = if aln.local {
+            None
+        } else {
+            Some(NodeIndex::new(0))
+        };
+        let mut i: usize = aln.xstart;
         for op in aln.operations.iter() {
             match op {
                 AlignmentOperation::Match(None) => {
+                    prev = Some(NodeIndex::new(0));
                     i += 1;
                 }
                 AlignmentOperation::Match(Some((_, p))) => {
                     let node = NodeIndex::new(*p);
                     if (seq[i] != self.graph.raw_nodes()[*p].weight) && (seq[i] != b'X') {
                         let node = self.graph.add_node(seq[i]);
-                        self.graph.add_edge(prev, node, 1);
-                        prev = node;
+                        if let Some(prev) = prev {
+                            self.graph.add_edge(prev, node, 1);
+                        }
+                        prev = Some(node);
                     } else {
-                        // increment node weight
-                        match self.graph.find_edge(prev, node) {
-                            Some(edge) => {
-                                *self.graph.edge_weight_mut(edge).unwrap() += 1;
-                            }
-                            None => {
-                                // where the previous node was newly added
-                                self.graph.add_edge(prev, node, 1);
+                        if let Some(prev) = prev {
+                            // increment node weight
+                            match self.graph.find_edge(prev, node) {
+                                Some(edge) => {
+                                    *self.graph.edge_weight_mut(edge).unwrap() += 1;
+                                }
+                                None => {
+                                    // where the previous node was newly added
+                                    self.graph.add_edge(prev, node, 1);
+                                }
                             }
                         }
-                        prev = NodeIndex::new(*p);
+                        prev = Some(node);
                     }
                     i += 1;
                 }
@@ -459,8 +582,10 @@ impl<F: MatchFunc> Poa<F> {
                 }
                 AlignmentOperation::Ins(Some(_)) => {
                     let node = self.graph.add_node(seq[i]);
-                    self.graph.add_edge(prev, node, 1);
-                    prev = node;
+                    if let Some(prev) = prev {
+                        self.graph.add_edge(prev, node, 1);
+                    }
+                    prev = Some(node);
                     i += 1;
                 }
                 AlignmentOperation::Del(_) => {} // we should only have to skip over deleted nodes
@@ -581,4 +706,44 @@ mod tests {
             .add_to_graph();
         assert_eq!(aligner.alignment().score, 10);
     }
+
+    #[test]
+    fn test_local_alignment() {
+        let scoring = Scoring::new(-1, 0, |a: u8, b: u8| if a == b { 1i32 } else { -1i32 });
+        let poa = Poa::from_string(scoring, b"AAAAGATTACATTTT");
+        assert_eq!(poa.local(b"CCGATTACACC").alignment().score, 7);
+        assert_eq!(poa.global(b"CCGATTACACC").alignment().score, -1);
+        assert_eq!(poa.local(b"NNNN").alignment().score, 0);
+        let alignment = poa.local(b"CCGATTACACC").alignment();
+        assert_eq!((alignment.xstart, alignment.xend), (2, 9));
+    }
+
+    #[test]
+    fn test_add_local_alignment() {
+        let scoring = Scoring::new(-1, 0, |a: u8, b: u8| if a == b { 1i32 } else { -1i32 });
+        let mut aligner = Aligner::new(scoring, b"AAAAGATTACATTTT");
+        // the unaligned flanks of the query are not added
+        aligner.local(b"CCGATTACACC").add_to_graph();
+        assert_eq!(aligner.graph().node_count(), 15);
+        assert_eq!(aligner.graph().edge_count(), 14);
+        assert_eq!(aligner.consensus(), b"AAAAGATTACATTTT");
+
+        // a substitution inside the local alignment becomes a new branch
+        for _ in 0..2 {
+            aligner.local(b"CCGATCACACC").add_to_graph();
+        }
+        assert_eq!(aligner.graph().node_count(), 16);
+        assert_eq!(aligner.graph().edge_count(), 16);
+        assert_eq!(aligner.consensus(), b"AAAAGATCACATTTT");
+    }
+
+    #[test]
+    fn test_consensus() {
+        let scoring = Scoring::new(-1, 0, |a: u8, b: u8| if a == b { 1i32 } else { -1i32 });
+        let mut aligner = Aligner::new(scoring, b"GATTACA");
+        for seq in [&b"GACTACA"[..], b"GATTACA", b"GATTAGA", b"GATTACA"].iter() {
+            aligner.global(seq).add_to_graph();
+        }
+        assert_eq!(aligner.consensus(), b"GATTACA");
+    }
 }
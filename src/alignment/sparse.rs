@@ -11,7 +11,8 @@
 //! genome-genome alignment. The backbone alignment can be used as-is, or can serve
 //! as a guide for a banded alignment.  By tuning k so that len(query) + len(reference) < 4^k,
 //! the number of false positive kmer matches is kept small, resulting in very
-//! fast run times for long strings.
+//! fast run times for long strings. Anchors of variable length, such as maximal exact matches,
+//! can be chained with `chain_anchors` (without gap costs) or `chain_anchors_gapped`.
 //!
 //! # Example
 //!
@@ -324,6 +325,156 @@ pub fn sdpkpp_union_lcskpp_path(
     path_union
 }
 
+/// Chain anchors of variable length, such as maximal exact matches (MEMs), by finding a set of
+/// non-overlapping, co-linear anchors of maximum total length (a weighted longest increasing
+/// subsequence). Runs in O(n * log(n)) for n anchors.
+///
+/// # Arguments
+///
+/// * `anchors` - a vector of tuples `(string1 position, string2 position, length)`, sorted by
+///   their positions
+///
+/// # Return value
+///
+/// A `SparseAlignmentResult`, with `path` being the chain as indices into `anchors` and `score`
+/// being the number of bases covered by the chained anchors. `dp_vector` holds the best chain
+/// score ending in each anchor together with the index of its predecessor (-1 if none).
+///
+/// # Example
+///
+/// ```
+/// use bio::alignment::sparse::chain_anchors;
+///
+/// let anchors = [(0, 10, 5), (3, 0, 6), (6, 16, 4), (12, 20, 8)];
+/// let chain = chain_anchors(&anchors);
+/// assert_eq!(chain.path, vec![0, 2, 3]);
+/// assert_eq!(chain.score, 17);
+/// ```
+pub fn chain_anchors(anchors: &[(u32, u32, u32)]) -> SparseAlignmentResult {
+    for i in 1..anchors.len() {
+        assert!(anchors[i - 1] < anchors[i], "anchors must be sorted");
+    }
+
+    // end events (kind 0) go before start events (kind 1) at the same position, such that
+    // adjacent anchors can be chained
+    let mut events: Vec<(u32, u32, usize)> = Vec::with_capacity(2 * anchors.len());
+    let mut n = 0;
+    for (idx, &(x, y, len)) in anchors.iter().enumerate() {
+        events.push((x, 1, idx));
+        events.push((x + len, 0, idx));
+        n = max(n, y + len);
+    }
+    events.sort_unstable();
+
+    // best chain score (and its anchor index + 1) among finished anchors, by end in string 2
+    let mut max_col_dp: MaxBitTree<(u32, u32)> = MaxBitTree::new(n as usize + 1);
+    let mut dp: Vec<(u32, i32)> = vec![(0, -1); anchors.len()];
+    let mut best_dp = (0, -1);
+
+    for (_, kind, idx) in events {
+        let (_, y, len) = anchors[idx];
+        if kind == 1 {
+            let (best_value, best_position) = max_col_dp.get(y as usize);
+            dp[idx] = (len + best_value, best_position as i32 - 1);
+            best_dp = max(best_dp, (dp[idx].0, idx as i32));
+        } else {
+            max_col_dp.set((y + len) as usize, (dp[idx].0, idx as u32 + 1));
+        }
+    }
+
+    let (best_score, mut prev_anchor) = best_dp;
+    let mut path = Vec::new();
+    while prev_anchor >= 0 {
+        path.push(prev_anchor as usize);
+        prev_anchor = dp[prev_anchor as usize].1;
+    }
+    path.reverse();
+    SparseAlignmentResult {
+        path,
+        score: best_score,
+        dp_vector: dp,
+    }
+}
+
+/// Chain anchors of variable length with gap costs, similar to minimap2. Anchors may overlap, in
+/// which case only the bases not covered by the predecessor are counted. Connecting two anchors
+/// whose diagonals differ by d costs `gap_open + d * gap_extend` (if d > 0). In order to bound
+/// the running time to O(n * lookback), only the `lookback` closest preceding anchors are
+/// considered as predecessors.
+///
+/// # Arguments
+///
+/// * `anchors` - a vector of tuples `(string1 position, string2 position, length)`, sorted by
+///   their positions
+/// * `gap_open` - the score for opening a gap (should not be positive)
+/// * `gap_extend` - the score for extending a gap (should not be positive)
+/// * `lookback` - the maximum number of predecessors to consider for each anchor
+///
+/// # Return value
+///
+/// A `SparseAlignmentResult` as for `chain_anchors`, with `score` being the covered bases
+/// minus the gap costs.
+///
+/// # Example
+///
+/// ```
+/// use bio::alignment::sparse::chain_anchors_gapped;
+///
+/// let anchors = [(0, 0, 10), (12, 12, 10), (15, 40, 10), (24, 24, 10)];
+/// let chain = chain_anchors_gapped(&anchors, -5, -1, 50);
+/// assert_eq!(chain.path, vec![0, 1, 3]);
+/// assert_eq!(chain.score, 30);
+/// ```
+pub fn chain_anchors_gapped(
+    anchors: &[(u32, u32, u32)],
+    gap_open: i32,
+    gap_extend: i32,
+    lookback: usize,
+) -> SparseAlignmentResult {
+    for i in 1..anchors.len() {
+        assert!(anchors[i - 1] < anchors[i], "anchors must be sorted");
+    }
+
+    let mut dp: Vec<(u32, i32)> = Vec::with_capacity(anchors.len());
+    let mut best_dp = (0, -1);
+    for (i, &(x, y, len)) in anchors.iter().enumerate() {
+        let mut best = (len as i64, -1);
+        for j in (i.saturating_sub(lookback)..i).rev() {
+            let (px, py, _) = anchors[j];
+            if px >= x || py >= y {
+                continue;
+            }
+            let (dx, dy) = ((x - px) as i64, (y - py) as i64);
+            let d = (dx - dy).abs();
+            let gap = if d > 0 {
+                gap_open as i64 + gap_extend as i64 * d
+            } else {
+                0
+            };
+            let score = dp[j].0 as i64 + min(min(dx, dy), len as i64) + gap;
+            if score > best.0 {
+                best = (score, j as i32);
+            }
+        }
+        // the score is at least the length of the anchor itself
+        dp.push((best.0 as u32, best.1));
+        best_dp = max(best_dp, (dp[i].0, i as i32));
+    }
+
+    let (best_score, mut prev_anchor) = best_dp;
+    let mut path = Vec::new();
+    while prev_anchor >= 0 {
+        path.push(prev_anchor as usize);
+        prev_anchor = dp[prev_anchor as usize].1;
+    }
+    path.reverse();
+    SparseAlignmentResult {
+        path,
+        score: best_score,
+        dp_vector: dp,
+    }
+}
+
 /// Find all matches of length k between two strings, using a q-gram
 /// index. For very long reference strings, it may be more efficient to use and
 /// FMD index to generate the matches. Note that this method is mainly for
@@ -782,4 +933,64 @@ CGGGAGGAGACCTGGGCAGCGGCGGACTCATTGCAGGTCGCTCTGCGGTGAGGACGCCACAGGCAC";
                 .collect::<Vec<(u32, u32)>>()
         );
     }
+
+    #[test]
+    pub fn test_chain_anchors() {
+        use rand::rngs::StdRng;
+        use rand::{Rng, SeedableRng};
+
+        assert_eq!(super::chain_anchors(&[]).score, 0);
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..50 {
+            let mut anchors: Vec<(u32, u32, u32)> = (0..rng.gen_range(1..30))
+                .map(|_| {
+                    (
+                        rng.gen_range(0..100),
+                        rng.gen_range(0..100),
+                        rng.gen_range(1..15),
+                    )
+                })
+                .collect();
+            anchors.sort_unstable();
+            anchors.dedup_by_key(|a| (a.0, a.1));
+            let res = super::chain_anchors(&anchors);
+
+            // quadratic DP
+            let mut best = vec![0; anchors.len()];
+            for i in 0..anchors.len() {
+                let (x, y, len) = anchors[i];
+                best[i] = len;
+                for j in 0..i {
+                    let (px, py, plen) = anchors[j];
+                    if px + plen <= x && py + plen <= y {
+                        best[i] = best[i].max(best[j] + len);
+                    }
+                }
+            }
+            assert_eq!(res.score, *best.iter().max().unwrap());
+
+            let path: Vec<_> = res.path.iter().map(|&i| anchors[i]).collect();
+            for w in path.windows(2) {
+                assert!(w[0].0 + w[0].2 <= w[1].0 && w[0].1 + w[0].2 <= w[1].1);
+            }
+            assert_eq!(path.iter().map(|a| a.2).sum::<u32>(), res.score);
+        }
+    }
+
+    #[test]
+    pub fn test_chain_anchors_gapped() {
+        // overlapping anchors on the same diagonal only count new bases
+        let anchors = [(0, 5, 10), (4, 9, 10), (30, 30, 10)];
+        let res = super::chain_anchors_gapped(&anchors, -4, -2, 50);
+        assert_eq!(res.path, vec![0, 1]);
+        assert_eq!(res.score, 14);
+        // a cheap gap is worth crossing
+        let res = super::chain_anchors_gapped(&anchors, -1, -1, 50);
+        assert_eq!(res.path, vec![0, 1, 2]);
+        assert_eq!(res.score, 14 + 10 - 1 - 5);
+        // predecessors beyond the lookback are ignored
+        let res = super::chain_anchors_gapped(&anchors, -1, -1, 0);
+        assert_eq!(res.path.len(), 1);
+        assert_eq!(res.score, 10);
+    }
 }
@@ -0,0 +1,153 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Summary statistics of pairwise alignments, such as the identity and the fraction of the
+//! query covered by the alignment.
+//!
+//! The [`AlignmentSummary`](trait.AlignmentSummary.html) trait is implemented for
+//! [`Alignment`](../struct.Alignment.html), with `x` being the query and `y` being the
+//! reference. Together with a fit alignment (i.e. a semiglobal alignment, which aligns the
+//! complete query to a substring of the reference), this allows to e.g. locate amplicons or
+//! adapters and judge how well they match.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::alignment::pairwise::Aligner;
+//! use bio::alignment::summary::AlignmentSummary;
+//!
+//! let adapter = b"AGATCGGAAGAGC";
+//! let read = b"TTGCAGGTCAAGATCGGTAGAGCTTT";
+//! let score = |a: u8, b: u8| if a == b { 1i32 } else { -1i32 };
+//! let mut aligner = Aligner::new(-5, -1, score);
+//! let alignment = aligner.semiglobal(adapter, read);
+//! assert_eq!((alignment.ystart, alignment.yend), (10, 23));
+//! assert_eq!(alignment.query_coverage(), 1.0);
+//! assert_eq!(alignment.mismatches(), 1);
+//! assert!((alignment.identity() - 12.0 / 13.0).abs() < 1e-12);
+//! ```
+
+use crate::alignment::{Alignment, AlignmentOperation};
+
+/// Summary statistics of an alignment.
+pub trait AlignmentSummary {
+    /// Number of aligned identical residues.
+    fn matches(&self) -> usize;
+
+    /// Number of aligned different residues.
+    fn mismatches(&self) -> usize;
+
+    /// Number of inserted and deleted residues.
+    fn gap_residues(&self) -> usize;
+
+    /// Number of gaps, i.e. of runs of insertions or deletions.
+    fn gap_opens(&self) -> usize;
+
+    /// Number of alignment columns, i.e. of aligned residues and gap residues.
+    fn columns(&self) -> usize {
+        self.matches() + self.mismatches() + self.gap_residues()
+    }
+
+    /// Fraction of identical residues among the alignment columns (as reported by BLAST),
+    /// or zero for an empty alignment.
+    fn identity(&self) -> f64 {
+        match self.columns() {
+            0 => 0.0,
+            columns => self.matches() as f64 / columns as f64,
+        }
+    }
+
+    /// Fraction of the query `x` that is part of the alignment, or zero for an empty query.
+    fn query_coverage(&self) -> f64;
+
+    /// Fraction of the reference `y` that is part of the alignment, or zero for an empty
+    /// reference.
+    fn reference_coverage(&self) -> f64;
+}
+
+fn fraction(len: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        len as f64 / total as f64
+    }
+}
+
+impl AlignmentSummary for Alignment {
+    fn matches(&self) -> usize {
+        self.operations
+            .iter()
+            .filter(|&&op| op == AlignmentOperation::Match)
+            .count()
+    }
+
+    fn mismatches(&self) -> usize {
+        self.operations
+            .iter()
+            .filter(|&&op| op == AlignmentOperation::Subst)
+            .count()
+    }
+
+    fn gap_residues(&self) -> usize {
+        self.operations
+            .iter()
+            .filter(|&&op| op == AlignmentOperation::Ins || op == AlignmentOperation::Del)
+            .count()
+    }
+
+    fn gap_opens(&self) -> usize {
+        let mut last = None;
+        let mut opens = 0;
+        for &op in &self.operations {
+            let is_gap = op == AlignmentOperation::Ins || op == AlignmentOperation::Del;
+            if is_gap && last != Some(op) {
+                opens += 1;
+            }
+            last = Some(op);
+        }
+        opens
+    }
+
+    fn query_coverage(&self) -> f64 {
+        fraction(self.xend - self.xstart, self.xlen)
+    }
+
+    fn reference_coverage(&self) -> f64 {
+        fraction(self.yend - self.ystart, self.ylen)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alignment::AlignmentMode;
+    use crate::alignment::AlignmentOperation::*;
+
+    #[test]
+    fn test_summary() {
+        // the operations consume 9 residues of x and 7 of y
+        let alignment = Alignment {
+            score: 0,
+            xstart: 1,
+            ystart: 0,
+            xend: 10,
+            yend: 7,
+            xlen: 10,
+            ylen: 14,
+            operations: vec![Match, Match, Ins, Ins, Match, Subst, Del, Ins, Match, Match],
+            mode: AlignmentMode::Local,
+        };
+        assert_eq!(alignment.matches(), 5);
+        assert_eq!(alignment.mismatches(), 1);
+        assert_eq!(alignment.gap_residues(), 4);
+        assert_eq!(alignment.gap_opens(), 3);
+        assert_eq!(alignment.columns(), 10);
+        assert_eq!(alignment.identity(), 0.5);
+        assert_eq!(alignment.query_coverage(), 0.9);
+        assert_eq!(alignment.reference_coverage(), 0.5);
+        assert_eq!(Alignment::default().identity(), 0.0);
+        assert_eq!(Alignment::default().query_coverage(), 0.0);
+    }
+}
@@ -0,0 +1,478 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Gap-affine global alignment with the wavefront algorithm (WFA).
+//!
+//! Instead of filling the whole dynamic programming matrix, WFA computes for increasing
+//! alignment penalty the furthest reaching point on each diagonal, and follows runs of
+//! matches for free. The running time is O(ns) for sequences of length n and alignment
+//! penalty s, which makes it orders of magnitude faster than classic dynamic programming
+//! for long, highly similar sequences, e.g. when computing consensus sequences of long reads.
+//! Memory is O(s²), since all wavefronts are kept for the traceback.
+//!
+//! Matches score zero, and mismatches, gap openings and gap extensions are scored with
+//! non-positive values, such that a gap of length k scores `gap_open + k * gap_extend`, as in
+//! [`pairwise::Scoring`](../pairwise/struct.Scoring.html). The optimal alignment is
+//! hence the same as the one of `pairwise::Aligner::global` with a match score of zero.
+//!
+//! With [`adaptive`](struct.Aligner.html#method.adaptive) pruning (WFA-Adapt), diagonals that
+//! fall too far behind the leading one are dropped. This speeds up the alignment of noisy
+//! sequences considerably, but the result is no longer guaranteed to be optimal.
+//!
+//! *Marco-Sola, S., Moure, J. C., Moreto, M., and Espinosa, A. (2021). Fast gap-affine
+//! pairwise alignment using the wavefront algorithm. Bioinformatics 37, 456–463.*
+//!
+//! # Example
+//!
+//! ```
+//! use bio::alignment::wfa::Aligner;
+//!
+//! let x = b"GATTACAGATTACAGATTACA";
+//! let y = b"GATTACAGATTTACAGATAACA";
+//! let mut aligner = Aligner::new(-4, -6, -2);
+//! let alignment = aligner.global(x, y);
+//! // one inserted T and one mismatch
+//! assert_eq!(alignment.score, -12);
+//! assert_eq!((alignment.xend, alignment.yend), (21, 22));
+//! ```
+
+use std::cmp::{max, min};
+
+use crate::alignment::{Alignment, AlignmentMode, AlignmentOperation};
+use crate::utils::TextSlice;
+
+/// Offset of unreachable points. Far from `i32::MIN`, so that it can be incremented.
+const NONE: i32 = i32::MIN / 2;
+
+/// The wavefronts of a single penalty. Offsets are positions in `y`, indexed by diagonal
+/// `k = j - i`, and are given for paths ending in any operation (`m`), in a gap in `x`
+/// (`i`), or in a gap in `y` (`d`).
+#[derive(Debug, Clone)]
+struct Wavefront {
+    lo: i32,
+    hi: i32,
+    m: Vec<i32>,
+    i: Vec<i32>,
+    d: Vec<i32>,
+}
+
+impl Wavefront {
+    fn new(lo: i32, hi: i32) -> Self {
+        let len = (hi - lo + 1) as usize;
+        Wavefront {
+            lo,
+            hi,
+            m: vec![NONE; len],
+            i: vec![NONE; len],
+            d: vec![NONE; len],
+        }
+    }
+
+    fn index(&self, k: i32) -> Option<usize> {
+        if k >= self.lo && k <= self.hi {
+            Some((k - self.lo) as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Restrict the wavefront to the diagonals `lo..=hi`.
+    fn trim(&mut self, lo: i32, hi: i32) {
+        let (start, end) = ((lo - self.lo) as usize, (hi - self.lo + 1) as usize);
+        for offsets in [&mut self.m, &mut self.i, &mut self.d].iter_mut() {
+            offsets.truncate(end);
+            offsets.drain(..start);
+        }
+        self.lo = lo;
+        self.hi = hi;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Component {
+    M,
+    I,
+    D,
+}
+
+/// A wavefront aligner. The penalties are stored as positive values.
+#[derive(Debug, Clone)]
+pub struct Aligner {
+    mismatch: i32,
+    gap_open: i32,
+    gap_extend: i32,
+    /// Minimal wavefront length and maximal distance from the leading diagonal for
+    /// adaptive pruning.
+    adaptive: Option<(usize, usize)>,
+    wavefronts: Vec<Option<Wavefront>>,
+}
+
+impl Aligner {
+    /// Create a new aligner with the given mismatch, gap open and gap extend scores. Gap
+    /// opening must not be positive, mismatches and gap extensions have to be negative.
+    pub fn new(mismatch: i32, gap_open: i32, gap_extend: i32) -> Self {
+        assert!(mismatch < 0, "mismatch has to be negative");
+        assert!(gap_open <= 0, "gap_open can't be positive");
+        assert!(gap_extend < 0, "gap_extend has to be negative");
+        Aligner {
+            mismatch: -mismatch,
+            gap_open: -gap_open,
+            gap_extend: -gap_extend,
+            adaptive: None,
+            wavefronts: Vec::new(),
+        }
+    }
+
+    /// Enable adaptive pruning: once a wavefront spans at least `min_wavefront_length`
+    /// diagonals, diagonals at its borders whose remaining distance to the end of the
+    /// sequences exceeds the minimal one by more than `max_distance_threshold` are dropped.
+    pub fn adaptive(mut self, min_wavefront_length: usize, max_distance_threshold: usize) -> Self {
+        self.adaptive = Some((min_wavefront_length, max_distance_threshold));
+        self
+    }
+
+    /// Calculate the global alignment of `x` against `y`.
+    pub fn global(&mut self, x: TextSlice<'_>, y: TextSlice<'_>) -> Alignment {
+        let (m, n) = (x.len() as i32, y.len() as i32);
+        let final_k = n - m;
+        self.wavefronts.clear();
+
+        let mut initial = Wavefront::new(0, 0);
+        initial.m[0] = extend(x, y, 0, 0);
+        self.wavefronts.push(Some(initial));
+
+        let mut s = 0;
+        while self.offset(s, Component::M, final_k) < n {
+            s += 1;
+            let wavefront = self.next(x, y, s);
+            self.wavefronts.push(wavefront);
+        }
+
+        let mut operations = self.traceback(x, y, s, final_k, n);
+        operations.reverse();
+        Alignment {
+            score: -(s as i32),
+            xstart: 0,
+            ystart: 0,
+            xend: x.len(),
+            yend: y.len(),
+            xlen: x.len(),
+            ylen: y.len(),
+            operations,
+            mode: AlignmentMode::Global,
+        }
+    }
+
+    /// Offset of the given component of the wavefront with penalty `s` on diagonal `k`.
+    fn offset(&self, s: usize, component: Component, k: i32) -> i32 {
+        let wavefront = match self.wavefronts.get(s) {
+            Some(Some(wavefront)) => wavefront,
+            _ => return NONE,
+        };
+        match wavefront.index(k) {
+            Some(idx) => match component {
+                Component::M => wavefront.m[idx],
+                Component::I => wavefront.i[idx],
+                Component::D => wavefront.d[idx],
+            },
+            None => NONE,
+        }
+    }
+
+    /// Penalty `s - delta`, if not negative.
+    fn prev(&self, s: usize, delta: i32) -> Option<usize> {
+        s.checked_sub(delta as usize)
+    }
+
+    /// Diagonals covered by the wavefront with penalty `s`.
+    fn range(&self, s: Option<usize>) -> Option<(i32, i32)> {
+        s.and_then(|s| self.wavefronts[s].as_ref())
+            .map(|wavefront| (wavefront.lo, wavefront.hi))
+    }
+
+    fn offset_at(&self, s: Option<usize>, component: Component, k: i32) -> i32 {
+        s.map_or(NONE, |s| self.offset(s, component, k))
+    }
+
+    /// Compute the wavefront with penalty `s` from the previous ones.
+    fn next(&self, x: TextSlice<'_>, y: TextSlice<'_>, s: usize) -> Option<Wavefront> {
+        let (m, n) = (x.len() as i32, y.len() as i32);
+        let s_mismatch = self.prev(s, self.mismatch);
+        let s_open = self.prev(s, self.gap_open + self.gap_extend);
+        let s_extend = self.prev(s, self.gap_extend);
+
+        let mut lo = i32::MAX;
+        let mut hi = i32::MIN;
+        if let Some((l, h)) = self.range(s_mismatch) {
+            lo = min(lo, l);
+            hi = max(hi, h);
+        }
+        for &s_gap in [s_open, s_extend].iter() {
+            if let Some((l, h)) = self.range(s_gap) {
+                lo = min(lo, l - 1);
+                hi = max(hi, h + 1);
+            }
+        }
+        if lo > hi {
+            return None;
+        }
+        let (lo, hi) = (max(lo, -m), min(hi, n));
+
+        let valid = |k: i32, offset: i32| {
+            if offset >= 0 && offset <= n && offset - k >= 0 && offset - k <= m {
+                offset
+            } else {
+                NONE
+            }
+        };
+
+        let mut wavefront = Wavefront::new(lo, hi);
+        for k in lo..=hi {
+            let idx = (k - lo) as usize;
+            let ins = max(
+                self.offset_at(s_open, Component::M, k - 1),
+                self.offset_at(s_extend, Component::I, k - 1),
+            ) + 1;
+            let del = max(
+                self.offset_at(s_open, Component::M, k + 1),
+                self.offset_at(s_extend, Component::D, k + 1),
+            );
+            let sub = self.offset_at(s_mismatch, Component::M, k) + 1;
+            let (ins, del, sub) = (valid(k, ins), valid(k, del), valid(k, sub));
+            wavefront.i[idx] = ins;
+            wavefront.d[idx] = del;
+            let best = max(max(ins, del), sub);
+            wavefront.m[idx] = if best == NONE {
+                NONE
+            } else {
+                extend(x, y, best - k, best)
+            };
+        }
+
+        if let Some((min_length, max_distance)) = self.adaptive {
+            self.prune(&mut wavefront, m, n, min_length, max_distance as i32);
+        }
+        Some(wavefront)
+    }
+
+    /// Drop diagonals at the borders of the wavefront that fall too far behind.
+    fn prune(
+        &self,
+        wavefront: &mut Wavefront,
+        m: i32,
+        n: i32,
+        min_length: usize,
+        max_distance: i32,
+    ) {
+        if wavefront.m.len() < min_length {
+            return;
+        }
+        let distance = |k: i32, offset: i32| {
+            if offset == NONE {
+                i32::MAX
+            } else {
+                max(m - (offset - k), n - offset)
+            }
+        };
+        let distances: Vec<i32> = (wavefront.lo..=wavefront.hi)
+            .zip(&wavefront.m)
+            .map(|(k, &offset)| distance(k, offset))
+            .collect();
+        let min_distance = *distances.iter().min().unwrap();
+        if min_distance == i32::MAX {
+            return;
+        }
+        let keep = |d: &i32| *d - min_distance <= max_distance;
+        let first = distances.iter().position(keep).unwrap() as i32;
+        let last = distances.iter().rposition(keep).unwrap() as i32;
+        let lo = wavefront.lo;
+        wavefront.trim(lo + first, lo + last);
+    }
+
+    /// Trace back the optimal path from the end, returning the operations in reverse order.
+    fn traceback(
+        &self,
+        x: TextSlice<'_>,
+        y: TextSlice<'_>,
+        mut s: usize,
+        mut k: i32,
+        mut offset: i32,
+    ) -> Vec<AlignmentOperation> {
+        let mut operations = Vec::new();
+        let mut component = Component::M;
+        loop {
+            match component {
+                Component::M => {
+                    if s == 0 {
+                        for _ in 0..offset {
+                            operations.push(AlignmentOperation::Match);
+                        }
+                        return operations;
+                    }
+                    let sub = self.offset_at(self.prev(s, self.mismatch), Component::M, k) + 1;
+                    let ins = self.offset(s, Component::I, k);
+                    let del = self.offset(s, Component::D, k);
+                    let source = max(max(ins, del), sub);
+                    for _ in source..offset {
+                        operations.push(AlignmentOperation::Match);
+                    }
+                    offset = source;
+                    if source == sub {
+                        debug_assert_ne!(x[(offset - k - 1) as usize], y[(offset - 1) as usize]);
+                        operations.push(AlignmentOperation::Subst);
+                        offset -= 1;
+                        s -= self.mismatch as usize;
+                    } else if source == ins {
+                        component = Component::I;
+                    } else {
+                        component = Component::D;
+                    }
+                }
+                Component::I => {
+                    // gap in x, consuming y
+                    operations.push(AlignmentOperation::Del);
+                    let s_open = self.prev(s, self.gap_open + self.gap_extend);
+                    let open = self.offset_at(s_open, Component::M, k - 1) + 1;
+                    s -= self.gap_extend as usize;
+                    if open == offset {
+                        s -= self.gap_open as usize;
+                        component = Component::M;
+                    }
+                    offset -= 1;
+                    k -= 1;
+                }
+                Component::D => {
+                    // gap in y, consuming x
+                    operations.push(AlignmentOperation::Ins);
+                    let s_open = self.prev(s, self.gap_open + self.gap_extend);
+                    let open = self.offset_at(s_open, Component::M, k + 1);
+                    s -= self.gap_extend as usize;
+                    if open == offset {
+                        s -= self.gap_open as usize;
+                        component = Component::M;
+                    }
+                    k += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Follow the matches on diagonal `j - i` from position `(i, j)`, returning the new offset.
+fn extend(x: TextSlice<'_>, y: TextSlice<'_>, i: i32, j: i32) -> i32 {
+    let (i, j) = (i as usize, j as usize);
+    let matches = x[i..]
+        .iter()
+        .zip(&y[j..])
+        .take_while(|(a, b)| a == b)
+        .count();
+    (j + matches) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alignment::pairwise;
+    use crate::utils::testing::random_seq;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    /// Score of the alignment, checking that it is consistent with the sequences.
+    fn rescore(alignment: &Alignment, x: &[u8], y: &[u8], scores: (i32, i32, i32)) -> i32 {
+        let (mismatch, gap_open, gap_extend) = scores;
+        let (mut i, mut j) = (0, 0);
+        let mut score = 0;
+        let mut last = None;
+        for &op in &alignment.operations {
+            match op {
+                AlignmentOperation::Match => {
+                    assert_eq!(x[i], y[j]);
+                    i += 1;
+                    j += 1;
+                }
+                AlignmentOperation::Subst => {
+                    assert_ne!(x[i], y[j]);
+                    score += mismatch;
+                    i += 1;
+                    j += 1;
+                }
+                _ => {
+                    if last != Some(op) {
+                        score += gap_open;
+                    }
+                    score += gap_extend;
+                    if op == AlignmentOperation::Ins {
+                        i += 1;
+                    } else {
+                        j += 1;
+                    }
+                }
+            }
+            last = Some(op);
+        }
+        assert_eq!((i, j), (x.len(), y.len()));
+        score
+    }
+
+    fn mutate(rng: &mut StdRng, seq: &[u8], rate: f64) -> Vec<u8> {
+        let mut mutated = Vec::new();
+        for &a in seq {
+            if rng.gen_bool(rate) {
+                match rng.gen_range(0..3) {
+                    0 => mutated.push(b"ACGT"[rng.gen_range(0..4)]),
+                    1 => {
+                        mutated.push(a);
+                        mutated.push(b"ACGT"[rng.gen_range(0..4)]);
+                    }
+                    _ => (),
+                }
+            } else {
+                mutated.push(a);
+            }
+        }
+        mutated
+    }
+
+    #[test]
+    fn test_random_against_full_dp() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for &scores in [(-4, -6, -2), (-1, 0, -1), (-3, -10, -1)].iter() {
+            let (mismatch, gap_open, gap_extend) = scores;
+            let match_fn = |a: u8, b: u8| if a == b { 0i32 } else { mismatch };
+            for _ in 0..50 {
+                let len = rng.gen_range(0..60);
+                let x = random_seq(&mut rng, b"ACGT", len);
+                let y = mutate(&mut rng, &x, 0.15);
+                let expected =
+                    pairwise::Aligner::new(gap_open, gap_extend, match_fn).global(&x, &y);
+                let alignment = Aligner::new(mismatch, gap_open, gap_extend).global(&x, &y);
+                assert_eq!(alignment.score, expected.score);
+                assert_eq!(rescore(&alignment, &x, &y, scores), alignment.score);
+            }
+        }
+    }
+
+    #[test]
+    fn test_adaptive() {
+        let mut rng = StdRng::seed_from_u64(13);
+        let x = random_seq(&mut rng, b"ACGT", 2000);
+        let y = mutate(&mut rng, &x, 0.05);
+        let optimal = Aligner::new(-4, -6, -2).global(&x, &y);
+        let mut aligner = Aligner::new(-4, -6, -2).adaptive(10, 50);
+        let alignment = aligner.global(&x, &y);
+        assert!(alignment.score <= optimal.score);
+        assert_eq!(rescore(&alignment, &x, &y, (-4, -6, -2)), alignment.score);
+    }
+
+    #[test]
+    fn test_empty() {
+        let mut aligner = Aligner::new(-4, -6, -2);
+        assert_eq!(aligner.global(b"", b"").score, 0);
+        assert_eq!(aligner.global(b"ACG", b"").score, -12);
+        let alignment = aligner.global(b"", b"AC");
+        assert_eq!(alignment.score, -10);
+        assert_eq!(alignment.operations, vec![AlignmentOperation::Del; 2]);
+    }
+}
@@ -90,6 +90,36 @@ where
         .collect()
 }
 
+/// Complement the given text in place (IUPAC alphabet supported).
+///
+/// ```
+/// use bio::alphabets::dna;
+///
+/// let mut seq = b"ACGTn".to_vec();
+/// dna::complement_in_place(&mut seq);
+/// assert_eq!(seq, b"TGCAn");
+/// ```
+pub fn complement_in_place(text: &mut [u8]) {
+    for a in text.iter_mut() {
+        *a = complement(*a);
+    }
+}
+
+/// Reverse complement the given text in place (IUPAC alphabet supported),
+/// avoiding the allocation of [`revcomp`](fn.revcomp.html).
+///
+/// ```
+/// use bio::alphabets::dna;
+///
+/// let mut seq = b"GaTtaCA".to_vec();
+/// dna::revcomp_in_place(&mut seq);
+/// assert_eq!(seq, b"TGtaAtC");
+/// ```
+pub fn revcomp_in_place(text: &mut [u8]) {
+    text.reverse();
+    complement_in_place(text);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -113,4 +143,11 @@ mod tests {
     fn number_is_no_word() {
         assert!(!alphabet().is_word(b"42"));
     }
+
+    #[test]
+    fn test_revcomp_in_place_odd_length() {
+        let mut seq = b"AGCTYRWSKMDVHBN".to_vec();
+        revcomp_in_place(&mut seq);
+        assert_eq!(seq, revcomp(b"AGCTYRWSKMDVHBN"));
+    }
 }
@@ -0,0 +1,249 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Algebra of IUPAC nucleotide ambiguity codes.
+//!
+//! Each code denotes a set of nucleotides, e.g. `R` denotes `A` or `G`. Internally, such sets
+//! are represented as 4-bit masks (`A` = 1, `C` = 2, `G` = 4, `T` = 8), which makes
+//! compatibility tests, intersections and unions simple bit operations.
+//! Functions accept upper- and lowercase codes, and treat `U` like `T`. Returned codes
+//! are uppercase DNA.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::alphabets::iupac;
+//!
+//! assert!(iupac::is_compatible(b'R', b'A'));
+//! assert!(!iupac::is_compatible(b'R', b'C'));
+//! assert_eq!(iupac::union(b'A', b'G'), Some(b'R'));
+//! assert_eq!(iupac::intersection(b'R', b'K'), Some(b'G'));
+//! assert_eq!(iupac::expand(b'N'), Some(&b"ACGT"[..]));
+//!
+//! let seqs = iupac::expand_seq(b"ARY", 10).unwrap();
+//! assert_eq!(seqs, vec![b"AAC", b"AAT", b"AGC", b"AGT"]);
+//! ```
+
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum Error {
+    #[error("invalid IUPAC nucleotide code '{}' at position {pos}", *.code as char)]
+    InvalidCode { code: u8, pos: usize },
+
+    #[error("sequence expands to more than {max} concrete sequences")]
+    TooManySequences { max: usize },
+}
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Codes ordered by their bit mask, i.e. `CODES[mask]` is the code for `mask`.
+const CODES: &[u8; 16] = b"-ACMGRSVTWYHKDBN";
+
+/// Nucleotides denoted by each code, ordered by bit mask.
+const EXPANSIONS: [&[u8]; 16] = [
+    b"", b"A", b"C", b"AC", b"G", b"AG", b"CG", b"ACG", b"T", b"AT", b"CT", b"ACT", b"GT", b"AGT",
+    b"CGT", b"ACGT",
+];
+
+/// Bit mask of the nucleotides denoted by the given code, or `None` if it is not a
+/// nucleotide code.
+///
+/// ```
+/// use bio::alphabets::iupac;
+///
+/// assert_eq!(iupac::bits(b'A'), Some(0b0001));
+/// assert_eq!(iupac::bits(b'y'), Some(0b1010)); // C or T
+/// assert_eq!(iupac::bits(b'X'), None);
+/// ```
+pub fn bits(code: u8) -> Option<u8> {
+    match code.to_ascii_uppercase() {
+        b'U' => Some(8),
+        b'-' => None,
+        code => CODES.iter().position(|&c| c == code).map(|mask| mask as u8),
+    }
+}
+
+/// The code denoting the nucleotides of the given bit mask, or `None` for the empty set.
+///
+/// ```
+/// use bio::alphabets::iupac;
+///
+/// assert_eq!(iupac::from_bits(0b0110), Some(b'S')); // C or G
+/// assert_eq!(iupac::from_bits(0), None);
+/// ```
+pub fn from_bits(mask: u8) -> Option<u8> {
+    match mask & 0xf {
+        0 => None,
+        mask => Some(CODES[mask as usize]),
+    }
+}
+
+/// The nucleotides denoted by the given code.
+pub fn expand(code: u8) -> Option<&'static [u8]> {
+    bits(code).map(|mask| EXPANSIONS[mask as usize])
+}
+
+/// The number of nucleotides denoted by the given code, e.g. 1 for `A`, 2 for `R` and 4
+/// for `N`.
+pub fn degeneracy(code: u8) -> Option<usize> {
+    bits(code).map(|mask| mask.count_ones() as usize)
+}
+
+/// Whether the given code denotes exactly one nucleotide.
+pub fn is_unambiguous(code: u8) -> bool {
+    degeneracy(code) == Some(1)
+}
+
+/// Whether two codes can denote the same nucleotide, e.g. `R` (`A` or `G`) is compatible
+/// with `A` and with `K` (`G` or `T`), but not with `C`.
+/// Invalid codes are not compatible with anything.
+pub fn is_compatible(a: u8, b: u8) -> bool {
+    intersection(a, b).is_some()
+}
+
+/// Whether all nucleotides denoted by code `b` are also denoted by code `a`, i.e. whether
+/// `a` matches `b` under all possible resolutions of `b`.
+///
+/// ```
+/// use bio::alphabets::iupac;
+///
+/// assert!(iupac::contains(b'N', b'R'));
+/// assert!(!iupac::contains(b'R', b'N'));
+/// ```
+pub fn contains(a: u8, b: u8) -> bool {
+    match (bits(a), bits(b)) {
+        (Some(a), Some(b)) => a & b == b,
+        _ => false,
+    }
+}
+
+/// The code denoting the nucleotides denoted by both codes, or `None` if there are none
+/// (or a code is invalid).
+pub fn intersection(a: u8, b: u8) -> Option<u8> {
+    from_bits(bits(a)? & bits(b)?)
+}
+
+/// The code denoting the nucleotides denoted by any of the two codes, or `None` if a
+/// code is invalid.
+pub fn union(a: u8, b: u8) -> Option<u8> {
+    from_bits(bits(a)? | bits(b)?)
+}
+
+/// The code denoting the nucleotides not denoted by the given code (e.g. `B` for `A`),
+/// or `None` for `N` or an invalid code.
+pub fn negation(code: u8) -> Option<u8> {
+    from_bits(!bits(code)?)
+}
+
+/// Whether two sequences of equal length are compatible at every position.
+///
+/// ```
+/// use bio::alphabets::iupac;
+///
+/// assert!(iupac::is_compatible_seq(b"GATNACA", b"GATTRCA"));
+/// assert!(!iupac::is_compatible_seq(b"GATTACA", b"GATTACC"));
+/// ```
+pub fn is_compatible_seq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(&a, &b)| is_compatible(a, b))
+}
+
+/// The number of concrete sequences denoted by the given sequence, or `None` if it
+/// contains an invalid code or the number overflows.
+pub fn count_expansions(seq: &[u8]) -> Option<usize> {
+    seq.iter()
+        .try_fold(1usize, |count, &code| count.checked_mul(degeneracy(code)?))
+}
+
+/// Expand a sequence with ambiguity codes into the set of all concrete sequences it
+/// denotes, in lexicographical order.
+///
+/// Since the number of sequences grows exponentially with the number of ambiguous
+/// positions, an error is returned if it exceeds `max`.
+pub fn expand_seq(seq: &[u8], max: usize) -> Result<Vec<Vec<u8>>> {
+    let mut expansions = Vec::with_capacity(seq.len());
+    for (pos, &code) in seq.iter().enumerate() {
+        expansions.push(expand(code).ok_or(Error::InvalidCode { code, pos })?);
+    }
+    match count_expansions(seq) {
+        Some(count) if count <= max => (),
+        _ => return Err(Error::TooManySequences { max }),
+    }
+
+    let mut seqs = vec![Vec::with_capacity(seq.len())];
+    for bases in expansions {
+        seqs = seqs
+            .into_iter()
+            .flat_map(|prefix| {
+                bases.iter().map(move |&base| {
+                    let mut seq = prefix.clone();
+                    seq.push(base);
+                    seq
+                })
+            })
+            .collect();
+    }
+    Ok(seqs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bits_roundtrip() {
+        for &code in b"ACGTRYSWKMBDHVN" {
+            assert_eq!(from_bits(bits(code).unwrap()), Some(code));
+            assert_eq!(
+                bits(code.to_ascii_lowercase()),
+                bits(code),
+                "lowercase {}",
+                code as char
+            );
+        }
+        assert_eq!(bits(b'U'), bits(b'T'));
+        assert_eq!(bits(b'-'), None);
+    }
+
+    #[test]
+    fn test_expansions_match_bits() {
+        for mask in 1..16u8 {
+            let code = from_bits(mask).unwrap();
+            let expanded = expand(code).unwrap();
+            assert_eq!(expanded.len(), degeneracy(code).unwrap());
+            let union = expanded
+                .iter()
+                .fold(0, |acc, &base| acc | bits(base).unwrap());
+            assert_eq!(union, mask);
+        }
+    }
+
+    #[test]
+    fn test_algebra() {
+        assert_eq!(union(b'R', b'Y'), Some(b'N'));
+        assert_eq!(intersection(b'R', b'Y'), None);
+        assert_eq!(negation(b'A'), Some(b'B'));
+        assert_eq!(negation(b'N'), None);
+        assert!(is_compatible(b'u', b'W'));
+        assert!(!is_compatible(b'X', b'N'));
+        assert!(is_unambiguous(b'c'));
+        assert!(!is_unambiguous(b'S'));
+    }
+
+    #[test]
+    fn test_expand_seq_guard() {
+        assert_eq!(count_expansions(b"NNNNN"), Some(1024));
+        assert_eq!(
+            expand_seq(b"NNNNN", 1000),
+            Err(Error::TooManySequences { max: 1000 })
+        );
+        assert_eq!(expand_seq(b"NNNNN", 1024).unwrap().len(), 1024);
+        assert_eq!(count_expansions(&[b'N'; 64]), None);
+        assert_eq!(
+            expand_seq(b"ACX", 10),
+            Err(Error::InvalidCode { code: b'X', pos: 2 })
+        );
+        assert_eq!(expand_seq(b"", 1).unwrap(), vec![Vec::<u8>::new()]);
+    }
+}
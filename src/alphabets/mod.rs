@@ -21,6 +21,7 @@ use bit_set::BitSet;
 use vec_map::VecMap;
 
 pub mod dna;
+pub mod iupac;
 pub mod protein;
 pub mod rna;
 
@@ -264,6 +265,146 @@ impl RankTransform {
         *self.ranks.get(a as usize).expect("Unexpected character.")
     }
 
+    /// Get the rank of symbol `a`, or `None` if it is not contained in the alphabet.
+    ///
+    /// Complexity: O(1)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::alphabets;
+    ///
+    /// let dna_ranks = alphabets::RankTransform::new(&alphabets::dna::alphabet());
+    /// assert_eq!(dna_ranks.try_get(b'C'), Some(1));
+    /// assert_eq!(dna_ranks.try_get(b'N'), None);
+    /// ```
+    pub fn try_get(&self, a: u8) -> Option<u8> {
+        self.ranks.get(a as usize).copied()
+    }
+
+    /// Return the symbols of the alphabet, ordered by rank, i.e. the symbol of rank `r`
+    /// is found at index `r`.
+    ///
+    /// Complexity: O(n), where n is the number of symbols in the alphabet.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::alphabets;
+    ///
+    /// let dna_ranks = alphabets::RankTransform::new(&alphabets::Alphabet::new(b"TGCA"));
+    /// assert_eq!(dna_ranks.symbols(), b"ACGT");
+    /// ```
+    pub fn symbols(&self) -> Vec<u8> {
+        let mut symbols = vec![0; self.ranks.len()];
+        for (a, &r) in self.ranks.iter() {
+            symbols[r as usize] = a as u8;
+        }
+        symbols
+    }
+
+    /// Transform a given sequence of rank values back into the symbols of the alphabet,
+    /// i.e. the inverse of [`transform`](#method.transform).
+    ///
+    /// This method panics for ranks that are not smaller than the alphabet size.
+    ///
+    /// Complexity: O(n + m), where n is the length of the text and m the number of symbols
+    /// in the alphabet.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::alphabets;
+    ///
+    /// let dna_ranks = alphabets::RankTransform::new(&alphabets::Alphabet::new(b"ACGT"));
+    /// let ranks = dna_ranks.transform(b"GATTACA");
+    /// assert_eq!(dna_ranks.untransform(&ranks), b"GATTACA");
+    /// ```
+    pub fn untransform<C, T>(&self, ranks: T) -> Vec<u8>
+    where
+        C: Borrow<u8>,
+        T: IntoIterator<Item = C>,
+    {
+        let symbols = self.symbols();
+        ranks
+            .into_iter()
+            .map(|r| {
+                *symbols
+                    .get(*r.borrow() as usize)
+                    .expect("Unexpected rank in text.")
+            })
+            .collect()
+    }
+
+    /// Encode a single q-gram as `usize`, in the same way as [`qgrams`](#method.qgrams),
+    /// i.e. with the rank of the first symbol in the most significant bits.
+    ///
+    /// This method panics for characters not contained in the alphabet, and for q-grams
+    /// that do not fit into a `usize`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::alphabets;
+    ///
+    /// let dna_ranks = alphabets::RankTransform::new(&alphabets::Alphabet::new(b"ACGT"));
+    /// assert_eq!(dna_ranks.qgram(b"CG"), 0b0110);
+    /// let encoded: Vec<usize> = dna_ranks.qgrams(2, b"ACGT").collect();
+    /// assert_eq!(encoded[1], dna_ranks.qgram(b"CG"));
+    /// ```
+    pub fn qgram<C, T>(&self, qgram: T) -> usize
+    where
+        C: Borrow<u8>,
+        T: IntoIterator<Item = C>,
+    {
+        let bits = self.get_width();
+        let mut code = 0;
+        for (i, a) in qgram.into_iter().enumerate() {
+            assert!(
+                (i + 1) * bits <= mem::size_of::<usize>() * 8,
+                "Expecting q to be smaller than usize / log2(|A|)"
+            );
+            code = code << bits | self.get(*a.borrow()) as usize;
+        }
+        code
+    }
+
+    /// Decode a q-gram of length `q` that was encoded by [`qgram`](#method.qgram) or
+    /// [`qgrams`](#method.qgrams).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::alphabets;
+    ///
+    /// let dna_ranks = alphabets::RankTransform::new(&alphabets::Alphabet::new(b"ACGT"));
+    /// assert_eq!(dna_ranks.decode_qgram(3, dna_ranks.qgram(b"TGA")), b"TGA");
+    /// ```
+    pub fn decode_qgram(&self, q: usize, qgram: usize) -> Vec<u8> {
+        let bits = self.get_width();
+        let mask = (1 << bits) - 1;
+        let ranks: Vec<u8> = (0..q)
+            .rev()
+            .map(|i| ((qgram >> (i * bits)) & mask) as u8)
+            .collect();
+        self.untransform(ranks)
+    }
+
+    /// Number of distinct q-gram codes for the given `q`, i.e. the size of a table that
+    /// is indexed by the q-grams returned by [`qgrams`](#method.qgrams).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::alphabets;
+    ///
+    /// let dna_ranks = alphabets::RankTransform::new(&alphabets::Alphabet::new(b"ACGT"));
+    /// assert_eq!(dna_ranks.qgram_universe(3), 64);
+    /// ```
+    pub fn qgram_universe(&self, q: u32) -> usize {
+        1 << (self.get_width() as u32 * q)
+    }
+
     /// Transform a given `text` into a vector of rank values.
     ///
     /// Complexity: O(n), where n is the length of the text.
@@ -448,4 +589,15 @@ mod tests {
         assert_eq!(Alphabet::new(b"ATCG"), Alphabet::new(b"TAGC"));
         assert_ne!(Alphabet::new(b"ATCG"), Alphabet::new(b"ATC"));
     }
+
+    #[test]
+    fn test_qgram_roundtrip() {
+        let ranks = RankTransform::new(&Alphabet::new(b"ACGTN"));
+        let text = b"GATTACANNAGT";
+        for (i, code) in ranks.qgrams(4, &text[..]).enumerate() {
+            assert_eq!(code, ranks.qgram(&text[i..i + 4]));
+            assert_eq!(ranks.decode_qgram(4, code), &text[i..i + 4]);
+            assert!(code < ranks.qgram_universe(4));
+        }
+    }
 }
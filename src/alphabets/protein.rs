@@ -13,14 +13,245 @@
 //! assert!(alphabet.is_word(b"DEQsga"));
 //! assert!(!alphabet.is_word(b"BzJ"));
 //! ```
+//!
+//! In addition, this module provides physicochemical properties of amino acids, e.g.
+//! for computing the GRAVY score of a peptide:
+//!
+//! ```
+//! use bio::alphabets::protein;
+//!
+//! let peptide = b"MKWVTF";
+//! let gravy = peptide
+//!     .iter()
+//!     .map(|&aa| protein::hydrophobicity(aa).unwrap())
+//!     .sum::<f64>()
+//!     / peptide.len() as f64;
+//! assert!((gravy - 3.4 / 6.0).abs() < 1e-9);
+//! ```
 
 use crate::alphabets::Alphabet;
 
+/// The 20 common amino acids, in the order of the property tables below.
+const AMINO_ACIDS: &[u8; 20] = b"ARNDCEQGHILKMFPSTWYV";
+
+/// Kyte-Doolittle hydropathy index.
+const HYDROPHOBICITY: [f64; 20] = [
+    1.8, -4.5, -3.5, -3.5, 2.5, -3.5, -3.5, -0.4, -3.2, 4.5, 3.8, -3.9, 1.9, 2.8, -1.6, -0.8, -0.7,
+    -0.9, -1.3, 4.2,
+];
+
+/// Average masses of amino acid residues (i.e., within a peptide chain) in Dalton.
+const AVERAGE_MASS: [f64; 20] = [
+    71.0788, 156.1875, 114.1038, 115.0886, 103.1388, 129.1155, 128.1307, 57.0519, 137.1411,
+    113.1594, 113.1594, 128.1741, 131.1926, 147.1766, 97.1167, 87.0782, 101.1051, 186.2132,
+    163.1760, 99.1326,
+];
+
+/// Monoisotopic masses of amino acid residues in Dalton.
+const MONOISOTOPIC_MASS: [f64; 20] = [
+    71.03711, 156.10111, 114.04293, 115.02694, 103.00919, 129.04259, 128.05858, 57.02146,
+    137.05891, 113.08406, 113.08406, 128.09496, 131.04049, 147.06841, 97.05276, 87.03203,
+    101.04768, 186.07931, 163.06333, 99.06841,
+];
+
+/// Dipeptide instability weight values (DIWV) of Guruprasad et al. (Protein Engineering, 1990),
+/// indexed by the first and the second amino acid of a dipeptide.
+const INSTABILITY_WEIGHTS: [[f64; 20]; 20] = [
+    // A
+    [
+        1.00, 1.00, 1.00, -7.49, 44.94, 1.00, 1.00, 1.00, -7.49, 1.00, 1.00, 1.00, 1.00, 1.00,
+        20.26, 1.00, 1.00, 1.00, 1.00, 1.00,
+    ],
+    // R
+    [
+        1.00, 58.28, 13.34, 1.00, 1.00, 1.00, 20.26, -7.49, 20.26, 1.00, 1.00, 1.00, 1.00, 1.00,
+        20.26, 44.94, 1.00, 58.28, -6.54, 1.00,
+    ],
+    // N
+    [
+        1.00, 1.00, 1.00, 1.00, -1.88, 1.00, -6.54, -14.03, 1.00, 44.94, 1.00, 24.68, 1.00, -14.03,
+        -1.88, 1.00, -7.49, -9.37, 1.00, 1.00,
+    ],
+    // D
+    [
+        1.00, -6.54, 1.00, 1.00, 1.00, 1.00, 1.00, 1.00, 1.00, 1.00, 1.00, -7.49, 1.00, -6.54,
+        1.00, 20.26, -14.03, 1.00, 1.00, 1.00,
+    ],
+    // C
+    [
+        1.00, 1.00, 1.00, 20.26, 1.00, 1.00, -6.54, 1.00, 33.60, 1.00, 20.26, 1.00, 33.60, 1.00,
+        20.26, 1.00, 33.60, 24.68, 1.00, -6.54,
+    ],
+    // E
+    [
+        1.00, 1.00, 1.00, 20.26, 44.94, 33.60, 20.26, 1.00, -6.54, 20.26, 1.00, 1.00, 1.00, 1.00,
+        20.26, 20.26, 1.00, -14.03, 1.00, 1.00,
+    ],
+    // Q
+    [
+        1.00, 1.00, 1.00, 20.26, -6.54, 20.26, 20.26, 1.00, 1.00, 1.00, 1.00, 1.00, 1.00, -6.54,
+        20.26, 44.94, 1.00, 1.00, -6.54, -6.54,
+    ],
+    // G
+    [
+        -7.49, 1.00, -7.49, 1.00, 1.00, -6.54, 1.00, 13.34, 1.00, -7.49, 1.00, -7.49, 1.00, 1.00,
+        1.00, 1.00, -7.49, 13.34, -7.49, 1.00,
+    ],
+    // H
+    [
+        1.00, 1.00, 24.68, 1.00, 1.00, 1.00, 1.00, -9.37, 1.00, 44.94, 1.00, 24.68, 1.00, -9.37,
+        -1.88, 1.00, -6.54, -1.88, 44.94, 1.00,
+    ],
+    // I
+    [
+        1.00, 1.00, 1.00, 1.00, 1.00, 44.94, 1.00, 1.00, 13.34, 1.00, 20.26, -7.49, 1.00, 1.00,
+        -1.88, 1.00, 1.00, 1.00, 1.00, -7.49,
+    ],
+    // L
+    [
+        1.00, 20.26, 1.00, 1.00, 1.00, 1.00, 33.60, 1.00, 1.00, 1.00, 1.00, -7.49, 1.00, 1.00,
+        20.26, 1.00, 1.00, 24.68, 1.00, 1.00,
+    ],
+    // K
+    [
+        1.00, 33.60, 1.00, 1.00, 1.00, 1.00, 24.64, -7.49, 1.00, -7.49, -7.49, 1.00, 33.60, 1.00,
+        -6.54, 1.00, 1.00, 1.00, 1.00, -7.49,
+    ],
+    // M
+    [
+        13.34, -6.54, 1.00, 1.00, 1.00, 1.00, -6.54, 1.00, 58.28, 1.00, 1.00, 1.00, -1.88, 1.00,
+        44.94, 44.94, -1.88, 1.00, 24.68, 1.00,
+    ],
+    // F
+    [
+        1.00, 1.00, 1.00, 13.34, 1.00, 1.00, 1.00, 1.00, 1.00, 1.00, 1.00, -14.03, 1.00, 1.00,
+        20.26, 1.00, 1.00, 1.00, 33.601, 1.00,
+    ],
+    // P
+    [
+        20.26, -6.54, 1.00, -6.54, -6.54, 18.38, 20.26, 1.00, 1.00, 1.00, 1.00, 1.00, -6.54, 20.26,
+        20.26, 20.26, 1.00, -1.88, 1.00, 20.26,
+    ],
+    // S
+    [
+        1.00, 20.26, 1.00, 1.00, 33.60, 20.26, 20.26, 1.00, 1.00, 1.00, 1.00, 1.00, 1.00, 1.00,
+        44.94, 20.26, 1.00, 1.00, 1.00, 1.00,
+    ],
+    // T
+    [
+        1.00, 1.00, -14.03, 1.00, 1.00, 20.26, -6.54, -7.49, 1.00, 1.00, 1.00, 1.00, 1.00, 13.34,
+        1.00, 1.00, 1.00, -14.03, 1.00, 1.00,
+    ],
+    // W
+    [
+        -14.03, 1.00, 13.34, 1.00, 1.00, 1.00, 1.00, -9.37, 24.68, 1.00, 13.34, 1.00, 24.68, 1.00,
+        1.00, 1.00, -14.03, 1.00, 1.00, -7.49,
+    ],
+    // Y
+    [
+        24.68, -15.91, 1.00, 24.68, 1.00, -6.54, 1.00, -7.49, 13.34, 1.00, 1.00, 1.00, 44.94, 1.00,
+        13.34, 1.00, -7.49, -9.37, 13.34, 1.00,
+    ],
+    // V
+    [
+        1.00, 1.00, 1.00, -14.03, 1.00, 1.00, 1.00, -7.49, 1.00, 1.00, 1.00, -1.88, 1.00, 1.00,
+        20.26, 1.00, -7.49, 1.00, -6.54, 1.00,
+    ],
+];
+
+/// Average mass of water in Dalton, to be added to the residue masses of a peptide.
+pub const WATER_AVERAGE_MASS: f64 = 18.01524;
+
+/// Monoisotopic mass of water in Dalton, to be added to the residue masses of a peptide.
+pub const WATER_MONOISOTOPIC_MASS: f64 = 18.01056;
+
+/// pKa of the N-terminal amino group (EMBOSS values).
+pub const N_TERMINUS_PKA: f64 = 8.6;
+
+/// pKa of the C-terminal carboxyl group (EMBOSS values).
+pub const C_TERMINUS_PKA: f64 = 3.6;
+
 /// Returns the standard protein alphabet, containing the 20 common amino acids.
 pub fn alphabet() -> Alphabet {
     Alphabet::new(&b"ARNDCEQGHILKMFPSTWYVarndceqghilkmfpstwyv"[..])
 }
 
+/// Returns the extended protein alphabet, containing the 20 common amino acids,
+/// selenocysteine (`U`), and the ambiguity codes `B` (`D` or `N`), `Z` (`E` or `Q`)
+/// and `X` (any).
+pub fn extended_alphabet() -> Alphabet {
+    Alphabet::new(&b"ARNDCEQGHILKMFPSTWYVUBZXarndceqghilkmfpstwyvubzx"[..])
+}
+
+/// Position of a common amino acid (upper- or lowercase) in the property tables.
+fn index(aa: u8) -> Option<usize> {
+    AMINO_ACIDS
+        .iter()
+        .position(|&a| a == aa.to_ascii_uppercase())
+}
+
+/// Kyte-Doolittle hydropathy index of the given amino acid, or `None` if it is not one of
+/// the 20 common amino acids.
+pub fn hydrophobicity(aa: u8) -> Option<f64> {
+    index(aa).map(|i| HYDROPHOBICITY[i])
+}
+
+/// Average mass of the given amino acid residue in Dalton, or `None` if it is not one of
+/// the 20 common amino acids or selenocysteine. The mass of a peptide is the sum of its
+/// residue masses plus [`WATER_AVERAGE_MASS`](constant.WATER_AVERAGE_MASS.html).
+///
+/// ```
+/// use bio::alphabets::protein;
+///
+/// let mass = b"GG".iter().map(|&aa| protein::average_mass(aa).unwrap()).sum::<f64>()
+///     + protein::WATER_AVERAGE_MASS;
+/// assert!((mass - 132.12).abs() < 0.01);
+/// ```
+pub fn average_mass(aa: u8) -> Option<f64> {
+    match aa.to_ascii_uppercase() {
+        b'U' => Some(150.0388),
+        _ => index(aa).map(|i| AVERAGE_MASS[i]),
+    }
+}
+
+/// Monoisotopic mass of the given amino acid residue in Dalton, or `None` if it is not one
+/// of the 20 common amino acids or selenocysteine.
+pub fn monoisotopic_mass(aa: u8) -> Option<f64> {
+    match aa.to_ascii_uppercase() {
+        b'U' => Some(150.95364),
+        _ => index(aa).map(|i| MONOISOTOPIC_MASS[i]),
+    }
+}
+
+/// pKa of the ionizable side chain of the given amino acid (EMBOSS values), or `None` if
+/// the side chain is not ionizable. See [`is_basic`](fn.is_basic.html) for the sign of
+/// the charge.
+pub fn side_chain_pka(aa: u8) -> Option<f64> {
+    match aa.to_ascii_uppercase() {
+        b'C' => Some(8.5),
+        b'D' => Some(3.9),
+        b'E' => Some(4.1),
+        b'H' => Some(6.5),
+        b'K' => Some(10.8),
+        b'R' => Some(12.5),
+        b'Y' => Some(10.1),
+        _ => None,
+    }
+}
+
+/// Whether the side chain of the given amino acid is basic, i.e. positively charged when
+/// protonated (`H`, `K`, `R`). The remaining ionizable side chains (`C`, `D`, `E`, `Y`)
+/// are acidic, i.e. negatively charged when deprotonated.
+pub fn is_basic(aa: u8) -> bool {
+    matches!(aa.to_ascii_uppercase(), b'H' | b'K' | b'R')
+}
+
+/// Dipeptide instability weight value (DIWV) of Guruprasad et al. for the given pair of
+/// consecutive amino acids, or `None` if one of them is not one of the 20 common amino acids.
+pub fn instability_weight(first: u8, second: u8) -> Option<f64> {
+    Some(INSTABILITY_WEIGHTS[index(first)?][index(second)?])
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -44,4 +275,28 @@ mod tests {
     fn number_is_no_word() {
         assert!(!alphabet().is_word(b"42"));
     }
+
+    #[test]
+    fn test_extended_alphabet() {
+        assert!(extended_alphabet().is_word(b"MKXBZuv"));
+        assert!(!extended_alphabet().is_word(b"J"));
+    }
+
+    #[test]
+    fn test_property_tables() {
+        for &aa in b"ARNDCEQGHILKMFPSTWYVarndceqghilkmfpstwyv" {
+            assert!(hydrophobicity(aa).is_some());
+            assert!(monoisotopic_mass(aa).unwrap() < average_mass(aa).unwrap());
+        }
+        assert_eq!(hydrophobicity(b'X'), None);
+        assert_eq!(average_mass(b'B'), None);
+        assert_eq!(hydrophobicity(b'i'), Some(4.5));
+        assert_eq!(side_chain_pka(b'k'), Some(10.8));
+        assert_eq!(side_chain_pka(b'A'), None);
+        assert!(is_basic(b'R') && !is_basic(b'D'));
+        assert_eq!(instability_weight(b'A', b'C'), Some(44.94));
+        assert_eq!(instability_weight(b'c', b'a'), Some(1.0));
+        assert_eq!(instability_weight(b'R', b'Y'), Some(-6.54));
+        assert_eq!(instability_weight(b'A', b'X'), None);
+    }
 }
@@ -77,6 +77,7 @@ pub fn complement(a: u8) -> u8 {
 /// assert_eq!(rna::revcomp(b"ACGUN"), b"NACGU");
 /// assert_eq!(rna::revcomp(b"GaUuaCA"), b"UGuaAuC");
 /// assert_eq!(rna::revcomp(b"AGCUYRWSKMDVHBNZ"), b"ZNVDBHKMSWYRAGCU");
+/// ```
 pub fn revcomp<C, T>(text: T) -> Vec<u8>
 where
     C: Borrow<u8>,
@@ -89,6 +90,109 @@ where
         .collect()
 }
 
+/// Reverse complement the given text in place (IUPAC alphabet supported).
+///
+/// ```
+/// use bio::alphabets::rna;
+///
+/// let mut seq = b"GaUuaCA".to_vec();
+/// rna::revcomp_in_place(&mut seq);
+/// assert_eq!(seq, b"UGuaAuC");
+/// ```
+pub fn revcomp_in_place(text: &mut [u8]) {
+    text.reverse();
+    for a in text.iter_mut() {
+        *a = complement(*a);
+    }
+}
+
+/// Transcribe the given DNA text into RNA, i.e. replace `T` by `U`.
+///
+/// Casing is preserved, all other characters (e.g. IUPAC ambiguity codes, which are
+/// shared by DNA and RNA) remain as they are. Note that this is the transcript of the
+/// coding strand; to obtain the transcript of a template strand, use
+/// [`dna::revcomp`](../dna/fn.revcomp.html) first.
+///
+/// ```
+/// use bio::alphabets::rna;
+///
+/// assert_eq!(rna::transcribe(b"ATGgtNRT"), b"AUGguNRU");
+/// ```
+pub fn transcribe<C, T>(text: T) -> Vec<u8>
+where
+    C: Borrow<u8>,
+    T: IntoIterator<Item = C>,
+{
+    text.into_iter()
+        .map(|a| match *a.borrow() {
+            b'T' => b'U',
+            b't' => b'u',
+            a => a,
+        })
+        .collect()
+}
+
+/// Transcribe the given DNA text into RNA in place.
+///
+/// ```
+/// use bio::alphabets::rna;
+///
+/// let mut seq = b"ATGt".to_vec();
+/// rna::transcribe_in_place(&mut seq);
+/// assert_eq!(seq, b"AUGu");
+/// ```
+pub fn transcribe_in_place(text: &mut [u8]) {
+    for a in text.iter_mut() {
+        match *a {
+            b'T' => *a = b'U',
+            b't' => *a = b'u',
+            _ => (),
+        }
+    }
+}
+
+/// Back-transcribe the given RNA text into DNA, i.e. replace `U` by `T`.
+///
+/// Casing is preserved, all other characters remain as they are.
+///
+/// ```
+/// use bio::alphabets::rna;
+///
+/// assert_eq!(rna::back_transcribe(b"AUGguNRU"), b"ATGgtNRT");
+/// ```
+pub fn back_transcribe<C, T>(text: T) -> Vec<u8>
+where
+    C: Borrow<u8>,
+    T: IntoIterator<Item = C>,
+{
+    text.into_iter()
+        .map(|a| match *a.borrow() {
+            b'U' => b'T',
+            b'u' => b't',
+            a => a,
+        })
+        .collect()
+}
+
+/// Back-transcribe the given RNA text into DNA in place.
+///
+/// ```
+/// use bio::alphabets::rna;
+///
+/// let mut seq = b"AUGu".to_vec();
+/// rna::back_transcribe_in_place(&mut seq);
+/// assert_eq!(seq, b"ATGt");
+/// ```
+pub fn back_transcribe_in_place(text: &mut [u8]) {
+    for a in text.iter_mut() {
+        match *a {
+            b'U' => *a = b'T',
+            b'u' => *a = b't',
+            _ => (),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -117,4 +221,12 @@ mod tests {
     fn test_reverse_complement() {
         assert_eq!(revcomp(b"GAUUACA"), b"UGUAAUC");
     }
+
+    #[test]
+    fn test_transcription_roundtrip() {
+        let dna = b"GATTACAgattacaNRYT";
+        let rna = transcribe(dna.iter());
+        assert!(iupac_alphabet().is_word(&rna));
+        assert_eq!(back_transcribe(&rna), dna);
+    }
 }
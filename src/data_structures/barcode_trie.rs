@@ -0,0 +1,295 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A trie over fixed-length barcodes, e.g. the cell barcode whitelist of a single-cell
+//! protocol, supporting exact lookup and the search for all barcodes within a given Hamming
+//! distance of a query.
+//!
+//! The Hamming neighborhood is searched by a depth-first traversal that follows mismatching
+//! edges as long as the mismatch budget allows, such that shared prefixes of the whitelist are
+//! compared only once. This is considerably faster than comparing a query against every
+//! barcode, and, unlike enumerating all variants of the query, does not depend on the
+//! alphabet of the query, which may contain `N`s.
+//!
+//! Time Complexity: O(L) for inserting or looking up a barcode of length L, and O(L^(k+1)
+//! σ^k) in the worst case for a search with up to k mismatches over an alphabet of size σ.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::data_structures::barcode_trie::BarcodeTrie;
+//!
+//! let whitelist = [b"ACGTAC", b"ACGTTT", b"GGCATC"];
+//! let trie = BarcodeTrie::from_whitelist(&whitelist).unwrap();
+//! assert_eq!(trie.get(b"GGCATC"), Some(2));
+//! assert_eq!(trie.get(b"GGCATA"), None);
+//!
+//! // all barcodes with at most two mismatches, ordered by distance
+//! let hits = trie.find(b"ACGTAT", 2);
+//! assert_eq!(hits.len(), 2);
+//! assert_eq!((hits[0].barcode, hits[0].mismatches), (0, 1));
+//! assert_eq!((hits[1].barcode, hits[1].mismatches), (1, 1));
+//!
+//! // correct a read barcode to the unique closest barcode
+//! assert_eq!(trie.correct(b"GGNATC", 1), Some(2));
+//! assert_eq!(trie.correct(b"ACGTAT", 1), None);
+//! ```
+
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum Error {
+    #[error("barcode of length {len} differs from the barcode length {expected}")]
+    InvalidLength { len: usize, expected: usize },
+}
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// A barcode within the mismatch budget of a query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hit {
+    /// Index of the barcode, in order of insertion.
+    pub barcode: usize,
+    /// Number of mismatches to the query.
+    pub mismatches: usize,
+}
+
+/// A trie over barcodes of equal length.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BarcodeTrie {
+    barcode_len: usize,
+    /// Children of each inner node, sorted by symbol. Below the last level, children refer
+    /// to the barcode index instead of a node.
+    nodes: Vec<Vec<(u8, usize)>>,
+    /// The concatenated barcodes.
+    barcodes: Vec<u8>,
+}
+
+impl BarcodeTrie {
+    /// Create an empty trie for barcodes of the given length.
+    pub fn new(barcode_len: usize) -> Self {
+        BarcodeTrie {
+            barcode_len,
+            nodes: vec![Vec::new()],
+            barcodes: Vec::new(),
+        }
+    }
+
+    /// Build a trie from a whitelist of barcodes of equal length. Barcodes are inserted in
+    /// lexicographical order, such that the children of each node are appended in sorted
+    /// order. Duplicates refer to the index of their first occurrence.
+    pub fn from_whitelist<I, B>(whitelist: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = B>,
+        B: AsRef<[u8]>,
+    {
+        let whitelist: Vec<B> = whitelist.into_iter().collect();
+        let barcode_len = whitelist.first().map_or(0, |b| b.as_ref().len());
+        let mut trie = BarcodeTrie::new(barcode_len);
+        for barcode in &whitelist {
+            let len = barcode.as_ref().len();
+            if len != barcode_len {
+                return Err(Error::InvalidLength {
+                    len,
+                    expected: barcode_len,
+                });
+            }
+            trie.barcodes.extend_from_slice(barcode.as_ref());
+        }
+        let mut order: Vec<usize> = (0..whitelist.len()).collect();
+        order.sort_by_key(|&i| whitelist[i].as_ref());
+        for i in order {
+            trie.insert_at(whitelist[i].as_ref(), i);
+        }
+        Ok(trie)
+    }
+
+    /// Length of the barcodes.
+    pub fn barcode_len(&self) -> usize {
+        self.barcode_len
+    }
+
+    /// Number of barcodes.
+    pub fn len(&self) -> usize {
+        self.barcodes
+            .len()
+            .checked_div(self.barcode_len)
+            .unwrap_or(0)
+    }
+
+    /// Whether there are no barcodes.
+    pub fn is_empty(&self) -> bool {
+        self.barcodes.is_empty()
+    }
+
+    /// The barcode with the given index.
+    pub fn barcode(&self, index: usize) -> &[u8] {
+        &self.barcodes[index * self.barcode_len..(index + 1) * self.barcode_len]
+    }
+
+    fn child(&self, node: usize, a: u8) -> Option<usize> {
+        let children = &self.nodes[node];
+        children
+            .binary_search_by_key(&a, |&(b, _)| b)
+            .ok()
+            .map(|k| children[k].1)
+    }
+
+    /// Insert a barcode and return its index. If the barcode is already present, the index of
+    /// the existing barcode is returned.
+    pub fn insert(&mut self, barcode: &[u8]) -> Result<usize> {
+        if barcode.len() != self.barcode_len {
+            return Err(Error::InvalidLength {
+                len: barcode.len(),
+                expected: self.barcode_len,
+            });
+        }
+        if let Some(index) = self.get(barcode) {
+            return Ok(index);
+        }
+        let index = self.len();
+        self.barcodes.extend_from_slice(barcode);
+        self.insert_at(barcode, index);
+        Ok(index)
+    }
+
+    /// Add the path of a barcode with the given index, unless the barcode is already present.
+    fn insert_at(&mut self, barcode: &[u8], index: usize) {
+        let mut node = 0;
+        for (depth, &a) in barcode.iter().enumerate() {
+            let last = depth + 1 == self.barcode_len;
+            match self.nodes[node].binary_search_by_key(&a, |&(b, _)| b) {
+                Ok(k) => node = self.nodes[node][k].1,
+                Err(k) => {
+                    let child = if last {
+                        index
+                    } else {
+                        self.nodes.push(Vec::new());
+                        self.nodes.len() - 1
+                    };
+                    self.nodes[node].insert(k, (a, child));
+                    node = child;
+                }
+            }
+        }
+    }
+
+    /// The index of the given barcode, if present.
+    pub fn get(&self, barcode: &[u8]) -> Option<usize> {
+        if barcode.len() != self.barcode_len || self.is_empty() {
+            return None;
+        }
+        barcode.iter().try_fold(0, |node, &a| self.child(node, a))
+    }
+
+    /// All barcodes with at most `k` mismatches to the query, ordered by the number of
+    /// mismatches and index.
+    pub fn find(&self, query: &[u8], k: usize) -> Vec<Hit> {
+        let mut hits = Vec::new();
+        if query.len() == self.barcode_len && !self.is_empty() {
+            self.search(query, 0, 0, k, &mut hits);
+        }
+        hits.sort_unstable_by_key(|hit| (hit.mismatches, hit.barcode));
+        hits
+    }
+
+    fn search(&self, query: &[u8], node: usize, mismatches: usize, k: usize, hits: &mut Vec<Hit>) {
+        let depth = self.barcode_len - query.len();
+        let last = depth + 1 == self.barcode_len;
+        for &(a, child) in &self.nodes[node] {
+            let mismatches = mismatches + (a != query[0]) as usize;
+            if mismatches > k {
+                continue;
+            }
+            if last {
+                hits.push(Hit {
+                    barcode: child,
+                    mismatches,
+                });
+            } else {
+                self.search(&query[1..], child, mismatches, k, hits);
+            }
+        }
+    }
+
+    /// The barcode closest to the query, if it has at most `k` mismatches and no other barcode
+    /// is equally close.
+    pub fn correct(&self, query: &[u8], k: usize) -> Option<usize> {
+        match self.find(query, k)[..] {
+            [best] => Some(best.barcode),
+            [best, second, ..] if best.mismatches < second.mismatches => Some(best.barcode),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::random_seq;
+    use rand::prelude::*;
+
+    fn hamming(a: &[u8], b: &[u8]) -> usize {
+        a.iter().zip(b).filter(|(a, b)| a != b).count()
+    }
+
+    #[test]
+    fn test_against_naive() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut whitelist: Vec<Vec<u8>> =
+            (0..300).map(|_| random_seq(&mut rng, b"ACGT", 8)).collect();
+        whitelist.sort_unstable();
+        whitelist.dedup();
+        whitelist.shuffle(&mut rng);
+        let trie = BarcodeTrie::from_whitelist(&whitelist).unwrap();
+        let mut incremental = BarcodeTrie::new(8);
+        for barcode in &whitelist {
+            incremental.insert(barcode).unwrap();
+        }
+        assert_eq!(trie.len(), whitelist.len());
+
+        for _ in 0..200 {
+            let mut query = whitelist.choose(&mut rng).unwrap().clone();
+            for _ in 0..rng.gen_range(0..4) {
+                query[rng.gen_range(0..8)] = *b"ACGTN".choose(&mut rng).unwrap();
+            }
+            for k in 0..4 {
+                let mut expected: Vec<Hit> = whitelist
+                    .iter()
+                    .enumerate()
+                    .map(|(barcode, b)| Hit {
+                        barcode,
+                        mismatches: hamming(b, &query),
+                    })
+                    .filter(|hit| hit.mismatches <= k)
+                    .collect();
+                expected.sort_unstable_by_key(|hit| (hit.mismatches, hit.barcode));
+                assert_eq!(trie.find(&query, k), expected);
+                assert_eq!(incremental.find(&query, k), expected);
+            }
+            let exact = whitelist.iter().position(|b| *b == query);
+            assert_eq!(trie.get(&query), exact);
+        }
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut trie = BarcodeTrie::new(4);
+        assert!(trie.is_empty());
+        assert_eq!(trie.insert(b"ACGT"), Ok(0));
+        assert_eq!(trie.insert(b"ACGA"), Ok(1));
+        assert_eq!(trie.insert(b"ACGT"), Ok(0));
+        assert_eq!(
+            trie.insert(b"ACG"),
+            Err(Error::InvalidLength {
+                len: 3,
+                expected: 4
+            })
+        );
+        assert_eq!(trie.len(), 2);
+        assert_eq!(trie.barcode(1), b"ACGA");
+        assert_eq!(trie.get(b"ACGA"), Some(1));
+        assert!(trie.find(b"ACG", 1).is_empty());
+    }
+}
@@ -25,10 +25,25 @@
 //! assert_eq!(bit.get(2), (2, 2));
 //! assert_eq!(bit.get(3), (4, 3));
 //! assert_eq!(bit.get(4), (4, 3));
+//! ```
+//!
+//! # Example for per-base coverage with range updates
+//!
+//! ```
+//! use bio::data_structures::bit_tree::RangeSumBitTree;
+//!
+//! let mut coverage = RangeSumBitTree::new(100);
+//! for read in &[10..40, 20..50, 35..60] {
+//!     coverage.add(read.clone(), 1);
+//! }
+//! assert_eq!(coverage.get(36), 3);
+//! assert_eq!(coverage.sum(0..100), 85);
+//! assert_eq!(coverage.sum(30..40), 25);
+//! ```
 
 use std::cmp::max;
 use std::marker::PhantomData;
-use std::ops::Add;
+use std::ops::{Add, Range};
 
 /// Fenwick tree prefix operator
 pub trait PrefixOp<T> {
@@ -107,9 +122,100 @@ where
 /// Fenwick tree specialized for prefix-sum
 pub type SumBitTree<T> = FenwickTree<T, SumOp>;
 
+/// A pair of Fenwick trees supporting the addition of a value to a range of positions and
+/// sums over ranges, e.g. for accumulating per-base coverage from aligned reads.
+/// Time Complexity: O(n) to build a new tree or O(log n) for updates and queries,
+/// where `n = tree.len()`.
+pub struct RangeSumBitTree {
+    len: usize,
+    /// Differences of consecutive values.
+    deltas: SumBitTree<i64>,
+    /// Differences of consecutive values, multiplied by their position.
+    weighted: SumBitTree<i64>,
+}
+
+impl RangeSumBitTree {
+    /// Create a new tree over the positions `0..len`, with all values zero.
+    pub fn new(len: usize) -> Self {
+        RangeSumBitTree {
+            len,
+            deltas: SumBitTree::new(len),
+            weighted: SumBitTree::new(len),
+        }
+    }
+
+    /// Number of positions.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether there are no positions.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Add `val` to all positions in the given range.
+    pub fn add(&mut self, range: Range<usize>, val: i64) {
+        assert!(
+            range.start <= range.end && range.end <= self.len,
+            "Range out of bounds."
+        );
+        // positions equal to len are beyond the tree and thereby ignored
+        self.deltas.set(range.start, val);
+        self.deltas.set(range.end, -val);
+        self.weighted.set(range.start, val * range.start as i64);
+        self.weighted.set(range.end, -val * range.end as i64);
+    }
+
+    /// Sum of the values at positions `0..end`.
+    fn prefix_sum(&self, end: usize) -> i64 {
+        if end == 0 {
+            return 0;
+        }
+        self.deltas.get(end - 1) * end as i64 - self.weighted.get(end - 1)
+    }
+
+    /// Value at the given position.
+    pub fn get(&self, pos: usize) -> i64 {
+        self.deltas.get(pos)
+    }
+
+    /// Sum of the values in the given range.
+    pub fn sum(&self, range: Range<usize>) -> i64 {
+        assert!(
+            range.start <= range.end && range.end <= self.len,
+            "Range out of bounds."
+        );
+        self.prefix_sum(range.end) - self.prefix_sum(range.start)
+    }
+}
+
 #[cfg(test)]
 mod test_bit_tree {
-    use super::MaxBitTree;
+    use super::{MaxBitTree, RangeSumBitTree};
+    use rand::prelude::*;
+
+    #[test]
+    fn test_range_sum_bit_tree() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let n = 50;
+        let mut tree = RangeSumBitTree::new(n);
+        let mut values = vec![0i64; n];
+        for _ in 0..300 {
+            let start = rng.gen_range(0..=n);
+            let end = rng.gen_range(start..=n);
+            let val = rng.gen_range(-3..=3);
+            tree.add(start..end, val);
+            values[start..end].iter_mut().for_each(|v| *v += val);
+
+            let start = rng.gen_range(0..=n);
+            let end = rng.gen_range(start..=n);
+            assert_eq!(tree.sum(start..end), values[start..end].iter().sum::<i64>());
+        }
+        for (i, &val) in values.iter().enumerate() {
+            assert_eq!(tree.get(i), val);
+        }
+    }
 
     #[test]
     pub fn test_bit_tree() {
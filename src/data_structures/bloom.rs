@@ -0,0 +1,334 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Bloom filters for approximate set membership, e.g. for filtering k-mers of huge datasets
+//! before exact counting.
+//!
+//! A [`BloomFilter`](struct.BloomFilter.html) stores a set in `m` bits, setting `h` bits per
+//! item. Queries never yield false negatives, and false positives occur with a probability
+//! of about `(1 - e^(-hn/m))^h` after inserting `n` items. Given the expected number of items
+//! and the desired false positive rate, the optimal `m` and `h` are chosen. The `h` bit
+//! positions are derived from two hash values by double hashing (Kirsch and Mitzenmacher,
+//! Less hashing, same performance: building a better Bloom filter, ESA 2006).
+//!
+//! A [`CountingBloomFilter`](struct.CountingBloomFilter.html) stores saturating 8-bit counters
+//! instead of bits. It supports removal, and the minimum of the counters of an item is an
+//! upper bound of its abundance (as in a count-min sketch), e.g. to discard k-mers that occur
+//! only once.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::data_structures::bloom::{BloomFilter, CountingBloomFilter};
+//! use bio::seq::kmers;
+//!
+//! let mut filter = BloomFilter::new(1000, 0.01);
+//! for kmer in kmers::kmers(b"ACGTTGCATGCA", 5) {
+//!     filter.insert(kmer);
+//! }
+//! assert!(filter.contains(b"TTGCA"));
+//!
+//! let mut counts = CountingBloomFilter::new(1000, 0.01);
+//! for kmer in kmers::encoded(b"ACGTACGTACGT", 4) {
+//!     counts.insert(&kmer.canonical());
+//! }
+//! let acgt = kmers::encode(b"ACGT").unwrap();
+//! assert!(counts.count(&acgt) >= 3);
+//! ```
+
+use std::f64::consts::LN_2;
+use std::hash::{Hash, Hasher};
+
+use fxhash::FxHasher64;
+
+/// Mix the bits of a 64-bit value (the finalizer of splitmix64).
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// A well mixed 64-bit hash of the given item, which is stable across runs.
+pub(crate) fn hash_item<T: Hash + ?Sized>(item: &T) -> u64 {
+    let mut hasher = FxHasher64::default();
+    item.hash(&mut hasher);
+    mix(hasher.finish())
+}
+
+/// Positions of the given item in a table of size `m`, by double hashing.
+fn positions<T: Hash + ?Sized>(item: &T, m: usize, h: u32) -> impl Iterator<Item = usize> {
+    let h1 = hash_item(item);
+    let h2 = mix(h1) | 1;
+    (0..h as u64).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % m as u64) as usize)
+}
+
+/// Optimal number of bits and hash functions for `n` items and false positive rate `fp_rate`.
+fn optimal_size(n: usize, fp_rate: f64) -> (usize, u32) {
+    assert!(
+        fp_rate > 0.0 && fp_rate < 1.0,
+        "false positive rate must be in (0, 1)"
+    );
+    let n = n.max(1) as f64;
+    let m = (-n * fp_rate.ln() / (LN_2 * LN_2)).ceil();
+    let h = (m / n * LN_2).round().max(1.0);
+    (m as usize, h as u32)
+}
+
+/// A classic Bloom filter.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BloomFilter {
+    bits: Vec<u64>,
+    m: usize,
+    h: u32,
+}
+
+impl BloomFilter {
+    /// Create a Bloom filter for about `n` items with the given false positive rate.
+    ///
+    /// # Panics
+    ///
+    /// If the false positive rate is not in the open interval (0, 1).
+    pub fn new(n: usize, fp_rate: f64) -> Self {
+        let (m, h) = optimal_size(n, fp_rate);
+        BloomFilter::with_size(m, h)
+    }
+
+    /// Create a Bloom filter with `m` bits and `h` hash functions.
+    ///
+    /// # Panics
+    ///
+    /// If `m` or `h` is zero.
+    pub fn with_size(m: usize, h: u32) -> Self {
+        assert!(m > 0 && h > 0, "size and number of hashes must be positive");
+        BloomFilter {
+            bits: vec![0; (m + 63) >> 6],
+            m,
+            h,
+        }
+    }
+
+    /// Number of bits.
+    pub fn num_bits(&self) -> usize {
+        self.m
+    }
+
+    /// Number of hash functions.
+    pub fn num_hashes(&self) -> u32 {
+        self.h
+    }
+
+    /// Number of set bits.
+    pub fn count_ones(&self) -> usize {
+        self.bits.iter().map(|b| b.count_ones() as usize).sum()
+    }
+
+    /// Insert an item.
+    pub fn insert<T: Hash + ?Sized>(&mut self, item: &T) {
+        for i in positions(item, self.m, self.h) {
+            self.bits[i >> 6] |= 1 << (i & 63);
+        }
+    }
+
+    /// Whether the item may have been inserted. False positives occur with about the
+    /// estimated false positive rate, false negatives do not occur.
+    pub fn contains<T: Hash + ?Sized>(&self, item: &T) -> bool {
+        positions(item, self.m, self.h).all(|i| self.bits[i >> 6] >> (i & 63) & 1 == 1)
+    }
+
+    /// Estimated false positive rate, given the fraction of set bits.
+    pub fn estimated_fp_rate(&self) -> f64 {
+        (self.count_ones() as f64 / self.m as f64).powi(self.h as i32)
+    }
+
+    /// Add all items of the other filter, which must have the same size and number of hashes.
+    ///
+    /// # Panics
+    ///
+    /// If the filters have a different size or number of hashes.
+    pub fn union(&mut self, other: &BloomFilter) {
+        assert!(
+            self.m == other.m && self.h == other.h,
+            "filters must have the same parameters"
+        );
+        for (a, b) in self.bits.iter_mut().zip(&other.bits) {
+            *a |= b;
+        }
+    }
+
+    /// Remove all items.
+    pub fn clear(&mut self) {
+        self.bits.iter_mut().for_each(|b| *b = 0);
+    }
+}
+
+/// A counting Bloom filter with saturating 8-bit counters.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CountingBloomFilter {
+    counters: Vec<u8>,
+    h: u32,
+}
+
+impl CountingBloomFilter {
+    /// Create a counting Bloom filter for about `n` distinct items with the given false
+    /// positive rate.
+    ///
+    /// # Panics
+    ///
+    /// If the false positive rate is not in the open interval (0, 1).
+    pub fn new(n: usize, fp_rate: f64) -> Self {
+        let (m, h) = optimal_size(n, fp_rate);
+        CountingBloomFilter::with_size(m, h)
+    }
+
+    /// Create a counting Bloom filter with `m` counters and `h` hash functions.
+    ///
+    /// # Panics
+    ///
+    /// If `m` or `h` is zero.
+    pub fn with_size(m: usize, h: u32) -> Self {
+        assert!(m > 0 && h > 0, "size and number of hashes must be positive");
+        CountingBloomFilter {
+            counters: vec![0; m],
+            h,
+        }
+    }
+
+    /// Number of counters.
+    pub fn num_counters(&self) -> usize {
+        self.counters.len()
+    }
+
+    /// Number of hash functions.
+    pub fn num_hashes(&self) -> u32 {
+        self.h
+    }
+
+    /// Insert an item, returning its new count.
+    pub fn insert<T: Hash + ?Sized>(&mut self, item: &T) -> u8 {
+        let m = self.counters.len();
+        let mut count = u8::MAX;
+        for i in positions(item, m, self.h) {
+            self.counters[i] = self.counters[i].saturating_add(1);
+            count = count.min(self.counters[i]);
+        }
+        count
+    }
+
+    /// Remove an item, returning false if it was not contained. Counters that reached the
+    /// maximum are not decremented, since their true value is unknown.
+    pub fn remove<T: Hash + ?Sized>(&mut self, item: &T) -> bool {
+        if !self.contains(item) {
+            return false;
+        }
+        let m = self.counters.len();
+        for i in positions(item, m, self.h) {
+            if self.counters[i] < u8::MAX {
+                self.counters[i] -= 1;
+            }
+        }
+        true
+    }
+
+    /// Upper bound of the number of insertions of the item (saturating at 255), i.e. the
+    /// minimum of its counters.
+    pub fn count<T: Hash + ?Sized>(&self, item: &T) -> u8 {
+        positions(item, self.counters.len(), self.h)
+            .map(|i| self.counters[i])
+            .min()
+            .unwrap()
+    }
+
+    /// Whether the item may have been inserted.
+    pub fn contains<T: Hash + ?Sized>(&self, item: &T) -> bool {
+        self.count(item) > 0
+    }
+
+    /// Add the counts of the other filter, which must have the same size and number of hashes.
+    ///
+    /// # Panics
+    ///
+    /// If the filters have a different size or number of hashes.
+    pub fn union(&mut self, other: &CountingBloomFilter) {
+        assert!(
+            self.counters.len() == other.counters.len() && self.h == other.h,
+            "filters must have the same parameters"
+        );
+        for (a, &b) in self.counters.iter_mut().zip(&other.counters) {
+            *a = a.saturating_add(b);
+        }
+    }
+
+    /// The classic Bloom filter of all items with a count of at least `min_count`.
+    pub fn to_bloom_filter(&self, min_count: u8) -> BloomFilter {
+        let mut filter = BloomFilter::with_size(self.counters.len(), self.h);
+        for (i, &c) in self.counters.iter().enumerate() {
+            if c >= min_count.max(1) {
+                filter.bits[i >> 6] |= 1 << (i & 63);
+            }
+        }
+        filter
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_false_negatives_and_fp_rate() {
+        let mut filter = BloomFilter::new(10_000, 0.01);
+        assert_eq!(filter.num_hashes(), 7);
+        for i in 0..10_000u64 {
+            filter.insert(&i);
+        }
+        assert!((0..10_000u64).all(|i| filter.contains(&i)));
+        let fp = (10_000..110_000u64).filter(|i| filter.contains(i)).count();
+        let fp_rate = fp as f64 / 100_000.0;
+        assert!(fp_rate < 0.015, "false positive rate {}", fp_rate);
+        assert!((filter.estimated_fp_rate() - 0.01).abs() < 0.005);
+    }
+
+    #[test]
+    fn test_union() {
+        let mut a = BloomFilter::new(100, 0.01);
+        let mut b = BloomFilter::new(100, 0.01);
+        a.insert("ACGT");
+        b.insert("TTTT");
+        assert!(!a.contains("TTTT"));
+        a.union(&b);
+        assert!(a.contains("ACGT") && a.contains("TTTT"));
+        a.clear();
+        assert_eq!(a.count_ones(), 0);
+    }
+
+    #[test]
+    fn test_counting() {
+        let mut filter = CountingBloomFilter::new(1000, 0.001);
+        for i in 0..1000u64 {
+            for _ in 0..i % 5 {
+                filter.insert(&i);
+            }
+        }
+        for i in 0..1000u64 {
+            assert!(filter.count(&i) as u64 >= i % 5);
+        }
+        let exact = (0..1000u64)
+            .filter(|&i| filter.count(&i) as u64 == i % 5)
+            .count();
+        assert!(exact > 990);
+
+        let solid = filter.to_bloom_filter(2);
+        assert!((0..1000u64)
+            .filter(|i| i % 5 >= 2)
+            .all(|i| solid.contains(&i)));
+
+        let mut other = CountingBloomFilter::new(1000, 0.001);
+        other.insert(&3u64);
+        filter.union(&other);
+        assert!(filter.count(&3u64) >= 4);
+        assert!(filter.remove(&3u64));
+        assert!(filter.count(&3u64) >= 3);
+    }
+}
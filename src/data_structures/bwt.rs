@@ -7,6 +7,7 @@
 //! The implementation is based on the lecture notes
 //! "Algorithmen auf Sequenzen", Kopczynski, Marschall, Martin and Rahmann, 2008 - 2015.
 
+use std::borrow::Borrow;
 use std::iter::repeat;
 
 use crate::alphabets::Alphabet;
@@ -72,20 +73,38 @@ pub fn invert_bwt(bwt: &BWTSlice) -> Vec<u8> {
     inverse
 }
 
+/// Occurrence counts of symbols in prefixes of a BWT, as needed by the FM-index. This is
+/// implemented by the sampled occurrence array [`Occ`](struct.Occ.html) (and anything that
+/// borrows as one), the 2-bit packed [`DnaOcc`](struct.DnaOcc.html) for DNA texts, and
+/// [`WaveletMatrix`](../wavelet_matrix/struct.WaveletMatrix.html).
+pub trait OccCount {
+    /// Get occurrence count of symbol a in BWT[..r+1].
+    fn occ(&self, bwt: &BWTSlice, r: usize, a: u8) -> usize;
+}
+
+impl<T: Borrow<Occ>> OccCount for T {
+    fn occ(&self, bwt: &BWTSlice, r: usize, a: u8) -> usize {
+        self.borrow().get(bwt, r, a)
+    }
+}
+
 /// An occurrence array implementation.
 #[derive(Serialize, Deserialize)]
 pub struct Occ {
     occ: Vec<Vec<usize>>,
     k: u32,
+    /// Column of each symbol in the sampled counts. Occurrence arrays serialized by earlier
+    /// versions lack it and have one column per symbol value instead.
+    #[serde(default)]
+    ranks: Vec<Option<usize>>,
 }
 
 impl Occ {
     /// Calculate occ array with sampling from BWT of length n.
     /// Time complexity: O(n).
-    /// Space complexity: O(n / k * A) with A being the alphabet size.
-    /// Alphabet size is determined on the fly from the BWT.
-    /// For large texts, it is therefore advisable to transform
-    /// the text before calculating the BWT (see alphabets::rank_transform).
+    /// Space complexity: O(n / k * A) with A being the number of distinct symbols of the
+    /// alphabet and the BWT (e.g. 11 for DNA with N, lowercase symbols and the sentinel),
+    /// independent of their values.
     ///
     /// # Arguments
     ///
@@ -93,20 +112,23 @@ impl Occ {
     /// * `k` - the sampling rate: every k-th entry will be stored
     pub fn new(bwt: &BWTSlice, k: u32, alphabet: &Alphabet) -> Self {
         let n = bwt.len();
-        let m = alphabet
-            .max_symbol()
-            .expect("Expecting non-empty alphabet.") as usize
-            + 1;
-        let mut occ = Vec::with_capacity(n / k as usize);
-        let mut curr_occ: Vec<usize> = repeat(0).take(m).collect();
+        let symbols = alphabet.union(&Alphabet::new(bwt));
+        let m = symbols.max_symbol().expect("Expecting non-empty alphabet.") as usize + 1;
+        let mut ranks = vec![None; m];
+        for (rank, c) in symbols.symbols.iter().enumerate() {
+            ranks[c] = Some(rank);
+        }
+
+        let mut occ = Vec::with_capacity(n / k as usize + 1);
+        let mut curr_occ = vec![0; symbols.len()];
         for (i, &c) in bwt.iter().enumerate() {
-            curr_occ[c as usize] += 1;
+            curr_occ[ranks[c as usize].unwrap()] += 1;
             if i % k as usize == 0 {
                 occ.push(curr_occ.clone());
             }
         }
 
-        Occ { occ, k }
+        Occ { occ, k, ranks }
     }
 
     /// Get occurrence count of symbol a in BWT[..r+1].
@@ -122,7 +144,7 @@ impl Occ {
         //     .iter()
         //     .filter(|&&c| c == a)
         //     .count();
-        // self.occ[i][a as usize] + count
+        // self.occ[i][rank] + count
         // ```
         //
         // But there are a couple of reasons to do this manually:
@@ -137,17 +159,24 @@ impl Occ {
         // https://github.com/rust-bio/rust-bio/pull/74
         // https://github.com/rust-bio/rust-bio/pull/76
 
+        // Symbols that are neither part of the alphabet nor of the BWT do not occur.
+        let rank = match self.ranks.get(a as usize) {
+            Some(&Some(rank)) => rank,
+            None if self.ranks.is_empty() && (a as usize) < self.occ[0].len() => a as usize,
+            _ => return 0,
+        };
+
         // self.k is our sampling rate, so find the checkpoints either side of r.
         let lo_checkpoint = r / self.k as usize;
         // Get the occurences at the low checkpoint
-        let lo_occ = self.occ[lo_checkpoint][a as usize];
+        let lo_occ = self.occ[lo_checkpoint][rank];
 
         // If the sampling rate is infrequent it is worth checking if there is a closer
         // hi checkpoint.
         if self.k > 64 {
             let hi_checkpoint = lo_checkpoint + 1;
             if let Some(hi_occs) = self.occ.get(hi_checkpoint) {
-                let hi_occ = hi_occs[a as usize];
+                let hi_occ = hi_occs[rank];
 
                 // Its possible that there are no occurences between the low and high
                 // checkpoint in which case we bail early.
@@ -158,7 +187,6 @@ impl Occ {
                 // If r is closer to the high checkpoint, count backwards from there.
                 let hi_idx = hi_checkpoint * self.k as usize;
                 if (hi_idx - r) < (self.k as usize / 2) {
-                    let hi_occ = hi_occs[a as usize];
                     return hi_occ - bytecount::count(&bwt[r + 1..=hi_idx], a) as usize;
                 }
             }
@@ -170,6 +198,138 @@ impl Occ {
     }
 }
 
+/// Number of bases per sample of the counts in `DnaOcc`.
+const DNA_OCC_BLOCK: usize = 256;
+
+/// Occurrence counts for the BWT of a DNA text over `ACGT` with a sentinel (e.g. an
+/// [FMD text](../fmindex/fn.fmd_text.html)). The BWT is packed into two bits per base, with
+/// counts of each base sampled every 256 bases, i.e. about 3 bits per base in total, while
+/// `Occ` needs one word per symbol at each of its samples.
+/// Counting takes at most eight popcounts of packed words.
+///
+/// # Example
+///
+/// ```
+/// use bio::data_structures::bwt::{bwt, less, DnaOcc};
+/// use bio::data_structures::fmindex::{FMIndex, FMIndexable};
+/// use bio::data_structures::suffix_array::suffix_array;
+/// use bio::alphabets::dna;
+///
+/// let text = b"GCCTTAACATTATTACGCCTA$";
+/// let alphabet = dna::n_alphabet();
+/// let sa = suffix_array(text);
+/// let bwt = bwt(text, &sa);
+/// let less = less(&bwt, &alphabet);
+/// let occ = DnaOcc::new(&bwt);
+/// let fm = FMIndex::new(&bwt, &less, occ);
+///
+/// let interval = fm.backward_search(b"TTA".iter());
+/// let mut positions = interval.occ(&sa);
+/// positions.sort_unstable();
+/// assert_eq!(positions, [3, 9, 12]);
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DnaOcc {
+    /// The BWT with two bits per base, sentinels being stored as `A`.
+    words: Vec<u64>,
+    /// Counts of `A`, `C`, `G` and `T` before each block of 256 bases.
+    counts: Vec<[usize; 4]>,
+    /// Positions of the sentinels in the BWT, in increasing order.
+    sentinels: Vec<usize>,
+    sentinel: Option<u8>,
+}
+
+impl DnaOcc {
+    /// Calculate the packed BWT and the sampled counts from a BWT of length n.
+    /// Complexity: O(n).
+    ///
+    /// # Panics
+    ///
+    /// If the BWT contains symbols other than (uppercase) `ACGT` and a single sentinel symbol.
+    pub fn new(bwt: &BWTSlice) -> Self {
+        let mut words = vec![0u64; bwt.len().div_ceil(32)];
+        let mut counts = Vec::with_capacity(bwt.len() / DNA_OCC_BLOCK + 1);
+        let mut curr_counts = [0; 4];
+        let mut sentinels = Vec::new();
+        let mut sentinel = None;
+        for (i, &c) in bwt.iter().enumerate() {
+            if i % DNA_OCC_BLOCK == 0 {
+                counts.push(curr_counts);
+            }
+            let code = match c {
+                b'A' => 0,
+                b'C' => 1,
+                b'G' => 2,
+                b'T' => 3,
+                _ => {
+                    assert!(
+                        sentinel.is_none() || sentinel == Some(c),
+                        "Expecting BWT over ACGT and a single sentinel symbol."
+                    );
+                    sentinel = Some(c);
+                    sentinels.push(i);
+                    0
+                }
+            };
+            curr_counts[code] += 1;
+            words[i / 32] |= (code as u64) << (2 * (i % 32));
+        }
+        counts.push(curr_counts);
+
+        DnaOcc {
+            words,
+            counts,
+            sentinels,
+            sentinel,
+        }
+    }
+
+    /// Get occurrence count of symbol a in BWT[..r+1].
+    /// Complexity: O(1), or O(log s) for the sentinel with s sentinels.
+    pub fn get(&self, r: usize, a: u8) -> usize {
+        let code = match a {
+            b'A' => 0,
+            b'C' => 1,
+            b'G' => 2,
+            b'T' => 3,
+            _ if Some(a) == self.sentinel => {
+                return self.sentinels.partition_point(|&p| p <= r);
+            }
+            _ => return 0,
+        };
+
+        let end = r + 1;
+        let block = end / DNA_OCC_BLOCK;
+        let mut count = self.counts[block][code];
+        // positions of the matching bases, at the lower bit of each base
+        let pattern = code as u64 * 0x5555_5555_5555_5555;
+        let matches = |word: u64| {
+            let diff = word ^ pattern;
+            !(diff | (diff >> 1)) & 0x5555_5555_5555_5555
+        };
+        let full = end / 32;
+        for &word in &self.words[block * DNA_OCC_BLOCK / 32..full] {
+            count += matches(word).count_ones() as usize;
+        }
+        let rest = end % 32;
+        if rest > 0 {
+            let mask = (1u64 << (2 * rest)) - 1;
+            count += (matches(self.words[full]) & mask).count_ones() as usize;
+        }
+        if code == 0 {
+            // sentinels are stored as A
+            count -= self.sentinels.partition_point(|&p| p <= r);
+        }
+        count
+    }
+}
+
+impl OccCount for DnaOcc {
+    fn occ(&self, _bwt: &BWTSlice, r: usize, a: u8) -> usize {
+        self.get(r, a)
+    }
+}
+
 /// Calculate the less array for a given BWT. Complexity O(n).
 pub fn less(bwt: &BWTSlice, alphabet: &Alphabet) -> Less {
     let m = alphabet
@@ -202,11 +362,14 @@ pub fn bwtfind(bwt: &BWTSlice, alphabet: &Alphabet) -> BWTFind {
 
 #[cfg(test)]
 mod tests {
-    use super::{bwt, bwtfind, invert_bwt, Occ};
+    use super::{bwt, bwtfind, invert_bwt, DnaOcc, Occ};
     use crate::alphabets::dna;
     use crate::alphabets::Alphabet;
+    use crate::data_structures::fmindex::fmd_text;
     use crate::data_structures::suffix_array::suffix_array;
     use crate::data_structures::wavelet_matrix::WaveletMatrix;
+    use crate::utils::testing::random_seq;
+    use rand::prelude::*;
 
     #[test]
     fn test_bwtfind() {
@@ -237,6 +400,45 @@ mod tests {
         assert_eq!(occ.get(&bwt, 4, 3u8), 2);
     }
 
+    #[test]
+    fn test_occ_legacy_layout() {
+        // occurrence arrays serialized by earlier versions have one column per symbol value
+        let bwt = vec![1u8, 3u8, 3u8, 1u8, 2u8, 0u8];
+        let occ = Occ {
+            occ: vec![vec![0, 1, 0, 0], vec![0, 2, 0, 2]],
+            k: 3,
+            ranks: Vec::new(),
+        };
+        assert_eq!(occ.get(&bwt, 4, 2u8), 1);
+        assert_eq!(occ.get(&bwt, 4, 3u8), 2);
+        assert_eq!(occ.get(&bwt, 4, 7u8), 0);
+    }
+
+    #[test]
+    fn test_dna_occ() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let seqs: Vec<Vec<u8>> = [1, 31, 32, 33, 300, 1000]
+            .iter()
+            .map(|&len| random_seq(&mut rng, b"ACGT", len))
+            .collect();
+        let text = fmd_text(seqs.iter());
+        let sa = suffix_array(&text);
+        let bwt = bwt(&text, &sa);
+        let occ = Occ::new(&bwt, 32, &dna::n_alphabet());
+        let dna_occ = DnaOcc::new(&bwt);
+        for &c in b"ACGTN$" {
+            for r in 0..bwt.len() {
+                assert_eq!(dna_occ.get(r, c), occ.get(&bwt, r, c));
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Expecting BWT over ACGT and a single sentinel symbol.")]
+    fn test_dna_occ_invalid() {
+        DnaOcc::new(b"ACN$GT");
+    }
+
     #[test]
     fn test_occwm() {
         let text = b"GCCTTAACATTATTACGCCTA$";
@@ -0,0 +1,365 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A node-centric de Bruijn graph of DNA k-mers with k ≤ 32, built from k-mer counts.
+//!
+//! Nodes are the k-mers, and there is an edge from `x` to `y` if the last `k - 1` bases of
+//! `x` equal the first `k - 1` bases of `y`. Edges are thus implicit, and the successors of a
+//! node are found by looking up its four possible extensions. If the counts are canonical
+//! (see [`KmerCounter`](../kmer_count/struct.KmerCounter.html)), both orientations of each
+//! k-mer are added, such that the graph represents both strands; unitigs are then reported
+//! once per pair of reverse complements, in their lexicographically smaller orientation.
+//!
+//! Sequencing errors cause short dead ends (tips) and short alternative paths (bubbles),
+//! which can be removed before extracting the unitigs, i.e. the maximal non-branching paths.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::data_structures::debruijn::DeBruijnGraph;
+//! use bio::data_structures::kmer_count::KmerCounter;
+//!
+//! let genome = b"ACGGCTAGCTAGGATCGATCGGATCGTAGCTAAGCTAGGCTTAACGGA";
+//! let mut reads: Vec<Vec<u8>> = vec![genome.to_vec(); 3];
+//! // a read with a sequencing error near its end
+//! reads.push(b"GATCGATCGGATCGTAGCTAAGCTCGG".to_vec());
+//!
+//! let counts = KmerCounter::from_seqs(&reads, 11, true, 1);
+//! let mut graph = DeBruijnGraph::from_counts(&counts, 1);
+//! assert_eq!(graph.unitigs().len(), 3);
+//!
+//! graph.clip_tips(11);
+//! let unitigs = graph.unitigs();
+//! assert_eq!(unitigs.len(), 1);
+//! assert_eq!(unitigs[0].seq, genome);
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+use crate::alignment::sparse::HashMapFx;
+use crate::alphabets::dna;
+use crate::data_structures::kmer_count::KmerCounter;
+use crate::seq::kmers;
+
+/// A maximal non-branching path of the graph.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Unitig {
+    /// The sequence spelled by the path.
+    pub seq: Vec<u8>,
+    /// The mean count of the k-mers of the path.
+    pub coverage: f64,
+}
+
+/// A node-centric de Bruijn graph.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeBruijnGraph {
+    k: usize,
+    canonical: bool,
+    mask: u64,
+    /// Encoded k-mers and their counts.
+    nodes: HashMapFx<u64, u32>,
+}
+
+impl DeBruijnGraph {
+    /// Build the graph of all k-mers with a count of at least `min_count`.
+    pub fn from_counts(counts: &KmerCounter, min_count: u32) -> Self {
+        let k = counts.k();
+        let mut graph = DeBruijnGraph {
+            k,
+            canonical: counts.is_canonical(),
+            mask: u64::MAX >> (64 - 2 * k),
+            nodes: HashMapFx::default(),
+        };
+        for (code, count) in counts.iter().filter(|&(_, c)| c >= min_count) {
+            graph.nodes.insert(code, count);
+            if graph.canonical {
+                graph.nodes.insert(kmers::revcomp(code, k), count);
+            }
+        }
+        graph
+    }
+
+    /// The k-mer length.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Whether both orientations of each k-mer are contained.
+    pub fn is_canonical(&self) -> bool {
+        self.canonical
+    }
+
+    /// Number of nodes (counting both orientations of canonical k-mers).
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Whether the graph has no nodes.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Count of the given k-mer, or `None` if it is not a node.
+    pub fn count(&self, kmer: &[u8]) -> Option<u32> {
+        if kmer.len() != self.k {
+            return None;
+        }
+        self.nodes.get(&kmers::encode(kmer)?).copied()
+    }
+
+    /// Encoded successors of the given encoded k-mer.
+    pub fn successors(&self, code: u64) -> impl Iterator<Item = u64> + '_ {
+        let shifted = code << 2 & self.mask;
+        (0..4)
+            .map(move |b| shifted | b)
+            .filter(move |next| self.nodes.contains_key(next))
+    }
+
+    /// Encoded predecessors of the given encoded k-mer.
+    pub fn predecessors(&self, code: u64) -> impl Iterator<Item = u64> + '_ {
+        let shifted = code >> 2;
+        let shift = 2 * (self.k - 1);
+        (0..4)
+            .map(move |b| shifted | b << shift)
+            .filter(move |prev| self.nodes.contains_key(prev))
+    }
+
+    fn unique_successor(&self, code: u64) -> Option<u64> {
+        let mut successors = self.successors(code);
+        let first = successors.next()?;
+        match successors.next() {
+            Some(_) => None,
+            None => Some(first),
+        }
+    }
+
+    fn unique_predecessor(&self, code: u64) -> Option<u64> {
+        let mut predecessors = self.predecessors(code);
+        let first = predecessors.next()?;
+        match predecessors.next() {
+            Some(_) => None,
+            None => Some(first),
+        }
+    }
+
+    /// The maximal non-branching paths as lists of encoded k-mers, in a deterministic order.
+    fn paths(&self) -> Vec<Vec<u64>> {
+        let mut nodes: Vec<u64> = self.nodes.keys().copied().collect();
+        nodes.sort_unstable();
+        let mut visited = HashSet::new();
+        let mut paths = Vec::new();
+        for &start in &nodes {
+            if visited.contains(&start) {
+                continue;
+            }
+            let mut first = start;
+            while let Some(prev) = self.unique_predecessor(first) {
+                if prev == start || self.unique_successor(prev) != Some(first) {
+                    break;
+                }
+                first = prev;
+            }
+            let mut path = vec![first];
+            visited.insert(first);
+            let mut last = first;
+            while let Some(next) = self.unique_successor(last) {
+                if visited.contains(&next) || self.unique_predecessor(next) != Some(last) {
+                    break;
+                }
+                path.push(next);
+                visited.insert(next);
+                last = next;
+            }
+            paths.push(path);
+        }
+        paths
+    }
+
+    fn coverage(&self, path: &[u64]) -> f64 {
+        path.iter().map(|code| self.nodes[code] as f64).sum::<f64>() / path.len() as f64
+    }
+
+    fn spell(&self, path: &[u64]) -> Vec<u8> {
+        let mut seq = kmers::decode(path[0], self.k);
+        seq.extend(path[1..].iter().map(|&code| kmers::decode(code & 3, 1)[0]));
+        seq
+    }
+
+    fn remove(&mut self, code: u64) {
+        self.nodes.remove(&code);
+        if self.canonical {
+            self.nodes.remove(&kmers::revcomp(code, self.k));
+        }
+    }
+
+    /// Remove tips, i.e. non-branching paths of at most `max_len` k-mers that are attached to
+    /// the rest of the graph at only one end. Returns the number of removed k-mers. As removing
+    /// tips can create new ones, this may be repeated until nothing is removed.
+    pub fn clip_tips(&mut self, max_len: usize) -> usize {
+        let tips: Vec<Vec<u64>> = self
+            .paths()
+            .into_iter()
+            .filter(|path| {
+                let first = path[0];
+                let last = path[path.len() - 1];
+                let dead_start = self.predecessors(first).next().is_none();
+                let dead_end = self.successors(last).next().is_none();
+                path.len() <= max_len && dead_start != dead_end
+            })
+            .collect();
+        let before = self.len();
+        for code in tips.into_iter().flatten() {
+            self.remove(code);
+        }
+        before - self.len()
+    }
+
+    /// Pop bubbles, i.e. groups of non-branching paths of at most `max_len` k-mers that
+    /// leave the same node and enter the same node, keeping only the path with the highest
+    /// coverage. Returns the number of removed k-mers. A bubble caused by a substitution
+    /// consists of paths of `k` k-mers.
+    pub fn pop_bubbles(&mut self, max_len: usize) -> usize {
+        let mut bubbles: HashMap<(u64, u64), Vec<Vec<u64>>> = HashMap::new();
+        for path in self.paths() {
+            if path.len() > max_len {
+                continue;
+            }
+            let prev = self.unique_predecessor(path[0]);
+            let next = self.unique_successor(path[path.len() - 1]);
+            if let (Some(prev), Some(next)) = (prev, next) {
+                bubbles.entry((prev, next)).or_default().push(path);
+            }
+        }
+
+        let mut removed = Vec::new();
+        for (_, mut paths) in bubbles {
+            if paths.len() < 2 {
+                continue;
+            }
+            // ties are broken by the sequence, consistently on both strands
+            let key = |path: &Vec<u64>| {
+                let seq = self.spell(path);
+                let rc = dna::revcomp(&seq);
+                (self.coverage(path), std::cmp::min(seq, rc))
+            };
+            let best = (0..paths.len())
+                .max_by(|&a, &b| key(&paths[a]).partial_cmp(&key(&paths[b])).unwrap())
+                .unwrap();
+            paths.swap_remove(best);
+            removed.extend(paths.into_iter().flatten());
+        }
+        let before = self.len();
+        for code in removed {
+            self.remove(code);
+        }
+        before - self.len()
+    }
+
+    /// The unitigs, i.e. the sequences of the maximal non-branching paths. For canonical
+    /// graphs, only the lexicographically smaller of a unitig and its reverse complement is
+    /// reported.
+    pub fn unitigs(&self) -> Vec<Unitig> {
+        let mut seen = HashSet::new();
+        let mut unitigs = Vec::new();
+        for path in self.paths() {
+            let mut seq = self.spell(&path);
+            if self.canonical {
+                seq = std::cmp::min(dna::revcomp(&seq), seq);
+                if !seen.insert(seq.clone()) {
+                    continue;
+                }
+            }
+            unitigs.push(Unitig {
+                coverage: self.coverage(&path),
+                seq,
+            });
+        }
+        unitigs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::random_seq;
+    use rand::prelude::*;
+
+    fn graph(reads: &[Vec<u8>], k: usize, canonical: bool) -> DeBruijnGraph {
+        DeBruijnGraph::from_counts(&KmerCounter::from_seqs(reads, k, canonical, 1), 1)
+    }
+
+    #[test]
+    fn test_linear() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let genome = random_seq(&mut rng, b"ACGT", 1000);
+        // overlapping reads from both strands
+        let reads: Vec<Vec<u8>> = (0..19)
+            .map(|i| {
+                let read = genome[i * 50..i * 50 + 100].to_vec();
+                if i % 2 == 0 {
+                    read
+                } else {
+                    dna::revcomp(&read)
+                }
+            })
+            .collect();
+        let graph = graph(&reads, 21, true);
+        assert_eq!(graph.len(), 2 * (1000 - 21 + 1));
+        let unitigs = graph.unitigs();
+        assert_eq!(unitigs.len(), 1);
+        assert!(unitigs[0].seq == genome || unitigs[0].seq == dna::revcomp(&genome));
+
+        let forward = self::graph(std::slice::from_ref(&genome), 21, false);
+        let unitigs = forward.unitigs();
+        assert_eq!(unitigs.len(), 1);
+        assert_eq!(unitigs[0].seq, genome);
+        assert_eq!(unitigs[0].coverage, 1.0);
+    }
+
+    #[test]
+    fn test_branches_and_cycles() {
+        // two sequences sharing a middle part, and a cycle
+        let reads = vec![
+            b"AAAAACCGTTGCATG".to_vec(),
+            b"TTTTTCCGTTGCAGC".to_vec(),
+            b"GATTACAGATTACA".to_vec(),
+        ];
+        let graph = graph(&reads, 5, false);
+        let mut unitigs: Vec<Vec<u8>> = graph.unitigs().into_iter().map(|u| u.seq).collect();
+        unitigs.sort();
+        // AAAAA and TTTTT have self loops
+        let expected: Vec<&[u8]> = vec![
+            b"AAAAA",
+            b"AAAACCGT",
+            b"CAGATTACAGA",
+            b"CCGTTGCA",
+            b"TGCAGC",
+            b"TGCATG",
+            b"TTTTCCGT",
+            b"TTTTT",
+        ];
+        assert_eq!(unitigs, expected);
+    }
+
+    #[test]
+    fn test_bubble() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let genome = random_seq(&mut rng, b"ACGT", 300);
+        let mut variant = genome.clone();
+        variant[150] = if genome[150] == b'A' { b'C' } else { b'A' };
+        let mut reads = vec![genome.clone(); 5];
+        reads.push(variant);
+        for &canonical in &[false, true] {
+            let mut graph = graph(&reads, 15, canonical);
+            assert_eq!(graph.unitigs().len(), 4);
+            let strands = if canonical { 2 } else { 1 };
+            assert_eq!(graph.pop_bubbles(15), 15 * strands);
+            let unitigs = graph.unitigs();
+            assert_eq!(unitigs.len(), 1);
+            assert!(unitigs[0].seq == genome || unitigs[0].seq == dna::revcomp(&genome));
+            assert_eq!(unitigs[0].coverage, (271.0 * 6.0 + 15.0 * 5.0) / 286.0);
+        }
+    }
+}
@@ -0,0 +1,206 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Disjoint sets (union-find) over the elements `0..n`, with union by rank and path
+//! compression (Tarjan, Efficiency of a good but not linear set union algorithm, JACM 1975).
+//!
+//! Time Complexity: O(α(n)) amortized per operation, where α is the inverse Ackermann function.
+//! Memory Complexity: O(n).
+//!
+//! Disjoint sets yield single-linkage clusters, e.g. of reads sharing k-mers, or the
+//! connected components of a graph, e.g. of an overlap graph.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::data_structures::disjoint_set::DisjointSet;
+//!
+//! // cluster reads that share a 4-mer
+//! let reads: [&[u8]; 4] = [b"ACGTAC", b"GTACCA", b"TTTTGG", b"ACCAAA"];
+//! let mut clusters = DisjointSet::new(reads.len());
+//! for i in 0..reads.len() {
+//!     for j in i + 1..reads.len() {
+//!         if reads[i].windows(4).any(|w| reads[j].windows(4).any(|v| v == w)) {
+//!             clusters.union(i, j);
+//!         }
+//!     }
+//! }
+//! assert_eq!(clusters.num_sets(), 2);
+//! assert!(clusters.same_set(0, 3));
+//! assert_eq!(clusters.sets(), [vec![0, 1, 3], vec![2]]);
+//! ```
+
+/// A partition of the elements `0..n` into disjoint sets.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DisjointSet {
+    parent: Vec<usize>,
+    rank: Vec<u8>,
+    /// Size of the set of each root.
+    size: Vec<usize>,
+    num_sets: usize,
+}
+
+impl DisjointSet {
+    /// Create `n` singleton sets.
+    pub fn new(n: usize) -> Self {
+        DisjointSet {
+            parent: (0..n).collect(),
+            rank: vec![0; n],
+            size: vec![1; n],
+            num_sets: n,
+        }
+    }
+
+    /// Number of elements.
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    /// Whether there are no elements.
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_empty()
+    }
+
+    /// Number of sets.
+    pub fn num_sets(&self) -> usize {
+        self.num_sets
+    }
+
+    /// Add a new element in a singleton set and return it.
+    pub fn add(&mut self) -> usize {
+        let x = self.parent.len();
+        self.parent.push(x);
+        self.rank.push(0);
+        self.size.push(1);
+        self.num_sets += 1;
+        x
+    }
+
+    /// The representative of the set containing `x`. Compresses the path from `x` to it.
+    ///
+    /// # Panics
+    ///
+    /// If `x` is not an element.
+    pub fn find(&mut self, x: usize) -> usize {
+        let root = self.root(x);
+        let mut x = x;
+        while self.parent[x] != root {
+            let next = self.parent[x];
+            self.parent[x] = root;
+            x = next;
+        }
+        root
+    }
+
+    /// The representative of the set containing `x`, without path compression.
+    fn root(&self, mut x: usize) -> usize {
+        while self.parent[x] != x {
+            x = self.parent[x];
+        }
+        x
+    }
+
+    /// Merge the sets containing `x` and `y`. Returns false if they were already in the same
+    /// set.
+    ///
+    /// # Panics
+    ///
+    /// If `x` or `y` is not an element.
+    pub fn union(&mut self, x: usize, y: usize) -> bool {
+        let (mut x, mut y) = (self.find(x), self.find(y));
+        if x == y {
+            return false;
+        }
+        if self.rank[x] < self.rank[y] {
+            std::mem::swap(&mut x, &mut y);
+        }
+        self.parent[y] = x;
+        self.size[x] += self.size[y];
+        if self.rank[x] == self.rank[y] {
+            self.rank[x] += 1;
+        }
+        self.num_sets -= 1;
+        true
+    }
+
+    /// Whether `x` and `y` are in the same set.
+    pub fn same_set(&mut self, x: usize, y: usize) -> bool {
+        self.find(x) == self.find(y)
+    }
+
+    /// Size of the set containing `x`.
+    pub fn set_size(&mut self, x: usize) -> usize {
+        let root = self.find(x);
+        self.size[root]
+    }
+
+    /// The elements of each set in increasing order, with the sets ordered by their smallest
+    /// element.
+    pub fn sets(&self) -> Vec<Vec<usize>> {
+        let mut index = vec![usize::MAX; self.len()];
+        let mut sets: Vec<Vec<usize>> = Vec::with_capacity(self.num_sets);
+        for x in 0..self.len() {
+            let root = self.root(x);
+            if index[root] == usize::MAX {
+                index[root] = sets.len();
+                sets.push(Vec::with_capacity(self.size[root]));
+            }
+            sets[index[root]].push(x);
+        }
+        sets
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+
+    #[test]
+    fn test_against_naive() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let n = 200;
+        let mut sets = DisjointSet::new(n);
+        // naive labels, relabeling one set on each union
+        let mut labels: Vec<usize> = (0..n).collect();
+        for _ in 0..150 {
+            let (x, y) = (rng.gen_range(0..n), rng.gen_range(0..n));
+            let merged = labels[x] != labels[y];
+            assert_eq!(sets.union(x, y), merged);
+            let (from, to) = (labels[y], labels[x]);
+            labels
+                .iter_mut()
+                .filter(|l| **l == from)
+                .for_each(|l| *l = to);
+
+            let x = rng.gen_range(0..n);
+            let size = labels.iter().filter(|&&l| l == labels[x]).count();
+            assert_eq!(sets.set_size(x), size);
+        }
+        for x in 0..n {
+            for y in 0..n {
+                assert_eq!(sets.same_set(x, y), labels[x] == labels[y]);
+            }
+        }
+        let partition = sets.sets();
+        assert_eq!(partition.len(), sets.num_sets());
+        assert_eq!(partition.iter().map(|s| s.len()).sum::<usize>(), n);
+        for set in &partition {
+            assert!(set.iter().all(|&x| labels[x] == labels[set[0]]));
+        }
+    }
+
+    #[test]
+    fn test_add() {
+        let mut sets = DisjointSet::new(0);
+        assert!(sets.is_empty());
+        let a = sets.add();
+        let b = sets.add();
+        assert_eq!(sets.num_sets(), 2);
+        assert!(sets.union(a, b));
+        assert!(!sets.union(b, a));
+        assert_eq!(sets.sets(), [vec![0, 1]]);
+    }
+}
@@ -0,0 +1,217 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Elias–Fano encoding of non-decreasing sequences of integers, e.g. the sorted position
+//! lists of a q-gram or minimizer index (Elias, Efficient storage and retrieval by content
+//! and address of static files, JACM 1974; Fano, On the number of bits required to implement
+//! an associative memory, MIT 1971).
+//!
+//! Each of the `n` values below the universe `u` is split into its `l = ⌊log2(u / n)⌋` lower
+//! bits, which are stored verbatim, and its upper bits, which are stored in unary in a
+//! bitvector of about `2n` bits. In total, this takes at most `2 + ⌈log2(u / n)⌉` bits per
+//! value, instead of 64. Values are accessed by a select query on the upper bits (see
+//! [`RsBitVec`](../rank_select/struct.RsBitVec.html)), and the successor of a value is found
+//! by a select query locating its bucket of upper bits, followed by a short scan.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::data_structures::elias_fano::EliasFano;
+//!
+//! let positions = [3, 4, 7, 13, 14, 15, 21, 43, 1000];
+//! let ef = EliasFano::new(&positions);
+//! assert_eq!(ef.len(), 9);
+//! assert_eq!(ef.get(3), 13);
+//! assert_eq!(ef.successor(16), Some((6, 21)));
+//! assert_eq!(ef.successor(1001), None);
+//! assert!(ef.iter().eq(positions.iter().copied()));
+//! ```
+
+use bv::{BitVec, BitsMut};
+
+use crate::data_structures::rank_select::RsBitVec;
+
+/// An Elias–Fano encoded non-decreasing sequence of integers.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct EliasFano {
+    len: usize,
+    /// Number of lower bits per value.
+    low_bits: u32,
+    /// The lower bits of the values, packed.
+    lows: Vec<u64>,
+    /// The upper bits of the i-th value, `h`, are encoded by a 1-bit at position `h + i`.
+    highs: RsBitVec,
+}
+
+impl EliasFano {
+    /// Encode the given non-decreasing values.
+    ///
+    /// # Panics
+    ///
+    /// If the values are not sorted.
+    pub fn new(values: &[u64]) -> Self {
+        assert!(
+            values.windows(2).all(|w| w[0] <= w[1]),
+            "values must be sorted"
+        );
+        let n = values.len() as u64;
+        let universe = values.last().map_or(0, |&max| max + 1);
+        let low_bits = if n > 0 && universe > n {
+            63 - (universe / n).leading_zeros()
+        } else {
+            0
+        };
+
+        let mut lows = vec![0u64; values.len() * low_bits as usize / 64 + 1];
+        let mut highs: BitVec<u64> = BitVec::new_fill(false, n + (universe >> low_bits) + 1);
+        let mask = (1u64 << low_bits) - 1;
+        for (i, &value) in values.iter().enumerate() {
+            if low_bits > 0 {
+                let low = value & mask;
+                let pos = i * low_bits as usize;
+                lows[pos / 64] |= low << (pos % 64);
+                if pos % 64 + low_bits as usize > 64 {
+                    lows[pos / 64 + 1] |= low >> (64 - pos % 64);
+                }
+            }
+            highs.set_bit((value >> low_bits) + i as u64, true);
+        }
+
+        EliasFano {
+            len: values.len(),
+            low_bits,
+            lows,
+            highs: RsBitVec::new(&highs),
+        }
+    }
+
+    /// Number of values.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether there are no values.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of bits used by the encoding (excluding the rank/select support).
+    pub fn num_bits(&self) -> u64 {
+        self.len as u64 * self.low_bits as u64 + self.highs.len()
+    }
+
+    fn low(&self, i: usize) -> u64 {
+        if self.low_bits == 0 {
+            return 0;
+        }
+        let pos = i * self.low_bits as usize;
+        let mut low = self.lows[pos / 64] >> (pos % 64);
+        if pos % 64 + self.low_bits as usize > 64 {
+            low |= self.lows[pos / 64 + 1] << (64 - pos % 64);
+        }
+        low & ((1u64 << self.low_bits) - 1)
+    }
+
+    /// The value at the given index.
+    ///
+    /// # Panics
+    ///
+    /// If the index is out of bounds.
+    pub fn get(&self, i: usize) -> u64 {
+        assert!(i < self.len, "Index out of bounds.");
+        let high = self.highs.select_1(i as u64 + 1).unwrap() - i as u64;
+        (high << self.low_bits) | self.low(i)
+    }
+
+    /// The index and value of the first value that is at least `x`, if any.
+    pub fn successor(&self, x: u64) -> Option<(usize, u64)> {
+        let bucket = x >> self.low_bits;
+        // the position of the first 1-bit of the bucket, after the bucket-th 0-bit
+        let start = if bucket == 0 {
+            0
+        } else {
+            self.highs.select_0(bucket)? + 1
+        };
+        let first = (start - bucket) as usize;
+        (first..self.len)
+            .map(|i| (i, self.get(i)))
+            .find(|&(_, value)| value >= x)
+    }
+
+    /// Iterate over the values.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            ef: self,
+            index: 0,
+            pos: 0,
+        }
+    }
+}
+
+/// Iterator over the values of an `EliasFano` sequence, decoding the upper bits sequentially.
+pub struct Iter<'a> {
+    ef: &'a EliasFano,
+    index: usize,
+    /// Position in the upper bits.
+    pos: u64,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        if self.index >= self.ef.len {
+            return None;
+        }
+        while !self.ef.highs.get(self.pos) {
+            self.pos += 1;
+        }
+        let high = self.pos - self.index as u64;
+        let value = (high << self.ef.low_bits) | self.ef.low(self.index);
+        self.index += 1;
+        self.pos += 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.ef.len - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a> ExactSizeIterator for Iter<'a> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+
+    #[test]
+    fn test_against_naive() {
+        let mut rng = StdRng::seed_from_u64(42);
+        for &(n, universe) in &[(0, 1), (1, 1), (10, 5), (100, 100), (1000, 1_000_000)] {
+            let mut values: Vec<u64> = (0..n).map(|_| rng.gen_range(0..universe)).collect();
+            values.sort_unstable();
+            let ef = EliasFano::new(&values);
+            assert_eq!(ef.len(), values.len());
+            assert!(ef.iter().eq(values.iter().copied()));
+            for (i, &value) in values.iter().enumerate() {
+                assert_eq!(ef.get(i), value);
+            }
+            for x in (0..universe + 2).step_by((universe as usize / 50).max(1)) {
+                let expected = values.iter().position(|&v| v >= x).map(|i| (i, values[i]));
+                assert_eq!(ef.successor(x), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_space() {
+        let values: Vec<u64> = (0..10_000).map(|i| i * 1000).collect();
+        let ef = EliasFano::new(&values);
+        // 9 lower bits and about 2 upper bits per value
+        assert!(ef.num_bits() < 12 * values.len() as u64);
+    }
+}
@@ -25,6 +25,12 @@
 //! let fm = FMIndex::new(&bwt, &less, &occ);
 //! ```
 //!
+//! Memory usage can be reduced by sampling the occurrence array (every 3rd entry above) and
+//! the suffix array, either in suffix array order (see `SuffixArray::sample`) or in text
+//! order (see `SuffixArray::sample_text_order`), at the cost of slower lookups.
+//! For DNA texts, `bwt::DnaOcc` packs the BWT into two bits per base and can be used
+//! instead of `Occ` (any `bwt::OccCount` implementation is accepted).
+//!
 //! ## Enclose in struct
 //!
 //! `FMIndex` was designed to not forcibly own the BWT and auxiliary data structures.
@@ -58,7 +64,7 @@ use std::borrow::Borrow;
 use std::iter::DoubleEndedIterator;
 
 use crate::alphabets::dna;
-use crate::data_structures::bwt::{Less, Occ, BWT};
+use crate::data_structures::bwt::{Less, OccCount, BWT};
 use crate::data_structures::suffix_array::SuffixArray;
 use std::mem::swap;
 
@@ -142,17 +148,17 @@ pub trait FMIndexable {
 /// The Fast Index in Minute space (FM-Index, Ferragina and Manzini, 2000) for finding suffix array
 /// intervals matching a given pattern.
 #[derive(Serialize, Deserialize)]
-pub struct FMIndex<DBWT: Borrow<BWT>, DLess: Borrow<Less>, DOcc: Borrow<Occ>> {
+pub struct FMIndex<DBWT: Borrow<BWT>, DLess: Borrow<Less>, DOcc: OccCount> {
     bwt: DBWT,
     less: DLess,
     occ: DOcc,
 }
 
-impl<DBWT: Borrow<BWT>, DLess: Borrow<Less>, DOcc: Borrow<Occ>> FMIndexable
+impl<DBWT: Borrow<BWT>, DLess: Borrow<Less>, DOcc: OccCount> FMIndexable
     for FMIndex<DBWT, DLess, DOcc>
 {
     fn occ(&self, r: usize, a: u8) -> usize {
-        self.occ.borrow().get(self.bwt.borrow(), r, a)
+        self.occ.occ(self.bwt.borrow(), r, a)
     }
     fn less(&self, a: u8) -> usize {
         self.less.borrow()[a as usize]
@@ -163,14 +169,14 @@ impl<DBWT: Borrow<BWT>, DLess: Borrow<Less>, DOcc: Borrow<Occ>> FMIndexable
     }
 }
 
-impl<DBWT: Borrow<BWT>, DLess: Borrow<Less>, DOcc: Borrow<Occ>> FMIndex<DBWT, DLess, DOcc> {
+impl<DBWT: Borrow<BWT>, DLess: Borrow<Less>, DOcc: OccCount> FMIndex<DBWT, DLess, DOcc> {
     /// Construct a new instance of the FM index.
     ///
     /// # Arguments
     ///
     /// * `bwt` - the BWT
     /// * `less` - the less array of the BWT
-    /// * `occ` - the occurence array of the BWT
+    /// * `occ` - the occurence counts of the BWT, e.g. an `Occ` or `DnaOcc`
     pub fn new(bwt: DBWT, less: DLess, occ: DOcc) -> Self {
         FMIndex { bwt, less, occ }
     }
@@ -186,6 +192,17 @@ pub struct BiInterval {
 }
 
 impl BiInterval {
+    /// Number of occurrences of the match on both strands, i.e. the size of the forward
+    /// (and reverse complement) suffix array interval.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Length of the match.
+    pub fn match_size(&self) -> usize {
+        self.match_size
+    }
+
     pub fn forward(&self) -> Interval {
         Interval {
             upper: self.lower + self.size,
@@ -209,14 +226,57 @@ impl BiInterval {
     }
 }
 
+/// Concatenate the given DNA texts with their reverse complements, each followed by the
+/// sentinel `$`, i.e. T1$R1$T2$R2$..., which is the text expected by the FMD-index.
+/// As both strands are indexed, a single backward search finds matches on both of them.
+///
+/// # Example
+///
+/// ```
+/// use bio::alphabets::dna;
+/// use bio::data_structures::bwt::{bwt, less, Occ};
+/// use bio::data_structures::fmindex::{fmd_text, FMDIndex, FMIndex, FMIndexable};
+/// use bio::data_structures::suffix_array::suffix_array;
+///
+/// let text = fmd_text(&[&b"GCCTTAACAT"[..], b"TTATTAC"]);
+/// assert_eq!(text, b"GCCTTAACAT$ATGTTAAGGC$TTATTAC$GTAATAA$");
+///
+/// let alphabet = dna::n_alphabet();
+/// let sa = suffix_array(&text);
+/// let bwt = bwt(&text, &sa);
+/// let less = less(&bwt, &alphabet);
+/// let occ = Occ::new(&bwt, 3, &alphabet);
+/// let fmdindex = FMDIndex::from(FMIndex::new(&bwt, &less, &occ));
+///
+/// // GTTA occurs on the reverse strand of the first text
+/// let mut positions = fmdindex.backward_search(b"GTTA".iter()).occ(&sa);
+/// positions.sort_unstable();
+/// assert_eq!(positions, [13]);
+/// ```
+pub fn fmd_text<I, T>(texts: I) -> Vec<u8>
+where
+    I: IntoIterator<Item = T>,
+    T: AsRef<[u8]>,
+{
+    let mut fmd_text = Vec::new();
+    for text in texts {
+        let text = text.as_ref();
+        fmd_text.extend_from_slice(text);
+        fmd_text.push(b'$');
+        fmd_text.extend(dna::revcomp(text));
+        fmd_text.push(b'$');
+    }
+    fmd_text
+}
+
 /// The FMD-Index for linear time search of supermaximal exact matches on forward and reverse
 /// strand of DNA texts (Li, 2012).
 #[derive(Serialize, Deserialize)]
-pub struct FMDIndex<DBWT: Borrow<BWT>, DLess: Borrow<Less>, DOcc: Borrow<Occ>> {
+pub struct FMDIndex<DBWT: Borrow<BWT>, DLess: Borrow<Less>, DOcc: OccCount> {
     fmindex: FMIndex<DBWT, DLess, DOcc>,
 }
 
-impl<DBWT: Borrow<BWT>, DLess: Borrow<Less>, DOcc: Borrow<Occ>> FMIndexable
+impl<DBWT: Borrow<BWT>, DLess: Borrow<Less>, DOcc: OccCount> FMIndexable
     for FMDIndex<DBWT, DLess, DOcc>
 {
     fn occ(&self, r: usize, a: u8) -> usize {
@@ -233,7 +293,7 @@ impl<DBWT: Borrow<BWT>, DLess: Borrow<Less>, DOcc: Borrow<Occ>> FMIndexable
     }
 }
 
-impl<DBWT: Borrow<BWT>, DLess: Borrow<Less>, DOcc: Borrow<Occ>> From<FMIndex<DBWT, DLess, DOcc>>
+impl<DBWT: Borrow<BWT>, DLess: Borrow<Less>, DOcc: OccCount> From<FMIndex<DBWT, DLess, DOcc>>
     for FMDIndex<DBWT, DLess, DOcc>
 {
     /// Construct a new instance of the FMD index (see Heng Li (2012) Bioinformatics).
@@ -242,7 +302,7 @@ impl<DBWT: Borrow<BWT>, DLess: Borrow<Less>, DOcc: Borrow<Occ>> From<FMIndex<DBW
     /// concatenation with its reverse complement, separated by the sentinel symbol `$`.
     /// I.e., let T be the original text and R be its reverse complement.
     /// Then, the expected text is T$R$. Further, multiple concatenated texts are allowed, e.g.
-    /// T1$R1$T2$R2$T3$R3$.
+    /// T1$R1$T2$R2$T3$R3$. Such a text can be obtained with `fmd_text`.
     fn from(fmindex: FMIndex<DBWT, DLess, DOcc>) -> FMDIndex<DBWT, DLess, DOcc> {
         let mut alphabet = dna::n_alphabet();
         alphabet.insert(b'$');
@@ -255,7 +315,7 @@ impl<DBWT: Borrow<BWT>, DLess: Borrow<Less>, DOcc: Borrow<Occ>> From<FMIndex<DBW
     }
 }
 
-impl<DBWT: Borrow<BWT>, DLess: Borrow<Less>, DOcc: Borrow<Occ>> FMDIndex<DBWT, DLess, DOcc> {
+impl<DBWT: Borrow<BWT>, DLess: Borrow<Less>, DOcc: OccCount> FMDIndex<DBWT, DLess, DOcc> {
     /// Find supermaximal exact matches (of length >= l) of given pattern that overlap position i in the pattern.
     /// Complexity O(m) with pattern of length m.
     ///
@@ -694,4 +754,59 @@ mod tests {
             assert_eq!(matches, vec![read_pos]);
         }
     }
+
+    #[test]
+    fn test_all_smems_against_naive() {
+        use crate::utils::testing::random_seq;
+        use rand::prelude::*;
+
+        let occurs = |text: &[u8], p: &[u8]| text.windows(p.len()).filter(|w| w == &p).count();
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let alphabet = dna::n_alphabet();
+        for _ in 0..10 {
+            let forward = random_seq(&mut rng, b"ACGT", 200);
+            let text = fmd_text(&[&forward]);
+
+            let sa = suffix_array(&text);
+            let bwt = bwt(&text, &sa);
+            let less = less(&bwt, &alphabet);
+            let occ = Occ::new(&bwt, 3, &alphabet);
+            let fmdindex = FMDIndex::from(FMIndex::new(&bwt, &less, &occ));
+
+            // a substring of the text with some mismatches
+            let start = rng.gen_range(0..150);
+            let mut pattern = forward[start..start + 40].to_vec();
+            for _ in 0..3 {
+                pattern[rng.gen_range(0..40)] = *b"ACGT".choose(&mut rng).unwrap();
+            }
+
+            // maximal exact matches, which are SMEMs if not contained in another one
+            let m = pattern.len();
+            let mems: Vec<(usize, usize)> = (0..m)
+                .flat_map(|i| (i + 1..=m).map(move |j| (i, j)))
+                .filter(|&(i, j)| {
+                    occurs(&text, &pattern[i..j]) > 0
+                        && (i == 0 || occurs(&text, &pattern[i - 1..j]) == 0)
+                        && (j == m || occurs(&text, &pattern[i..j + 1]) == 0)
+                })
+                .collect();
+            let mut expected: Vec<(usize, usize)> = mems
+                .iter()
+                .filter(|&&(i, j)| !mems.iter().any(|&(k, l)| k <= i && j <= l && l - k > j - i))
+                .map(|&(i, j)| (i, j - i))
+                .collect();
+            expected.sort_unstable();
+
+            let smems = fmdindex.all_smems(&pattern, 1);
+            let mut found: Vec<(usize, usize)> = smems.iter().map(|&(_, i, l)| (i, l)).collect();
+            found.sort_unstable();
+            assert_eq!(found, expected);
+
+            for (interval, i, l) in smems {
+                assert_eq!(interval.match_size(), l);
+                assert_eq!(interval.size(), occurs(&text, &pattern[i..i + l]));
+            }
+        }
+    }
 }
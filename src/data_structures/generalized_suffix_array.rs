@@ -0,0 +1,260 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A suffix array and FM-index over multiple named sequences, e.g. the records of a
+//! multi-FASTA reference. The sequences are concatenated, each followed by the sentinel `$`,
+//! and positions in the concatenation are mapped back to the sequence and offset they belong
+//! to. As the sentinel does not occur in patterns, matches never span two sequences.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::alphabets::dna;
+//! use bio::data_structures::fmindex::FMIndexable;
+//! use bio::data_structures::generalized_suffix_array::Builder;
+//!
+//! let gsa = Builder::new()
+//!     .sequence("chr1", b"GCCTTAACATTATTACGCCTA")
+//!     .sequence("chr2", b"ATTACGG")
+//!     .build();
+//!
+//! let mut hits = gsa.occurrences(b"TTAC");
+//! hits.sort_unstable();
+//! assert_eq!(hits, [("chr1", 12), ("chr2", 1)]);
+//!
+//! // the same via backward search in the FM-index
+//! let fm = gsa.fmindex(&dna::n_alphabet(), 4);
+//! let interval = fm.backward_search(b"TTAC".iter());
+//! let mut hits: Vec<_> = interval
+//!     .occ(gsa.suffix_array())
+//!     .into_iter()
+//!     .map(|pos| gsa.locate(pos).unwrap())
+//!     .collect();
+//! hits.sort_unstable();
+//! assert_eq!(hits, [("chr1", 12), ("chr2", 1)]);
+//! ```
+
+use crate::alphabets::Alphabet;
+use crate::data_structures::bwt::{bwt, less, Less, Occ, BWT};
+use crate::data_structures::fmindex::FMIndex;
+use crate::data_structures::suffix_array::{suffix_array, RawSuffixArray, SuffixArray};
+use crate::io::fasta;
+
+/// The sentinel separating the sequences.
+pub const SENTINEL: u8 = b'$';
+
+/// A builder collecting the sequences to index.
+#[derive(Default, Debug, Clone)]
+pub struct Builder {
+    text: Vec<u8>,
+    ids: Vec<String>,
+    starts: Vec<usize>,
+}
+
+impl Builder {
+    /// Create a new, empty builder.
+    pub fn new() -> Self {
+        Builder::default()
+    }
+
+    /// Add a sequence with the given ID. The sequence may not contain the sentinel `$`.
+    pub fn sequence(mut self, id: &str, seq: &[u8]) -> Self {
+        assert!(
+            !seq.contains(&SENTINEL),
+            "Sequence {} contains the sentinel $.",
+            id
+        );
+        self.ids.push(id.to_owned());
+        self.starts.push(self.text.len());
+        self.text.extend_from_slice(seq);
+        self.text.push(SENTINEL);
+        self
+    }
+
+    /// Add a FASTA record.
+    pub fn record(self, record: &fasta::Record) -> Self {
+        self.sequence(record.id(), record.seq())
+    }
+
+    /// Add all given FASTA records.
+    pub fn records<'a, I: IntoIterator<Item = &'a fasta::Record>>(self, records: I) -> Self {
+        records
+            .into_iter()
+            .fold(self, |builder, record| builder.record(record))
+    }
+
+    /// Build the suffix array of the concatenated sequences.
+    /// Complexity: O(n) with n being the total length of the sequences.
+    pub fn build(mut self) -> GeneralizedSuffixArray {
+        if self.text.is_empty() {
+            self.text.push(SENTINEL);
+        }
+        let pos = suffix_array(&self.text);
+        let bwt = bwt(&self.text, &pos);
+        GeneralizedSuffixArray {
+            text: self.text,
+            ids: self.ids,
+            starts: self.starts,
+            pos,
+            bwt,
+        }
+    }
+}
+
+/// A suffix array over multiple named sequences.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GeneralizedSuffixArray {
+    text: Vec<u8>,
+    ids: Vec<String>,
+    starts: Vec<usize>,
+    pos: RawSuffixArray,
+    bwt: BWT,
+}
+
+impl GeneralizedSuffixArray {
+    /// The concatenated sequences, each followed by the sentinel.
+    pub fn text(&self) -> &[u8] {
+        &self.text
+    }
+
+    /// The suffix array of the concatenated sequences.
+    pub fn suffix_array(&self) -> &RawSuffixArray {
+        &self.pos
+    }
+
+    /// The BWT of the concatenated sequences.
+    pub fn bwt(&self) -> &BWT {
+        &self.bwt
+    }
+
+    /// IDs of the indexed sequences.
+    pub fn ids(&self) -> &[String] {
+        &self.ids
+    }
+
+    /// Number of indexed sequences.
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Whether no sequences are indexed.
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// The sequence with the given index.
+    pub fn seq(&self, i: usize) -> &[u8] {
+        let end = self.starts[..]
+            .get(i + 1)
+            .copied()
+            .unwrap_or(self.text.len())
+            - 1;
+        &self.text[self.starts[i]..end]
+    }
+
+    /// Map a position of the concatenated sequences back to the ID of the sequence and the
+    /// offset within it. Returns `None` for positions of sentinels.
+    /// Complexity: O(log k) with k being the number of sequences.
+    pub fn locate(&self, pos: usize) -> Option<(&str, usize)> {
+        if pos >= self.text.len() || self.text[pos] == SENTINEL {
+            return None;
+        }
+        let i = match self.starts.binary_search(&pos) {
+            Ok(i) => i,
+            Err(i) => i - 1,
+        };
+        Some((&self.ids[i], pos - self.starts[i]))
+    }
+
+    /// Find all occurrences of the given pattern by binary search in the suffix array, as pairs
+    /// of sequence ID and offset, in the order of the suffix array.
+    /// Complexity: O(m log n) with pattern length m.
+    pub fn occurrences(&self, pattern: &[u8]) -> Vec<(&str, usize)> {
+        if pattern.is_empty() {
+            return Vec::new();
+        }
+        self.pos
+            .occurrences(&self.text, pattern)
+            .into_iter()
+            .filter_map(|pos| self.locate(pos))
+            .collect()
+    }
+
+    /// Build an FM-index of the concatenated sequences, with the given alphabet (without the
+    /// sentinel) and sampling rate of the occurrence array.
+    pub fn fmindex(&self, alphabet: &Alphabet, occ_sampling_rate: u32) -> FMIndex<&BWT, Less, Occ> {
+        let less = less(&self.bwt, alphabet);
+        let occ = Occ::new(&self.bwt, occ_sampling_rate, alphabet);
+        FMIndex::new(&self.bwt, less, occ)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabets::dna;
+    use crate::data_structures::fmindex::FMIndexable;
+
+    #[test]
+    fn test_against_naive() {
+        let records = vec![
+            fasta::Record::with_attrs("a", None, b"ACGTACGTTTACG"),
+            fasta::Record::with_attrs("b", None, b""),
+            fasta::Record::with_attrs("c", None, b"TTACGA"),
+            fasta::Record::with_attrs("d", None, b"CG"),
+        ];
+        let gsa = Builder::new().records(&records).build();
+        assert_eq!(gsa.len(), 4);
+        assert_eq!(gsa.text(), b"ACGTACGTTTACG$$TTACGA$CG$");
+        for (i, record) in records.iter().enumerate() {
+            assert_eq!(gsa.seq(i), record.seq());
+        }
+        let fm = gsa.fmindex(&dna::n_alphabet(), 3);
+
+        for pattern in &[
+            &b"ACG"[..],
+            b"CG",
+            b"G",
+            b"TTACG",
+            b"GA",
+            b"CGT",
+            b"GTA",
+            b"GC",
+        ] {
+            let mut expected: Vec<(&str, usize)> = records
+                .iter()
+                .flat_map(|record| {
+                    let seq = record.seq();
+                    (0..seq.len())
+                        .filter(move |&i| seq[i..].starts_with(pattern))
+                        .map(move |i| (record.id(), i))
+                })
+                .collect();
+            expected.sort_unstable();
+
+            let mut hits = gsa.occurrences(pattern);
+            hits.sort_unstable();
+            assert_eq!(hits, expected);
+
+            let mut hits: Vec<(&str, usize)> = fm
+                .backward_search(pattern.iter())
+                .occ(gsa.suffix_array())
+                .into_iter()
+                .map(|pos| gsa.locate(pos).unwrap())
+                .collect();
+            hits.sort_unstable();
+            assert_eq!(hits, expected);
+        }
+        assert_eq!(gsa.locate(13), None);
+        assert_eq!(gsa.locate(15), Some(("c", 0)));
+    }
+
+    #[test]
+    fn test_empty() {
+        let gsa = Builder::new().build();
+        assert!(gsa.is_empty());
+        assert!(gsa.occurrences(b"A").is_empty());
+    }
+}
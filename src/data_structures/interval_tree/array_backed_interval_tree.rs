@@ -31,6 +31,9 @@
 //! assert_eq!(i2.interval().start, 12);
 //! assert_eq!(i2.interval().end, 34);
 //! assert_eq!(i2.data(), &0u32);
+//!
+//! // counting overlaps does not allocate
+//! assert_eq!(tree.count(22..25), 2);
 //! ```
 
 use crate::utils::Interval;
@@ -191,16 +194,69 @@ impl<N: Ord + Clone + Copy, D: Clone> ArrayBackedIntervalTree<N, D> {
         &'a self,
         interval: I,
         results: &'b mut Vec<Entry<'a, N, D>>,
+    ) {
+        results.clear();
+        self.visit_overlaps(interval.into(), |entry| {
+            results.push(Entry {
+                interval: &entry.interval,
+                data: &entry.data,
+            })
+        });
+    }
+
+    /// Count the intervals overlapping the given interval, without collecting them.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - The interval for which overlaps are to be counted. Can also be a `Range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this `IITree` instance has not been indexed yet.
+    pub fn count<I: Into<Interval<N>>>(&self, interval: I) -> usize {
+        let mut count = 0;
+        self.visit_overlaps(interval.into(), |_| count += 1);
+        count
+    }
+
+    /// Number of intervals in the tree.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the tree contains no intervals.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterate over all intervals and their data, ordered by start position.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this `IITree` instance has not been indexed yet.
+    pub fn iter(&self) -> impl Iterator<Item = Entry<'_, N, D>> {
+        if !self.indexed {
+            panic!("This IITree has not been indexed yet. Call `index()` first.")
+        }
+        self.entries.iter().map(|entry| Entry {
+            interval: &entry.interval,
+            data: &entry.data,
+        })
+    }
+
+    /// Call `f` on each entry overlapping the given interval.
+    fn visit_overlaps<'a, F: FnMut(&'a InternalEntry<N, D>)>(
+        &'a self,
+        interval: Interval<N>,
+        mut f: F,
     ) {
         if !self.indexed {
             panic!("This IITree has not been indexed yet. Call `index()` first.")
         }
 
-        let interval = interval.into();
         let (start, end) = (interval.start, interval.end);
         let n = self.entries.len() as usize;
         let a = &self.entries;
-        results.clear();
         let mut stack = [StackCell::empty(); 64];
         // push the root; this is a top down traversal
         stack[0].k = self.max_level;
@@ -214,16 +270,12 @@ impl<N: Ord + Clone + Copy, D: Clone> ArrayBackedIntervalTree<N, D> {
                 // we are in a small subtree; traverse every node in this subtree
                 let i0 = x >> k << k;
                 let i1 = min(i0 + (1 << (k + 1)) - 1, n);
-                for (i, node) in a.iter().enumerate().take(i1).skip(i0) {
+                for node in a.iter().take(i1).skip(i0) {
                     if node.interval.start >= end {
                         break;
                     }
                     if start < node.interval.end {
-                        // if overlap, append to `results`
-                        results.push(Entry {
-                            interval: &self.entries[i].interval,
-                            data: &self.entries[i].data,
-                        });
+                        f(node);
                     }
                 }
             } else if !w {
@@ -243,10 +295,7 @@ impl<N: Ord + Clone + Copy, D: Clone> ArrayBackedIntervalTree<N, D> {
             } else if x < n && a[x].interval.start < end {
                 // need to push the right child
                 if start < a[x].interval.end {
-                    results.push(Entry {
-                        interval: &self.entries[x].interval,
-                        data: &self.entries[x].data,
-                    });
+                    f(&a[x]);
                 }
                 stack[t].k = k - 1;
                 stack[t].x = x + (1 << (k - 1));
@@ -305,5 +354,30 @@ mod tests {
         };
         let expected = vec![e1, e2];
         assert_eq!(overlap, expected);
+        assert_eq!(tree.count(22..25), 2);
+        assert_eq!(tree.count(56..60), 0);
+    }
+
+    #[test]
+    fn test_count_and_iter() {
+        let intervals: Vec<(std::ops::Range<u32>, usize)> = (0..1000)
+            .map(|i| {
+                let start = (i * 7919 % 1000) as u32;
+                (start..start + (i * 31 % 50) as u32, i)
+            })
+            .collect();
+        let tree: ArrayBackedIntervalTree<u32, usize> = intervals.iter().cloned().collect();
+        assert_eq!(tree.len(), 1000);
+        let starts: Vec<u32> = tree.iter().map(|e| e.interval().start).collect();
+        assert!(starts.windows(2).all(|w| w[0] <= w[1]));
+        for start in (0..1100).step_by(13) {
+            let query = start..start + 5;
+            let expected = intervals
+                .iter()
+                .filter(|(r, _)| r.start < query.end && query.start < r.end)
+                .count();
+            assert_eq!(tree.count(query.clone()), expected);
+            assert_eq!(tree.find(query).len(), expected);
+        }
     }
 }
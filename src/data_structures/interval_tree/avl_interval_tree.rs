@@ -67,6 +67,8 @@ impl<'a, N: Ord + Clone + 'a, D: 'a> Entry<'a, N, D> {
 pub struct IntervalTreeIterator<'a, N: Ord + Clone, D> {
     nodes: Vec<&'a Node<N, D>>,
     interval: Interval<N>,
+    // whether the query is the single point `interval.start` (a stabbing query)
+    point: bool,
 }
 
 impl<'a, N: Ord + Clone + 'a, D: 'a> Iterator for IntervalTreeIterator<'a, N, D> {
@@ -87,13 +89,20 @@ impl<'a, N: Ord + Clone + 'a, D: 'a> Iterator for IntervalTreeIterator<'a, N, D>
 
                 // don't traverse right if the query interval is completely before the current
                 // interval
-                if self.interval.end > candidate.interval.start {
+                if self.interval.end > candidate.interval.start
+                    || (self.point && self.interval.start == candidate.interval.start)
+                {
                     if let Some(ref right) = candidate.right {
                         self.nodes.push(right);
                     }
 
                     // overlap is only possible if both tests pass
-                    if intersect(&self.interval, &candidate.interval) {
+                    let hit = if self.point {
+                        contains(&candidate.interval, &self.interval.start)
+                    } else {
+                        intersect(&self.interval, &candidate.interval)
+                    };
+                    if hit {
                         return Some(Entry {
                             data: &candidate.value,
                             interval: &candidate.interval,
@@ -191,14 +200,55 @@ impl<N: Clone + Ord, D> IntervalTree<N, D> {
             Some(ref n) => IntervalTreeIterator {
                 nodes: vec![n],
                 interval,
+                point: false,
             },
             None => {
                 let nodes = vec![];
-                IntervalTreeIterator { nodes, interval }
+                IntervalTreeIterator {
+                    nodes,
+                    interval,
+                    point: false,
+                }
             }
         }
     }
 
+    /// Finds the intervals containing the given point (a stabbing query), i.e. the intervals
+    /// with `start <= point < end`, and returns an `IntervalTreeIterator`
+    ///
+    /// # Example
+    /// ```
+    /// use bio::data_structures::interval_tree::IntervalTree;
+    /// use bio::io::bed;
+    ///
+    /// let features: &[u8] = b"chr1\t100\t200\tgeneA\nchr1\t150\t300\tgeneB\n";
+    /// let tree: IntervalTree<u64, String> = bed::Reader::new(features)
+    ///     .records()
+    ///     .map(|record| {
+    ///         let record = record.unwrap();
+    ///         (record.start()..record.end(), record.name().unwrap().to_owned())
+    ///     })
+    ///     .collect();
+    ///
+    /// // the genes overlapping a variant at position 170
+    /// let mut genes: Vec<&String> = tree.find_point(170).map(|e| e.data()).collect();
+    /// genes.sort();
+    /// assert_eq!(genes, ["geneA", "geneB"]);
+    /// assert_eq!(tree.find_point(200).count(), 1);
+    /// ```
+    pub fn find_point(&self, point: N) -> IntervalTreeIterator<'_, N, D> {
+        let interval = Interval::new(point.clone()..point).unwrap();
+        let nodes = match self.root {
+            Some(ref n) => vec![n],
+            None => vec![],
+        };
+        IntervalTreeIterator {
+            nodes,
+            interval,
+            point: true,
+        }
+    }
+
     /// Uses the provided `Interval` to find overlapping intervals in the tree and returns an
     /// `IntervalTreeIteratorMut` that allows mutable access to the `data`
     pub fn find_mut<I: Into<Interval<N>>>(
@@ -374,6 +424,10 @@ fn intersect<N: Ord + Clone>(range_1: &Interval<N>, range_2: &Interval<N>) -> bo
         && range_1.start < range_2.end
 }
 
+fn contains<N: Ord + Clone>(range: &Interval<N>, point: &N) -> bool {
+    range.start <= *point && *point < range.end
+}
+
 #[cfg(test)]
 mod tests {
     use super::{Entry, IntervalTree, Node};
@@ -588,6 +642,28 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_stabbing() {
+        let mut tree: IntervalTree<i64, usize> = IntervalTree::new();
+        assert_eq!(tree.find_point(5).count(), 0);
+        let mut intervals = vec![];
+        for i in 0..200 {
+            // many intervals share their start
+            let start = (i * 37 % 101) / 3;
+            let end = start + (i * 13 % 17);
+            intervals.push(start..end);
+            tree.insert(start..end, i as usize);
+        }
+        for point in -1..60 {
+            let mut found: Vec<usize> = tree.find_point(point).map(|e| *e.data()).collect();
+            found.sort_unstable();
+            let expected: Vec<usize> = (0..intervals.len())
+                .filter(|&i| intervals[i].contains(&point))
+                .collect();
+            assert_eq!(found, expected);
+        }
+    }
+
     #[test]
     fn zero_width_ranges() {
         let mut tree: IntervalTree<i64, String> = IntervalTree::new();
@@ -0,0 +1,353 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! In-memory counting of DNA k-mers with k ≤ 32.
+//!
+//! k-mers are stored in a hash map keyed by their 2-bit encoding (see
+//! [`seq::kmers`](../../seq/kmers/index.html)), optionally in canonical form such that a
+//! k-mer and its reverse complement are counted together. k-mers containing symbols other
+//! than `ACGT` are skipped. Sequences can be counted with multiple threads, each thread
+//! filling its own table, which are merged at the end.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::data_structures::kmer_count::KmerCounter;
+//! use bio::io::fasta;
+//!
+//! let data: &[u8] = b">a\nACGTACGTT\n>b\nAACGTNACG\n";
+//! let records = fasta::Reader::new(data).records();
+//! let counter = KmerCounter::from_records(records, 3, true, 2).unwrap();
+//!
+//! // ACG and its reverse complement CGT
+//! assert_eq!(counter.get(b"ACG"), 7);
+//! assert_eq!(counter.get(b"CGT"), 7);
+//! assert_eq!(counter.total(), 11);
+//!
+//! // number of distinct canonical k-mers per count
+//! assert_eq!(counter.histogram(), [0, 0, 2, 0, 0, 0, 0, 1]);
+//! let frequent: Vec<Vec<u8>> = counter.above(3).map(|(kmer, _)| kmer).collect();
+//! assert_eq!(frequent, [b"ACG"]);
+//! ```
+
+use std::convert::Infallible;
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::thread;
+
+use crate::alignment::sparse::HashMapFx;
+use crate::io::fasta;
+use crate::seq::kmers;
+
+/// Number of sequences passed to a counting thread at once.
+const BATCH_SIZE: usize = 64;
+
+/// A counter of k-mers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KmerCounter {
+    k: usize,
+    canonical: bool,
+    counts: HashMapFx<u64, u32>,
+}
+
+impl KmerCounter {
+    /// Create an empty counter for k-mers of length `k`. If `canonical` is true, k-mers are
+    /// counted together with their reverse complement.
+    ///
+    /// # Panics
+    ///
+    /// If `k` is zero or larger than [`kmers::MAX_K`](../../seq/kmers/constant.MAX_K.html).
+    pub fn new(k: usize, canonical: bool) -> Self {
+        assert!(
+            k > 0 && k <= kmers::MAX_K,
+            "k must be in 1..={}",
+            kmers::MAX_K
+        );
+        KmerCounter {
+            k,
+            canonical,
+            counts: HashMapFx::default(),
+        }
+    }
+
+    /// Count the k-mers of the given sequences with `threads` threads.
+    pub fn from_seqs<I, S>(seqs: I, k: usize, canonical: bool, threads: usize) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<[u8]> + Send,
+    {
+        let result: Result<Self, Infallible> =
+            Self::count_parallel(seqs.into_iter().map(Ok), k, canonical, threads);
+        result.unwrap()
+    }
+
+    /// Count the k-mers of the given FASTA records with `threads` threads, e.g. from
+    /// [`fasta::Reader::records`](../../io/fasta/struct.Reader.html#method.records).
+    /// Reading stops at the first error, which is returned.
+    pub fn from_records<I, E>(
+        records: I,
+        k: usize,
+        canonical: bool,
+        threads: usize,
+    ) -> Result<Self, E>
+    where
+        I: IntoIterator<Item = Result<fasta::Record, E>>,
+    {
+        Self::count_parallel(
+            records
+                .into_iter()
+                .map(|record| record.map(|r| r.seq().to_owned())),
+            k,
+            canonical,
+            threads,
+        )
+    }
+
+    fn count_parallel<I, S, E>(
+        seqs: I,
+        k: usize,
+        canonical: bool,
+        threads: usize,
+    ) -> Result<Self, E>
+    where
+        I: Iterator<Item = Result<S, E>>,
+        S: AsRef<[u8]> + Send,
+    {
+        let mut counter = KmerCounter::new(k, canonical);
+        if threads <= 1 {
+            for seq in seqs {
+                counter.add_seq(seq?.as_ref());
+            }
+            return Ok(counter);
+        }
+
+        let (sender, receiver) = mpsc::sync_channel::<Vec<S>>(2 * threads);
+        let receiver = Mutex::new(receiver);
+        thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads)
+                .map(|_| {
+                    let receiver = &receiver;
+                    scope.spawn(move || {
+                        let mut local = KmerCounter::new(k, canonical);
+                        loop {
+                            // the lock is released before counting
+                            let batch = receiver.lock().unwrap().recv();
+                            match batch {
+                                Ok(batch) => {
+                                    for seq in batch {
+                                        local.add_seq(seq.as_ref());
+                                    }
+                                }
+                                Err(_) => return local,
+                            }
+                        }
+                    })
+                })
+                .collect();
+
+            let mut result = Ok(());
+            let mut batch = Vec::with_capacity(BATCH_SIZE);
+            for seq in seqs {
+                match seq {
+                    Ok(seq) => batch.push(seq),
+                    Err(e) => {
+                        result = Err(e);
+                        break;
+                    }
+                }
+                if batch.len() == BATCH_SIZE {
+                    sender.send(batch).unwrap();
+                    batch = Vec::with_capacity(BATCH_SIZE);
+                }
+            }
+            if !batch.is_empty() {
+                sender.send(batch).unwrap();
+            }
+            // closing the channel terminates the workers
+            drop(sender);
+
+            for worker in workers {
+                counter.merge(&worker.join().unwrap());
+            }
+            result
+        })?;
+        Ok(counter)
+    }
+
+    /// The k-mer length.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Whether k-mers are counted together with their reverse complement.
+    pub fn is_canonical(&self) -> bool {
+        self.canonical
+    }
+
+    /// Number of distinct k-mers.
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Whether no k-mers have been counted.
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Total number of counted k-mers.
+    pub fn total(&self) -> u64 {
+        self.counts.values().map(|&c| c as u64).sum()
+    }
+
+    fn key(&self, code: u64) -> u64 {
+        if self.canonical {
+            kmers::canonical(code, self.k)
+        } else {
+            code
+        }
+    }
+
+    /// Count the k-mers of the given sequence.
+    pub fn add_seq(&mut self, seq: &[u8]) {
+        for kmer in kmers::encoded(seq, self.k) {
+            let key = if self.canonical {
+                kmer.canonical()
+            } else {
+                kmer.forward
+            };
+            let count = self.counts.entry(key).or_insert(0);
+            *count = count.saturating_add(1);
+        }
+    }
+
+    /// Add the counts of another counter with the same parameters.
+    ///
+    /// # Panics
+    ///
+    /// If the k-mer length or canonicalization of the counters differ.
+    pub fn merge(&mut self, other: &KmerCounter) {
+        assert!(
+            self.k == other.k && self.canonical == other.canonical,
+            "counters must have the same parameters"
+        );
+        for (&key, &count) in &other.counts {
+            let c = self.counts.entry(key).or_insert(0);
+            *c = c.saturating_add(count);
+        }
+    }
+
+    /// Count of the given k-mer (0 if it has not been seen or is not a valid k-mer).
+    pub fn get(&self, kmer: &[u8]) -> u32 {
+        if kmer.len() != self.k {
+            return 0;
+        }
+        kmers::encode(kmer).map_or(0, |code| self.get_encoded(code))
+    }
+
+    /// Count of the given encoded k-mer.
+    pub fn get_encoded(&self, code: u64) -> u32 {
+        self.counts.get(&self.key(code)).copied().unwrap_or(0)
+    }
+
+    /// Iterate over the encoded (canonical) k-mers and their counts, in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = (u64, u32)> + '_ {
+        self.counts.iter().map(|(&code, &count)| (code, count))
+    }
+
+    /// Iterate over the (canonical) k-mers with a count of at least `min_count`, together
+    /// with their counts, sorted lexicographically.
+    pub fn above(&self, min_count: u32) -> impl Iterator<Item = (Vec<u8>, u32)> + '_ {
+        let mut codes: Vec<(u64, u32)> = self.iter().filter(|&(_, c)| c >= min_count).collect();
+        codes.sort_unstable();
+        codes
+            .into_iter()
+            .map(move |(code, count)| (kmers::decode(code, self.k), count))
+    }
+
+    /// Histogram of the counts, i.e. the number of distinct k-mers for each count, starting
+    /// with count 0.
+    pub fn histogram(&self) -> Vec<u64> {
+        let max = self.counts.values().copied().max().unwrap_or(0) as usize;
+        let mut histogram = vec![0; max + 1];
+        for &count in self.counts.values() {
+            histogram[count as usize] += 1;
+        }
+        histogram
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabets::dna;
+    use crate::utils::testing::random_seq;
+    use rand::prelude::*;
+    use std::collections::HashMap;
+
+    fn random_seqs(n: usize) -> Vec<Vec<u8>> {
+        let mut rng = StdRng::seed_from_u64(42);
+        (0..n)
+            .map(|_| {
+                let len = rng.gen_range(0..200);
+                random_seq(&mut rng, b"ACGTN", len)
+            })
+            .collect()
+    }
+
+    fn naive(seqs: &[Vec<u8>], k: usize, canonical: bool) -> HashMap<Vec<u8>, u32> {
+        let mut counts = HashMap::new();
+        for seq in seqs {
+            for kmer in seq.windows(k) {
+                if kmer.contains(&b'N') {
+                    continue;
+                }
+                let kmer = if canonical {
+                    kmers::canonical_seq(kmer)
+                } else {
+                    kmer.to_owned()
+                };
+                *counts.entry(kmer).or_insert(0) += 1;
+            }
+        }
+        counts
+    }
+
+    #[test]
+    fn test_against_naive() {
+        let seqs = random_seqs(500);
+        for &k in &[1, 4, 11] {
+            for &canonical in &[false, true] {
+                let expected = naive(&seqs, k, canonical);
+                for &threads in &[1, 4] {
+                    let counter = KmerCounter::from_seqs(&seqs, k, canonical, threads);
+                    assert_eq!(counter.len(), expected.len());
+                    for (kmer, &count) in &expected {
+                        assert_eq!(counter.get(kmer), count);
+                        if canonical {
+                            assert_eq!(counter.get(&dna::revcomp(kmer)), count);
+                        }
+                    }
+                    let histogram = counter.histogram();
+                    assert_eq!(histogram.iter().sum::<u64>(), expected.len() as u64);
+                    let above: Vec<(Vec<u8>, u32)> = counter.above(3).collect();
+                    let mut frequent: Vec<(Vec<u8>, u32)> = expected
+                        .iter()
+                        .filter(|&(_, &c)| c >= 3)
+                        .map(|(kmer, &c)| (kmer.clone(), c))
+                        .collect();
+                    frequent.sort_unstable();
+                    assert_eq!(above, frequent);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_record_error() {
+        let data: &[u8] = b"ACGT\n>a\nACGT\n";
+        let records = fasta::Reader::new(data).records();
+        assert!(KmerCounter::from_records(records, 2, false, 2).is_err());
+    }
+}
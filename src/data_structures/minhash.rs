@@ -0,0 +1,266 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Bottom-k MinHash sketches of the canonical k-mers of sequences, and estimation of the
+//! Jaccard index and the Mash distance between them (Ondov et al., Mash: fast genome and
+//! metagenome distance estimation using MinHash, Genome Biology, 2016).
+//!
+//! A sketch keeps the `size` smallest distinct hashes of the k-mers, which are a uniform
+//! random sample of the k-mer set. The Jaccard index of two sets is estimated by the fraction
+//! of the smallest `size` hashes of the union of both sketches that occur in both sketches.
+//! The Mash distance `-1/k ln(2j / (1 + j))` converts a Jaccard index `j` into an estimate of
+//! the mutation rate between the sequences. k-mers are hashed with the invertible hash of the
+//! minimizers (see [`minimizers::hash64`](../../seq/minimizers/fn.hash64.html)).
+//!
+//! Sketches can be stored with `io::binary` (requires the `binary` feature).
+//!
+//! # Example
+//!
+//! ```
+//! use bio::data_structures::minhash::Sketch;
+//! use bio::alphabets::dna;
+//!
+//! let a = b"ACGGCTAGCTAGGATCGATCGGATCGTAGCTAGCTAGGCTTAACGGATTACA";
+//! let mut b = a.to_vec();
+//! b[20] = b'T';
+//!
+//! let sketch_a = Sketch::from_seq(a, 11, 100);
+//! let sketch_b = Sketch::from_seq(&b, 11, 100);
+//! let j = sketch_a.jaccard(&sketch_b);
+//! assert!(j > 0.5 && j < 1.0);
+//! assert!(sketch_a.mash_distance(&sketch_b) > 0.0);
+//!
+//! // canonical k-mers make the sketch strand independent
+//! let rc = Sketch::from_seq(&dna::revcomp(&a[..]), 11, 100);
+//! assert_eq!(sketch_a.jaccard(&rc), 1.0);
+//! ```
+
+use std::cmp::Ordering;
+
+use crate::seq::kmers;
+use crate::seq::minimizers::hash64;
+
+/// A bottom-k MinHash sketch of the canonical k-mers of one or more sequences.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Sketch {
+    k: usize,
+    size: usize,
+    /// The smallest distinct hashes, in increasing order.
+    hashes: Vec<u64>,
+}
+
+impl Sketch {
+    /// Create an empty sketch of at most `size` hashes of k-mers of length `k`.
+    ///
+    /// # Panics
+    ///
+    /// If `size` is zero, or `k` is zero or larger than
+    /// [`kmers::MAX_K`](../../seq/kmers/constant.MAX_K.html).
+    pub fn new(k: usize, size: usize) -> Self {
+        assert!(
+            k > 0 && k <= kmers::MAX_K,
+            "k must be in 1..={}",
+            kmers::MAX_K
+        );
+        assert!(size > 0, "sketch size must be positive");
+        Sketch {
+            k,
+            size,
+            hashes: Vec::new(),
+        }
+    }
+
+    /// Sketch the given sequence.
+    pub fn from_seq(seq: &[u8], k: usize, size: usize) -> Self {
+        let mut sketch = Sketch::new(k, size);
+        sketch.add_seq(seq);
+        sketch
+    }
+
+    /// The k-mer length.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// The maximal number of hashes.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The hashes of the sketch, in increasing order.
+    pub fn hashes(&self) -> &[u64] {
+        &self.hashes
+    }
+
+    /// Number of hashes in the sketch.
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    /// Whether the sketch contains no hashes.
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    fn normalize(&mut self) {
+        self.hashes.sort_unstable();
+        self.hashes.dedup();
+        self.hashes.truncate(self.size);
+    }
+
+    /// Add the k-mers of the given sequence. k-mers containing symbols other than `ACGT` are
+    /// skipped.
+    pub fn add_seq(&mut self, seq: &[u8]) {
+        for kmer in kmers::encoded(seq, self.k) {
+            let hash = hash64(kmer.canonical(), self.k);
+            if self.hashes.len() >= self.size && hash > self.hashes[self.size - 1] {
+                continue;
+            }
+            self.hashes.push(hash);
+            if self.hashes.len() >= 2 * self.size {
+                self.normalize();
+            }
+        }
+        self.normalize();
+    }
+
+    fn check_compatible(&self, other: &Sketch) {
+        assert!(
+            self.k == other.k && self.size == other.size,
+            "sketches must have the same k-mer length and size"
+        );
+    }
+
+    /// Merge the other sketch into this one, yielding the sketch of the union of the k-mers.
+    ///
+    /// # Panics
+    ///
+    /// If the sketches differ in k-mer length or size.
+    pub fn merge(&mut self, other: &Sketch) {
+        self.check_compatible(other);
+        self.hashes.extend_from_slice(&other.hashes);
+        self.normalize();
+    }
+
+    /// Estimate the Jaccard index of the k-mer sets of both sketches.
+    ///
+    /// # Panics
+    ///
+    /// If the sketches differ in k-mer length or size.
+    pub fn jaccard(&self, other: &Sketch) -> f64 {
+        self.check_compatible(other);
+        let (mut i, mut j) = (0, 0);
+        let (mut shared, mut union) = (0, 0);
+        // walk the union of both sketches up to its smallest `size` hashes
+        while union < self.size && (i < self.hashes.len() || j < other.hashes.len()) {
+            let ord = match (self.hashes.get(i), other.hashes.get(j)) {
+                (Some(a), Some(b)) => a.cmp(b),
+                (Some(_), None) => Ordering::Less,
+                _ => Ordering::Greater,
+            };
+            match ord {
+                Ordering::Less => i += 1,
+                Ordering::Greater => j += 1,
+                Ordering::Equal => {
+                    shared += 1;
+                    i += 1;
+                    j += 1;
+                }
+            }
+            union += 1;
+        }
+        if union == 0 {
+            return 0.0;
+        }
+        shared as f64 / union as f64
+    }
+
+    /// Estimate the Mash distance between the sequences of both sketches, i.e. the rate of
+    /// point mutations. It is 1 if the sketches share no hashes.
+    ///
+    /// # Panics
+    ///
+    /// If the sketches differ in k-mer length or size.
+    pub fn mash_distance(&self, other: &Sketch) -> f64 {
+        let j = self.jaccard(other);
+        if j == 0.0 {
+            return 1.0;
+        }
+        (-1.0 / self.k as f64 * (2.0 * j / (1.0 + j)).ln()).max(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::random_seq;
+    use rand::prelude::*;
+    use std::collections::HashSet;
+
+    fn kmer_set(seq: &[u8], k: usize) -> HashSet<u64> {
+        kmers::encoded(seq, k)
+            .map(|kmer| kmer.canonical())
+            .collect()
+    }
+
+    #[test]
+    fn test_bottom_k() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let seq = random_seq(&mut rng, b"ACGT", 5000);
+        let sketch = Sketch::from_seq(&seq, 15, 200);
+        let mut expected: Vec<u64> = kmer_set(&seq, 15)
+            .into_iter()
+            .map(|code| hash64(code, 15))
+            .collect();
+        expected.sort_unstable();
+        expected.truncate(200);
+        assert_eq!(sketch.hashes(), &expected[..]);
+
+        // sketching in parts and merging yields the same sketch
+        let mut a = Sketch::from_seq(&seq[..2514], 15, 200);
+        let b = Sketch::from_seq(&seq[2500..], 15, 200);
+        a.merge(&b);
+        assert_eq!(a, sketch);
+    }
+
+    #[test]
+    fn test_jaccard_and_distance() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let a = random_seq(&mut rng, b"ACGT", 20_000);
+        // mutate 1% of the positions
+        let mut b = a.clone();
+        for _ in 0..200 {
+            let i = rng.gen_range(0..b.len());
+            b[i] = *b"ACGT".choose(&mut rng).unwrap();
+        }
+        let k = 21;
+        let (set_a, set_b) = (kmer_set(&a, k), kmer_set(&b, k));
+        let exact = set_a.intersection(&set_b).count() as f64 / set_a.union(&set_b).count() as f64;
+
+        let sa = Sketch::from_seq(&a, k, 1000);
+        let sb = Sketch::from_seq(&b, k, 1000);
+        assert!((sa.jaccard(&sb) - exact).abs() < 0.05);
+        let d = sa.mash_distance(&sb);
+        assert!(d > 0.002 && d < 0.015, "distance {}", d);
+        assert_eq!(sa.mash_distance(&sa), 0.0);
+
+        let c = random_seq(&mut rng, b"ACGT", 20_000);
+        let sc = Sketch::from_seq(&c, k, 1000);
+        assert_eq!(sa.jaccard(&sc), 0.0);
+        assert_eq!(sa.mash_distance(&sc), 1.0);
+    }
+
+    #[cfg(feature = "binary")]
+    #[test]
+    fn test_serialization() {
+        use crate::io::binary;
+
+        let sketch = Sketch::from_seq(b"ACGGCTAGCTAGGATCGATCGGATCG", 5, 10);
+        let mut buffer = Vec::new();
+        binary::write(&mut buffer, &sketch).unwrap();
+        let read: Sketch = binary::read(&buffer[..]).unwrap();
+        assert_eq!(read, sketch);
+    }
+}
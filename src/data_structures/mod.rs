@@ -6,14 +6,28 @@
 //! Various useful data structures.
 
 pub mod annot_map;
+pub mod barcode_trie;
 pub mod bit_tree;
 pub mod bitenc;
+pub mod bloom;
 pub mod bwt;
+pub mod debruijn;
+pub mod disjoint_set;
+pub mod elias_fano;
 pub mod fmindex;
+pub mod generalized_suffix_array;
+pub mod hyperloglog;
 pub mod interpolation_table;
 pub mod interval_tree;
+pub mod kmer_count;
+pub mod minhash;
+pub mod overlap_graph;
 pub mod qgram_index;
 pub mod rank_select;
+pub mod rindex;
+pub mod rmq;
+pub mod segment_tree;
 pub mod smallints;
 pub mod suffix_array;
+pub mod suffix_tree;
 pub mod wavelet_matrix;
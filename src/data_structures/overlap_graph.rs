@@ -0,0 +1,542 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Overlap graphs and string graphs for overlap-layout-consensus assembly (Myers, The
+//! fragment assembly string graph, Bioinformatics, 2005).
+//!
+//! Each read is represented by two vertices, one per orientation (see
+//! [`Vertex`](struct.Vertex.html)). An edge `v -> w` with overlap length `o` states that the
+//! suffix of length `o` of `v` matches the prefix of `w`. Every edge is stored together with
+//! its complement `w' -> v'` on the opposite orientations, such that the graph is the same
+//! when viewed from either strand.
+//!
+//! Overlaps can be added directly or derived from pairwise alignments (e.g. semiglobal or
+//! local alignments from [`alignment::pairwise`](../../alignment/pairwise/index.html)),
+//! which are classified into dovetail overlaps, containments and internal matches. Reads
+//! contained in other reads are removed, since they carry no information for the layout.
+//! The transitive reduction then removes the edges implied by two shorter edges, which turns
+//! the overlap graph into a string graph. Its non-branching paths (see
+//! [`OverlapGraph::compact`](struct.OverlapGraph.html#method.compact)) spell contigs, and the
+//! reads of each path together with their offsets form the layout for computing a consensus,
+//! e.g. with [`alignment::poa`](../../alignment/poa/index.html).
+//!
+//! # Example
+//!
+//! ```
+//! use bio::data_structures::overlap_graph::{OverlapGraph, Vertex};
+//!
+//! let reads: [&[u8]; 3] = [b"ACGTACGGA", b"TACGGATTC", b"GGATTCAAG"];
+//! let mut graph = OverlapGraph::new();
+//! for read in &reads {
+//!     graph.add_read(read.len());
+//! }
+//! let (a, b, c) = (Vertex::forward(0), Vertex::forward(1), Vertex::forward(2));
+//! graph.add_overlap(a, b, 6);
+//! graph.add_overlap(b, c, 6);
+//! graph.add_overlap(a, c, 3);
+//!
+//! // a -> c is implied by a -> b -> c
+//! assert_eq!(graph.transitive_reduction(0), 1);
+//! let paths = graph.compact();
+//! assert_eq!(paths.len(), 1);
+//! assert_eq!(graph.spell(&paths[0], &reads), b"ACGTACGGATTCAAG");
+//! ```
+
+use crate::alignment::Alignment;
+use crate::alphabets::dna;
+
+/// A read in one of its two orientations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Vertex {
+    pub read: usize,
+    pub forward: bool,
+}
+
+impl Vertex {
+    /// The forward orientation of the given read.
+    pub fn forward(read: usize) -> Self {
+        Vertex {
+            read,
+            forward: true,
+        }
+    }
+
+    /// The reverse complementary orientation of the given read.
+    pub fn reverse(read: usize) -> Self {
+        Vertex {
+            read,
+            forward: false,
+        }
+    }
+
+    /// The same read in the opposite orientation.
+    pub fn complement(self) -> Self {
+        Vertex {
+            read: self.read,
+            forward: !self.forward,
+        }
+    }
+
+    fn index(self) -> usize {
+        2 * self.read + !self.forward as usize
+    }
+}
+
+/// An edge of the overlap graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Edge {
+    pub target: Vertex,
+    /// Length of the overlap.
+    pub overlap: usize,
+}
+
+/// The relation of two reads as given by an alignment between them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverlapType {
+    /// The suffix of the first read overlaps the prefix of the second read.
+    Dovetail {
+        from: Vertex,
+        to: Vertex,
+        overlap: usize,
+    },
+    /// The given read is contained in the other one.
+    Contained(Vertex),
+    /// The alignment covers neither end of one of the reads, e.g. due to a repeat.
+    Internal,
+}
+
+/// Classify an alignment of read `x` (the query) against read `y` in the given orientation,
+/// tolerating unaligned overhangs of up to `max_hang` at the read ends.
+pub fn classify(aln: &Alignment, x: Vertex, y: Vertex, max_hang: usize) -> OverlapType {
+    let (x_left, y_left) = (aln.xstart, aln.ystart);
+    let (x_right, y_right) = (aln.xlen - aln.xend, aln.ylen - aln.yend);
+    if x_left.min(y_left) > max_hang || x_right.min(y_right) > max_hang {
+        OverlapType::Internal
+    } else if y_left <= x_left && y_right <= x_right {
+        OverlapType::Contained(y)
+    } else if x_left <= y_left && x_right <= y_right {
+        OverlapType::Contained(x)
+    } else if x_left > y_left {
+        OverlapType::Dovetail {
+            from: x,
+            to: y,
+            overlap: aln.xlen - (x_left - y_left),
+        }
+    } else {
+        OverlapType::Dovetail {
+            from: y,
+            to: x,
+            overlap: aln.ylen - (y_left - x_left),
+        }
+    }
+}
+
+/// A non-branching path of the graph.
+pub type Path = Vec<Vertex>;
+
+/// A bidirected overlap graph of reads.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OverlapGraph {
+    lens: Vec<usize>,
+    contained: Vec<bool>,
+    /// Outgoing edges of each vertex, see `Vertex::index`.
+    edges: Vec<Vec<Edge>>,
+}
+
+impl OverlapGraph {
+    /// Create an empty graph.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a read of the given length and return its index.
+    pub fn add_read(&mut self, len: usize) -> usize {
+        self.lens.push(len);
+        self.contained.push(false);
+        self.edges.push(Vec::new());
+        self.edges.push(Vec::new());
+        self.lens.len() - 1
+    }
+
+    /// Number of reads.
+    pub fn num_reads(&self) -> usize {
+        self.lens.len()
+    }
+
+    /// Length of the given read.
+    pub fn read_len(&self, read: usize) -> usize {
+        self.lens[read]
+    }
+
+    /// Whether the given read has been removed as contained in another read.
+    pub fn is_contained(&self, read: usize) -> bool {
+        self.contained[read]
+    }
+
+    /// Number of edges, counting each edge and its complement once.
+    pub fn num_edges(&self) -> usize {
+        self.edges.iter().map(|e| e.len()).sum::<usize>() / 2
+    }
+
+    /// Outgoing edges of the given vertex.
+    pub fn edges(&self, v: Vertex) -> &[Edge] {
+        &self.edges[v.index()]
+    }
+
+    /// Vertices with an edge to the given vertex, together with the edge.
+    pub fn predecessors(&self, v: Vertex) -> impl Iterator<Item = Edge> + '_ {
+        self.edges(v.complement()).iter().map(|e| Edge {
+            target: e.target.complement(),
+            overlap: e.overlap,
+        })
+    }
+
+    /// Length of the part of `v` that precedes the overlap of the given edge.
+    fn hang(&self, v: Vertex, edge: &Edge) -> usize {
+        self.lens[v.read].saturating_sub(edge.overlap)
+    }
+
+    /// Add an overlap of the suffix of `from` with the prefix of `to`, and its complement.
+    /// Overlaps with contained reads and duplicate overlaps are ignored.
+    ///
+    /// # Panics
+    ///
+    /// If one of the reads has not been added.
+    pub fn add_overlap(&mut self, from: Vertex, to: Vertex, overlap: usize) {
+        if self.contained[from.read]
+            || self.contained[to.read]
+            || self.edges(from).iter().any(|e| e.target == to)
+        {
+            return;
+        }
+        self.edges[from.index()].push(Edge {
+            target: to,
+            overlap,
+        });
+        // an overlap of a read with its own reverse complement is its own complement
+        if to.complement() != from {
+            self.edges[to.complement().index()].push(Edge {
+                target: from.complement(),
+                overlap,
+            });
+        }
+    }
+
+    /// Add the overlap given by an alignment of the forward read `x` (the query) against read
+    /// `y` in the given orientation (see [`classify`](fn.classify.html)). Contained reads
+    /// are removed from the graph. Returns the type of the overlap.
+    pub fn add_alignment(
+        &mut self,
+        aln: &Alignment,
+        x: usize,
+        y: Vertex,
+        max_hang: usize,
+    ) -> OverlapType {
+        let kind = classify(aln, Vertex::forward(x), y, max_hang);
+        match kind {
+            OverlapType::Dovetail { from, to, overlap } => self.add_overlap(from, to, overlap),
+            OverlapType::Contained(v) => self.remove_read(v.read),
+            OverlapType::Internal => (),
+        }
+        kind
+    }
+
+    /// Remove all edges of the given read and mark it as contained.
+    pub fn remove_read(&mut self, read: usize) {
+        self.contained[read] = true;
+        for &v in &[Vertex::forward(read), Vertex::reverse(read)] {
+            self.edges[v.index()].clear();
+        }
+        for edges in &mut self.edges {
+            edges.retain(|e| e.target.read != read);
+        }
+    }
+
+    /// Remove the edge `from -> to` and its complement.
+    fn remove_edge(&mut self, from: Vertex, to: Vertex) {
+        self.edges[from.index()].retain(|e| e.target != to);
+        let from_c = from.complement();
+        self.edges[to.complement().index()].retain(|e| e.target != from_c);
+    }
+
+    /// Remove transitive edges, i.e. edges `v -> x` for which there are edges `v -> w` and
+    /// `w -> x` spanning the same distance up to `fuzz` (Myers 2005). Returns the number of
+    /// removed edges.
+    pub fn transitive_reduction(&mut self, fuzz: usize) -> usize {
+        let mut reducible = Vec::new();
+        for index in 0..self.edges.len() {
+            let v = Vertex {
+                read: index / 2,
+                forward: index % 2 == 0,
+            };
+            let mut edges = self.edges[index].clone();
+            edges.sort_by_key(|e| self.hang(v, e));
+            let longest = match edges.last() {
+                Some(e) => self.hang(v, e) + fuzz,
+                None => continue,
+            };
+            for (i, vw) in edges.iter().enumerate() {
+                let hang_vw = self.hang(v, vw);
+                for wx in self.edges(vw.target) {
+                    let hang = hang_vw + self.hang(vw.target, wx);
+                    if hang > longest {
+                        continue;
+                    }
+                    // an edge v -> x spanning (about) the same distance is transitive
+                    if let Some(vx) = edges[i + 1..].iter().find(|vx| vx.target == wx.target) {
+                        let hang_vx = self.hang(v, vx);
+                        if hang <= hang_vx + fuzz && hang_vx <= hang + fuzz {
+                            reducible.push((v, vx.target));
+                        }
+                    }
+                }
+            }
+        }
+        reducible.sort_unstable();
+        reducible.dedup();
+        let before = self.num_edges();
+        for (v, x) in reducible {
+            self.remove_edge(v, x);
+        }
+        before - self.num_edges()
+    }
+
+    fn in_degree(&self, v: Vertex) -> usize {
+        self.edges(v.complement()).len()
+    }
+
+    fn out_degree(&self, v: Vertex) -> usize {
+        self.edges(v).len()
+    }
+
+    /// The unique successor of `v`, if `v` has exactly one successor which has exactly one
+    /// predecessor.
+    fn unique_successor(&self, v: Vertex) -> Option<Vertex> {
+        if self.out_degree(v) != 1 {
+            return None;
+        }
+        let w = self.edges(v)[0].target;
+        if self.in_degree(w) == 1 && w.read != v.read {
+            Some(w)
+        } else {
+            None
+        }
+    }
+
+    /// Follow unique successors from `start` until a branch or a visited read.
+    fn extend_path(&self, start: Vertex, visited: &mut [bool]) -> Path {
+        let mut path = vec![start];
+        visited[start.read] = true;
+        let mut v = start;
+        while let Some(w) = self.unique_successor(v) {
+            if visited[w.read] {
+                break;
+            }
+            visited[w.read] = true;
+            path.push(w);
+            v = w;
+        }
+        path
+    }
+
+    /// Maximal non-branching paths, each reported in one of its two orientations. Every read
+    /// that is not contained occurs in exactly one path.
+    pub fn compact(&self) -> Vec<Path> {
+        let mut visited = vec![false; self.lens.len()];
+        let mut paths = Vec::new();
+        for read in 0..self.lens.len() {
+            if self.contained[read] || visited[read] {
+                continue;
+            }
+            let v = Vertex::forward(read);
+            // start at vertices that cannot be extended to the left
+            let is_start = self.unique_successor(v.complement()).is_none();
+            let is_end = self.unique_successor(v).is_none();
+            if is_start {
+                paths.push(self.extend_path(v, &mut visited));
+            } else if is_end {
+                paths.push(self.extend_path(v.complement(), &mut visited));
+            }
+        }
+        // the remaining reads lie on cycles
+        for read in 0..self.lens.len() {
+            if !self.contained[read] && !visited[read] {
+                paths.push(self.extend_path(Vertex::forward(read), &mut visited));
+            }
+        }
+        paths
+    }
+
+    /// The reads of the given path with their offsets in the spelled sequence.
+    pub fn layout(&self, path: &[Vertex]) -> Vec<(Vertex, usize)> {
+        let mut offset = 0;
+        let mut layout = Vec::with_capacity(path.len());
+        for (i, &v) in path.iter().enumerate() {
+            layout.push((v, offset));
+            if let Some(&w) = path.get(i + 1) {
+                let edge = self
+                    .edges(v)
+                    .iter()
+                    .find(|e| e.target == w)
+                    .expect("bug: consecutive vertices of a path must be adjacent");
+                offset += self.hang(v, edge);
+            }
+        }
+        layout
+    }
+
+    /// Spell the sequence of the given path from the read sequences, taking each read up to
+    /// the overlap with its successor.
+    pub fn spell<S: AsRef<[u8]>>(&self, path: &[Vertex], reads: &[S]) -> Vec<u8> {
+        let mut seq = Vec::new();
+        let layout = self.layout(path);
+        for (i, &(v, offset)) in layout.iter().enumerate() {
+            let read = reads[v.read].as_ref();
+            let read = if v.forward {
+                read.to_owned()
+            } else {
+                dna::revcomp(read)
+            };
+            let end = match layout.get(i + 1) {
+                Some(&(_, next)) => next - offset,
+                None => read.len(),
+            };
+            seq.extend_from_slice(&read[..end.min(read.len())]);
+        }
+        seq
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alignment::pairwise::Aligner;
+    use crate::utils::testing::random_seq;
+    use rand::prelude::*;
+
+    /// Reads tiling a random genome, every second one reverse complemented, together with
+    /// their exact overlaps.
+    fn tiled_reads(rng: &mut StdRng) -> (Vec<u8>, Vec<Vec<u8>>) {
+        let genome = random_seq(rng, b"ACGT", 606);
+        let reads = (0..24)
+            .map(|i| {
+                let read = genome[i * 22..i * 22 + 100].to_vec();
+                if i % 2 == 1 {
+                    dna::revcomp(&read)
+                } else {
+                    read
+                }
+            })
+            .collect();
+        (genome, reads)
+    }
+
+    fn exact_overlap(a: &[u8], b: &[u8]) -> usize {
+        (20..a.len().min(b.len()))
+            .rev()
+            .find(|&o| a[a.len() - o..] == b[..o])
+            .unwrap_or(0)
+    }
+
+    #[test]
+    fn test_assembly() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let (genome, reads) = tiled_reads(&mut rng);
+        let mut graph = OverlapGraph::new();
+        for read in &reads {
+            graph.add_read(read.len());
+        }
+        for a in 0..reads.len() {
+            for b in a + 1..reads.len() {
+                for &(forward, ref seq) in
+                    &[(true, reads[b].clone()), (false, dna::revcomp(&reads[b]))]
+                {
+                    let y = Vertex { read: b, forward };
+                    let o = exact_overlap(&reads[a], seq);
+                    if o > 0 {
+                        graph.add_overlap(Vertex::forward(a), y, o);
+                    }
+                    let o = exact_overlap(seq, &reads[a]);
+                    if o > 0 {
+                        graph.add_overlap(y, Vertex::forward(a), o);
+                    }
+                }
+            }
+        }
+        assert!(graph.num_edges() > reads.len());
+        graph.transitive_reduction(0);
+        assert_eq!(graph.num_edges(), reads.len() - 1);
+
+        let paths = graph.compact();
+        assert_eq!(paths.len(), 1);
+        let contig = graph.spell(&paths[0], &reads);
+        assert!(contig == genome || contig == dna::revcomp(&genome));
+        let layout = graph.layout(&paths[0]);
+        assert_eq!(layout.len(), reads.len());
+        assert!(layout
+            .iter()
+            .all(|&(v, offset)| offset % 22 == 0 && v.read < reads.len()));
+    }
+
+    #[test]
+    fn test_alignments() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let genome = random_seq(&mut rng, b"ACGT", 200);
+        // a read sharing only an internal segment with the first read, e.g. a repeat
+        let repeat = [
+            random_seq(&mut rng, b"ACGT", 30),
+            genome[40..80].to_vec(),
+            random_seq(&mut rng, b"ACGT", 30),
+        ]
+        .concat();
+        let reads = [
+            &genome[..120],
+            &genome[80..200],
+            &genome[90..150],
+            &repeat[..],
+        ];
+        let score = |a: u8, b: u8| if a == b { 1i32 } else { -3i32 };
+        let mut aligner = Aligner::new(-5, -1, &score);
+        let mut graph = OverlapGraph::new();
+        for read in &reads {
+            graph.add_read(read.len());
+        }
+
+        let aln = aligner.local(reads[1], reads[0]);
+        assert_eq!(
+            graph.add_alignment(&aln, 1, Vertex::forward(0), 5),
+            OverlapType::Dovetail {
+                from: Vertex::forward(0),
+                to: Vertex::forward(1),
+                overlap: 40
+            }
+        );
+        let aln = aligner.local(reads[3], reads[0]);
+        assert_eq!(
+            graph.add_alignment(&aln, 3, Vertex::forward(0), 5),
+            OverlapType::Internal
+        );
+        let rc = dna::revcomp(reads[1]);
+        let aln = aligner.local(reads[2], &rc);
+        assert_eq!(
+            graph.add_alignment(&aln, 2, Vertex::reverse(1), 5),
+            OverlapType::Internal
+        );
+        let aln = aligner.local(reads[2], reads[1]);
+        assert_eq!(
+            graph.add_alignment(&aln, 2, Vertex::forward(1), 5),
+            OverlapType::Contained(Vertex::forward(2))
+        );
+        assert!(graph.is_contained(2));
+        assert_eq!(graph.num_edges(), 1);
+        assert_eq!(
+            graph.predecessors(Vertex::forward(1)).collect::<Vec<_>>(),
+            [Edge {
+                target: Vertex::forward(0),
+                overlap: 40
+            }]
+        );
+    }
+}
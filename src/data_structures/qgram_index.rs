@@ -189,6 +189,42 @@ impl QGramIndex {
 
         matches
     }
+
+    /// Return the exact matches of the given pattern as anchors `(text position, pattern
+    /// position, length)`, sorted by their positions, as they are expected by the chaining
+    /// functions of `alignment::sparse`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::alignment::sparse::chain_anchors;
+    /// use bio::alphabets::dna;
+    /// use bio::data_structures::qgram_index::QGramIndex;
+    ///
+    /// let text = b"ACGGCTGAGATGATTACGAT";
+    /// let qgram_index = QGramIndex::new(3, text, &dna::alphabet());
+    ///
+    /// let pattern = b"GCTGAGTTTGATTAC";
+    /// let anchors = qgram_index.anchors(pattern);
+    /// let chain = chain_anchors(&anchors);
+    /// let chained: Vec<_> = chain.path.iter().map(|&i| anchors[i]).collect();
+    /// assert_eq!(chained, [(3, 0, 6), (10, 8, 7)]);
+    /// ```
+    pub fn anchors(&self, pattern: &[u8]) -> Vec<(u32, u32, u32)> {
+        let mut anchors: Vec<(u32, u32, u32)> = self
+            .exact_matches(pattern)
+            .into_iter()
+            .map(|m| {
+                (
+                    m.text.start as u32,
+                    m.pattern.start as u32,
+                    (m.text.stop - m.text.start) as u32,
+                )
+            })
+            .collect();
+        anchors.sort_unstable();
+        anchors
+    }
 }
 
 /// An interval, consisting of start and stop position (the latter exclusive).
@@ -300,6 +336,23 @@ mod tests {
         assert!(!exact_matches.is_empty());
     }
 
+    #[test]
+    fn test_anchors() {
+        let (text, alphabet) = setup();
+        let qgram_index = QGramIndex::new(3, text, &alphabet);
+
+        let pattern = b"TGAGATCGGCTG";
+        let anchors = qgram_index.anchors(pattern);
+        for w in anchors.windows(2) {
+            assert!(w[0] < w[1]);
+        }
+        for &(x, y, len) in &anchors {
+            let (x, y, len) = (x as usize, y as usize, len as usize);
+            assert_eq!(text[x..x + len], pattern[y..y + len]);
+        }
+        assert!(anchors.contains(&(5, 0, 6)));
+    }
+
     #[test]
     #[cfg(feature = "nightly")]
     fn test_serde() {
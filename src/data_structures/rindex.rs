@@ -0,0 +1,332 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The run-length encoded BWT (RLBWT) and the r-index (Gagie, Navarro and Prezza, Fully
+//! functional suffix trees and optimal text searching in BWT-runs bounded space, JACM 2020)
+//! for counting and locating patterns in space proportional to the number `r` of runs of
+//! equal symbols in the BWT.
+//!
+//! For highly repetitive texts, like collections of genomes of the same species, `r` is
+//! orders of magnitude smaller than the text length. The [`RLBWT`](struct.RLBWT.html)
+//! stores the symbol and start of each run, and answers rank queries by binary search over
+//! the runs. The [`RIndex`](struct.RIndex.html) additionally stores the suffix array values
+//! at the ends and starts of the runs. During the backward search, it maintains the suffix
+//! array value of the last position of the current interval (the toehold), from which all
+//! other occurrences are obtained with the function `φ(SA[i]) = SA[i - 1]`, which can be
+//! evaluated with the sampled values.
+//!
+//! Time Complexity: O(m log r) for counting a pattern of length m, and additionally
+//! O(occ log r) for locating its occ occurrences.
+//! Memory Complexity: O(r).
+//!
+//! # Example
+//!
+//! ```
+//! use bio::data_structures::rindex::RIndex;
+//!
+//! let genome = b"ACGTTGCAGGCTAGCTTAGC".repeat(10);
+//! let text = [&genome[..], b"$"].concat();
+//! let index = RIndex::new(&text);
+//! assert!(index.rlbwt().num_runs() < 30);
+//!
+//! assert_eq!(index.count(b"TTAGCA"), 9);
+//! assert_eq!(index.locate(b"GCAGG")[..3], [5, 25, 45]);
+//! assert!(index.locate(b"AAAA").is_empty());
+//! ```
+
+use crate::data_structures::bwt::{bwt, BWTSlice};
+use crate::data_structures::suffix_array::suffix_array;
+
+/// A run-length encoded BWT.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RLBWT {
+    len: usize,
+    /// Symbol of each run.
+    heads: Vec<u8>,
+    /// Start position of each run.
+    starts: Vec<usize>,
+    /// Indices of the runs of each symbol.
+    symbol_runs: Vec<Vec<usize>>,
+    /// For each symbol, the total length of its first k runs, for each k.
+    symbol_lens: Vec<Vec<usize>>,
+    /// For each symbol, the number of smaller symbols in the BWT.
+    less: Vec<usize>,
+}
+
+impl RLBWT {
+    /// Run-length encode the given BWT.
+    pub fn new(bwt: &BWTSlice) -> Self {
+        let mut heads = Vec::new();
+        let mut starts = Vec::new();
+        let mut symbol_runs = vec![Vec::new(); 256];
+        let mut symbol_lens = vec![vec![0]; 256];
+        let mut counts = vec![0; 256];
+        for (i, &a) in bwt.iter().enumerate() {
+            if heads.last() != Some(&a) {
+                symbol_runs[a as usize].push(heads.len());
+                let total = *symbol_lens[a as usize].last().unwrap();
+                symbol_lens[a as usize].push(total);
+                heads.push(a);
+                starts.push(i);
+            }
+            *symbol_lens[a as usize].last_mut().unwrap() += 1;
+            counts[a as usize] += 1;
+        }
+        let mut less = vec![0; 256];
+        for a in 1..256 {
+            less[a] = less[a - 1] + counts[a - 1];
+        }
+        RLBWT {
+            len: bwt.len(),
+            heads,
+            starts,
+            symbol_runs,
+            symbol_lens,
+            less,
+        }
+    }
+
+    /// Length of the BWT.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the BWT is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of runs.
+    pub fn num_runs(&self) -> usize {
+        self.heads.len()
+    }
+
+    /// Index of the run containing the given position.
+    fn run(&self, pos: usize) -> usize {
+        self.starts.partition_point(|&start| start <= pos) - 1
+    }
+
+    /// Symbol at the given position.
+    pub fn get(&self, pos: usize) -> u8 {
+        assert!(pos < self.len, "Position out of bounds.");
+        self.heads[self.run(pos)]
+    }
+
+    /// Number of runs of `a` before run `i`.
+    fn runs_before(&self, a: u8, i: usize) -> usize {
+        self.symbol_runs[a as usize].partition_point(|&j| j < i)
+    }
+
+    /// Number of occurrences of `a` in `bwt[..pos]`.
+    pub fn rank(&self, a: u8, pos: usize) -> usize {
+        assert!(pos <= self.len, "Position out of bounds.");
+        if pos == 0 {
+            return 0;
+        }
+        let i = self.run(pos - 1);
+        let k = self.runs_before(a, i);
+        let mut rank = self.symbol_lens[a as usize][k];
+        if self.heads[i] == a {
+            rank += pos - self.starts[i];
+        }
+        rank
+    }
+
+    /// Number of symbols in the BWT that are smaller than `a`.
+    pub fn less(&self, a: u8) -> usize {
+        self.less[a as usize]
+    }
+
+    /// The BWT.
+    pub fn decode(&self) -> Vec<u8> {
+        let mut bwt = Vec::with_capacity(self.len);
+        for (i, &a) in self.heads.iter().enumerate() {
+            let end = self.starts.get(i + 1).copied().unwrap_or(self.len);
+            bwt.resize(end, a);
+        }
+        bwt
+    }
+
+    /// The suffix array interval of the given pattern, i.e. the range of suffixes starting with
+    /// it (which is empty if the pattern does not occur).
+    pub fn backward_search(&self, pattern: &[u8]) -> std::ops::Range<usize> {
+        let (mut l, mut r) = (0, self.len);
+        for &a in pattern.iter().rev() {
+            l = self.less(a) + self.rank(a, l);
+            r = self.less(a) + self.rank(a, r);
+            if l >= r {
+                return l..l;
+            }
+        }
+        l..r
+    }
+}
+
+/// The r-index: an RLBWT with suffix array samples at the run boundaries.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RIndex {
+    rlbwt: RLBWT,
+    /// Suffix array value at the last position of each run.
+    run_end_samples: Vec<usize>,
+    /// Suffix array values at the first positions of the runs (except the first run), sorted.
+    phi_keys: Vec<usize>,
+    /// For each key `SA[i]`, the suffix array value `SA[i - 1]`.
+    phi_values: Vec<usize>,
+}
+
+impl RIndex {
+    /// Build the r-index of the given text, which has to end with a unique sentinel (e.g.
+    /// `$`) that is lexicographically smaller than all other symbols. The construction
+    /// temporarily needs the full suffix array.
+    ///
+    /// # Panics
+    ///
+    /// If the text is empty.
+    pub fn new(text: &[u8]) -> Self {
+        assert!(!text.is_empty(), "text must end with a sentinel");
+        let sa = suffix_array(text);
+        let rlbwt = RLBWT::new(&bwt(text, &sa));
+        let n = text.len();
+        let run_end_samples = (0..rlbwt.num_runs())
+            .map(|i| {
+                let end = rlbwt.starts.get(i + 1).copied().unwrap_or(n);
+                sa[end - 1]
+            })
+            .collect();
+        let mut phi: Vec<(usize, usize)> = rlbwt.starts[1..]
+            .iter()
+            .map(|&start| (sa[start], sa[start - 1]))
+            .collect();
+        phi.sort_unstable();
+        let (phi_keys, phi_values) = phi.into_iter().unzip();
+        RIndex {
+            rlbwt,
+            run_end_samples,
+            phi_keys,
+            phi_values,
+        }
+    }
+
+    /// The run-length encoded BWT.
+    pub fn rlbwt(&self) -> &RLBWT {
+        &self.rlbwt
+    }
+
+    /// Number of occurrences of the pattern.
+    pub fn count(&self, pattern: &[u8]) -> usize {
+        self.rlbwt.backward_search(pattern).len()
+    }
+
+    /// `SA[i - 1]` for the position `i` with `SA[i] = pos`.
+    fn phi(&self, pos: usize) -> usize {
+        let k = self.phi_keys.partition_point(|&key| key <= pos) - 1;
+        self.phi_values[k] + (pos - self.phi_keys[k])
+    }
+
+    /// Backward search, additionally yielding the suffix array value of the last position of
+    /// the resulting interval, if it is not empty.
+    fn toehold_search(&self, pattern: &[u8]) -> (usize, Option<usize>) {
+        let rlbwt = &self.rlbwt;
+        let n = rlbwt.len();
+        let (mut l, mut r) = (0, n);
+        let mut toehold = self.run_end_samples[rlbwt.num_runs() - 1];
+        for &a in pattern.iter().rev() {
+            let new_l = rlbwt.less(a) + rlbwt.rank(a, l);
+            let new_r = rlbwt.less(a) + rlbwt.rank(a, r);
+            if new_l >= new_r {
+                return (0, None);
+            }
+            let i = rlbwt.run(r - 1);
+            let sample = if rlbwt.heads[i] == a {
+                toehold
+            } else {
+                // the last occurrence of a in the interval ends a run of a
+                let k = rlbwt.runs_before(a, i);
+                self.run_end_samples[rlbwt.symbol_runs[a as usize][k - 1]]
+            };
+            toehold = if sample == 0 { n - 1 } else { sample - 1 };
+            l = new_l;
+            r = new_r;
+        }
+        (r - l, Some(toehold))
+    }
+
+    /// Positions of all occurrences of the pattern in the text, in increasing order.
+    pub fn locate(&self, pattern: &[u8]) -> Vec<usize> {
+        let (count, toehold) = self.toehold_search(pattern);
+        let mut positions = Vec::with_capacity(count);
+        if let Some(mut pos) = toehold {
+            positions.push(pos);
+            for _ in 1..count {
+                pos = self.phi(pos);
+                positions.push(pos);
+            }
+        }
+        positions.sort_unstable();
+        positions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::random_seq;
+    use rand::prelude::*;
+
+    /// A repetitive text of mutated copies of a random sequence.
+    fn repetitive_text(rng: &mut StdRng) -> Vec<u8> {
+        let base = random_seq(rng, b"ACGT", 300);
+        let mut text = Vec::new();
+        for _ in 0..20 {
+            let mut copy = base.clone();
+            for _ in 0..3 {
+                let i = rng.gen_range(0..copy.len());
+                copy[i] = *b"ACGT".choose(rng).unwrap();
+            }
+            text.extend(copy);
+        }
+        text.push(b'$');
+        text
+    }
+
+    #[test]
+    fn test_rlbwt() {
+        let text = b"GCCTTAACATTATTACGCCTA$";
+        let sa = suffix_array(text);
+        let bwt = bwt(text, &sa);
+        let rlbwt = RLBWT::new(&bwt);
+        assert_eq!(rlbwt.decode(), bwt);
+        for a in b"$ACGT".iter().copied() {
+            for pos in 0..=bwt.len() {
+                let expected = bwt[..pos].iter().filter(|&&b| b == a).count();
+                assert_eq!(rlbwt.rank(a, pos), expected);
+            }
+        }
+        assert!((0..bwt.len()).all(|i| rlbwt.get(i) == bwt[i]));
+    }
+
+    #[test]
+    fn test_against_naive() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let text = repetitive_text(&mut rng);
+        let index = RIndex::new(&text);
+        assert!(index.rlbwt().num_runs() < text.len() / 10);
+        for _ in 0..200 {
+            let len = rng.gen_range(1..12);
+            let start = rng.gen_range(0..text.len() - len);
+            let mut pattern = text[start..start + len].to_vec();
+            if rng.gen_bool(0.2) {
+                pattern[0] = *b"ACGT".choose(&mut rng).unwrap();
+            }
+            let expected: Vec<usize> = (0..text.len() - len)
+                .filter(|&i| text[i..i + len] == pattern[..])
+                .collect();
+            assert_eq!(index.count(&pattern), expected.len());
+            assert_eq!(index.locate(&pattern), expected);
+        }
+        // the empty pattern occurs at every position
+        assert_eq!(index.locate(b"").len(), text.len());
+    }
+}
@@ -0,0 +1,130 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Range minimum queries (RMQ) based on a sparse table
+//! (Bender and Farach-Colton, The LCA problem revisited, LATIN 2000).
+//! For each position and each power of two, the position of the minimum in the range of that
+//! length is precomputed, such that any range is covered by two (overlapping) precomputed ranges.
+//!
+//! Time Complexity: O(n log n) for construction and O(1) per query.
+//! Memory Complexity: O(n log n) where `n` is the number of values.
+//!
+//! Over an LCP array, range minimum queries yield the longest common prefix of any two suffixes
+//! (see `suffix_array::LCE`) and the child intervals of lcp-intervals, i.e. the nodes of the
+//! emulated suffix tree.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::data_structures::rmq::RMQ;
+//!
+//! let rmq = RMQ::new(vec![5, 2, 4, 7, 2, 1, 3]);
+//! assert_eq!(rmq.argmin(0..4), Some(1));
+//! assert_eq!(rmq.min(2..5), Some(&2));
+//! assert_eq!(rmq.argmin(6..7), Some(6));
+//! assert_eq!(rmq.argmin(3..3), None);
+//! ```
+
+use std::ops::Range;
+
+/// A sparse table for range minimum queries.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RMQ<T> {
+    values: Vec<T>,
+    /// `table[k][i]` is the position of the (leftmost) minimum of `values[i..i + 2^(k + 1)]`.
+    table: Vec<Vec<usize>>,
+}
+
+impl<T: Ord> RMQ<T> {
+    /// Build the sparse table for the given values.
+    pub fn new(values: Vec<T>) -> Self {
+        let n = values.len();
+        let mut table: Vec<Vec<usize>> = Vec::new();
+        let mut len = 2;
+        while len <= n {
+            let half = len / 2;
+            let row = (0..=n - len)
+                .map(|i| {
+                    let (a, b) = match table.last() {
+                        Some(prev) => (prev[i], prev[i + half]),
+                        None => (i, i + 1),
+                    };
+                    if values[b] < values[a] {
+                        b
+                    } else {
+                        a
+                    }
+                })
+                .collect();
+            table.push(row);
+            len *= 2;
+        }
+
+        RMQ { values, table }
+    }
+
+    /// Number of values.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Whether there are no values.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// The values the table was built for.
+    pub fn values(&self) -> &[T] {
+        &self.values
+    }
+
+    /// Position of the minimum in the given range (the leftmost one in case of ties), or `None`
+    /// if the range is empty.
+    pub fn argmin(&self, range: Range<usize>) -> Option<usize> {
+        assert!(range.end <= self.values.len(), "Range out of bounds.");
+        if range.start >= range.end {
+            return None;
+        }
+        let len = range.end - range.start;
+        if len == 1 {
+            return Some(range.start);
+        }
+        // largest power of two not exceeding the length of the range
+        let k = (usize::BITS - 1 - len.leading_zeros()) as usize;
+        let row = &self.table[k - 1];
+        let (a, b) = (row[range.start], row[range.end - (1 << k)]);
+        Some(if self.values[b] < self.values[a] {
+            b
+        } else {
+            a
+        })
+    }
+
+    /// Minimum of the given range, or `None` if the range is empty.
+    pub fn min(&self, range: Range<usize>) -> Option<&T> {
+        self.argmin(range).map(|i| &self.values[i])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+
+    #[test]
+    fn test_against_naive() {
+        let mut rng = StdRng::seed_from_u64(42);
+        for &n in &[0, 1, 2, 3, 7, 8, 9, 100] {
+            let values: Vec<u8> = (0..n).map(|_| rng.gen_range(0..10)).collect();
+            let rmq = RMQ::new(values.clone());
+            for start in 0..=n {
+                for end in start..=n {
+                    let expected = (start..end).min_by_key(|&i| values[i]);
+                    assert_eq!(rmq.argmin(start..end), expected);
+                }
+            }
+        }
+    }
+}
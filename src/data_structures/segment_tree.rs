@@ -0,0 +1,253 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Segment tree with lazy propagation over the positions `0..len`, supporting the addition of
+//! a value to all positions of a range and the sum and maximum of the values in a range. This
+//! allows to accumulate per-base coverage from alignments (adding one over each aligned
+//! range) and to query the coverage of a region or locate its peak, while alignments are
+//! still being added.
+//!
+//! Time Complexity: O(n) for construction and O(log n) per update or query.
+//! Memory Complexity: O(n) where `n` is the number of positions.
+//!
+//! If only sums are needed, the Fenwick tree
+//! [`bit_tree::RangeSumBitTree`](../bit_tree/struct.RangeSumBitTree.html) is more compact.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::data_structures::segment_tree::SegmentTree;
+//!
+//! // coverage of a 100bp region from three aligned reads
+//! let mut coverage = SegmentTree::new(100);
+//! for read in &[10..40, 20..50, 35..60] {
+//!     coverage.add(read.clone(), 1);
+//! }
+//! assert_eq!(coverage.get(30), 2);
+//! assert_eq!(coverage.sum(0..100), 30 + 30 + 25);
+//! // the leftmost position of maximal coverage
+//! assert_eq!(coverage.max(0..100), Some((35, 3)));
+//! assert_eq!(coverage.max(50..60), Some((50, 1)));
+//! ```
+
+use std::ops::Range;
+
+/// A segment tree of `i64` values, initially zero.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SegmentTree {
+    len: usize,
+    /// Sum of the values in the range of each node, including pending additions.
+    sums: Vec<i64>,
+    /// Maximum and its leftmost position in the range of each node, including pending
+    /// additions.
+    maxs: Vec<(i64, usize)>,
+    /// Value still to be added to both children of each node.
+    pending: Vec<i64>,
+}
+
+impl SegmentTree {
+    /// Create a segment tree over the positions `0..len`, with all values zero.
+    pub fn new(len: usize) -> Self {
+        let size = 4 * len.max(1);
+        let mut tree = SegmentTree {
+            len,
+            sums: vec![0; size],
+            maxs: vec![(0, 0); size],
+            pending: vec![0; size],
+        };
+        if len > 0 {
+            tree.build(1, 0, len);
+        }
+        tree
+    }
+
+    fn build(&mut self, node: usize, start: usize, end: usize) {
+        self.maxs[node] = (0, start);
+        if end - start > 1 {
+            let mid = (start + end) / 2;
+            self.build(2 * node, start, mid);
+            self.build(2 * node + 1, mid, end);
+        }
+    }
+
+    /// Number of positions.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether there are no positions.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn apply(&mut self, node: usize, len: usize, value: i64) {
+        self.sums[node] += value * len as i64;
+        self.maxs[node].0 += value;
+        self.pending[node] += value;
+    }
+
+    fn push_down(&mut self, node: usize, start: usize, mid: usize, end: usize) {
+        let value = self.pending[node];
+        if value != 0 {
+            self.apply(2 * node, mid - start, value);
+            self.apply(2 * node + 1, end - mid, value);
+            self.pending[node] = 0;
+        }
+    }
+
+    fn check_range(&self, range: &Range<usize>) {
+        assert!(
+            range.start <= range.end && range.end <= self.len,
+            "Range out of bounds."
+        );
+    }
+
+    /// Add `value` to all positions in the given range.
+    pub fn add(&mut self, range: Range<usize>, value: i64) {
+        self.check_range(&range);
+        if range.start < range.end {
+            self.add_rec(1, 0, self.len, &range, value);
+        }
+    }
+
+    fn add_rec(&mut self, node: usize, start: usize, end: usize, range: &Range<usize>, value: i64) {
+        if range.start <= start && end <= range.end {
+            self.apply(node, end - start, value);
+            return;
+        }
+        let mid = (start + end) / 2;
+        self.push_down(node, start, mid, end);
+        if range.start < mid {
+            self.add_rec(2 * node, start, mid, range, value);
+        }
+        if range.end > mid {
+            self.add_rec(2 * node + 1, mid, end, range, value);
+        }
+        self.sums[node] = self.sums[2 * node] + self.sums[2 * node + 1];
+        let (left, right) = (self.maxs[2 * node], self.maxs[2 * node + 1]);
+        self.maxs[node] = if right.0 > left.0 { right } else { left };
+    }
+
+    /// Value at the given position.
+    pub fn get(&self, pos: usize) -> i64 {
+        self.sum(pos..pos + 1)
+    }
+
+    /// Sum of the values in the given range.
+    pub fn sum(&self, range: Range<usize>) -> i64 {
+        self.check_range(&range);
+        if range.start == range.end {
+            return 0;
+        }
+        self.sum_rec(1, 0, self.len, &range, 0)
+    }
+
+    /// Pending additions of the ancestors are accumulated in `above` instead of pushing them
+    /// down, such that queries do not modify the tree.
+    fn sum_rec(
+        &self,
+        node: usize,
+        start: usize,
+        end: usize,
+        range: &Range<usize>,
+        above: i64,
+    ) -> i64 {
+        if range.start <= start && end <= range.end {
+            return self.sums[node] + above * (end - start) as i64;
+        }
+        let mid = (start + end) / 2;
+        let above = above + self.pending[node];
+        let mut sum = 0;
+        if range.start < mid {
+            sum += self.sum_rec(2 * node, start, mid, range, above);
+        }
+        if range.end > mid {
+            sum += self.sum_rec(2 * node + 1, mid, end, range, above);
+        }
+        sum
+    }
+
+    /// The leftmost position of the maximal value in the given range, together with that
+    /// value, or `None` if the range is empty.
+    pub fn max(&self, range: Range<usize>) -> Option<(usize, i64)> {
+        self.check_range(&range);
+        if range.start == range.end {
+            return None;
+        }
+        let (value, pos) = self.max_rec(1, 0, self.len, &range, 0);
+        Some((pos, value))
+    }
+
+    fn max_rec(
+        &self,
+        node: usize,
+        start: usize,
+        end: usize,
+        range: &Range<usize>,
+        above: i64,
+    ) -> (i64, usize) {
+        if range.start <= start && end <= range.end {
+            let (value, pos) = self.maxs[node];
+            return (value + above, pos);
+        }
+        let mid = (start + end) / 2;
+        let above = above + self.pending[node];
+        if range.end <= mid {
+            self.max_rec(2 * node, start, mid, range, above)
+        } else if range.start >= mid {
+            self.max_rec(2 * node + 1, mid, end, range, above)
+        } else {
+            let left = self.max_rec(2 * node, start, mid, range, above);
+            let right = self.max_rec(2 * node + 1, mid, end, range, above);
+            if right.0 > left.0 {
+                right
+            } else {
+                left
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+
+    #[test]
+    fn test_against_naive() {
+        let mut rng = StdRng::seed_from_u64(42);
+        for &n in &[1, 2, 7, 64, 100] {
+            let mut tree = SegmentTree::new(n);
+            let mut values = vec![0i64; n];
+            for _ in 0..500 {
+                let start = rng.gen_range(0..=n);
+                let end = rng.gen_range(start..=n);
+                if rng.gen_bool(0.5) {
+                    let value = rng.gen_range(-5..=5);
+                    tree.add(start..end, value);
+                    values[start..end].iter_mut().for_each(|v| *v += value);
+                } else {
+                    assert_eq!(tree.sum(start..end), values[start..end].iter().sum::<i64>());
+                    let expected = (start..end)
+                        .rev()
+                        .max_by_key(|&i| values[i])
+                        .map(|i| (i, values[i]));
+                    assert_eq!(tree.max(start..end), expected);
+                }
+            }
+            for (i, &value) in values.iter().enumerate() {
+                assert_eq!(tree.get(i), value);
+            }
+        }
+    }
+
+    #[test]
+    fn test_empty() {
+        let tree = SegmentTree::new(0);
+        assert!(tree.is_empty());
+        assert_eq!(tree.sum(0..0), 0);
+        assert_eq!(tree.max(0..0), None);
+    }
+}
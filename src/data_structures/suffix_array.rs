@@ -21,10 +21,12 @@
 //! );
 //! ```
 
+use std::borrow::Borrow;
 use std::cmp;
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::iter;
-use std::ops::Deref;
+use std::ops::{Deref, Range};
 
 use num_integer::Integer;
 use num_traits::{cast, NumCast, Unsigned};
@@ -33,6 +35,9 @@ use bv::{BitVec, Bits, BitsMut};
 use vec_map::VecMap;
 
 use crate::alphabets::{Alphabet, RankTransform};
+use crate::data_structures::bwt::{Less, OccCount, BWT};
+use crate::data_structures::rank_select::RsBitVec;
+use crate::data_structures::rmq::RMQ;
 use crate::data_structures::smallints::SmallInts;
 
 pub type LCPArray = SmallInts<i8, isize>;
@@ -45,63 +50,226 @@ pub trait SuffixArray {
     fn len(&self) -> usize;
     fn is_empty(&self) -> bool;
 
-    // /// Sample the suffix array with the given sample rate.
-    // ///
-    // /// # Arguments
-    // ///
-    // /// * `bwt` - the corresponding BWT
-    // /// * `less` - the corresponding less array
-    // /// * `occ` - the corresponding occ table
-    // /// * `sampling_rate` - if sampling rate is k, every k-th entry will be kept
-    // ///
-    // /// # Example
-    // ///
-    // /// ```
-    // /// use bio::data_structures::suffix_array::{suffix_array, SuffixArray};
-    // /// use bio::data_structures::bwt::{bwt, less, Occ};
-    // /// use bio::alphabets::dna;
-    // ///
-    // /// let text = b"ACGCGAT$";
-    // /// let alphabet = dna::n_alphabet();
-    // /// let sa = suffix_array(text);
-    // /// let bwt = bwt(text, &sa);
-    // /// let less = less(&bwt, &alphabet);
-    // /// let occ = Occ::new(&bwt, 3, &alphabet);
-    // /// let sampled = sa.sample(&bwt, &less, &occ, 1);
-    // ///
-    // /// for i in 0..sa.len() {
-    // ///    assert_eq!(sa.get(i), sampled.get(i));
-    // /// }
-    // /// ```
-    // fn sample<DBWT: DerefBWT, DLess: DerefLess, DOcc: DerefOcc>
-    //     (&self, bwt: DBWT, less: DLess, occ: DOcc, sampling_rate: usize) ->
-    //     SampledSuffixArray<DBWT, DLess, DOcc> {
-    //
-    //     let mut sample = Vec::with_capacity((self.len() as f32 / sampling_rate as f32).ceil() as usize);
-    //     for i in 0..self.len() {
-    //         if (i % sampling_rate) == 0 {
-    //             sample.push(self.get(i).unwrap());
-    //         }
-    //     }
-    //
-    //     SampledSuffixArray {
-    //         bwt: bwt,
-    //         less: less,
-    //         occ: occ,
-    //         sample: sample,
-    //         s: sampling_rate,
-    //     }
-    // }
+    /// Interval of the suffix array holding the suffixes of the given text that start with the
+    /// given pattern (which should not contain the sentinel), found by binary search.
+    /// Complexity: O(m log n) with pattern length m and text length n.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::data_structures::suffix_array::{suffix_array, SuffixArray};
+    /// let text = b"GCCTTAACATTATTACGCCTA$";
+    /// let pos = suffix_array(text);
+    /// assert_eq!(pos.interval(text, b"TTA"), 19..22);
+    /// assert!(pos.interval(text, b"GG").is_empty());
+    /// ```
+    fn interval(&self, text: &[u8], pattern: &[u8]) -> Range<usize> {
+        // compare the prefix of length m of the suffix at the given rank with the pattern
+        let cmp_prefix = |rank: usize| {
+            let p = self.get(rank).unwrap();
+            let end = cmp::min(p + pattern.len(), text.len());
+            text[p..end].cmp(pattern)
+        };
+        // first rank for which the predicate is false, given that it is monotone
+        let partition_point = |pred: &dyn Fn(usize) -> bool| {
+            let (mut lo, mut hi) = (0, self.len());
+            while lo < hi {
+                let mid = lo + (hi - lo) / 2;
+                if pred(mid) {
+                    lo = mid + 1;
+                } else {
+                    hi = mid;
+                }
+            }
+            lo
+        };
+        let start = partition_point(&|rank| cmp_prefix(rank) == cmp::Ordering::Less);
+        let end = partition_point(&|rank| cmp_prefix(rank) != cmp::Ordering::Greater);
+        start..end
+    }
+
+    /// Positions of all occurrences of the given pattern in the given text, in the order of the
+    /// suffix array.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::data_structures::suffix_array::{suffix_array, SuffixArray};
+    /// let text = b"GCCTTAACATTATTACGCCTA$";
+    /// let pos = suffix_array(text);
+    /// let mut occ = pos.occurrences(text, b"TTA");
+    /// occ.sort_unstable();
+    /// assert_eq!(occ, [3, 9, 12]);
+    /// ```
+    fn occurrences(&self, text: &[u8], pattern: &[u8]) -> Vec<usize> {
+        self.interval(text, pattern)
+            .map(|rank| self.get(rank).unwrap())
+            .collect()
+    }
+
+    /// Sample the suffix array with the given sampling rate, keeping every k-th entry.
+    /// Other entries are recovered by LF-mapping over the BWT in O(k) steps (times the
+    /// sampling rate of the occurrence array), such that larger rates trade speed for memory.
+    ///
+    /// # Arguments
+    ///
+    /// * `bwt` - the corresponding BWT
+    /// * `less` - the corresponding less array
+    /// * `occ` - the corresponding occ table
+    /// * `sampling_rate` - if sampling rate is k, every k-th entry will be kept
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::alphabets::dna;
+    /// use bio::data_structures::bwt::{bwt, less, Occ};
+    /// use bio::data_structures::suffix_array::{suffix_array, SuffixArray};
+    ///
+    /// let text = b"ACGCGAT$";
+    /// let alphabet = dna::n_alphabet();
+    /// let sa = suffix_array(text);
+    /// let bwt = bwt(text, &sa);
+    /// let less = less(&bwt, &alphabet);
+    /// let occ = Occ::new(&bwt, 3, &alphabet);
+    /// let sampled = sa.sample(&bwt, &less, &occ, 4);
+    ///
+    /// for i in 0..sa.len() {
+    ///     assert_eq!(sa.get(i), sampled.get(i));
+    /// }
+    /// ```
+    fn sample<DBWT: Borrow<BWT>, DLess: Borrow<Less>, DOcc: OccCount>(
+        &self,
+        bwt: DBWT,
+        less: DLess,
+        occ: DOcc,
+        sampling_rate: usize,
+    ) -> SampledSuffixArray<DBWT, DLess, DOcc>
+    where
+        Self: Sized,
+    {
+        assert!(sampling_rate > 0, "sampling rate must be positive");
+        let sample = (0..self.len())
+            .step_by(sampling_rate)
+            .map(|i| self.get(i).unwrap())
+            .collect();
+        let (sentinel, sentinel_entries) = sentinel_entries(self, bwt.borrow());
+
+        SampledSuffixArray {
+            bwt,
+            less,
+            occ,
+            sample,
+            s: sampling_rate,
+            marks: None,
+            sentinel,
+            sentinel_entries,
+        }
+    }
+
+    /// Sample the suffix array in text order with the given sampling rate, keeping the entries
+    /// that are a multiple of k, i.e. every k-th position of the text. The sampled entries are
+    /// marked in a bitvector with rank support. In contrast to `sample`, where the number of
+    /// LF-mapping steps to recover an entry is only k on average, at most k - 1 steps are
+    /// needed, at the cost of about one additional bit per entry.
+    ///
+    /// # Arguments
+    ///
+    /// * `bwt` - the corresponding BWT
+    /// * `less` - the corresponding less array
+    /// * `occ` - the corresponding occ table
+    /// * `sampling_rate` - if sampling rate is k, every entry that is a multiple of k will be kept
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::alphabets::dna;
+    /// use bio::data_structures::bwt::{bwt, less, Occ};
+    /// use bio::data_structures::fmindex::{FMIndex, FMIndexable};
+    /// use bio::data_structures::suffix_array::{suffix_array, SuffixArray};
+    ///
+    /// let text = b"GCCTTAACATTATTACGCCTA$";
+    /// let alphabet = dna::n_alphabet();
+    /// let sa = suffix_array(text);
+    /// let bwt = bwt(text, &sa);
+    /// let less = less(&bwt, &alphabet);
+    /// let occ = Occ::new(&bwt, 3, &alphabet);
+    /// let sampled = sa.sample_text_order(&bwt, &less, &occ, 4);
+    /// assert!(sampled.is_text_order());
+    ///
+    /// let fm = FMIndex::new(&bwt, &less, &occ);
+    /// let interval = fm.backward_search(b"TA".iter());
+    /// let mut positions = interval.occ(&sampled);
+    /// positions.sort_unstable();
+    /// assert_eq!(positions, [4, 10, 13, 19]);
+    /// ```
+    fn sample_text_order<DBWT: Borrow<BWT>, DLess: Borrow<Less>, DOcc: OccCount>(
+        &self,
+        bwt: DBWT,
+        less: DLess,
+        occ: DOcc,
+        sampling_rate: usize,
+    ) -> SampledSuffixArray<DBWT, DLess, DOcc>
+    where
+        Self: Sized,
+    {
+        assert!(sampling_rate > 0, "sampling rate must be positive");
+        let mut marks: BitVec<u64> = BitVec::new_fill(false, self.len() as u64);
+        let mut sample = Vec::with_capacity(self.len() / sampling_rate + 1);
+        for i in 0..self.len() {
+            let pos = self.get(i).unwrap();
+            if pos % sampling_rate == 0 {
+                marks.set_bit(i as u64, true);
+                sample.push(pos);
+            }
+        }
+        let (sentinel, sentinel_entries) = sentinel_entries(self, bwt.borrow());
+
+        SampledSuffixArray {
+            bwt,
+            less,
+            occ,
+            sample,
+            s: sampling_rate,
+            marks: Some(RsBitVec::new(&marks)),
+            sentinel,
+            sentinel_entries,
+        }
+    }
+}
+
+/// The sentinel and the suffix array entries preceded by it in the text.
+/// LF-mapping cannot pass the sentinel, as there may be several of them in the text.
+/// Hence, these entries are kept by sampled suffix arrays as well.
+fn sentinel_entries<SA: SuffixArray>(sa: &SA, bwt: &BWT) -> (u8, HashMap<usize, usize>) {
+    let sentinel = (0..sa.len())
+        .find(|&i| sa.get(i) == Some(0))
+        .map_or(0, |i| bwt[i]);
+    let entries = bwt
+        .iter()
+        .enumerate()
+        .filter(|&(_, &c)| c == sentinel)
+        .map(|(i, _)| (i, sa.get(i).unwrap()))
+        .collect();
+    (sentinel, entries)
 }
 
-// /// A sampled suffix array.
-// pub struct SampledSuffixArray<DBWT: DerefBWT, DLess: DerefLess, DOcc: DerefOcc> {
-//     bwt: DBWT,
-//     less: DLess,
-//     occ: DOcc,
-//     sample: Vec<usize>,
-//     s: usize, // Rate of sampling
-// }
+/// A sampled suffix array, keeping only every k-th entry of the suffix array (see
+/// `SuffixArray::sample`), or the entries that are a multiple of k (see
+/// `SuffixArray::sample_text_order`).
+#[derive(Serialize, Deserialize)]
+pub struct SampledSuffixArray<DBWT: Borrow<BWT>, DLess: Borrow<Less>, DOcc: OccCount> {
+    bwt: DBWT,
+    less: DLess,
+    occ: DOcc,
+    sample: Vec<usize>,
+    s: usize, // Rate of sampling
+    // Sampled entries, if sampled in text order. Missing in sampled suffix arrays serialized
+    // by earlier versions, which are always sampled in suffix array order.
+    #[serde(default)]
+    marks: Option<RsBitVec>,
+    sentinel: u8,
+    sentinel_entries: HashMap<usize, usize>,
+}
 
 impl SuffixArray for RawSuffixArray {
     fn get(&self, index: usize) -> Option<usize> {
@@ -120,58 +288,125 @@ impl SuffixArray for RawSuffixArray {
     fn is_empty(&self) -> bool {
         Vec::is_empty(self)
     }
+}
 
-    // fn sample<DBWT: DerefBWT, DLess: DerefLess, DOcc: DerefOcc>
-    //     (&self, bwt: DBWT, less: DLess, occ: DOcc, sampling_rate: usize) ->
-    //     SampledSuffixArray<DBWT, DLess, DOcc> {
-    //     // Provide a specialized, faster implementation using iterators.
-    //
-    //     let sample = self.iter().cloned().step(sampling_rate).collect();
-    //
-    //     SampledSuffixArray {
-    //         bwt: bwt,
-    //         less: less,
-    //         occ: occ,
-    //         sample: sample,
-    //         s: sampling_rate,
-    //     }
-    // }
+impl<DBWT: Borrow<BWT>, DLess: Borrow<Less>, DOcc: OccCount> SuffixArray
+    for SampledSuffixArray<DBWT, DLess, DOcc>
+{
+    fn get(&self, index: usize) -> Option<usize> {
+        if index < self.len() {
+            let bwt = self.bwt.borrow();
+            let mut pos = index;
+            let mut offset = 0;
+            loop {
+                let sampled = match &self.marks {
+                    Some(marks) if marks.get(pos as u64) => {
+                        Some(marks.rank_1(pos as u64).unwrap() as usize - 1)
+                    }
+                    Some(_) => None,
+                    None => {
+                        let (i, r) = pos.div_rem(&self.s);
+                        if r == 0 {
+                            Some(i)
+                        } else {
+                            None
+                        }
+                    }
+                };
+                if let Some(i) = sampled {
+                    return Some(self.sample[i] + offset);
+                }
+
+                let c = bwt[pos];
+                if c == self.sentinel {
+                    return Some(self.sentinel_entries[&pos] + offset);
+                }
+                pos = self.less.borrow()[c as usize] + self.occ.occ(bwt, pos, c) - 1;
+                offset += 1;
+            }
+        } else {
+            None
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.bwt.borrow().len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.bwt.borrow().is_empty()
+    }
+}
+
+impl<DBWT: Borrow<BWT>, DLess: Borrow<Less>, DOcc: OccCount> SampledSuffixArray<DBWT, DLess, DOcc> {
+    /// Rate at which the suffix array has been sampled.
+    pub fn sampling_rate(&self) -> usize {
+        self.s
+    }
+
+    /// Whether the suffix array has been sampled in text order.
+    pub fn is_text_order(&self) -> bool {
+        self.marks.is_some()
+    }
 }
 
-// impl<DBWT: DerefBWT, DLess: DerefLess, DOcc: DerefOcc> SuffixArray for SampledSuffixArray<DBWT, DLess, DOcc> {
-//     fn get(&self, index: usize) -> Option<usize> {
-//         if index < self.len() {
-//             let mut pos = index;
-//             let mut offset = 0;
-//             loop {
-//                 if pos % self.s == 0 {
-//                     return Some(self.sample[pos / self.s] + offset);
-//                 }
-//
-//                 let c = self.bwt[pos];
-//                 pos = self.less[c as usize] + self.occ.get(&self.bwt, pos - 1, c);
-//                 offset += 1;
-//             }
-//         } else {
-//             None
-//         }
-//     }
-//
-//     fn len(&self) -> usize {
-//         self.bwt.len()
-//     }
-
-//     fn is_empty(&self) -> bool {
-//         self.bwt.is_empty()
-//     }
-// }
-//
-//
-// impl<DBWT: DerefBWT, DLess: DerefLess, DOcc: DerefOcc> SampledSuffixArray<DBWT, DLess, DOcc> {
-//     pub fn sampling_rate(&self) -> usize {
-//         self.s
-//     }
-// }
+/// A suffix array storing positions as `u32` if the text is short enough, and as `u64`
+/// otherwise. This takes half the memory of a `RawSuffixArray` for texts of less than 4 GiB on
+/// 64-bit platforms.
+///
+/// # Example
+///
+/// ```
+/// use bio::data_structures::suffix_array::{CompactSuffixArray, SuffixArray};
+/// let text = b"GCCTTAACATTATTACGCCTA$";
+/// let pos = CompactSuffixArray::new(text);
+/// assert!(matches!(pos, CompactSuffixArray::U32(_)));
+/// assert_eq!(pos.get(2), Some(5));
+/// assert_eq!(pos.interval(text, b"CC"), 8..10);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CompactSuffixArray {
+    U32(Vec<u32>),
+    U64(Vec<u64>),
+}
+
+impl CompactSuffixArray {
+    /// Construct the suffix array of the given text, which has to be ended by a sentinel (see
+    /// `suffix_array`).
+    pub fn new(text: &[u8]) -> Self {
+        suffix_array(text).into()
+    }
+}
+
+impl From<RawSuffixArray> for CompactSuffixArray {
+    fn from(pos: RawSuffixArray) -> Self {
+        if pos.len() <= u32::MAX as usize {
+            CompactSuffixArray::U32(pos.into_iter().map(|p| p as u32).collect())
+        } else {
+            CompactSuffixArray::U64(pos.into_iter().map(|p| p as u64).collect())
+        }
+    }
+}
+
+impl SuffixArray for CompactSuffixArray {
+    fn get(&self, index: usize) -> Option<usize> {
+        match self {
+            CompactSuffixArray::U32(pos) => pos.get(index).map(|&p| p as usize),
+            CompactSuffixArray::U64(pos) => pos.get(index).map(|&p| p as usize),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            CompactSuffixArray::U32(pos) => pos.len(),
+            CompactSuffixArray::U64(pos) => pos.len(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
 
 /// Construct suffix array for given text of length n.
 /// Complexity: O(n).
@@ -352,6 +587,137 @@ pub fn shortest_unique_substrings<SA: SuffixArray>(pos: &SA, lcp: &LCPArray) ->
     sus
 }
 
+/// Longest common extension queries over a suffix array, i.e. the length of the longest common
+/// prefix of any two suffixes of the text, answered by a range minimum query over the LCP array.
+/// Complexity: O(n log n) for construction and O(1) per query.
+///
+/// # Example
+///
+/// ```
+/// use bio::data_structures::suffix_array::{lcp, suffix_array, LCE};
+/// let text = b"GCCTTAACATTATTACGCCTA$";
+/// let pos = suffix_array(text);
+/// let lce = LCE::new(&pos, &lcp(text, &pos));
+/// // GCCT is shared by the suffixes starting at positions 0 and 16
+/// assert_eq!(lce.get(0, 16), 4);
+/// assert_eq!(lce.get(4, 4), 18);
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LCE {
+    rank: Vec<usize>,
+    rmq: RMQ<isize>,
+}
+
+impl LCE {
+    /// Build from the suffix array and LCP array of a text.
+    pub fn new<SA: SuffixArray>(pos: &SA, lcp: &LCPArray) -> Self {
+        let mut rank = vec![0; pos.len()];
+        for r in 0..pos.len() {
+            rank[pos.get(r).unwrap()] = r;
+        }
+        LCE {
+            rank,
+            rmq: RMQ::new(lcp.decompress()),
+        }
+    }
+
+    /// Length of the longest common prefix of the suffixes starting at positions i and j.
+    pub fn get(&self, i: usize, j: usize) -> usize {
+        if i == j {
+            return self.rank.len() - i;
+        }
+        let (ri, rj) = (self.rank[i], self.rank[j]);
+        let range = cmp::min(ri, rj) + 1..cmp::max(ri, rj) + 1;
+        *self.rmq.min(range).unwrap() as usize
+    }
+}
+
+/// A repeat in the text, given by its length and the suffix array interval of its occurrences.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repeat {
+    pub len: usize,
+    pub interval: Range<usize>,
+}
+
+impl Repeat {
+    /// Positions of the occurrences of the repeat in the text.
+    pub fn occ<SA: SuffixArray>(&self, pos: &SA) -> Vec<usize> {
+        self.interval
+            .clone()
+            .map(|r| pos.get(r).expect("Interval out of range of suffix array"))
+            .collect()
+    }
+}
+
+/// Calculate all supermaximal repeats of length at least `min_len`, i.e. maximal repeats that
+/// do not occur as a substring of any other maximal repeat. They are given by the local maxima
+/// of the LCP array whose suffixes are preceded by pairwise distinct symbols
+/// (Ohlebusch (2013). "Bioinformatics Algorithms". ISBN 978-3-00-041316-2).
+/// Complexity: O(n)
+///
+/// # Arguments
+///
+/// * `text` - the text ended by sentinel symbol (being lexicographically smallest)
+/// * `pos` - the suffix array
+/// * `lcp` - the lcp array
+/// * `min_len` - the minimum length of reported repeats
+///
+/// # Example
+///
+/// ```
+/// use bio::data_structures::suffix_array::{lcp, suffix_array, supermaximal_repeats};
+/// let text = b"GCCTTAACATTATTACGCCTA$";
+/// let pos = suffix_array(text);
+/// let repeats = supermaximal_repeats(text, &pos, &lcp(text, &pos), 3);
+/// let found: Vec<(&[u8], Vec<usize>)> = repeats
+///     .iter()
+///     .map(|r| {
+///         let mut occ = r.occ(&pos);
+///         occ.sort_unstable();
+///         (&text[occ[0]..occ[0] + r.len], occ)
+///     })
+///     .collect();
+/// assert_eq!(found, [(&b"ATTA"[..], vec![8, 11]), (&b"GCCT"[..], vec![0, 16])]);
+/// ```
+pub fn supermaximal_repeats<SA: SuffixArray>(
+    text: &[u8],
+    pos: &SA,
+    lcp: &LCPArray,
+    min_len: usize,
+) -> Vec<Repeat> {
+    let n = pos.len();
+    let lcp = lcp.decompress();
+    let mut repeats = Vec::new();
+    let mut r = 1;
+    while r < n {
+        if lcp[r] <= lcp[r - 1] {
+            r += 1;
+            continue;
+        }
+        // lcp-interval [r - 1, end] with values lcp[r..=end] all being equal
+        let l = lcp[r];
+        let mut end = r;
+        while end + 1 < n && lcp[end + 1] == l {
+            end += 1;
+        }
+        if lcp[end + 1] < l && l as usize >= min_len {
+            let mut seen = [false; 256];
+            let left_diverse = (r - 1..=end).all(|k| match pos.get(k).unwrap() {
+                0 => true,
+                p => !std::mem::replace(&mut seen[text[p - 1] as usize], true),
+            });
+            if left_diverse {
+                repeats.push(Repeat {
+                    len: l as usize,
+                    interval: r - 1..end + 1,
+                });
+            }
+        }
+        r = end + 1;
+    }
+    repeats
+}
+
 /// Return last character of the text (expected to be the sentinel).
 fn sentinel(text: &[u8]) -> u8 {
     text[text.len() - 1]
@@ -679,13 +1045,12 @@ impl PosTypes {
 
 #[cfg(test)]
 mod tests {
-    // Commented-out imports waiting on re-enabling of sampled suffix array
-    // See issue #70
     use super::*;
     use super::{transform_text, PosTypes, SAIS};
+    use crate::alphabets::dna;
     use crate::alphabets::Alphabet;
+    use crate::data_structures::bwt::{bwt, less, Occ};
     use bv::{BitVec, BitsPush};
-    //use data_structures::bwt::{bwt, less, Occ};
     use std::str;
 
     #[test]
@@ -819,35 +1184,162 @@ mod tests {
         }
     }
 
-    // #[test]
-    // fn test_sampled_matches() {
-    //     let test_cases =             [(&b"A$C$G$T$"[..], "simple"),
-    //          (&b"A$A$T$T$"[..], "duplicates"),
-    //          (&b"AA$GA$CA$TA$TC$TG$GT$GC$"[..], "two letter"),
-    //          (&b"AGCCAT$\
-    //             CAGCC$"[..],
-    //             "substring"),
-    //          (&b"GTAGGCCTAATTATAATCAGCGGACATTTCGTATTGCTCGGGCTGCCAGGATTTTAGCATCAGTAGCCGGGTAATGGAACCTCAAGAGGTCAGCGTCGAA$\
-    //             AATCAGCGGACATTTCGTATTGCTCGGGCTGCCAGGATTTTAGCATCAGTAGCCGGGTAATGGAACCTCAAGAGGTCAGCGTCGAATGGCTATTCCAATA$"[..],
-    //             "complex"),
-    //          (&b"GTAGGCCTAATTATAATCAGCGGACATTTCGTATTGCTCGGGCTGCCAGGATTTTAGCATCAGTAGCCGGGTAATGGAACCTCAAGAGGTCAGCGTCGAA$\
-    //             TTCGACGCTGACCTCTTGAGGTTCCATTACCCGGCTACTGATGCTAAAATCCTGGCAGCCCGAGCAATACGAAATGTCCGCTGATTATAATTAGGCCTAC$\
-    //             AATCAGCGGACATTTCGTATTGCTCGGGCTGCCAGGATTTTAGCATCAGTAGCCGGGTAATGGAACCTCAAGAGGTCAGCGTCGAATGGCTATTCCAATA$\
-    //             TATTGGAATAGCCATTCGACGCTGACCTCTTGAGGTTCCATTACCCGGCTACTGATGCTAAAATCCTGGCAGCCCGAGCAATACGAAATGTCCGCTGATT$"[..],
-    //             "complex with revcomps"),
-    //          ];
-    //
-    //     for &(text, _) in test_cases.into_iter() {
-    //         let alphabet = dna::n_alphabet();
-    //         let sa = suffix_array(text);
-    //         let bwt = bwt(text, &sa);
-    //         let less = less(&bwt, &alphabet);
-    //         let occ = Occ::new(&bwt, 3, &alphabet);
-    //         let sampled = sa.sample(&bwt, &less, &occ, 2);
-    //
-    //         for i in 0..sa.len() {
-    //             assert_eq!(sa.get(i), sampled.get(i));
-    //         }
-    //     }
-    // }
+    #[test]
+    fn test_sampled_matches() {
+        let test_cases = [
+            (&b"A$C$G$T$"[..], "simple"),
+            (&b"A$A$T$T$"[..], "duplicates"),
+            (&b"AA$GA$CA$TA$TC$TG$GT$GC$"[..], "two letter"),
+            (
+                &b"AGCCAT$\
+                    CAGCC$"[..],
+                "substring",
+            ),
+            (
+                &b"GTAGGCCTAATTATAATCAGCGGACATTTCGTATTGCTCGGGCTGCCAGGATTTTAGCATCAGTAGCCGGGTAATGGAACCTCAAGAGGTCAGCGTCGAA$\
+                    AATCAGCGGACATTTCGTATTGCTCGGGCTGCCAGGATTTTAGCATCAGTAGCCGGGTAATGGAACCTCAAGAGGTCAGCGTCGAATGGCTATTCCAATA$"[..],
+                "complex",
+            ),
+            (
+                &b"GTAGGCCTAATTATAATCAGCGGACATTTCGTATTGCTCGGGCTGCCAGGATTTTAGCATCAGTAGCCGGGTAATGGAACCTCAAGAGGTCAGCGTCGAA$\
+                    TTCGACGCTGACCTCTTGAGGTTCCATTACCCGGCTACTGATGCTAAAATCCTGGCAGCCCGAGCAATACGAAATGTCCGCTGATTATAATTAGGCCTAC$\
+                    AATCAGCGGACATTTCGTATTGCTCGGGCTGCCAGGATTTTAGCATCAGTAGCCGGGTAATGGAACCTCAAGAGGTCAGCGTCGAATGGCTATTCCAATA$\
+                    TATTGGAATAGCCATTCGACGCTGACCTCTTGAGGTTCCATTACCCGGCTACTGATGCTAAAATCCTGGCAGCCCGAGCAATACGAAATGTCCGCTGATT$"[..],
+                "complex with revcomps",
+            ),
+        ];
+
+        for &(text, test_name) in test_cases.iter() {
+            let alphabet = dna::n_alphabet();
+            let sa = suffix_array(text);
+            let bwt = bwt(text, &sa);
+            let less = less(&bwt, &alphabet);
+            let occ = Occ::new(&bwt, 3, &alphabet);
+            for &rate in &[1, 2, 5, 32] {
+                let sampled = sa.sample(&bwt, &less, &occ, rate);
+                assert_eq!(sampled.len(), sa.len());
+                for i in 0..sa.len() {
+                    assert_eq!(sa.get(i), sampled.get(i), "{}, rate {}", test_name, rate);
+                }
+                let sampled = sa.sample_text_order(&bwt, &less, &occ, rate);
+                assert!(sampled.is_text_order());
+                for i in 0..sa.len() {
+                    assert_eq!(
+                        sa.get(i),
+                        sampled.get(i),
+                        "{}, text order rate {}",
+                        test_name,
+                        rate
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_interval_against_naive() {
+        let text = b"ACGTACGGACGTAAACGT$";
+        let pos = suffix_array(text);
+        let compact = CompactSuffixArray::from(pos.clone());
+        for &pattern in &[
+            &b"ACG"[..],
+            b"A",
+            b"T",
+            b"ACGT",
+            b"CGTA",
+            b"GGG",
+            b"",
+            b"TAAACGT",
+        ] {
+            let mut expected: Vec<usize> = (0..text.len())
+                .filter(|&i| text[i..].starts_with(pattern))
+                .collect();
+            expected.sort_unstable();
+            for occ in [
+                pos.occurrences(text, pattern),
+                compact.occurrences(text, pattern),
+            ]
+            .iter()
+            {
+                let mut occ = occ.clone();
+                occ.sort_unstable();
+                assert_eq!(occ, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_lce_and_supermaximal_repeats_against_naive() {
+        use crate::utils::testing::random_seq;
+        use rand::prelude::*;
+
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..20 {
+            let mut text = random_seq(&mut rng, b"ACGT", 40);
+            text.push(b'$');
+            let n = text.len();
+            let pos = suffix_array(&text);
+            let lcp = lcp(&text, &pos);
+
+            let lce = LCE::new(&pos, &lcp);
+            for i in 0..n {
+                for j in 0..n {
+                    let expected = text[i..]
+                        .iter()
+                        .zip(&text[j..])
+                        .take_while(|(a, b)| a == b)
+                        .count();
+                    assert_eq!(lce.get(i, j), expected);
+                }
+            }
+
+            // naive: substrings occurring at least twice, with different symbols before and
+            // after some of their occurrences (the text start counting as a unique symbol)
+            let occurrences = |w: &[u8]| -> Vec<usize> {
+                (0..=n - w.len())
+                    .filter(|&p| text[p..].starts_with(w))
+                    .collect()
+            };
+            let mut maximal: Vec<Vec<u8>> = Vec::new();
+            for start in 0..n - 1 {
+                for end in start + 1..n {
+                    let w = &text[start..end];
+                    let occ = occurrences(w);
+                    let left: Vec<Option<u8>> = occ
+                        .iter()
+                        .map(|&p| if p == 0 { None } else { Some(text[p - 1]) })
+                        .collect();
+                    let right: Vec<u8> = occ.iter().map(|&p| text[p + w.len()]).collect();
+                    if occ.len() > 1
+                        && (left.contains(&None) || left.iter().any(|&a| a != left[0]))
+                        && right.iter().any(|&a| a != right[0])
+                        && !maximal.iter().any(|m| m == w)
+                    {
+                        maximal.push(w.to_vec());
+                    }
+                }
+            }
+            let mut expected: Vec<Vec<u8>> = maximal
+                .iter()
+                .filter(|w| {
+                    !maximal
+                        .iter()
+                        .any(|m| m.len() > w.len() && m.windows(w.len()).any(|x| x == &w[..]))
+                })
+                .cloned()
+                .collect();
+            expected.sort();
+
+            let mut found: Vec<Vec<u8>> = supermaximal_repeats(&text, &pos, &lcp, 1)
+                .iter()
+                .map(|r| {
+                    let occ = r.occ(&pos);
+                    assert_eq!(occ.len(), occurrences(&text[occ[0]..occ[0] + r.len]).len());
+                    text[occ[0]..occ[0] + r.len].to_vec()
+                })
+                .collect();
+            found.sort();
+            assert_eq!(found, expected);
+        }
+    }
 }
@@ -0,0 +1,573 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A generalized suffix tree over multiple texts, built online with Ukkonen's algorithm
+//! (Ukkonen, On-line construction of suffix trees, Algorithmica 1995).
+//!
+//! Each text is implicitly terminated by its own unique sentinel, such that every suffix of
+//! every text ends in a leaf. Texts can be added one after another; each text is appended to
+//! the tree symbol by symbol, maintaining suffix links between the inner nodes. Besides
+//! pattern search, the tree supports traversals that are cumbersome with a suffix array:
+//! the longest common substring of all texts, the matching statistics of a query and the
+//! enumeration of maximal repeats.
+//!
+//! Time Complexity: O(n log σ) for the construction over texts of total length n with
+//! alphabet size σ, O(m log σ + occ) for locating a pattern of length m, and O(m log σ) for
+//! the matching statistics of a query of length m.
+//! Memory Complexity: O(n), with a considerably larger constant than suffix arrays.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::data_structures::suffix_tree::SuffixTree;
+//!
+//! let texts: [&[u8]; 2] = [b"GCCTTAACATTATTACGCCTA", b"TTACGCCAACATT"];
+//! let mut tree = SuffixTree::new();
+//! for text in &texts {
+//!     tree.add_text(text);
+//! }
+//! assert_eq!(tree.occurrences(b"ACAT"), [(0, 6), (1, 8)]);
+//!
+//! // the longest substring shared by all texts
+//! let lcs = tree.longest_common_substring().unwrap();
+//! assert_eq!(lcs.len, 7);
+//! assert_eq!(lcs.occurrences, [(0, 12), (1, 0)]);
+//! assert_eq!(&texts[0][12..19], b"TTACGCC");
+//!
+//! // length of the longest prefix of each suffix of the query that occurs in the texts
+//! assert_eq!(tree.matching_statistics(b"ACGGTT"), [3, 2, 1, 1, 2, 1]);
+//! ```
+
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::iter;
+
+/// Root node index.
+const ROOT: usize = 0;
+/// Sentinel of the first text. Sentinels are larger than all byte symbols.
+const SENTINEL: u32 = 256;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+struct Node {
+    /// Start of the label of the edge into the node.
+    start: usize,
+    /// End of the label of the edge into the node, or `None` for leaves, whose labels extend
+    /// to the end of their text.
+    end: Option<usize>,
+    /// String depth of inner nodes.
+    depth: usize,
+    /// Suffix link of inner nodes.
+    link: usize,
+    children: BTreeMap<u32, usize>,
+    /// The text and position of the suffix of a leaf.
+    suffix: Option<(usize, usize)>,
+}
+
+/// A substring of the texts with all its occurrences.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Substring {
+    pub len: usize,
+    /// Occurrences as pairs of text index and position, in increasing order.
+    pub occurrences: Vec<(usize, usize)>,
+}
+
+/// A generalized suffix tree.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SuffixTree {
+    /// Concatenation of all texts with their sentinels.
+    text: Vec<u32>,
+    /// End of each text (after its sentinel) in the concatenation.
+    ends: Vec<usize>,
+    nodes: Vec<Node>,
+}
+
+impl Default for SuffixTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SuffixTree {
+    /// Create an empty suffix tree.
+    pub fn new() -> Self {
+        SuffixTree {
+            text: Vec::new(),
+            ends: Vec::new(),
+            nodes: vec![Node {
+                start: 0,
+                end: Some(0),
+                depth: 0,
+                link: ROOT,
+                children: BTreeMap::new(),
+                suffix: None,
+            }],
+        }
+    }
+
+    /// Number of texts.
+    pub fn num_texts(&self) -> usize {
+        self.ends.len()
+    }
+
+    /// Number of nodes, including the root and the leaves.
+    pub fn num_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Start of the given text in the concatenation.
+    fn text_start(&self, text: usize) -> usize {
+        if text == 0 {
+            0
+        } else {
+            self.ends[text - 1]
+        }
+    }
+
+    /// Length of the given text, without its sentinel.
+    pub fn text_len(&self, text: usize) -> usize {
+        self.ends[text] - self.text_start(text) - 1
+    }
+
+    fn edge_end(&self, node: usize) -> usize {
+        let node = &self.nodes[node];
+        node.end.unwrap_or_else(|| {
+            let (text, _) = node.suffix.unwrap();
+            self.ends.get(text).copied().unwrap_or(self.text.len())
+        })
+    }
+
+    fn edge_len(&self, node: usize) -> usize {
+        self.edge_end(node) - self.nodes[node].start
+    }
+
+    fn is_leaf(&self, node: usize) -> bool {
+        self.nodes[node].suffix.is_some()
+    }
+
+    fn add_node(&mut self, start: usize, end: Option<usize>, depth: usize) -> usize {
+        self.nodes.push(Node {
+            start,
+            end,
+            depth,
+            link: ROOT,
+            children: BTreeMap::new(),
+            suffix: None,
+        });
+        self.nodes.len() - 1
+    }
+
+    /// Add a text to the tree and return its index.
+    pub fn add_text(&mut self, text: &[u8]) -> usize {
+        let id = self.ends.len();
+        let offset = self.text.len();
+        let symbols = text
+            .iter()
+            .map(|&a| a as u32)
+            .chain(iter::once(SENTINEL + id as u32));
+        // the active point and the number of suffixes that remain to be inserted
+        let (mut active_node, mut active_edge, mut active_len) = (ROOT, 0, 0);
+        let mut remainder = 0;
+        for (i, a) in symbols.enumerate() {
+            let pos = offset + i;
+            self.text.push(a);
+            remainder += 1;
+            let mut last_inner: Option<usize> = None;
+            while remainder > 0 {
+                if active_len == 0 {
+                    active_edge = pos;
+                }
+                let b = self.text[active_edge];
+                let suffix = (id, i + 1 - remainder);
+                match self.nodes[active_node].children.get(&b).copied() {
+                    None => {
+                        let leaf = self.add_node(pos, None, 0);
+                        self.nodes[leaf].suffix = Some(suffix);
+                        self.nodes[active_node].children.insert(b, leaf);
+                        if let Some(inner) = last_inner.take() {
+                            self.nodes[inner].link = active_node;
+                        }
+                    }
+                    Some(next) => {
+                        let len = self.edge_len(next);
+                        if active_len >= len {
+                            // walk down
+                            active_edge += len;
+                            active_len -= len;
+                            active_node = next;
+                            continue;
+                        }
+                        let start = self.nodes[next].start;
+                        if self.text[start + active_len] == a {
+                            // the suffix is already contained implicitly
+                            if let Some(inner) = last_inner.take() {
+                                self.nodes[inner].link = active_node;
+                            }
+                            active_len += 1;
+                            break;
+                        }
+                        // split the edge
+                        let depth = self.nodes[active_node].depth + active_len;
+                        let split = self.add_node(start, Some(start + active_len), depth);
+                        let leaf = self.add_node(pos, None, 0);
+                        self.nodes[leaf].suffix = Some(suffix);
+                        self.nodes[active_node].children.insert(b, split);
+                        self.nodes[next].start += active_len;
+                        let c = self.text[start + active_len];
+                        self.nodes[split].children.insert(c, next);
+                        self.nodes[split].children.insert(a, leaf);
+                        if let Some(inner) = last_inner.replace(split) {
+                            self.nodes[inner].link = split;
+                        }
+                    }
+                }
+                remainder -= 1;
+                if active_node == ROOT && active_len > 0 {
+                    active_len -= 1;
+                    active_edge = pos + 1 - remainder;
+                } else if active_node != ROOT {
+                    active_node = self.nodes[active_node].link;
+                }
+            }
+        }
+        self.ends.push(self.text.len());
+        id
+    }
+
+    /// The node at or below which the path of the pattern ends, if the pattern occurs.
+    fn locus(&self, pattern: &[u8]) -> Option<usize> {
+        let (mut node, mut i) = (ROOT, 0);
+        while i < pattern.len() {
+            node = *self.nodes[node].children.get(&(pattern[i] as u32))?;
+            let start = self.nodes[node].start;
+            let len = self.edge_len(node).min(pattern.len() - i);
+            if !(0..len).all(|j| self.text[start + j] == pattern[i + j] as u32) {
+                return None;
+            }
+            i += len;
+        }
+        Some(node)
+    }
+
+    /// Occurrences of the suffixes below the given node, in increasing order.
+    fn leaves(&self, node: usize) -> Vec<(usize, usize)> {
+        let mut leaves = Vec::new();
+        let mut stack = vec![node];
+        while let Some(node) = stack.pop() {
+            match self.nodes[node].suffix {
+                // ignore the empty suffixes consisting only of a sentinel
+                Some((text, pos)) if pos < self.text_len(text) => leaves.push((text, pos)),
+                Some(_) => (),
+                None => stack.extend(self.nodes[node].children.values()),
+            }
+        }
+        leaves.sort_unstable();
+        leaves
+    }
+
+    /// Whether the pattern occurs in any of the texts.
+    pub fn contains(&self, pattern: &[u8]) -> bool {
+        self.locus(pattern).is_some()
+    }
+
+    /// Occurrences of the pattern as pairs of text index and position, in increasing order.
+    pub fn occurrences(&self, pattern: &[u8]) -> Vec<(usize, usize)> {
+        self.locus(pattern)
+            .map_or_else(Vec::new, |node| self.leaves(node))
+    }
+
+    /// The nodes in post-order.
+    fn postorder(&self) -> Vec<usize> {
+        let mut order = Vec::with_capacity(self.nodes.len());
+        let mut stack = vec![ROOT];
+        while let Some(node) = stack.pop() {
+            order.push(node);
+            stack.extend(self.nodes[node].children.values());
+        }
+        // reversing a pre-order with reversed children yields a post-order
+        order.reverse();
+        order
+    }
+
+    /// The longest substring that occurs in all texts, if there is a non-empty one. If there
+    /// are several, the one with the first occurrence is returned.
+    pub fn longest_common_substring(&self) -> Option<Substring> {
+        let k = self.num_texts();
+        let words = k / 64 + 1;
+        // for each node, the set of texts and the first occurrence of its suffixes
+        let mut texts = vec![0u64; self.nodes.len() * words];
+        let mut first = vec![(usize::MAX, usize::MAX); self.nodes.len()];
+        // the length, first occurrence and node of the best candidate
+        let mut best = (0, (usize::MAX, usize::MAX), ROOT);
+        for node in self.postorder() {
+            let depth = match self.nodes[node].suffix {
+                Some((text, pos)) => {
+                    texts[node * words + text / 64] |= 1 << (text % 64);
+                    first[node] = (text, pos);
+                    self.text_len(text) - pos
+                }
+                None => {
+                    for &child in self.nodes[node].children.values() {
+                        for w in 0..words {
+                            texts[node * words + w] |= texts[child * words + w];
+                        }
+                        first[node] = first[node].min(first[child]);
+                    }
+                    self.nodes[node].depth
+                }
+            };
+            let covered = texts[node * words..(node + 1) * words]
+                .iter()
+                .map(|w| w.count_ones() as usize)
+                .sum::<usize>()
+                == k;
+            if covered && (depth, Reverse(first[node])) > (best.0, Reverse(best.1)) {
+                best = (depth, first[node], node);
+            }
+        }
+        let (len, _, node) = best;
+        if len == 0 {
+            return None;
+        }
+        Some(Substring {
+            len,
+            occurrences: self.leaves(node),
+        })
+    }
+
+    /// The matching statistics of the query: for each position `i`, the length of the longest
+    /// prefix of `query[i..]` that occurs in any of the texts.
+    pub fn matching_statistics(&self, query: &[u8]) -> Vec<usize> {
+        let mut stats = Vec::with_capacity(query.len());
+        // query[i..i + len] is matched, and node is the deepest inner node on its path
+        let (mut node, mut len) = (ROOT, 0);
+        for i in 0..query.len() {
+            while i + len < query.len() {
+                let depth = self.nodes[node].depth;
+                let child = match self.nodes[node].children.get(&(query[i + depth] as u32)) {
+                    Some(&child) => child,
+                    None => break,
+                };
+                let offset = len - depth;
+                if self.text[self.nodes[child].start + offset] != query[i + len] as u32 {
+                    break;
+                }
+                len += 1;
+                if !self.is_leaf(child) && offset + 1 == self.edge_len(child) {
+                    node = child;
+                }
+            }
+            stats.push(len);
+            if len == 0 {
+                continue;
+            }
+            // continue with the next suffix of the query, following the suffix link
+            len -= 1;
+            node = self.nodes[node].link;
+            node = self.rescan(node, &query[i + 1..i + 1 + len]);
+        }
+        stats
+    }
+
+    /// Descend from the given inner node along the pattern, which has to occur, to the
+    /// deepest inner node on its path.
+    fn rescan(&self, mut node: usize, pattern: &[u8]) -> usize {
+        loop {
+            let depth = self.nodes[node].depth;
+            if depth == pattern.len() {
+                return node;
+            }
+            let child = self.nodes[node].children[&(pattern[depth] as u32)];
+            if self.is_leaf(child) || self.nodes[child].depth > pattern.len() {
+                return node;
+            }
+            node = child;
+        }
+    }
+
+    /// The maximal repeats of at least the given length, i.e. substrings that occur at least
+    /// twice, and whose occurrences can neither all be extended to the left nor all to the
+    /// right by the same symbol. The ends of the texts count as distinct symbols. Repeats are
+    /// reported within and across the texts, ordered by their first occurrence and length.
+    pub fn maximal_repeats(&self, min_len: usize) -> Vec<Substring> {
+        // for each node, the common symbol left of all its suffixes, or None if they differ
+        let mut left = vec![None; self.nodes.len()];
+        let mut repeats = Vec::new();
+        for node in self.postorder() {
+            match self.nodes[node].suffix {
+                Some((text, pos)) => {
+                    if pos > 0 {
+                        left[node] = Some(self.text[self.text_start(text) + pos - 1]);
+                    }
+                }
+                None => {
+                    let mut children = self.nodes[node].children.values().map(|&c| left[c]);
+                    let first = children.next().flatten();
+                    if children.all(|l| l.is_some() && l == first) {
+                        left[node] = first;
+                    }
+                    if node != ROOT && left[node].is_none() && self.nodes[node].depth >= min_len {
+                        repeats.push(Substring {
+                            len: self.nodes[node].depth,
+                            occurrences: self.leaves(node),
+                        });
+                    }
+                }
+            }
+        }
+        repeats.sort_unstable_by_key(|repeat| (repeat.occurrences[0], repeat.len));
+        repeats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::random_seq;
+    use rand::prelude::*;
+
+    fn random_texts(rng: &mut StdRng) -> Vec<Vec<u8>> {
+        (0..rng.gen_range(1..4))
+            .map(|_| {
+                let len = rng.gen_range(0..25);
+                random_seq(rng, b"ACG", len)
+            })
+            .collect()
+    }
+
+    fn naive_occurrences(texts: &[Vec<u8>], pattern: &[u8]) -> Vec<(usize, usize)> {
+        let mut occurrences = Vec::new();
+        for (t, text) in texts.iter().enumerate() {
+            for pos in 0..text.len() {
+                if text[pos..].starts_with(pattern) {
+                    occurrences.push((t, pos));
+                }
+            }
+        }
+        occurrences
+    }
+
+    fn build(texts: &[Vec<u8>]) -> SuffixTree {
+        let mut tree = SuffixTree::new();
+        for text in texts {
+            tree.add_text(text);
+        }
+        tree
+    }
+
+    #[test]
+    fn test_occurrences() {
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..100 {
+            let texts = random_texts(&mut rng);
+            let tree = build(&texts);
+            assert_eq!(tree.num_texts(), texts.len());
+            for _ in 0..20 {
+                let len = rng.gen_range(0..6);
+                let pattern = random_seq(&mut rng, b"ACGT", len);
+                let expected = naive_occurrences(&texts, &pattern);
+                assert_eq!(tree.occurrences(&pattern), expected);
+                assert_eq!(tree.contains(&pattern), !expected.is_empty() || len == 0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_matching_statistics() {
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..100 {
+            let texts = random_texts(&mut rng);
+            let tree = build(&texts);
+            let query = random_seq(&mut rng, b"ACGT", 30);
+            let expected: Vec<usize> = (0..query.len())
+                .map(|i| {
+                    (0..=query.len() - i)
+                        .rev()
+                        .find(|&len| {
+                            len == 0 || !naive_occurrences(&texts, &query[i..i + len]).is_empty()
+                        })
+                        .unwrap()
+                })
+                .collect();
+            assert_eq!(tree.matching_statistics(&query), expected);
+        }
+    }
+
+    #[test]
+    fn test_longest_common_substring() {
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..100 {
+            let texts = random_texts(&mut rng);
+            let tree = build(&texts);
+            // the longest substrings of the first text that occur in all texts
+            let mut expected: Option<Substring> = None;
+            let first = &texts[0];
+            for len in (1..=first.len()).rev() {
+                for start in 0..=first.len() - len {
+                    let pattern = &first[start..start + len];
+                    let occurrences = naive_occurrences(&texts, pattern);
+                    let covered = (0..texts.len()).all(|t| occurrences.iter().any(|o| o.0 == t));
+                    if covered && expected.is_none() {
+                        expected = Some(Substring { len, occurrences });
+                    }
+                }
+                if expected.is_some() {
+                    break;
+                }
+            }
+            assert_eq!(tree.longest_common_substring(), expected);
+        }
+    }
+
+    #[test]
+    fn test_maximal_repeats() {
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..50 {
+            let texts = random_texts(&mut rng);
+            let tree = build(&texts);
+            // symbol at the given offset from an occurrence, with distinct text ends
+            let symbol = |(t, pos): (usize, usize), offset: isize| -> Option<u32> {
+                let i = pos as isize + offset;
+                if i < 0 {
+                    None
+                } else {
+                    Some(
+                        texts[t]
+                            .get(i as usize)
+                            .map_or(SENTINEL + t as u32, |&a| a as u32),
+                    )
+                }
+            };
+            let mut expected = Vec::new();
+            let mut seen = std::collections::HashSet::new();
+            for text in &texts {
+                for start in 0..text.len() {
+                    for end in start + 2..=text.len() {
+                        let pattern = &text[start..end];
+                        if !seen.insert(pattern.to_vec()) {
+                            continue;
+                        }
+                        let occurrences = naive_occurrences(&texts, pattern);
+                        let first = occurrences[0];
+                        let right_diverse = occurrences.iter().any(|&o| {
+                            symbol(o, pattern.len() as isize)
+                                != symbol(first, pattern.len() as isize)
+                        });
+                        let left_diverse = occurrences.iter().any(|&o| {
+                            symbol(o, -1).is_none() || symbol(o, -1) != symbol(first, -1)
+                        });
+                        if right_diverse && left_diverse {
+                            expected.push(Substring {
+                                len: pattern.len(),
+                                occurrences,
+                            });
+                        }
+                    }
+                }
+            }
+            expected.sort_unstable_by_key(|repeat| (repeat.occurrences[0], repeat.len));
+            assert_eq!(tree.maximal_repeats(2), expected);
+        }
+    }
+}
@@ -1,7 +1,16 @@
-//! Wavelet Matrix data structure for DNA alphabet.
+//! Wavelet Matrix data structure for DNA and other small alphabets.
 //! The implementation is based on the paper
 //! [Claude Francisco and Gonzalo Navarro. The wavelet matrix. SPIRE (2012)](https://doi.org/10.1007/978-3-642-34109-0_18)
 //!
+//! Symbols are encoded by their rank in the alphabet, using one level of rank/select
+//! bitvectors per bit of the encoding. Access, rank and select queries take O(log σ) time
+//! with alphabet size σ, as do quantile and range count queries over intervals of the text.
+//! Built over a BWT, the rank query yields the occurrence counts of an FM-index in
+//! n log σ bits (plus the rank/select overhead), instead of the sampled counts of `bwt::Occ`.
+//! To this end, the wavelet matrix implements `bwt::OccCount` and can be passed to
+//! `FMIndex::new` in place of an `Occ`. Since `WaveletMatrix::new` folds unknown symbols into
+//! the DNA alphabet, build it with `WaveletMatrix::with_alphabet` for texts over other symbols.
+//!
 //! # Example
 //!
 //! ```
@@ -11,9 +20,31 @@
 //! assert_eq!(wm.rank(b'A', 0), 1);
 //! assert_eq!(wm.rank(b'G', 9), 2);
 //! assert_eq!(wm.rank(b'T', 13), 3);
+//! assert_eq!(wm.access(3), b'G');
+//! assert_eq!(wm.select(b'T', 2), Some(11));
+//! // the median symbol of AANGG (in alphabet order ACGTN$)
+//! assert_eq!(wm.quantile(0..5, 2), Some(b'G'));
+//! // number of C and G in CCNTT
+//! assert_eq!(wm.range_count(8..13, b'C', b'G'), 2);
+//! ```
+//!
+//! Arbitrary alphabets are supported as well:
+//!
+//! ```
+//! use bio::alphabets::Alphabet;
+//! use bio::data_structures::wavelet_matrix::WaveletMatrix;
+//! let text = b"MKVLAAGIVALLLAAGCSS";
+//! let wm = WaveletMatrix::with_alphabet(text, &Alphabet::new(text));
+//! assert_eq!(wm.rank(b'L', 12), 4);
+//! assert_eq!(wm.select(b'A', 4), Some(13));
+//! assert_eq!(wm.range_count(0..10, b'A', b'I'), 5);
 //! ```
 
-use crate::data_structures::rank_select::RankSelect;
+use std::ops::Range;
+
+use crate::alphabets::Alphabet;
+use crate::data_structures::bwt::{BWTSlice, OccCount};
+use crate::data_structures::rank_select::RsBitVec;
 use bv::BitVec;
 use bv::BitsMut;
 
@@ -33,25 +64,30 @@ const DNA2INT: [u8; 128] = [
     0, 0, 0, 0, 0, 0, 0, 0,
 ]; // 120
 
+/// Symbols of the DNA encoding, in the order of their codes.
+const DNA_SYMBOLS: &[u8] = b"ACGTN$67";
+
 #[derive(Serialize, Deserialize)]
 pub struct WaveletMatrix {
     width: usize,  // levels[0].len()
     height: usize, // zeros.len() == levels.len()
     zeros: Vec<u64>,
-    levels: Vec<RankSelect>,
+    levels: Vec<RsBitVec>,
+    codes: Vec<Option<u8>>,
+    symbols: Vec<u8>,
 }
 
 fn build_partlevel(
-    vals: &Vec<u8>,
+    vals: &[u8],
     shift: u8,
     next_zeros: &mut Vec<u8>,
     next_ones: &mut Vec<u8>,
-    bits: &mut BitVec<u8>,
+    bits: &mut BitVec<u64>,
     prev_bits: u64,
 ) {
     let mut p = prev_bits;
     for val in vals {
-        let bit = ((DNA2INT[usize::from(*val)] >> shift) & 1) == 1; // get shifted lsb
+        let bit = ((*val >> shift) & 1) == 1; // get shifted lsb
         bits.set_bit(p, bit);
         p += 1;
         if bit {
@@ -64,21 +100,49 @@ fn build_partlevel(
 
 impl WaveletMatrix {
     /// Construct a new instance of the wavelet matrix of given text of length n (DNA alphabet plus sentinel symbol).
+    /// Lowercase symbols are treated like their uppercase counterparts.
     /// Complexity: O(n).
     pub fn new(text: &[u8]) -> Self {
+        let codes = (0..=255usize)
+            .map(|a| DNA2INT.get(a).copied())
+            .collect::<Vec<_>>();
+        // hardcoded for alphabet size <= 8 (ACGTN$)
+        Self::build(text, 3, codes, DNA_SYMBOLS.to_vec())
+    }
+
+    /// Construct a new instance of the wavelet matrix of given text of length n over the given
+    /// alphabet, with symbols ordered by their value.
+    /// Complexity: O(n log σ) with alphabet size σ.
+    pub fn with_alphabet(text: &[u8], alphabet: &Alphabet) -> Self {
+        let symbols: Vec<u8> = alphabet.symbols.iter().map(|a| a as u8).collect();
+        assert!(!symbols.is_empty(), "Expecting non-empty alphabet.");
+        let mut codes = vec![None; 256];
+        for (code, &a) in symbols.iter().enumerate() {
+            codes[a as usize] = Some(code as u8);
+        }
+        let mut height = 1;
+        while (1 << height) < symbols.len() {
+            height += 1;
+        }
+        Self::build(text, height, codes, symbols)
+    }
+
+    fn build(text: &[u8], height: usize, codes: Vec<Option<u8>>, symbols: Vec<u8>) -> Self {
         let width = text.len();
-        let height: usize = 3; // hardcoded for alphabet size <= 8 (ACGTN$)
 
-        let mut curr_zeros: Vec<u8> = text.to_vec().clone();
+        let mut curr_zeros: Vec<u8> = text
+            .iter()
+            .map(|&a| codes[a as usize].expect("Expecting text over the given alphabet."))
+            .collect();
         let mut curr_ones: Vec<u8> = Vec::new();
 
         let mut zeros: Vec<u64> = Vec::new();
-        let mut levels: Vec<RankSelect> = Vec::new();
+        let mut levels: Vec<RsBitVec> = Vec::new();
 
         for level in 0..height {
             let mut next_zeros: Vec<u8> = Vec::with_capacity(width);
             let mut next_ones: Vec<u8> = Vec::with_capacity(width);
-            let mut curr_bits: BitVec<u8> = BitVec::new_fill(false, width as u64);
+            let mut curr_bits: BitVec<u64> = BitVec::new_fill(false, width as u64);
             let shift = (height - level - 1) as u8;
             build_partlevel(
                 &curr_zeros,
@@ -100,7 +164,7 @@ impl WaveletMatrix {
             curr_zeros = next_zeros;
             curr_ones = next_ones;
 
-            let level = RankSelect::new(curr_bits, 1);
+            let level = RsBitVec::new(&curr_bits);
             levels.push(level);
             zeros.push(curr_zeros.len() as u64);
         }
@@ -110,46 +174,194 @@ impl WaveletMatrix {
             height,
             zeros,
             levels,
+            codes,
+            symbols,
         }
     }
 
+    /// Length of the text.
+    pub fn len(&self) -> usize {
+        self.width
+    }
+
+    /// Whether the text is empty.
+    pub fn is_empty(&self) -> bool {
+        self.width == 0
+    }
+
     fn check_overflow(&self, p: u64) -> bool {
         p >= self.width as u64
     }
 
+    fn code(&self, val: u8) -> Option<u64> {
+        self.codes[val as usize].map(u64::from)
+    }
+
     fn prank(&self, level: usize, p: u64, val: u8) -> u64 {
         if p == 0 {
             0
+        } else if val == 0 {
+            self.levels[level].rank_0(p - 1).unwrap()
         } else {
-            if val == 0 {
-                self.levels[level].rank_0(p - 1).unwrap()
-            } else {
-                self.levels[level].rank_1(p - 1).unwrap()
-            }
+            self.levels[level].rank_1(p - 1).unwrap()
+        }
+    }
+
+    /// Map the interval [spos, epos) of the given level to the next level, following the
+    /// given bit.
+    fn descend(&self, level: usize, spos: u64, epos: u64, bit: bool) -> (u64, u64) {
+        if bit {
+            (
+                self.prank(level, spos, 1) + self.zeros[level],
+                self.prank(level, epos, 1) + self.zeros[level],
+            )
+        } else {
+            (self.prank(level, spos, 0), self.prank(level, epos, 0))
         }
     }
 
     /// Compute the number of occurrences of symbol val in the original text up to position p (inclusive).
-    /// Complexity O(1).
+    /// Complexity O(log σ).
     pub fn rank(&self, val: u8, p: u64) -> u64 {
         if self.check_overflow(p) {
             panic!("Invalid p (it must be in range 0..wm_size-1");
         }
-        let height = self.height as usize;
+        let code = match self.code(val) {
+            Some(code) => code,
+            None => return 0,
+        };
         let mut spos = 0;
         let mut epos = p + 1;
-        for level in 0..height {
-            let shift = height - level - 1;
-            let bit = ((DNA2INT[val as usize] >> shift) & 1) == 1; // get shifted lsb
-            if bit {
-                spos = self.prank(level, spos, 1) + self.zeros[level];
-                epos = self.prank(level, epos, 1) + self.zeros[level];
+        for level in 0..self.height {
+            let shift = self.height - level - 1;
+            let bit = ((code >> shift) & 1) == 1; // get shifted lsb
+            let (s, e) = self.descend(level, spos, epos, bit);
+            spos = s;
+            epos = e;
+        }
+        epos - spos
+    }
+
+    /// Return the symbol at position p of the original text.
+    /// For DNA texts, symbols are returned in uppercase.
+    /// Complexity O(log σ).
+    pub fn access(&self, p: u64) -> u8 {
+        if self.check_overflow(p) {
+            panic!("Invalid p (it must be in range 0..wm_size-1");
+        }
+        let mut p = p;
+        let mut code = 0;
+        for level in 0..self.height {
+            let bit = self.levels[level].get(p);
+            code = (code << 1) | bit as usize;
+            let (s, _) = self.descend(level, p, p, bit);
+            p = s;
+        }
+        self.symbols[code]
+    }
+
+    /// Return the position of the j-th occurrence (starting with j = 1) of symbol val in the
+    /// original text, or `None` if there are less than j occurrences.
+    /// Complexity O(log σ).
+    pub fn select(&self, val: u8, j: u64) -> Option<u64> {
+        let code = self.code(val)?;
+        if j == 0 {
+            return None;
+        }
+        let mut spos = 0;
+        let mut epos = self.width as u64;
+        for level in 0..self.height {
+            let bit = ((code >> (self.height - level - 1)) & 1) == 1;
+            let (s, e) = self.descend(level, spos, epos, bit);
+            spos = s;
+            epos = e;
+        }
+        if j > epos - spos {
+            return None;
+        }
+
+        // walk back up from the j-th entry of the symbol in the last level
+        let mut p = spos + j - 1;
+        for level in (0..self.height).rev() {
+            let bit = ((code >> (self.height - level - 1)) & 1) == 1;
+            p = if bit {
+                self.levels[level].select_1(p - self.zeros[level] + 1)
             } else {
-                spos = self.prank(level, spos, 0);
-                epos = self.prank(level, epos, 0);
+                self.levels[level].select_0(p + 1)
             }
+            .unwrap();
         }
-        epos - spos
+        Some(p)
+    }
+
+    /// Return the k-th smallest symbol (starting with k = 0) in the given range of the
+    /// original text, or `None` if the range contains no more than k symbols.
+    /// Symbols are ordered by their code, i.e. by value or as A, C, G, T, N, $ for DNA.
+    /// Complexity O(log σ).
+    pub fn quantile(&self, range: Range<u64>, k: u64) -> Option<u8> {
+        assert!(range.end <= self.width as u64, "Invalid range.");
+        if range.start >= range.end || k >= range.end - range.start {
+            return None;
+        }
+        let (mut spos, mut epos) = (range.start, range.end);
+        let mut k = k;
+        let mut code = 0;
+        for level in 0..self.height {
+            let (zs, ze) = self.descend(level, spos, epos, false);
+            let zero_count = ze - zs;
+            let bit = k >= zero_count;
+            if bit {
+                k -= zero_count;
+            }
+            code = (code << 1) | bit as usize;
+            let (s, e) = self.descend(level, spos, epos, bit);
+            spos = s;
+            epos = e;
+        }
+        Some(self.symbols[code])
+    }
+
+    /// Count the symbols of the given range of the original text whose codes are smaller than
+    /// the given one.
+    fn count_less(&self, range: Range<u64>, code: u64) -> u64 {
+        if code >= 1 << self.height {
+            return range.end - range.start;
+        }
+        let (mut spos, mut epos) = (range.start, range.end);
+        let mut count = 0;
+        for level in 0..self.height {
+            let bit = ((code >> (self.height - level - 1)) & 1) == 1;
+            if bit {
+                let (zs, ze) = self.descend(level, spos, epos, false);
+                count += ze - zs;
+            }
+            let (s, e) = self.descend(level, spos, epos, bit);
+            spos = s;
+            epos = e;
+        }
+        count
+    }
+
+    /// Count the symbols in the given range of the original text that lie between the symbols
+    /// lo and hi (inclusive), in the order used by `WaveletMatrix::quantile`.
+    /// Complexity O(log σ).
+    pub fn range_count(&self, range: Range<u64>, lo: u8, hi: u8) -> u64 {
+        assert!(range.end <= self.width as u64, "Invalid range.");
+        if range.start >= range.end {
+            return 0;
+        }
+        let lo = self.code(lo).expect("Symbol not in alphabet.");
+        let hi = self.code(hi).expect("Symbol not in alphabet.");
+        if lo > hi {
+            return 0;
+        }
+        self.count_less(range.clone(), hi + 1) - self.count_less(range, lo)
+    }
+}
+
+impl OccCount for WaveletMatrix {
+    fn occ(&self, _bwt: &BWTSlice, r: usize, a: u8) -> usize {
+        self.rank(a, r as u64) as usize
     }
 }
 
@@ -179,7 +391,7 @@ mod tests {
         for level in 0..wm.height {
             assert_eq!(wm.zeros[level], zeros[level]);
             for i in 0..wm.width {
-                assert_eq!(wm.levels[level].bits().get(i as u64), levels[level][i]);
+                assert_eq!(wm.levels[level].get(i as u64), levels[level][i]);
             }
         }
     }
@@ -206,7 +418,7 @@ mod tests {
         for level in 0..wm.height {
             assert_eq!(wm.zeros[level], zeros[level]);
             for i in 0..wm.width {
-                assert_eq!(wm.levels[level].bits().get(i as u64), levels[level][i]);
+                assert_eq!(wm.levels[level].get(i as u64), levels[level][i]);
             }
         }
     }
@@ -296,4 +508,91 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_wm_queries_against_naive() {
+        use crate::utils::testing::random_seq;
+        use rand::prelude::*;
+
+        let mut rng = StdRng::seed_from_u64(42);
+        for &symbols in &[&b"ACGTN$"[..], b"ACDEFGHIKLMNPQRSTVWY", b"01"] {
+            let text = random_seq(&mut rng, symbols, 300);
+            let wm = if symbols == b"ACGTN$" {
+                WaveletMatrix::new(&text)
+            } else {
+                WaveletMatrix::with_alphabet(&text, &Alphabet::new(symbols))
+            };
+
+            for (p, &a) in text.iter().enumerate() {
+                assert_eq!(wm.access(p as u64), a);
+            }
+            for &a in symbols {
+                let positions: Vec<u64> = (0..text.len() as u64)
+                    .filter(|&p| text[p as usize] == a)
+                    .collect();
+                for (j, &p) in positions.iter().enumerate() {
+                    assert_eq!(wm.select(a, j as u64 + 1), Some(p));
+                    assert_eq!(wm.rank(a, p), j as u64 + 1);
+                }
+                assert_eq!(wm.select(a, positions.len() as u64 + 1), None);
+            }
+            for _ in 0..100 {
+                let start = rng.gen_range(0..text.len() as u64);
+                let end = rng.gen_range(start..=text.len() as u64);
+                let mut window = text[start as usize..end as usize].to_vec();
+                window.sort_by_key(|&a| wm.code(a));
+                for (k, &a) in window.iter().enumerate() {
+                    assert_eq!(wm.quantile(start..end, k as u64), Some(a));
+                }
+                assert_eq!(wm.quantile(start..end, window.len() as u64), None);
+
+                let lo = *symbols.choose(&mut rng).unwrap();
+                let hi = *symbols.choose(&mut rng).unwrap();
+                let count = window
+                    .iter()
+                    .filter(|&&a| wm.code(lo) <= wm.code(a) && wm.code(a) <= wm.code(hi))
+                    .count();
+                assert_eq!(wm.range_count(start..end, lo, hi), count as u64);
+            }
+        }
+    }
+
+    #[test]
+    fn test_wm_as_occ() {
+        use crate::alphabets::dna;
+        use crate::data_structures::bwt::{bwt, Occ};
+        use crate::data_structures::suffix_array::suffix_array;
+
+        let text = b"GCCTTAACATTATTACGCCTA$ATCCGTAATATAG$";
+        let bwt = bwt(text, &suffix_array(text));
+        let occ = Occ::new(&bwt, 3, &dna::n_alphabet());
+        let wm = WaveletMatrix::new(&bwt);
+        for r in 0..bwt.len() {
+            for &a in b"ACGT$" {
+                assert_eq!(wm.rank(a, r as u64) as usize, occ.get(&bwt, r, a));
+            }
+        }
+    }
+
+    #[test]
+    fn test_wm_in_fmindex() {
+        use crate::alphabets::dna;
+        use crate::data_structures::bwt::{bwt, less, Occ};
+        use crate::data_structures::fmindex::{FMIndex, FMIndexable};
+        use crate::data_structures::suffix_array::suffix_array;
+
+        let text = b"GCCTTAACATTATTACGCCTA$";
+        let alphabet = dna::n_alphabet();
+        let sa = suffix_array(text);
+        let bwt = bwt(text, &sa);
+        let less = less(&bwt, &alphabet);
+        let occ = Occ::new(&bwt, 3, &alphabet);
+        let wm = WaveletMatrix::with_alphabet(&bwt, &Alphabet::new(&bwt));
+        let fm = FMIndex::new(&bwt, &less, &occ);
+        let fm_wm = FMIndex::new(&bwt, &less, wm);
+        for pattern in [&b"TTA"[..], b"GCC", b"A", b"TAC", b"CCCC", b"N"].iter() {
+            let expected = fm.backward_search(pattern.iter()).occ(&sa);
+            assert_eq!(fm_wm.backward_search(pattern.iter()).occ(&sa), expected);
+        }
+    }
 }
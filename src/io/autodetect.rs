@@ -0,0 +1,425 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Automatic detection of file formats, for tools that accept "any sequence file".
+//!
+//! [`open`](fn.open.html) inspects the first bytes of a file to determine whether it is
+//! gzip or BGZF compressed, and the first lines of the (decompressed) content to determine
+//! whether it is FASTA, FASTQ, SAM, VCF, BED or GFF/GTF. It returns a [`Reader`](enum.Reader.html)
+//! that wraps the matching reader of this crate. SAM and VCF have no parser in this crate
+//! (see [rust-htslib](https://docs.rs/rust-htslib)), so for those the decompressed input
+//! is returned as is.
+//!
+//! Compressed input is decompressed on the fly while reading.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::io::autodetect;
+//!
+//! let data: &[u8] = b"@read1\nACGT\n+\nIIII\n";
+//! match autodetect::from_reader(data).unwrap() {
+//!     autodetect::Reader::Fastq(reader) => {
+//!         let record = reader.records().next().unwrap().unwrap();
+//!         assert_eq!(record.seq(), b"ACGT");
+//!     }
+//!     _ => panic!("expected FASTQ"),
+//! }
+//! ```
+
+use std::fs;
+use std::io;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use std::str;
+
+use flate2::read::MultiGzDecoder;
+use thiserror::Error;
+
+use crate::io::{bed, bgzf, fasta, fastq, gff};
+
+/// Maximum number of bytes inspected to detect compression and format.
+const SNIFF_LEN: usize = 64 * 1024;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("can't open {path} file: {source}")]
+    FileOpen { path: PathBuf, source: io::Error },
+
+    #[error("can't read input")]
+    ReadError(#[from] io::Error),
+
+    #[error("unable to detect the file format")]
+    UnknownFormat,
+}
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Compression of a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Uncompressed,
+    /// Gzip, including concatenated gzip members.
+    Gzip,
+    /// Blocked gzip, see [`io::bgzf`](../bgzf/index.html).
+    Bgzf,
+}
+
+/// A detected file format.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Fasta,
+    Fastq,
+    Sam,
+    Vcf,
+    Bed,
+    /// GFF3, GFF2 or GTF2, as indicated by the `##gff-version` directive or the style of
+    /// the attribute column.
+    Gff(gff::GffType),
+}
+
+/// The decompressed input of a [`Reader`](enum.Reader.html).
+pub type Input = Box<dyn BufRead + Send>;
+
+/// A reader for the detected file format.
+pub enum Reader {
+    Fasta(fasta::Reader<Input>),
+    Fastq(fastq::Reader<Input>),
+    Bed(bed::Reader<Input>),
+    Gff(gff::Reader<Input>),
+    /// SAM input, starting at the first line.
+    Sam(Input),
+    /// VCF input, starting at the first line.
+    Vcf(Input),
+}
+
+/// Open the given file, detecting its compression and format.
+pub fn open<P: AsRef<Path>>(path: P) -> Result<Reader> {
+    let file = fs::File::open(&path).map_err(|source| Error::FileOpen {
+        path: path.as_ref().to_owned(),
+        source,
+    })?;
+    from_reader(file)
+}
+
+/// Detect compression and format of the given input.
+pub fn from_reader<R: Read + Send + 'static>(reader: R) -> Result<Reader> {
+    let (head, reader) = peek(reader)?;
+    let decompressed: Input = match detect_compression(&head) {
+        Compression::Uncompressed => Box::new(io::BufReader::new(reader)),
+        Compression::Gzip => Box::new(io::BufReader::new(MultiGzDecoder::new(reader))),
+        Compression::Bgzf => Box::new(bgzf::Reader::new(reader)),
+    };
+
+    let (head, reader) = peek(decompressed)?;
+    let input: Input = Box::new(io::BufReader::new(reader));
+    Ok(match detect_format(&head).ok_or(Error::UnknownFormat)? {
+        Format::Fasta => Reader::Fasta(fasta::Reader::new(input)),
+        Format::Fastq => Reader::Fastq(fastq::Reader::new(input)),
+        Format::Bed => Reader::Bed(bed::Reader::new(input)),
+        Format::Gff(gff_type) => Reader::Gff(gff::Reader::new(input, gff_type)),
+        Format::Sam => Reader::Sam(input),
+        Format::Vcf => Reader::Vcf(input),
+    })
+}
+
+/// A reader yielding previously inspected bytes, followed by the rest of the input.
+type Peeked<R> = io::Chain<io::Cursor<Vec<u8>>, R>;
+
+/// Read up to `SNIFF_LEN` bytes, and return them together with a reader that yields the
+/// complete input again.
+fn peek<R: Read>(mut reader: R) -> io::Result<(Vec<u8>, Peeked<R>)> {
+    let mut head = Vec::with_capacity(SNIFF_LEN);
+    (&mut reader)
+        .take(SNIFF_LEN as u64)
+        .read_to_end(&mut head)?;
+    Ok((head.clone(), io::Cursor::new(head).chain(reader)))
+}
+
+/// Detect the compression from the first bytes of a file.
+///
+/// # Example
+///
+/// ```
+/// use bio::io::autodetect::{detect_compression, Compression};
+///
+/// assert_eq!(detect_compression(b">seq1\nACGT\n"), Compression::Uncompressed);
+/// let gzip_header = [0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03];
+/// assert_eq!(detect_compression(&gzip_header), Compression::Gzip);
+/// ```
+pub fn detect_compression(head: &[u8]) -> Compression {
+    if head.len() < 10 || head[..3] != [0x1f, 0x8b, 0x08] {
+        return Compression::Uncompressed;
+    }
+    // BGZF blocks are gzip members with a `BC` extra subfield
+    if head[3] & 0x04 != 0 && head.len() >= 12 {
+        let xlen = u16::from_le_bytes([head[10], head[11]]) as usize;
+        let mut extra = &head[12..(12 + xlen).min(head.len())];
+        while extra.len() >= 4 {
+            if extra[..2] == [b'B', b'C'] {
+                return Compression::Bgzf;
+            }
+            let slen = u16::from_le_bytes([extra[2], extra[3]]) as usize;
+            extra = &extra[(4 + slen).min(extra.len())..];
+        }
+    }
+    Compression::Gzip
+}
+
+/// Detect the format from the first lines of a (decompressed) file.
+///
+/// Returns `None` if the format can not be recognized.
+///
+/// # Example
+///
+/// ```
+/// use bio::io::autodetect::{detect_format, Format};
+/// use bio::io::gff::GffType;
+///
+/// assert_eq!(detect_format(b">seq1\nACGT\n"), Some(Format::Fasta));
+/// assert_eq!(detect_format(b"@HD\tVN:1.6\n"), Some(Format::Sam));
+/// assert_eq!(detect_format(b"chr1\t10\t20\n"), Some(Format::Bed));
+/// assert_eq!(
+///     detect_format(b"##gff-version 3\n"),
+///     Some(Format::Gff(GffType::GFF3))
+/// );
+/// ```
+pub fn detect_format(head: &[u8]) -> Option<Format> {
+    let lines = head
+        .split(|&b| b == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .filter(|line| !line.is_empty());
+    for line in lines {
+        match line[0] {
+            b'>' => return Some(Format::Fasta),
+            b'@' => {
+                return Some(if is_sam_header(line) {
+                    Format::Sam
+                } else {
+                    Format::Fastq
+                })
+            }
+            b'#' => {
+                if line.starts_with(b"##fileformat=VCF") || line.starts_with(b"#CHROM\t") {
+                    return Some(Format::Vcf);
+                }
+                if line.starts_with(b"##gff-version 3") {
+                    return Some(Format::Gff(gff::GffType::GFF3));
+                }
+                // other comments and directives, e.g. of GFF2, are decided by the first
+                // data line
+            }
+            _ => {
+                if line.starts_with(b"track") || line.starts_with(b"browser") {
+                    return Some(Format::Bed);
+                }
+                return detect_tabular(line);
+            }
+        }
+    }
+    None
+}
+
+/// SAM header lines start with a two-letter record type, e.g. `@HD` or `@SQ`.
+fn is_sam_header(line: &[u8]) -> bool {
+    line.len() >= 4
+        && line[1].is_ascii_uppercase()
+        && line[2].is_ascii_uppercase()
+        && line[3] == b'\t'
+}
+
+fn is_integer(field: &str) -> bool {
+    field.parse::<u64>().is_ok()
+}
+
+fn is_cigar(field: &str) -> bool {
+    field == "*"
+        || (!field.is_empty()
+            && field
+                .bytes()
+                .all(|b| b.is_ascii_digit() || b"MIDNSHP=X".contains(&b)))
+}
+
+/// Distinguish the tab-separated formats by the content of their first data line.
+fn detect_tabular(line: &[u8]) -> Option<Format> {
+    let fields: Vec<&str> = str::from_utf8(line).ok()?.split('\t').collect();
+    if fields.len() >= 11
+        && is_integer(fields[1])
+        && is_integer(fields[3])
+        && is_integer(fields[4])
+        && is_cigar(fields[5])
+    {
+        return Some(Format::Sam);
+    }
+    if fields.len() == 9
+        && is_integer(fields[3])
+        && is_integer(fields[4])
+        && ["+", "-", ".", "?"].contains(&fields[6])
+        && [".", "0", "1", "2"].contains(&fields[7])
+    {
+        let attributes = fields[8];
+        let gff_type = if attributes.contains("gene_id \"") {
+            gff::GffType::GTF2
+        } else if attributes.contains('=') {
+            gff::GffType::GFF3
+        } else {
+            gff::GffType::GFF2
+        };
+        return Some(Format::Gff(gff_type));
+    }
+    if fields.len() >= 3 && is_integer(fields[1]) && is_integer(fields[2]) {
+        return Some(Format::Bed);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use flate2::write::GzEncoder;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    /// A reader that counts the bytes read from it.
+    struct Counting<R> {
+        inner: R,
+        count: Arc<AtomicUsize>,
+    }
+
+    impl<R: Read> Read for Counting<R> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.inner.read(buf)?;
+            self.count.fetch_add(n, Ordering::Relaxed);
+            Ok(n)
+        }
+    }
+
+    fn bgzip(data: &[u8]) -> Vec<u8> {
+        let mut writer = bgzf::Writer::new(Vec::new());
+        writer.write_all(data).unwrap();
+        writer.finish().unwrap()
+    }
+
+    const FASTA: &[u8] = b">id desc\nACCGTAGGCTGA\n";
+    const BED: &[u8] = b"track name=test\nchr1\t5\t5000\tname1\t0.5\n";
+    const SAM: &[u8] = b"read1\t0\tchr1\t100\t60\t4M\t*\t0\t0\tACGT\tIIII\n";
+    const VCF: &[u8] = b"##fileformat=VCFv4.2\n#CHROM\tPOS\tID\tREF\tALT\n";
+    const GTF: &[u8] = b"chr1\tHAVANA\tgene\t11869\t14409\t.\t+\t.\tgene_id \"ENSG00000223972\";\n";
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(detect_format(FASTA), Some(Format::Fasta));
+        assert_eq!(
+            detect_format(b"\n@r1\nACGT\n+\nIIII\n"),
+            Some(Format::Fastq)
+        );
+        assert_eq!(detect_format(BED), Some(Format::Bed));
+        assert_eq!(detect_format(SAM), Some(Format::Sam));
+        assert_eq!(detect_format(VCF), Some(Format::Vcf));
+        assert_eq!(detect_format(GTF), Some(Format::Gff(gff::GffType::GTF2)));
+        assert_eq!(
+            detect_format(b"# comment\nP0A7B8\tUniProtKB\tChain\t1\t176\t.\t.\t.\tID=PRO_1\n"),
+            Some(Format::Gff(gff::GffType::GFF3))
+        );
+        assert_eq!(detect_format(b"just some text\n"), None);
+        assert_eq!(detect_format(b""), None);
+    }
+
+    #[test]
+    fn test_detect_compression() {
+        assert_eq!(detect_compression(FASTA), Compression::Uncompressed);
+        assert_eq!(detect_compression(&gzip(FASTA)), Compression::Gzip);
+        assert_eq!(detect_compression(&bgzip(FASTA)), Compression::Bgzf);
+    }
+
+    #[test]
+    fn test_read_compressed_fasta() {
+        for compressed in [FASTA.to_vec(), gzip(FASTA), bgzip(FASTA)] {
+            match from_reader(io::Cursor::new(compressed)).unwrap() {
+                Reader::Fasta(reader) => {
+                    let records: Vec<_> = reader.records().map(|r| r.unwrap()).collect();
+                    assert_eq!(records.len(), 1);
+                    assert_eq!(records[0].id(), "id");
+                    assert_eq!(records[0].seq(), b"ACCGTAGGCTGA");
+                }
+                _ => panic!("expected FASTA"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_read_concatenated_gzip_members() {
+        let mut data = gzip(b"chr1\t5\t5000\n");
+        data.extend(gzip(b"chr2\t10\t20\n"));
+        match from_reader(io::Cursor::new(data)).unwrap() {
+            Reader::Bed(mut reader) => {
+                let chroms: Vec<_> = reader
+                    .records()
+                    .map(|r| r.unwrap().chrom().to_owned())
+                    .collect();
+                assert_eq!(chroms, vec!["chr1", "chr2"]);
+            }
+            _ => panic!("expected BED"),
+        }
+    }
+
+    #[test]
+    fn test_gzip_is_streamed() {
+        let mut fastq = Vec::new();
+        for i in 0..100_000 {
+            fastq.extend(format!("@read{}\nACGT\n+\nIIII\n", i).bytes());
+        }
+        let compressed = gzip(&fastq);
+        let count = Arc::new(AtomicUsize::new(0));
+        let reader = Counting {
+            inner: io::Cursor::new(compressed.clone()),
+            count: count.clone(),
+        };
+        match from_reader(reader).unwrap() {
+            Reader::Fastq(reader) => {
+                let record = reader.records().next().unwrap().unwrap();
+                assert_eq!(record.id(), "read0");
+            }
+            _ => panic!("expected FASTQ"),
+        }
+        assert!(count.load(Ordering::Relaxed) < compressed.len());
+    }
+
+    #[test]
+    fn test_read_raw_formats() {
+        match from_reader(io::Cursor::new(bgzip(VCF))).unwrap() {
+            Reader::Vcf(mut input) => {
+                let mut content = Vec::new();
+                input.read_to_end(&mut content).unwrap();
+                assert_eq!(content, VCF);
+            }
+            _ => panic!("expected VCF"),
+        }
+        assert!(matches!(from_reader(SAM).unwrap(), Reader::Sam(_)));
+        assert!(matches!(from_reader(GTF).unwrap(), Reader::Gff(_)));
+    }
+
+    #[test]
+    fn test_unknown_format() {
+        assert!(matches!(
+            from_reader(&b"\x00\x01\x02"[..]),
+            Err(Error::UnknownFormat)
+        ));
+    }
+
+    #[test]
+    fn test_open_missing_file() {
+        assert!(matches!(
+            open("/I/dont/exist.fa.gz"),
+            Err(Error::FileOpen { .. })
+        ));
+    }
+}
@@ -0,0 +1,310 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Reading and writing of the [BGZF](https://samtools.github.io/hts-specs/SAMv1.pdf)
+//! (blocked GNU zip format) compression format.
+//!
+//! BGZF files are a series of gzip members of at most 64 KiB each, which makes them
+//! readable by any gzip decompressor while allowing random access: a position in the
+//! uncompressed data is addressed by a [`VirtualOffset`](struct.VirtualOffset.html),
+//! combining the offset of a compressed block in the file with an offset into the
+//! uncompressed data of that block. BGZF is the basis of BAM, BCF and tabix-indexed files.
+//!
+//! The [`Writer`](struct.Writer.html) can compress blocks in parallel on a pool of threads,
+//! and terminates its output with the empty BGZF end-of-file block. Blocks are compressed
+//! and decompressed with [flate2](https://docs.rs/flate2).
+//! The [`Reader`](struct.Reader.html) implements `io::BufRead`, so that it can be wrapped
+//! by any of the readers in this crate.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::io::{bgzf, fasta};
+//!
+//! // bgzip FASTA output, using two compression threads
+//! let mut bgzf_writer = bgzf::Writer::with_threads(Vec::new(), 2);
+//! let mut writer = fasta::Writer::new(&mut bgzf_writer);
+//! writer.write("chr1", None, b"ACGTACGTACGT").unwrap();
+//! writer.flush().unwrap();
+//! drop(writer);
+//! let compressed = bgzf_writer.finish().unwrap();
+//!
+//! let reader = fasta::Reader::new(bgzf::Reader::new(&compressed[..]));
+//! let record = reader.records().next().unwrap().unwrap();
+//! assert_eq!(record.seq(), b"ACGTACGTACGT");
+//! ```
+
+mod reader;
+mod writer;
+
+pub use self::reader::Reader;
+pub use self::writer::Writer;
+
+use std::io;
+use std::io::prelude::*;
+
+use flate2::write::DeflateEncoder;
+use flate2::{Crc, Decompress, FlushDecompress, Status};
+
+/// Maximum number of uncompressed bytes per block. Slightly less than 64 KiB, so that
+/// even incompressible data fits into a block of at most 64 KiB.
+pub(crate) const MAX_BLOCK_DATA: usize = 0xff00;
+
+/// Length of the header of a BGZF block (a gzip header with a single `BC` extra field).
+const HEADER_LEN: usize = 18;
+
+/// Length of the fixed part of a gzip header, up to and including `XLEN`.
+const GZIP_HEADER_LEN: usize = 12;
+
+/// The empty block terminating a BGZF file.
+pub(crate) const EOF_BLOCK: [u8; 28] = [
+    0x1f, 0x8b, 0x08, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x06, 0x00, 0x42, 0x43, 0x02, 0x00,
+    0x1b, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+/// A virtual file offset into a BGZF file.
+///
+/// The upper 48 bits hold the offset of a compressed block in the file, the lower 16 bits
+/// the offset into the uncompressed data of that block.
+///
+/// # Example
+///
+/// ```
+/// use bio::io::bgzf::VirtualOffset;
+///
+/// let offset = VirtualOffset::new(4096, 17);
+/// assert_eq!(offset.compressed_offset(), 4096);
+/// assert_eq!(offset.uncompressed_offset(), 17);
+/// assert_eq!(u64::from(offset), 4096 << 16 | 17);
+/// ```
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize,
+)]
+pub struct VirtualOffset(u64);
+
+impl VirtualOffset {
+    /// Create a new virtual offset from the offset of a compressed block and the offset
+    /// into the uncompressed data of that block.
+    pub fn new(compressed_offset: u64, uncompressed_offset: u16) -> Self {
+        VirtualOffset(compressed_offset << 16 | uncompressed_offset as u64)
+    }
+
+    /// Offset of the compressed block in the file.
+    pub fn compressed_offset(self) -> u64 {
+        self.0 >> 16
+    }
+
+    /// Offset into the uncompressed data of the block.
+    pub fn uncompressed_offset(self) -> u16 {
+        self.0 as u16
+    }
+}
+
+impl From<u64> for VirtualOffset {
+    fn from(offset: u64) -> Self {
+        VirtualOffset(offset)
+    }
+}
+
+impl From<VirtualOffset> for u64 {
+    fn from(offset: VirtualOffset) -> Self {
+        offset.0
+    }
+}
+
+/// CRC-32 checksum as used by gzip.
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    let mut crc = Crc::new();
+    crc.update(data);
+    crc.sum()
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Compress `data` (at most `MAX_BLOCK_DATA` bytes) into a complete BGZF block.
+pub(crate) fn compress_block(data: &[u8]) -> Vec<u8> {
+    debug_assert!(data.len() <= MAX_BLOCK_DATA);
+    let mut block = Vec::with_capacity(HEADER_LEN + data.len() / 2 + 8);
+    block.extend_from_slice(&EOF_BLOCK[..HEADER_LEN]);
+    let mut encoder = DeflateEncoder::new(block, flate2::Compression::default());
+    encoder
+        .write_all(data)
+        .expect("bug: writing to a Vec cannot fail");
+    let mut block = encoder.finish().expect("bug: writing to a Vec cannot fail");
+    block.extend_from_slice(&crc32(data).to_le_bytes());
+    block.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    let bsize = (block.len() - 1) as u16;
+    block[16..18].copy_from_slice(&bsize.to_le_bytes());
+    block
+}
+
+/// Decompress the raw deflate stream at the start of `data`, appending the result to `out`.
+///
+/// Returns the number of bytes of `data` taken by the stream.
+fn inflate(data: &[u8], out: &mut Vec<u8>) -> io::Result<usize> {
+    let mut decompress = Decompress::new(false);
+    loop {
+        if out.len() == out.capacity() {
+            out.reserve(MAX_BLOCK_DATA);
+        }
+        let consumed = decompress.total_in() as usize;
+        let status = decompress
+            .decompress_vec(&data[consumed..], out, FlushDecompress::Finish)
+            .map_err(|e| invalid_data(&e.to_string()))?;
+        match status {
+            Status::StreamEnd => return Ok(decompress.total_in() as usize),
+            // more output space is needed, unless the input is exhausted
+            _ if out.len() < out.capacity() => {
+                return Err(invalid_data("truncated deflate stream"))
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Fill `buf` completely, or return `false` if the reader is exhausted before the first byte.
+fn read_exact_or_eof<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<bool> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "truncated BGZF block",
+                ))
+            }
+            Ok(n) => filled += n,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => (),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(true)
+}
+
+/// Read the next BGZF block from `reader`, decompressing its content into `out`
+/// (which is cleared first). `compressed` serves as scratch buffer.
+///
+/// Returns the size of the compressed block, or `None` if the reader is exhausted.
+pub(crate) fn read_block<R: Read>(
+    reader: &mut R,
+    compressed: &mut Vec<u8>,
+    out: &mut Vec<u8>,
+) -> io::Result<Option<usize>> {
+    out.clear();
+    compressed.resize(GZIP_HEADER_LEN, 0);
+    if !read_exact_or_eof(reader, &mut compressed[..])? {
+        return Ok(None);
+    }
+    if compressed[0..4] != [0x1f, 0x8b, 0x08, 0x04] {
+        return Err(invalid_data("invalid BGZF block header"));
+    }
+    let xlen = u16::from_le_bytes([compressed[10], compressed[11]]) as usize;
+    compressed.resize(GZIP_HEADER_LEN + xlen, 0);
+    reader.read_exact(&mut compressed[GZIP_HEADER_LEN..])?;
+
+    // search the extra subfields for the BGZF block size
+    let mut bsize = None;
+    let mut extra = &compressed[GZIP_HEADER_LEN..];
+    while extra.len() >= 4 {
+        let slen = u16::from_le_bytes([extra[2], extra[3]]) as usize;
+        if extra[..2] == [b'B', b'C'] && slen == 2 && extra.len() >= 6 {
+            bsize = Some(u16::from_le_bytes([extra[4], extra[5]]) as usize + 1);
+        }
+        extra = &extra[(4 + slen).min(extra.len())..];
+    }
+    let bsize = bsize.ok_or_else(|| invalid_data("missing BGZF block size"))?;
+    let data_start = GZIP_HEADER_LEN + xlen;
+    if bsize < data_start + 8 {
+        return Err(invalid_data("invalid BGZF block size"));
+    }
+    compressed.resize(bsize, 0);
+    reader.read_exact(&mut compressed[data_start..])?;
+
+    let footer = &compressed[bsize - 8..];
+    let crc = u32::from_le_bytes([footer[0], footer[1], footer[2], footer[3]]);
+    let isize = u32::from_le_bytes([footer[4], footer[5], footer[6], footer[7]]) as usize;
+    out.reserve(isize);
+    inflate(&compressed[data_start..bsize - 8], out)?;
+    if out.len() != isize || crc32(out) != crc {
+        return Err(invalid_data("BGZF block checksum mismatch"));
+    }
+    Ok(Some(bsize))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::random_seq;
+    use rand::prelude::*;
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn test_block_roundtrip() {
+        let data = b"ACGTACGTACGTNNNNNNNNNNNNNNNNNNNNacgt".repeat(100);
+        let block = compress_block(&data);
+        let mut out = Vec::new();
+        let size = read_block(&mut &block[..], &mut Vec::new(), &mut out).unwrap();
+        assert_eq!(size, Some(block.len()));
+        assert_eq!(out, data);
+    }
+
+    #[test]
+    fn test_block_roundtrip_random() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let seq = random_seq(&mut rng, b"ACGT", MAX_BLOCK_DATA);
+        let block = compress_block(&seq);
+        // random DNA compresses to roughly two bits per base
+        assert!(block.len() < seq.len() / 3);
+        let mut out = Vec::new();
+        read_block(&mut &block[..], &mut Vec::new(), &mut out).unwrap();
+        assert_eq!(out, seq);
+
+        // incompressible data still fits into a block of at most 64 KiB
+        let mut data = vec![0; MAX_BLOCK_DATA];
+        rng.fill_bytes(&mut data);
+        let block = compress_block(&data);
+        assert!(block.len() <= 1 << 16);
+        read_block(&mut &block[..], &mut Vec::new(), &mut out).unwrap();
+        assert_eq!(out, data);
+    }
+
+    #[test]
+    fn test_inflate_zlib_output() {
+        // raw deflate stream of b"ACGTACGTACGTACGTNNNNNNNNNNACGTTTTT" as produced by zlib
+        let data = [
+            0x73, 0x74, 0x76, 0x0f, 0x71, 0x44, 0xc2, 0x7e, 0x70, 0x00, 0xe2, 0x81, 0x00, 0x00,
+        ];
+        let mut out = Vec::new();
+        assert_eq!(inflate(&data, &mut out).unwrap(), data.len());
+        assert_eq!(out, b"ACGTACGTACGTACGTNNNNNNNNNNACGTTTTT".to_vec());
+        assert!(inflate(&data[..5], &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_eof_block() {
+        assert_eq!(compress_block(b""), EOF_BLOCK.to_vec());
+        let mut out = vec![1, 2, 3];
+        let size = read_block(&mut &EOF_BLOCK[..], &mut Vec::new(), &mut out).unwrap();
+        assert_eq!(size, Some(EOF_BLOCK.len()));
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn test_corrupt_block() {
+        let mut block = compress_block(b"GATTACA");
+        let n = block.len();
+        block[n - 5] ^= 0xff;
+        assert!(read_block(&mut &block[..], &mut Vec::new(), &mut Vec::new()).is_err());
+        assert!(read_block(&mut &block[..10], &mut Vec::new(), &mut Vec::new()).is_err());
+    }
+}
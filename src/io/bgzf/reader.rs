@@ -0,0 +1,289 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::convert::AsRef;
+use std::fs;
+use std::io;
+use std::io::prelude::*;
+use std::path::Path;
+
+use anyhow::Context;
+
+use super::{read_block, VirtualOffset};
+
+/// A BGZF reader, decompressing one block at a time.
+///
+/// # Example
+///
+/// ```
+/// use bio::io::bgzf;
+/// use std::io::{BufRead, Write};
+///
+/// let mut writer = bgzf::Writer::new(Vec::new());
+/// writer.write_all(b"line 1\nline 2\n").unwrap();
+/// let compressed = writer.finish().unwrap();
+///
+/// let reader = bgzf::Reader::new(&compressed[..]);
+/// let lines: Vec<String> = reader.lines().map(|l| l.unwrap()).collect();
+/// assert_eq!(lines, vec!["line 1", "line 2"]);
+/// ```
+#[derive(Debug)]
+pub struct Reader<R: io::Read> {
+    inner: R,
+    compressed: Vec<u8>,
+    block: Vec<u8>,
+    pos: usize,
+    block_offset: u64,
+    next_block_offset: u64,
+}
+
+impl Reader<fs::File> {
+    /// Read from a given file path.
+    pub fn from_file<P: AsRef<Path> + std::fmt::Debug>(path: P) -> anyhow::Result<Self> {
+        fs::File::open(&path)
+            .map(Reader::new)
+            .with_context(|| format!("Failed to read bgzf from {:#?}", path))
+    }
+}
+
+impl<R: io::Read> Reader<R> {
+    /// Read from a given [`io::Read`](https://doc.rust-lang.org/std/io/trait.Read.html),
+    /// which is assumed to be positioned at the start of a BGZF block.
+    pub fn new(reader: R) -> Self {
+        Reader {
+            inner: reader,
+            compressed: Vec::new(),
+            block: Vec::new(),
+            pos: 0,
+            block_offset: 0,
+            next_block_offset: 0,
+        }
+    }
+
+    /// Virtual offset of the next byte that will be read.
+    ///
+    /// If the current block is exhausted, this points to the start of the next block.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::io::bgzf;
+    /// use std::io::{Read, Write};
+    ///
+    /// let mut writer = bgzf::Writer::new(Vec::new());
+    /// writer.write_all(b"ACGT").unwrap();
+    /// let compressed = writer.finish().unwrap();
+    ///
+    /// let mut reader = bgzf::Reader::new(&compressed[..]);
+    /// let mut buf = [0; 3];
+    /// reader.read_exact(&mut buf).unwrap();
+    /// assert_eq!(reader.virtual_offset(), bgzf::VirtualOffset::new(0, 3));
+    /// ```
+    pub fn virtual_offset(&self) -> VirtualOffset {
+        if self.pos < self.block.len() {
+            VirtualOffset::new(self.block_offset, self.pos as u16)
+        } else {
+            VirtualOffset::new(self.next_block_offset, 0)
+        }
+    }
+
+    /// Load the next block. Returns `false` if the input is exhausted.
+    fn next_block(&mut self) -> io::Result<bool> {
+        match read_block(&mut self.inner, &mut self.compressed, &mut self.block)? {
+            Some(size) => {
+                self.block_offset = self.next_block_offset;
+                self.next_block_offset += size as u64;
+                self.pos = 0;
+                Ok(true)
+            }
+            None => {
+                self.pos = 0;
+                self.block_offset = self.next_block_offset;
+                Ok(false)
+            }
+        }
+    }
+
+    /// Unwrap the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: io::Read + io::Seek> Reader<R> {
+    /// Seek to the given virtual offset.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::io::bgzf;
+    /// use std::io::{BufRead, Cursor, Write};
+    ///
+    /// let mut writer = bgzf::Writer::new(Vec::new());
+    /// writer.write_all(b"first\n").unwrap();
+    /// writer.flush().unwrap(); // end the block
+    /// let offset = writer.virtual_offset().unwrap();
+    /// writer.write_all(b"second\n").unwrap();
+    /// let compressed = writer.finish().unwrap();
+    ///
+    /// let mut reader = bgzf::Reader::new(Cursor::new(compressed));
+    /// reader.seek(offset).unwrap();
+    /// let mut line = String::new();
+    /// reader.read_line(&mut line).unwrap();
+    /// assert_eq!(line, "second\n");
+    /// ```
+    pub fn seek(&mut self, offset: VirtualOffset) -> io::Result<()> {
+        let compressed_offset = offset.compressed_offset();
+        self.inner.seek(io::SeekFrom::Start(compressed_offset))?;
+        self.block.clear();
+        self.pos = 0;
+        self.block_offset = compressed_offset;
+        self.next_block_offset = compressed_offset;
+
+        let uncompressed_offset = offset.uncompressed_offset() as usize;
+        if uncompressed_offset > 0 {
+            if !self.next_block()? || uncompressed_offset > self.block.len() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "virtual offset beyond end of block",
+                ));
+            }
+            self.pos = uncompressed_offset;
+        }
+        Ok(())
+    }
+}
+
+impl<R: io::Read> io::Read for Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: io::Read> io::BufRead for Reader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        // skip empty blocks, e.g. EOF markers of concatenated files
+        while self.pos >= self.block.len() {
+            if !self.next_block()? {
+                break;
+            }
+        }
+        Ok(&self.block[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.block.len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Writer, EOF_BLOCK};
+    use super::*;
+
+    use std::io::Cursor;
+
+    #[test]
+    fn test_read_multiple_blocks() {
+        let data: Vec<u8> = (0..200_000).map(|i| b"ACGT"[(i * i) % 4]).collect();
+        let mut writer = Writer::new(Vec::new());
+        writer.write_all(&data).unwrap();
+        let compressed = writer.finish().unwrap();
+
+        let mut reader = Reader::new(&compressed[..]);
+        let mut out = Vec::new();
+        reader.read_to_end(&mut out).unwrap();
+        assert_eq!(out, data);
+        assert_eq!(
+            reader.virtual_offset(),
+            VirtualOffset::new(compressed.len() as u64, 0)
+        );
+    }
+
+    #[test]
+    fn test_read_concatenated() {
+        let mut compressed = Vec::new();
+        for part in &[&b"AAAA"[..], &b"CCCC"[..]] {
+            let mut writer = Writer::new(Vec::new());
+            writer.write_all(part).unwrap();
+            compressed.extend(writer.finish().unwrap());
+        }
+        let mut out = String::new();
+        Reader::new(&compressed[..])
+            .read_to_string(&mut out)
+            .unwrap();
+        assert_eq!(out, "AAAACCCC");
+    }
+
+    #[test]
+    fn test_read_without_eof_block() {
+        let mut writer = Writer::new(Vec::new());
+        writer.write_all(b"GATTACA").unwrap();
+        let mut compressed = writer.finish().unwrap();
+        compressed.truncate(compressed.len() - EOF_BLOCK.len());
+
+        let mut reader = Reader::new(&compressed[..]);
+        let mut out = Vec::new();
+        reader.read_to_end(&mut out).unwrap();
+        assert_eq!(out, b"GATTACA");
+        assert_eq!(
+            reader.virtual_offset(),
+            VirtualOffset::new(compressed.len() as u64, 0)
+        );
+    }
+
+    #[test]
+    fn test_read_empty() {
+        let mut out = Vec::new();
+        Reader::new(&EOF_BLOCK[..]).read_to_end(&mut out).unwrap();
+        assert!(out.is_empty());
+        Reader::new(&b""[..]).read_to_end(&mut out).unwrap();
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn test_seek_records_virtual_offsets() {
+        let mut writer = Writer::new(Vec::new());
+        let mut offsets = Vec::new();
+        for i in 0..5000 {
+            offsets.push(writer.virtual_offset().unwrap());
+            writeln!(writer, "record {}", i).unwrap();
+        }
+        let compressed = writer.finish().unwrap();
+
+        let mut reader = Reader::new(Cursor::new(compressed));
+        for &i in &[4999, 0, 2500, 1234] {
+            reader.seek(offsets[i]).unwrap();
+            assert_eq!(reader.virtual_offset(), offsets[i]);
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            assert_eq!(line, format!("record {}\n", i));
+        }
+    }
+
+    #[test]
+    fn test_seek_beyond_block() {
+        let mut writer = Writer::new(Vec::new());
+        writer.write_all(b"ACGT").unwrap();
+        let compressed = writer.finish().unwrap();
+        let mut reader = Reader::new(Cursor::new(compressed));
+        assert!(reader.seek(VirtualOffset::new(0, 5)).is_err());
+    }
+
+    #[test]
+    fn test_reader_from_file_path_doesnt_exist_returns_err() {
+        let path = Path::new("/I/dont/exist.gz");
+        let error = Reader::from_file(path)
+            .unwrap_err()
+            .downcast::<String>()
+            .unwrap();
+
+        assert_eq!(&error, "Failed to read bgzf from \"/I/dont/exist.gz\"")
+    }
+}
@@ -0,0 +1,321 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::collections::BTreeMap;
+use std::convert::AsRef;
+use std::fs;
+use std::io;
+use std::mem;
+use std::path::Path;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+
+use super::{compress_block, VirtualOffset, EOF_BLOCK, MAX_BLOCK_DATA};
+
+/// A pool of threads compressing blocks, together with the bookkeeping needed to write
+/// the compressed blocks in their original order.
+#[derive(Debug)]
+struct Pool {
+    jobs: Option<mpsc::Sender<(usize, Vec<u8>)>>,
+    results: mpsc::Receiver<(usize, Vec<u8>)>,
+    workers: Vec<thread::JoinHandle<()>>,
+    submitted: usize,
+    written: usize,
+    finished: BTreeMap<usize, Vec<u8>>,
+}
+
+impl Pool {
+    fn new(threads: usize) -> Self {
+        let (jobs, job_receiver) = mpsc::channel::<(usize, Vec<u8>)>();
+        let (result_sender, results) = mpsc::channel();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+        let workers = (0..threads)
+            .map(|_| {
+                let job_receiver = Arc::clone(&job_receiver);
+                let result_sender = result_sender.clone();
+                thread::spawn(move || loop {
+                    let job = job_receiver.lock().unwrap().recv();
+                    match job {
+                        Ok((index, data)) => {
+                            if result_sender.send((index, compress_block(&data))).is_err() {
+                                break;
+                            }
+                        }
+                        Err(_) => break,
+                    }
+                })
+            })
+            .collect();
+        Pool {
+            jobs: Some(jobs),
+            results,
+            workers,
+            submitted: 0,
+            written: 0,
+            finished: BTreeMap::new(),
+        }
+    }
+
+    fn in_flight(&self) -> usize {
+        self.submitted - self.written
+    }
+
+    fn submit(&mut self, data: Vec<u8>) -> io::Result<()> {
+        self.jobs
+            .as_ref()
+            .unwrap()
+            .send((self.submitted, data))
+            .map_err(|_| io::Error::other("BGZF worker thread died"))?;
+        self.submitted += 1;
+        Ok(())
+    }
+
+    /// Wait for the next compressed block and write all blocks that are ready, in order.
+    fn write_next<W: io::Write>(&mut self, inner: &mut W, offset: &mut u64) -> io::Result<()> {
+        let (index, block) = self
+            .results
+            .recv()
+            .map_err(|_| io::Error::other("BGZF worker thread died"))?;
+        self.finished.insert(index, block);
+        while let Some(block) = self.finished.remove(&self.written) {
+            inner.write_all(&block)?;
+            *offset += block.len() as u64;
+            self.written += 1;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Pool {
+    fn drop(&mut self) {
+        // closing the job channel terminates the workers
+        self.jobs.take();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// A BGZF writer.
+///
+/// Data is collected into blocks of up to 65280 bytes, which are compressed either
+/// directly or, if created with [`with_threads`](#method.with_threads), in parallel on a
+/// pool of threads. Call [`finish`](#method.finish) to write the end-of-file block and
+/// retrieve the underlying writer. If the writer is dropped instead, the end-of-file
+/// block is written as well, but errors are ignored.
+///
+/// # Example
+///
+/// ```
+/// use bio::io::bgzf;
+/// use std::io::Write;
+///
+/// let mut writer = bgzf::Writer::with_threads(Vec::new(), 4);
+/// for i in 0..1000 {
+///     writeln!(writer, "chr1\t{}\t{}", i * 100, i * 100 + 50).unwrap();
+/// }
+/// let compressed = writer.finish().unwrap();
+/// assert!(compressed.ends_with(&[0x1b, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]));
+/// ```
+#[derive(Debug)]
+pub struct Writer<W: io::Write> {
+    inner: Option<W>,
+    buffer: Vec<u8>,
+    compressed_offset: u64,
+    pool: Option<Pool>,
+    finished: bool,
+}
+
+impl Writer<fs::File> {
+    /// Write to a given file path.
+    #[allow(clippy::wrong_self_convention)]
+    pub fn to_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        fs::File::create(path).map(Writer::new)
+    }
+}
+
+impl<W: io::Write> Writer<W> {
+    /// Write to a given writer, compressing blocks on the calling thread.
+    pub fn new(writer: W) -> Self {
+        Writer {
+            inner: Some(writer),
+            buffer: Vec::with_capacity(MAX_BLOCK_DATA),
+            compressed_offset: 0,
+            pool: None,
+            finished: false,
+        }
+    }
+
+    /// Write to a given writer, compressing blocks on a pool of `threads` threads.
+    /// With `threads <= 1`, this is equivalent to [`new`](#method.new).
+    pub fn with_threads(writer: W, threads: usize) -> Self {
+        let mut writer = Writer::new(writer);
+        if threads > 1 {
+            writer.pool = Some(Pool::new(threads));
+        }
+        writer
+    }
+
+    /// Virtual offset at which the next written byte will be located.
+    ///
+    /// With multiple threads, this waits until all pending blocks have been written.
+    pub fn virtual_offset(&mut self) -> io::Result<VirtualOffset> {
+        self.write_pending(0)?;
+        Ok(VirtualOffset::new(
+            self.compressed_offset,
+            self.buffer.len() as u16,
+        ))
+    }
+
+    /// Write pending blocks until at most `max_in_flight` blocks remain in the pool.
+    fn write_pending(&mut self, max_in_flight: usize) -> io::Result<()> {
+        if let Some(pool) = self.pool.as_mut() {
+            let inner = self.inner.as_mut().unwrap();
+            while pool.in_flight() > max_in_flight {
+                pool.write_next(inner, &mut self.compressed_offset)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Compress the buffered data into a block.
+    fn emit_block(&mut self) -> io::Result<()> {
+        let data = mem::replace(&mut self.buffer, Vec::with_capacity(MAX_BLOCK_DATA));
+        match self.pool.as_mut() {
+            Some(pool) => {
+                pool.submit(data)?;
+                // bound memory usage by limiting the number of blocks in flight
+                let max_in_flight = 2 * pool.workers.len();
+                self.write_pending(max_in_flight)
+            }
+            None => {
+                let block = compress_block(&data);
+                self.inner.as_mut().unwrap().write_all(&block)?;
+                self.compressed_offset += block.len() as u64;
+                Ok(())
+            }
+        }
+    }
+
+    fn check_not_finished(&self) -> io::Result<()> {
+        if self.finished {
+            Err(io::Error::other("BGZF writer has already been finished"))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Write all remaining data and the end-of-file block.
+    fn try_finish(&mut self) -> io::Result<()> {
+        if self.finished {
+            return Ok(());
+        }
+        if !self.buffer.is_empty() {
+            self.emit_block()?;
+        }
+        self.write_pending(0)?;
+        let inner = self.inner.as_mut().unwrap();
+        inner.write_all(&EOF_BLOCK)?;
+        inner.flush()?;
+        self.compressed_offset += EOF_BLOCK.len() as u64;
+        self.finished = true;
+        Ok(())
+    }
+
+    /// Write all remaining data and the end-of-file block, and return the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.try_finish()?;
+        Ok(self.inner.take().unwrap())
+    }
+}
+
+impl<W: io::Write> io::Write for Writer<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.check_not_finished()?;
+        let n = buf.len().min(MAX_BLOCK_DATA - self.buffer.len());
+        self.buffer.extend_from_slice(&buf[..n]);
+        if self.buffer.len() == MAX_BLOCK_DATA {
+            self.emit_block()?;
+        }
+        Ok(n)
+    }
+
+    /// Compress all buffered data (ending the current block) and flush the underlying writer.
+    fn flush(&mut self) -> io::Result<()> {
+        self.check_not_finished()?;
+        if !self.buffer.is_empty() {
+            self.emit_block()?;
+        }
+        self.write_pending(0)?;
+        self.inner.as_mut().unwrap().flush()
+    }
+}
+
+impl<W: io::Write> Drop for Writer<W> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            let _ = self.try_finish();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Reader;
+    use super::*;
+    use crate::utils::testing::random_seq;
+    use rand::prelude::*;
+
+    use std::io::{Read, Write};
+
+    fn sequence(len: usize) -> Vec<u8> {
+        random_seq(&mut StdRng::seed_from_u64(1), b"ACGT", len)
+    }
+
+    #[test]
+    fn test_threaded_output_equals_single_threaded() {
+        let data = sequence(1_000_000);
+        let mut single = Writer::new(Vec::new());
+        single.write_all(&data).unwrap();
+        let single = single.finish().unwrap();
+        let mut threaded = Writer::with_threads(Vec::new(), 3);
+        threaded.write_all(&data).unwrap();
+        let threaded = threaded.finish().unwrap();
+        assert_eq!(single, threaded);
+
+        let mut out = Vec::new();
+        Reader::new(&threaded[..]).read_to_end(&mut out).unwrap();
+        assert_eq!(out, data);
+    }
+
+    #[test]
+    fn test_empty_output() {
+        let writer = Writer::with_threads(Vec::new(), 2);
+        assert_eq!(writer.finish().unwrap(), EOF_BLOCK.to_vec());
+    }
+
+    #[test]
+    fn test_drop_writes_eof_block() {
+        let mut buf = Vec::new();
+        {
+            let mut writer = Writer::new(&mut buf);
+            writer.write_all(b"ACGT").unwrap();
+        }
+        assert!(buf.ends_with(&EOF_BLOCK));
+        let mut out = Vec::new();
+        Reader::new(&buf[..]).read_to_end(&mut out).unwrap();
+        assert_eq!(out, b"ACGT");
+    }
+
+    #[test]
+    fn test_virtual_offset() {
+        let mut writer = Writer::with_threads(Vec::new(), 2);
+        assert_eq!(writer.virtual_offset().unwrap(), VirtualOffset::new(0, 0));
+        writer.write_all(&sequence(MAX_BLOCK_DATA + 10)).unwrap();
+        let offset = writer.virtual_offset().unwrap();
+        assert!(offset.compressed_offset() > 0);
+        assert_eq!(offset.uncompressed_offset(), 10);
+    }
+}
@@ -0,0 +1,419 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A compact, versioned binary format for storing data structures on disk, such that
+//! heavyweight indexes (suffix arrays, FM-indexes, q-gram indexes, k-mer counts) have to be
+//! built only once. This module requires the `binary` feature.
+//!
+//! Values are encoded with [bincode](https://docs.rs/bincode), i.e. integers and floats in
+//! little-endian byte order (`usize` always taking 8 bytes), sequences, maps and strings
+//! prefixed by their length, and enum variants by their index. Each stored value starts with
+//! a header consisting of the magic bytes `RBIO`, the format version (see `VERSION`) and the
+//! type tag of the value (see `TypeTag`), which names the stored type and the version of its
+//! serialized layout. Reading a value as a different type, or a value whose layout has
+//! changed since it was written, fails with an error instead of yielding garbage.
+//!
+//! Values are always read into owned data structures, i.e. memory-mapped loading is not
+//! supported. The index types of this crate own their arrays (e.g. `FMIndex` borrows a
+//! `Vec<u8>` as BWT, and suffix arrays are vectors), so reading from a mapped file would
+//! still copy every array, and sharing the pages of the file would require borrowed
+//! variants of `BWT`, `Less`, `Occ` and the suffix arrays. Reading with
+//! [`from_file`](fn.from_file.html) is a single buffered pass over the file.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::alphabets::dna;
+//! use bio::data_structures::bwt::{bwt, less, Occ, Less, BWT};
+//! use bio::data_structures::fmindex::{FMIndex, FMIndexable};
+//! use bio::data_structures::suffix_array::{suffix_array, RawSuffixArray};
+//! use bio::io::binary;
+//!
+//! let text = b"GCCTTAACATTATTACGCCTA$";
+//! let alphabet = dna::n_alphabet();
+//! let sa = suffix_array(text);
+//! let bwt = bwt(text, &sa);
+//! let less = less(&bwt, &alphabet);
+//! let occ = Occ::new(&bwt, 3, &alphabet);
+//! let fm = FMIndex::new(bwt, less, occ);
+//!
+//! let mut buffer = Vec::new();
+//! binary::write(&mut buffer, &sa).unwrap();
+//! binary::write(&mut buffer, &fm).unwrap();
+//!
+//! let mut reader = &buffer[..];
+//! let sa: RawSuffixArray = binary::read(&mut reader).unwrap();
+//! let fm: FMIndex<BWT, Less, Occ> = binary::read(&mut reader).unwrap();
+//! let mut positions = fm.backward_search(b"TTA".iter()).occ(&sa);
+//! positions.sort_unstable();
+//! assert_eq!(positions, [3, 9, 12]);
+//! ```
+
+use std::borrow::Borrow;
+use std::convert::TryFrom;
+use std::fs;
+use std::io;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::data_structures::bwt::{DnaOcc, Less, Occ, OccCount, BWT};
+use crate::data_structures::fmindex::{FMDIndex, FMIndex};
+use crate::data_structures::generalized_suffix_array::GeneralizedSuffixArray;
+use crate::data_structures::kmer_count::KmerCounter;
+use crate::data_structures::minhash::Sketch;
+use crate::data_structures::qgram_index::QGramIndex;
+use crate::data_structures::rindex::RIndex;
+use crate::data_structures::suffix_array::{CompactSuffixArray, SampledSuffixArray};
+use crate::data_structures::wavelet_matrix::WaveletMatrix;
+use crate::seq::minimizers::MinimizerIndex;
+
+/// Magic bytes at the start of each stored value.
+pub const MAGIC: &[u8; 4] = b"RBIO";
+/// Version of the header and value encoding written by this module.
+pub const VERSION: u32 = 2;
+/// Maximum length of a type tag, guarding against allocating huge buffers for corrupt input.
+const MAX_TAG_LEN: usize = 1024;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("can't open {path} file: {source}")]
+    FileOpen { path: PathBuf, source: io::Error },
+
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("input does not start with the magic bytes of the binary format")]
+    InvalidMagic,
+
+    #[error("unsupported binary format version {version} (supported: {VERSION})")]
+    UnsupportedVersion { version: u32 },
+
+    #[error("invalid type tag in input")]
+    InvalidTag,
+
+    #[error("input contains a value of type {found}, expected {expected}")]
+    TypeMismatch { expected: String, found: String },
+
+    #[error("invalid encoding of value: {0}")]
+    Encoding(#[from] bincode::Error),
+}
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// A tag identifying a type and the version of its serialized layout, stored along with each
+/// value. The version has to be increased whenever the fields of the type change, such that
+/// values written before cannot be misread. Tags of generic types include the tags of their
+/// type parameters.
+///
+/// # Example
+///
+/// ```
+/// use bio::io::binary::TypeTag;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Contig {
+///     name: String,
+///     seq: Vec<u8>,
+/// }
+///
+/// impl TypeTag for Contig {
+///     fn type_tag() -> String {
+///         "my_crate::Contig/1".to_owned()
+///     }
+/// }
+///
+/// let mut buffer = Vec::new();
+/// let contig = Contig { name: "chr1".to_owned(), seq: b"ACGT".to_vec() };
+/// bio::io::binary::write(&mut buffer, &contig).unwrap();
+/// let contig: Contig = bio::io::binary::read(&buffer[..]).unwrap();
+/// assert_eq!(contig.seq, b"ACGT");
+/// assert!(bio::io::binary::read::<_, Vec<u8>>(&buffer[..]).is_err());
+/// ```
+pub trait TypeTag {
+    fn type_tag() -> String;
+}
+
+impl<T: TypeTag + ?Sized> TypeTag for &T {
+    fn type_tag() -> String {
+        T::type_tag()
+    }
+}
+
+impl<T: TypeTag> TypeTag for Vec<T> {
+    fn type_tag() -> String {
+        format!("Vec<{}>", T::type_tag())
+    }
+}
+
+impl<T: TypeTag> TypeTag for [T] {
+    fn type_tag() -> String {
+        format!("Vec<{}>", T::type_tag())
+    }
+}
+
+macro_rules! type_tags {
+    ($($t:ty => $tag:expr),* $(,)?) => {
+        $(
+            impl TypeTag for $t {
+                fn type_tag() -> String {
+                    $tag.to_owned()
+                }
+            }
+        )*
+    };
+}
+
+type_tags!(
+    u8 => "u8", u16 => "u16", u32 => "u32", u64 => "u64", usize => "usize",
+    i8 => "i8", i16 => "i16", i32 => "i32", i64 => "i64", isize => "isize",
+    f32 => "f32", f64 => "f64", bool => "bool", char => "char", String => "String",
+    str => "String",
+);
+
+type_tags!(
+    Occ => "bio::Occ/2",
+    DnaOcc => "bio::DnaOcc/1",
+    WaveletMatrix => "bio::WaveletMatrix/2",
+    CompactSuffixArray => "bio::CompactSuffixArray/1",
+    GeneralizedSuffixArray => "bio::GeneralizedSuffixArray/1",
+    QGramIndex => "bio::QGramIndex/1",
+    RIndex => "bio::RIndex/1",
+    KmerCounter => "bio::KmerCounter/1",
+    MinimizerIndex => "bio::MinimizerIndex/1",
+    Sketch => "bio::Sketch/1",
+);
+
+impl<DBWT: Borrow<BWT>, DLess: Borrow<Less>, DOcc: OccCount + TypeTag> TypeTag
+    for FMIndex<DBWT, DLess, DOcc>
+{
+    fn type_tag() -> String {
+        format!("bio::FMIndex<{}>/1", DOcc::type_tag())
+    }
+}
+
+impl<DBWT: Borrow<BWT>, DLess: Borrow<Less>, DOcc: OccCount + TypeTag> TypeTag
+    for FMDIndex<DBWT, DLess, DOcc>
+{
+    fn type_tag() -> String {
+        format!("bio::FMDIndex<{}>/1", DOcc::type_tag())
+    }
+}
+
+impl<DBWT: Borrow<BWT>, DLess: Borrow<Less>, DOcc: OccCount + TypeTag> TypeTag
+    for SampledSuffixArray<DBWT, DLess, DOcc>
+{
+    fn type_tag() -> String {
+        format!("bio::SampledSuffixArray<{}>/2", DOcc::type_tag())
+    }
+}
+
+/// Write the given value with a header to the given writer.
+pub fn write<W: io::Write, T: Serialize + TypeTag + ?Sized>(
+    mut writer: W,
+    value: &T,
+) -> Result<()> {
+    let tag = T::type_tag();
+    writer.write_all(MAGIC)?;
+    writer.write_all(&VERSION.to_le_bytes())?;
+    writer.write_all(&(tag.len() as u32).to_le_bytes())?;
+    writer.write_all(tag.as_bytes())?;
+    bincode::serialize_into(writer, value)?;
+    Ok(())
+}
+
+/// Read a value with a header from the given reader. The reader is left right after the
+/// value, such that several values written one after another can be read in turn.
+pub fn read<R: io::Read, T: DeserializeOwned + TypeTag>(mut reader: R) -> Result<T> {
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(Error::InvalidMagic);
+    }
+    let mut version = [0; 4];
+    reader.read_exact(&mut version)?;
+    let version = u32::from_le_bytes(version);
+    if version != VERSION {
+        return Err(Error::UnsupportedVersion { version });
+    }
+
+    let mut len = [0; 4];
+    reader.read_exact(&mut len)?;
+    let len = usize::try_from(u32::from_le_bytes(len)).map_err(|_| Error::InvalidTag)?;
+    if len > MAX_TAG_LEN {
+        return Err(Error::InvalidTag);
+    }
+    let mut tag = vec![0; len];
+    reader.read_exact(&mut tag)?;
+    let tag = String::from_utf8(tag).map_err(|_| Error::InvalidTag)?;
+    let expected = T::type_tag();
+    if tag != expected {
+        return Err(Error::TypeMismatch {
+            expected,
+            found: tag,
+        });
+    }
+
+    Ok(bincode::deserialize_from(reader)?)
+}
+
+/// Write the given value to the given file path.
+pub fn to_file<P: AsRef<Path> + std::fmt::Debug, T: Serialize + TypeTag + ?Sized>(
+    path: P,
+    value: &T,
+) -> anyhow::Result<()> {
+    let file = fs::File::create(path.as_ref()).map_err(|e| Error::FileOpen {
+        path: path.as_ref().to_owned(),
+        source: e,
+    })?;
+    let mut writer = io::BufWriter::new(file);
+    write(&mut writer, value)
+        .and_then(|_| writer.flush().map_err(Error::from))
+        .with_context(|| format!("Failed to write binary file {:#?}", path))
+}
+
+/// Read a value from the given file path.
+pub fn from_file<P: AsRef<Path> + std::fmt::Debug, T: DeserializeOwned + TypeTag>(
+    path: P,
+) -> anyhow::Result<T> {
+    let file = fs::File::open(path.as_ref()).map_err(|e| Error::FileOpen {
+        path: path.as_ref().to_owned(),
+        source: e,
+    })?;
+    read(io::BufReader::new(file))
+        .with_context(|| format!("Failed to read binary file {:#?}", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabets::dna;
+    use crate::data_structures::bwt::{bwt, less, Less, Occ, BWT};
+    use crate::data_structures::fmindex::{FMDIndex, FMIndex, FMIndexable};
+    use crate::data_structures::qgram_index::QGramIndex;
+    use crate::data_structures::suffix_array::{
+        suffix_array, CompactSuffixArray, SampledSuffixArray, SuffixArray,
+    };
+
+    fn roundtrip<T: Serialize + TypeTag, U: DeserializeOwned + TypeTag>(value: &T) -> U {
+        let mut buffer = Vec::new();
+        write(&mut buffer, value).unwrap();
+        read(&buffer[..]).unwrap()
+    }
+
+    #[test]
+    fn test_roundtrip_indexes() {
+        let text = b"ACGTTACGATTTACGAACGT$ACGTTCGTAAATCGTAACGT$";
+        let alphabet = dna::n_alphabet();
+        let sa = suffix_array(text);
+        let compact = CompactSuffixArray::from(sa.clone());
+        assert_eq!(roundtrip::<_, Vec<usize>>(&sa), sa);
+        assert_eq!(roundtrip::<_, CompactSuffixArray>(&compact), compact);
+
+        let bwt = bwt(text, &sa);
+        let less = less(&bwt, &alphabet);
+        let occ = Occ::new(&bwt, 3, &alphabet);
+        let fmdindex = FMDIndex::from(FMIndex::new(&bwt, &less, &occ));
+        let loaded: FMDIndex<BWT, Less, Occ> = roundtrip(&fmdindex);
+        for pattern in &[&b"ACG"[..], b"TTA", b"CGTAA"] {
+            assert_eq!(
+                loaded.backward_search(pattern.iter()),
+                fmdindex.backward_search(pattern.iter())
+            );
+            assert_eq!(
+                loaded.all_smems(pattern, 1).len(),
+                fmdindex.all_smems(pattern, 1).len()
+            );
+        }
+
+        let sampled = sa.sample(&bwt, &less, &occ, 4);
+        let loaded: SampledSuffixArray<BWT, Less, Occ> = roundtrip(&sampled);
+        for i in 0..sa.len() {
+            assert_eq!(loaded.get(i), sa.get(i));
+        }
+        let sampled = sa.sample_text_order(&bwt, &less, &occ, 4);
+        let loaded: SampledSuffixArray<BWT, Less, Occ> = roundtrip(&sampled);
+        for i in 0..sa.len() {
+            assert_eq!(loaded.get(i), sa.get(i));
+        }
+
+        let qgram_index = QGramIndex::new(3, &text[..20], &dna::alphabet());
+        let loaded: QGramIndex = roundtrip(&qgram_index);
+        for qgram in 0..64 {
+            assert_eq!(
+                loaded.qgram_matches(qgram),
+                qgram_index.qgram_matches(qgram)
+            );
+        }
+    }
+
+    #[test]
+    fn test_type_tags() {
+        assert_eq!(
+            FMIndex::<BWT, Less, Occ>::type_tag(),
+            FMIndex::<&BWT, &Less, &Occ>::type_tag()
+        );
+        assert_ne!(
+            FMIndex::<BWT, Less, Occ>::type_tag(),
+            FMIndex::<BWT, Less, DnaOcc>::type_tag()
+        );
+        assert_eq!(<[u8]>::type_tag(), BWT::type_tag());
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut buffer = Vec::new();
+        write(&mut buffer, &vec![1u32, 2, 3]).unwrap();
+
+        let mut invalid = buffer.clone();
+        invalid[0] = b'X';
+        assert!(matches!(
+            read::<_, Vec<u32>>(&invalid[..]),
+            Err(Error::InvalidMagic)
+        ));
+
+        let mut invalid = buffer.clone();
+        invalid[4] = 3;
+        assert!(matches!(
+            read::<_, Vec<u32>>(&invalid[..]),
+            Err(Error::UnsupportedVersion { version: 3 })
+        ));
+
+        let mut invalid = buffer.clone();
+        invalid[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            read::<_, Vec<u32>>(&invalid[..]),
+            Err(Error::InvalidTag)
+        ));
+
+        match read::<_, Vec<u64>>(&buffer[..]) {
+            Err(Error::TypeMismatch { expected, found }) => {
+                assert_eq!(expected, "Vec<u64>");
+                assert_eq!(found, "Vec<u32>");
+            }
+            _ => panic!("expected a type mismatch"),
+        }
+
+        assert!(matches!(
+            read::<_, Vec<u32>>(&buffer[..buffer.len() - 1]),
+            Err(Error::Encoding(_))
+        ));
+    }
+
+    #[test]
+    fn test_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sa.bin");
+        let sa = suffix_array(b"GCCTTAACATTATTACGCCTA$");
+        to_file(&path, &sa).unwrap();
+        let loaded: Vec<usize> = from_file(&path).unwrap();
+        assert_eq!(loaded, sa);
+        assert!(from_file::<_, Vec<usize>>(dir.path().join("missing.bin")).is_err());
+    }
+}
@@ -0,0 +1,1105 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Reader and writer for the Graphical Fragment Assembly format
+//! ([GFA 1 and GFA 2](https://github.com/GFA-spec/GFA-spec)), the common exchange format
+//! for assembly and variation graphs.
+//!
+//! Each line is parsed into a [`Record`](enum.Record.html): the header (`H`), segments
+//! (`S`), links (`L`, GFA 1), edges (`E`, GFA 2), paths (`P`, GFA 1), walks (`W`, GFA 1.1)
+//! and groups (`O`/`U`, GFA 2). Other lines (e.g. containments, gaps and fragments) are kept
+//! verbatim. Optional fields are kept as [`Tag`](struct.Tag.html)s. Segment lines are parsed
+//! according to the version given in the header (`VN:Z:2.0` for GFA 2, GFA 1 otherwise),
+//! since GFA 2 segments carry an additional length field. Comment lines (starting with `#`)
+//! and empty lines are skipped. Records are written back in the same form, such that a file
+//! can be read, modified and written again.
+//!
+//! Assembly graphs can be converted from and to
+//! [`OverlapGraph`](../../data_structures/overlap_graph/struct.OverlapGraph.html)s, and the
+//! unitigs of a [`DeBruijnGraph`](../../data_structures/debruijn/struct.DeBruijnGraph.html)
+//! can be saved together with the links between them.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::io::gfa;
+//!
+//! let data: &[u8] = b"H\tVN:Z:1.0
+//! S\t1\tACGTACGGA\tRC:i:12
+//! S\t2\tTACGGATTC
+//! L\t1\t+\t2\t+\t6M
+//! P\tcontig\t1+,2+\t6M
+//! ";
+//! let records: Vec<gfa::Record> = gfa::Reader::new(data)
+//!     .records()
+//!     .collect::<Result<_, _>>()
+//!     .unwrap();
+//! match &records[1] {
+//!     gfa::Record::Segment { name, seq, tags, .. } => {
+//!         assert_eq!(name, "1");
+//!         assert_eq!(seq.as_deref(), Some(&b"ACGTACGGA"[..]));
+//!         assert_eq!(tags[0].int(), Some(12));
+//!     }
+//!     _ => unreachable!(),
+//! }
+//!
+//! // build an overlap graph, e.g. to simplify it, and save it again
+//! let (graph, names, seqs) = gfa::to_overlap_graph(&records).unwrap();
+//! assert_eq!(graph.num_edges(), 1);
+//! let mut writer = gfa::Writer::new(Vec::new());
+//! for record in gfa::from_overlap_graph(&graph, &names, &seqs) {
+//!     writer.write(&record).unwrap();
+//! }
+//! assert_eq!(
+//!     writer.into_inner().unwrap(),
+//!     b"H\tVN:Z:1.0\nS\t1\tACGTACGGA\nS\t2\tTACGGATTC\nL\t1\t+\t2\t+\t6M\n"
+//! );
+//! ```
+
+use std::collections::HashMap;
+use std::convert::AsRef;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::Context;
+use thiserror::Error;
+
+use crate::alphabets::dna;
+use crate::data_structures::debruijn::DeBruijnGraph;
+use crate::data_structures::overlap_graph::{OverlapGraph, Vertex};
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("can't open {path} file: {source}")]
+    FileOpen { path: PathBuf, source: io::Error },
+
+    #[error("can't read input")]
+    ReadError(#[from] io::Error),
+
+    #[error("line {line}: expected at least {expected} fields in '{kind}' line, found {found}")]
+    MissingFields {
+        line: usize,
+        kind: char,
+        expected: usize,
+        found: usize,
+    },
+
+    #[error("line {line}: invalid value '{value}' in field '{field}'")]
+    InvalidValue {
+        line: usize,
+        field: &'static str,
+        value: String,
+    },
+
+    #[error("link or path refers to unknown segment '{name}'")]
+    UnknownSegment { name: String },
+}
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// An optional field `NAME:TYPE:VALUE`, e.g. `LN:i:1000`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tag {
+    pub name: String,
+    /// The type, one of `A` (character), `i` (integer), `f` (float), `Z` (string), `J`
+    /// (JSON), `H` (hex bytes) and `B` (numeric array).
+    pub kind: char,
+    pub value: String,
+}
+
+impl Tag {
+    /// Create a tag.
+    pub fn new(name: &str, kind: char, value: &str) -> Self {
+        Tag {
+            name: name.to_owned(),
+            kind,
+            value: value.to_owned(),
+        }
+    }
+
+    /// The value of an integer tag.
+    pub fn int(&self) -> Option<i64> {
+        if self.kind == 'i' {
+            self.value.parse().ok()
+        } else {
+            None
+        }
+    }
+
+    /// The value of an integer or float tag.
+    pub fn float(&self) -> Option<f64> {
+        if matches!(self.kind, 'i' | 'f') {
+            self.value.parse().ok()
+        } else {
+            None
+        }
+    }
+}
+
+impl FromStr for Tag {
+    type Err = ();
+
+    fn from_str(s: &str) -> std::result::Result<Self, ()> {
+        let mut fields = s.splitn(3, ':');
+        match (fields.next(), fields.next(), fields.next()) {
+            (Some(name), Some(kind), Some(value)) if name.len() == 2 && kind.len() == 1 => {
+                Ok(Tag::new(name, kind.chars().next().unwrap(), value))
+            }
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.name, self.kind, self.value)
+    }
+}
+
+/// A segment in a given orientation, e.g. an end of a link or a step of a path.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Step {
+    pub segment: String,
+    pub forward: bool,
+}
+
+impl Step {
+    /// Create a step.
+    pub fn new(segment: &str, forward: bool) -> Self {
+        Step {
+            segment: segment.to_owned(),
+            forward,
+        }
+    }
+
+    fn sign(&self) -> char {
+        if self.forward {
+            '+'
+        } else {
+            '-'
+        }
+    }
+
+    /// Parse a reference of the form `name+` or `name-`.
+    fn parse_ref(s: &str) -> Option<Self> {
+        let forward = match s.chars().last()? {
+            '+' => true,
+            '-' => false,
+            _ => return None,
+        };
+        let segment = &s[..s.len() - 1];
+        if segment.is_empty() {
+            return None;
+        }
+        Some(Step::new(segment, forward))
+    }
+}
+
+/// A position in a segment, which may be marked as the end of the segment (`$`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Position {
+    pub pos: u64,
+    pub is_end: bool,
+}
+
+impl FromStr for Position {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let is_end = s.ends_with('$');
+        Ok(Position {
+            pos: s.trim_end_matches('$').parse()?,
+            is_end,
+        })
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.pos, if self.is_end { "$" } else { "" })
+    }
+}
+
+/// A line of a GFA file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Record {
+    /// Header (`H`).
+    Header { tags: Vec<Tag> },
+    /// Segment (`S`). The length is only given in GFA 2, and a missing sequence (`*`) is
+    /// `None`.
+    Segment {
+        name: String,
+        len: Option<u64>,
+        seq: Option<Vec<u8>>,
+        tags: Vec<Tag>,
+    },
+    /// Link between two oriented segments with an overlap given as CIGAR string or `*`
+    /// (`L`, GFA 1).
+    Link {
+        from: Step,
+        to: Step,
+        overlap: String,
+        tags: Vec<Tag>,
+    },
+    /// Edge between the given intervals of two oriented segments, with an alignment given
+    /// as CIGAR string, trace or `*` (`E`, GFA 2).
+    Edge {
+        id: String,
+        from: Step,
+        to: Step,
+        from_range: (Position, Position),
+        to_range: (Position, Position),
+        alignment: String,
+        tags: Vec<Tag>,
+    },
+    /// Path through oriented segments with the overlaps between them (`P`, GFA 1).
+    Path {
+        name: String,
+        steps: Vec<Step>,
+        overlaps: Vec<String>,
+        tags: Vec<Tag>,
+    },
+    /// Walk of a haplotype through oriented segments (`W`, GFA 1.1).
+    Walk {
+        sample: String,
+        haplotype: u64,
+        seq_id: String,
+        range: Option<(u64, u64)>,
+        steps: Vec<Step>,
+        tags: Vec<Tag>,
+    },
+    /// Ordered (`O`) or unordered (`U`) group of references (GFA 2).
+    Group {
+        ordered: bool,
+        id: String,
+        items: Vec<String>,
+        tags: Vec<Tag>,
+    },
+    /// Any other line, e.g. a containment, gap or fragment, split into its fields.
+    Other { kind: String, fields: Vec<String> },
+}
+
+fn write_tags(f: &mut fmt::Formatter<'_>, tags: &[Tag]) -> fmt::Result {
+    for tag in tags {
+        write!(f, "\t{}", tag)?;
+    }
+    Ok(())
+}
+
+fn join<T: fmt::Display>(items: &[T], sep: &str) -> String {
+    items
+        .iter()
+        .map(|item| item.to_string())
+        .collect::<Vec<_>>()
+        .join(sep)
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.segment, self.sign())
+    }
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Record::Header { tags } => {
+                write!(f, "H")?;
+                write_tags(f, tags)
+            }
+            Record::Segment {
+                name,
+                len,
+                seq,
+                tags,
+            } => {
+                write!(f, "S\t{}", name)?;
+                if let Some(len) = len {
+                    write!(f, "\t{}", len)?;
+                }
+                match seq {
+                    Some(seq) => write!(f, "\t{}", String::from_utf8_lossy(seq))?,
+                    None => write!(f, "\t*")?,
+                }
+                write_tags(f, tags)
+            }
+            Record::Link {
+                from,
+                to,
+                overlap,
+                tags,
+            } => {
+                write!(
+                    f,
+                    "L\t{}\t{}\t{}\t{}\t{}",
+                    from.segment,
+                    from.sign(),
+                    to.segment,
+                    to.sign(),
+                    overlap
+                )?;
+                write_tags(f, tags)
+            }
+            Record::Edge {
+                id,
+                from,
+                to,
+                from_range,
+                to_range,
+                alignment,
+                tags,
+            } => {
+                write!(
+                    f,
+                    "E\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                    id, from, to, from_range.0, from_range.1, to_range.0, to_range.1, alignment
+                )?;
+                write_tags(f, tags)
+            }
+            Record::Path {
+                name,
+                steps,
+                overlaps,
+                tags,
+            } => {
+                let overlaps = if overlaps.is_empty() {
+                    "*".to_owned()
+                } else {
+                    overlaps.join(",")
+                };
+                write!(f, "P\t{}\t{}\t{}", name, join(steps, ","), overlaps)?;
+                write_tags(f, tags)
+            }
+            Record::Walk {
+                sample,
+                haplotype,
+                seq_id,
+                range,
+                steps,
+                tags,
+            } => {
+                let (start, end) = match range {
+                    Some((start, end)) => (start.to_string(), end.to_string()),
+                    None => ("*".to_owned(), "*".to_owned()),
+                };
+                write!(
+                    f,
+                    "W\t{}\t{}\t{}\t{}\t{}\t",
+                    sample, haplotype, seq_id, start, end
+                )?;
+                for step in steps {
+                    let dir = if step.forward { '>' } else { '<' };
+                    write!(f, "{}{}", dir, step.segment)?;
+                }
+                write_tags(f, tags)
+            }
+            Record::Group {
+                ordered,
+                id,
+                items,
+                tags,
+            } => {
+                let kind = if *ordered { 'O' } else { 'U' };
+                write!(f, "{}\t{}\t{}", kind, id, items.join(" "))?;
+                write_tags(f, tags)
+            }
+            Record::Other { kind, fields } => {
+                write!(f, "{}", kind)?;
+                for field in fields {
+                    write!(f, "\t{}", field)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The fields of a line, for parsing with error reporting.
+struct Fields<'a> {
+    fields: Vec<&'a str>,
+    line: usize,
+}
+
+impl<'a> Fields<'a> {
+    fn require(&self, kind: char, n: usize) -> Result<()> {
+        // the record type is the first field
+        if self.fields.len() < n + 1 {
+            return Err(Error::MissingFields {
+                line: self.line,
+                kind,
+                expected: n,
+                found: self.fields.len() - 1,
+            });
+        }
+        Ok(())
+    }
+
+    fn invalid(&self, field: &'static str, value: &str) -> Error {
+        Error::InvalidValue {
+            line: self.line,
+            field,
+            value: value.to_owned(),
+        }
+    }
+
+    fn parse<T: FromStr>(&self, i: usize, field: &'static str) -> Result<T> {
+        self.fields[i]
+            .parse()
+            .map_err(|_| self.invalid(field, self.fields[i]))
+    }
+
+    fn reference(&self, i: usize, field: &'static str) -> Result<Step> {
+        Step::parse_ref(self.fields[i]).ok_or_else(|| self.invalid(field, self.fields[i]))
+    }
+
+    fn orientation(&self, i: usize, field: &'static str) -> Result<bool> {
+        match self.fields[i] {
+            "+" => Ok(true),
+            "-" => Ok(false),
+            value => Err(self.invalid(field, value)),
+        }
+    }
+
+    fn seq(&self, i: usize) -> Option<Vec<u8>> {
+        match self.fields[i] {
+            "*" => None,
+            seq => Some(seq.as_bytes().to_owned()),
+        }
+    }
+
+    fn tags(&self, from: usize) -> Result<Vec<Tag>> {
+        self.fields[from.min(self.fields.len())..]
+            .iter()
+            .map(|tag| tag.parse().map_err(|_| self.invalid("tag", tag)))
+            .collect()
+    }
+}
+
+/// Parse a walk of the form `>s1<s2>s3`.
+fn parse_walk(walk: &str) -> Option<Vec<Step>> {
+    let mut steps = Vec::new();
+    let mut rest = walk;
+    while !rest.is_empty() {
+        let forward = match rest.as_bytes()[0] {
+            b'>' => true,
+            b'<' => false,
+            _ => return None,
+        };
+        rest = &rest[1..];
+        let end = rest.find(&['>', '<'][..]).unwrap_or(rest.len());
+        if end == 0 {
+            return None;
+        }
+        steps.push(Step::new(&rest[..end], forward));
+        rest = &rest[end..];
+    }
+    Some(steps)
+}
+
+/// Parse a line (without line break) of a GFA file of the given major version.
+fn parse_line(text: &str, line: usize, version2: bool) -> Result<Record> {
+    let f = Fields {
+        fields: text.split('\t').collect(),
+        line,
+    };
+    let record = match f.fields[0] {
+        "H" => Record::Header { tags: f.tags(1)? },
+        "S" if version2 => {
+            f.require('S', 3)?;
+            Record::Segment {
+                name: f.fields[1].to_owned(),
+                len: Some(f.parse(2, "segment length")?),
+                seq: f.seq(3),
+                tags: f.tags(4)?,
+            }
+        }
+        "S" => {
+            f.require('S', 2)?;
+            Record::Segment {
+                name: f.fields[1].to_owned(),
+                len: None,
+                seq: f.seq(2),
+                tags: f.tags(3)?,
+            }
+        }
+        "L" => {
+            f.require('L', 5)?;
+            Record::Link {
+                from: Step::new(f.fields[1], f.orientation(2, "from orientation")?),
+                to: Step::new(f.fields[3], f.orientation(4, "to orientation")?),
+                overlap: f.fields[5].to_owned(),
+                tags: f.tags(6)?,
+            }
+        }
+        "E" => {
+            f.require('E', 8)?;
+            Record::Edge {
+                id: f.fields[1].to_owned(),
+                from: f.reference(2, "first segment")?,
+                to: f.reference(3, "second segment")?,
+                from_range: (f.parse(4, "beg1")?, f.parse(5, "end1")?),
+                to_range: (f.parse(6, "beg2")?, f.parse(7, "end2")?),
+                alignment: f.fields[8].to_owned(),
+                tags: f.tags(9)?,
+            }
+        }
+        "P" => {
+            f.require('P', 3)?;
+            let steps = f.fields[2]
+                .split(',')
+                .map(|step| Step::parse_ref(step).ok_or_else(|| f.invalid("segment names", step)))
+                .collect::<Result<_>>()?;
+            let overlaps = match f.fields[3] {
+                "*" => Vec::new(),
+                overlaps => overlaps.split(',').map(|o| o.to_owned()).collect(),
+            };
+            Record::Path {
+                name: f.fields[1].to_owned(),
+                steps,
+                overlaps,
+                tags: f.tags(4)?,
+            }
+        }
+        "W" => {
+            f.require('W', 6)?;
+            let range = match (f.fields[4], f.fields[5]) {
+                ("*", "*") => None,
+                _ => Some((f.parse(4, "seq start")?, f.parse(5, "seq end")?)),
+            };
+            Record::Walk {
+                sample: f.fields[1].to_owned(),
+                haplotype: f.parse(2, "haplotype index")?,
+                seq_id: f.fields[3].to_owned(),
+                range,
+                steps: parse_walk(f.fields[6]).ok_or_else(|| f.invalid("walk", f.fields[6]))?,
+                tags: f.tags(7)?,
+            }
+        }
+        kind @ "O" | kind @ "U" => {
+            f.require(kind.chars().next().unwrap(), 2)?;
+            Record::Group {
+                ordered: kind == "O",
+                id: f.fields[1].to_owned(),
+                items: f.fields[2].split(' ').map(|item| item.to_owned()).collect(),
+                tags: f.tags(3)?,
+            }
+        }
+        kind => Record::Other {
+            kind: kind.to_owned(),
+            fields: f.fields[1..]
+                .iter()
+                .map(|field| (*field).to_owned())
+                .collect(),
+        },
+    };
+    Ok(record)
+}
+
+/// A GFA reader.
+#[derive(Debug)]
+pub struct Reader<R: io::Read> {
+    reader: io::BufReader<R>,
+    line: String,
+    line_number: usize,
+    version2: bool,
+}
+
+impl Reader<fs::File> {
+    /// Read from a given file path.
+    pub fn from_file<P: AsRef<Path> + std::fmt::Debug>(path: P) -> anyhow::Result<Self> {
+        fs::File::open(path.as_ref())
+            .map_err(|e| Error::FileOpen {
+                path: path.as_ref().to_owned(),
+                source: e,
+            })
+            .map(Reader::new)
+            .with_context(|| format!("Failed to read GFA from {:#?}", path))
+    }
+}
+
+impl<R: io::Read> Reader<R> {
+    /// Read from a given [`io::Read`](https://doc.rust-lang.org/std/io/trait.Read.html).
+    pub fn new(reader: R) -> Self {
+        Reader {
+            reader: io::BufReader::new(reader),
+            line: String::new(),
+            line_number: 0,
+            version2: false,
+        }
+    }
+
+    /// Read the next record. Returns `Ok(None)` once all records have been read.
+    pub fn read(&mut self) -> Result<Option<Record>> {
+        loop {
+            self.line.clear();
+            if self.reader.read_line(&mut self.line)? == 0 {
+                return Ok(None);
+            }
+            self.line_number += 1;
+            let text = self.line.trim_end_matches(&['\n', '\r'][..]);
+            if text.is_empty() || text.starts_with('#') {
+                continue;
+            }
+            let record = parse_line(text, self.line_number, self.version2)?;
+            if let Record::Header { tags } = &record {
+                if let Some(version) = tags.iter().find(|tag| tag.name == "VN") {
+                    self.version2 = version.value.starts_with('2');
+                }
+            }
+            return Ok(Some(record));
+        }
+    }
+
+    /// Iterate over all records.
+    pub fn records(&mut self) -> Records<'_, R> {
+        Records {
+            reader: self,
+            error_has_occured: false,
+        }
+    }
+}
+
+pub struct Records<'a, R: io::Read> {
+    reader: &'a mut Reader<R>,
+    error_has_occured: bool,
+}
+
+impl<'a, R: io::Read> Iterator for Records<'a, R> {
+    type Item = Result<Record>;
+
+    fn next(&mut self) -> Option<Result<Record>> {
+        if self.error_has_occured {
+            return None;
+        }
+        match self.reader.read() {
+            Ok(record) => record.map(Ok),
+            Err(err) => {
+                self.error_has_occured = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// A GFA writer.
+#[derive(Debug)]
+pub struct Writer<W: io::Write> {
+    writer: io::BufWriter<W>,
+}
+
+impl Writer<fs::File> {
+    /// Write to a given file path.
+    #[allow(clippy::wrong_self_convention)]
+    pub fn to_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        fs::File::create(path).map(Writer::new)
+    }
+}
+
+impl<W: io::Write> Writer<W> {
+    /// Write to a given `io::Write`.
+    pub fn new(writer: W) -> Self {
+        Writer {
+            writer: io::BufWriter::new(writer),
+        }
+    }
+
+    /// Write a record.
+    pub fn write(&mut self, record: &Record) -> io::Result<()> {
+        writeln!(self.writer, "{}", record)
+    }
+
+    /// Flush the writer, ensuring that everything is written.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Flush the writer and return the underlying `io::Write`.
+    pub fn into_inner(self) -> io::Result<W> {
+        self.writer.into_inner().map_err(|e| e.into_error())
+    }
+}
+
+/// Length of an overlap given as CIGAR string on the first sequence, i.e. the sum of the
+/// `M`, `=`, `X` and `D` operations, or `None` if there is no valid CIGAR string (`*`).
+fn overlap_len(cigar: &str) -> Option<usize> {
+    let mut len = 0;
+    let mut num = 0;
+    let mut has_ops = false;
+    for c in cigar.chars() {
+        if let Some(d) = c.to_digit(10) {
+            num = num * 10 + d as usize;
+        } else {
+            match c {
+                'M' | '=' | 'X' | 'D' | 'N' => len += num,
+                'I' | 'S' | 'H' | 'P' => (),
+                _ => return None,
+            }
+            num = 0;
+            has_ops = true;
+        }
+    }
+    if has_ops {
+        Some(len)
+    } else {
+        None
+    }
+}
+
+/// Build an [`OverlapGraph`](../../data_structures/overlap_graph/struct.OverlapGraph.html)
+/// from the segments and links (GFA 1) or edges (GFA 2) of the given records. Returns the
+/// graph, and the names and sequences of the segments in the order of the reads of the
+/// graph. Links without overlap (`*`) are added with overlap zero, and edges are added if
+/// they are dovetail overlaps, i.e. connect the end of the first with the start of the
+/// second oriented segment.
+pub fn to_overlap_graph(records: &[Record]) -> Result<(OverlapGraph, Vec<String>, Vec<Vec<u8>>)> {
+    let mut graph = OverlapGraph::new();
+    let mut names = Vec::new();
+    let mut seqs = Vec::new();
+    let mut index = HashMap::new();
+    for record in records {
+        if let Record::Segment { name, len, seq, .. } = record {
+            let seq = seq.clone().unwrap_or_default();
+            let len = len.map_or(seq.len(), |len| len as usize);
+            index.insert(name.clone(), graph.add_read(len));
+            names.push(name.clone());
+            seqs.push(seq);
+        }
+    }
+    let vertex = |step: &Step| -> Result<Vertex> {
+        index
+            .get(&step.segment)
+            .map(|&read| Vertex {
+                read,
+                forward: step.forward,
+            })
+            .ok_or_else(|| Error::UnknownSegment {
+                name: step.segment.clone(),
+            })
+    };
+    for record in records {
+        match record {
+            Record::Link {
+                from, to, overlap, ..
+            } => {
+                graph.add_overlap(
+                    vertex(from)?,
+                    vertex(to)?,
+                    overlap_len(overlap).unwrap_or(0),
+                );
+            }
+            Record::Edge {
+                from,
+                to,
+                from_range,
+                to_range,
+                ..
+            } => {
+                // the ranges are given on the forward strand of the segments
+                let (from_v, to_v) = (vertex(from)?, vertex(to)?);
+                let from_dovetail = if from.forward {
+                    from_range.1.is_end
+                } else {
+                    from_range.0.pos == 0
+                };
+                let to_dovetail = if to.forward {
+                    to_range.0.pos == 0
+                } else {
+                    to_range.1.is_end
+                };
+                if from_dovetail && to_dovetail {
+                    let overlap = (from_range.1.pos - from_range.0.pos) as usize;
+                    graph.add_overlap(from_v, to_v, overlap);
+                }
+            }
+            _ => (),
+        }
+    }
+    Ok((graph, names, seqs))
+}
+
+/// GFA 1 records of the given overlap graph: a header, a segment for each read that is not
+/// contained, and a link for each edge (but not for its complement). Reads are named as
+/// given, and their sequences are only written if given.
+pub fn from_overlap_graph<S: AsRef<[u8]>>(
+    graph: &OverlapGraph,
+    names: &[String],
+    seqs: &[S],
+) -> Vec<Record> {
+    let mut records = vec![Record::Header {
+        tags: vec![Tag::new("VN", 'Z', "1.0")],
+    }];
+    let reads = (0..graph.num_reads()).filter(|&read| !graph.is_contained(read));
+    for read in reads.clone() {
+        let seq = seqs.get(read).map(|seq| seq.as_ref().to_owned());
+        records.push(Record::Segment {
+            name: names[read].clone(),
+            len: None,
+            seq,
+            tags: Vec::new(),
+        });
+    }
+    let step = |v: Vertex| Step::new(&names[v.read], v.forward);
+    for read in reads {
+        for &v in &[Vertex::forward(read), Vertex::reverse(read)] {
+            for edge in graph.edges(v) {
+                let w = edge.target;
+                // report each pair of complementary edges once
+                if (v, w) <= (w.complement(), v.complement()) {
+                    records.push(Record::Link {
+                        from: step(v),
+                        to: step(w),
+                        overlap: format!("{}M", edge.overlap),
+                        tags: Vec::new(),
+                    });
+                }
+            }
+        }
+    }
+    records
+}
+
+/// GFA 1 records of the unitigs of the given de Bruijn graph (see
+/// [`DeBruijnGraph::unitigs`](../../data_structures/debruijn/struct.DeBruijnGraph.html#method.unitigs)):
+/// a header, a segment for each unitig (named by its index, starting at 1) with its
+/// coverage as `KC:f` tag, and links between unitigs that overlap by `k - 1`. For canonical
+/// graphs, links between both orientations of the unitigs are reported, but each pair of
+/// complementary links only once.
+pub fn from_debruijn(graph: &DeBruijnGraph) -> Vec<Record> {
+    let k = graph.k();
+    let mut records = vec![Record::Header {
+        tags: vec![Tag::new("VN", 'Z', "1.0")],
+    }];
+    // oriented unitigs by their first (k - 1)-mer
+    let mut starts: HashMap<Vec<u8>, Vec<Step>> = HashMap::new();
+    let mut oriented = Vec::new();
+    for (i, unitig) in graph.unitigs().iter().enumerate() {
+        let name = (i + 1).to_string();
+        records.push(Record::Segment {
+            name: name.clone(),
+            len: None,
+            seq: Some(unitig.seq.clone()),
+            tags: vec![Tag::new("KC", 'f', &format!("{:.2}", unitig.coverage))],
+        });
+        oriented.push((Step::new(&name, true), unitig.seq.clone()));
+        if graph.is_canonical() {
+            oriented.push((Step::new(&name, false), dna::revcomp(&unitig.seq)));
+        }
+    }
+    for (step, seq) in &oriented {
+        starts
+            .entry(seq[..k - 1].to_owned())
+            .or_default()
+            .push(step.clone());
+    }
+    // a link and its complement, with the orientation as sort key
+    let key = |from: &Step, to: &Step| {
+        (
+            from.segment.clone(),
+            !from.forward,
+            to.segment.clone(),
+            !to.forward,
+        )
+    };
+    for (from, seq) in &oriented {
+        for to in starts
+            .get(&seq[seq.len() - (k - 1)..])
+            .into_iter()
+            .flatten()
+        {
+            let complement = (
+                Step::new(&to.segment, !to.forward),
+                Step::new(&from.segment, !from.forward),
+            );
+            if !graph.is_canonical() || key(from, to) <= key(&complement.0, &complement.1) {
+                records.push(Record::Link {
+                    from: from.clone(),
+                    to: to.clone(),
+                    overlap: format!("{}M", k - 1),
+                    tags: Vec::new(),
+                });
+            }
+        }
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_structures::kmer_count::KmerCounter;
+
+    const GFA1: &str = "H\tVN:Z:1.0
+S\t11\tACCTT\tLN:i:5
+S\t12\t*\tLN:i:6\tRC:i:20
+C\t11\t+\t12\t-\t1\t5M
+L\t11\t+\t12\t-\t4M
+P\tp1\t11+,12-\t4M\tSR:i:0
+W\tNA12878\t1\tchr1\t0\t11\t>11<12
+W\tNA12878\t2\tchr1\t*\t*\t>11
+";
+
+    const GFA2: &str = "H\tVN:Z:2.0
+S\ts1\t10\tACGTACGTAA
+S\ts2\t8\t*\tDP:f:1.5
+E\te1\ts1+\ts2-\t6\t10$\t4\t8$\t4M
+O\tp1\ts1+ s2-
+U\tset\ts1 s2 e1
+G\tg1\ts1+\ts2+\t100\t*
+";
+
+    fn read_all(data: &str) -> Vec<Record> {
+        Reader::new(data.as_bytes())
+            .records()
+            .collect::<Result<_>>()
+            .unwrap()
+    }
+
+    fn write_all(records: &[Record]) -> String {
+        let mut writer = Writer::new(Vec::new());
+        for record in records {
+            writer.write(record).unwrap();
+        }
+        String::from_utf8(writer.into_inner().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_gfa1() {
+        let records = read_all(&format!("# comment\n{}\n", GFA1));
+        assert_eq!(records.len(), 8);
+        assert_eq!(
+            records[2],
+            Record::Segment {
+                name: "12".to_owned(),
+                len: None,
+                seq: None,
+                tags: vec![Tag::new("LN", 'i', "6"), Tag::new("RC", 'i', "20")],
+            }
+        );
+        assert_eq!(
+            records[3],
+            Record::Other {
+                kind: "C".to_owned(),
+                fields: ["11", "+", "12", "-", "1", "5M"]
+                    .iter()
+                    .map(|f| f.to_string())
+                    .collect(),
+            }
+        );
+        match &records[5] {
+            Record::Path {
+                steps, overlaps, ..
+            } => {
+                assert_eq!(steps, &[Step::new("11", true), Step::new("12", false)]);
+                assert_eq!(overlaps, &["4M"]);
+            }
+            record => panic!("unexpected record {:?}", record),
+        }
+        match &records[6] {
+            Record::Walk { range, steps, .. } => {
+                assert_eq!(*range, Some((0, 11)));
+                assert_eq!(steps, &[Step::new("11", true), Step::new("12", false)]);
+            }
+            record => panic!("unexpected record {:?}", record),
+        }
+        assert_eq!(write_all(&records), GFA1);
+    }
+
+    #[test]
+    fn test_gfa2() {
+        let records = read_all(GFA2);
+        assert_eq!(
+            records[1],
+            Record::Segment {
+                name: "s1".to_owned(),
+                len: Some(10),
+                seq: Some(b"ACGTACGTAA".to_vec()),
+                tags: Vec::new(),
+            }
+        );
+        match &records[3] {
+            Record::Edge {
+                from,
+                to,
+                from_range,
+                ..
+            } => {
+                assert_eq!((from.forward, to.forward), (true, false));
+                assert_eq!(
+                    from_range.1,
+                    Position {
+                        pos: 10,
+                        is_end: true
+                    }
+                );
+            }
+            record => panic!("unexpected record {:?}", record),
+        }
+        assert_eq!(write_all(&records), GFA2);
+
+        // the edge is a dovetail overlap of s1 with the reverse complement of s2
+        let (graph, names, _) = to_overlap_graph(&records).unwrap();
+        assert_eq!(names, ["s1", "s2"]);
+        assert_eq!(
+            graph.edges(Vertex::forward(0))[0].target,
+            Vertex::reverse(1)
+        );
+        assert_eq!(graph.edges(Vertex::forward(0))[0].overlap, 4);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut reader = Reader::new(&b"H\tVN:Z:1.0\nL\t1\t+\t2\n"[..]);
+        assert!(reader.read().unwrap().is_some());
+        assert!(matches!(
+            reader.read(),
+            Err(Error::MissingFields {
+                line: 2,
+                kind: 'L',
+                expected: 5,
+                found: 3
+            })
+        ));
+        let mut reader = Reader::new(&b"S\t1\tACGT\tinvalid\n"[..]);
+        assert!(matches!(
+            reader.read(),
+            Err(Error::InvalidValue { line: 1, .. })
+        ));
+        let records = read_all("S\t1\tACGT\nL\t1\t+\t2\t+\t*\n");
+        assert!(matches!(
+            to_overlap_graph(&records),
+            Err(Error::UnknownSegment { .. })
+        ));
+    }
+
+    #[test]
+    fn test_unitigs() {
+        let seqs = [&b"ACGGTCAGGCT"[..], &b"ACGGTCATTTAC"[..]];
+        let counter = KmerCounter::from_seqs(seqs, 5, false, 1);
+        let graph = DeBruijnGraph::from_counts(&counter, 1);
+        let records = from_debruijn(&graph);
+        let links: Vec<&Record> = records
+            .iter()
+            .filter(|r| matches!(r, Record::Link { .. }))
+            .collect();
+        // the common prefix branches into the two suffixes
+        assert_eq!(graph.unitigs().len(), 3);
+        assert_eq!(links.len(), 2);
+
+        // in a canonical graph, the links are found on both strands, but reported once
+        let counter = KmerCounter::from_seqs(seqs, 5, true, 1);
+        let graph = DeBruijnGraph::from_counts(&counter, 1);
+        let links = from_debruijn(&graph)
+            .into_iter()
+            .filter(|r| matches!(r, Record::Link { .. }))
+            .count();
+        assert_eq!(links, 2);
+        for record in &records {
+            assert_eq!(read_all(&record.to_string()), std::slice::from_ref(record));
+        }
+    }
+}
@@ -0,0 +1,332 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Reader for HMMER per-domain hit tables (`--domtblout`).
+//!
+//! Each line describes one domain of a hit and holds 22 whitespace-separated
+//! columns followed by the free-text target description.
+//! All coordinates are reported as in HMMER, i.e. 1-based and inclusive.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::io::hmmer::domtblout;
+//!
+//! let domtbl = b"ARCA_ECOLI - 238 Response_reg PF00072.25 112 2.1e-28 97.2 0.1 1 1 3.6e-32 4.3e-28 96.2 0.1 1 111 6 118 6 119 0.97 -\n";
+//! let mut reader = domtblout::Reader::new(&domtbl[..]);
+//! let record = reader.records().next().unwrap().unwrap();
+//! assert_eq!(record.target_len(), 238);
+//! assert_eq!((record.hmm_from(), record.hmm_to()), (1, 111));
+//! assert_eq!((record.env_from(), record.env_to()), (6, 119));
+//! assert_eq!(record.i_evalue(), 4.3e-28);
+//! assert_eq!(record.description(), None);
+//! ```
+
+use std::convert::AsRef;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use anyhow::Context;
+
+use super::{read_data_line, Columns, Error, Result, Scores};
+
+/// Number of whitespace-separated columns preceding the description.
+const COLUMNS: usize = 22;
+
+/// A reader for HMMER `--domtblout` files.
+#[derive(Debug)]
+pub struct Reader<R: io::Read> {
+    reader: io::BufReader<R>,
+    line: String,
+    line_number: usize,
+}
+
+impl Reader<fs::File> {
+    /// Read from a given file path.
+    pub fn from_file<P: AsRef<Path> + std::fmt::Debug>(path: P) -> anyhow::Result<Self> {
+        fs::File::open(path.as_ref())
+            .map_err(|e| Error::FileOpen {
+                path: path.as_ref().to_owned(),
+                source: e,
+            })
+            .map(Reader::new)
+            .with_context(|| format!("Failed to read domtblout from {:#?}", path))
+    }
+}
+
+impl<R: io::Read> Reader<R> {
+    /// Read from a given [`io::Read`](https://doc.rust-lang.org/std/io/trait.Read.html).
+    pub fn new(reader: R) -> Self {
+        Reader {
+            reader: io::BufReader::new(reader),
+            line: String::new(),
+            line_number: 0,
+        }
+    }
+
+    /// Read the next record. Returns `Ok(None)` once all records have been read.
+    pub fn read(&mut self) -> Result<Option<Record>> {
+        if !read_data_line(&mut self.reader, &mut self.line, &mut self.line_number)? {
+            return Ok(None);
+        }
+        Record::from_columns(&Columns::split(&self.line, COLUMNS, self.line_number)?).map(Some)
+    }
+
+    /// Iterate over all records.
+    pub fn records(&mut self) -> Records<'_, R> {
+        Records {
+            reader: self,
+            error_has_occured: false,
+        }
+    }
+}
+
+/// An iterator over the records of a `--domtblout` file.
+pub struct Records<'a, R: io::Read> {
+    reader: &'a mut Reader<R>,
+    error_has_occured: bool,
+}
+
+impl<'a, R: io::Read> Iterator for Records<'a, R> {
+    type Item = Result<Record>;
+
+    fn next(&mut self) -> Option<Result<Record>> {
+        if self.error_has_occured {
+            return None;
+        }
+        match self.reader.read() {
+            Ok(record) => record.map(Ok),
+            Err(err) => {
+                self.error_has_occured = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// A single domain of a hit of a profile against a target sequence.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    target_name: String,
+    target_accession: Option<String>,
+    target_len: u64,
+    query_name: String,
+    query_accession: Option<String>,
+    query_len: u64,
+    full_sequence: Scores,
+    domain_index: u32,
+    domain_count: u32,
+    c_evalue: f64,
+    i_evalue: f64,
+    score: f64,
+    bias: f64,
+    hmm_from: u64,
+    hmm_to: u64,
+    ali_from: u64,
+    ali_to: u64,
+    env_from: u64,
+    env_to: u64,
+    accuracy: f64,
+    description: Option<String>,
+}
+
+impl Record {
+    fn from_columns(columns: &Columns<'_>) -> Result<Self> {
+        Ok(Record {
+            target_name: columns.text(0),
+            target_accession: columns.optional_text(1),
+            target_len: columns.parse(2, "tlen")?,
+            query_name: columns.text(3),
+            query_accession: columns.optional_text(4),
+            query_len: columns.parse(5, "qlen")?,
+            full_sequence: Scores {
+                evalue: columns.parse(6, "full sequence E-value")?,
+                score: columns.parse(7, "full sequence score")?,
+                bias: columns.parse(8, "full sequence bias")?,
+            },
+            domain_index: columns.parse(9, "#")?,
+            domain_count: columns.parse(10, "of")?,
+            c_evalue: columns.parse(11, "c-Evalue")?,
+            i_evalue: columns.parse(12, "i-Evalue")?,
+            score: columns.parse(13, "domain score")?,
+            bias: columns.parse(14, "domain bias")?,
+            hmm_from: columns.parse(15, "hmm from")?,
+            hmm_to: columns.parse(16, "hmm to")?,
+            ali_from: columns.parse(17, "ali from")?,
+            ali_to: columns.parse(18, "ali to")?,
+            env_from: columns.parse(19, "env from")?,
+            env_to: columns.parse(20, "env to")?,
+            accuracy: columns.parse(21, "acc")?,
+            description: columns.description(),
+        })
+    }
+
+    /// Name of the target sequence (or profile, for `hmmscan`).
+    pub fn target_name(&self) -> &str {
+        &self.target_name
+    }
+
+    /// Accession of the target, if any.
+    pub fn target_accession(&self) -> Option<&str> {
+        self.target_accession.as_deref()
+    }
+
+    /// Length of the target.
+    pub fn target_len(&self) -> u64 {
+        self.target_len
+    }
+
+    /// Name of the query profile (or sequence, for `hmmscan`).
+    pub fn query_name(&self) -> &str {
+        &self.query_name
+    }
+
+    /// Accession of the query, if any.
+    pub fn query_accession(&self) -> Option<&str> {
+        self.query_accession.as_deref()
+    }
+
+    /// Length of the query.
+    pub fn query_len(&self) -> u64 {
+        self.query_len
+    }
+
+    /// E-value, score and bias of the full sequence this domain belongs to.
+    pub fn full_sequence(&self) -> Scores {
+        self.full_sequence
+    }
+
+    /// 1-based index of this domain among all domains of the hit (`#`).
+    pub fn domain_index(&self) -> u32 {
+        self.domain_index
+    }
+
+    /// Total number of domains of the hit (`of`).
+    pub fn domain_count(&self) -> u32 {
+        self.domain_count
+    }
+
+    /// Conditional E-value of the domain (`c-Evalue`).
+    pub fn c_evalue(&self) -> f64 {
+        self.c_evalue
+    }
+
+    /// Independent E-value of the domain (`i-Evalue`).
+    pub fn i_evalue(&self) -> f64 {
+        self.i_evalue
+    }
+
+    /// Bit score of the domain.
+    pub fn score(&self) -> f64 {
+        self.score
+    }
+
+    /// Biased-composition correction of the domain score.
+    pub fn bias(&self) -> f64 {
+        self.bias
+    }
+
+    /// Start of the domain alignment on the profile.
+    pub fn hmm_from(&self) -> u64 {
+        self.hmm_from
+    }
+
+    /// End of the domain alignment on the profile.
+    pub fn hmm_to(&self) -> u64 {
+        self.hmm_to
+    }
+
+    /// Start of the domain alignment on the sequence.
+    pub fn ali_from(&self) -> u64 {
+        self.ali_from
+    }
+
+    /// End of the domain alignment on the sequence.
+    pub fn ali_to(&self) -> u64 {
+        self.ali_to
+    }
+
+    /// Start of the domain envelope on the sequence.
+    pub fn env_from(&self) -> u64 {
+        self.env_from
+    }
+
+    /// End of the domain envelope on the sequence.
+    pub fn env_to(&self) -> u64 {
+        self.env_to
+    }
+
+    /// Mean posterior probability of the aligned residues (`acc`).
+    pub fn accuracy(&self) -> f64 {
+        self.accuracy
+    }
+
+    /// Free-text description of the target, if any.
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOMTBLOUT_FILE: &[u8] = b"#                                                                            --- full sequence --- -------------- this domain -------------   hmm coord   ali coord   env coord
+# target name        accession   tlen query name           accession   qlen   E-value  score  bias   #  of  c-Evalue  i-Evalue  score  bias  from    to  from    to  from    to  acc description of target
+#------------------- ---------- ----- -------------------- ---------- ----- --------- ------ ----- --- --- --------- --------- ------ ----- ----- ----- ----- ----- ----- ----- ---- ---------------------
+sp|P0AEC5|BARA_ECOLI -            918 Response_reg         PF00072.25   112   1.4e-24   84.9   0.0   1   2   2.9e-27   3.5e-23   80.3   0.0     1   111   730   841   730   842 0.96 Signal transduction histidine-protein kinase BarA
+sp|P0AEC5|BARA_ECOLI -            918 Response_reg         PF00072.25   112   1.4e-24   84.9   0.0   2   2      0.12   1.4e+03   -1.2   0.0    41    78   880   912   869   915 0.71 Signal transduction histidine-protein kinase BarA
+";
+
+    #[test]
+    fn test_reader() {
+        let mut reader = Reader::new(DOMTBLOUT_FILE);
+        let records = reader
+            .records()
+            .collect::<Result<Vec<_>>>()
+            .expect("Error reading records");
+        assert_eq!(records.len(), 2);
+
+        let first = &records[0];
+        assert_eq!(first.target_name(), "sp|P0AEC5|BARA_ECOLI");
+        assert_eq!(first.target_len(), 918);
+        assert_eq!(first.query_accession(), Some("PF00072.25"));
+        assert_eq!(first.query_len(), 112);
+        assert_eq!(first.full_sequence().score, 84.9);
+        assert_eq!((first.domain_index(), first.domain_count()), (1, 2));
+        assert_eq!(first.c_evalue(), 2.9e-27);
+        assert_eq!(first.i_evalue(), 3.5e-23);
+        assert_eq!(first.score(), 80.3);
+        assert_eq!((first.hmm_from(), first.hmm_to()), (1, 111));
+        assert_eq!((first.ali_from(), first.ali_to()), (730, 841));
+        assert_eq!((first.env_from(), first.env_to()), (730, 842));
+        assert_eq!(first.accuracy(), 0.96);
+        assert_eq!(
+            first.description(),
+            Some("Signal transduction histidine-protein kinase BarA")
+        );
+
+        let second = &records[1];
+        assert_eq!(second.domain_index(), 2);
+        assert_eq!(second.i_evalue(), 1.4e3);
+        assert_eq!(second.score(), -1.2);
+        assert_eq!((second.env_from(), second.env_to()), (869, 915));
+    }
+
+    #[test]
+    fn test_reader_missing_columns() {
+        let domtbl = b"t - 100 q - 50 1e-5 1.0 0.1 1 1\n";
+        let mut reader = Reader::new(&domtbl[..]);
+        match reader.read() {
+            Err(Error::MissingColumns {
+                line: 1,
+                expected: 22,
+                found: 11,
+            }) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
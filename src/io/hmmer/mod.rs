@@ -0,0 +1,195 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Readers for the tabular outputs of [HMMER](http://hmmer.org).
+//!
+//! HMMER's `--tblout` and `--domtblout` files consist of whitespace-separated
+//! columns, except for the last one (the target description), which is free text
+//! that may itself contain whitespace. The readers in this module split off the
+//! fixed columns and keep the remainder of the line as description.
+//! Comment lines (starting with `#`) and empty lines are skipped.
+//!
+//! * [`tblout`](tblout/index.html): per-sequence hits (`hmmsearch`/`hmmscan --tblout`).
+//! * [`domtblout`](domtblout/index.html): per-domain hits (`hmmsearch`/`hmmscan --domtblout`).
+//!
+//! # Example
+//!
+//! ```
+//! use bio::io::hmmer::domtblout;
+//!
+//! let domtbl = b"# comment
+//! sp|P0A9Q1|ARCA_ECOLI - 238 Response_reg PF00072.25 112 2.1e-28 97.2 0.1 1 1 3.6e-32 4.3e-28 96.2 0.1 1 111 6 118 6 119 0.97 Aerobic respiration control protein ArcA
+//! ";
+//! let mut reader = domtblout::Reader::new(&domtbl[..]);
+//! for record in reader.records() {
+//!     let record = record.unwrap();
+//!     assert_eq!(record.query_name(), "Response_reg");
+//!     assert_eq!(record.ali_from(), 6);
+//!     assert_eq!(record.ali_to(), 118);
+//!     assert_eq!(
+//!         record.description(),
+//!         Some("Aerobic respiration control protein ArcA")
+//!     );
+//! }
+//! ```
+
+pub mod domtblout;
+pub mod tblout;
+
+use std::io;
+use std::io::prelude::*;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("can't open {path} file: {source}")]
+    FileOpen { path: PathBuf, source: io::Error },
+
+    #[error("can't read input")]
+    ReadError(#[from] io::Error),
+
+    #[error("line {line}: expected at least {expected} columns, found {found}")]
+    MissingColumns {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+
+    #[error("line {line}: invalid value '{value}' in column '{column}'")]
+    InvalidValue {
+        line: usize,
+        column: &'static str,
+        value: String,
+    },
+}
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Sequence-level or domain-level E-value, bit score and bias of a hit.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Scores {
+    /// E-value.
+    pub evalue: f64,
+    /// Bit score.
+    pub score: f64,
+    /// Biased-composition correction (in bits) that was applied to the score.
+    pub bias: f64,
+}
+
+/// Read the next non-comment, non-empty line into `line`.
+/// Returns `false` once the input is exhausted. `line_number` is advanced for every
+/// line consumed, so that errors can point to the offending line.
+fn read_data_line<R: BufRead>(
+    reader: &mut R,
+    line: &mut String,
+    line_number: &mut usize,
+) -> Result<bool> {
+    loop {
+        line.clear();
+        if reader.read_line(line)? == 0 {
+            return Ok(false);
+        }
+        *line_number += 1;
+        let trimmed = line.trim();
+        if !trimmed.is_empty() && !trimmed.starts_with('#') {
+            return Ok(true);
+        }
+    }
+}
+
+/// The fixed columns of a line, followed by the free-text description (if any).
+struct Columns<'a> {
+    fields: Vec<&'a str>,
+    description: Option<&'a str>,
+    line: usize,
+}
+
+impl<'a> Columns<'a> {
+    /// Split off `n` whitespace-separated columns; everything after the `n`-th column is
+    /// kept verbatim (apart from surrounding whitespace) as description.
+    fn split(text: &'a str, n: usize, line: usize) -> Result<Self> {
+        let mut fields = Vec::with_capacity(n);
+        let mut rest = text.trim();
+        while fields.len() < n && !rest.is_empty() {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            fields.push(&rest[..end]);
+            rest = rest[end..].trim_start();
+        }
+        if fields.len() < n {
+            return Err(Error::MissingColumns {
+                line,
+                expected: n,
+                found: fields.len(),
+            });
+        }
+        let description = match rest {
+            "" | "-" => None,
+            desc => Some(desc),
+        };
+        Ok(Columns {
+            fields,
+            description,
+            line,
+        })
+    }
+
+    fn text(&self, i: usize) -> String {
+        self.fields[i].to_owned()
+    }
+
+    /// Accessions and similar optional columns are given as `-` if absent.
+    fn optional_text(&self, i: usize) -> Option<String> {
+        match self.fields[i] {
+            "-" => None,
+            value => Some(value.to_owned()),
+        }
+    }
+
+    fn parse<T: FromStr>(&self, i: usize, column: &'static str) -> Result<T> {
+        self.fields[i].parse().map_err(|_| Error::InvalidValue {
+            line: self.line,
+            column,
+            value: self.fields[i].to_owned(),
+        })
+    }
+
+    fn description(&self) -> Option<String> {
+        self.description.map(|d| d.to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_keeps_description_whitespace() {
+        let columns = Columns::split("a  b\tc  some  free text ", 3, 1).unwrap();
+        assert_eq!(columns.fields, vec!["a", "b", "c"]);
+        assert_eq!(columns.description, Some("some  free text"));
+    }
+
+    #[test]
+    fn test_split_missing_description() {
+        let columns = Columns::split("a b c -", 3, 1).unwrap();
+        assert_eq!(columns.description, None);
+        let columns = Columns::split("a b c", 3, 1).unwrap();
+        assert_eq!(columns.description, None);
+    }
+
+    #[test]
+    fn test_split_too_few_columns() {
+        match Columns::split("a b", 3, 7) {
+            Err(Error::MissingColumns {
+                line: 7,
+                expected: 3,
+                found: 2,
+            }) => (),
+            other => panic!("unexpected result: {:?}", other.map(|c| c.fields)),
+        }
+    }
+}
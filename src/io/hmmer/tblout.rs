@@ -0,0 +1,315 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Reader for HMMER per-sequence hit tables (`--tblout`).
+//!
+//! Each line holds 18 whitespace-separated columns followed by the free-text
+//! target description.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::io::hmmer::tblout;
+//!
+//! let tbl = b"#  --- full sequence ---- --- best 1 domain ----
+//! ARCA_ECOLI - Response_reg PF00072.25 2.1e-28 97.2 0.1 3.6e-32 96.2 0.1 1.1 1 0 0 1 1 1 1 Aerobic respiration control
+//! ";
+//! let mut reader = tblout::Reader::new(&tbl[..]);
+//! let record = reader.records().next().unwrap().unwrap();
+//! assert_eq!(record.target_name(), "ARCA_ECOLI");
+//! assert_eq!(record.target_accession(), None);
+//! assert_eq!(record.full_sequence().score, 97.2);
+//! assert_eq!(record.description(), Some("Aerobic respiration control"));
+//! ```
+
+use std::convert::AsRef;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use anyhow::Context;
+
+use super::{read_data_line, Columns, Error, Result, Scores};
+
+/// Number of whitespace-separated columns preceding the description.
+const COLUMNS: usize = 18;
+
+/// A reader for HMMER `--tblout` files.
+#[derive(Debug)]
+pub struct Reader<R: io::Read> {
+    reader: io::BufReader<R>,
+    line: String,
+    line_number: usize,
+}
+
+impl Reader<fs::File> {
+    /// Read from a given file path.
+    pub fn from_file<P: AsRef<Path> + std::fmt::Debug>(path: P) -> anyhow::Result<Self> {
+        fs::File::open(path.as_ref())
+            .map_err(|e| Error::FileOpen {
+                path: path.as_ref().to_owned(),
+                source: e,
+            })
+            .map(Reader::new)
+            .with_context(|| format!("Failed to read tblout from {:#?}", path))
+    }
+}
+
+impl<R: io::Read> Reader<R> {
+    /// Read from a given [`io::Read`](https://doc.rust-lang.org/std/io/trait.Read.html).
+    pub fn new(reader: R) -> Self {
+        Reader {
+            reader: io::BufReader::new(reader),
+            line: String::new(),
+            line_number: 0,
+        }
+    }
+
+    /// Read the next record. Returns `Ok(None)` once all records have been read.
+    pub fn read(&mut self) -> Result<Option<Record>> {
+        if !read_data_line(&mut self.reader, &mut self.line, &mut self.line_number)? {
+            return Ok(None);
+        }
+        Record::from_columns(&Columns::split(&self.line, COLUMNS, self.line_number)?).map(Some)
+    }
+
+    /// Iterate over all records.
+    pub fn records(&mut self) -> Records<'_, R> {
+        Records {
+            reader: self,
+            error_has_occured: false,
+        }
+    }
+}
+
+/// An iterator over the records of a `--tblout` file.
+pub struct Records<'a, R: io::Read> {
+    reader: &'a mut Reader<R>,
+    error_has_occured: bool,
+}
+
+impl<'a, R: io::Read> Iterator for Records<'a, R> {
+    type Item = Result<Record>;
+
+    fn next(&mut self) -> Option<Result<Record>> {
+        if self.error_has_occured {
+            return None;
+        }
+        match self.reader.read() {
+            Ok(record) => record.map(Ok),
+            Err(err) => {
+                self.error_has_occured = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// A per-sequence hit of a profile against a target sequence.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    target_name: String,
+    target_accession: Option<String>,
+    query_name: String,
+    query_accession: Option<String>,
+    full_sequence: Scores,
+    best_domain: Scores,
+    expected_domains: f64,
+    regions: u32,
+    clustered: u32,
+    overlaps: u32,
+    envelopes: u32,
+    domains: u32,
+    reported: u32,
+    included: u32,
+    description: Option<String>,
+}
+
+impl Record {
+    fn from_columns(columns: &Columns<'_>) -> Result<Self> {
+        Ok(Record {
+            target_name: columns.text(0),
+            target_accession: columns.optional_text(1),
+            query_name: columns.text(2),
+            query_accession: columns.optional_text(3),
+            full_sequence: Scores {
+                evalue: columns.parse(4, "full sequence E-value")?,
+                score: columns.parse(5, "full sequence score")?,
+                bias: columns.parse(6, "full sequence bias")?,
+            },
+            best_domain: Scores {
+                evalue: columns.parse(7, "best domain E-value")?,
+                score: columns.parse(8, "best domain score")?,
+                bias: columns.parse(9, "best domain bias")?,
+            },
+            expected_domains: columns.parse(10, "exp")?,
+            regions: columns.parse(11, "reg")?,
+            clustered: columns.parse(12, "clu")?,
+            overlaps: columns.parse(13, "ov")?,
+            envelopes: columns.parse(14, "env")?,
+            domains: columns.parse(15, "dom")?,
+            reported: columns.parse(16, "rep")?,
+            included: columns.parse(17, "inc")?,
+            description: columns.description(),
+        })
+    }
+
+    /// Name of the target sequence (or profile, for `hmmscan`).
+    pub fn target_name(&self) -> &str {
+        &self.target_name
+    }
+
+    /// Accession of the target, if any.
+    pub fn target_accession(&self) -> Option<&str> {
+        self.target_accession.as_deref()
+    }
+
+    /// Name of the query profile (or sequence, for `hmmscan`).
+    pub fn query_name(&self) -> &str {
+        &self.query_name
+    }
+
+    /// Accession of the query, if any.
+    pub fn query_accession(&self) -> Option<&str> {
+        self.query_accession.as_deref()
+    }
+
+    /// E-value, score and bias of the full sequence.
+    pub fn full_sequence(&self) -> Scores {
+        self.full_sequence
+    }
+
+    /// E-value, score and bias of the single best-scoring domain.
+    pub fn best_domain(&self) -> Scores {
+        self.best_domain
+    }
+
+    /// Expected number of domains (`exp`).
+    pub fn expected_domains(&self) -> f64 {
+        self.expected_domains
+    }
+
+    /// Number of discrete regions defined by posterior decoding (`reg`).
+    pub fn regions(&self) -> u32 {
+        self.regions
+    }
+
+    /// Number of regions that required multidomain clustering (`clu`).
+    pub fn clustered(&self) -> u32 {
+        self.clustered
+    }
+
+    /// Number of envelopes that overlap with others (`ov`).
+    pub fn overlaps(&self) -> u32 {
+        self.overlaps
+    }
+
+    /// Number of envelopes defined (`env`).
+    pub fn envelopes(&self) -> u32 {
+        self.envelopes
+    }
+
+    /// Number of domains defined (`dom`).
+    pub fn domains(&self) -> u32 {
+        self.domains
+    }
+
+    /// Number of domains satisfying the reporting thresholds (`rep`).
+    pub fn reported(&self) -> u32 {
+        self.reported
+    }
+
+    /// Number of domains satisfying the inclusion thresholds (`inc`).
+    pub fn included(&self) -> u32 {
+        self.included
+    }
+
+    /// Free-text description of the target, if any.
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TBLOUT_FILE: &[u8] = b"#                                                               --- full sequence ---- --- best 1 domain ---- --- domain number estimation ----
+# target name        accession  query name           accession    E-value  score  bias   E-value  score  bias   exp reg clu  ov env dom rep inc description of target
+#------------------- ---------- -------------------- ---------- --------- ------ ----- --------- ------ -----   --- --- --- --- --- --- --- --- ---------------------
+sp|P0A9Q1|ARCA_ECOLI -          Response_reg         PF00072.25   2.1e-28   97.2   0.1   3.6e-32   96.2   0.1   1.5   1   0   0   1   1   1   1 Aerobic respiration control protein ArcA OS=Escherichia coli
+sp|P0AEC5|BARA_ECOLI -          Response_reg         PF00072.25   1.4e-24   84.9   0.0   2.9e-27   83.1   0.0   2.0   2   0   0   2   2   2   1 -
+#
+# Program:         hmmsearch
+";
+
+    #[test]
+    fn test_reader() {
+        let mut reader = Reader::new(TBLOUT_FILE);
+        let records = reader
+            .records()
+            .collect::<Result<Vec<_>>>()
+            .expect("Error reading records");
+        assert_eq!(records.len(), 2);
+
+        let arca = &records[0];
+        assert_eq!(arca.target_name(), "sp|P0A9Q1|ARCA_ECOLI");
+        assert_eq!(arca.target_accession(), None);
+        assert_eq!(arca.query_name(), "Response_reg");
+        assert_eq!(arca.query_accession(), Some("PF00072.25"));
+        assert_eq!(
+            arca.full_sequence(),
+            Scores {
+                evalue: 2.1e-28,
+                score: 97.2,
+                bias: 0.1
+            }
+        );
+        assert_eq!(arca.best_domain().evalue, 3.6e-32);
+        assert_eq!(arca.expected_domains(), 1.5);
+        assert_eq!(arca.regions(), 1);
+        assert_eq!(arca.included(), 1);
+        assert_eq!(
+            arca.description(),
+            Some("Aerobic respiration control protein ArcA OS=Escherichia coli")
+        );
+
+        let bara = &records[1];
+        assert_eq!(bara.domains(), 2);
+        assert_eq!(bara.reported(), 2);
+        assert_eq!(bara.included(), 1);
+        assert_eq!(bara.description(), None);
+    }
+
+    #[test]
+    fn test_reader_invalid_value() {
+        let tbl = b"t - q - 1e-5 x 0.1 1e-5 1.0 0.1 1.0 1 0 0 1 1 1 1\n";
+        let mut reader = Reader::new(&tbl[..]);
+        match reader.read() {
+            Err(Error::InvalidValue { line: 1, value, .. }) => assert_eq!(value, "x"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_reader_stops_after_error() {
+        let tbl = b"t - q\nt - q - 1e-5 1.0 0.1 1e-5 1.0 0.1 1.0 1 0 0 1 1 1 1\n";
+        let mut reader = Reader::new(&tbl[..]);
+        let mut records = reader.records();
+        assert!(records.next().unwrap().is_err());
+        assert!(records.next().is_none());
+    }
+
+    #[test]
+    fn test_reader_from_file_path_doesnt_exist_returns_err() {
+        let path = Path::new("/I/dont/exist.tbl");
+        let error = Reader::from_file(path)
+            .unwrap_err()
+            .downcast::<String>()
+            .unwrap();
+
+        assert_eq!(&error, "Failed to read tblout from \"/I/dont/exist.tbl\"")
+    }
+}
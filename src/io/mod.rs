@@ -1,8 +1,15 @@
 //! Readers and writers for common bioinformatics file formats.
 
+pub mod autodetect;
 pub mod bed;
+pub mod bgzf;
+#[cfg(feature = "binary")]
+pub mod binary;
 pub mod fasta;
 pub mod fastq;
+pub mod gfa;
 pub mod gff;
+pub mod hmmer;
+pub mod motif;
 #[cfg(feature = "phylogeny")]
 pub mod newick;
@@ -0,0 +1,283 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Reader for JASPAR position frequency matrices.
+//!
+//! Each motif starts with a header line `>ID name`, followed by one row per nucleotide.
+//! Rows are either labelled and bracketed (`.jaspar` format, e.g. `A [ 4 19 0 ]`), or plain
+//! counts in the order A, C, G, T (`.pfm` format). Files holding probabilities instead of
+//! counts (position weight matrices) are read the same way.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::io::motif::jaspar;
+//!
+//! let pfm = b">MA0004.1 Arnt
+//! 4 19  0  0  0  0
+//! 16  0 20  0  0  0
+//! 0  1  0 20  0 20
+//! 0  0  0  0 20  0
+//! ";
+//! let mut reader = jaspar::Reader::new(&pfm[..]);
+//! for record in reader.records() {
+//!     let record = record.unwrap();
+//!     assert_eq!(record.id(), "MA0004.1");
+//!     assert_eq!(record.len(), 6);
+//!     assert_eq!(record.counts()[0], [4.0, 16.0, 0.0, 0.0]);
+//! }
+//! ```
+
+use std::convert::AsRef;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use anyhow::Context;
+
+use super::{parse_all, read_line, Error, Result};
+use crate::pattern_matching::motif::{self, Pwm, DNA};
+
+/// A reader for JASPAR matrix files.
+#[derive(Debug)]
+pub struct Reader<R: io::Read> {
+    reader: io::BufReader<R>,
+    line: String,
+    line_number: usize,
+}
+
+impl Reader<fs::File> {
+    /// Read from a given file path.
+    pub fn from_file<P: AsRef<Path> + std::fmt::Debug>(path: P) -> anyhow::Result<Self> {
+        fs::File::open(path.as_ref())
+            .map_err(|e| Error::FileOpen {
+                path: path.as_ref().to_owned(),
+                source: e,
+            })
+            .map(Reader::new)
+            .with_context(|| format!("Failed to read JASPAR matrices from {:#?}", path))
+    }
+}
+
+impl<R: io::Read> Reader<R> {
+    /// Read from a given [`io::Read`](https://doc.rust-lang.org/std/io/trait.Read.html).
+    pub fn new(reader: R) -> Self {
+        Reader {
+            reader: io::BufReader::new(reader),
+            line: String::new(),
+            line_number: 0,
+        }
+    }
+
+    /// Read the next record. Returns `Ok(None)` once all records have been read.
+    pub fn read(&mut self) -> Result<Option<Record>> {
+        if !read_line(&mut self.reader, &mut self.line, &mut self.line_number)? {
+            return Ok(None);
+        }
+        let header = match self.line.trim().strip_prefix('>') {
+            Some(header) => header.trim(),
+            None => {
+                return Err(Error::UnexpectedLine {
+                    line: self.line_number,
+                })
+            }
+        };
+        let mut fields = header.splitn(2, char::is_whitespace);
+        let id = fields.next().unwrap_or("").to_owned();
+        let name = fields
+            .next()
+            .map(|name| name.trim().to_owned())
+            .filter(|name| !name.is_empty());
+
+        let mut rows: [Option<Vec<f64>>; 4] = Default::default();
+        for i in 0..DNA.len() {
+            if !read_line(&mut self.reader, &mut self.line, &mut self.line_number)? {
+                return Err(Error::IncompleteMotif { id });
+            }
+            let line = self.line.trim();
+            // labelled rows may come in any order, plain rows are in the order of DNA
+            let (k, values) = match line.as_bytes()[0] {
+                b'>' => return Err(Error::IncompleteMotif { id }),
+                a if a.is_ascii_alphabetic() => {
+                    let k = DNA
+                        .iter()
+                        .position(|&b| b == a.to_ascii_uppercase())
+                        .ok_or_else(|| Error::InvalidValue {
+                            line: self.line_number,
+                            value: line.to_owned(),
+                        })?;
+                    (
+                        k,
+                        line[1..]
+                            .trim()
+                            .trim_start_matches('[')
+                            .trim_end_matches(']'),
+                    )
+                }
+                _ => (i, line),
+            };
+            let values = parse_all(values, self.line_number)?;
+            if let Some(row) = rows.iter().flatten().next() {
+                if row.len() != values.len() {
+                    return Err(Error::InconsistentLen {
+                        line: self.line_number,
+                        expected: row.len(),
+                        found: values.len(),
+                    });
+                }
+            }
+            rows[k] = Some(values);
+        }
+        let rows = rows
+            .iter()
+            .map(|row| {
+                row.as_ref()
+                    .ok_or_else(|| Error::IncompleteMotif { id: id.clone() })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let counts = (0..rows[0].len())
+            .map(|j| rows.iter().map(|row| row[j]).collect())
+            .collect();
+        Ok(Some(Record { id, name, counts }))
+    }
+
+    /// Iterate over all records.
+    pub fn records(&mut self) -> Records<'_, R> {
+        Records {
+            reader: self,
+            error_has_occured: false,
+        }
+    }
+}
+
+/// An iterator over the records of a JASPAR file.
+pub struct Records<'a, R: io::Read> {
+    reader: &'a mut Reader<R>,
+    error_has_occured: bool,
+}
+
+impl<'a, R: io::Read> Iterator for Records<'a, R> {
+    type Item = Result<Record>;
+
+    fn next(&mut self) -> Option<Result<Record>> {
+        if self.error_has_occured {
+            return None;
+        }
+        match self.reader.read() {
+            Ok(record) => record.map(Ok),
+            Err(err) => {
+                self.error_has_occured = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// A JASPAR matrix.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    id: String,
+    name: Option<String>,
+    counts: Vec<Vec<f64>>,
+}
+
+impl Record {
+    /// Matrix ID, e.g. `MA0004.1`.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Name of the transcription factor, if any.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Length of the motif.
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Whether the motif is empty.
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Counts of A, C, G and T at each position.
+    pub fn counts(&self) -> &[Vec<f64>] {
+        &self.counts
+    }
+
+    /// Position weight matrix of the motif, adding the given pseudocount to each count.
+    pub fn pwm(&self, pseudocount: f64) -> motif::Result<Pwm> {
+        Pwm::from_counts(DNA, &self.counts, pseudocount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JASPAR: &[u8] = b">MA0001.1 AGL3
+A  [ 0  3 ]
+C  [94 75 ]
+G  [ 1  0 ]
+T  [ 2 19 ]
+
+>MA0002.2\tRUNX1
+T  [ 1  2 ]
+G  [ 3  4 ]
+C  [ 5  6 ]
+A  [ 7  8 ]
+";
+
+    #[test]
+    fn test_read() {
+        let mut reader = Reader::new(JASPAR);
+        let records: Vec<Record> = reader.records().map(|r| r.unwrap()).collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].id(), "MA0001.1");
+        assert_eq!(records[0].name(), Some("AGL3"));
+        assert_eq!(
+            records[0].counts(),
+            [vec![0.0, 94.0, 1.0, 2.0], vec![3.0, 75.0, 0.0, 19.0]]
+        );
+        assert_eq!(records[1].name(), Some("RUNX1"));
+        assert_eq!(
+            records[1].counts(),
+            [vec![7.0, 5.0, 3.0, 1.0], vec![8.0, 6.0, 4.0, 2.0]]
+        );
+        let pwm = records[1].pwm(0.0).unwrap();
+        assert_eq!(pwm.probabilities()[0], [0.4375, 0.3125, 0.1875, 0.0625]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut reader = Reader::new(&b"A [ 1 2 ]\n"[..]);
+        assert!(matches!(
+            reader.read(),
+            Err(Error::UnexpectedLine { line: 1 })
+        ));
+
+        let mut reader = Reader::new(&b">M1\n1 2\n3 4\n5\n"[..]);
+        assert!(matches!(
+            reader.read(),
+            Err(Error::InconsistentLen {
+                line: 4,
+                expected: 2,
+                found: 1
+            })
+        ));
+
+        let mut reader = Reader::new(&b">M1\n1 2\n3 4\n>M2\n"[..]);
+        assert!(matches!(reader.read(), Err(Error::IncompleteMotif { .. })));
+
+        let mut reader = Reader::new(&b">M1\nA [1 x]\n"[..]);
+        assert!(matches!(
+            reader.read(),
+            Err(Error::InvalidValue { line: 2, .. })
+        ));
+    }
+}
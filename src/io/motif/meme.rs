@@ -0,0 +1,447 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Reader for motifs in [MEME](https://meme-suite.org/meme/doc/meme-format.html) minimal
+//! format, as well as the letter-probability matrices of MEME text output (`meme.txt`).
+//!
+//! The reader picks up the alphabet (`ALPHABET= ACGT`), the background letter frequencies,
+//! and for each `MOTIF` line the following `letter-probability matrix`. Other lines are
+//! ignored. If no alphabet is given (or it is given as a custom alphabet definition), it is
+//! inferred from the number of columns as DNA (4) or protein (20), in the column order of
+//! [`motif::DNA`](../../../pattern_matching/motif/constant.DNA.html) and
+//! [`motif::PROTEIN`](../../../pattern_matching/motif/constant.PROTEIN.html).
+//!
+//! # Example
+//!
+//! ```
+//! use bio::io::motif::meme;
+//!
+//! let motifs = b"MEME version 4
+//!
+//! ALPHABET= ACGT
+//!
+//! Background letter frequencies
+//! A 0.3 C 0.2 G 0.2 T 0.3
+//!
+//! MOTIF crp CRP
+//! letter-probability matrix: alength= 4 w= 3 nsites= 10 E= 1e-5
+//!  0.0 0.1 0.0 0.9
+//!  0.0 0.0 1.0 0.0
+//!  0.6 0.0 0.2 0.2
+//! ";
+//! let mut reader = meme::Reader::new(&motifs[..]);
+//! let record = reader.records().next().unwrap().unwrap();
+//! assert_eq!(record.id(), "crp");
+//! assert_eq!(record.alt_name(), Some("CRP"));
+//! assert_eq!(record.evalue(), Some(1e-5));
+//! assert_eq!(record.background(), Some(&[0.3, 0.2, 0.2, 0.3][..]));
+//!
+//! let pssm = record
+//!     .pwm(0.1)
+//!     .unwrap()
+//!     .log_odds(record.background())
+//!     .unwrap();
+//! assert_eq!(pssm.find_all(b"CCTGAC", 3.0)[0].pos, 2);
+//! ```
+
+use std::convert::AsRef;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use anyhow::Context;
+
+use super::{parse, parse_all, read_line, Error, Result};
+use crate::pattern_matching::motif::{self, Pwm, DNA, PROTEIN};
+
+/// Number of sites assumed by MEME if a matrix does not specify it.
+const DEFAULT_NSITES: f64 = 20.0;
+
+/// A reader for MEME motif files.
+#[derive(Debug)]
+pub struct Reader<R: io::Read> {
+    reader: io::BufReader<R>,
+    line: String,
+    line_number: usize,
+    /// Whether the current line still has to be processed.
+    pending_line: bool,
+    symbols: Option<Vec<u8>>,
+    background: Vec<(u8, f64)>,
+    /// ID and alternative name of the last `MOTIF` line.
+    motif: Option<(String, Option<String>)>,
+}
+
+impl Reader<fs::File> {
+    /// Read from a given file path.
+    pub fn from_file<P: AsRef<Path> + std::fmt::Debug>(path: P) -> anyhow::Result<Self> {
+        fs::File::open(path.as_ref())
+            .map_err(|e| Error::FileOpen {
+                path: path.as_ref().to_owned(),
+                source: e,
+            })
+            .map(Reader::new)
+            .with_context(|| format!("Failed to read MEME motifs from {:#?}", path))
+    }
+}
+
+impl<R: io::Read> Reader<R> {
+    /// Read from a given [`io::Read`](https://doc.rust-lang.org/std/io/trait.Read.html).
+    pub fn new(reader: R) -> Self {
+        Reader {
+            reader: io::BufReader::new(reader),
+            line: String::new(),
+            line_number: 0,
+            pending_line: false,
+            symbols: None,
+            background: Vec::new(),
+            motif: None,
+        }
+    }
+
+    fn next_line(&mut self) -> Result<bool> {
+        if self.pending_line {
+            self.pending_line = false;
+            return Ok(true);
+        }
+        read_line(&mut self.reader, &mut self.line, &mut self.line_number)
+    }
+
+    /// Read the next record. Returns `Ok(None)` once all records have been read.
+    pub fn read(&mut self) -> Result<Option<Record>> {
+        while self.next_line()? {
+            let line = self.line.trim();
+            if let Some(symbols) = line.strip_prefix("ALPHABET=") {
+                self.symbols = Some(
+                    symbols
+                        .bytes()
+                        .filter(|a| !a.is_ascii_whitespace())
+                        .collect(),
+                );
+            } else if line.starts_with("Background letter frequencies") {
+                self.read_background()?;
+            } else if let Some(header) = line.strip_prefix("MOTIF") {
+                let mut fields = header.split_whitespace();
+                let id = fields.next().ok_or(Error::UnexpectedLine {
+                    line: self.line_number,
+                })?;
+                // MEME text output continues with "width = ..."
+                let alt_name = fields.next().filter(|&name| name != "width");
+                self.motif = Some((id.to_owned(), alt_name.map(|name| name.to_owned())));
+            } else if line.starts_with("letter-probability matrix") {
+                return self.read_matrix().map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    fn read_background(&mut self) -> Result<()> {
+        self.background.clear();
+        while self.next_line()? {
+            let fields: Vec<&str> = self.line.split_whitespace().collect();
+            let is_frequency = |pair: &[&str]| pair[0].len() == 1 && pair[1].parse::<f64>().is_ok();
+            let pairs = fields.chunks_exact(2);
+            if !pairs.remainder().is_empty() || !pairs.clone().all(is_frequency) {
+                self.pending_line = true;
+                break;
+            }
+            for pair in pairs {
+                self.background
+                    .push((pair[0].as_bytes()[0], parse(pair[1], self.line_number)?));
+            }
+        }
+        Ok(())
+    }
+
+    fn read_matrix(&mut self) -> Result<Record> {
+        let header_line = self.line_number;
+        let (id, alt_name) = self
+            .motif
+            .take()
+            .ok_or(Error::UnexpectedLine { line: header_line })?;
+
+        // key-value pairs like "alength= 4 w= 19 nsites= 17 E= 4.1e-009"
+        let mut alength = None;
+        let mut width = None;
+        let mut nsites = None;
+        let mut evalue = None;
+        let header = self
+            .line
+            .split_once(':')
+            .map_or("", |(_, pairs)| pairs)
+            .replace('=', "= ");
+        let mut fields = header.split_whitespace();
+        while let Some(key) = fields.next() {
+            let value = match fields.next() {
+                Some(value) if key.ends_with('=') => value,
+                _ => continue,
+            };
+            match key {
+                "alength=" => alength = Some(parse::<usize>(value, header_line)?),
+                "w=" => width = Some(parse::<usize>(value, header_line)?),
+                "nsites=" => nsites = Some(parse::<f64>(value, header_line)?),
+                "E=" => evalue = Some(parse::<f64>(value, header_line)?),
+                _ => (),
+            }
+        }
+
+        let mut probs: Vec<Vec<f64>> = Vec::new();
+        while !matches!(width, Some(w) if probs.len() >= w) && self.next_line()? {
+            let row = match parse_all::<f64>(&self.line, self.line_number) {
+                Ok(row) => row,
+                Err(_) if width.is_none() => {
+                    self.pending_line = true;
+                    break;
+                }
+                Err(e) => return Err(e),
+            };
+            let expected = alength.or_else(|| probs.first().map(|row| row.len()));
+            if let Some(expected) = expected {
+                if row.len() != expected {
+                    return Err(Error::InconsistentLen {
+                        line: self.line_number,
+                        expected,
+                        found: row.len(),
+                    });
+                }
+            }
+            probs.push(row);
+        }
+        if probs.is_empty() || matches!(width, Some(w) if probs.len() < w) {
+            return Err(Error::IncompleteMotif { id });
+        }
+
+        let columns = probs[0].len();
+        let symbols = match &self.symbols {
+            Some(symbols) if symbols.len() == columns => symbols.clone(),
+            Some(symbols) => {
+                return Err(Error::InconsistentLen {
+                    line: header_line,
+                    expected: symbols.len(),
+                    found: columns,
+                })
+            }
+            None if columns == DNA.len() => DNA.to_vec(),
+            None if columns == PROTEIN.len() => PROTEIN.to_vec(),
+            None => {
+                return Err(Error::InvalidValue {
+                    line: header_line,
+                    value: format!("alength= {}", columns),
+                })
+            }
+        };
+        let background = symbols
+            .iter()
+            .map(|&a| {
+                self.background
+                    .iter()
+                    .find(|&&(b, _)| b.eq_ignore_ascii_case(&a))
+                    .map(|&(_, q)| q)
+            })
+            .collect();
+
+        Ok(Record {
+            id,
+            alt_name,
+            symbols,
+            background,
+            nsites,
+            evalue,
+            probs,
+        })
+    }
+
+    /// Iterate over all records.
+    pub fn records(&mut self) -> Records<'_, R> {
+        Records {
+            reader: self,
+            error_has_occured: false,
+        }
+    }
+}
+
+/// An iterator over the records of a MEME file.
+pub struct Records<'a, R: io::Read> {
+    reader: &'a mut Reader<R>,
+    error_has_occured: bool,
+}
+
+impl<'a, R: io::Read> Iterator for Records<'a, R> {
+    type Item = Result<Record>;
+
+    fn next(&mut self) -> Option<Result<Record>> {
+        if self.error_has_occured {
+            return None;
+        }
+        match self.reader.read() {
+            Ok(record) => record.map(Ok),
+            Err(err) => {
+                self.error_has_occured = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// A MEME motif.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    id: String,
+    alt_name: Option<String>,
+    symbols: Vec<u8>,
+    background: Option<Vec<f64>>,
+    nsites: Option<f64>,
+    evalue: Option<f64>,
+    probs: Vec<Vec<f64>>,
+}
+
+impl Record {
+    /// Motif ID.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Alternative name of the motif, if any.
+    pub fn alt_name(&self) -> Option<&str> {
+        self.alt_name.as_deref()
+    }
+
+    /// Symbols of the motif, in column order.
+    pub fn symbols(&self) -> &[u8] {
+        &self.symbols
+    }
+
+    /// Background frequencies of the symbols, if given for all of them.
+    pub fn background(&self) -> Option<&[f64]> {
+        self.background.as_deref()
+    }
+
+    /// Number of sites the motif was built from, if given.
+    pub fn nsites(&self) -> Option<f64> {
+        self.nsites
+    }
+
+    /// E-value of the motif, if given.
+    pub fn evalue(&self) -> Option<f64> {
+        self.evalue
+    }
+
+    /// Length of the motif.
+    pub fn len(&self) -> usize {
+        self.probs.len()
+    }
+
+    /// Whether the motif is empty.
+    pub fn is_empty(&self) -> bool {
+        self.probs.is_empty()
+    }
+
+    /// Probabilities of the symbols (columns) at each position (rows).
+    pub fn probabilities(&self) -> &[Vec<f64>] {
+        &self.probs
+    }
+
+    /// Position weight matrix of the motif. The probabilities are turned into counts with the
+    /// number of sites (20 if not given, as in MEME), to which the given pseudocount is added.
+    pub fn pwm(&self, pseudocount: f64) -> motif::Result<Pwm> {
+        let nsites = self.nsites.unwrap_or(DEFAULT_NSITES);
+        let counts: Vec<Vec<f64>> = self
+            .probs
+            .iter()
+            .map(|row| row.iter().map(|p| p * nsites).collect())
+            .collect();
+        Pwm::from_counts(&self.symbols, &counts, pseudocount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MEME_TXT: &[u8] =
+        b"********************************************************************************
+MEME - Motif discovery tool
+********************************************************************************
+ALPHABET= ACGT
+Background letter frequencies (from dataset with add-one prior applied):
+A 0.302 C 0.183
+G 0.209 T 0.306
+********************************************************************************
+MOTIF  1\twidth =  2  sites =  4  llr = 20  E-value = 2.0e-003
+--------------------------------------------------------------------------------
+\tMotif 1 position-specific probability matrix
+--------------------------------------------------------------------------------
+letter-probability matrix: alength= 4 w= 2 nsites= 4 E= 2.0e-003
+  1.000000  0.000000  0.000000  0.000000
+  0.000000  0.250000  0.750000  0.000000
+--------------------------------------------------------------------------------
+";
+
+    #[test]
+    fn test_meme_txt() {
+        let mut reader = Reader::new(MEME_TXT);
+        let records: Vec<Record> = reader.records().map(|r| r.unwrap()).collect();
+        assert_eq!(records.len(), 1);
+        let record = &records[0];
+        assert_eq!(record.id(), "1");
+        assert_eq!(record.alt_name(), None);
+        assert_eq!(record.nsites(), Some(4.0));
+        assert_eq!(record.evalue(), Some(2e-3));
+        assert_eq!(record.background(), Some(&[0.302, 0.183, 0.209, 0.306][..]));
+        assert_eq!(record.probabilities()[1], [0.0, 0.25, 0.75, 0.0]);
+        let pwm = record.pwm(1.0).unwrap();
+        assert_eq!(
+            pwm.probabilities()[0],
+            [5.0 / 8.0, 1.0 / 8.0, 1.0 / 8.0, 1.0 / 8.0]
+        );
+    }
+
+    #[test]
+    fn test_minimal_without_alphabet() {
+        let motifs = b"MEME version 4
+
+MOTIF M1
+letter-probability matrix:
+0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05
+0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05
+URL http://example.org
+
+MOTIF M2 alt
+letter-probability matrix: alength= 20 w= 1
+0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05 0.05
+";
+        let mut reader = Reader::new(&motifs[..]);
+        let records: Vec<Record> = reader.records().map(|r| r.unwrap()).collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].len(), 2);
+        assert_eq!(records[0].symbols(), PROTEIN);
+        assert_eq!(records[0].background(), None);
+        assert_eq!(records[1].id(), "M2");
+        assert_eq!(records[1].alt_name(), Some("alt"));
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut reader = Reader::new(&b"letter-probability matrix: w= 1\n0.5 0.5\n"[..]);
+        assert!(matches!(
+            reader.read(),
+            Err(Error::UnexpectedLine { line: 1 })
+        ));
+
+        let motifs = b"ALPHABET= ACGT\nMOTIF M\nletter-probability matrix: w= 1\n0.5 0.5\n";
+        let mut reader = Reader::new(&motifs[..]);
+        assert!(matches!(
+            reader.read(),
+            Err(Error::InconsistentLen {
+                line: 3,
+                expected: 4,
+                found: 2
+            })
+        ));
+
+        let motifs = b"MOTIF M\nletter-probability matrix: alength= 4 w= 2\n0.25 0.25 0.25 0.25\n";
+        let mut reader = Reader::new(&motifs[..]);
+        assert!(matches!(reader.read(), Err(Error::IncompleteMotif { .. })));
+    }
+}
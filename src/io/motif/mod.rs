@@ -0,0 +1,102 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Readers for motif databases, yielding matrices that can be turned into position weight
+//! matrices of the [`motif`](../../pattern_matching/motif/index.html) module and scanned
+//! against sequences.
+//!
+//! * [`meme`](meme/index.html): MEME minimal motif format and MEME text output.
+//! * [`jaspar`](jaspar/index.html): JASPAR position frequency matrices (`.jaspar` and `.pfm`).
+//!
+//! # Example
+//!
+//! ```
+//! use bio::io::motif::jaspar;
+//!
+//! let pfm = b">MA0004.1 Arnt
+//! A  [ 4 19  0  0  0  0 ]
+//! C  [16  0 20  0  0  0 ]
+//! G  [ 0  1  0 20  0 20 ]
+//! T  [ 0  0  0  0 20  0 ]
+//! ";
+//! let mut reader = jaspar::Reader::new(&pfm[..]);
+//! let record = reader.records().next().unwrap().unwrap();
+//! assert_eq!(record.name(), Some("Arnt"));
+//! let pssm = record.pwm(0.8).unwrap().log_odds(None).unwrap();
+//! let hits = pssm.find_all(b"TTCACGTGAA", 5.0);
+//! assert_eq!(hits.len(), 1);
+//! assert_eq!(hits[0].pos, 2);
+//! ```
+
+pub mod jaspar;
+pub mod meme;
+
+use std::io;
+use std::io::prelude::*;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("can't open {path} file: {source}")]
+    FileOpen { path: PathBuf, source: io::Error },
+
+    #[error("can't read input")]
+    ReadError(#[from] io::Error),
+
+    #[error("line {line}: invalid value '{value}'")]
+    InvalidValue { line: usize, value: String },
+
+    #[error("line {line}: expected {expected} values, found {found}")]
+    InconsistentLen {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+
+    #[error("line {line}: unexpected line outside of a motif")]
+    UnexpectedLine { line: usize },
+
+    #[error("motif {id} ends before all of its rows were read")]
+    IncompleteMotif { id: String },
+}
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Read the next non-empty line into `line`, returning `false` once the input is exhausted.
+/// `line_number` is advanced for every line consumed, so that errors can point to the
+/// offending line.
+fn read_line<R: BufRead>(
+    reader: &mut R,
+    line: &mut String,
+    line_number: &mut usize,
+) -> Result<bool> {
+    loop {
+        line.clear();
+        if reader.read_line(line)? == 0 {
+            return Ok(false);
+        }
+        *line_number += 1;
+        if !line.trim().is_empty() {
+            return Ok(true);
+        }
+    }
+}
+
+/// Parse a value of the given line.
+fn parse<T: FromStr>(value: &str, line: usize) -> Result<T> {
+    value.parse().map_err(|_| Error::InvalidValue {
+        line,
+        value: value.to_owned(),
+    })
+}
+
+/// Parse all whitespace-separated values of the given text.
+fn parse_all<T: FromStr>(text: &str, line: usize) -> Result<Vec<T>> {
+    text.split_whitespace()
+        .map(|value| parse(value, line))
+        .collect()
+}
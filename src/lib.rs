@@ -237,6 +237,8 @@ pub mod data_structures;
 pub mod io;
 pub mod pattern_matching;
 pub mod scores;
+pub mod seq;
 pub mod seq_analysis;
 pub mod stats;
 pub mod utils;
+pub mod variant;
@@ -0,0 +1,314 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Algorithm of Aho and Corasick for searching a set of patterns simultaneously, e.g. for
+//! screening reads for adapters or finding CRISPR spacers.
+//! Constructs a trie of the patterns, in which each node links to the node of its longest
+//! proper suffix that is also present in the trie (failure links). Scanning the text then
+//! follows the trie and falls back along the failure links on mismatches.
+//! Complexity: O(m) for building the automaton for patterns of total length m, and
+//! O(n + z) for scanning a text of length n with z matches.
+//!
+//! Optionally, the reverse complements of DNA patterns can be searched as well.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::pattern_matching::aho_corasick::AhoCorasick;
+//!
+//! let patterns = [&b"GATT"[..], b"ATTAC", b"TAC"];
+//! let ac = AhoCorasick::new(&patterns);
+//! let text = b"CGATTACA";
+//! let occ: Vec<(usize, usize)> = ac.find_all(text).map(|m| (m.pattern, m.start)).collect();
+//! assert_eq!(occ, [(0, 1), (1, 2), (2, 4)]);
+//!
+//! // also report matches of the reverse complements
+//! let ac = AhoCorasick::with_revcomp(&[b"AACC"]);
+//! let hits: Vec<_> = ac.find_all(b"AACCTTGGTT").collect();
+//! assert_eq!(hits.len(), 2);
+//! assert!(hits[1].reverse);
+//! assert_eq!((hits[1].start, hits[1].end), (6, 10));
+//! ```
+
+use std::borrow::Borrow;
+use std::iter::Enumerate;
+
+use crate::alphabets::dna;
+
+/// A match of a pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match {
+    /// Index of the pattern, as given at construction.
+    pub pattern: usize,
+    /// Start position of the match in the text.
+    pub start: usize,
+    /// End position (exclusive) of the match in the text.
+    pub end: usize,
+    /// Whether the reverse complement of the pattern matched.
+    pub reverse: bool,
+}
+
+/// A node of the trie.
+#[derive(Debug, Clone, Default)]
+struct Node {
+    /// Outgoing edges, sorted by symbol.
+    children: Vec<(u8, usize)>,
+    fail: usize,
+    /// Patterns (index into `AhoCorasick::patterns`) ending at this node or at a node
+    /// reachable via failure links.
+    outputs: Vec<usize>,
+}
+
+impl Node {
+    fn child(&self, a: u8) -> Option<usize> {
+        self.children
+            .binary_search_by_key(&a, |&(b, _)| b)
+            .ok()
+            .map(|k| self.children[k].1)
+    }
+}
+
+/// Aho-Corasick automaton.
+#[derive(Debug, Clone)]
+pub struct AhoCorasick {
+    nodes: Vec<Node>,
+    /// Pattern index, strand and length of each searched sequence.
+    patterns: Vec<(usize, bool, usize)>,
+}
+
+impl AhoCorasick {
+    /// Create a new instance for the given patterns. Empty patterns are ignored.
+    pub fn new<I, P>(patterns: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<[u8]>,
+    {
+        AhoCorasick::build(patterns, false)
+    }
+
+    /// Create a new instance for the given DNA patterns and their reverse complements.
+    /// Palindromic patterns are only searched once.
+    pub fn with_revcomp<I, P>(patterns: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<[u8]>,
+    {
+        AhoCorasick::build(patterns, true)
+    }
+
+    fn build<I, P>(patterns: I, revcomp: bool) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<[u8]>,
+    {
+        let mut ac = AhoCorasick {
+            nodes: vec![Node::default()],
+            patterns: Vec::new(),
+        };
+        for (i, pattern) in patterns.into_iter().enumerate() {
+            let pattern = pattern.as_ref();
+            ac.insert(pattern, i, false);
+            if revcomp {
+                let rc = dna::revcomp(pattern);
+                if rc != pattern {
+                    ac.insert(&rc, i, true);
+                }
+            }
+        }
+
+        // compute failure links in breadth-first order
+        let mut queue: Vec<usize> = ac.nodes[0].children.iter().map(|&(_, v)| v).collect();
+        let mut k = 0;
+        while k < queue.len() {
+            let u = queue[k];
+            k += 1;
+            for (a, v) in ac.nodes[u].children.clone() {
+                let mut f = ac.nodes[u].fail;
+                let fail = loop {
+                    if let Some(w) = ac.nodes[f].child(a) {
+                        break w;
+                    }
+                    if f == 0 {
+                        break 0;
+                    }
+                    f = ac.nodes[f].fail;
+                };
+                ac.nodes[v].fail = fail;
+                let inherited = ac.nodes[fail].outputs.clone();
+                ac.nodes[v].outputs.extend(inherited);
+                queue.push(v);
+            }
+        }
+        ac
+    }
+
+    fn insert(&mut self, pattern: &[u8], index: usize, reverse: bool) {
+        if pattern.is_empty() {
+            return;
+        }
+        let mut u = 0;
+        for &a in pattern {
+            u = match self.nodes[u].child(a) {
+                Some(v) => v,
+                None => {
+                    let v = self.nodes.len();
+                    self.nodes.push(Node::default());
+                    let children = &mut self.nodes[u].children;
+                    let k = children.partition_point(|&(b, _)| b < a);
+                    children.insert(k, (a, v));
+                    v
+                }
+            };
+        }
+        self.nodes[u].outputs.push(self.patterns.len());
+        self.patterns.push((index, reverse, pattern.len()));
+    }
+
+    fn delta(&self, mut u: usize, a: u8) -> usize {
+        loop {
+            if let Some(v) = self.nodes[u].child(a) {
+                return v;
+            }
+            if u == 0 {
+                return 0;
+            }
+            u = self.nodes[u].fail;
+        }
+    }
+
+    /// Find all matches of the patterns in a given text. Matches are returned as iterator,
+    /// ordered by end position, and by decreasing length for equal end positions.
+    pub fn find_all<C, T>(&self, text: T) -> Matches<'_, C, T::IntoIter>
+    where
+        C: Borrow<u8>,
+        T: IntoIterator<Item = C>,
+    {
+        Matches {
+            ac: self,
+            state: 0,
+            pending: (0, 0),
+            text: text.into_iter().enumerate(),
+        }
+    }
+}
+
+/// Iterator over matches.
+pub struct Matches<'a, C, T>
+where
+    C: Borrow<u8>,
+    T: Iterator<Item = C>,
+{
+    ac: &'a AhoCorasick,
+    state: usize,
+    /// End position and index into the outputs of the current state still to be reported.
+    pending: (usize, usize),
+    text: Enumerate<T>,
+}
+
+impl<'a, C, T> Iterator for Matches<'a, C, T>
+where
+    C: Borrow<u8>,
+    T: Iterator<Item = C>,
+{
+    type Item = Match;
+
+    fn next(&mut self) -> Option<Match> {
+        loop {
+            let (end, k) = self.pending;
+            if let Some(&p) = self.ac.nodes[self.state].outputs.get(k) {
+                self.pending.1 += 1;
+                let (pattern, reverse, len) = self.ac.patterns[p];
+                return Some(Match {
+                    pattern,
+                    start: end - len,
+                    end,
+                    reverse,
+                });
+            }
+            let (i, c) = self.text.next()?;
+            self.state = self.ac.delta(self.state, *c.borrow());
+            self.pending = (i + 1, 0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn naive(patterns: &[&[u8]], text: &[u8]) -> Vec<(usize, usize)> {
+        let mut hits = Vec::new();
+        for (i, pattern) in patterns.iter().enumerate() {
+            for start in 0..(text.len() + 1).saturating_sub(pattern.len()) {
+                if !pattern.is_empty() && text[start..].starts_with(pattern) {
+                    hits.push((i, start));
+                }
+            }
+        }
+        hits.sort_unstable();
+        hits
+    }
+
+    #[test]
+    fn test_against_naive() {
+        let patterns: [&[u8]; 6] = [b"he", b"she", b"his", b"hers", b"", b"s"];
+        let text = b"ushershishehis";
+        let mut hits: Vec<_> = AhoCorasick::new(patterns)
+            .find_all(text)
+            .map(|m| (m.pattern, m.start))
+            .collect();
+        hits.sort_unstable();
+        assert_eq!(hits, naive(&patterns, text));
+    }
+
+    #[test]
+    fn test_nested_and_repeated() {
+        let patterns: [&[u8]; 3] = [b"AAA", b"AA", b"A"];
+        let text = b"CAAAAC";
+        let ac = AhoCorasick::new(patterns);
+        let hits: Vec<_> = ac
+            .find_all(text.iter())
+            .map(|m| (m.pattern, m.start))
+            .collect();
+        assert_eq!(
+            hits,
+            [
+                (2, 1),
+                (1, 1),
+                (2, 2),
+                (0, 1),
+                (1, 2),
+                (2, 3),
+                (0, 2),
+                (1, 3),
+                (2, 4)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_revcomp() {
+        // GAATTC is palindromic and only reported once
+        let ac = AhoCorasick::with_revcomp([&b"GAATTC"[..], b"ACG"]);
+        let hits: Vec<_> = ac.find_all(b"GAATTCGT").collect();
+        assert_eq!(
+            hits,
+            [
+                Match {
+                    pattern: 0,
+                    start: 0,
+                    end: 6,
+                    reverse: false
+                },
+                Match {
+                    pattern: 1,
+                    start: 5,
+                    end: 8,
+                    reverse: true
+                }
+            ]
+        );
+    }
+}
@@ -0,0 +1,315 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Matching of patterns with bounded gaps, e.g. promoters consisting of two boxes separated
+//! by a spacer of variable length (`TTGACA N{15,19} TATAAT`).
+//!
+//! A pattern consists of IUPAC nucleotide codes, each optionally followed by a number of
+//! repetitions `{n}` or a range of repetitions `{n,m}`. Whitespace is ignored.
+//! The pattern is compiled into a bit-parallel nondeterministic automaton, in which the
+//! repetitions beyond the minimal number are optional positions that can be skipped by
+//! epsilon transitions (Navarro and Raffinot, Fast and simple character classes and bounded
+//! gaps pattern matching, with applications to protein searching, Journal of Computational
+//! Biology, 2003). Like `ShiftAnd`, it scans the text forward, updating the state with a
+//! constant number of bit operations per symbol.
+//! Patterns may expand to at most 64 positions (counting each repetition up to its maximum).
+//! Complexity: O(n) with text length n.
+//!
+//! As matches are of variable length, they are reported by their end positions (the
+//! position of their last symbol).
+//!
+//! # Example
+//!
+//! ```
+//! use bio::pattern_matching::gapped::Gapped;
+//!
+//! // sigma-70 promoter: -35 box, spacer, -10 box
+//! let promoter = Gapped::new("TTGACA N{15,19} TATAAT").unwrap();
+//! assert_eq!((promoter.min_len(), promoter.max_len()), (27, 31));
+//! let text = b"GGTTGACAATGCATGCATGCATGCATATAATCC";
+//! let ends: Vec<usize> = promoter.find_all_end(text).collect();
+//! assert_eq!(ends, [30]);
+//!
+//! // degenerate boxes and a fixed spacer
+//! let motif = Gapped::new("GCCNNNWGG{2}").unwrap();
+//! assert_eq!(motif.find_all_end(b"AGCCACGTGGGA").collect::<Vec<_>>(), [10]);
+//! ```
+
+use std::borrow::Borrow;
+use std::iter::Enumerate;
+
+use thiserror::Error;
+
+use crate::alphabets::iupac;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum Error {
+    #[error("invalid IUPAC nucleotide code '{}' at position {pos}", *.code as char)]
+    InvalidCode { code: u8, pos: usize },
+
+    #[error("invalid repetition at position {pos}")]
+    InvalidRepetition { pos: usize },
+
+    #[error("pattern expands to {len} positions, but at most 64 are supported")]
+    PatternTooLong { len: usize },
+
+    #[error("pattern does not contain any mandatory position")]
+    EmptyPattern,
+}
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// A pattern element: an IUPAC code with a range of repetitions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Element {
+    code: u8,
+    min: usize,
+    max: usize,
+}
+
+fn parse(pattern: &str) -> Result<Vec<Element>> {
+    let pattern = pattern.as_bytes();
+    let mut elements = Vec::new();
+    let mut pos = 0;
+    while pos < pattern.len() {
+        let code = pattern[pos];
+        if code.is_ascii_whitespace() {
+            pos += 1;
+            continue;
+        }
+        if iupac::bits(code).is_none() {
+            return Err(Error::InvalidCode { code, pos });
+        }
+        pos += 1;
+        while pos < pattern.len() && pattern[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+
+        let (min, max) = if pattern.get(pos) == Some(&b'{') {
+            let len = pattern[pos..]
+                .iter()
+                .position(|&a| a == b'}')
+                .ok_or(Error::InvalidRepetition { pos })?;
+            let inner = std::str::from_utf8(&pattern[pos + 1..pos + len])
+                .map_err(|_| Error::InvalidRepetition { pos })?;
+            let number = |n: &str| {
+                n.trim()
+                    .parse::<usize>()
+                    .map_err(|_| Error::InvalidRepetition { pos })
+            };
+            let (min, max) = match inner.find(',') {
+                Some(k) => (number(&inner[..k])?, number(&inner[k + 1..])?),
+                None => (number(inner)?, number(inner)?),
+            };
+            if min > max {
+                return Err(Error::InvalidRepetition { pos });
+            }
+            pos += len + 1;
+            (min, max)
+        } else {
+            (1, 1)
+        };
+        elements.push(Element { code, min, max });
+    }
+    Ok(elements)
+}
+
+/// Bit-parallel matcher for patterns with bounded gaps.
+#[derive(Debug, Clone)]
+pub struct Gapped {
+    masks: [u64; 256],
+    /// Optional positions.
+    optional: u64,
+    /// Positions preceding a run of optional positions.
+    before_optional: u64,
+    /// Last positions of runs of optional positions.
+    last_optional: u64,
+    accept: u64,
+    min_len: usize,
+    max_len: usize,
+}
+
+impl Gapped {
+    /// Compile the given pattern. Codes match all (upper- or lowercase) codes of the text that
+    /// denote a subset of their nucleotides, e.g. `R` matches `A`, `G` and `R`.
+    pub fn new(pattern: &str) -> Result<Self> {
+        let elements = parse(pattern)?;
+        let min_len = elements.iter().map(|e| e.min).sum();
+        let max_len = elements.iter().map(|e| e.max).sum();
+
+        // expand into positions, each with a code and whether it is optional
+        let mut positions: Vec<(u8, bool)> = elements
+            .iter()
+            .flat_map(|e| (0..e.max).map(move |k| (e.code, k >= e.min)))
+            .collect();
+        // leading optional positions do not affect where matches end
+        let leading = positions.iter().take_while(|&&(_, opt)| opt).count();
+        positions.drain(..leading);
+        if positions.is_empty() {
+            return Err(Error::EmptyPattern);
+        }
+        if positions.len() > 64 {
+            return Err(Error::PatternTooLong {
+                len: positions.len(),
+            });
+        }
+
+        let mut gapped = Gapped {
+            masks: [0; 256],
+            optional: 0,
+            before_optional: 0,
+            last_optional: 0,
+            accept: 1 << (positions.len() - 1),
+            min_len,
+            max_len,
+        };
+        for (k, &(code, opt)) in positions.iter().enumerate() {
+            for a in 0..=255u8 {
+                if iupac::contains(code, a) {
+                    gapped.masks[a as usize] |= 1 << k;
+                }
+            }
+            if opt {
+                gapped.optional |= 1 << k;
+                if !positions[k - 1].1 {
+                    gapped.before_optional |= 1 << (k - 1);
+                }
+                if !matches!(positions.get(k + 1), Some(&(_, true))) {
+                    gapped.last_optional |= 1 << k;
+                }
+            }
+        }
+        Ok(gapped)
+    }
+
+    /// Minimal length of a match.
+    pub fn min_len(&self) -> usize {
+        self.min_len
+    }
+
+    /// Maximal length of a match.
+    pub fn max_len(&self) -> usize {
+        self.max_len
+    }
+
+    /// Activate all optional positions that follow an active position within their run.
+    fn epsilon_closure(&self, state: u64) -> u64 {
+        let state_f = state | self.last_optional;
+        state | (self.optional & (!state_f.wrapping_sub(self.before_optional) ^ state_f))
+    }
+
+    /// Find all matches of the pattern in the given text. Matches are returned as an iterator
+    /// over end positions (i.e. the positions of their last symbols).
+    pub fn find_all_end<C, T>(&self, text: T) -> Matches<'_, C, T::IntoIter>
+    where
+        C: Borrow<u8>,
+        T: IntoIterator<Item = C>,
+    {
+        Matches {
+            gapped: self,
+            active: 0,
+            text: text.into_iter().enumerate(),
+        }
+    }
+}
+
+/// Iterator over end positions of matches.
+pub struct Matches<'a, C, T>
+where
+    C: Borrow<u8>,
+    T: Iterator<Item = C>,
+{
+    gapped: &'a Gapped,
+    active: u64,
+    text: Enumerate<T>,
+}
+
+impl<'a, C, T> Iterator for Matches<'a, C, T>
+where
+    C: Borrow<u8>,
+    T: Iterator<Item = C>,
+{
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        for (i, c) in self.text.by_ref() {
+            let active = ((self.active << 1) | 1) & self.gapped.masks[*c.borrow() as usize];
+            self.active = self.gapped.epsilon_closure(active);
+            if self.active & self.gapped.accept > 0 {
+                return Some(i);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Naive matching by expanding the repetitions into all fixed-length patterns.
+    fn naive(pattern: &str, text: &[u8]) -> Vec<usize> {
+        let mut patterns: Vec<Vec<u8>> = vec![Vec::new()];
+        for e in parse(pattern).unwrap() {
+            patterns = patterns
+                .iter()
+                .flat_map(|p| {
+                    (e.min..=e.max).map(move |n| {
+                        let mut p = p.clone();
+                        p.resize(p.len() + n, e.code);
+                        p
+                    })
+                })
+                .collect();
+        }
+        (0..text.len())
+            .filter(|&end| {
+                patterns.iter().any(|p| {
+                    p.len() <= end + 1
+                        && p.iter()
+                            .zip(&text[end + 1 - p.len()..=end])
+                            .all(|(&code, &a)| iupac::contains(code, a))
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_against_naive() {
+        let text = b"ACGTTACGATTTACGAACGTAACCGTACGTTTTTACGGA";
+        for &pattern in &[
+            "ACG",
+            "AC N{0,3} G",
+            "A N{1,4} C{0,2} G",
+            "T{1,3} A W{0,2} S{0,1} G",
+            "N{0,2} CG T{0,4}",
+            "A{2}C",
+        ] {
+            let gapped = Gapped::new(pattern).unwrap();
+            let ends: Vec<usize> = gapped.find_all_end(text).collect();
+            assert_eq!(ends, naive(pattern, text), "pattern {}", pattern);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            Gapped::new("AC J").err(),
+            Some(Error::InvalidCode { code: b'J', pos: 3 })
+        );
+        assert_eq!(
+            Gapped::new("AN{3,1}").err(),
+            Some(Error::InvalidRepetition { pos: 2 })
+        );
+        assert_eq!(
+            Gapped::new("AN{3").err(),
+            Some(Error::InvalidRepetition { pos: 2 })
+        );
+        assert_eq!(Gapped::new("N{0,5}").err(), Some(Error::EmptyPattern));
+        assert_eq!(
+            Gapped::new("A N{60,70}").err(),
+            Some(Error::PatternTooLong { len: 71 })
+        );
+    }
+}
@@ -14,14 +14,27 @@
 //! * KMP algorithm: the classical ancestor.
 //! * Ukkonens algorithm: approximate pattern matching with dynamic programming.
 //! * Myers algorithm: linear-time approximate pattern matching with edit distance for small patterns
+//! * Aho-Corasick algorithm: simultaneous search for a set of patterns.
+//! * Z-algorithm: linear-time exact matching, plus Z-array and prefix function utilities.
+//! * PROSITE patterns: protein motifs with residue classes and variable-length spacers.
+//! * Position weight matrices: scanning for motifs with p-value based score thresholds.
+//! * Gapped patterns: bit-parallel matching of patterns with bounded spacers.
+//!
+//! All matchers can be run over chunked streams (e.g. large FASTA files) with the `stream` module.
 //!
 //! Another fast pattern matching algorithm is available in the twoway crate: https://crates.io/crates/twoway
 
+pub mod aho_corasick;
 pub mod bndm;
 pub mod bom;
+pub mod gapped;
 pub mod horspool;
 pub mod kmp;
+pub mod motif;
 pub mod myers;
+pub mod prosite;
 pub mod pssm;
 pub mod shift_and;
+pub mod stream;
 pub mod ukkonen;
+pub mod z;
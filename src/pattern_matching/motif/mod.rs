@@ -0,0 +1,557 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Position weight matrices (PWMs) for scanning sequences for motifs such as transcription
+//! factor binding sites.
+//!
+//! A [`Pwm`](struct.Pwm.html) holds the probability of each symbol at each position of a
+//! motif. It is built from aligned sites or from counts, with a pseudocount added to each
+//! count to avoid zero probabilities. Relative to the background frequencies of the symbols,
+//! it yields a [`Pssm`](struct.Pssm.html) (position-specific scoring matrix) of log-odds
+//! scores in bits, which is used to score all windows of a sequence, for DNA optionally on
+//! both strands.
+//!
+//! Score thresholds can be chosen by p-value: the distribution of window scores under the
+//! background model is computed exactly by dynamic programming over the scores rounded to a
+//! given granularity (Touzet and Varré, Efficient and accurate P-value computation for
+//! Position Weight Matrices, Algorithms for Molecular Biology, 2007).
+//! Complexity: O(m * s * r / g) for a motif of length m over s symbols, with a range r of
+//! scores per position and granularity g.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::pattern_matching::motif::{Pwm, DNA};
+//!
+//! let sites = [b"TTGACA", b"TTGACT", b"TTTACA", b"TTGATA"];
+//! let pwm = Pwm::from_sites(DNA, &sites, 0.25).unwrap();
+//! let pssm = pwm.log_odds(None).unwrap();
+//!
+//! // choose the score threshold for a p-value of 0.001 per window
+//! let threshold = pssm.distribution(0.01).threshold(1e-3);
+//! let hits: Vec<(usize, bool)> = pssm
+//!     .find_all_both_strands(b"GCTTGACAGGCCTGTCAAGC", threshold)
+//!     .unwrap()
+//!     .iter()
+//!     .map(|hit| (hit.pos, hit.reverse))
+//!     .collect();
+//! assert_eq!(hits, [(2, false), (12, true)]);
+//! ```
+
+use thiserror::Error;
+
+use crate::alphabets::dna;
+
+/// Symbols of DNA motifs, in the column order used by most motif databases.
+pub const DNA: &[u8] = b"ACGT";
+/// Symbols of protein motifs, in the column order used by most motif databases.
+pub const PROTEIN: &[u8] = b"ACDEFGHIKLMNPQRSTVWY";
+
+#[derive(Error, Debug, PartialEq)]
+pub enum Error {
+    #[error("motif cannot be created from zero positions or sites")]
+    EmptyMotif,
+    #[error("expected {expected} values or symbols, found {found}")]
+    InconsistentLen { expected: usize, found: usize },
+    #[error("symbol '{}' is not part of the motif alphabet", char::from(*symbol))]
+    InvalidSymbol { symbol: u8 },
+    #[error("symbol '{}' occurs twice in the motif alphabet", char::from(*symbol))]
+    DuplicateSymbol { symbol: u8 },
+    #[error("background frequencies must be positive and given for each symbol")]
+    InvalidBackground,
+    #[error("the reverse complement is only defined for DNA motifs")]
+    NotComplementable,
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The symbols of a motif together with a case-insensitive lookup of their columns.
+#[derive(Debug, Clone)]
+struct Alphabet {
+    symbols: Vec<u8>,
+    columns: [Option<usize>; 256],
+}
+
+impl Alphabet {
+    fn new(symbols: &[u8]) -> Result<Self> {
+        let mut columns = [None; 256];
+        for (k, &a) in symbols.iter().enumerate() {
+            for &b in &[a.to_ascii_uppercase(), a.to_ascii_lowercase()] {
+                if columns[b as usize].replace(k).is_some() {
+                    return Err(Error::DuplicateSymbol { symbol: a });
+                }
+                if a.to_ascii_uppercase() == a.to_ascii_lowercase() {
+                    break;
+                }
+            }
+        }
+        Ok(Alphabet {
+            symbols: symbols.to_vec(),
+            columns,
+        })
+    }
+
+    fn column(&self, a: u8) -> Option<usize> {
+        self.columns[a as usize]
+    }
+
+    /// Normalized background frequencies, uniform if none are given.
+    fn background(&self, background: Option<&[f64]>) -> Result<Vec<f64>> {
+        let n = self.symbols.len();
+        match background {
+            None => Ok(vec![1.0 / n as f64; n]),
+            Some(background) => {
+                if background.len() != n || background.iter().any(|&q| q.is_nan() || q <= 0.0) {
+                    return Err(Error::InvalidBackground);
+                }
+                let total: f64 = background.iter().sum();
+                Ok(background.iter().map(|q| q / total).collect())
+            }
+        }
+    }
+}
+
+/// A position weight matrix, i.e. the probabilities of the symbols at each position of a
+/// motif.
+#[derive(Debug, Clone)]
+pub struct Pwm {
+    alphabet: Alphabet,
+    probs: Vec<Vec<f64>>,
+}
+
+impl Pwm {
+    /// Create a PWM from counts (or frequencies) of the given symbols at each position of a
+    /// motif. The pseudocount is added to each count before normalizing. Positions without
+    /// any counts get uniform probabilities.
+    pub fn from_counts(symbols: &[u8], counts: &[Vec<f64>], pseudocount: f64) -> Result<Self> {
+        let alphabet = Alphabet::new(symbols)?;
+        if counts.is_empty() {
+            return Err(Error::EmptyMotif);
+        }
+        let n = symbols.len();
+        let mut probs = Vec::with_capacity(counts.len());
+        for row in counts {
+            if row.len() != n {
+                return Err(Error::InconsistentLen {
+                    expected: n,
+                    found: row.len(),
+                });
+            }
+            let total: f64 = row.iter().map(|c| c + pseudocount).sum();
+            probs.push(if total > 0.0 {
+                row.iter().map(|c| (c + pseudocount) / total).collect()
+            } else {
+                vec![1.0 / n as f64; n]
+            });
+        }
+        Ok(Pwm { alphabet, probs })
+    }
+
+    /// Create a PWM from aligned sites of equal length, consisting of the given symbols
+    /// (case-insensitive). The pseudocount is added to each count before normalizing.
+    pub fn from_sites<S, I>(symbols: &[u8], sites: I, pseudocount: f64) -> Result<Self>
+    where
+        S: AsRef<[u8]>,
+        I: IntoIterator<Item = S>,
+    {
+        let alphabet = Alphabet::new(symbols)?;
+        let mut counts: Vec<Vec<f64>> = Vec::new();
+        for (i, site) in sites.into_iter().enumerate() {
+            let site = site.as_ref();
+            if i == 0 {
+                counts = vec![vec![0.0; symbols.len()]; site.len()];
+            } else if site.len() != counts.len() {
+                return Err(Error::InconsistentLen {
+                    expected: counts.len(),
+                    found: site.len(),
+                });
+            }
+            for (row, &a) in counts.iter_mut().zip(site) {
+                let k = alphabet
+                    .column(a)
+                    .ok_or(Error::InvalidSymbol { symbol: a })?;
+                row[k] += 1.0;
+            }
+        }
+        Pwm::from_counts(symbols, &counts, pseudocount)
+    }
+
+    /// Length of the motif.
+    pub fn len(&self) -> usize {
+        self.probs.len()
+    }
+
+    /// Whether the motif is empty (never true for constructed PWMs).
+    pub fn is_empty(&self) -> bool {
+        self.probs.is_empty()
+    }
+
+    /// Symbols of the motif, in column order.
+    pub fn symbols(&self) -> &[u8] {
+        &self.alphabet.symbols
+    }
+
+    /// Probabilities of the symbols (columns) at each position (rows).
+    pub fn probabilities(&self) -> &[Vec<f64>] {
+        &self.probs
+    }
+
+    /// Information content in bits of each position, i.e. the relative entropy of its
+    /// probabilities to the background frequencies (uniform if none are given).
+    pub fn information_content(&self, background: Option<&[f64]>) -> Result<Vec<f64>> {
+        let background = self.alphabet.background(background)?;
+        Ok(self
+            .probs
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .zip(&background)
+                    .filter(|&(&p, _)| p > 0.0)
+                    .map(|(&p, &q)| p * (p / q).log2())
+                    .sum()
+            })
+            .collect())
+    }
+
+    /// Log-odds scoring matrix (in bits) of the motif against the background frequencies
+    /// (uniform if none are given).
+    pub fn log_odds(&self, background: Option<&[f64]>) -> Result<Pssm> {
+        let background = self.alphabet.background(background)?;
+        let scores = self
+            .probs
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .zip(&background)
+                    .map(|(&p, &q)| (p / q).log2())
+                    .collect()
+            })
+            .collect();
+        Ok(Pssm {
+            alphabet: self.alphabet.clone(),
+            scores,
+            background,
+        })
+    }
+}
+
+/// A hit of a motif.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hit {
+    /// Start position of the window on the forward strand.
+    pub pos: usize,
+    pub score: f64,
+    /// Whether the reverse complement of the motif matched.
+    pub reverse: bool,
+}
+
+/// A position-specific scoring matrix of log-odds scores.
+#[derive(Debug, Clone)]
+pub struct Pssm {
+    alphabet: Alphabet,
+    scores: Vec<Vec<f64>>,
+    background: Vec<f64>,
+}
+
+impl Pssm {
+    /// Length of the motif.
+    pub fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    /// Whether the motif is empty (never true for constructed PSSMs).
+    pub fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+
+    /// Scores of the symbols (columns) at each position (rows).
+    pub fn scores(&self) -> &[Vec<f64>] {
+        &self.scores
+    }
+
+    /// Minimal score of a window.
+    pub fn min_score(&self) -> f64 {
+        self.scores
+            .iter()
+            .map(|row| row.iter().cloned().fold(f64::INFINITY, f64::min))
+            .sum()
+    }
+
+    /// Maximal score of a window.
+    pub fn max_score(&self) -> f64 {
+        self.scores
+            .iter()
+            .map(|row| row.iter().cloned().fold(f64::NEG_INFINITY, f64::max))
+            .sum()
+    }
+
+    /// Score of a window of the length of the motif, or `None` if the window has a different
+    /// length or contains symbols outside of the motif alphabet.
+    pub fn score(&self, window: &[u8]) -> Option<f64> {
+        if window.len() != self.len() {
+            return None;
+        }
+        self.scores
+            .iter()
+            .zip(window)
+            .map(|(row, &a)| self.alphabet.column(a).map(|k| row[k]))
+            .sum()
+    }
+
+    /// Find all windows of the text scoring at least the given threshold. Windows containing
+    /// symbols outside of the motif alphabet (e.g. `N`) are skipped.
+    pub fn find_all(&self, text: &[u8], threshold: f64) -> Vec<Hit> {
+        self.hits(text, threshold, false).collect()
+    }
+
+    /// Find all windows of the text scoring at least the given threshold on either strand,
+    /// ordered by position. Only defined for DNA motifs.
+    pub fn find_all_both_strands(&self, text: &[u8], threshold: f64) -> Result<Vec<Hit>> {
+        let revcomp = self.revcomp()?;
+        let mut hits: Vec<Hit> = self
+            .hits(text, threshold, false)
+            .chain(revcomp.hits(text, threshold, true))
+            .collect();
+        hits.sort_by_key(|hit| (hit.pos, hit.reverse));
+        Ok(hits)
+    }
+
+    fn hits<'a>(
+        &'a self,
+        text: &'a [u8],
+        threshold: f64,
+        reverse: bool,
+    ) -> impl Iterator<Item = Hit> + 'a {
+        text.windows(self.len())
+            .enumerate()
+            .filter_map(move |(pos, window)| {
+                self.score(window)
+                    .filter(|&score| score >= threshold)
+                    .map(|score| Hit {
+                        pos,
+                        score,
+                        reverse,
+                    })
+            })
+    }
+
+    /// The scoring matrix of the reverse complement of a DNA motif.
+    pub fn revcomp(&self) -> Result<Pssm> {
+        if !dna::iupac_alphabet().is_word(&self.alphabet.symbols) {
+            return Err(Error::NotComplementable);
+        }
+        let complement = self
+            .alphabet
+            .symbols
+            .iter()
+            .map(|&a| {
+                self.alphabet
+                    .column(dna::complement(a))
+                    .ok_or(Error::NotComplementable)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Pssm {
+            alphabet: self.alphabet.clone(),
+            scores: self
+                .scores
+                .iter()
+                .rev()
+                .map(|row| complement.iter().map(|&k| row[k]).collect())
+                .collect(),
+            background: complement.iter().map(|&k| self.background[k]).collect(),
+        })
+    }
+
+    /// Distribution of the scores of windows drawn from the background model, with scores
+    /// rounded to multiples of the given granularity.
+    pub fn distribution(&self, granularity: f64) -> ScoreDistribution {
+        assert!(granularity > 0.0, "granularity must be positive");
+        // rounded scores, with impossible symbols (score -inf) left out
+        let rows: Vec<Vec<(i64, f64)>> = self
+            .scores
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .zip(&self.background)
+                    .filter(|&(s, _)| s.is_finite())
+                    .map(|(s, &q)| ((s / granularity).round() as i64, q))
+                    .collect()
+            })
+            .collect();
+
+        // probs[k] is the probability of a prefix of the window scoring min + k
+        let mut min = 0;
+        let mut probs = vec![1.0];
+        for row in &rows {
+            let row_min = row.iter().map(|&(s, _)| s).min().unwrap_or(0);
+            let row_max = row.iter().map(|&(s, _)| s).max().unwrap_or(0);
+            let mut next = vec![0.0; probs.len() + (row_max - row_min) as usize];
+            for (k, &p) in probs.iter().enumerate() {
+                if p > 0.0 {
+                    for &(s, q) in row {
+                        next[k + (s - row_min) as usize] += p * q;
+                    }
+                }
+            }
+            min += row_min;
+            probs = next;
+        }
+
+        let mut tail = probs;
+        for k in (0..tail.len() - 1).rev() {
+            tail[k] += tail[k + 1];
+        }
+        ScoreDistribution {
+            granularity,
+            min,
+            tail,
+        }
+    }
+}
+
+/// Distribution of window scores under the background model, for converting between scores
+/// and p-values.
+#[derive(Debug, Clone)]
+pub struct ScoreDistribution {
+    granularity: f64,
+    /// Minimal rounded score, in multiples of the granularity.
+    min: i64,
+    /// Probability of a rounded score of at least `min + k`.
+    tail: Vec<f64>,
+}
+
+impl ScoreDistribution {
+    /// Probability of a window scoring at least the given score.
+    pub fn pvalue(&self, score: f64) -> f64 {
+        // tolerate floating point errors for scores on the grid
+        let k = (score / self.granularity - 1e-6).ceil() as i64 - self.min;
+        if k <= 0 {
+            self.tail[0]
+        } else {
+            self.tail.get(k as usize).cloned().unwrap_or(0.0)
+        }
+    }
+
+    /// Smallest score such that a window scores at least this with probability at most the
+    /// given p-value.
+    pub fn threshold(&self, pvalue: f64) -> f64 {
+        let k = self.tail.partition_point(|&p| p > pvalue);
+        (self.min + k as i64) as f64 * self.granularity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pwm() -> Pwm {
+        Pwm::from_sites(DNA, [b"AC", b"AG", b"at"], 0.0).unwrap()
+    }
+
+    #[test]
+    fn test_from_sites() {
+        let pwm = Pwm::from_sites(DNA, [b"AC", b"AG", b"AT", b"AA"], 1.0).unwrap();
+        assert_eq!(pwm.len(), 2);
+        assert_eq!(
+            pwm.probabilities()[0],
+            [5.0 / 8.0, 1.0 / 8.0, 1.0 / 8.0, 1.0 / 8.0]
+        );
+        assert_eq!(pwm.probabilities()[1], [0.25; 4]);
+
+        assert_eq!(
+            Pwm::from_sites(DNA, [&b"AC"[..], b"A"], 1.0).err(),
+            Some(Error::InconsistentLen {
+                expected: 2,
+                found: 1
+            })
+        );
+        assert_eq!(
+            Pwm::from_sites(DNA, [b"AN"], 1.0).err(),
+            Some(Error::InvalidSymbol { symbol: b'N' })
+        );
+        assert_eq!(
+            Pwm::from_sites(DNA, Vec::<&[u8]>::new(), 1.0).err(),
+            Some(Error::EmptyMotif)
+        );
+        assert_eq!(
+            Pwm::from_sites(b"ACa", [b"A"], 1.0).err(),
+            Some(Error::DuplicateSymbol { symbol: b'a' })
+        );
+    }
+
+    #[test]
+    fn test_information_content() {
+        let ic = pwm().information_content(None).unwrap();
+        assert!((ic[0] - 2.0).abs() < 1e-12);
+        assert!((ic[1] - (4.0f64 / 3.0).log2()).abs() < 1e-12);
+        assert_eq!(
+            pwm().information_content(Some(&[0.5, 0.5])).err(),
+            Some(Error::InvalidBackground)
+        );
+    }
+
+    #[test]
+    fn test_scores() {
+        let pssm = pwm().log_odds(None).unwrap();
+        assert_eq!(pssm.score(b"AC"), Some(2.0 + (4.0f64 / 3.0).log2()));
+        assert_eq!(pssm.score(b"CC"), Some(f64::NEG_INFINITY));
+        assert_eq!(pssm.score(b"AN"), None);
+        assert_eq!(pssm.score(b"A"), None);
+        assert_eq!(pssm.max_score(), pssm.score(b"AG").unwrap());
+
+        let hits = pssm.find_all(b"ACANAGT", 0.0);
+        let hits: Vec<usize> = hits.iter().map(|hit| hit.pos).collect();
+        assert_eq!(hits, [0, 4]);
+
+        let revcomp = pssm.revcomp().unwrap();
+        assert_eq!(revcomp.score(b"GT"), pssm.score(b"AC"));
+        let hits = pssm.find_all_both_strands(b"CTGT", 0.0).unwrap();
+        let hits: Vec<_> = hits.iter().map(|hit| (hit.pos, hit.reverse)).collect();
+        assert_eq!(hits, [(0, true), (2, true)]);
+
+        let protein = Pwm::from_sites(PROTEIN, [b"MEK"], 1.0).unwrap();
+        assert_eq!(
+            protein.log_odds(None).unwrap().revcomp().err(),
+            Some(Error::NotComplementable)
+        );
+    }
+
+    #[test]
+    fn test_distribution() {
+        let pwm = Pwm::from_sites(DNA, [b"ACG", b"ACT", b"TCG"], 0.5).unwrap();
+        let pssm = pwm.log_odds(Some(&[0.3, 0.2, 0.2, 0.3])).unwrap();
+        let distribution = pssm.distribution(0.001);
+
+        // compare with enumerating all windows, with scores rounded as in the distribution
+        let mut windows = Vec::new();
+        for &a in DNA {
+            for &b in DNA {
+                for &c in DNA {
+                    let (mut score, mut q) = (0, 1.0);
+                    for (row, &x) in pssm.scores().iter().zip(&[a, b, c]) {
+                        let k = pssm.alphabet.column(x).unwrap();
+                        score += (row[k] / 0.001).round() as i64;
+                        q *= pssm.background[k];
+                    }
+                    windows.push((score, q));
+                }
+            }
+        }
+        for &(score, _) in &windows {
+            let expected: f64 = windows
+                .iter()
+                .filter(|&&(s, _)| s >= score)
+                .map(|&(_, q)| q)
+                .sum();
+            assert!((distribution.pvalue(score as f64 * 0.001) - expected).abs() < 1e-9);
+        }
+        assert!((distribution.pvalue(pssm.min_score() - 1.0) - 1.0).abs() < 1e-12);
+        assert_eq!(distribution.pvalue(pssm.max_score() + 1.0), 0.0);
+
+        let threshold = distribution.threshold(0.05);
+        assert!(distribution.pvalue(threshold) <= 0.05);
+        assert!(distribution.pvalue(threshold - 0.001) > 0.05);
+    }
+}
@@ -0,0 +1,334 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Matching of protein motifs given in PROSITE pattern syntax, e.g. `[ILV]-x(2,3)-{P}-G`.
+//!
+//! A pattern consists of elements separated by `-`, each being one of
+//!
+//! * an amino acid (e.g. `G`),
+//! * `x` for any amino acid,
+//! * a class of allowed amino acids in square brackets (e.g. `[ILV]`), which may contain `>`
+//!   to also match the C-terminal end of the sequence,
+//! * a class of forbidden amino acids in curly braces (e.g. `{P}`),
+//!
+//! optionally followed by a number of repetitions `(n)` or a range of repetitions `(n,m)`.
+//! A leading `<` anchors the pattern at the N-terminus, a trailing `>` at the C-terminus, and
+//! a trailing `.` is ignored.
+//!
+//! Matches are searched by backtracking over the repetition ranges, preferring more
+//! repetitions. For each start position, the first match found this way is reported, together
+//! with the range of the sequence covered by each element, e.g. the extent of variable-length
+//! spacers. Failing combinations of element and text position are remembered across start
+//! positions, such that the search takes O(n * m * r) for a text of length n, a pattern with m
+//! elements and at most r repetitions per element.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::pattern_matching::prosite::Pattern;
+//!
+//! // N-glycosylation site (PS00001)
+//! let pattern: Pattern = "N-{P}-[ST]-{P}".parse().unwrap();
+//! let text = b"MKNVSAGLLNPTQNGTW";
+//! let starts: Vec<usize> = pattern.find_all(text).iter().map(|m| m.start).collect();
+//! assert_eq!(starts, [2, 13]);
+//!
+//! let pattern: Pattern = "C-x(2,4)-C-x(3)-[LIVMFYWC]".parse().unwrap();
+//! let hits = pattern.find_all(b"AACAACGGHCLLL");
+//! assert_eq!(hits.len(), 1);
+//! // the spacer x(2,4) covers two residues
+//! assert_eq!(hits[0].elements[1], 3..5);
+//! ```
+
+use std::ops::Range;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum Error {
+    #[error("invalid PROSITE pattern element '{element}'")]
+    InvalidElement { element: String },
+
+    #[error("invalid repetition '{repetition}' in PROSITE pattern")]
+    InvalidRepetition { repetition: String },
+
+    #[error("empty PROSITE pattern")]
+    EmptyPattern,
+}
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// An element of a pattern, i.e. a class of residues with a range of repetitions.
+#[derive(Debug, Clone)]
+struct Element {
+    allowed: [bool; 256],
+    /// Whether the element also matches the C-terminal end.
+    c_term: bool,
+    min: usize,
+    max: usize,
+}
+
+/// A compiled PROSITE pattern.
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    elements: Vec<Element>,
+    n_term: bool,
+    c_term: bool,
+}
+
+/// A match of a pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    pub start: usize,
+    pub end: usize,
+    /// The range covered by each element of the pattern.
+    pub elements: Vec<Range<usize>>,
+}
+
+fn parse_element(element: &str) -> Result<Element> {
+    let invalid = || Error::InvalidElement {
+        element: element.to_owned(),
+    };
+    let (class, repetition) = match element.find('(') {
+        Some(k) => (&element[..k], Some(&element[k..])),
+        None => (element, None),
+    };
+
+    let mut allowed = [false; 256];
+    let mut c_term = false;
+    let mut set = |residues: &str, value: bool| -> Result<()> {
+        for a in residues.bytes() {
+            match a {
+                b'>' => c_term = true,
+                a if a.is_ascii_alphabetic() => {
+                    allowed[a.to_ascii_uppercase() as usize] = value;
+                    allowed[a.to_ascii_lowercase() as usize] = value;
+                }
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(())
+    };
+    if class == "x" || class == "X" {
+        set("ABCDEFGHIJKLMNOPQRSTUVWXYZ", true)?;
+    } else if class.len() > 2 && class.starts_with('[') && class.ends_with(']') {
+        set(&class[1..class.len() - 1], true)?;
+    } else if class.len() > 2 && class.starts_with('{') && class.ends_with('}') {
+        set("ABCDEFGHIJKLMNOPQRSTUVWXYZ", true)?;
+        set(&class[1..class.len() - 1], false)?;
+        if c_term {
+            return Err(invalid());
+        }
+    } else if class.len() == 1 && class.as_bytes()[0].is_ascii_uppercase() {
+        set(class, true)?;
+    } else {
+        return Err(invalid());
+    }
+
+    let (min, max) = match repetition {
+        None => (1, 1),
+        Some(repetition) => {
+            let invalid = || Error::InvalidRepetition {
+                repetition: repetition.to_owned(),
+            };
+            let inner = repetition
+                .strip_prefix('(')
+                .and_then(|r| r.strip_suffix(')'))
+                .ok_or_else(invalid)?;
+            let number = |n: &str| n.trim().parse::<usize>().map_err(|_| invalid());
+            let (min, max) = match inner.find(',') {
+                Some(k) => (number(&inner[..k])?, number(&inner[k + 1..])?),
+                None => (number(inner)?, number(inner)?),
+            };
+            if min > max || max == 0 {
+                return Err(invalid());
+            }
+            (min, max)
+        }
+    };
+
+    Ok(Element {
+        allowed,
+        c_term,
+        min,
+        max,
+    })
+}
+
+impl FromStr for Pattern {
+    type Err = Error;
+
+    fn from_str(pattern: &str) -> Result<Self> {
+        let mut pattern: String = pattern.chars().filter(|c| !c.is_whitespace()).collect();
+        if pattern.ends_with('.') {
+            pattern.pop();
+        }
+        let n_term = pattern.starts_with('<');
+        if n_term {
+            pattern.remove(0);
+        }
+        // a trailing '>' outside of brackets anchors at the C-terminus
+        let c_term = pattern.ends_with('>');
+        if c_term {
+            pattern.pop();
+        }
+        if pattern.is_empty() {
+            return Err(Error::EmptyPattern);
+        }
+        let elements = pattern
+            .split('-')
+            .map(parse_element)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Pattern {
+            elements,
+            n_term,
+            c_term,
+        })
+    }
+}
+
+impl Pattern {
+    /// Find all matches of the pattern in the given protein sequence (upper- or lowercase),
+    /// at most one per start position, ordered by start position.
+    pub fn find_all(&self, text: &[u8]) -> Vec<Match> {
+        let starts = if self.n_term { 0..1 } else { 0..text.len() };
+        let mut matches = Vec::new();
+        let mut ranges = Vec::with_capacity(self.elements.len());
+        let mut failed = vec![false; (self.elements.len() + 1) * (text.len() + 1)];
+        for start in starts {
+            ranges.clear();
+            if let Some(end) = self.match_at(text, 0, start, &mut ranges, &mut failed) {
+                matches.push(Match {
+                    start,
+                    end,
+                    elements: ranges.clone(),
+                });
+            }
+        }
+        matches
+    }
+
+    /// Whether the pattern occurs in the given protein sequence.
+    pub fn is_match(&self, text: &[u8]) -> bool {
+        !self.find_all(text).is_empty()
+    }
+
+    /// Match the elements from index `k` on at position `pos` of the text, returning the end
+    /// of the match and storing the ranges of the elements.
+    fn match_at(
+        &self,
+        text: &[u8],
+        k: usize,
+        pos: usize,
+        ranges: &mut Vec<Range<usize>>,
+        failed: &mut [bool],
+    ) -> Option<usize> {
+        let state = k * (text.len() + 1) + pos;
+        if failed[state] {
+            return None;
+        }
+        let end = self.match_element(text, k, pos, ranges, failed);
+        if end.is_none() {
+            failed[state] = true;
+        }
+        end
+    }
+
+    fn match_element(
+        &self,
+        text: &[u8],
+        k: usize,
+        pos: usize,
+        ranges: &mut Vec<Range<usize>>,
+        failed: &mut [bool],
+    ) -> Option<usize> {
+        let element = match self.elements.get(k) {
+            Some(element) => element,
+            None if self.c_term && pos != text.len() => return None,
+            None => return Some(pos),
+        };
+        if element.c_term && pos == text.len() {
+            ranges.push(pos..pos);
+            match self.match_at(text, k + 1, pos, ranges, failed) {
+                Some(end) => return Some(end),
+                None => {
+                    ranges.pop();
+                }
+            }
+        }
+        let available = text[pos..]
+            .iter()
+            .take(element.max)
+            .take_while(|&&a| element.allowed[a as usize])
+            .count();
+        if available < element.min {
+            return None;
+        }
+        for count in (element.min..=available).rev() {
+            ranges.push(pos..pos + count);
+            if let Some(end) = self.match_at(text, k + 1, pos + count, ranges, failed) {
+                return Some(end);
+            }
+            ranges.pop();
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn starts(pattern: &str, text: &[u8]) -> Vec<usize> {
+        let pattern: Pattern = pattern.parse().unwrap();
+        pattern.find_all(text).iter().map(|m| m.start).collect()
+    }
+
+    #[test]
+    fn test_elements() {
+        assert_eq!(starts("[ILV]-x(2,3)-{P}-G", b"AIAAAGVAAAPG"), [1]);
+        assert_eq!(starts("A-x-A", b"AAAaCa"), [0, 1, 3]);
+        assert_eq!(starts("G(2)", b"GGGAGG"), [0, 1, 4]);
+    }
+
+    #[test]
+    fn test_anchors() {
+        assert_eq!(starts("<M-x-K", b"MAKMAK"), [0]);
+        assert_eq!(starts("M-x-K>", b"MAKMAK"), [3]);
+        assert_eq!(starts("K-[G>]", b"KAKGAK"), [2, 5]);
+        let pattern: Pattern = "K-[G>]".parse().unwrap();
+        assert_eq!(pattern.find_all(b"AK")[0].elements, [1..2, 2..2]);
+    }
+
+    #[test]
+    fn test_backtracking() {
+        // the spacer must leave a residue for the last element
+        let pattern: Pattern = "C-x(1,5)-H.".parse().unwrap();
+        let hits = pattern.find_all(b"CAHAHAA");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].elements, [0..1, 1..4, 4..5]);
+
+        // many nested spacers without a match
+        let pattern: Pattern = "A-x(0,20)-x(0,20)-x(0,20)-x(0,20)-C".parse().unwrap();
+        assert!(!pattern.is_match(&[b'A'; 200]));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            "A-[IL".parse::<Pattern>().err(),
+            Some(Error::InvalidElement {
+                element: "[IL".to_owned()
+            })
+        );
+        assert_eq!(
+            "A-x(3,2)".parse::<Pattern>().err(),
+            Some(Error::InvalidRepetition {
+                repetition: "(3,2)".to_owned()
+            })
+        );
+        assert_eq!("<".parse::<Pattern>().err(), Some(Error::EmptyPattern));
+    }
+}
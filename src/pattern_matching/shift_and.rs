@@ -7,6 +7,11 @@
 //! Patterns may contain at most 64 symbols.
 //! Complexity: O(n) with text length n.
 //!
+//! Instead of a fixed symbol, each pattern position may allow a class of symbols, which
+//! e.g. allows to search degenerate primers or motifs given as IUPAC codes without expanding
+//! them. `MultiShiftAnd` searches several such patterns with a total length of at most 64
+//! simultaneously, by packing them into a single bit vector.
+//!
 //! # Example
 //!
 //! ```rust
@@ -16,11 +21,24 @@
 //! let shiftand = shift_and::ShiftAnd::new(pattern);
 //! let occ = shiftand.find_all(text).next().unwrap();
 //! assert_eq!(occ, 8);
+//!
+//! // IUPAC codes
+//! let shiftand = shift_and::ShiftAnd::iupac(b"ANNGTRAC").unwrap();
+//! let text = b"TTACTGTGACCAGGGTAACT";
+//! let occ: Vec<usize> = shiftand.find_all(text).collect();
+//! assert_eq!(occ, [2, 11]);
+//!
+//! // several patterns at once, reported as pairs of pattern index and start position
+//! let shiftand = shift_and::MultiShiftAnd::iupac(&[&b"GGNA"[..], b"TRAC"]).unwrap();
+//! let occ: Vec<(usize, usize)> = shiftand.find_all(text).collect();
+//! assert_eq!(occ, [(1, 6), (0, 13), (1, 15)]);
 //! ```
 
 use std::borrow::Borrow;
 use std::iter::Enumerate;
 
+use crate::alphabets::iupac;
+
 /// `ShiftAnd` algorithm.
 pub struct ShiftAnd {
     m: usize,
@@ -44,6 +62,34 @@ impl ShiftAnd {
         ShiftAnd { m, masks, accept }
     }
 
+    /// Create new ShiftAnd instance from a pattern given as a class of allowed symbols for
+    /// each position.
+    pub fn from_classes<I, S>(classes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<[u8]>,
+    {
+        let mut masks = [0; 256];
+        let mut m = 0;
+        for class in classes {
+            assert!(m < 64, "Expecting a pattern of at most 64 symbols.");
+            for &a in class.as_ref() {
+                masks[a as usize] |= 1 << m;
+            }
+            m += 1;
+        }
+        let accept = if m == 0 { 0 } else { 1 << (m - 1) };
+
+        ShiftAnd { m, masks, accept }
+    }
+
+    /// Create new ShiftAnd instance from a pattern of IUPAC nucleotide codes. A pattern
+    /// position matches all (upper- or lowercase) codes of the text that denote a subset of
+    /// its nucleotides, e.g. `R` matches `A`, `G` and `R`.
+    pub fn iupac(pattern: &[u8]) -> iupac::Result<Self> {
+        Ok(ShiftAnd::from_classes(iupac_classes(pattern)?))
+    }
+
     /// Find all matches of pattern in the given text. Matches are returned as an iterator
     /// over start positions.
     pub fn find_all<C, T>(&self, text: T) -> Matches<'_, C, T::IntoIter>
@@ -77,6 +123,18 @@ where
     (masks, bit / 2)
 }
 
+/// Symbols of the text matched by each IUPAC code of the pattern.
+fn iupac_classes(pattern: &[u8]) -> iupac::Result<Vec<Vec<u8>>> {
+    pattern
+        .iter()
+        .enumerate()
+        .map(|(pos, &code)| {
+            iupac::bits(code).ok_or(iupac::Error::InvalidCode { code, pos })?;
+            Ok((0..=255u8).filter(|&a| iupac::contains(code, a)).collect())
+        })
+        .collect()
+}
+
 /// Iterator over start positions of matches.
 pub struct Matches<'a, C, T>
 where
@@ -107,6 +165,137 @@ where
     }
 }
 
+/// `ShiftAnd` algorithm for a set of patterns with a total length of at most 64 symbols.
+pub struct MultiShiftAnd {
+    masks: [u64; 256],
+    /// First position of each pattern.
+    starts: u64,
+    /// Last position of each pattern.
+    accept: u64,
+    /// Index and length of the pattern ending at each position.
+    patterns: Vec<(usize, usize)>,
+}
+
+impl MultiShiftAnd {
+    /// Create new instance for the given patterns. Empty patterns are ignored.
+    pub fn new<I, P>(patterns: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<[u8]>,
+    {
+        MultiShiftAnd::from_classes(patterns.into_iter().map(|pattern| {
+            pattern
+                .as_ref()
+                .iter()
+                .map(|&a| vec![a])
+                .collect::<Vec<_>>()
+        }))
+    }
+
+    /// Create new instance for the given patterns, each given as a class of allowed symbols
+    /// for each position.
+    pub fn from_classes<I, P, S>(patterns: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: IntoIterator<Item = S>,
+        S: AsRef<[u8]>,
+    {
+        let mut shiftand = MultiShiftAnd {
+            masks: [0; 256],
+            starts: 0,
+            accept: 0,
+            patterns: vec![(0, 0); 64],
+        };
+        let mut offset = 0;
+        for (i, classes) in patterns.into_iter().enumerate() {
+            let start = offset;
+            for class in classes {
+                assert!(
+                    offset < 64,
+                    "Expecting patterns of at most 64 symbols in total."
+                );
+                for &a in class.as_ref() {
+                    shiftand.masks[a as usize] |= 1 << offset;
+                }
+                offset += 1;
+            }
+            if offset > start {
+                shiftand.starts |= 1 << start;
+                shiftand.accept |= 1 << (offset - 1);
+                shiftand.patterns[offset - 1] = (i, offset - start);
+            }
+        }
+
+        shiftand
+    }
+
+    /// Create new instance for the given patterns of IUPAC nucleotide codes (see
+    /// `ShiftAnd::iupac`).
+    pub fn iupac<I, P>(patterns: I) -> iupac::Result<Self>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<[u8]>,
+    {
+        let classes = patterns
+            .into_iter()
+            .map(|pattern| iupac_classes(pattern.as_ref()))
+            .collect::<iupac::Result<Vec<_>>>()?;
+        Ok(MultiShiftAnd::from_classes(classes))
+    }
+
+    /// Find all matches of the patterns in the given text. Matches are returned as an
+    /// iterator over pairs of pattern index and start position, ordered by end position.
+    pub fn find_all<C, T>(&self, text: T) -> MultiMatches<'_, C, T::IntoIter>
+    where
+        C: Borrow<u8>,
+        T: IntoIterator<Item = C>,
+    {
+        MultiMatches {
+            shiftand: self,
+            active: 0,
+            hits: 0,
+            end: 0,
+            text: text.into_iter().enumerate(),
+        }
+    }
+}
+
+/// Iterator over pairs of pattern index and start position of matches.
+pub struct MultiMatches<'a, C, T>
+where
+    C: Borrow<u8>,
+    T: Iterator<Item = C>,
+{
+    shiftand: &'a MultiShiftAnd,
+    active: u64,
+    /// Accepting positions at the current end position that have not been reported yet.
+    hits: u64,
+    end: usize,
+    text: Enumerate<T>,
+}
+
+impl<'a, C, T> Iterator for MultiMatches<'a, C, T>
+where
+    C: Borrow<u8>,
+    T: Iterator<Item = C>,
+{
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<(usize, usize)> {
+        while self.hits == 0 {
+            let (i, c) = self.text.next()?;
+            self.active = ((self.active << 1) | self.shiftand.starts)
+                & self.shiftand.masks[*c.borrow() as usize];
+            self.hits = self.active & self.shiftand.accept;
+            self.end = i + 1;
+        }
+        let bit = self.hits.trailing_zeros() as usize;
+        self.hits &= self.hits - 1;
+        let (pattern, len) = self.shiftand.patterns[bit];
+        Some((pattern, self.end - len))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -135,4 +324,39 @@ mod tests {
         let shiftand = ShiftAnd::new(pattern);
         assert_eq!(shiftand.find_all(text).collect_vec(), [0, 3, 6]);
     }
+
+    #[test]
+    fn test_classes() {
+        let shiftand = ShiftAnd::from_classes(&[&b"Aa"[..], b"C", b"GT"]);
+        assert_eq!(shiftand.find_all(b"ACGaCTACA").collect_vec(), [0, 3]);
+        let shiftand = ShiftAnd::iupac(b"ryN").unwrap();
+        assert_eq!(shiftand.find_all(b"ACGGTnAUR").collect_vec(), [0, 3, 6]);
+        assert_eq!(
+            ShiftAnd::iupac(b"ACXT").err(),
+            Some(iupac::Error::InvalidCode { code: b'X', pos: 2 })
+        );
+    }
+
+    #[test]
+    fn test_multi() {
+        let patterns: [&[u8]; 4] = [b"CC", b"", b"TCC", b"C"];
+        let shiftand = MultiShiftAnd::new(&patterns);
+        let text = b"CCTCCTCC";
+        assert_eq!(
+            shiftand.find_all(text).collect_vec(),
+            [
+                (3, 0),
+                (0, 0),
+                (3, 1),
+                (3, 3),
+                (0, 3),
+                (2, 2),
+                (3, 4),
+                (3, 6),
+                (0, 6),
+                (2, 5),
+                (3, 7)
+            ]
+        );
+    }
 }
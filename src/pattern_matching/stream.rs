@@ -0,0 +1,363 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Pattern matching over streams, e.g. for scanning multi-gigabyte FASTA files without
+//! loading whole sequences into memory.
+//!
+//! The text is read in chunks. Each chunk is prepended with the last `max_len - 1` symbols of
+//! the previous one, such that every match of at most `max_len` symbols is completely
+//! contained in some window. Any of the matchers of this module can then be run on the
+//! windows. A match is reported only by the window in which it ends in the newly read part,
+//! such that matches within the overlap are not reported twice.
+//! For exact matchers, `max_len` is the length of the pattern, for approximate matchers with
+//! up to k edits it is the length of the pattern plus k.
+//!
+//! [`Windows`](struct.Windows.html) reads plain text, while
+//! [`FastaWindows`](struct.FastaWindows.html) reads the sequences of a FASTA file, skipping
+//! headers and line breaks, with windows never spanning two records.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::pattern_matching::horspool::Horspool;
+//! use bio::pattern_matching::myers::Myers;
+//! use bio::pattern_matching::stream::{FastaWindows, Windows};
+//!
+//! let text = b"ACGGCTAGAAAAGGCTAGAGCTAG";
+//! let pattern = b"GCTAG";
+//! let horspool = Horspool::new(pattern);
+//! // a tiny chunk size, to demonstrate the overlap
+//! let windows = Windows::new(&text[..], pattern.len()).chunk_size(4);
+//! let ends = windows
+//!     .find_all(|window| {
+//!         horspool
+//!             .find_all(window)
+//!             .map(|start| (start + pattern.len(), ()))
+//!             .collect::<Vec<_>>()
+//!     })
+//!     .unwrap();
+//! let starts: Vec<usize> = ends.iter().map(|&(end, _)| end - pattern.len()).collect();
+//! assert_eq!(starts, [3, 13, 19]);
+//!
+//! // approximate matching of the sequences of a FASTA file
+//! let fasta = b">chr1 first\nACGTTAGCT\nAGCATT\n>chr2\nTTTTT\n";
+//! let myers = Myers::<u64>::new(b"AGCTAG");
+//! let windows = FastaWindows::new(&fasta[..], 6 + 1).chunk_size(3);
+//! let hits = windows
+//!     .find_all(|window| {
+//!         myers
+//!             .find_all_end(window, 1)
+//!             .map(|(end, dist)| (end + 1, dist))
+//!             .collect::<Vec<_>>()
+//!     })
+//!     .unwrap();
+//! let hits: Vec<(&str, usize, u8)> = hits
+//!     .iter()
+//!     .map(|(id, end, dist)| (&id[..], *end, *dist))
+//!     .collect();
+//! assert_eq!(hits, [("chr1", 10, 1), ("chr1", 11, 0), ("chr1", 12, 1)]);
+//! ```
+
+use std::io;
+use std::io::prelude::*;
+
+/// Default number of symbols read per window.
+pub const DEFAULT_CHUNK_SIZE: usize = 1 << 20;
+
+/// A window of a stream, consisting of the last symbols of the previous window followed by
+/// newly read symbols.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Window<'a> {
+    /// The symbols of the window.
+    pub text: &'a [u8],
+    /// Position of the first symbol of the window in the stream (or in the FASTA record).
+    pub offset: usize,
+    /// Position of the first newly read symbol in the window.
+    pub new: usize,
+}
+
+impl<'a> Window<'a> {
+    /// Whether a match ending at the given position (exclusive) of the window ends in the
+    /// newly read part, i.e. has not been visible in the previous window.
+    pub fn is_new(&self, end: usize) -> bool {
+        end > self.new
+    }
+}
+
+/// Windows over the symbols of a stream.
+#[derive(Debug)]
+pub struct Windows<R: io::Read> {
+    reader: R,
+    overlap: usize,
+    chunk_size: usize,
+    buffer: Vec<u8>,
+    offset: usize,
+}
+
+impl<R: io::Read> Windows<R> {
+    /// Read windows from the given reader, such that each match of at most `max_len` symbols
+    /// is contained in a window.
+    pub fn new(reader: R, max_len: usize) -> Self {
+        Windows {
+            reader,
+            overlap: max_len.saturating_sub(1),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            buffer: Vec::new(),
+            offset: 0,
+        }
+    }
+
+    /// Number of new symbols read per window (at least 1, default: 1 MiB).
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Read the next window. Returns `Ok(None)` once the stream is exhausted.
+    pub fn next_window(&mut self) -> io::Result<Option<Window<'_>>> {
+        let drop = self.buffer.len().saturating_sub(self.overlap);
+        self.buffer.drain(..drop);
+        self.offset += drop;
+        let new = self.buffer.len();
+        (&mut self.reader)
+            .take(self.chunk_size as u64)
+            .read_to_end(&mut self.buffer)?;
+        if self.buffer.len() == new {
+            return Ok(None);
+        }
+        Ok(Some(Window {
+            text: &self.buffer,
+            offset: self.offset,
+            new,
+        }))
+    }
+
+    /// Run a matcher on all windows. The matcher returns the end positions (exclusive) of
+    /// matches within a window (e.g. collected into a vector), together with arbitrary data,
+    /// e.g. the edit distance. Returns all matches with end positions relative to the stream.
+    pub fn find_all<F, I, T>(mut self, mut find: F) -> io::Result<Vec<(usize, T)>>
+    where
+        F: FnMut(&[u8]) -> I,
+        I: IntoIterator<Item = (usize, T)>,
+    {
+        let mut matches = Vec::new();
+        while let Some(window) = self.next_window()? {
+            for (end, data) in find(window.text) {
+                if window.is_new(end) {
+                    matches.push((window.offset + end, data));
+                }
+            }
+        }
+        Ok(matches)
+    }
+}
+
+/// Windows over the sequences of a FASTA file. Windows do not span records, and positions
+/// are relative to the start of the respective record.
+#[derive(Debug)]
+pub struct FastaWindows<R: io::Read> {
+    reader: io::BufReader<R>,
+    overlap: usize,
+    chunk_size: usize,
+    buffer: Vec<u8>,
+    offset: usize,
+    id: String,
+    in_record: bool,
+    at_line_start: bool,
+}
+
+impl<R: io::Read> FastaWindows<R> {
+    /// Read windows from the given FASTA reader, such that each match of at most `max_len`
+    /// symbols is contained in a window.
+    pub fn new(reader: R, max_len: usize) -> Self {
+        FastaWindows {
+            reader: io::BufReader::new(reader),
+            overlap: max_len.saturating_sub(1),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            buffer: Vec::new(),
+            offset: 0,
+            id: String::new(),
+            in_record: false,
+            at_line_start: true,
+        }
+    }
+
+    /// Number of new symbols read per window (at least 1, default: 1 MiB).
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Read the next window, together with the ID of the record it belongs to. Returns
+    /// `Ok(None)` once the stream is exhausted.
+    pub fn next_window(&mut self) -> io::Result<Option<(&str, Window<'_>)>> {
+        let drop = self.buffer.len().saturating_sub(self.overlap);
+        self.buffer.drain(..drop);
+        self.offset += drop;
+        let mut new = self.buffer.len();
+
+        while self.buffer.len() - new < self.chunk_size {
+            let available = self.reader.fill_buf()?;
+            if available.is_empty() {
+                break;
+            }
+            if self.at_line_start && available[0] == b'>' {
+                if self.buffer.len() > new {
+                    // report the end of the current record first
+                    break;
+                }
+                let mut header = String::new();
+                self.reader.read_line(&mut header)?;
+                self.id = header[1..]
+                    .split_whitespace()
+                    .next()
+                    .unwrap_or("")
+                    .to_owned();
+                self.in_record = true;
+                self.buffer.clear();
+                self.offset = 0;
+                new = 0;
+                continue;
+            }
+
+            let mut consumed = 0;
+            for &a in available {
+                if self.buffer.len() - new == self.chunk_size || (self.at_line_start && a == b'>') {
+                    break;
+                }
+                consumed += 1;
+                match a {
+                    b'\n' => self.at_line_start = true,
+                    b'\r' => (),
+                    _ => {
+                        if !self.in_record && !a.is_ascii_whitespace() {
+                            return Err(io::Error::new(
+                                io::ErrorKind::InvalidData,
+                                "Expected > at file start.",
+                            ));
+                        }
+                        if !a.is_ascii_whitespace() {
+                            self.buffer.push(a);
+                        }
+                        self.at_line_start = false;
+                    }
+                }
+            }
+            self.reader.consume(consumed);
+        }
+
+        if self.buffer.len() == new {
+            return Ok(None);
+        }
+        Ok(Some((
+            &self.id,
+            Window {
+                text: &self.buffer,
+                offset: self.offset,
+                new,
+            },
+        )))
+    }
+
+    /// Run a matcher on all windows. The matcher returns the end positions (exclusive) of
+    /// matches within a window (e.g. collected into a vector), together with arbitrary data,
+    /// e.g. the edit distance. Returns all matches as record ID, end position relative to the
+    /// record, and data.
+    pub fn find_all<F, I, T>(mut self, mut find: F) -> io::Result<Vec<(String, usize, T)>>
+    where
+        F: FnMut(&[u8]) -> I,
+        I: IntoIterator<Item = (usize, T)>,
+    {
+        let mut matches = Vec::new();
+        while let Some((id, window)) = self.next_window()? {
+            for (end, data) in find(window.text) {
+                if window.is_new(end) {
+                    matches.push((id.to_owned(), window.offset + end, data));
+                }
+            }
+        }
+        Ok(matches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern_matching::shift_and::ShiftAnd;
+
+    fn naive(pattern: &[u8], text: &[u8]) -> Vec<usize> {
+        (0..(text.len() + 1).saturating_sub(pattern.len()))
+            .filter(|&i| text[i..].starts_with(pattern))
+            .collect()
+    }
+
+    #[test]
+    fn test_windows() {
+        let text = b"AAACAAAACAACAAAAAAC";
+        for &pattern in &[&b"AAC"[..], b"A", b"CAAAAAAC"] {
+            let shift_and = ShiftAnd::new(pattern);
+            for chunk_size in 1..8 {
+                let windows = Windows::new(&text[..], pattern.len()).chunk_size(chunk_size);
+                let starts: Vec<usize> = windows
+                    .find_all(|window| {
+                        shift_and
+                            .find_all(window)
+                            .map(|start| (start + pattern.len(), ()))
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap()
+                    .iter()
+                    .map(|&(end, _)| end - pattern.len())
+                    .collect();
+                assert_eq!(starts, naive(pattern, text));
+            }
+        }
+    }
+
+    #[test]
+    fn test_next_window() {
+        let mut windows = Windows::new(&b"ABCDEFG"[..], 3).chunk_size(3);
+        let window = windows.next_window().unwrap().unwrap();
+        assert_eq!(
+            (window.text, window.offset, window.new),
+            (&b"ABC"[..], 0, 0)
+        );
+        let window = windows.next_window().unwrap().unwrap();
+        assert_eq!(
+            (window.text, window.offset, window.new),
+            (&b"BCDEF"[..], 1, 2)
+        );
+        let window = windows.next_window().unwrap().unwrap();
+        assert_eq!(
+            (window.text, window.offset, window.new),
+            (&b"EFG"[..], 4, 2)
+        );
+        assert_eq!(windows.next_window().unwrap(), None);
+    }
+
+    #[test]
+    fn test_fasta_windows() {
+        let fasta = b">r1 desc\nAAC\r\nAA\n\n>r2\nCAAC\nAAC>\n>r3\n";
+        let pattern = b"AAC";
+        let shift_and = ShiftAnd::new(pattern);
+        for chunk_size in 1..6 {
+            let windows = FastaWindows::new(&fasta[..], pattern.len()).chunk_size(chunk_size);
+            let hits = windows
+                .find_all(|window| {
+                    shift_and
+                        .find_all(window)
+                        .map(|start| (start + 3, ()))
+                        .collect::<Vec<_>>()
+                })
+                .unwrap();
+            let hits: Vec<(&str, usize)> =
+                hits.iter().map(|(id, end, _)| (&id[..], *end)).collect();
+            assert_eq!(hits, [("r1", 3), ("r2", 4), ("r2", 7)]);
+        }
+
+        let mut windows = FastaWindows::new(&b"ACGT\n"[..], 3);
+        assert!(windows.next_window().is_err());
+    }
+}
@@ -22,7 +22,14 @@
 //! let pattern = b"TGAGCGA";
 //! let occ: Vec<(usize, usize)> = ukkonen.find_all_end(pattern, text, 1).collect();
 //! assert_eq!(occ, [(13, 1), (14, 1)]);
+//!
+//! // with start positions
+//! let occ: Vec<(usize, usize, usize)> = ukkonen.find_all(pattern, text, 1).collect();
+//! assert_eq!(occ, [(8, 13, 1), (8, 14, 1)]);
 //! ```
+//!
+//! Given start and end of a match, its alignment can be obtained with e.g.
+//! [`pairwise::Aligner::global`](../../alignment/pairwise/struct.Aligner.html#method.global).
 
 use std::borrow::Borrow;
 use std::cmp::min;
@@ -43,6 +50,8 @@ where
     F: Fn(u8, u8) -> u32,
 {
     D: [Vec<usize>; 2],
+    /// Start positions in the text of the paths ending in the cells of `D`.
+    S: [Vec<usize>; 2],
     cost: F,
 }
 
@@ -55,6 +64,7 @@ where
         let get_vec = || Vec::with_capacity(m + 1);
         Ukkonen {
             D: [get_vec(), get_vec()],
+            S: [get_vec(), get_vec()],
             cost,
         }
     }
@@ -66,7 +76,7 @@ where
         pattern: TextSlice<'a>,
         text: T,
         k: usize,
-    ) -> Matches<'_, F, C, T::IntoIter>
+    ) -> Matches<'a, F, C, T::IntoIter>
     where
         C: Borrow<u8>,
         T: IntoIterator<Item = C>,
@@ -76,6 +86,10 @@ where
         self.D[0].extend(repeat(k + 1).take(m + 1));
         self.D[1].clear();
         self.D[1].extend(0..=m);
+        for starts in self.S.iter_mut() {
+            starts.clear();
+            starts.resize(m + 1, 0);
+        }
         Matches {
             ukkonen: self,
             pattern,
@@ -85,6 +99,24 @@ where
             k,
         }
     }
+
+    /// Find all matches between pattern and text with up to k errors.
+    /// Matches are returned as an iterator over triples of start position, end position
+    /// (inclusive, as for `find_all_end`) and distance.
+    pub fn find_all<'a, C, T>(
+        &'a mut self,
+        pattern: TextSlice<'a>,
+        text: T,
+        k: usize,
+    ) -> FullMatches<'a, F, C, T::IntoIter>
+    where
+        C: Borrow<u8>,
+        T: IntoIterator<Item = C>,
+    {
+        FullMatches {
+            matches: self.find_all_end(pattern, text, k),
+        }
+    }
 }
 
 /// Iterator over pairs of end positions and distance of matches.
@@ -111,31 +143,52 @@ where
     type Item = (usize, usize);
 
     fn next(&mut self) -> Option<(usize, usize)> {
+        self.next_hit().map(|(_, end, dist)| (end, dist))
+    }
+}
+
+impl<'a, F, C, T> Matches<'a, F, C, T>
+where
+    F: 'a + Fn(u8, u8) -> u32,
+    C: Borrow<u8>,
+    T: Iterator<Item = C>,
+{
+    /// Next triple of start, end and distance.
+    #[allow(non_snake_case)]
+    fn next_hit(&mut self) -> Option<(usize, usize, usize)> {
         let cost = &self.ukkonen.cost;
         for (i, c) in &mut self.text {
             let col = i % 2;
             let prev = 1 - col;
+            let (D, S) = (&mut self.ukkonen.D, &mut self.ukkonen.S);
 
             // start with zero edit distance (semi-global alignment)
-            self.ukkonen.D[col][0] = 0;
+            D[col][0] = 0;
+            S[col][0] = i + 1;
             self.lastk = min(self.lastk + 1, self.m);
             // in each column, go at most one cell further than before
             // do not look at cells with too big k
             for j in 1..=self.lastk {
-                self.ukkonen.D[col][j] = min(
-                    min(self.ukkonen.D[prev][j] + 1, self.ukkonen.D[col][j - 1] + 1),
-                    self.ukkonen.D[prev][j - 1] + (cost)(self.pattern[j - 1], *c.borrow()) as usize,
-                );
+                let diag = D[prev][j - 1] + (cost)(self.pattern[j - 1], *c.borrow()) as usize;
+                let (dist, start) = if diag <= D[prev][j] + 1 && diag <= D[col][j - 1] + 1 {
+                    (diag, S[prev][j - 1])
+                } else if D[prev][j] <= D[col][j - 1] {
+                    (D[prev][j] + 1, S[prev][j])
+                } else {
+                    (D[col][j - 1] + 1, S[col][j - 1])
+                };
+                D[col][j] = dist;
+                S[col][j] = start;
             }
 
             // reduce lastk as long as k is exceeded: while lastk can increase by at most 1, it can
             // decrease more in one iteration.
-            while self.ukkonen.D[col][self.lastk] > self.k {
+            while D[col][self.lastk] > self.k {
                 self.lastk -= 1;
             }
 
             if self.lastk == self.m {
-                return Some((i, self.ukkonen.D[col][self.m]));
+                return Some((S[col][self.m], i, D[col][self.m]));
             }
         }
 
@@ -143,6 +196,29 @@ where
     }
 }
 
+/// Iterator over triples of start position, end position and distance of matches.
+pub struct FullMatches<'a, F, C, T>
+where
+    F: Fn(u8, u8) -> u32,
+    C: Borrow<u8>,
+    T: Iterator<Item = C>,
+{
+    matches: Matches<'a, F, C, T>,
+}
+
+impl<'a, F, C, T> Iterator for FullMatches<'a, F, C, T>
+where
+    F: 'a + Fn(u8, u8) -> u32,
+    C: Borrow<u8>,
+    T: Iterator<Item = C>,
+{
+    type Item = (usize, usize, usize);
+
+    fn next(&mut self) -> Option<(usize, usize, usize)> {
+        self.matches.next_hit()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -171,4 +247,19 @@ mod tests {
         let occ: Vec<(usize, usize)> = u.find_all_end(pattern, text2, 1).collect();
         assert_eq!(occ, [(4, 1), (5, 0), (6, 1)]);
     }
+
+    #[test]
+    fn test_find_all() {
+        let mut u = Ukkonen::with_capacity(10, unit_cost);
+        let text = b"TTTTACGGTACGTTTTACCGTA";
+        let pattern = b"ACGGTA";
+        let occ: Vec<(usize, usize, usize)> = u.find_all(pattern, text, 1).collect();
+        assert_eq!(occ, [(4, 8, 1), (4, 9, 0), (4, 10, 1), (16, 21, 1)]);
+        for &(start, end, dist) in &occ {
+            assert_eq!(
+                crate::alignment::distance::levenshtein(pattern, &text[start..=end]) as usize,
+                dist
+            );
+        }
+    }
 }
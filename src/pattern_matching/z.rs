@@ -0,0 +1,195 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Z-algorithm and prefix function.
+//!
+//! The Z-array of a text assigns to each position i the length of the longest common prefix
+//! of the text and its suffix starting at i. The prefix function assigns to each position i
+//! the length of the longest proper prefix of `text[..=i]` that is also a suffix of it (this is
+//! the failure function of the algorithm of Knuth, Morris and Pratt). Both are computed in
+//! O(n) for a text of length n, and are building blocks for many string algorithms, e.g. for
+//! finding periods and tandem structures.
+//!
+//! With the Z-array of a pattern, all occurrences of the pattern in a text can be found in
+//! O(n + m) for text length n and pattern length m.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::pattern_matching::z::{prefix_function, smallest_period, z_array, Z};
+//!
+//! assert_eq!(z_array(b"aabxaab"), [7, 1, 0, 0, 3, 1, 0]);
+//! assert_eq!(prefix_function(b"aabxaab"), [0, 1, 0, 0, 1, 2, 3]);
+//! assert_eq!(smallest_period(b"ACGACGACGA"), 3);
+//!
+//! let z = Z::new(b"ACGA");
+//! let occ: Vec<usize> = z.find_all(b"TACGACGATACGA").collect();
+//! assert_eq!(occ, [1, 4, 9]);
+//! ```
+
+use crate::utils::TextSlice;
+
+/// Compute the Z-array of the given text. By convention, the first entry is the length of
+/// the text.
+pub fn z_array(text: TextSlice<'_>) -> Vec<usize> {
+    let n = text.len();
+    let mut z = vec![0; n];
+    if n == 0 {
+        return z;
+    }
+    z[0] = n;
+    // the rightmost Z-box text[l..r] found so far
+    let (mut l, mut r) = (0, 0);
+    for i in 1..n {
+        let mut k = if i < r { (r - i).min(z[i - l]) } else { 0 };
+        while i + k < n && text[k] == text[i + k] {
+            k += 1;
+        }
+        z[i] = k;
+        if i + k > r {
+            l = i;
+            r = i + k;
+        }
+    }
+    z
+}
+
+/// Compute the prefix function of the given text.
+pub fn prefix_function(text: TextSlice<'_>) -> Vec<usize> {
+    let mut pi = vec![0; text.len()];
+    let mut q = 0;
+    for i in 1..text.len() {
+        while q > 0 && text[q] != text[i] {
+            q = pi[q - 1];
+        }
+        if text[q] == text[i] {
+            q += 1;
+        }
+        pi[i] = q;
+    }
+    pi
+}
+
+/// Smallest period p of the given text, i.e. the smallest p > 0 with `text[i] == text[i + p]`
+/// for all valid i. The text is a tandem repeat of its first p symbols if p divides its length.
+/// The period of the empty text is zero.
+pub fn smallest_period(text: TextSlice<'_>) -> usize {
+    match prefix_function(text).last() {
+        Some(border) => text.len() - border,
+        None => 0,
+    }
+}
+
+/// Exact pattern matching with the Z-array of the pattern.
+pub struct Z<'a> {
+    pattern: TextSlice<'a>,
+    z: Vec<usize>,
+}
+
+impl<'a> Z<'a> {
+    /// Create a new instance for a given pattern.
+    pub fn new(pattern: TextSlice<'a>) -> Self {
+        Z {
+            pattern,
+            z: z_array(pattern),
+        }
+    }
+
+    /// Length of the longest common prefix of the pattern and each suffix of the text.
+    pub fn matching_lengths<'b>(&'b self, text: TextSlice<'b>) -> MatchingLengths<'b> {
+        MatchingLengths {
+            z: self,
+            text,
+            i: 0,
+            l: 0,
+            r: 0,
+        }
+    }
+
+    /// Find all matches of the pattern in a given text. Matches are returned as iterator over
+    /// start positions. The empty pattern matches nowhere.
+    pub fn find_all<'b>(&'b self, text: TextSlice<'b>) -> impl Iterator<Item = usize> + 'b {
+        let m = self.pattern.len();
+        self.matching_lengths(text)
+            .enumerate()
+            .filter(move |&(_, len)| m > 0 && len == m)
+            .map(|(i, _)| i)
+    }
+}
+
+/// Iterator over the lengths of the longest common prefixes of a pattern and the suffixes of
+/// a text.
+pub struct MatchingLengths<'a> {
+    z: &'a Z<'a>,
+    text: TextSlice<'a>,
+    i: usize,
+    /// The rightmost match `text[l..r]` of a pattern prefix found so far.
+    l: usize,
+    r: usize,
+}
+
+impl<'a> Iterator for MatchingLengths<'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let (i, text, pattern) = (self.i, self.text, self.z.pattern);
+        if i >= text.len() {
+            return None;
+        }
+        let mut k = if i < self.r {
+            // text[i..r] equals pattern[i - l..r - l]
+            let z = self.z.z[i - self.l];
+            if z < self.r - i {
+                self.i += 1;
+                return Some(z);
+            }
+            self.r - i
+        } else {
+            0
+        };
+        while k < pattern.len() && i + k < text.len() && pattern[k] == text[i + k] {
+            k += 1;
+        }
+        if i + k > self.r {
+            self.l = i;
+            self.r = i + k;
+        }
+        self.i += 1;
+        Some(k)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_z_array() {
+        assert_eq!(z_array(b""), Vec::<usize>::new());
+        assert_eq!(z_array(b"aaaaa"), [5, 4, 3, 2, 1]);
+        assert_eq!(z_array(b"abacaba"), [7, 0, 1, 0, 3, 0, 1]);
+    }
+
+    #[test]
+    fn test_prefix_function_and_period() {
+        assert_eq!(prefix_function(b"abacaba"), [0, 0, 1, 0, 1, 2, 3]);
+        assert_eq!(smallest_period(b""), 0);
+        assert_eq!(smallest_period(b"ACGT"), 4);
+        assert_eq!(smallest_period(b"CACACA"), 2);
+    }
+
+    #[test]
+    fn test_find_all() {
+        let text = b"AAAAACAAAA";
+        let z = Z::new(b"AAA");
+        assert_eq!(z.find_all(text).collect::<Vec<_>>(), [0, 1, 2, 6, 7]);
+        assert_eq!(
+            z.matching_lengths(text).collect::<Vec<_>>(),
+            [3, 3, 3, 2, 1, 0, 3, 3, 2, 1]
+        );
+        assert_eq!(Z::new(b"").find_all(text).count(), 0);
+        assert_eq!(Z::new(b"CAAAAA").find_all(text).count(), 0);
+    }
+}
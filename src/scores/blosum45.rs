@@ -0,0 +1,117 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+lazy_static! {
+    // taken from https://ftp.ncbi.nih.gov/blast/matrices/BLOSUM45, J, O and U are scored like X
+    static ref MAT: ndarray::Array2<i32> = ndarray::Array::from_shape_vec((27, 27), vec![
+         5, -1, -1, -2, -1, -2,  0, -2, -1,  0, -1, -1, -1, -1,  0, -1, -1, -2,  1,  0,  0,
+         0, -2, -2, -1,  0, -5,
+        -1,  4, -2,  5,  1, -3, -1,  0, -3, -1,  0, -3, -2,  4, -1, -2,  0, -1,  0,  0, -1,
+        -3, -4, -2,  2, -1, -5,
+        -1, -2, 12, -3, -3, -2, -3, -3, -3, -2, -3, -2, -2, -2, -2, -4, -3, -3, -1, -1, -2,
+        -1, -5, -3, -3, -2, -5,
+        -2,  5, -3,  7,  2, -4, -1,  0, -4, -1,  0, -3, -3,  2, -1, -1,  0, -1,  0, -1, -1,
+        -3, -4, -2,  1, -1, -5,
+        -1,  1, -3,  2,  6, -3, -2,  0, -3, -1,  1, -2, -2,  0, -1,  0,  2,  0,  0, -1, -1,
+        -3, -3, -2,  4, -1, -5,
+        -2, -3, -2, -4, -3,  8, -3, -2,  0, -1, -3,  1,  0, -2, -1, -3, -4, -2, -2, -1, -1,
+         0,  1,  3, -3, -1, -5,
+         0, -1, -3, -1, -2, -3,  7, -2, -4, -1, -2, -3, -2,  0, -1, -2, -2, -2,  0, -2, -1,
+        -3, -2, -3, -2, -1, -5,
+        -2,  0, -3,  0,  0, -2, -2, 10, -3, -1, -1, -2,  0,  1, -1, -2,  1,  0, -1, -2, -1,
+        -3, -3,  2,  0, -1, -5,
+        -1, -3, -3, -4, -3,  0, -4, -3,  5, -1, -3,  2,  2, -2, -1, -2, -2, -3, -2, -1, -1,
+         3, -2,  0, -3, -1, -5,
+         0, -1, -2, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1,  0,  0, -1,
+        -1, -2, -1, -1, -1, -5,
+        -1,  0, -3,  0,  1, -3, -2, -1, -3, -1,  5, -3, -1,  0, -1, -1,  1,  3, -1, -1, -1,
+        -2, -2, -1,  1, -1, -5,
+        -1, -3, -2, -3, -2,  1, -3, -2,  2, -1, -3,  5,  2, -3, -1, -3, -2, -2, -3, -1, -1,
+         1, -2,  0, -2, -1, -5,
+        -1, -2, -2, -3, -2,  0, -2,  0,  2, -1, -1,  2,  6, -2, -1, -2,  0, -1, -2, -1, -1,
+         1, -2,  0, -1, -1, -5,
+        -1,  4, -2,  2,  0, -2,  0,  1, -2, -1,  0, -3, -2,  6, -1, -2,  0,  0,  1,  0, -1,
+        -3, -4, -2,  0, -1, -5,
+         0, -1, -2, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1,  0,  0, -1,
+        -1, -2, -1, -1, -1, -5,
+        -1, -2, -4, -1,  0, -3, -2, -2, -2, -1, -1, -3, -2, -2, -1,  9, -1, -2, -1, -1, -1,
+        -3, -3, -3, -1, -1, -5,
+        -1,  0, -3,  0,  2, -4, -2,  1, -2, -1,  1, -2,  0,  0, -1, -1,  6,  1,  0, -1, -1,
+        -3, -2, -1,  4, -1, -5,
+        -2, -1, -3, -1,  0, -2, -2,  0, -3, -1,  3, -2, -1,  0, -1, -2,  1,  7, -1, -1, -1,
+        -2, -2, -1,  0, -1, -5,
+         1,  0, -1,  0,  0, -2,  0, -1, -2,  0, -1, -3, -2,  1,  0, -1,  0, -1,  4,  2,  0,
+        -1, -4, -2,  0,  0, -5,
+         0,  0, -1, -1, -1, -1, -2, -2, -1,  0, -1, -1, -1,  0,  0, -1, -1, -1,  2,  5,  0,
+         0, -3, -1, -1,  0, -5,
+         0, -1, -2, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1,  0,  0, -1,
+        -1, -2, -1, -1, -1, -5,
+         0, -3, -1, -3, -3,  0, -3, -3,  3, -1, -2,  1,  1, -3, -1, -3, -3, -2, -1,  0, -1,
+         5, -3, -1, -3, -1, -5,
+        -2, -4, -5, -4, -3,  1, -2, -3, -2, -2, -2, -2, -2, -4, -2, -3, -2, -2, -4, -3, -2,
+        -3, 15,  3, -2, -2, -5,
+        -2, -2, -3, -2, -2,  3, -3,  2,  0, -1, -1,  0,  0, -2, -1, -3, -1, -1, -2, -1, -1,
+        -1,  3,  8, -2, -1, -5,
+        -1,  2, -3,  1,  4, -3, -2,  0, -3, -1,  1, -2, -1,  0, -1, -1,  4,  0,  0, -1, -1,
+        -3, -2, -2,  4, -1, -5,
+         0, -1, -2, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1,  0,  0, -1,
+        -1, -2, -1, -1, -1, -5,
+        -5, -5, -5, -5, -5, -5, -5, -5, -5, -5, -5, -5, -5, -5, -5, -5, -5, -5, -5, -5, -5,
+        -5, -5, -5, -5, -5,  1
+    ]).unwrap();
+}
+
+#[inline]
+fn lookup(a: u8) -> usize {
+    if a == b'Y' {
+        23
+    } else if a == b'Z' {
+        24
+    } else if a == b'X' {
+        25
+    } else if a == b'*' {
+        26
+    } else {
+        (a - 65) as usize
+    }
+}
+
+/// Return the BLOSUM45 substitution matrix score of [a, b]
+///
+/// # Example
+///
+/// ```
+/// use bio::scores::blosum45;
+/// assert_eq!(blosum45(b'H', b'A'), -2);
+/// ```
+pub fn blosum45(a: u8, b: u8) -> i32 {
+    let a = lookup(a);
+    let b = lookup(b);
+
+    MAT[(a, b)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blosum45() {
+        let score1 = blosum45(b'A', b'A');
+        assert_eq!(score1, 5);
+        let score2 = blosum45(b'W', b'W');
+        assert_eq!(score2, 15);
+        let score3 = blosum45(b'H', b'A');
+        assert_eq!(score3, -2);
+        let score4 = blosum45(b'X', b'X');
+        assert_eq!(score4, -1);
+        let score5 = blosum45(b'A', b'*');
+        assert_eq!(score5, -5);
+        let score6 = blosum45(b'*', b'*');
+        assert_eq!(score6, 1);
+        let score7 = blosum45(b'J', b'I');
+        assert_eq!(score7, -1);
+    }
+}
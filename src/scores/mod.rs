@@ -3,14 +3,22 @@
 // This file may not be copied, modified, or distributed
 // except according to those terms.
 
+pub use self::blosum45::blosum45;
 pub use self::blosum62::blosum62;
+pub use self::blosum80::blosum80;
 pub use self::pam120::pam120;
 pub use self::pam200::pam200;
 pub use self::pam250::pam250;
+pub use self::pam30::pam30;
 pub use self::pam40::pam40;
+pub use self::pam70::pam70;
 
+pub mod blosum45;
 pub mod blosum62;
+pub mod blosum80;
 pub mod pam120;
 pub mod pam200;
 pub mod pam250;
+pub mod pam30;
 pub mod pam40;
+pub mod pam70;
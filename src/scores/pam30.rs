@@ -0,0 +1,117 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+lazy_static! {
+    // taken from https://ftp.ncbi.nih.gov/blast/matrices/PAM30, J, O and U are scored like X
+    static ref MAT: ndarray::Array2<i32> = ndarray::Array::from_shape_vec((27, 27), vec![
+          6,  -3,  -6,  -3,  -2,  -8,  -2,  -7,  -5,  -3,  -7,  -6,  -5,  -4,  -3,  -2,  -4,  -7,   0,  -1,  -3,
+         -2, -13,  -8,  -3,  -3, -17,
+         -3,   6, -12,   6,   1, -10,  -3,  -1,  -6,  -5,  -2,  -9, -10,   6,  -5,  -7,  -3,  -7,  -1,  -3,  -5,
+         -8, -10,  -6,   0,  -5, -17,
+         -6, -12,  10, -14, -14, -13,  -9,  -7,  -6,  -9, -14, -15, -13, -11,  -9,  -8, -14,  -8,  -3,  -8,  -9,
+         -6, -15,  -4, -14,  -9, -17,
+         -3,   6, -14,   8,   2, -15,  -3,  -4,  -7,  -5,  -4, -12, -11,   2,  -5,  -8,  -2, -10,  -4,  -5,  -5,
+         -8, -15, -11,   1,  -5, -17,
+         -2,   1, -14,   2,   8, -14,  -4,  -5,  -5,  -5,  -4,  -9,  -7,  -2,  -5,  -5,   1,  -9,  -4,  -6,  -5,
+         -6, -17,  -8,   6,  -5, -17,
+         -8, -10, -13, -15, -14,   9,  -9,  -6,  -2,  -8, -14,  -3,  -4,  -9,  -8, -10, -13,  -9,  -6,  -9,  -8,
+         -8,  -4,   2, -13,  -8, -17,
+         -2,  -3,  -9,  -3,  -4,  -9,   6,  -9, -11,  -5,  -7, -10,  -8,  -3,  -5,  -6,  -7,  -9,  -2,  -6,  -5,
+         -5, -15, -14,  -5,  -5, -17,
+         -7,  -1,  -7,  -4,  -5,  -6,  -9,   9,  -9,  -5,  -6,  -6, -10,   0,  -5,  -4,   1,  -2,  -6,  -7,  -5,
+         -6,  -7,  -3,  -1,  -5, -17,
+         -5,  -6,  -6,  -7,  -5,  -2, -11,  -9,   8,  -5,  -6,  -1,  -1,  -5,  -5,  -8,  -8,  -5,  -7,  -2,  -5,
+          2, -14,  -6,  -6,  -5, -17,
+         -3,  -5,  -9,  -5,  -5,  -8,  -5,  -5,  -5,  -5,  -5,  -6,  -5,  -3,  -5,  -5,  -5,  -6,  -3,  -4,  -5,
+         -5, -11,  -7,  -5,  -5, -17,
+         -7,  -2, -14,  -4,  -4, -14,  -7,  -6,  -6,  -5,   7,  -8,  -2,  -1,  -5,  -6,  -3,   0,  -4,  -3,  -5,
+         -9, -12,  -9,  -4,  -5, -17,
+         -6,  -9, -15, -12,  -9,  -3, -10,  -6,  -1,  -6,  -8,   7,   1,  -7,  -6,  -7,  -5,  -8,  -8,  -7,  -6,
+         -2,  -6,  -7,  -7,  -6, -17,
+         -5, -10, -13, -11,  -7,  -4,  -8, -10,  -1,  -5,  -2,   1,  11,  -9,  -5,  -8,  -4,  -4,  -5,  -4,  -5,
+         -1, -13, -11,  -5,  -5, -17,
+         -4,   6, -11,   2,  -2,  -9,  -3,   0,  -5,  -3,  -1,  -7,  -9,   8,  -3,  -6,  -3,  -6,   0,  -2,  -3,
+         -8,  -8,  -4,  -3,  -3, -17,
+         -3,  -5,  -9,  -5,  -5,  -8,  -5,  -5,  -5,  -5,  -5,  -6,  -5,  -3,  -5,  -5,  -5,  -6,  -3,  -4,  -5,
+         -5, -11,  -7,  -5,  -5, -17,
+         -2,  -7,  -8,  -8,  -5, -10,  -6,  -4,  -8,  -5,  -6,  -7,  -8,  -6,  -5,   8,  -3,  -4,  -2,  -4,  -5,
+         -6, -14, -13,  -4,  -5, -17,
+         -4,  -3, -14,  -2,   1, -13,  -7,   1,  -8,  -5,  -3,  -5,  -4,  -3,  -5,  -3,   8,  -2,  -5,  -5,  -5,
+         -7, -13, -12,   6,  -5, -17,
+         -7,  -7,  -8, -10,  -9,  -9,  -9,  -2,  -5,  -6,   0,  -8,  -4,  -6,  -6,  -4,  -2,   8,  -3,  -6,  -6,
+         -8,  -2, -10,  -4,  -6, -17,
+          0,  -1,  -3,  -4,  -4,  -6,  -2,  -6,  -7,  -3,  -4,  -8,  -5,   0,  -3,  -2,  -5,  -3,   6,   0,  -3,
+         -6,  -5,  -7,  -5,  -3, -17,
+         -1,  -3,  -8,  -5,  -6,  -9,  -6,  -7,  -2,  -4,  -3,  -7,  -4,  -2,  -4,  -4,  -5,  -6,   0,   7,  -4,
+         -3, -13,  -6,  -6,  -4, -17,
+         -3,  -5,  -9,  -5,  -5,  -8,  -5,  -5,  -5,  -5,  -5,  -6,  -5,  -3,  -5,  -5,  -5,  -6,  -3,  -4,  -5,
+         -5, -11,  -7,  -5,  -5, -17,
+         -2,  -8,  -6,  -8,  -6,  -8,  -5,  -6,   2,  -5,  -9,  -2,  -1,  -8,  -5,  -6,  -7,  -8,  -6,  -3,  -5,
+          7, -15,  -7,  -6,  -5, -17,
+        -13, -10, -15, -15, -17,  -4, -15,  -7, -14, -11, -12,  -6, -13,  -8, -11, -14, -13,  -2,  -5, -13, -11,
+        -15,  13,  -5, -14, -11, -17,
+         -8,  -6,  -4, -11,  -8,   2, -14,  -3,  -6,  -7,  -9,  -7, -11,  -4,  -7, -13, -12, -10,  -7,  -6,  -7,
+         -7,  -5,  10,  -9,  -7, -17,
+         -3,   0, -14,   1,   6, -13,  -5,  -1,  -6,  -5,  -4,  -7,  -5,  -3,  -5,  -4,   6,  -4,  -5,  -6,  -5,
+         -6, -14,  -9,   6,  -5, -17,
+         -3,  -5,  -9,  -5,  -5,  -8,  -5,  -5,  -5,  -5,  -5,  -6,  -5,  -3,  -5,  -5,  -5,  -6,  -3,  -4,  -5,
+         -5, -11,  -7,  -5,  -5, -17,
+        -17, -17, -17, -17, -17, -17, -17, -17, -17, -17, -17, -17, -17, -17, -17, -17, -17, -17, -17, -17, -17,
+        -17, -17, -17, -17, -17,   1
+    ]).unwrap();
+}
+
+#[inline]
+fn lookup(a: u8) -> usize {
+    if a == b'Y' {
+        23
+    } else if a == b'Z' {
+        24
+    } else if a == b'X' {
+        25
+    } else if a == b'*' {
+        26
+    } else {
+        (a - 65) as usize
+    }
+}
+
+/// Return the PAM30 substitution matrix score of [a, b]
+///
+/// # Example
+///
+/// ```
+/// use bio::scores::pam30;
+/// assert_eq!(pam30(b'H', b'A'), -7);
+/// ```
+pub fn pam30(a: u8, b: u8) -> i32 {
+    let a = lookup(a);
+    let b = lookup(b);
+
+    MAT[(a, b)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pam30() {
+        let score1 = pam30(b'A', b'A');
+        assert_eq!(score1, 6);
+        let score2 = pam30(b'W', b'W');
+        assert_eq!(score2, 13);
+        let score3 = pam30(b'H', b'A');
+        assert_eq!(score3, -7);
+        let score4 = pam30(b'X', b'X');
+        assert_eq!(score4, -5);
+        let score5 = pam30(b'A', b'*');
+        assert_eq!(score5, -17);
+        let score6 = pam30(b'*', b'*');
+        assert_eq!(score6, 1);
+        let score7 = pam30(b'J', b'I');
+        assert_eq!(score7, -5);
+    }
+}
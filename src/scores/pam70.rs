@@ -0,0 +1,117 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+lazy_static! {
+    // taken from https://ftp.ncbi.nih.gov/blast/matrices/PAM70, J, O and U are scored like X
+    static ref MAT: ndarray::Array2<i32> = ndarray::Array::from_shape_vec((27, 27), vec![
+          5,  -1,  -4,  -1,  -1,  -6,   0,  -4,  -2,  -2,  -4,  -4,  -3,  -2,  -2,   0,  -2,  -4,   1,   1,  -2,
+         -1,  -9,  -5,  -1,  -2, -11,
+         -1,   5,  -8,   5,   2,  -7,  -1,   0,  -4,  -2,  -1,  -6,  -6,   5,  -2,  -4,  -1,  -4,   0,  -1,  -2,
+         -5,  -7,  -4,   1,  -2, -11,
+         -4,  -8,   9,  -9,  -9,  -8,  -6,  -5,  -4,  -6,  -9, -10,  -9,  -7,  -6,  -5,  -9,  -5,  -1,  -5,  -6,
+         -4, -11,  -2,  -9,  -6, -11,
+         -1,   5,  -9,   6,   3, -10,  -1,  -1,  -5,  -3,  -2,  -8,  -7,   3,  -3,  -4,   0,  -6,  -1,  -2,  -3,
+         -5, -10,  -7,   2,  -3, -11,
+         -1,   2,  -9,   3,   6,  -9,  -2,  -2,  -4,  -3,  -2,  -6,  -4,   0,  -3,  -3,   2,  -5,  -2,  -3,  -3,
+         -4, -11,  -6,   5,  -3, -11,
+         -6,  -7,  -8, -10,  -9,   8,  -7,  -4,   0,  -5,  -9,  -1,  -2,  -6,  -5,  -7,  -9,  -7,  -4,  -6,  -5,
+         -5,  -2,   4,  -9,  -5, -11,
+          0,  -1,  -6,  -1,  -2,  -7,   6,  -6,  -6,  -3,  -5,  -7,  -6,  -1,  -3,  -3,  -4,  -6,   0,  -3,  -3,
+         -3, -10,  -9,  -3,  -3, -11,
+         -4,   0,  -5,  -1,  -2,  -4,  -6,   8,  -6,  -3,  -3,  -4,  -6,   1,  -3,  -2,   2,   0,  -3,  -4,  -3,
+         -4,  -5,  -1,   1,  -3, -11,
+         -2,  -4,  -4,  -5,  -4,   0,  -6,  -6,   7,  -3,  -4,   1,   1,  -3,  -3,  -5,  -5,  -3,  -4,  -1,  -3,
+          3,  -9,  -4,  -4,  -3, -11,
+         -2,  -2,  -6,  -3,  -3,  -5,  -3,  -3,  -3,  -3,  -3,  -4,  -3,  -2,  -3,  -3,  -2,  -3,  -1,  -2,  -3,
+         -2,  -7,  -5,  -3,  -3, -11,
+         -4,  -1,  -9,  -2,  -2,  -9,  -5,  -3,  -4,  -3,   6,  -5,   0,   0,  -3,  -4,  -1,   2,  -2,  -1,  -3,
+         -6,  -7,  -7,  -2,  -3, -11,
+         -4,  -6, -10,  -8,  -6,  -1,  -7,  -4,   1,  -4,  -5,   6,   2,  -5,  -4,  -5,  -3,  -6,  -6,  -4,  -4,
+          0,  -4,  -4,  -4,  -4, -11,
+         -3,  -6,  -9,  -7,  -4,  -2,  -6,  -6,   1,  -3,   0,   2,  10,  -5,  -3,  -5,  -2,  -2,  -3,  -2,  -3,
+          0,  -8,  -7,  -3,  -3, -11,
+         -2,   5,  -7,   3,   0,  -6,  -1,   1,  -3,  -2,   0,  -5,  -5,   6,  -2,  -3,  -1,  -3,   1,   0,  -2,
+         -5,  -6,  -3,  -1,  -2, -11,
+         -2,  -2,  -6,  -3,  -3,  -5,  -3,  -3,  -3,  -3,  -3,  -4,  -3,  -2,  -3,  -3,  -2,  -3,  -1,  -2,  -3,
+         -2,  -7,  -5,  -3,  -3, -11,
+          0,  -4,  -5,  -4,  -3,  -7,  -3,  -2,  -5,  -3,  -4,  -5,  -5,  -3,  -3,   7,  -1,  -2,   0,  -2,  -3,
+         -3,  -9,  -9,  -2,  -3, -11,
+         -2,  -1,  -9,   0,   2,  -9,  -4,   2,  -5,  -2,  -1,  -3,  -2,  -1,  -2,  -1,   7,   0,  -3,  -3,  -2,
+         -4,  -8,  -8,   5,  -2, -11,
+         -4,  -4,  -5,  -6,  -5,  -7,  -6,   0,  -3,  -3,   2,  -6,  -2,  -3,  -3,  -2,   0,   8,  -1,  -4,  -3,
+         -5,   0,  -7,  -2,  -3, -11,
+          1,   0,  -1,  -1,  -2,  -4,   0,  -3,  -4,  -1,  -2,  -6,  -3,   1,  -1,   0,  -3,  -1,   5,   2,  -1,
+         -3,  -3,  -5,  -2,  -1, -11,
+          1,  -1,  -5,  -2,  -3,  -6,  -3,  -4,  -1,  -2,  -1,  -4,  -2,   0,  -2,  -2,  -3,  -4,   2,   6,  -2,
+         -1,  -8,  -4,  -3,  -2, -11,
+         -2,  -2,  -6,  -3,  -3,  -5,  -3,  -3,  -3,  -3,  -3,  -4,  -3,  -2,  -3,  -3,  -2,  -3,  -1,  -2,  -3,
+         -2,  -7,  -5,  -3,  -3, -11,
+         -1,  -5,  -4,  -5,  -4,  -5,  -3,  -4,   3,  -2,  -6,   0,   0,  -5,  -2,  -3,  -4,  -5,  -3,  -1,  -2,
+          6, -10,  -5,  -4,  -2, -11,
+         -9,  -7, -11, -10, -11,  -2, -10,  -5,  -9,  -7,  -7,  -4,  -8,  -6,  -7,  -9,  -8,   0,  -3,  -8,  -7,
+        -10,  13,  -3, -10,  -7, -11,
+         -5,  -4,  -2,  -7,  -6,   4,  -9,  -1,  -4,  -5,  -7,  -4,  -7,  -3,  -5,  -9,  -8,  -7,  -5,  -4,  -5,
+         -5,  -3,   9,  -7,  -5, -11,
+         -1,   1,  -9,   2,   5,  -9,  -3,   1,  -4,  -3,  -2,  -4,  -3,  -1,  -3,  -2,   5,  -2,  -2,  -3,  -3,
+         -4, -10,  -7,   5,  -3, -11,
+         -2,  -2,  -6,  -3,  -3,  -5,  -3,  -3,  -3,  -3,  -3,  -4,  -3,  -2,  -3,  -3,  -2,  -3,  -1,  -2,  -3,
+         -2,  -7,  -5,  -3,  -3, -11,
+        -11, -11, -11, -11, -11, -11, -11, -11, -11, -11, -11, -11, -11, -11, -11, -11, -11, -11, -11, -11, -11,
+        -11, -11, -11, -11, -11,   1
+    ]).unwrap();
+}
+
+#[inline]
+fn lookup(a: u8) -> usize {
+    if a == b'Y' {
+        23
+    } else if a == b'Z' {
+        24
+    } else if a == b'X' {
+        25
+    } else if a == b'*' {
+        26
+    } else {
+        (a - 65) as usize
+    }
+}
+
+/// Return the PAM70 substitution matrix score of [a, b]
+///
+/// # Example
+///
+/// ```
+/// use bio::scores::pam70;
+/// assert_eq!(pam70(b'H', b'A'), -4);
+/// ```
+pub fn pam70(a: u8, b: u8) -> i32 {
+    let a = lookup(a);
+    let b = lookup(b);
+
+    MAT[(a, b)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pam70() {
+        let score1 = pam70(b'A', b'A');
+        assert_eq!(score1, 5);
+        let score2 = pam70(b'W', b'W');
+        assert_eq!(score2, 13);
+        let score3 = pam70(b'H', b'A');
+        assert_eq!(score3, -4);
+        let score4 = pam70(b'X', b'X');
+        assert_eq!(score4, -3);
+        let score5 = pam70(b'A', b'*');
+        assert_eq!(score5, -11);
+        let score6 = pam70(b'*', b'*');
+        assert_eq!(score6, 1);
+        let score7 = pam70(b'J', b'I');
+        assert_eq!(score7, -3);
+    }
+}
@@ -0,0 +1,556 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Editable sequences, e.g. for patching a reference with variants to obtain a personalized
+//! sequence, or for iteratively editing a consensus.
+//!
+//! An [`EditableSeq`](struct.EditableSeq.html) is a piece table: the original sequence is
+//! never modified, and the edited sequence is described by a list of pieces, each referring
+//! either to a range of the original sequence or to a range of a buffer holding all inserted
+//! symbols. The pieces are kept in a balanced tree, so that an edit takes time logarithmic in
+//! the number of previous edits, independent of the sequence length. The pieces also allow
+//! to translate coordinates between the original and the edited sequence, e.g. to lift
+//! annotations over to the patched sequence.
+//!
+//! Edits can be given in coordinates of the edited sequence (`insert`, `delete`, `replace`)
+//! or in coordinates of the original sequence (`replace_original`), which is convenient for
+//! applying variants from a VCF file regardless of the length changes caused by previous
+//! variants.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::seq::edit::EditableSeq;
+//!
+//! let mut seq = EditableSeq::new(b"ACGTACGTACGT".to_vec());
+//!
+//! // variants given as (0-based position, REF, ALT) on the original sequence
+//! let variants: &[(usize, &[u8], &[u8])] = &[(1, b"C", b"T"), (4, b"A", b"AGG"), (7, b"TAC", b"T")];
+//! for &(pos, reference, alt) in variants {
+//!     seq.replace_original(pos..pos + reference.len(), alt).unwrap();
+//! }
+//! assert_eq!(seq.to_vec(), b"ATGTAGGCGTGT");
+//!
+//! // the position of the original G at position 10 in the edited sequence
+//! assert_eq!(seq.to_edited(10), Some(10));
+//! // the deleted bases have no edited position, and inserted bases no original position
+//! assert_eq!(seq.to_edited(8), None);
+//! assert_eq!(seq.to_original(5), None);
+//! assert_eq!(seq.to_original(7), Some(5));
+//! ```
+
+use std::ops::Range;
+
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum Error {
+    #[error("range {start}..{end} exceeds the original sequence of length {len}")]
+    OutOfBounds {
+        start: usize,
+        end: usize,
+        len: usize,
+    },
+
+    #[error("range {start}..{end} of the original sequence overlaps a previous edit")]
+    Overlap { start: usize, end: usize },
+}
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// A range of either the original sequence or the buffer of inserted symbols.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct Piece {
+    original: bool,
+    start: usize,
+    len: usize,
+}
+
+impl Piece {
+    fn end(&self) -> usize {
+        self.start + self.len
+    }
+}
+
+/// A node of the treap holding the pieces in the order of the edited sequence.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Node {
+    piece: Piece,
+    priority: u64,
+    left: Option<usize>,
+    right: Option<usize>,
+    /// Total length of the pieces in the subtree.
+    len: usize,
+    /// End of the last piece of the original sequence in the subtree, or zero if there is
+    /// none. Pieces of the original sequence never change their order, so this is also the
+    /// largest end of all of them.
+    original_end: usize,
+}
+
+/// A pseudo-random treap priority for the node with the given index (SplitMix64).
+fn priority(index: usize) -> u64 {
+    let mut x = (index as u64).wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// A sequence supporting efficient insertions, deletions and replacements, and the
+/// translation of positions between the original and the edited sequence.
+///
+/// The pieces are kept in a balanced tree (a treap), such that edits, random access and the
+/// translation of positions take logarithmic time in the number of edits.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EditableSeq {
+    original: Vec<u8>,
+    inserted: Vec<u8>,
+    nodes: Vec<Node>,
+    /// Indices of unused nodes, to be reused.
+    free: Vec<usize>,
+    root: Option<usize>,
+}
+
+impl EditableSeq {
+    /// Create an editable sequence, initially equal to the given sequence.
+    pub fn new(original: Vec<u8>) -> Self {
+        let len = original.len();
+        let mut seq = EditableSeq {
+            original,
+            inserted: Vec::new(),
+            nodes: Vec::new(),
+            free: Vec::new(),
+            root: None,
+        };
+        if len > 0 {
+            seq.root = Some(seq.new_node(Piece {
+                original: true,
+                start: 0,
+                len,
+            }));
+        }
+        seq
+    }
+
+    /// The original sequence.
+    pub fn original(&self) -> &[u8] {
+        &self.original
+    }
+
+    /// Length of the edited sequence.
+    pub fn len(&self) -> usize {
+        self.subtree_len(self.root)
+    }
+
+    /// Whether the edited sequence is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn slice(&self, piece: &Piece) -> &[u8] {
+        let buffer = if piece.original {
+            &self.original
+        } else {
+            &self.inserted
+        };
+        &buffer[piece.start..piece.end()]
+    }
+
+    /// Iterate over the pieces in the order of the edited sequence.
+    fn pieces(&self) -> impl Iterator<Item = &Piece> + '_ {
+        let mut stack = Vec::new();
+        let mut node = self.root;
+        std::iter::from_fn(move || {
+            while let Some(i) = node {
+                stack.push(i);
+                node = self.nodes[i].left;
+            }
+            let i = stack.pop()?;
+            node = self.nodes[i].right;
+            Some(&self.nodes[i].piece)
+        })
+    }
+
+    /// Iterate over the symbols of the edited sequence.
+    pub fn iter(&self) -> impl Iterator<Item = &u8> + '_ {
+        self.pieces()
+            .flat_map(move |piece| self.slice(piece).iter())
+    }
+
+    /// The edited sequence.
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut seq = Vec::with_capacity(self.len());
+        for piece in self.pieces() {
+            seq.extend_from_slice(self.slice(piece));
+        }
+        seq
+    }
+
+    /// The piece containing the given position of the edited sequence, together with the
+    /// position of its start.
+    fn locate(&self, pos: usize) -> Option<(usize, &Piece)> {
+        let mut node = self.root;
+        let mut offset = 0;
+        while let Some(i) = node {
+            let Node {
+                piece, left, right, ..
+            } = &self.nodes[i];
+            let left_len = self.subtree_len(*left);
+            if pos < offset + left_len {
+                node = *left;
+            } else if pos < offset + left_len + piece.len {
+                return Some((offset + left_len, piece));
+            } else {
+                offset += left_len + piece.len;
+                node = *right;
+            }
+        }
+        None
+    }
+
+    /// The first piece of the original sequence that ends at or after `pos`, together with
+    /// the position of its start in the edited sequence.
+    fn locate_original(&self, pos: usize) -> Option<(usize, &Piece)> {
+        let mut node = self.root;
+        let mut offset = 0;
+        while let Some(i) = node {
+            let Node {
+                piece, left, right, ..
+            } = &self.nodes[i];
+            let left_len = self.subtree_len(*left);
+            if self.original_end(*left) >= pos.max(1) {
+                node = *left;
+            } else if piece.original && piece.end() >= pos {
+                return Some((offset + left_len, piece));
+            } else {
+                offset += left_len + piece.len;
+                node = *right;
+            }
+        }
+        None
+    }
+
+    /// Symbol at the given position of the edited sequence, or `None` if the position is out
+    /// of bounds.
+    pub fn get(&self, pos: usize) -> Option<u8> {
+        self.locate(pos)
+            .map(|(offset, piece)| self.slice(piece)[pos - offset])
+    }
+
+    fn subtree_len(&self, node: Option<usize>) -> usize {
+        node.map_or(0, |i| self.nodes[i].len)
+    }
+
+    fn original_end(&self, node: Option<usize>) -> usize {
+        node.map_or(0, |i| self.nodes[i].original_end)
+    }
+
+    fn new_node(&mut self, piece: Piece) -> usize {
+        let node = Node {
+            piece,
+            priority: 0,
+            left: None,
+            right: None,
+            len: piece.len,
+            original_end: if piece.original { piece.end() } else { 0 },
+        };
+        let i = match self.free.pop() {
+            Some(i) => {
+                self.nodes[i] = node;
+                i
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        };
+        self.nodes[i].priority = priority(i);
+        i
+    }
+
+    /// Mark all nodes of the given subtree as unused.
+    fn release(&mut self, node: Option<usize>) {
+        let mut stack: Vec<usize> = node.into_iter().collect();
+        while let Some(i) = stack.pop() {
+            stack.extend(self.nodes[i].left);
+            stack.extend(self.nodes[i].right);
+            self.free.push(i);
+        }
+    }
+
+    /// Recompute the subtree summaries of the given node from its children.
+    fn update(&mut self, i: usize) {
+        let Node {
+            piece, left, right, ..
+        } = self.nodes[i];
+        let len = self.subtree_len(left) + piece.len + self.subtree_len(right);
+        let original_end = match (self.original_end(right), piece.original) {
+            (0, true) => piece.end(),
+            (0, false) => self.original_end(left),
+            (end, _) => end,
+        };
+        let node = &mut self.nodes[i];
+        node.len = len;
+        node.original_end = original_end;
+    }
+
+    /// Concatenate two treaps.
+    fn merge(&mut self, left: Option<usize>, right: Option<usize>) -> Option<usize> {
+        match (left, right) {
+            (None, node) | (node, None) => node,
+            (Some(l), Some(r)) => {
+                if self.nodes[l].priority > self.nodes[r].priority {
+                    let merged = self.merge(self.nodes[l].right, right);
+                    self.nodes[l].right = merged;
+                    self.update(l);
+                    left
+                } else {
+                    let merged = self.merge(left, self.nodes[r].left);
+                    self.nodes[r].left = merged;
+                    self.update(r);
+                    right
+                }
+            }
+        }
+    }
+
+    /// Split a treap at the given position of the edited sequence, splitting the piece
+    /// containing it if necessary.
+    fn split(&mut self, node: Option<usize>, pos: usize) -> (Option<usize>, Option<usize>) {
+        let i = match node {
+            Some(i) => i,
+            None => return (None, None),
+        };
+        let Node {
+            piece, left, right, ..
+        } = self.nodes[i];
+        let left_len = self.subtree_len(left);
+        if pos <= left_len {
+            let (l, r) = self.split(left, pos);
+            self.nodes[i].left = r;
+            self.update(i);
+            (l, node)
+        } else if pos >= left_len + piece.len {
+            let (l, r) = self.split(right, pos - left_len - piece.len);
+            self.nodes[i].right = l;
+            self.update(i);
+            (node, r)
+        } else {
+            let k = pos - left_len;
+            self.nodes[i].piece.len = k;
+            self.nodes[i].right = None;
+            self.update(i);
+            let rest = self.new_node(Piece {
+                original: piece.original,
+                start: piece.start + k,
+                len: piece.len - k,
+            });
+            (node, self.merge(Some(rest), right))
+        }
+    }
+
+    /// Replace the given range of the edited sequence with `seq`.
+    ///
+    /// # Panics
+    ///
+    /// If the range exceeds the edited sequence.
+    pub fn replace(&mut self, range: Range<usize>, seq: &[u8]) {
+        assert!(
+            range.start <= range.end && range.end <= self.len(),
+            "Range out of bounds."
+        );
+        if range.start == range.end && seq.is_empty() {
+            return;
+        }
+        let (left, rest) = self.split(self.root, range.start);
+        let (removed, right) = self.split(rest, range.end - range.start);
+        self.release(removed);
+        let middle = if seq.is_empty() {
+            None
+        } else {
+            let piece = Piece {
+                original: false,
+                start: self.inserted.len(),
+                len: seq.len(),
+            };
+            self.inserted.extend_from_slice(seq);
+            Some(self.new_node(piece))
+        };
+        let left = self.merge(left, middle);
+        self.root = self.merge(left, right);
+    }
+
+    /// Insert `seq` before the given position of the edited sequence.
+    ///
+    /// # Panics
+    ///
+    /// If the position exceeds the edited sequence.
+    pub fn insert(&mut self, pos: usize, seq: &[u8]) {
+        self.replace(pos..pos, seq);
+    }
+
+    /// Delete the given range of the edited sequence.
+    ///
+    /// # Panics
+    ///
+    /// If the range exceeds the edited sequence.
+    pub fn delete(&mut self, range: Range<usize>) {
+        self.replace(range, b"");
+    }
+
+    /// Replace the given range of the original sequence with `seq`, e.g. the REF allele of a
+    /// variant with its ALT allele. The range must not overlap a previous edit, and an empty
+    /// range inserts before its start position.
+    pub fn replace_original(&mut self, range: Range<usize>, seq: &[u8]) -> Result<()> {
+        if range.start > range.end || range.end > self.original.len() {
+            return Err(Error::OutOfBounds {
+                start: range.start,
+                end: range.end,
+                len: self.original.len(),
+            });
+        }
+        if self.original.is_empty() {
+            self.insert(0, seq);
+            return Ok(());
+        }
+        // the piece containing a non-empty range, or ending at or after an empty one
+        let pos = if range.is_empty() {
+            range.start
+        } else {
+            range.start + 1
+        };
+        match self.locate_original(pos) {
+            Some((offset, piece)) if piece.start <= range.start && range.end <= piece.end() => {
+                let start = offset + range.start - piece.start;
+                self.replace(start..start + range.end - range.start, seq);
+                Ok(())
+            }
+            _ => Err(Error::Overlap {
+                start: range.start,
+                end: range.end,
+            }),
+        }
+    }
+
+    /// Position in the edited sequence of the given position of the original sequence, or
+    /// `None` if it has been deleted or replaced.
+    pub fn to_edited(&self, pos: usize) -> Option<usize> {
+        self.locate_original(pos + 1)
+            .filter(|(_, piece)| piece.start <= pos)
+            .map(|(offset, piece)| offset + pos - piece.start)
+    }
+
+    /// Position in the original sequence of the given position of the edited sequence, or
+    /// `None` if it is an inserted symbol or out of bounds.
+    pub fn to_original(&self, pos: usize) -> Option<usize> {
+        self.locate(pos)
+            .filter(|(_, piece)| piece.original)
+            .map(|(offset, piece)| piece.start + pos - offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::random_seq;
+    use rand::prelude::*;
+
+    #[test]
+    fn test_against_vec() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let original = random_seq(&mut rng, b"ACGT", 100);
+        let mut seq = EditableSeq::new(original.clone());
+        // the edited sequence, with the original position of each symbol
+        let mut expected: Vec<(u8, Option<usize>)> = original
+            .iter()
+            .enumerate()
+            .map(|(i, &a)| (a, Some(i)))
+            .collect();
+        for _ in 0..200 {
+            let start = rng.gen_range(0..=expected.len());
+            let end = rng.gen_range(start..=(start + 5).min(expected.len()));
+            let len = rng.gen_range(0..4);
+            let insertion = random_seq(&mut rng, b"acgt", len);
+            seq.replace(start..end, &insertion);
+            expected.splice(start..end, insertion.iter().map(|&a| (a, None)));
+
+            assert_eq!(seq.len(), expected.len());
+            let symbols: Vec<u8> = expected.iter().map(|&(a, _)| a).collect();
+            assert_eq!(seq.to_vec(), symbols);
+            assert!(seq.iter().eq(symbols.iter()));
+            for (pos, &(a, orig)) in expected.iter().enumerate() {
+                assert_eq!(seq.get(pos), Some(a));
+                assert_eq!(seq.to_original(pos), orig);
+                if let Some(orig) = orig {
+                    assert_eq!(seq.to_edited(orig), Some(pos));
+                }
+            }
+            assert_eq!(seq.get(expected.len()), None);
+        }
+    }
+
+    #[test]
+    fn test_many_variants() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let original = random_seq(&mut rng, b"ACGT", 100_000);
+        let mut seq = EditableSeq::new(original.clone());
+        // variants replacing two bases every ten bases, applied in random order
+        let mut variants: Vec<(usize, Vec<u8>)> = (0..original.len())
+            .step_by(10)
+            .map(|pos| (pos, random_seq(&mut rng, b"acgt", pos % 3)))
+            .collect();
+        variants.shuffle(&mut rng);
+        for (pos, alt) in &variants {
+            seq.replace_original(*pos..*pos + 2, alt).unwrap();
+        }
+        assert_eq!(
+            seq.replace_original(10..12, b""),
+            Err(Error::Overlap { start: 10, end: 12 })
+        );
+
+        variants.sort_unstable();
+        let mut expected = Vec::new();
+        for (pos, alt) in &variants {
+            expected.extend_from_slice(alt);
+            expected.extend_from_slice(&original[pos + 2..pos + 10]);
+        }
+        assert_eq!(seq.len(), expected.len());
+        assert_eq!(seq.to_vec(), expected);
+        for (pos, &a) in original.iter().enumerate() {
+            let edited = seq.to_edited(pos);
+            assert_eq!(edited.is_none(), pos % 10 < 2);
+            if let Some(edited) = edited {
+                assert_eq!(seq.get(edited), Some(a));
+                assert_eq!(seq.to_original(edited), Some(pos));
+            }
+        }
+    }
+
+    #[test]
+    fn test_replace_original() {
+        let mut seq = EditableSeq::new(b"ACGTACGT".to_vec());
+        seq.replace_original(2..4, b"").unwrap();
+        seq.replace_original(0..1, b"TT").unwrap();
+        assert_eq!(
+            seq.replace_original(3..5, b"G"),
+            Err(Error::Overlap { start: 3, end: 5 })
+        );
+        // adjacent to the deletion is fine
+        seq.replace_original(4..5, b"C").unwrap();
+        seq.replace_original(8..8, b"AAA").unwrap();
+        assert_eq!(seq.to_vec(), b"TTCCCGTAAA");
+        assert_eq!(
+            seq.replace_original(8..9, b""),
+            Err(Error::OutOfBounds {
+                start: 8,
+                end: 9,
+                len: 8
+            })
+        );
+        assert_eq!(seq.original(), b"ACGTACGT");
+
+        let mut empty = EditableSeq::new(Vec::new());
+        empty.replace_original(0..0, b"ACGT").unwrap();
+        assert_eq!(empty.to_vec(), b"ACGT");
+    }
+}
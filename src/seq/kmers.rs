@@ -0,0 +1,329 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Utilities for DNA k-mers: iteration, canonicalization, and 2-bit integer encoding.
+//!
+//! k-mers of length up to 32 are encoded into a `u64` with two bits per base (`A` = 0,
+//! `C` = 1, `G` = 2, `T` = 3), the first base being the most significant. With this
+//! encoding, integer order equals lexicographical order, and the canonical k-mer (the
+//! smaller of a k-mer and its reverse complement) is simply the minimum of both codes.
+//! The [`Encoder`](struct.Encoder.html) maintains the codes of both strands in O(1) per
+//! base, which is the basis for k-mer counting, minimizers and sketching.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::seq::kmers;
+//!
+//! let code = kmers::encode(b"GATTACA").unwrap();
+//! assert_eq!(kmers::decode(code, 7), b"GATTACA");
+//! assert_eq!(kmers::decode(kmers::revcomp(code, 7), 7), b"TGTAATC");
+//! assert_eq!(kmers::canonical(code, 7), code);
+//!
+//! // all valid 3-mers with their positions, skipping those with an N
+//! let encoded: Vec<(usize, u64)> = kmers::encoded(b"ACGNACGT", 3)
+//!     .map(|kmer| (kmer.pos, kmer.canonical()))
+//!     .collect();
+//! assert_eq!(
+//!     encoded,
+//!     vec![
+//!         (0, kmers::encode(b"ACG").unwrap()),
+//!         (4, kmers::encode(b"ACG").unwrap()),
+//!         (5, kmers::encode(b"ACG").unwrap()),
+//!     ]
+//! );
+//! ```
+
+use std::slice::Windows;
+
+use crate::alphabets::dna;
+
+/// Maximal k-mer length that can be encoded into a `u64`.
+pub const MAX_K: usize = 32;
+
+const BASES: &[u8; 4] = b"ACGT";
+
+/// Iterate over all k-mers of a sequence.
+///
+/// # Panics
+///
+/// If `k` is zero.
+pub fn kmers(seq: &[u8], k: usize) -> Windows<'_, u8> {
+    seq.windows(k)
+}
+
+/// 2-bit code of a base (case insensitive), or `None` if it is not one of `ACGT`.
+pub fn encode_base(a: u8) -> Option<u64> {
+    match a {
+        b'A' | b'a' => Some(0),
+        b'C' | b'c' => Some(1),
+        b'G' | b'g' => Some(2),
+        b'T' | b't' => Some(3),
+        _ => None,
+    }
+}
+
+/// Encode a k-mer into a `u64`, or return `None` if it is longer than
+/// [`MAX_K`](constant.MAX_K.html) or contains a symbol other than `ACGT` (case insensitive).
+pub fn encode(kmer: &[u8]) -> Option<u64> {
+    if kmer.len() > MAX_K {
+        return None;
+    }
+    kmer.iter()
+        .try_fold(0, |code, &a| Some(code << 2 | encode_base(a)?))
+}
+
+/// Decode a k-mer of length `k` into uppercase bases.
+///
+/// # Panics
+///
+/// If `k` is larger than [`MAX_K`](constant.MAX_K.html).
+pub fn decode(code: u64, k: usize) -> Vec<u8> {
+    assert!(k <= MAX_K, "k must not exceed {}", MAX_K);
+    (0..k)
+        .rev()
+        .map(|i| BASES[(code >> (2 * i) & 3) as usize])
+        .collect()
+}
+
+/// Bit mask covering the codes of k-mers of length `k`.
+fn mask(k: usize) -> u64 {
+    if k >= MAX_K {
+        u64::MAX
+    } else {
+        (1 << (2 * k)) - 1
+    }
+}
+
+/// Reverse complement of an encoded k-mer of length `k`.
+///
+/// # Panics
+///
+/// If `k` is larger than [`MAX_K`](constant.MAX_K.html).
+pub fn revcomp(code: u64, k: usize) -> u64 {
+    assert!(k <= MAX_K, "k must not exceed {}", MAX_K);
+    if k == 0 {
+        return 0;
+    }
+    // complementing is negating with the chosen encoding, then reverse the 2-bit groups
+    let mut x = !code;
+    x = (x >> 2 & 0x3333_3333_3333_3333) | (x & 0x3333_3333_3333_3333) << 2;
+    x = (x >> 4 & 0x0F0F_0F0F_0F0F_0F0F) | (x & 0x0F0F_0F0F_0F0F_0F0F) << 4;
+    x.swap_bytes() >> (64 - 2 * k)
+}
+
+/// Canonical form of an encoded k-mer of length `k`, i.e. the minimum of its code and the
+/// code of its reverse complement.
+pub fn canonical(code: u64, k: usize) -> u64 {
+    code.min(revcomp(code, k))
+}
+
+/// Canonical form of a k-mer, i.e. the lexicographically smaller of the k-mer and its
+/// reverse complement. Symbols are compared as given, so the k-mer should be uppercase.
+///
+/// ```
+/// use bio::seq::kmers;
+///
+/// assert_eq!(kmers::canonical_seq(b"TTGC"), b"GCAA");
+/// assert_eq!(kmers::canonical_seq(b"ACGT"), b"ACGT");
+/// ```
+pub fn canonical_seq(kmer: &[u8]) -> Vec<u8> {
+    let rc = dna::revcomp(kmer);
+    if rc.as_slice() < kmer {
+        rc
+    } else {
+        kmer.to_owned()
+    }
+}
+
+/// An encoded k-mer of a sequence, see [`encoded`](fn.encoded.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Kmer {
+    /// Start position of the k-mer in the sequence.
+    pub pos: usize,
+    /// Code of the k-mer.
+    pub forward: u64,
+    /// Code of the reverse complement of the k-mer.
+    pub reverse: u64,
+}
+
+impl Kmer {
+    /// The canonical code, i.e. the minimum of the forward and reverse code.
+    pub fn canonical(&self) -> u64 {
+        self.forward.min(self.reverse)
+    }
+
+    /// Whether the forward code is the canonical one.
+    pub fn is_forward_canonical(&self) -> bool {
+        self.forward <= self.reverse
+    }
+}
+
+/// Rolling encoder maintaining the codes of the last `k` bases on both strands.
+///
+/// # Example
+///
+/// ```
+/// use bio::seq::kmers::{self, Encoder};
+///
+/// let mut encoder = Encoder::new(2);
+/// assert_eq!(encoder.push(b'G'), None);
+/// assert_eq!(
+///     encoder.push(b'A'),
+///     Some((kmers::encode(b"GA").unwrap(), kmers::encode(b"TC").unwrap()))
+/// );
+/// // an invalid base resets the encoder
+/// assert_eq!(encoder.push(b'N'), None);
+/// assert_eq!(encoder.push(b'T'), None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Encoder {
+    k: usize,
+    mask: u64,
+    forward: u64,
+    reverse: u64,
+    /// Number of valid bases pushed since the last reset.
+    len: usize,
+}
+
+impl Encoder {
+    /// Create a new encoder for k-mers of length `k`.
+    ///
+    /// # Panics
+    ///
+    /// If `k` is zero or larger than [`MAX_K`](constant.MAX_K.html).
+    pub fn new(k: usize) -> Self {
+        assert!(k > 0 && k <= MAX_K, "k must be in 1..={}", MAX_K);
+        Encoder {
+            k,
+            mask: mask(k),
+            forward: 0,
+            reverse: 0,
+            len: 0,
+        }
+    }
+
+    /// The k-mer length.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Forget all pushed bases.
+    pub fn reset(&mut self) {
+        self.forward = 0;
+        self.reverse = 0;
+        self.len = 0;
+    }
+
+    /// Append a base, and return the forward and reverse complement codes of the last `k`
+    /// bases if they are all valid. Symbols other than `ACGT` (case insensitive) reset the
+    /// encoder.
+    pub fn push(&mut self, a: u8) -> Option<(u64, u64)> {
+        match encode_base(a) {
+            Some(code) => {
+                self.forward = (self.forward << 2 | code) & self.mask;
+                self.reverse = self.reverse >> 2 | (3 - code) << (2 * (self.k - 1));
+                self.len += 1;
+                if self.len >= self.k {
+                    Some((self.forward, self.reverse))
+                } else {
+                    None
+                }
+            }
+            None => {
+                self.reset();
+                None
+            }
+        }
+    }
+}
+
+/// Iterator over the encoded k-mers of a sequence, see [`encoded`](fn.encoded.html).
+#[derive(Debug, Clone)]
+pub struct EncodedKmers<'a> {
+    seq: &'a [u8],
+    pos: usize,
+    encoder: Encoder,
+}
+
+impl<'a> Iterator for EncodedKmers<'a> {
+    type Item = Kmer;
+
+    fn next(&mut self) -> Option<Kmer> {
+        while self.pos < self.seq.len() {
+            let a = self.seq[self.pos];
+            self.pos += 1;
+            if let Some((forward, reverse)) = self.encoder.push(a) {
+                return Some(Kmer {
+                    pos: self.pos - self.encoder.k,
+                    forward,
+                    reverse,
+                });
+            }
+        }
+        None
+    }
+}
+
+/// Iterate over the encoded k-mers of a sequence in O(1) per base, skipping k-mers that
+/// contain symbols other than `ACGT` (case insensitive).
+///
+/// # Panics
+///
+/// If `k` is zero or larger than [`MAX_K`](constant.MAX_K.html).
+pub fn encoded(seq: &[u8], k: usize) -> EncodedKmers<'_> {
+    EncodedKmers {
+        seq,
+        pos: 0,
+        encoder: Encoder::new(k),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_decode() {
+        assert_eq!(encode(b"ACGT"), Some(0b00_01_10_11));
+        assert_eq!(encode(b"acgt"), encode(b"ACGT"));
+        assert_eq!(encode(b"ACNT"), None);
+        assert_eq!(encode(&[b'T'; 33]), None);
+        assert_eq!(encode(&[b'T'; 32]), Some(u64::MAX));
+        assert_eq!(decode(u64::MAX, 32), vec![b'T'; 32]);
+        assert_eq!(encode(b""), Some(0));
+    }
+
+    #[test]
+    fn test_revcomp_matches_sequence() {
+        let seq = b"GATTACAGGCTTACGATCGATCGGCTAGCTAGCATCGA";
+        for k in 1..=MAX_K {
+            for kmer in kmers(seq, k) {
+                let code = encode(kmer).unwrap();
+                assert_eq!(decode(revcomp(code, k), k), dna::revcomp(kmer));
+                assert_eq!(decode(canonical(code, k), k), canonical_seq(kmer));
+            }
+        }
+    }
+
+    #[test]
+    fn test_rolling_matches_direct() {
+        let seq = b"ACGTTGCAnACGGTAcgtaNNATTTGCCAT";
+        for k in [1, 3, 5, 11].iter().copied() {
+            let expected: Vec<Kmer> = kmers(seq, k)
+                .enumerate()
+                .filter_map(|(pos, kmer)| {
+                    let forward = encode(kmer)?;
+                    Some(Kmer {
+                        pos,
+                        forward,
+                        reverse: encode(&dna::revcomp(kmer))?,
+                    })
+                })
+                .collect();
+            assert_eq!(encoded(seq, k).collect::<Vec<_>>(), expected);
+        }
+    }
+}
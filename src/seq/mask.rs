@@ -0,0 +1,142 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Masking of sequence intervals, e.g. of repeats reported by RepeatMasker.
+//!
+//! Sequences can be hard-masked (replacing masked symbols by `N`, or `X` for proteins) or
+//! soft-masked (converting masked symbols to lowercase). Masked intervals are given and
+//! returned as 0-based, half-open ranges.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::seq::mask;
+//!
+//! let mut seq = b"ACGTACGTACGT".to_vec();
+//! mask::soft_mask(&mut seq, vec![2..5, 9..20]);
+//! assert_eq!(seq, b"ACgtaCGTAcgt");
+//!
+//! // uppercase the sequence, but remember the soft-masked intervals
+//! let masked = mask::unmask(&mut seq);
+//! assert_eq!(seq, b"ACGTACGTACGT");
+//! assert_eq!(masked, vec![2..5, 9..12]);
+//!
+//! mask::hard_mask(&mut seq, masked, b'N');
+//! assert_eq!(seq, b"ACNNNCGTANNN");
+//! ```
+
+use std::ops::Range;
+
+/// Apply `f` to all symbols in the given intervals, which are clipped to the sequence.
+fn apply<I, F>(seq: &mut [u8], intervals: I, f: F)
+where
+    I: IntoIterator<Item = Range<usize>>,
+    F: Fn(&mut u8),
+{
+    let len = seq.len();
+    for interval in intervals {
+        let start = interval.start.min(len);
+        let end = interval.end.min(len);
+        if start < end {
+            seq[start..end].iter_mut().for_each(&f);
+        }
+    }
+}
+
+/// Hard-mask the given intervals, replacing their symbols by `symbol` (usually `N` for
+/// nucleotide and `X` for protein sequences). Intervals exceeding the sequence are clipped.
+pub fn hard_mask<I>(seq: &mut [u8], intervals: I, symbol: u8)
+where
+    I: IntoIterator<Item = Range<usize>>,
+{
+    apply(seq, intervals, |a| *a = symbol);
+}
+
+/// Soft-mask the given intervals, converting their symbols to lowercase. Intervals
+/// exceeding the sequence are clipped.
+pub fn soft_mask<I>(seq: &mut [u8], intervals: I)
+where
+    I: IntoIterator<Item = Range<usize>>,
+{
+    apply(seq, intervals, |a| a.make_ascii_lowercase());
+}
+
+/// Maximal runs of consecutive symbols fulfilling the given predicate.
+fn runs<F: Fn(u8) -> bool>(seq: &[u8], predicate: F) -> Vec<Range<usize>> {
+    let mut runs = Vec::new();
+    let mut start = None;
+    for (i, &a) in seq.iter().enumerate() {
+        match (predicate(a), start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                runs.push(s..i);
+                start = None;
+            }
+            _ => (),
+        }
+    }
+    if let Some(s) = start {
+        runs.push(s..seq.len());
+    }
+    runs
+}
+
+/// Return the soft-masked intervals, i.e. the maximal runs of lowercase symbols.
+///
+/// # Example
+///
+/// ```
+/// use bio::seq::mask;
+///
+/// assert_eq!(mask::soft_masked_intervals(b"acGTNnn"), vec![0..2, 5..7]);
+/// ```
+pub fn soft_masked_intervals(seq: &[u8]) -> Vec<Range<usize>> {
+    runs(seq, |a| a.is_ascii_lowercase())
+}
+
+/// Return the hard-masked intervals, i.e. the maximal runs of `symbol` (ignoring case).
+///
+/// # Example
+///
+/// ```
+/// use bio::seq::mask;
+///
+/// assert_eq!(mask::hard_masked_intervals(b"NNACnTN", b'N'), vec![0..2, 4..5, 6..7]);
+/// ```
+pub fn hard_masked_intervals(seq: &[u8], symbol: u8) -> Vec<Range<usize>> {
+    runs(seq, |a| a.eq_ignore_ascii_case(&symbol))
+}
+
+/// Convert the sequence to uppercase, returning the intervals that were soft-masked
+/// before, so that the mask can be restored with [`soft_mask`](fn.soft_mask.html).
+pub fn unmask(seq: &mut [u8]) -> Vec<Range<usize>> {
+    let masked = soft_masked_intervals(seq);
+    seq.make_ascii_uppercase();
+    masked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clipping_and_empty_intervals() {
+        let mut seq = b"ACGT".to_vec();
+        hard_mask(&mut seq, vec![3..10, 6..8, 1..1], b'N');
+        assert_eq!(seq, b"ACGN");
+        soft_mask(&mut seq, std::iter::once(0..1));
+        assert_eq!(seq, b"aCGN");
+    }
+
+    #[test]
+    fn test_soft_mask_roundtrip() {
+        let original = b"acgTTTAGgNnnCCa".to_vec();
+        let mut seq = original.clone();
+        let masked = unmask(&mut seq);
+        assert!(soft_masked_intervals(&seq).is_empty());
+        soft_mask(&mut seq, masked);
+        assert_eq!(seq, original);
+    }
+}
@@ -0,0 +1,454 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! (w,k)-minimizers of DNA sequences and an index from minimizers to their positions, as
+//! used for seeding in minimap2 (Li, Minimap2: pairwise alignment for nucleotide sequences,
+//! Bioinformatics, 2018).
+//!
+//! The minimizer of a window of `w` consecutive k-mers is the k-mer with the smallest hash.
+//! Hashing the 2-bit codes of the k-mers with an invertible integer hash
+//! ([`hash64`](fn.hash64.html)) avoids the bias towards poly-A k-mers of the lexicographical
+//! order, while the k-mer can still be recovered from its hash.
+//! Ties are broken by robust winnowing (Schleimer et al., Winnowing: local algorithms for
+//! document fingerprinting, SIGMOD 2003): if the previous minimizer is still in the window
+//! and has the minimal hash, it is kept, otherwise the rightmost k-mer with the minimal hash
+//! is selected. Each minimizer is reported once, even if it is selected in many windows.
+//! With canonical k-mers, a sequence and its reverse complement yield the same minimizers
+//! (up to ties). k-mers containing symbols other than `ACGT` are skipped, and windows do not
+//! span them.
+//!
+//! Complexity: O(n) amortized for a sequence of length n.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::seq::kmers;
+//! use bio::seq::minimizers::{self, MinimizerIndex};
+//!
+//! let seq = b"ACGGCTAGCTAGGATCGATCGGATCGTAGCTAGCTAGGCTTAACGG";
+//! let (w, k) = (4, 7);
+//! let mins: Vec<_> = minimizers::minimizers(seq, w, k, true).collect();
+//! // every window of w consecutive k-mers contains a minimizer
+//! for start in 0..=seq.len() - (w + k - 1) {
+//!     assert!(mins.iter().any(|m| m.pos >= start && m.pos < start + w));
+//! }
+//! // the hash is invertible
+//! let m = mins[0];
+//! let kmer = &seq[m.pos..m.pos + k];
+//! assert_eq!(
+//!     minimizers::hash64_inv(m.hash, k),
+//!     kmers::canonical(kmers::encode(kmer).unwrap(), k)
+//! );
+//!
+//! // seeds between a query and the indexed sequences, skipping repetitive minimizers
+//! let mut index = MinimizerIndex::new(w, k, true);
+//! index.insert(seq);
+//! let seeds = index.seeds(&seq[10..40], 1);
+//! assert!(!seeds.is_empty());
+//! assert!(seeds.iter().all(|s| s.target_pos == s.query_pos + 10 && s.same_strand));
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::seq::kmers::{self, EncodedKmers};
+
+/// Invertible integer hash of an encoded k-mer of length `k`, mapping `0..4^k` onto itself
+/// (Thomas Wang's 64-bit integer hash, restricted to 2k bits as in minimap2).
+///
+/// # Panics
+///
+/// If `k` is zero or larger than [`kmers::MAX_K`](../kmers/constant.MAX_K.html).
+pub fn hash64(code: u64, k: usize) -> u64 {
+    let mask = mask(k);
+    let mut key = code;
+    key = (!key).wrapping_add(key << 21) & mask;
+    key ^= key >> 24;
+    key = key.wrapping_mul(265) & mask;
+    key ^= key >> 14;
+    key = key.wrapping_mul(21) & mask;
+    key ^= key >> 28;
+    key.wrapping_add(key << 31) & mask
+}
+
+/// Inverse of [`hash64`](fn.hash64.html), i.e. the code of the k-mer with the given hash.
+///
+/// # Panics
+///
+/// If `k` is zero or larger than [`kmers::MAX_K`](../kmers/constant.MAX_K.html).
+pub fn hash64_inv(hash: u64, k: usize) -> u64 {
+    let mask = mask(k);
+    let mut key = hash;
+    // invert key + (key << 31)
+    let tmp = key.wrapping_sub(key << 31);
+    key = key.wrapping_sub(tmp << 31) & mask;
+    // invert key ^ (key >> 28)
+    let tmp = key ^ key >> 28;
+    key ^= tmp >> 28;
+    // invert key * 21
+    key = key.wrapping_mul(14_933_078_535_860_113_213) & mask;
+    // invert key ^ (key >> 14)
+    let mut tmp = key ^ key >> 14;
+    tmp = key ^ tmp >> 14;
+    tmp = key ^ tmp >> 14;
+    key ^= tmp >> 14;
+    // invert key * 265
+    key = key.wrapping_mul(15_244_667_743_933_553_977) & mask;
+    // invert key ^ (key >> 24)
+    let tmp = key ^ key >> 24;
+    key ^= tmp >> 24;
+    // invert !key + (key << 21)
+    let mut tmp = !key;
+    tmp = !(key.wrapping_sub(tmp << 21));
+    tmp = !(key.wrapping_sub(tmp << 21));
+    !(key.wrapping_sub(tmp << 21)) & mask
+}
+
+fn mask(k: usize) -> u64 {
+    assert!(
+        k > 0 && k <= kmers::MAX_K,
+        "k must be in 1..={}",
+        kmers::MAX_K
+    );
+    if k == kmers::MAX_K {
+        u64::MAX
+    } else {
+        (1 << (2 * k)) - 1
+    }
+}
+
+/// A minimizer of a sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Minimizer {
+    /// Start position of the k-mer in the sequence.
+    pub pos: usize,
+    /// Hash of the (canonical) k-mer.
+    pub hash: u64,
+    /// Whether the k-mer is taken from the forward strand, i.e. the forward k-mer is the
+    /// canonical one. Always true if minimizers are not canonical.
+    pub forward: bool,
+}
+
+/// Iterator over the minimizers of a sequence, see [`minimizers`](fn.minimizers.html).
+#[derive(Debug, Clone)]
+pub struct Minimizers<'a> {
+    kmers: EncodedKmers<'a>,
+    k: usize,
+    w: usize,
+    canonical: bool,
+    /// Candidates of the current window with increasing hashes.
+    candidates: VecDeque<Minimizer>,
+    /// Number of consecutive valid k-mers seen so far.
+    run: usize,
+    last: Option<Minimizer>,
+    selected: Option<Minimizer>,
+}
+
+impl<'a> Iterator for Minimizers<'a> {
+    type Item = Minimizer;
+
+    fn next(&mut self) -> Option<Minimizer> {
+        loop {
+            let kmer = self.kmers.next()?;
+            if !matches!(self.last, Some(last) if last.pos + 1 == kmer.pos) {
+                // start of a new run of valid k-mers
+                self.candidates.clear();
+                self.run = 0;
+                self.selected = None;
+            }
+            let current = if self.canonical {
+                Minimizer {
+                    pos: kmer.pos,
+                    hash: hash64(kmer.canonical(), self.k),
+                    forward: kmer.is_forward_canonical(),
+                }
+            } else {
+                Minimizer {
+                    pos: kmer.pos,
+                    hash: hash64(kmer.forward, self.k),
+                    forward: true,
+                }
+            };
+            self.last = Some(current);
+            self.run += 1;
+
+            // on ties, the rightmost candidate survives
+            while matches!(self.candidates.back(), Some(c) if c.hash >= current.hash) {
+                self.candidates.pop_back();
+            }
+            self.candidates.push_back(current);
+            if self.run < self.w {
+                continue;
+            }
+            let start = current.pos + 1 - self.w;
+            while self.candidates[0].pos < start {
+                self.candidates.pop_front();
+            }
+
+            let min = self.candidates[0];
+            let selected = match self.selected {
+                Some(s) if s.pos >= start && s.hash == min.hash => s,
+                _ => min,
+            };
+            if self.selected != Some(selected) {
+                self.selected = Some(selected);
+                return Some(selected);
+            }
+        }
+    }
+}
+
+/// Iterate over the (w,k)-minimizers of a sequence, i.e. the k-mers with minimal hash among
+/// `w` consecutive k-mers. If `canonical` is true, k-mers are hashed in their canonical
+/// form, such that both strands of the sequence yield the same minimizers.
+///
+/// # Panics
+///
+/// If `w` is zero, or `k` is zero or larger than [`kmers::MAX_K`](../kmers/constant.MAX_K.html).
+pub fn minimizers(seq: &[u8], w: usize, k: usize, canonical: bool) -> Minimizers<'_> {
+    assert!(w > 0, "w must be positive");
+    Minimizers {
+        kmers: kmers::encoded(seq, k),
+        k,
+        w,
+        canonical,
+        candidates: VecDeque::with_capacity(w),
+        run: 0,
+        last: None,
+        selected: None,
+    }
+}
+
+/// A position of a minimizer in the indexed sequences.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Location {
+    /// Index of the sequence, in the order of insertion.
+    pub seq: usize,
+    /// Start position of the k-mer in the sequence.
+    pub pos: usize,
+    /// Whether the k-mer is taken from the forward strand.
+    pub forward: bool,
+}
+
+/// A shared minimizer between a query and an indexed sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Seed {
+    /// Index of the sequence, in the order of insertion.
+    pub target: usize,
+    /// Start position of the k-mer in the indexed sequence.
+    pub target_pos: usize,
+    /// Start position of the k-mer in the query.
+    pub query_pos: usize,
+    /// Whether the k-mer occurs on the same strand in query and indexed sequence.
+    pub same_strand: bool,
+}
+
+/// An index from minimizers to their positions in a set of sequences.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MinimizerIndex {
+    w: usize,
+    k: usize,
+    canonical: bool,
+    seqs: usize,
+    locations: HashMap<u64, Vec<Location>>,
+}
+
+impl MinimizerIndex {
+    /// Create an empty index for (w,k)-minimizers, see [`minimizers`](fn.minimizers.html).
+    pub fn new(w: usize, k: usize, canonical: bool) -> Self {
+        MinimizerIndex {
+            w,
+            k,
+            canonical,
+            seqs: 0,
+            locations: HashMap::new(),
+        }
+    }
+
+    /// The window size.
+    pub fn w(&self) -> usize {
+        self.w
+    }
+
+    /// The k-mer length.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Number of indexed sequences.
+    pub fn len(&self) -> usize {
+        self.seqs
+    }
+
+    /// Whether no sequences are indexed.
+    pub fn is_empty(&self) -> bool {
+        self.seqs == 0
+    }
+
+    /// Number of distinct minimizers.
+    pub fn minimizer_count(&self) -> usize {
+        self.locations.len()
+    }
+
+    /// Index the minimizers of the given sequence, returning the index of the sequence.
+    pub fn insert(&mut self, seq: &[u8]) -> usize {
+        let i = self.seqs;
+        for m in minimizers(seq, self.w, self.k, self.canonical) {
+            self.locations.entry(m.hash).or_default().push(Location {
+                seq: i,
+                pos: m.pos,
+                forward: m.forward,
+            });
+        }
+        self.seqs += 1;
+        i
+    }
+
+    /// Locations of the minimizer with the given hash.
+    pub fn get(&self, hash: u64) -> &[Location] {
+        self.locations.get(&hash).map_or(&[], |l| l.as_slice())
+    }
+
+    /// Find all seeds between the given query and the indexed sequences, ignoring minimizers
+    /// occurring more than `max_occ` times in the index. Seeds are sorted by target, target
+    /// position and query position.
+    pub fn seeds(&self, query: &[u8], max_occ: usize) -> Vec<Seed> {
+        let mut seeds = Vec::new();
+        for m in minimizers(query, self.w, self.k, self.canonical) {
+            let locations = self.get(m.hash);
+            if locations.len() > max_occ {
+                continue;
+            }
+            seeds.extend(locations.iter().map(|l| Seed {
+                target: l.seq,
+                target_pos: l.pos,
+                query_pos: m.pos,
+                same_strand: l.forward == m.forward,
+            }));
+        }
+        seeds.sort_unstable();
+        seeds
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabets::dna;
+    use crate::utils::testing::random_seq;
+    use rand::prelude::*;
+
+    /// Robust winnowing by explicitly enumerating all windows.
+    fn naive(seq: &[u8], w: usize, k: usize, canonical: bool) -> Vec<Minimizer> {
+        let hashes: Vec<Option<Minimizer>> = (0..(seq.len() + 1).saturating_sub(k))
+            .map(|pos| {
+                let code = kmers::encode(&seq[pos..pos + k])?;
+                let rc = kmers::revcomp(code, k);
+                Some(if canonical {
+                    Minimizer {
+                        pos,
+                        hash: hash64(code.min(rc), k),
+                        forward: code <= rc,
+                    }
+                } else {
+                    Minimizer {
+                        pos,
+                        hash: hash64(code, k),
+                        forward: true,
+                    }
+                })
+            })
+            .collect();
+        let mut result: Vec<Minimizer> = Vec::new();
+        let mut selected: Option<Minimizer> = None;
+        for window in hashes.windows(w) {
+            if window.iter().any(|m| m.is_none()) {
+                selected = None;
+                continue;
+            }
+            let window: Vec<Minimizer> = window.iter().map(|m| m.unwrap()).collect();
+            let min_hash = window.iter().map(|m| m.hash).min().unwrap();
+            let choice = match selected {
+                Some(s) if s.pos >= window[0].pos && s.hash == min_hash => s,
+                _ => *window.iter().rev().find(|m| m.hash == min_hash).unwrap(),
+            };
+            if selected != Some(choice) {
+                result.push(choice);
+            }
+            selected = Some(choice);
+        }
+        result
+    }
+
+    #[test]
+    fn test_hash_inverse() {
+        let mut rng = StdRng::seed_from_u64(42);
+        for k in 1..=kmers::MAX_K {
+            let mask = mask(k);
+            for _ in 0..100 {
+                let code = rng.gen::<u64>() & mask;
+                let hash = hash64(code, k);
+                assert!(hash <= mask);
+                assert_eq!(hash64_inv(hash, k), code);
+            }
+        }
+        // a bijection for small k
+        let mut hashes: Vec<u64> = (0..256).map(|code| hash64(code, 4)).collect();
+        hashes.sort_unstable();
+        assert_eq!(hashes, (0..256).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_against_naive() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut seq = random_seq(&mut rng, b"ACGT", 500);
+        seq[200] = b'N';
+        // low complexity with many ties
+        seq.extend_from_slice(b"AAAAAAAAAAAAAAAAAAAAACACACACACACACACACACNACGT");
+        for &(w, k) in &[(1, 3), (2, 2), (5, 4), (10, 15), (10, 2)] {
+            for &canonical in &[false, true] {
+                let mins: Vec<_> = minimizers(&seq, w, k, canonical).collect();
+                assert_eq!(mins, naive(&seq, w, k, canonical), "w={} k={}", w, k);
+            }
+        }
+    }
+
+    #[test]
+    fn test_canonical_strand_independent() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let seq = random_seq(&mut rng, b"ACGT", 1000);
+        let rc = dna::revcomp(&seq);
+        let (w, k) = (10, 15);
+        let mut forward: Vec<(usize, u64, bool)> = minimizers(&seq, w, k, true)
+            .map(|m| (m.pos, m.hash, m.forward))
+            .collect();
+        let mut reverse: Vec<(usize, u64, bool)> = minimizers(&rc, w, k, true)
+            .map(|m| (seq.len() - k - m.pos, m.hash, !m.forward))
+            .collect();
+        forward.sort_unstable();
+        reverse.sort_unstable();
+        assert_eq!(forward, reverse);
+    }
+
+    #[test]
+    fn test_index_seeds() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let a = random_seq(&mut rng, b"ACGT", 1000);
+        let b = random_seq(&mut rng, b"ACGT", 1000);
+        let mut index = MinimizerIndex::new(5, 15, true);
+        assert_eq!(index.insert(&a), 0);
+        assert_eq!(index.insert(&b), 1);
+        assert_eq!(index.len(), 2);
+
+        // a read from the reverse strand of b
+        let read = dna::revcomp(&b[300..500]);
+        let seeds = index.seeds(&read, 10);
+        assert!(!seeds.is_empty());
+        for s in seeds {
+            assert_eq!(s.target, 1);
+            assert!(!s.same_strand);
+            assert_eq!(s.target_pos, 300 + 200 - 15 - s.query_pos);
+        }
+    }
+}
@@ -0,0 +1,20 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Operations on biological sequences.
+
+pub mod edit;
+pub mod kmers;
+pub mod mask;
+pub mod minimizers;
+pub mod pcr;
+pub mod primers;
+pub mod random;
+pub mod shuffle;
+pub mod strobemers;
+pub mod syncmers;
+pub mod translation;
+
+pub use self::translation::translate;
@@ -0,0 +1,208 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! In-silico PCR: prediction of the products of primer pairs on the sequences of an indexed
+//! FASTA file, e.g. a reference genome.
+//!
+//! Binding sites are searched on both strands of each sequence, allowing for mismatches and
+//! optionally requiring the 3' ends of the primers to match exactly, see
+//! [`PrimerMatcher`](../primers/struct.PrimerMatcher.html). Candidate binding sites are found
+//! with Myers' approximate matching algorithm. A product is reported for each pair of binding
+//! sites of the forward and the reverse primer that face each other and span at most the
+//! maximal product length.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::io::fasta;
+//! use bio::seq::pcr::{Pcr, PrimerPair};
+//! use std::io;
+//!
+//! const FASTA: &[u8] = b">chr1\nTTTGACATTACAGGGGGGGGGGGGCCATTGTCATTT\n";
+//! const FAI: &[u8] = b"chr1\t36\t6\t36\t37\n";
+//! let mut reader = fasta::IndexedReader::new(io::Cursor::new(FASTA), FAI).unwrap();
+//!
+//! let pairs = [PrimerPair::new("amplicon1", b"GACATTAC", b"ATGACAAT")];
+//! let products = Pcr::new(1, 1000).amplify(&pairs, &mut reader).unwrap();
+//! assert_eq!(products.len(), 1);
+//! let product = &products[0];
+//! assert_eq!(product.contig, "chr1");
+//! assert_eq!((product.amplicon.start, product.amplicon.end), (3, 34));
+//! assert_eq!(product.sequence, b"GACATTACAGGGGGGGGGGGGCCATTGTCAT");
+//! ```
+
+use std::io;
+
+use bio_types::strand::ReqStrand;
+
+use crate::alphabets::dna;
+use crate::io::fasta;
+use crate::seq::primers::{Amplicon, PrimerMatcher};
+
+/// A named pair of primers, given 5' to 3'.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrimerPair {
+    pub name: String,
+    pub forward: Vec<u8>,
+    pub reverse: Vec<u8>,
+}
+
+impl PrimerPair {
+    /// Create a new primer pair.
+    pub fn new(name: &str, forward: &[u8], reverse: &[u8]) -> Self {
+        PrimerPair {
+            name: name.to_owned(),
+            forward: forward.to_vec(),
+            reverse: reverse.to_vec(),
+        }
+    }
+}
+
+/// A predicted PCR product.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Product {
+    /// Name of the primer pair.
+    pub primers: String,
+    /// Name of the sequence the product is found on.
+    pub contig: String,
+    /// Coordinates and binding sites of the product.
+    pub amplicon: Amplicon,
+    /// Sequence of the product, starting with the binding site of the forward primer, i.e.
+    /// reverse complemented if the product is found on the reverse strand. The binding sites
+    /// are given as found in the template, including mismatches to the primers.
+    pub sequence: Vec<u8>,
+}
+
+impl Product {
+    /// Length of the product, including the primers.
+    pub fn len(&self) -> usize {
+        self.amplicon.len()
+    }
+
+    /// Whether the product is empty, which is never the case for reported products.
+    pub fn is_empty(&self) -> bool {
+        self.amplicon.is_empty()
+    }
+}
+
+/// In-silico PCR with a maximal number of mismatches per primer and a maximal product length.
+#[derive(Debug, Clone, Copy)]
+pub struct Pcr {
+    matcher: PrimerMatcher,
+    max_len: usize,
+}
+
+impl Pcr {
+    /// Create a new in-silico PCR allowing up to `max_mismatches` mismatches per primer and
+    /// reporting products of at most `max_len` bases.
+    pub fn new(max_mismatches: usize, max_len: usize) -> Self {
+        Pcr {
+            matcher: PrimerMatcher::new(max_mismatches),
+            max_len,
+        }
+    }
+
+    /// Require the last `n` bases at the 3' end of the primers to match exactly.
+    pub fn three_prime_exact(mut self, n: usize) -> Self {
+        self.matcher = self.matcher.three_prime_exact(n);
+        self
+    }
+
+    /// Predict the products of the given primer pairs on all sequences of the given reader,
+    /// ordered by sequence (as in the index), primer pair, start and end.
+    pub fn amplify<R: io::Read + io::Seek>(
+        &self,
+        pairs: &[PrimerPair],
+        reader: &mut fasta::IndexedReader<R>,
+    ) -> io::Result<Vec<Product>> {
+        let mut products = Vec::new();
+        let mut seq = Vec::new();
+        for contig in reader.index.sequences() {
+            reader.fetch_all(&contig.name)?;
+            reader.read(&mut seq)?;
+            for pair in pairs {
+                for amplicon in
+                    self.matcher
+                        .amplicons(&pair.forward, &pair.reverse, &seq, self.max_len)
+                {
+                    let product = &seq[amplicon.start..amplicon.end];
+                    products.push(Product {
+                        primers: pair.name.clone(),
+                        contig: contig.name.clone(),
+                        amplicon,
+                        sequence: match amplicon.orientation {
+                            ReqStrand::Forward => product.to_vec(),
+                            ReqStrand::Reverse => dna::revcomp(product),
+                        },
+                    });
+                }
+            }
+        }
+        Ok(products)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FASTA: &[u8] = b">chr1\nTTTGACATTACAGGGGGGGGGGGGCCATTGTCATTT\n>chr2 rev\n\
+ACGTAAATGACAATGGCCCCCCCCCCCCTGTAATGTCAAACCC\n";
+    const FAI: &[u8] = b"chr1\t36\t6\t36\t37\nchr2\t43\t53\t43\t44\n";
+
+    fn reader() -> fasta::IndexedReader<io::Cursor<&'static [u8]>> {
+        fasta::IndexedReader::new(io::Cursor::new(FASTA), FAI).unwrap()
+    }
+
+    #[test]
+    fn test_amplify() {
+        let pairs = [
+            PrimerPair::new("exact", b"GACATTAC", b"ATGACAAT"),
+            // a mismatch at the 3' end of the forward primer
+            PrimerPair::new("mismatch", b"GACATTAG", b"ATGACAAT"),
+            PrimerPair::new("none", b"CCCCCCCC", b"AAAAAAAA"),
+        ];
+        let products = Pcr::new(1, 100).amplify(&pairs, &mut reader()).unwrap();
+        let summary: Vec<(&str, &str, usize, usize, ReqStrand)> = products
+            .iter()
+            .map(|p| {
+                (
+                    p.primers.as_str(),
+                    p.contig.as_str(),
+                    p.amplicon.start,
+                    p.amplicon.end,
+                    p.amplicon.orientation,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("exact", "chr1", 3, 34, ReqStrand::Forward),
+                ("mismatch", "chr1", 3, 34, ReqStrand::Forward),
+                ("exact", "chr2", 6, 37, ReqStrand::Reverse),
+                ("mismatch", "chr2", 6, 37, ReqStrand::Reverse),
+            ]
+        );
+        // the product is reported from the forward primer on both strands
+        for product in &products {
+            assert_eq!(product.len(), 31);
+            assert_eq!(product.sequence, b"GACATTACAGGGGGGGGGGGGCCATTGTCAT");
+        }
+        assert_eq!(products[1].amplicon.left.mismatches, 1);
+
+        let products = Pcr::new(1, 100)
+            .three_prime_exact(2)
+            .amplify(&pairs, &mut reader())
+            .unwrap();
+        assert!(products.iter().all(|p| p.primers == "exact"));
+        assert_eq!(products.len(), 2);
+
+        assert!(Pcr::new(1, 30)
+            .amplify(&pairs, &mut reader())
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,334 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Degenerate primers: expansion into concrete variants, binding site search on both
+//! strands, and amplicon prediction for in-silico PCR.
+//!
+//! Primers may contain IUPAC ambiguity codes. A primer position matches a target base if
+//! the primer code denotes the target base (or all bases denoted by an ambiguous target
+//! code), see [`iupac::contains`](../../alphabets/iupac/fn.contains.html). Since mismatches
+//! near the 3' end prevent elongation by the polymerase, the
+//! [`PrimerMatcher`](struct.PrimerMatcher.html) can require the last bases of the primer to
+//! match exactly.
+//!
+//! Coordinates are 0-based and half-open on the forward strand of the target.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::seq::primers::PrimerMatcher;
+//! use bio_types::strand::ReqStrand;
+//!
+//! let target = b"TTTGACRTTACAGGGGGGGGGGGGCCATTGTCATTT";
+//! let forward = b"GACATTAC";
+//! let reverse = b"ATGACAAT"; // binds the reverse strand
+//!
+//! let matcher = PrimerMatcher::new(1).three_prime_exact(3);
+//! let amplicons = matcher.amplicons(forward, reverse, target, 1000);
+//! assert_eq!(amplicons.len(), 1);
+//! assert_eq!((amplicons[0].start, amplicons[0].end), (3, 34));
+//! assert_eq!(amplicons[0].orientation, ReqStrand::Forward);
+//! // the ambiguous R in the target does not count as a match
+//! assert_eq!(amplicons[0].left.mismatches, 1);
+//! ```
+
+use std::ops::Range;
+
+use bio_types::strand::ReqStrand;
+
+use crate::alphabets::{dna, iupac};
+use crate::pattern_matching::myers::MyersBuilder;
+
+/// Expand a degenerate primer into all concrete primers it denotes, failing if there are
+/// more than `max`. See [`iupac::expand_seq`](../../alphabets/iupac/fn.expand_seq.html).
+///
+/// ```
+/// use bio::seq::primers;
+///
+/// let variants = primers::expand(b"ACY", 10).unwrap();
+/// assert_eq!(variants, vec![b"ACC", b"ACT"]);
+/// ```
+pub fn expand(primer: &[u8], max: usize) -> iupac::Result<Vec<Vec<u8>>> {
+    iupac::expand_seq(primer, max)
+}
+
+/// A binding site of a primer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BindingSite {
+    pub start: usize,
+    pub end: usize,
+    /// The strand the primer sequence is found on, i.e. `Reverse` if the reverse
+    /// complement of the primer occurs in the target.
+    pub strand: ReqStrand,
+    pub mismatches: usize,
+}
+
+/// A predicted PCR product.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Amplicon {
+    pub start: usize,
+    pub end: usize,
+    /// Binding site on the forward strand, at the start of the amplicon.
+    pub left: BindingSite,
+    /// Binding site on the reverse strand, at the end of the amplicon.
+    pub right: BindingSite,
+    /// `Forward` if the forward primer binds at the left site, `Reverse` if the reverse
+    /// primer does, i.e. if the product is found on the reverse strand of the target.
+    pub orientation: ReqStrand,
+}
+
+impl Amplicon {
+    /// Length of the amplicon, including the primers.
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Whether the amplicon is empty, which is never the case for reported amplicons.
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+/// Search for primer binding sites allowing a maximal number of mismatches. By default,
+/// mismatches are allowed at any position.
+#[derive(Debug, Clone, Copy)]
+pub struct PrimerMatcher {
+    max_mismatches: usize,
+    three_prime_exact: usize,
+}
+
+impl PrimerMatcher {
+    /// Create a new matcher allowing up to `max_mismatches` mismatches.
+    pub fn new(max_mismatches: usize) -> Self {
+        PrimerMatcher {
+            max_mismatches,
+            three_prime_exact: 0,
+        }
+    }
+
+    /// Require the last `n` bases at the 3' end of the primer to match exactly.
+    pub fn three_prime_exact(mut self, n: usize) -> Self {
+        self.three_prime_exact = n;
+        self
+    }
+
+    /// Number of mismatches of the pattern against a target window of the same length,
+    /// or `None` if the mismatch policy is violated. `exact` are the pattern positions that
+    /// have to match.
+    fn mismatches(&self, pattern: &[u8], window: &[u8], exact: &Range<usize>) -> Option<usize> {
+        let mut mismatches = 0;
+        for (i, (&p, &t)) in pattern.iter().zip(window).enumerate() {
+            if !iupac::contains(p, t) {
+                if exact.contains(&i) || mismatches == self.max_mismatches {
+                    return None;
+                }
+                mismatches += 1;
+            }
+        }
+        Some(mismatches)
+    }
+
+    fn find_strand(
+        &self,
+        pattern: &[u8],
+        target: &[u8],
+        strand: ReqStrand,
+        sites: &mut Vec<BindingSite>,
+    ) {
+        if pattern.is_empty() {
+            return;
+        }
+        let n = self.three_prime_exact.min(pattern.len());
+        // the 3' end of the primer is at the start of its reverse complement
+        let exact = match strand {
+            ReqStrand::Forward => pattern.len() - n..pattern.len(),
+            ReqStrand::Reverse => 0..n,
+        };
+        // Windows with at most k mismatches have an edit distance of at most k, hence
+        // candidates are found with Myers' algorithm and then checked for mismatches.
+        let myers = myers_builder(pattern).build_long_64(pattern);
+        for (end, _) in myers.find_all_end(target, self.max_mismatches) {
+            if end + 1 < pattern.len() {
+                continue;
+            }
+            let start = end + 1 - pattern.len();
+            if let Some(mismatches) = self.mismatches(pattern, &target[start..=end], &exact) {
+                sites.push(BindingSite {
+                    start,
+                    end: end + 1,
+                    strand,
+                    mismatches,
+                });
+            }
+        }
+    }
+
+    /// Find all binding sites of the primer on both strands of the target, ordered by
+    /// strand (forward first) and position.
+    pub fn find(&self, primer: &[u8], target: &[u8]) -> Vec<BindingSite> {
+        let mut sites = Vec::new();
+        self.find_strand(primer, target, ReqStrand::Forward, &mut sites);
+        self.find_strand(
+            &dna::revcomp(primer),
+            target,
+            ReqStrand::Reverse,
+            &mut sites,
+        );
+        sites
+    }
+
+    /// Predict the products of a PCR with the given primer pair, i.e. all pairs of a
+    /// forward strand binding site followed by a reverse strand binding site of the other
+    /// primer, spanning at most `max_len` bases. Amplicons are ordered by start and end.
+    pub fn amplicons(
+        &self,
+        forward: &[u8],
+        reverse: &[u8],
+        target: &[u8],
+        max_len: usize,
+    ) -> Vec<Amplicon> {
+        let (forward_plus, forward_minus) = self.split(forward, target);
+        let (reverse_plus, reverse_minus) = self.split(reverse, target);
+
+        let mut amplicons = Vec::new();
+        let pairs = [
+            (&forward_plus, &reverse_minus, ReqStrand::Forward),
+            (&reverse_plus, &forward_minus, ReqStrand::Reverse),
+        ];
+        for &(lefts, rights, orientation) in pairs.iter() {
+            for left in lefts {
+                for right in rights {
+                    if right.start >= left.start
+                        && right.end > left.end
+                        && right.end - left.start <= max_len
+                    {
+                        amplicons.push(Amplicon {
+                            start: left.start,
+                            end: right.end,
+                            left: *left,
+                            right: *right,
+                            orientation,
+                        });
+                    }
+                }
+            }
+        }
+        amplicons.sort_by_key(|amplicon| (amplicon.start, amplicon.end));
+        amplicons
+    }
+
+    /// Binding sites on the forward and on the reverse strand.
+    fn split(&self, primer: &[u8], target: &[u8]) -> (Vec<BindingSite>, Vec<BindingSite>) {
+        self.find(primer, target)
+            .into_iter()
+            .partition(|site| site.strand == ReqStrand::Forward)
+    }
+}
+
+/// A Myers builder matching each code of the pattern with all (upper- or lowercase) codes it
+/// contains.
+fn myers_builder(pattern: &[u8]) -> MyersBuilder {
+    let codes = b"ACGTURYSWKMBDHVNacgturyswkmbdhvn";
+    let mut builder = MyersBuilder::new();
+    for &p in pattern {
+        builder.ambig(p, codes.iter().filter(|&&t| iupac::contains(p, t)));
+    }
+    builder
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_three_prime_policy() {
+        let target = b"AAAACGTTGCAAAA";
+        // mismatch at the 3' end of the primer
+        let primer = b"ACGTTGG";
+        assert_eq!(PrimerMatcher::new(1).find(primer, target).len(), 1);
+        assert!(PrimerMatcher::new(1)
+            .three_prime_exact(1)
+            .find(primer, target)
+            .is_empty());
+        assert!(PrimerMatcher::new(0).find(primer, target).is_empty());
+    }
+
+    #[test]
+    fn test_reverse_strand() {
+        let target = b"AAAACGTTGCAAAA";
+        // reverse complement of CGTTGC, with a 3' mismatch
+        let primer = b"GCAACC";
+        let sites = PrimerMatcher::new(1).find(primer, target);
+        assert_eq!(
+            sites,
+            vec![BindingSite {
+                start: 4,
+                end: 10,
+                strand: ReqStrand::Reverse,
+                mismatches: 1,
+            }]
+        );
+        assert!(PrimerMatcher::new(1)
+            .three_prime_exact(1)
+            .find(primer, target)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_amplicons_both_orientations() {
+        let target = b"GGACGTAAAAAAAATTGCAGG";
+        let forward = b"ACGT";
+        let reverse = b"TGCA";
+        // both primers are their own reverse complement, so they bind both strands
+        let amplicons = PrimerMatcher::new(0).amplicons(forward, reverse, target, 100);
+        assert_eq!(amplicons.len(), 1);
+        assert_eq!(amplicons[0].len(), 17);
+        assert_eq!(amplicons[0].orientation, ReqStrand::Forward);
+        assert!(PrimerMatcher::new(0)
+            .amplicons(forward, reverse, target, 16)
+            .is_empty());
+
+        let swapped = PrimerMatcher::new(0).amplicons(b"TGCAA", b"ACGTA", target, 100);
+        assert_eq!(swapped.len(), 1);
+        assert_eq!(swapped[0].orientation, ReqStrand::Reverse);
+        assert_eq!((swapped[0].start, swapped[0].end), (2, 19));
+    }
+
+    #[test]
+    fn test_find_exhaustive() {
+        use crate::utils::testing::random_seq;
+        use rand::prelude::*;
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let target = random_seq(&mut rng, b"ACGTacgtN", 2000);
+        for primer in &[&b"ACGTR"[..], b"GGNACT", b"TTAGCACGTTAGCAAT"] {
+            for &max_mismatches in &[0, 1, 2] {
+                let matcher = PrimerMatcher::new(max_mismatches).three_prime_exact(1);
+                // compare with a naive search over all windows
+                let mut expected = Vec::new();
+                for (pattern, strand, exact) in &[
+                    (
+                        primer.to_vec(),
+                        ReqStrand::Forward,
+                        primer.len() - 1..primer.len(),
+                    ),
+                    (dna::revcomp(*primer), ReqStrand::Reverse, 0..1),
+                ] {
+                    for (start, window) in target.windows(primer.len()).enumerate() {
+                        if let Some(mismatches) = matcher.mismatches(pattern, window, exact) {
+                            expected.push(BindingSite {
+                                start,
+                                end: start + primer.len(),
+                                strand: *strand,
+                                mismatches,
+                            });
+                        }
+                    }
+                }
+                assert_eq!(matcher.find(primer, &target), expected);
+            }
+        }
+    }
+}
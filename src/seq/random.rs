@@ -0,0 +1,311 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Generation of random sequences from background models, e.g. for building null
+//! distributions of motif scores.
+//!
+//! * [`Composition`](struct.Composition.html) draws each symbol independently, either
+//!   uniformly or according to given (or estimated) symbol frequencies.
+//! * [`MarkovModel`](struct.MarkovModel.html) is a k-th order Markov chain fitted to an input
+//!   sequence, i.e. each symbol is drawn conditioned on the k preceding symbols. This
+//!   preserves e.g. dinucleotide frequencies (k = 1) like CpG depletion.
+//!
+//! All models take the random number generator as an argument, so that results are
+//! reproducible when using a seeded generator.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::seq::random::{Composition, MarkovModel};
+//! use rand::rngs::StdRng;
+//! use rand::SeedableRng;
+//!
+//! let mut rng = StdRng::seed_from_u64(42);
+//!
+//! let uniform = Composition::uniform(b"ACGT").unwrap();
+//! let seq = uniform.generate(100, &mut rng);
+//! assert_eq!(seq.len(), 100);
+//!
+//! let gc_rich = Composition::new(b"ACGT", &[0.1, 0.4, 0.4, 0.1]).unwrap();
+//! let seq = gc_rich.generate(100, &mut rng);
+//! assert!(seq.iter().all(|a| b"ACGT".contains(a)));
+//!
+//! let model = MarkovModel::fit(b"ACGTACGTACGTACGT", b"ACGT", 2, 0.0).unwrap();
+//! // each 2-mer is always followed by the same symbol in the training sequence
+//! let seq = model.generate(12, &mut rng);
+//! assert!(b"ACGTACGTACGTACGT".windows(12).any(|w| w == &seq[..]));
+//! ```
+
+use std::convert::TryFrom;
+
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
+use thiserror::Error;
+
+use crate::alphabets::{Alphabet, RankTransform};
+
+#[derive(Error, Debug, PartialEq)]
+pub enum Error {
+    #[error("symbol '{}' at position {pos} is not contained in the alphabet", *.symbol as char)]
+    InvalidSymbol { symbol: u8, pos: usize },
+
+    #[error("weights have to be non-negative, finite, not all zero, and one per symbol")]
+    InvalidWeights,
+
+    #[error("sequence of length {len} is too short to fit a Markov model of order {order}")]
+    SequenceTooShort { len: usize, order: usize },
+
+    #[error("Markov model of order {order} is too large for an alphabet of {size} symbols")]
+    OrderTooLarge { order: usize, size: usize },
+}
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Deduplicated and sorted symbols together with their ranks.
+fn ranks(symbols: &[u8]) -> (RankTransform, Vec<u8>) {
+    let ranks = RankTransform::new(&Alphabet::new(symbols));
+    let symbols = ranks.symbols();
+    (ranks, symbols)
+}
+
+/// Rank-transform a sequence, failing on symbols outside of the alphabet.
+fn transform(ranks: &RankTransform, seq: &[u8]) -> Result<Vec<usize>> {
+    seq.iter()
+        .enumerate()
+        .map(|(pos, &symbol)| {
+            ranks
+                .try_get(symbol)
+                .map(|r| r as usize)
+                .ok_or(Error::InvalidSymbol { symbol, pos })
+        })
+        .collect()
+}
+
+fn weighted_index(weights: &[f64]) -> Result<WeightedIndex<f64>> {
+    if weights.iter().any(|w| !w.is_finite()) {
+        return Err(Error::InvalidWeights);
+    }
+    WeightedIndex::new(weights).map_err(|_| Error::InvalidWeights)
+}
+
+/// Background model drawing each symbol independently with fixed probabilities.
+#[derive(Debug, Clone)]
+pub struct Composition {
+    symbols: Vec<u8>,
+    distribution: WeightedIndex<f64>,
+}
+
+impl Composition {
+    /// Create a model drawing the given symbols with probabilities proportional to the
+    /// given weights.
+    pub fn new(symbols: &[u8], weights: &[f64]) -> Result<Self> {
+        if symbols.len() != weights.len() {
+            return Err(Error::InvalidWeights);
+        }
+        Ok(Composition {
+            symbols: symbols.to_owned(),
+            distribution: weighted_index(weights)?,
+        })
+    }
+
+    /// Create a model drawing the given symbols uniformly. Duplicate symbols are ignored.
+    pub fn uniform(symbols: &[u8]) -> Result<Self> {
+        let (_, symbols) = ranks(symbols);
+        Composition::new(&symbols, &vec![1.0; symbols.len()])
+    }
+
+    /// Estimate symbol frequencies of the given alphabet symbols from a sequence, adding
+    /// `pseudocount` to each count.
+    pub fn from_seq(seq: &[u8], symbols: &[u8], pseudocount: f64) -> Result<Self> {
+        let (ranks, symbols) = ranks(symbols);
+        let mut counts = vec![pseudocount; symbols.len()];
+        for r in transform(&ranks, seq)? {
+            counts[r] += 1.0;
+        }
+        Composition::new(&symbols, &counts)
+    }
+
+    /// The symbols of this model.
+    pub fn symbols(&self) -> &[u8] {
+        &self.symbols
+    }
+
+    /// Draw a single symbol.
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> u8 {
+        self.symbols[self.distribution.sample(rng)]
+    }
+
+    /// Generate a random sequence of the given length.
+    pub fn generate<R: Rng + ?Sized>(&self, len: usize, rng: &mut R) -> Vec<u8> {
+        (0..len).map(|_| self.sample(rng)).collect()
+    }
+}
+
+/// Background model drawing each symbol conditioned on the `order` preceding symbols.
+#[derive(Debug, Clone)]
+pub struct MarkovModel {
+    order: usize,
+    symbols: Vec<u8>,
+    /// Distribution of the first `order` symbols, indexed by context.
+    initial: WeightedIndex<f64>,
+    /// Distribution of the next symbol for each context, `None` for contexts that were
+    /// never observed (without pseudocounts).
+    transitions: Vec<Option<WeightedIndex<f64>>>,
+    /// Symbol composition, used for unobserved contexts.
+    fallback: WeightedIndex<f64>,
+}
+
+impl MarkovModel {
+    /// Fit a Markov model of the given order over the given alphabet symbols to a sequence,
+    /// adding `pseudocount` to each k-mer count. Order 0 is equivalent to
+    /// [`Composition::from_seq`](struct.Composition.html#method.from_seq).
+    ///
+    /// Contexts are encoded as base-σ numbers, so that the model needs σ^(order + 1)
+    /// entries for an alphabet of size σ.
+    pub fn fit(seq: &[u8], symbols: &[u8], order: usize, pseudocount: f64) -> Result<Self> {
+        let (ranks, symbols) = ranks(symbols);
+        let sigma = symbols.len();
+        if seq.len() <= order {
+            return Err(Error::SequenceTooShort {
+                len: seq.len(),
+                order,
+            });
+        }
+        let contexts = u32::try_from(order)
+            .ok()
+            .and_then(|order| order.checked_add(1))
+            .and_then(|exp| sigma.checked_pow(exp))
+            .map(|n| n / sigma.max(1))
+            .ok_or(Error::OrderTooLarge { order, size: sigma })?;
+        let text = transform(&ranks, seq)?;
+
+        let mut initial = vec![pseudocount; contexts];
+        let mut counts = vec![pseudocount; contexts * sigma];
+        let mut composition = vec![pseudocount; sigma];
+        let mut context = 0;
+        for (i, &r) in text.iter().enumerate() {
+            composition[r] += 1.0;
+            if i >= order {
+                initial[context] += 1.0;
+                counts[context * sigma + r] += 1.0;
+            }
+            context = (context * sigma + r) % contexts;
+        }
+
+        Ok(MarkovModel {
+            order,
+            initial: weighted_index(&initial)?,
+            transitions: counts
+                .chunks(sigma)
+                .map(|counts| weighted_index(counts).ok())
+                .collect(),
+            fallback: weighted_index(&composition)?,
+            symbols,
+        })
+    }
+
+    /// The order of the model, i.e. the number of preceding symbols each symbol is
+    /// conditioned on.
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    /// The symbols of this model, ordered by rank.
+    pub fn symbols(&self) -> &[u8] {
+        &self.symbols
+    }
+
+    /// Generate a random sequence of the given length. The first `order` symbols are
+    /// drawn from the k-mer frequencies of the training sequence.
+    pub fn generate<R: Rng + ?Sized>(&self, len: usize, rng: &mut R) -> Vec<u8> {
+        let sigma = self.symbols.len();
+        let contexts = self.transitions.len();
+        let mut context = self.initial.sample(rng);
+        let mut text = Vec::with_capacity(len.max(self.order));
+        let mut c = context;
+        for _ in 0..self.order {
+            text.push(c % sigma);
+            c /= sigma;
+        }
+        text.reverse();
+        text.truncate(len);
+
+        while text.len() < len {
+            let r = match &self.transitions[context] {
+                Some(distribution) => distribution.sample(rng),
+                None => self.fallback.sample(rng),
+            };
+            text.push(r);
+            context = (context * sigma + r) % contexts;
+        }
+        text.into_iter().map(|r| self.symbols[r]).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_composition_frequencies() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let model = Composition::new(b"AT", &[3.0, 1.0]).unwrap();
+        let seq = model.generate(10000, &mut rng);
+        let a = seq.iter().filter(|&&a| a == b'A').count() as f64 / 10000.0;
+        assert!((a - 0.75).abs() < 0.02);
+
+        let estimated = Composition::from_seq(b"GGGG", b"ACGT", 0.0).unwrap();
+        assert_eq!(estimated.generate(5, &mut rng), b"GGGGG");
+    }
+
+    #[test]
+    fn test_seeded_reproducibility() {
+        let model = Composition::uniform(b"ACGTACGT").unwrap();
+        assert_eq!(model.symbols(), b"ACGT");
+        let a = model.generate(50, &mut StdRng::seed_from_u64(7));
+        let b = model.generate(50, &mut StdRng::seed_from_u64(7));
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_markov_preserves_transitions() {
+        let mut rng = StdRng::seed_from_u64(1);
+        // C is always followed by G, and G never by C
+        let model = MarkovModel::fit(b"ACGTTCGAACGTCGATACG", b"ACGT", 1, 0.0).unwrap();
+        let seq = model.generate(1000, &mut rng);
+        assert_eq!(seq.len(), 1000);
+        for w in seq.windows(2) {
+            if w[0] == b'C' {
+                assert_eq!(w[1], b'G');
+            }
+            assert_ne!(w, b"GC");
+        }
+        assert_eq!(model.generate(0, &mut rng), b"");
+    }
+
+    #[test]
+    fn test_markov_errors() {
+        assert_eq!(
+            MarkovModel::fit(b"AC", b"ACGT", 2, 1.0).unwrap_err(),
+            Error::SequenceTooShort { len: 2, order: 2 }
+        );
+        assert_eq!(
+            MarkovModel::fit(b"ACNGT", b"ACGT", 1, 1.0).unwrap_err(),
+            Error::InvalidSymbol {
+                symbol: b'N',
+                pos: 2
+            }
+        );
+        assert_eq!(
+            MarkovModel::fit(&[b'A'; 100], b"ACGT", 64, 1.0).unwrap_err(),
+            Error::OrderTooLarge { order: 64, size: 4 }
+        );
+        assert_eq!(
+            Composition::new(b"AC", &[1.0]).unwrap_err(),
+            Error::InvalidWeights
+        );
+    }
+}
@@ -0,0 +1,197 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Shuffling of sequences preserving the counts of all k-lets (substrings of length k), e.g.
+//! for building null models of motif or RNA structure scores that account for the
+//! dinucleotide composition of the input. In contrast to sampling from a
+//! [`MarkovModel`](../random/struct.MarkovModel.html), the k-let counts are preserved exactly.
+//!
+//! The shuffles are drawn uniformly from all sequences with the same k-let counts and the same
+//! first and last (k-1)-let, following the Eulerian path approach of Altschul and Erickson
+//! (Molecular Biology and Evolution, 1985) for dinucleotides, generalized to k-lets by Kandel
+//! et al. (Discrete Applied Mathematics, 1996) and Jiang et al. (uShuffle, BMC Bioinformatics,
+//! 2008): the (k-1)-lets form the vertices of a multigraph with an edge for each k-let. A random
+//! spanning tree towards the last (k-1)-let determines the last edge leaving each vertex, and
+//! the remaining edges are permuted uniformly. The sequence spelled by the Eulerian path
+//! following these edges is the shuffle.
+//!
+//! Symbols are compared exactly, i.e. case-sensitive. The random number generator is taken as
+//! an argument, so that results are reproducible when using a seeded generator.
+//!
+//! Complexity: O(n) for a sequence of length n.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::seq::shuffle;
+//! use rand::rngs::StdRng;
+//! use rand::SeedableRng;
+//!
+//! let mut rng = StdRng::seed_from_u64(42);
+//! let seq = b"ACGCGTTAACGGCATACCGTTAGCAT";
+//! let shuffled = shuffle::dinucleotide_shuffle(seq, &mut rng);
+//!
+//! let mut dinucleotides = seq.windows(2).collect::<Vec<_>>();
+//! let mut shuffled_dinucleotides = shuffled.windows(2).collect::<Vec<_>>();
+//! dinucleotides.sort();
+//! shuffled_dinucleotides.sort();
+//! assert_eq!(dinucleotides, shuffled_dinucleotides);
+//! ```
+
+use std::collections::HashMap;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+/// Shuffle the given sequence, preserving its symbol composition.
+pub fn shuffle<R: Rng + ?Sized>(seq: &[u8], rng: &mut R) -> Vec<u8> {
+    let mut shuffled = seq.to_vec();
+    shuffled.shuffle(rng);
+    shuffled
+}
+
+/// Shuffle the given sequence, preserving its dinucleotide counts (Altschul and Erickson,
+/// 1985).
+pub fn dinucleotide_shuffle<R: Rng + ?Sized>(seq: &[u8], rng: &mut R) -> Vec<u8> {
+    klet_shuffle(seq, 2, rng)
+}
+
+/// Shuffle the given sequence, preserving its k-let counts. Sequences shorter than or as long
+/// as k are returned unchanged.
+///
+/// # Panics
+///
+/// If k is zero.
+pub fn klet_shuffle<R: Rng + ?Sized>(seq: &[u8], k: usize, rng: &mut R) -> Vec<u8> {
+    assert!(k > 0, "k has to be positive");
+    if k == 1 {
+        return shuffle(seq, rng);
+    }
+    if seq.len() <= k {
+        return seq.to_vec();
+    }
+
+    // the vertices, i.e. the distinct (k-1)-lets, and the edges between them
+    let mut ids: HashMap<&[u8], usize> = HashMap::new();
+    let mut vertices: Vec<&[u8]> = Vec::new();
+    let mut id = |vertex| {
+        *ids.entry(vertex).or_insert_with(|| {
+            vertices.push(vertex);
+            vertices.len() - 1
+        })
+    };
+    let path: Vec<usize> = seq.windows(k - 1).map(&mut id).collect();
+    let mut edges: Vec<Vec<usize>> = vec![Vec::new(); vertices.len()];
+    for step in path.windows(2) {
+        edges[step[0]].push(step[1]);
+    }
+    let (first, last) = (path[0], path[path.len() - 1]);
+
+    // choose the last edge leaving each vertex by a random spanning tree towards the last
+    // vertex (Wilson's algorithm), then permute the remaining edges
+    let mut in_tree = vec![false; vertices.len()];
+    let mut last_edge = vec![0; vertices.len()];
+    in_tree[last] = true;
+    for start in 0..vertices.len() {
+        let mut vertex = start;
+        while !in_tree[vertex] {
+            last_edge[vertex] = rng.gen_range(0..edges[vertex].len());
+            vertex = edges[vertex][last_edge[vertex]];
+        }
+        let mut vertex = start;
+        while !in_tree[vertex] {
+            in_tree[vertex] = true;
+            vertex = edges[vertex][last_edge[vertex]];
+        }
+    }
+    for (vertex, targets) in edges.iter_mut().enumerate() {
+        if vertex != last {
+            let n = targets.len();
+            targets.swap(last_edge[vertex], n - 1);
+            targets[..n - 1].shuffle(rng);
+        } else {
+            targets.shuffle(rng);
+        }
+    }
+
+    // spell the Eulerian path
+    let mut shuffled = Vec::with_capacity(seq.len());
+    shuffled.extend_from_slice(vertices[first]);
+    let mut next = vec![0; vertices.len()];
+    let mut vertex = first;
+    while next[vertex] < edges[vertex].len() {
+        let target = edges[vertex][next[vertex]];
+        next[vertex] += 1;
+        shuffled.push(vertices[target][k - 2]);
+        vertex = target;
+    }
+    shuffled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::random_seq;
+    use rand::prelude::*;
+
+    fn klet_counts(seq: &[u8], k: usize) -> HashMap<&[u8], usize> {
+        let mut counts = HashMap::new();
+        for klet in seq.windows(k) {
+            *counts.entry(klet).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    #[test]
+    fn test_klet_shuffle() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let seq = random_seq(&mut rng, b"ACGT", 500);
+        for k in 1..=4 {
+            let shuffled = klet_shuffle(&seq, k, &mut rng);
+            assert_eq!(shuffled.len(), seq.len());
+            assert_ne!(shuffled, seq);
+            assert_eq!(klet_counts(&shuffled, k), klet_counts(&seq, k));
+            assert_eq!(shuffled[..k - 1], seq[..k - 1]);
+            assert_eq!(shuffled[500 - (k - 1)..], seq[500 - (k - 1)..]);
+        }
+        assert_eq!(klet_shuffle(b"ACG", 3, &mut rng), b"ACG");
+        assert_eq!(dinucleotide_shuffle(b"", &mut rng), b"");
+    }
+
+    #[test]
+    fn test_reproducible() {
+        let seq = b"ACGCGTTAACGGCATACCGTTAGCATTTAGCCA";
+        let shuffled = dinucleotide_shuffle(seq, &mut StdRng::seed_from_u64(1));
+        assert_eq!(
+            dinucleotide_shuffle(seq, &mut StdRng::seed_from_u64(1)),
+            shuffled
+        );
+    }
+
+    #[test]
+    fn test_uniform() {
+        // all sequences with the same dinucleotide counts, first and last symbol
+        let expected: [&[u8]; 6] = [
+            b"AACGACGTA",
+            b"AACGTACGA",
+            b"ACGAACGTA",
+            b"ACGACGTAA",
+            b"ACGTAACGA",
+            b"ACGTACGAA",
+        ];
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut counts = HashMap::new();
+        for _ in 0..6000 {
+            *counts
+                .entry(dinucleotide_shuffle(b"AACGACGTA", &mut rng))
+                .or_insert(0) += 1;
+        }
+        assert_eq!(counts.len(), 6);
+        for seq in &expected {
+            let count = counts[*seq];
+            assert!(count > 850 && count < 1150, "{}", count);
+        }
+    }
+}
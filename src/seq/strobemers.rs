@@ -0,0 +1,223 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Minstrobes and randstrobes (Sahlin, Effective sequence similarity detection with
+//! strobemers, Genome Research, 2021).
+//!
+//! A strobemer links `order` k-mers (strobes) of a sequence: the first strobe is the k-mer at
+//! the start position `i`, and the j-th strobe (counting from 0) is chosen from the k-mers
+//! starting in the window `i + w_min + (j - 1) * w_max..=i + j * w_max`. Minstrobes choose
+//! the k-mer with the smallest hash in each window, randstrobes the k-mer minimizing
+//! `(h(previous strobe) + h(k-mer)) mod p` for a large prime `p`, which makes the choice
+//! depend on the previous strobe. Since strobes are spaced by a variable distance, a shared
+//! strobemer between two sequences tolerates insertions and deletions between its strobes.
+//! Windows are truncated at the end of the sequence, and k-mers containing symbols other than
+//! `ACGT` are never chosen. Strobes are hashed with the invertible hash of the minimizers (see
+//! [`minimizers::hash64`](../minimizers/fn.hash64.html)) on the given strand.
+//!
+//! Complexity: O(n * order * (w_max - w_min)) for a sequence of length n.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::seq::strobemers::randstrobes;
+//!
+//! let seq = b"ACGGCTAGCTAGGATCGATCGGATCGTAGCTAGCTAGGCTTAACGG";
+//! let strobemers = randstrobes(seq, 2, 5, 6, 10);
+//! let first = &strobemers[0];
+//! assert_eq!(first.positions[0], 0);
+//! assert!(first.positions[1] >= 6 && first.positions[1] <= 10);
+//!
+//! // an insertion between the strobes preserves some strobemers
+//! let mut mutated = seq.to_vec();
+//! mutated.insert(8, b'T');
+//! let mutated = randstrobes(&mutated, 2, 5, 6, 10);
+//! assert!(strobemers
+//!     .iter()
+//!     .any(|a| mutated.iter().any(|b| a.hash == b.hash)));
+//! ```
+
+use crate::seq::kmers;
+use crate::seq::minimizers::hash64;
+
+/// Mersenne prime used as modulus for choosing randstrobes.
+const PRIME: u128 = (1 << 61) - 1;
+
+/// A strobemer, consisting of the positions of its strobes and a hash of them.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Strobemer {
+    /// Start positions of the strobes in the sequence.
+    pub positions: Vec<usize>,
+    /// Hash of the sequence of strobes.
+    pub hash: u64,
+}
+
+/// The method for choosing strobes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Method {
+    Minstrobes,
+    Randstrobes,
+}
+
+/// Compute the minstrobes of the given order, with strobes of length `k` and window offsets
+/// `w_min..=w_max`.
+///
+/// # Panics
+///
+/// If `order` is smaller than 2, `k` is zero or larger than
+/// [`kmers::MAX_K`](../kmers/constant.MAX_K.html), `w_min` is zero or `w_min > w_max`.
+pub fn minstrobes(
+    seq: &[u8],
+    order: usize,
+    k: usize,
+    w_min: usize,
+    w_max: usize,
+) -> Vec<Strobemer> {
+    strobemers(seq, order, k, w_min, w_max, Method::Minstrobes)
+}
+
+/// Compute the randstrobes of the given order, with strobes of length `k` and window offsets
+/// `w_min..=w_max`.
+///
+/// # Panics
+///
+/// If `order` is smaller than 2, `k` is zero or larger than
+/// [`kmers::MAX_K`](../kmers/constant.MAX_K.html), `w_min` is zero or `w_min > w_max`.
+pub fn randstrobes(
+    seq: &[u8],
+    order: usize,
+    k: usize,
+    w_min: usize,
+    w_max: usize,
+) -> Vec<Strobemer> {
+    strobemers(seq, order, k, w_min, w_max, Method::Randstrobes)
+}
+
+fn strobemers(
+    seq: &[u8],
+    order: usize,
+    k: usize,
+    w_min: usize,
+    w_max: usize,
+    method: Method,
+) -> Vec<Strobemer> {
+    assert!(order >= 2, "order must be at least 2");
+    assert!(
+        w_min > 0 && w_min <= w_max,
+        "window must satisfy 0 < w_min <= w_max"
+    );
+
+    let mut hashes: Vec<Option<u64>> = vec![None; (seq.len() + 1).saturating_sub(k)];
+    for kmer in kmers::encoded(seq, k) {
+        hashes[kmer.pos] = Some(hash64(kmer.forward, k));
+    }
+
+    let mut strobemers = Vec::new();
+    'start: for (i, &first) in hashes.iter().enumerate() {
+        let mut prev = match first {
+            Some(hash) => hash,
+            None => continue,
+        };
+        let mut positions = vec![i];
+        let mut hash = prev;
+        for j in 1..order {
+            let start = i + w_min + (j - 1) * w_max;
+            let end = (i + j * w_max + 1).min(hashes.len());
+            // leftmost k-mer with minimal key
+            let chosen = (start..end)
+                .filter_map(|pos| {
+                    let h = hashes[pos]?;
+                    let key = match method {
+                        Method::Minstrobes => h as u128,
+                        Method::Randstrobes => (prev as u128 + h as u128) % PRIME,
+                    };
+                    Some((key, pos, h))
+                })
+                .min();
+            match chosen {
+                Some((_, pos, h)) => {
+                    positions.push(pos);
+                    hash = hash.rotate_left(7) ^ h;
+                    prev = h;
+                }
+                None => continue 'start,
+            }
+        }
+        strobemers.push(Strobemer { positions, hash });
+    }
+    strobemers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::random_seq;
+    use rand::prelude::*;
+
+    fn strobe_hash(seq: &[u8], k: usize, pos: usize) -> u64 {
+        hash64(kmers::encode(&seq[pos..pos + k]).unwrap(), k)
+    }
+
+    #[test]
+    fn test_strobes_in_windows() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let seq = random_seq(&mut rng, b"ACGT", 300);
+        let (k, w_min, w_max) = (7, 8, 15);
+        for &order in &[2, 3] {
+            let minstrobes = minstrobes(&seq, order, k, w_min, w_max);
+            let randstrobes = randstrobes(&seq, order, k, w_min, w_max);
+            assert_eq!(
+                minstrobes.len(),
+                seq.len() - k - w_min - (order - 2) * w_max + 1
+            );
+            assert_eq!(randstrobes.len(), minstrobes.len());
+            for strobemer in minstrobes.iter().chain(&randstrobes) {
+                let i = strobemer.positions[0];
+                for (j, &pos) in strobemer.positions.iter().enumerate().skip(1) {
+                    assert!(pos >= i + w_min + (j - 1) * w_max && pos <= i + j * w_max);
+                }
+            }
+            // minstrobes choose the minimal hash in the window
+            for strobemer in &minstrobes {
+                let i = strobemer.positions[0];
+                let end = (i + w_max + 1).min(seq.len() - k + 1);
+                let min = (i + w_min..end)
+                    .map(|pos| strobe_hash(&seq, k, pos))
+                    .min()
+                    .unwrap();
+                assert_eq!(strobe_hash(&seq, k, strobemer.positions[1]), min);
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_kmers() {
+        let seq = b"ACGTNACGTACGGTCA";
+        let strobemers = randstrobes(seq, 2, 3, 2, 4);
+        for strobemer in strobemers {
+            for pos in strobemer.positions {
+                assert!(kmers::encode(&seq[pos..pos + 3]).is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn test_context_independent() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let seq = random_seq(&mut rng, b"ACGT", 300);
+        let (k, w_min, w_max) = (7, 8, 15);
+        let full = randstrobes(&seq, 3, k, w_min, w_max);
+        let part = randstrobes(&seq[50..], 3, k, w_min, w_max);
+        // strobemers whose windows are not truncated only depend on the windows
+        for (a, b) in full[50..200].iter().zip(&part[..150]) {
+            assert_eq!(a.hash, b.hash);
+            assert!(a
+                .positions
+                .iter()
+                .zip(&b.positions)
+                .all(|(x, y)| *x == y + 50));
+        }
+    }
+}
@@ -0,0 +1,197 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Open and closed syncmers (Edgar, Syncmers are more sensitive than minimizers for
+//! selecting conserved k-mers in biological sequences, PeerJ, 2021).
+//!
+//! Whether a k-mer is a syncmer depends only on the k-mer itself, not on its context: each
+//! k-mer is split into its `k - s + 1` s-mers, and it is selected if the s-mer with the
+//! smallest hash is at a given offset (open syncmers) or at the first or last offset (closed
+//! syncmers). As opposed to minimizers, a mutation thus only affects the selection of the
+//! k-mers overlapping it. s-mers are hashed with the invertible hash of the minimizers (see
+//! [`minimizers::hash64`](../minimizers/fn.hash64.html)), and ties are broken by taking the
+//! leftmost s-mer.
+//!
+//! Complexity: O(n) amortized for a sequence of length n.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::seq::syncmers::{syncmers, Kind};
+//!
+//! let seq = b"ACGGCTAGCTAGGATCGATCGGATCGTAGCTAGCTAGGCTTAACGG";
+//! let closed: Vec<usize> = syncmers(seq, 9, 4, Kind::Closed, true)
+//!     .map(|kmer| kmer.pos)
+//!     .collect();
+//! // the selection of a k-mer does not depend on its context
+//! let sub: Vec<usize> = syncmers(&seq[10..30], 9, 4, Kind::Closed, true)
+//!     .map(|kmer| kmer.pos + 10)
+//!     .collect();
+//! assert!(sub.iter().all(|pos| closed.contains(pos)));
+//! ```
+
+use std::collections::VecDeque;
+use std::iter::Peekable;
+
+use crate::seq::kmers::{self, EncodedKmers, Kmer};
+use crate::seq::minimizers::hash64;
+
+/// The kind of syncmers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kind {
+    /// The smallest s-mer is at the given offset within the k-mer.
+    Open(usize),
+    /// The smallest s-mer is at the first or last offset within the k-mer.
+    Closed,
+}
+
+/// Iterator over the syncmers of a sequence, see [`syncmers`](fn.syncmers.html).
+#[derive(Debug, Clone)]
+pub struct Syncmers<'a> {
+    kmers: EncodedKmers<'a>,
+    smers: Peekable<EncodedKmers<'a>>,
+    k: usize,
+    s: usize,
+    kind: Kind,
+    canonical: bool,
+    /// Positions and hashes of the s-mers with increasing hashes.
+    candidates: VecDeque<(usize, u64)>,
+}
+
+impl<'a> Iterator for Syncmers<'a> {
+    type Item = Kmer;
+
+    fn next(&mut self) -> Option<Kmer> {
+        loop {
+            let kmer = self.kmers.next()?;
+            let last = kmer.pos + self.k - self.s;
+            while let Some(smer) = self.smers.next_if(|smer| smer.pos <= last) {
+                let code = if self.canonical {
+                    smer.canonical()
+                } else {
+                    smer.forward
+                };
+                let hash = hash64(code, self.s);
+                // on ties, the leftmost candidate survives
+                while matches!(self.candidates.back(), Some(&(_, h)) if h > hash) {
+                    self.candidates.pop_back();
+                }
+                self.candidates.push_back((smer.pos, hash));
+            }
+            while self.candidates[0].0 < kmer.pos {
+                self.candidates.pop_front();
+            }
+
+            let offset = self.candidates[0].0 - kmer.pos;
+            let selected = match self.kind {
+                Kind::Open(t) => offset == t,
+                Kind::Closed => offset == 0 || offset == self.k - self.s,
+            };
+            if selected {
+                return Some(kmer);
+            }
+        }
+    }
+}
+
+/// Iterate over the syncmers among the k-mers of a sequence, with s-mers of length `s`.
+/// If `canonical` is true, s-mers are hashed in their canonical form, such that (up to ties)
+/// the same k-mers are selected on both strands of the sequence. k-mers containing symbols
+/// other than `ACGT` are skipped.
+///
+/// # Panics
+///
+/// If `s` is zero or not smaller than `k`, `k` is larger than
+/// [`kmers::MAX_K`](../kmers/constant.MAX_K.html), or the offset of open syncmers is larger
+/// than `k - s`.
+pub fn syncmers(seq: &[u8], k: usize, s: usize, kind: Kind, canonical: bool) -> Syncmers<'_> {
+    assert!(s > 0 && s < k, "s must be in 1..k");
+    if let Kind::Open(t) = kind {
+        assert!(t <= k - s, "offset must not exceed k - s");
+    }
+    Syncmers {
+        kmers: kmers::encoded(seq, k),
+        smers: kmers::encoded(seq, s).peekable(),
+        k,
+        s,
+        kind,
+        canonical,
+        candidates: VecDeque::with_capacity(k - s + 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabets::dna;
+    use crate::utils::testing::random_seq;
+    use rand::prelude::*;
+
+    fn naive(seq: &[u8], k: usize, s: usize, kind: Kind, canonical: bool) -> Vec<usize> {
+        (0..(seq.len() + 1).saturating_sub(k))
+            .filter(|&pos| {
+                let kmer = &seq[pos..pos + k];
+                if kmers::encode(kmer).is_none() {
+                    return false;
+                }
+                let hashes: Vec<u64> = kmer
+                    .windows(s)
+                    .map(|smer| {
+                        let code = kmers::encode(smer).unwrap();
+                        let code = if canonical {
+                            kmers::canonical(code, s)
+                        } else {
+                            code
+                        };
+                        hash64(code, s)
+                    })
+                    .collect();
+                let min = *hashes.iter().min().unwrap();
+                let offset = hashes.iter().position(|&h| h == min).unwrap();
+                match kind {
+                    Kind::Open(t) => offset == t,
+                    Kind::Closed => offset == 0 || offset == k - s,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_against_naive() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut seq = random_seq(&mut rng, b"ACGT", 500);
+        seq[100] = b'N';
+        seq[105] = b'N';
+        seq.extend_from_slice(b"AAAAAAAAAAAAAAAACACACACACACACAC");
+        for &(k, s) in &[(5, 2), (9, 4), (15, 5), (31, 11), (2, 1)] {
+            for &kind in &[Kind::Closed, Kind::Open(0), Kind::Open((k - s) / 2)] {
+                for &canonical in &[false, true] {
+                    let positions: Vec<usize> = syncmers(&seq, k, s, kind, canonical)
+                        .map(|kmer| kmer.pos)
+                        .collect();
+                    assert_eq!(positions, naive(&seq, k, s, kind, canonical));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_closed_canonical_strand_independent() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let seq = random_seq(&mut rng, b"ACGT", 1000);
+        let (k, s) = (21, 11);
+        let forward: Vec<usize> = syncmers(&seq, k, s, Kind::Closed, true)
+            .map(|kmer| kmer.pos)
+            .collect();
+        let mut reverse: Vec<usize> = syncmers(&dna::revcomp(&seq), k, s, Kind::Closed, true)
+            .map(|kmer| seq.len() - k - kmer.pos)
+            .collect();
+        reverse.reverse();
+        assert_eq!(forward, reverse);
+        // closed syncmers have a density of about 2 / (k - s + 1)
+        let density = forward.len() as f64 / (seq.len() - k + 1) as f64;
+        assert!((density - 2.0 / 11.0).abs() < 0.05);
+    }
+}
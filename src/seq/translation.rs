@@ -0,0 +1,625 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Translation of nucleotide sequences into protein sequences, supporting all
+//! [NCBI genetic codes](https://www.ncbi.nlm.nih.gov/Taxonomy/Utils/wprintgc.cgi).
+//!
+//! DNA and RNA input in upper- or lowercase is accepted. Codons containing IUPAC ambiguity
+//! codes are translated into the amino acid they unambiguously encode (e.g. `GCN` into `A`),
+//! or into `X` otherwise.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::seq::translate;
+//! use bio::seq::translation::{Frame, GeneticCode, Stop, Translator};
+//!
+//! let code = GeneticCode::standard();
+//! assert_eq!(translate(b"ATGGCNTGGTAAGG", code, Frame::Plus1).unwrap(), b"MAW*");
+//!
+//! // vertebrate mitochondria read TGA as tryptophan
+//! let mito = GeneticCode::ncbi(2).unwrap();
+//! assert_eq!(translate(b"ATGTGA", mito, Frame::Plus1).unwrap(), b"MW");
+//!
+//! // stop at the first stop codon
+//! let translator = Translator::new(code).stop(Stop::Truncate);
+//! assert_eq!(translator.translate(b"ATGTAAATG", Frame::Plus1).unwrap(), b"M");
+//!
+//! // translate all six reading frames
+//! for result in translator.six_frames(b"ATGGCCATTGTAATGGGCC") {
+//!     let (frame, protein) = result.unwrap();
+//!     println!("{:?}: {}", frame, String::from_utf8(protein).unwrap());
+//! }
+//! ```
+
+use thiserror::Error;
+
+use crate::alphabets::{dna, iupac};
+
+#[derive(Error, Debug, PartialEq)]
+pub enum Error {
+    #[error("stop codon at position {pos}")]
+    StopCodon { pos: usize },
+
+    #[error("incomplete codon of length {len} at the end of the sequence")]
+    PartialCodon { len: usize },
+}
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Definition of an NCBI genetic code: id, name, amino acids and start codons.
+/// Amino acids are given for all codons in the order `TTT`, `TTC`, `TTA`, `TTG`, `TCT`, ...,
+/// `GGG`.
+type CodeDefinition = (
+    u8,
+    &'static str,
+    &'static [u8; 64],
+    &'static [&'static [u8; 3]],
+);
+
+const NCBI_CODES: &[CodeDefinition] = &[
+    (
+        1,
+        "Standard",
+        b"FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        &[b"TTG", b"CTG", b"ATG"],
+    ),
+    (
+        2,
+        "Vertebrate Mitochondrial",
+        b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSS**VVVVAAAADDEEGGGG",
+        &[b"ATT", b"ATC", b"ATA", b"ATG", b"GTG"],
+    ),
+    (
+        3,
+        "Yeast Mitochondrial",
+        b"FFLLSSSSYY**CCWWTTTTPPPPHHQQRRRRIIMMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        &[b"ATA", b"ATG"],
+    ),
+    (
+        4,
+        "Mold, Protozoan, and Coelenterate Mitochondrial and Mycoplasma/Spiroplasma",
+        b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        &[
+            b"TTA", b"TTG", b"CTG", b"ATT", b"ATC", b"ATA", b"ATG", b"GTG",
+        ],
+    ),
+    (
+        5,
+        "Invertebrate Mitochondrial",
+        b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSSSSVVVVAAAADDEEGGGG",
+        &[b"TTG", b"ATT", b"ATC", b"ATA", b"ATG", b"GTG"],
+    ),
+    (
+        6,
+        "Ciliate, Dasycladacean and Hexamita Nuclear",
+        b"FFLLSSSSYYQQCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        &[b"ATG"],
+    ),
+    (
+        9,
+        "Echinoderm and Flatworm Mitochondrial",
+        b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNNKSSSSVVVVAAAADDEEGGGG",
+        &[b"ATG", b"GTG"],
+    ),
+    (
+        10,
+        "Euplotid Nuclear",
+        b"FFLLSSSSYY**CCCWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        &[b"ATG"],
+    ),
+    (
+        11,
+        "Bacterial, Archaeal and Plant Plastid",
+        b"FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        &[b"TTG", b"CTG", b"ATT", b"ATC", b"ATA", b"ATG", b"GTG"],
+    ),
+    (
+        12,
+        "Alternative Yeast Nuclear",
+        b"FFLLSSSSYY**CC*WLLLSPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        &[b"CTG", b"ATG"],
+    ),
+    (
+        13,
+        "Ascidian Mitochondrial",
+        b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSSGGVVVVAAAADDEEGGGG",
+        &[b"TTG", b"ATA", b"ATG", b"GTG"],
+    ),
+    (
+        14,
+        "Alternative Flatworm Mitochondrial",
+        b"FFLLSSSSYYY*CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNNKSSSSVVVVAAAADDEEGGGG",
+        &[b"ATG"],
+    ),
+    (
+        16,
+        "Chlorophycean Mitochondrial",
+        b"FFLLSSSSYY*LCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        &[b"ATG"],
+    ),
+    (
+        21,
+        "Trematode Mitochondrial",
+        b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNNKSSSSVVVVAAAADDEEGGGG",
+        &[b"ATG", b"GTG"],
+    ),
+    (
+        22,
+        "Scenedesmus obliquus Mitochondrial",
+        b"FFLLSS*SYY*LCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        &[b"ATG"],
+    ),
+    (
+        23,
+        "Thraustochytrium Mitochondrial",
+        b"FF*LSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        &[b"ATT", b"ATG", b"GTG"],
+    ),
+    (
+        24,
+        "Rhabdopleuridae Mitochondrial",
+        b"FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSSKVVVVAAAADDEEGGGG",
+        &[b"TTG", b"CTG", b"ATG", b"GTG"],
+    ),
+    (
+        25,
+        "Candidate Division SR1 and Gracilibacteria",
+        b"FFLLSSSSYY**CCGWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        &[b"TTG", b"ATG", b"GTG"],
+    ),
+    (
+        26,
+        "Pachysolen tannophilus Nuclear",
+        b"FFLLSSSSYY**CC*WLLLAPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        &[b"CTG", b"ATG"],
+    ),
+    (
+        27,
+        "Karyorelict Nuclear",
+        b"FFLLSSSSYYQQCCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        &[b"ATG"],
+    ),
+    (
+        28,
+        "Condylostoma Nuclear",
+        b"FFLLSSSSYYQQCCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        &[b"ATG"],
+    ),
+    (
+        29,
+        "Mesodinium Nuclear",
+        b"FFLLSSSSYYYYCC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        &[b"ATG"],
+    ),
+    (
+        30,
+        "Peritrich Nuclear",
+        b"FFLLSSSSYYEECC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        &[b"ATG"],
+    ),
+    (
+        31,
+        "Blastocrithidia Nuclear",
+        b"FFLLSSSSYYEECCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG",
+        &[b"ATG"],
+    ),
+    (
+        33,
+        "Cephalodiscidae Mitochondrial",
+        b"FFLLSSSSYYY*CCWWLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSSKVVVVAAAADDEEGGGG",
+        &[b"TTG", b"CTG", b"ATG", b"GTG"],
+    ),
+];
+
+/// Index of the nucleotide with the given IUPAC bit mask (`A` = 1, `C` = 2, `G` = 4,
+/// `T` = 8) in the NCBI codon order `T`, `C`, `A`, `G`.
+fn ncbi_index(mask: u8) -> usize {
+    match mask {
+        8 => 0,
+        2 => 1,
+        1 => 2,
+        _ => 3,
+    }
+}
+
+/// A genetic code, mapping codons to amino acids.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneticCode {
+    id: u8,
+    name: &'static str,
+    amino_acids: [u8; 64],
+    starts: [bool; 64],
+}
+
+lazy_static! {
+    static ref GENETIC_CODES: Vec<GeneticCode> = NCBI_CODES
+        .iter()
+        .map(|&(id, name, amino_acids, start_codons)| {
+            let mut starts = [false; 64];
+            for codon in start_codons {
+                starts[GeneticCode::index(&codon[..]).unwrap()] = true;
+            }
+            GeneticCode {
+                id,
+                name,
+                amino_acids: *amino_acids,
+                starts,
+            }
+        })
+        .collect();
+}
+
+impl GeneticCode {
+    /// The NCBI genetic code (translation table) with the given id, e.g. 11 for the
+    /// bacterial, archaeal and plant plastid code.
+    ///
+    /// Tables 27, 28 and 31 contain codons that encode either an amino acid or stop,
+    /// depending on context. They are translated as amino acids here.
+    pub fn ncbi(id: u8) -> Option<&'static GeneticCode> {
+        GENETIC_CODES.iter().find(|code| code.id == id)
+    }
+
+    /// The standard genetic code (NCBI table 1).
+    pub fn standard() -> &'static GeneticCode {
+        &GENETIC_CODES[0]
+    }
+
+    /// All NCBI genetic codes, ordered by id.
+    pub fn all() -> &'static [GeneticCode] {
+        &GENETIC_CODES
+    }
+
+    /// NCBI id of the code.
+    pub fn id(&self) -> u8 {
+        self.id
+    }
+
+    /// Name of the code.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Index of an unambiguous codon in the NCBI codon order.
+    fn index(codon: &[u8]) -> Option<usize> {
+        codon.iter().try_fold(0, |index, &base| {
+            let mask = iupac::bits(base)?;
+            if mask.count_ones() == 1 {
+                Some(index * 4 + ncbi_index(mask))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Translate a codon into an amino acid, `*` for stop codons, or `X` if the codon is
+    /// ambiguous or invalid.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::seq::translation::GeneticCode;
+    ///
+    /// let code = GeneticCode::standard();
+    /// assert_eq!(code.translate_codon(b"AUG"), b'M');
+    /// assert_eq!(code.translate_codon(b"tga"), b'*');
+    /// assert_eq!(code.translate_codon(b"CTN"), b'L');
+    /// assert_eq!(code.translate_codon(b"NTG"), b'X');
+    /// ```
+    pub fn translate_codon(&self, codon: &[u8]) -> u8 {
+        if codon.len() != 3 {
+            return b'X';
+        }
+        if let Some(index) = GeneticCode::index(codon) {
+            return self.amino_acids[index];
+        }
+        // resolve ambiguity codes: the amino acid is known if all expansions agree
+        let mut amino_acid = None;
+        let expansions = match iupac::expand_seq(codon, 64) {
+            Ok(expansions) => expansions,
+            Err(_) => return b'X',
+        };
+        for expansion in expansions {
+            let a = self.amino_acids[GeneticCode::index(&expansion).unwrap()];
+            match amino_acid {
+                Some(b) if a != b => return b'X',
+                _ => amino_acid = Some(a),
+            }
+        }
+        amino_acid.unwrap_or(b'X')
+    }
+
+    /// Whether the given codon is a start codon of this code.
+    pub fn is_start(&self, codon: &[u8]) -> bool {
+        codon.len() == 3 && GeneticCode::index(codon).is_some_and(|index| self.starts[index])
+    }
+
+    /// Whether the given codon is a stop codon of this code.
+    pub fn is_stop(&self, codon: &[u8]) -> bool {
+        self.translate_codon(codon) == b'*'
+    }
+
+    /// All start codons of this code, in NCBI codon order.
+    pub fn start_codons(&self) -> Vec<[u8; 3]> {
+        self.codons(|index| self.starts[index])
+    }
+
+    /// All stop codons of this code, in NCBI codon order.
+    pub fn stop_codons(&self) -> Vec<[u8; 3]> {
+        self.codons(|index| self.amino_acids[index] == b'*')
+    }
+
+    fn codons<F: Fn(usize) -> bool>(&self, select: F) -> Vec<[u8; 3]> {
+        const BASES: &[u8; 4] = b"TCAG";
+        (0..64)
+            .filter(|&index| select(index))
+            .map(|index| [BASES[index / 16], BASES[index / 4 % 4], BASES[index % 4]])
+            .collect()
+    }
+}
+
+/// A reading frame. Frames on the reverse strand are read on the reverse complement,
+/// i.e. `Minus1` starts with the last base of the sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Frame {
+    Plus1,
+    Plus2,
+    Plus3,
+    Minus1,
+    Minus2,
+    Minus3,
+}
+
+impl Frame {
+    /// All six frames.
+    pub const ALL: [Frame; 6] = [
+        Frame::Plus1,
+        Frame::Plus2,
+        Frame::Plus3,
+        Frame::Minus1,
+        Frame::Minus2,
+        Frame::Minus3,
+    ];
+
+    /// Offset of the first codon on the read strand.
+    pub fn offset(self) -> usize {
+        match self {
+            Frame::Plus1 | Frame::Minus1 => 0,
+            Frame::Plus2 | Frame::Minus2 => 1,
+            Frame::Plus3 | Frame::Minus3 => 2,
+        }
+    }
+
+    /// Whether the frame is on the reverse strand.
+    pub fn is_reverse(self) -> bool {
+        matches!(self, Frame::Minus1 | Frame::Minus2 | Frame::Minus3)
+    }
+}
+
+/// Handling of stop codons.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+    /// Translate stop codons into `*`.
+    Include,
+    /// End the translation before the first stop codon.
+    Truncate,
+    /// Report an error for any stop codon.
+    Error,
+}
+
+/// Handling of an incomplete codon at the end of the sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartialCodon {
+    /// Ignore the incomplete codon.
+    Discard,
+    /// Translate the incomplete codon into `X`.
+    Unknown,
+    /// Report an error.
+    Error,
+}
+
+/// A configurable translator. By default, stop codons are translated into `*` and
+/// incomplete codons are discarded.
+#[derive(Debug, Clone, Copy)]
+pub struct Translator<'a> {
+    code: &'a GeneticCode,
+    stop: Stop,
+    partial_codon: PartialCodon,
+}
+
+impl<'a> Translator<'a> {
+    /// Create a new translator for the given genetic code.
+    pub fn new(code: &'a GeneticCode) -> Self {
+        Translator {
+            code,
+            stop: Stop::Include,
+            partial_codon: PartialCodon::Discard,
+        }
+    }
+
+    /// Set the handling of stop codons.
+    pub fn stop(mut self, stop: Stop) -> Self {
+        self.stop = stop;
+        self
+    }
+
+    /// Set the handling of an incomplete codon at the end of the sequence.
+    pub fn partial_codon(mut self, partial_codon: PartialCodon) -> Self {
+        self.partial_codon = partial_codon;
+        self
+    }
+
+    /// Translate the given sequence in the given frame.
+    ///
+    /// Positions in errors refer to the read strand, i.e. to the reverse complement for
+    /// frames on the reverse strand.
+    pub fn translate(&self, seq: &[u8], frame: Frame) -> Result<Vec<u8>> {
+        if frame.is_reverse() {
+            self.translate_forward(&dna::revcomp(seq), frame.offset())
+        } else {
+            self.translate_forward(seq, frame.offset())
+        }
+    }
+
+    fn translate_forward(&self, seq: &[u8], offset: usize) -> Result<Vec<u8>> {
+        let seq = seq.get(offset..).unwrap_or(&[]);
+        let mut protein = Vec::with_capacity(seq.len() / 3 + 1);
+        let codons = seq.chunks_exact(3);
+        let rest = codons.remainder();
+        for (i, codon) in codons.enumerate() {
+            let amino_acid = self.code.translate_codon(codon);
+            if amino_acid == b'*' {
+                match self.stop {
+                    Stop::Include => (),
+                    Stop::Truncate => return Ok(protein),
+                    Stop::Error => {
+                        return Err(Error::StopCodon {
+                            pos: offset + i * 3,
+                        })
+                    }
+                }
+            }
+            protein.push(amino_acid);
+        }
+        if !rest.is_empty() {
+            match self.partial_codon {
+                PartialCodon::Discard => (),
+                PartialCodon::Unknown => protein.push(b'X'),
+                PartialCodon::Error => return Err(Error::PartialCodon { len: rest.len() }),
+            }
+        }
+        Ok(protein)
+    }
+
+    /// Iterate over the translations of the given sequence in all six frames, in the order
+    /// of [`Frame::ALL`](enum.Frame.html#associatedconstant.ALL).
+    pub fn six_frames(&self, seq: &[u8]) -> SixFrames<'a> {
+        SixFrames {
+            translator: *self,
+            forward: seq.to_vec(),
+            reverse: dna::revcomp(seq),
+            frames: Frame::ALL.iter(),
+        }
+    }
+}
+
+/// Iterator over the translations in all six frames, see
+/// [`Translator::six_frames`](struct.Translator.html#method.six_frames).
+#[derive(Debug)]
+pub struct SixFrames<'a> {
+    translator: Translator<'a>,
+    forward: Vec<u8>,
+    reverse: Vec<u8>,
+    frames: std::slice::Iter<'static, Frame>,
+}
+
+impl<'a> Iterator for SixFrames<'a> {
+    type Item = Result<(Frame, Vec<u8>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let frame = *self.frames.next()?;
+        let seq = if frame.is_reverse() {
+            &self.reverse
+        } else {
+            &self.forward
+        };
+        Some(
+            self.translator
+                .translate_forward(seq, frame.offset())
+                .map(|protein| (frame, protein)),
+        )
+    }
+}
+
+/// Translate the given sequence in the given frame, translating stop codons into `*` and
+/// discarding an incomplete codon at the end. Use a [`Translator`](struct.Translator.html)
+/// for other behavior.
+pub fn translate(seq: &[u8], code: &GeneticCode, frame: Frame) -> Result<Vec<u8>> {
+    Translator::new(code).translate(seq, frame)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tables() {
+        let ids: Vec<u8> = GeneticCode::all().iter().map(|code| code.id()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                1, 2, 3, 4, 5, 6, 9, 10, 11, 12, 13, 14, 16, 21, 22, 23, 24, 25, 26, 27, 28, 29,
+                30, 31, 33
+            ]
+        );
+        assert!(GeneticCode::ncbi(7).is_none());
+        let standard = GeneticCode::standard();
+        assert_eq!(standard.stop_codons(), vec![*b"TAA", *b"TAG", *b"TGA"]);
+        assert_eq!(standard.start_codons(), vec![*b"TTG", *b"CTG", *b"ATG"]);
+        let mito = GeneticCode::ncbi(2).unwrap();
+        assert_eq!(mito.stop_codons(), vec![*b"TAA", *b"TAG", *b"AGA", *b"AGG"]);
+        assert!(mito.is_start(b"ATA"));
+        assert!(!standard.is_start(b"ATA"));
+    }
+
+    #[test]
+    fn test_standard_code() {
+        let code = GeneticCode::standard();
+        let seq = b"TTTCTTATTGTTTCTCCTACTGCTTATCATAATGATTGTCGTAGTGGTTGGATGCAGAAACGA";
+        assert_eq!(
+            translate(seq, code, Frame::Plus1).unwrap(),
+            b"FLIVSPTAYHNDCRSGWMQKR".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_frames() {
+        let seq = b"AATGGCCTAA";
+        let code = GeneticCode::standard();
+        assert_eq!(translate(seq, code, Frame::Plus1).unwrap(), b"NGL");
+        assert_eq!(translate(seq, code, Frame::Plus2).unwrap(), b"MA*");
+        assert_eq!(translate(seq, code, Frame::Plus3).unwrap(), b"WP");
+        // reverse complement: TTAGGCCATT
+        assert_eq!(translate(seq, code, Frame::Minus1).unwrap(), b"LGH");
+        assert_eq!(translate(seq, code, Frame::Minus2).unwrap(), b"*AI");
+        assert_eq!(translate(seq, code, Frame::Minus3).unwrap(), b"RP");
+        assert_eq!(translate(b"A", code, Frame::Plus3).unwrap(), b"");
+
+        let frames: Vec<_> = Translator::new(code)
+            .six_frames(seq)
+            .map(|result| result.unwrap())
+            .collect();
+        assert_eq!(frames.len(), 6);
+        for (frame, protein) in frames {
+            assert_eq!(protein, translate(seq, code, frame).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_options() {
+        let code = GeneticCode::standard();
+        let translator = Translator::new(code).stop(Stop::Error);
+        assert_eq!(
+            translator.translate(b"ATGTAGATG", Frame::Plus1),
+            Err(Error::StopCodon { pos: 3 })
+        );
+        let translator = Translator::new(code).partial_codon(PartialCodon::Unknown);
+        assert_eq!(translator.translate(b"ATGAT", Frame::Plus1).unwrap(), b"MX");
+        let translator = translator.partial_codon(PartialCodon::Error);
+        assert_eq!(
+            translator.translate(b"ATGAT", Frame::Plus1),
+            Err(Error::PartialCodon { len: 2 })
+        );
+    }
+
+    #[test]
+    fn test_ambiguous_codons() {
+        let code = GeneticCode::standard();
+        assert_eq!(code.translate_codon(b"TAR"), b'*');
+        assert_eq!(code.translate_codon(b"AGY"), b'S');
+        assert_eq!(code.translate_codon(b"AGN"), b'X');
+        assert_eq!(code.translate_codon(b"A-G"), b'X');
+        assert_eq!(code.translate_codon(b"AG"), b'X');
+    }
+}
@@ -0,0 +1,373 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Codon usage statistics of coding sequences (CDS): codon counts, relative synonymous codon
+//! usage (RSCU) and the codon adaptation index (CAI) of Sharp and Li (Nucleic Acids Research,
+//! 1987), which measures how similar the codon usage of a gene is to that of a reference set of
+//! e.g. highly expressed genes. Coding sequences can be extracted from GFF annotations and an
+//! indexed FASTA file with [`cds_sequences`](fn.cds_sequences.html).
+//!
+//! # Example
+//!
+//! ```
+//! use approx::assert_relative_eq;
+//! use bio::seq::translation::GeneticCode;
+//! use bio::seq_analysis::codon_usage::{Cai, CodonUsage};
+//!
+//! let code = GeneticCode::standard();
+//! // codon usage of highly expressed genes
+//! let mut reference = CodonUsage::new();
+//! reference.add_cds(b"ATGCTGCTGCTGCTTAAAAAGTAA");
+//! assert_eq!(reference.count(b"CTG"), 3);
+//! // CTG is used three times as often as expected from the usage of leucine codons
+//! assert_relative_eq!(reference.rscu(code, b"CTG").unwrap(), 3.0 * 6.0 / 4.0);
+//!
+//! let cai = Cai::new(&reference, code);
+//! assert_eq!(cai.cai(b"ATGCTGAAATAA"), Some(1.0));
+//! assert!(cai.cai(b"ATGCTTAAGTAA").unwrap() < 1.0);
+//! ```
+
+use std::collections::HashMap;
+use std::io;
+
+use bio_types::strand::Strand;
+use thiserror::Error;
+
+use crate::alphabets::dna;
+use crate::io::{fasta, gff};
+use crate::seq::translation::GeneticCode;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("can't read sequence of CDS {id}")]
+    ReadError {
+        id: String,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("CDS {0} has features on different strands or sequences")]
+    InconsistentFeatures(String),
+}
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Pseudocount of unobserved codons when calculating relative adaptiveness, as proposed by
+/// Sharp and Li.
+const PSEUDOCOUNT: f64 = 0.5;
+
+/// Index of an unambiguous codon (DNA or RNA, ignoring case) in ACGT order.
+fn index(codon: &[u8]) -> Option<usize> {
+    codon.iter().try_fold(0, |index, &base| {
+        let rank = match base.to_ascii_uppercase() {
+            b'A' => 0,
+            b'C' => 1,
+            b'G' => 2,
+            b'T' | b'U' => 3,
+            _ => return None,
+        };
+        Some(index * 4 + rank)
+    })
+}
+
+/// The codon with the given index.
+fn codon(index: usize) -> [u8; 3] {
+    let base = |i: usize| b"ACGT"[(index >> (2 * i)) & 3];
+    [base(2), base(1), base(0)]
+}
+
+/// Counts of the 64 codons over a set of coding sequences.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodonUsage {
+    counts: [u64; 64],
+}
+
+impl Default for CodonUsage {
+    fn default() -> Self {
+        CodonUsage { counts: [0; 64] }
+    }
+}
+
+impl CodonUsage {
+    /// Create empty codon usage.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count the codons of the given coding sequence, starting at its first base. Incomplete
+    /// and ambiguous codons are ignored.
+    pub fn add_cds(&mut self, cds: &[u8]) {
+        for codon in cds.chunks_exact(3) {
+            if let Some(index) = index(codon) {
+                self.counts[index] += 1;
+            }
+        }
+    }
+
+    /// Add the counts of other codon usage to this one.
+    pub fn merge(&mut self, other: &CodonUsage) {
+        for (count, other) in self.counts.iter_mut().zip(other.counts.iter()) {
+            *count += other;
+        }
+    }
+
+    /// Number of occurrences of the given codon. Ambiguous codons are never counted.
+    pub fn count(&self, codon: &[u8]) -> u64 {
+        index(codon).map_or(0, |index| self.counts[index])
+    }
+
+    /// Total number of counted codons.
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Frequency of the given codon among all counted codons, or zero if no codons were
+    /// counted.
+    pub fn frequency(&self, codon: &[u8]) -> f64 {
+        match self.total() {
+            0 => 0.0,
+            total => self.count(codon) as f64 / total as f64,
+        }
+    }
+
+    /// Iterator over all 64 codons (in ACGT order) and their counts.
+    pub fn iter(&self) -> impl Iterator<Item = ([u8; 3], u64)> + '_ {
+        self.counts
+            .iter()
+            .enumerate()
+            .map(|(index, &count)| (codon(index), count))
+    }
+
+    /// Indices of the codons encoding the same amino acid (or stop) as the given codon,
+    /// including itself.
+    fn synonymous(code: &GeneticCode, index: usize) -> impl Iterator<Item = usize> + '_ {
+        let amino_acid = code.translate_codon(&codon(index));
+        (0..64).filter(move |&other| code.translate_codon(&codon(other)) == amino_acid)
+    }
+
+    /// Relative synonymous codon usage of the given codon, i.e. its count divided by the mean
+    /// count of the codons encoding the same amino acid (or stop). Returns `None` if the codon
+    /// is ambiguous or none of its synonymous codons were counted.
+    pub fn rscu(&self, code: &GeneticCode, codon: &[u8]) -> Option<f64> {
+        let index = index(codon)?;
+        let (mut sum, mut k) = (0, 0);
+        for other in Self::synonymous(code, index) {
+            sum += self.counts[other];
+            k += 1;
+        }
+        if sum == 0 {
+            None
+        } else {
+            Some(self.counts[index] as f64 * k as f64 / sum as f64)
+        }
+    }
+}
+
+/// The codon adaptation index with respect to a reference codon usage.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cai {
+    /// Logarithm of the relative adaptiveness of each codon, or `None` for codons that are not
+    /// informative, i.e. stop codons and codons without synonyms.
+    ln_weights: [Option<f64>; 64],
+}
+
+impl Cai {
+    /// Calculate the relative adaptiveness of each codon in the reference, i.e. its count
+    /// divided by the count of the most frequent synonymous codon. Unobserved codons get a count
+    /// of 0.5.
+    pub fn new(reference: &CodonUsage, code: &GeneticCode) -> Self {
+        let mut ln_weights = [None; 64];
+        for (index, weight) in ln_weights.iter_mut().enumerate() {
+            if code.translate_codon(&codon(index)) == b'*' {
+                continue;
+            }
+            let synonymous: Vec<usize> = CodonUsage::synonymous(code, index).collect();
+            if synonymous.len() < 2 {
+                continue;
+            }
+            let count = |index: usize| (reference.counts[index] as f64).max(PSEUDOCOUNT);
+            let max = synonymous
+                .iter()
+                .map(|&other| count(other))
+                .fold(0.0, f64::max);
+            *weight = Some((count(index) / max).ln());
+        }
+        Cai { ln_weights }
+    }
+
+    /// Relative adaptiveness of the given codon, or `None` if it is not informative or
+    /// ambiguous.
+    pub fn weight(&self, codon: &[u8]) -> Option<f64> {
+        self.ln_weights[index(codon)?].map(f64::exp)
+    }
+
+    /// The codon adaptation index of the given coding sequence, i.e. the geometric mean of the
+    /// relative adaptiveness of its codons, ignoring codons that are not informative, ambiguous
+    /// or incomplete. Returns `None` if there is no informative codon.
+    pub fn cai(&self, cds: &[u8]) -> Option<f64> {
+        let (mut sum, mut n) = (0.0, 0);
+        for codon in cds.chunks_exact(3) {
+            if let Some(Some(ln_weight)) = index(codon).map(|index| self.ln_weights[index]) {
+                sum += ln_weight;
+                n += 1;
+            }
+        }
+        if n == 0 {
+            None
+        } else {
+            Some((sum / n as f64).exp())
+        }
+    }
+}
+
+/// The id a CDS feature belongs to, i.e. its `Parent` (GFF3), `transcript_id` (GTF) or `ID`
+/// attribute.
+fn cds_id(record: &gff::Record) -> Option<&String> {
+    let attributes = record.attributes();
+    attributes
+        .get("Parent")
+        .or_else(|| attributes.get("transcript_id"))
+        .or_else(|| attributes.get("ID"))
+}
+
+/// Extract the coding sequences annotated by the `CDS` features among the given GFF records
+/// from the given indexed FASTA file. The features are grouped into coding sequences by their
+/// `Parent` (GFF3), `transcript_id` (GTF) or `ID` attribute, spliced, reverse complemented if
+/// on the reverse strand and trimmed by the phase of their first feature. Returns the ids and
+/// coding sequences in the order of their first feature. Features without an id are ignored.
+pub fn cds_sequences<'a, I, R>(
+    records: I,
+    reader: &mut fasta::IndexedReader<R>,
+) -> Result<Vec<(String, Vec<u8>)>>
+where
+    I: IntoIterator<Item = &'a gff::Record>,
+    R: io::Read + io::Seek,
+{
+    let mut ids = Vec::new();
+    let mut features: HashMap<String, Vec<&gff::Record>> = HashMap::new();
+    for record in records {
+        if record.feature_type() != "CDS" {
+            continue;
+        }
+        if let Some(id) = cds_id(record) {
+            features
+                .entry(id.clone())
+                .or_insert_with(|| {
+                    ids.push(id.clone());
+                    Vec::new()
+                })
+                .push(record);
+        }
+    }
+
+    let mut sequences = Vec::with_capacity(ids.len());
+    for id in ids {
+        let mut records = features.remove(&id).unwrap();
+        let (seqname, strand) = (records[0].seqname(), records[0].strand());
+        if records
+            .iter()
+            .any(|record| record.seqname() != seqname || record.strand() != strand)
+        {
+            return Err(Error::InconsistentFeatures(id));
+        }
+        records.sort_by_key(|record| *record.start());
+        let reverse = strand == Some(Strand::Reverse);
+        if reverse {
+            records.reverse();
+        }
+
+        let mut cds = Vec::new();
+        for record in &records {
+            let mut seq = Vec::new();
+            reader
+                .fetch(record.seqname(), record.start() - 1, *record.end())
+                .and_then(|_| reader.read(&mut seq))
+                .map_err(|source| Error::ReadError {
+                    id: id.clone(),
+                    source,
+                })?;
+            if reverse {
+                seq = dna::revcomp(&seq);
+            }
+            cds.extend(seq);
+        }
+        let phase = records[0].frame().parse::<usize>().unwrap_or(0);
+        cds.drain(..phase.min(cds.len()));
+        sequences.push((id, cds));
+    }
+    Ok(sequences)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codon_index() {
+        for i in 0..64 {
+            assert_eq!(index(&codon(i)), Some(i));
+        }
+        assert_eq!(index(b"aug"), index(b"ATG"));
+        assert_eq!(index(b"ATN"), None);
+    }
+
+    #[test]
+    fn test_codon_usage() {
+        let mut usage = CodonUsage::new();
+        usage.add_cds(b"ATGGCNGCAGCAGCGTAAGC");
+        assert_eq!(usage.total(), 5);
+        assert_eq!(usage.count(b"GCA"), 2);
+        assert_eq!(usage.frequency(b"GCA"), 0.4);
+        let mut other = CodonUsage::new();
+        other.add_cds(b"GCTTGA");
+        usage.merge(&other);
+        assert_eq!(usage.total(), 7);
+
+        let code = GeneticCode::standard();
+        // alanine: GCA 2, GCG 1, GCT 1, GCC 0
+        assert_eq!(usage.rscu(code, b"GCA"), Some(2.0));
+        assert_eq!(usage.rscu(code, b"GCC"), Some(0.0));
+        assert_eq!(usage.rscu(code, b"ATG"), Some(1.0));
+        assert_eq!(usage.rscu(code, b"TGG"), None);
+        // stop codons form a family of their own
+        assert_eq!(usage.rscu(code, b"TAG"), Some(0.0));
+        assert_eq!(usage.rscu(code, b"TAA"), Some(1.5));
+        assert_eq!(usage.iter().map(|(_, count)| count).sum::<u64>(), 7);
+    }
+
+    #[test]
+    fn test_cai() {
+        let code = GeneticCode::standard();
+        let mut reference = CodonUsage::new();
+        reference.add_cds(b"GCAGCAGCAGCAGCGAAAAAA");
+        let cai = Cai::new(&reference, code);
+        assert_eq!(cai.weight(b"GCA"), Some(1.0));
+        assert_relative_eq!(cai.weight(b"GCG").unwrap(), 0.25, epsilon = 1e-12);
+        assert_relative_eq!(cai.weight(b"GCT").unwrap(), 0.125, epsilon = 1e-12);
+        assert_eq!(cai.weight(b"ATG"), None);
+        assert_eq!(cai.weight(b"TAA"), None);
+        // geometric mean of 0.25 and 1, ignoring methionine and the stop codon
+        assert_relative_eq!(cai.cai(b"ATGGCGAAATAA").unwrap(), 0.5, epsilon = 1e-12);
+        assert_eq!(cai.cai(b"ATGTGGTAA"), None);
+    }
+
+    #[test]
+    fn test_cds_sequences() {
+        const FASTA: &[u8] = b">chr1\nGGATGAAACCCGGGTTTAGGCCCAAACATTTT\n";
+        const FAI: &[u8] = b"chr1\t32\t6\t32\t33\n";
+        const GFF: &[u8] = b"chr1\t.\tCDS\t3\t8\t.\t+\t0\tParent=t1
+chr1\t.\tCDS\t12\t17\t.\t+\t0\tParent=t1
+chr1\t.\texon\t3\t17\t.\t+\t.\tParent=t1
+chr1\t.\tCDS\t21\t31\t.\t-\t2\tParent=t2
+";
+        let mut reader = fasta::IndexedReader::new(io::Cursor::new(FASTA), FAI).unwrap();
+        let mut gff = gff::Reader::new(GFF, gff::GffType::GFF3);
+        let records: Vec<gff::Record> = gff.records().map(|r| r.unwrap()).collect();
+        let cds = cds_sequences(&records, &mut reader).unwrap();
+        assert_eq!(cds.len(), 2);
+        assert_eq!(cds[0], ("t1".to_owned(), b"ATGAAAGGGTTT".to_vec()));
+        // reverse complement of CCCAAACATTT is AAATGTTTGGG, trimmed by a phase of 2
+        assert_eq!(cds[1], ("t2".to_owned(), b"ATGTTTGGG".to_vec()));
+    }
+}
@@ -0,0 +1,452 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Sequence complexity measures and detection of low-complexity regions, e.g. to filter
+//! repetitive reads or to mask sequences before motif or homology search.
+//!
+//! The Shannon entropy, the linguistic complexity (Troyanskaya et al., Bioinformatics, 2002) and
+//! the complexity of Wootton and Federhen (Computers & Chemistry, 1993) can be calculated for
+//! whole sequences or, with [`windowed`](fn.windowed.html), over sliding windows.
+//! Nucleotide sequences are scanned with symmetric DUST (Morgulis et al., Journal of
+//! Computational Biology, 2006), protein sequences with SEG (Wootton and Federhen, Computers &
+//! Chemistry, 1993). Low-complexity regions are returned as sorted, non-overlapping, 0-based,
+//! half-open intervals, which can be masked with the functions of
+//! [`seq::mask`](../../seq/mask/index.html).
+//!
+//! # Example
+//!
+//! ```
+//! use bio::seq_analysis::complexity::{dust, soft_masked, DUST_THRESHOLD, DUST_WINDOW};
+//!
+//! let mut seq = b"ACGTTGCAGGCTACCGATAGCTTACG".to_vec();
+//! seq.extend(b"CA".repeat(30));
+//! seq.extend(b"GGTTATGGATTTGGCTTATGGCTTAT");
+//!
+//! let intervals = dust(&seq, DUST_WINDOW, DUST_THRESHOLD);
+//! assert_eq!(intervals, vec![26..86]);
+//! let masked = soft_masked(&seq, &intervals);
+//! assert_eq!(&masked[21..31], b"TTACGcacac");
+//! ```
+
+use std::collections::HashSet;
+use std::ops::Range;
+
+use crate::seq::mask;
+use crate::stats::combinatorics::ln_multinomial;
+
+/// Default window size of DUST.
+pub const DUST_WINDOW: usize = 64;
+/// Default score threshold of DUST.
+pub const DUST_THRESHOLD: f64 = 20.0;
+/// Default window size of SEG.
+pub const SEG_WINDOW: usize = 12;
+/// Default trigger complexity (in bits) of SEG.
+pub const SEG_TRIGGER: f64 = 2.2;
+/// Default extension complexity (in bits) of SEG.
+pub const SEG_EXTENSION: f64 = 2.5;
+
+/// Code of the nucleotide triplet starting at each position, or `None` if it contains a symbol
+/// other than A, C, G, T (ignoring case).
+fn triplets(seq: &[u8]) -> Vec<Option<usize>> {
+    let code = |a: u8| match a.to_ascii_uppercase() {
+        b'A' => Some(0),
+        b'C' => Some(1),
+        b'G' => Some(2),
+        b'T' => Some(3),
+        _ => None,
+    };
+    seq.windows(3)
+        .map(|w| Some(code(w[0])? * 16 + code(w[1])? * 4 + code(w[2])?))
+        .collect()
+}
+
+/// Sort and merge overlapping or adjacent intervals.
+fn merge(mut intervals: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    intervals.sort_by_key(|interval| interval.start);
+    let mut merged: Vec<Range<usize>> = Vec::with_capacity(intervals.len());
+    for interval in intervals {
+        match merged.last_mut() {
+            Some(last) if last.end >= interval.start => last.end = last.end.max(interval.end),
+            _ => merged.push(interval),
+        }
+    }
+    merged
+}
+
+/// Find low-complexity regions in a nucleotide sequence with symmetric DUST.
+///
+/// The score of an interval with l triplets, of which triplet t occurs c_t times, is
+/// sum_t c_t (c_t - 1) / 2 / (l - 1), scaled by 10 as in common implementations (e.g.
+/// minimap2), such that the default threshold is 20. For each window of the given size, the
+/// highest-scoring suffix with a score above the threshold is reported, unless removing its
+/// last triplet would increase the score. This approximates the perfect intervals of symmetric
+/// DUST and avoids extending masked regions into their flanks. Triplets containing symbols
+/// other than A, C, G, T are never part of a low-complexity region. With the default threshold,
+/// e.g., homopolymers of at least 7 bases are reported.
+///
+/// Complexity: O(n * w), where n is the length of the sequence and w the window size.
+///
+/// # Panics
+///
+/// If the window is smaller than 4.
+pub fn dust(seq: &[u8], window: usize, threshold: f64) -> Vec<Range<usize>> {
+    assert!(window >= 4, "window must contain at least two triplets");
+    let triplets = triplets(seq);
+    let max_triplets = window - 2;
+    let mut counts = [0usize; 64];
+    let mut intervals = Vec::new();
+
+    let score = |sum: usize, l: usize| 10.0 * sum as f64 / (l - 1) as f64;
+
+    for end in 1..=triplets.len() {
+        let mut sum = 0;
+        // start and sum of the best suffix
+        let mut best: Option<(usize, usize)> = None;
+        let first = end.saturating_sub(max_triplets);
+        let mut start = end;
+        while start > first {
+            let triplet = match triplets[start - 1] {
+                Some(triplet) => triplet,
+                None => break,
+            };
+            start -= 1;
+            sum += counts[triplet];
+            counts[triplet] += 1;
+            let l = end - start;
+            if l >= 2
+                && score(sum, l) > threshold
+                && !matches!(best, Some((s, b)) if score(b, end - s) >= score(sum, l))
+            {
+                best = Some((start, sum));
+            }
+        }
+        if let Some((best_start, best_sum)) = best {
+            let l = end - best_start;
+            // occurrences of the last triplet before it
+            let last = triplets[end - 1];
+            let c = triplets[best_start..end - 1]
+                .iter()
+                .filter(|&&t| t == last)
+                .count();
+            if l == 2 || score(best_sum - c, l - 1) <= score(best_sum, l) {
+                // the last triplet ends two bases after its start
+                intervals.push(best_start..end + 2);
+            }
+        }
+        for triplet in triplets[start..end].iter().flatten() {
+            counts[*triplet] = 0;
+        }
+    }
+    merge(intervals)
+}
+
+/// Find low-complexity regions in a protein sequence with SEG.
+///
+/// The complexity of a window is the Shannon entropy (in bits) of its residue composition,
+/// ignoring case. Windows with a complexity of at most `trigger` are extended by overlapping
+/// windows with a complexity of at most `extension`, and the union of these windows is
+/// reported. In contrast to the original SEG, the segments are not further refined to their
+/// optimal subsequence.
+///
+/// Complexity: O(n), where n is the length of the sequence.
+///
+/// # Panics
+///
+/// If the window is zero or `trigger` exceeds `extension`.
+///
+/// # Example
+///
+/// ```
+/// use bio::seq_analysis::complexity::{seg, SEG_EXTENSION, SEG_TRIGGER, SEG_WINDOW};
+///
+/// let seq = b"MKVLAWDEFGHIKLMNPQRSTVWYQQQQQQQQQQQQQQQQRSTVWYADEFGHIKLMNPC";
+/// let intervals = seg(seq, SEG_WINDOW, SEG_TRIGGER, SEG_EXTENSION);
+/// assert_eq!(intervals.len(), 1);
+/// assert!(intervals[0].start <= 24 && intervals[0].end >= 40);
+/// ```
+pub fn seg(seq: &[u8], window: usize, trigger: f64, extension: f64) -> Vec<Range<usize>> {
+    assert!(window > 0, "window must be positive");
+    assert!(trigger <= extension, "trigger must not exceed extension");
+    if seq.len() < window {
+        return Vec::new();
+    }
+    let w = window as f64;
+    let c_log_c = |c: usize| {
+        if c == 0 {
+            0.0
+        } else {
+            c as f64 * (c as f64).log2()
+        }
+    };
+    // the entropy is log2(w) - sum_c c log2(c) / w, the sum is updated incrementally
+    let mut counts = [0usize; 256];
+    let mut sum = 0.0;
+    let mut update = |a: u8, delta: isize, sum: &mut f64| {
+        let count = &mut counts[a.to_ascii_uppercase() as usize];
+        *sum -= c_log_c(*count);
+        *count = (*count as isize + delta) as usize;
+        *sum += c_log_c(*count);
+    };
+
+    let mut intervals = Vec::new();
+    // current run of windows below the extension complexity and whether it contains a trigger
+    let mut run: Option<(usize, bool)> = None;
+    for end in 1..=seq.len() {
+        update(seq[end - 1], 1, &mut sum);
+        if end < window {
+            continue;
+        }
+        let start = end - window;
+        if start > 0 {
+            update(seq[start - 1], -1, &mut sum);
+        }
+        let complexity = w.log2() - sum / w;
+        // tolerate rounding errors of the incremental update
+        if complexity <= extension + 1e-9 {
+            let triggered = complexity <= trigger + 1e-9;
+            run = match run {
+                Some((first, t)) => Some((first, t || triggered)),
+                None => Some((start, triggered)),
+            };
+        } else if let Some((first, triggered)) = run.take() {
+            if triggered {
+                intervals.push(first..start - 1 + window);
+            }
+        }
+    }
+    if let Some((first, true)) = run {
+        intervals.push(first..seq.len());
+    }
+    intervals
+}
+
+/// Return a copy of the sequence with the given intervals soft-masked, i.e. converted to
+/// lowercase.
+pub fn soft_masked(seq: &[u8], intervals: &[Range<usize>]) -> Vec<u8> {
+    let mut masked = seq.to_vec();
+    mask::soft_mask(&mut masked, intervals.iter().cloned());
+    masked
+}
+
+/// Occurrences of each symbol, ignoring case.
+fn symbol_counts(seq: &[u8]) -> [u64; 256] {
+    let mut counts = [0; 256];
+    for &a in seq {
+        counts[a.to_ascii_uppercase() as usize] += 1;
+    }
+    counts
+}
+
+/// Shannon entropy (in bits) of the symbol composition of the sequence, ignoring case. The
+/// entropy of an empty sequence is zero.
+///
+/// # Example
+///
+/// ```
+/// use bio::seq_analysis::complexity::shannon_entropy;
+///
+/// assert_eq!(shannon_entropy(b"ACGTacgt"), 2.0);
+/// assert_eq!(shannon_entropy(b"AAAA"), 0.0);
+/// ```
+pub fn shannon_entropy(seq: &[u8]) -> f64 {
+    if seq.is_empty() {
+        return 0.0;
+    }
+    let n = seq.len() as f64;
+    let sum: f64 = symbol_counts(seq)
+        .iter()
+        .filter(|&&c| c > 0)
+        .map(|&c| c as f64 * (c as f64).log2())
+        .sum();
+    n.log2() - sum / n
+}
+
+/// Linguistic complexity of the sequence, ignoring case, i.e. the number of distinct k-mers of
+/// length 1 to `max_k` divided by the maximal possible number of distinct k-mers, given the
+/// size of the alphabet and the length of the sequence (Troyanskaya et al., Bioinformatics,
+/// 2002). The complexity ranges from 0 to 1; for an empty sequence or `max_k` of zero, it is 1.
+///
+/// Complexity: O(n * k) for a sequence of length n.
+///
+/// # Example
+///
+/// ```
+/// use bio::seq_analysis::complexity::linguistic_complexity;
+///
+/// assert_eq!(linguistic_complexity(b"ACGT", 4, 4), 1.0);
+/// // 4 distinct k-mers out of 4 + 3 + 2 + 1 possible ones
+/// assert_eq!(linguistic_complexity(b"AAAA", 4, 4), 0.4);
+/// ```
+pub fn linguistic_complexity(seq: &[u8], alphabet_size: usize, max_k: usize) -> f64 {
+    let seq = seq.to_ascii_uppercase();
+    let n = seq.len();
+    let (mut observed, mut possible) = (0, 0);
+    for k in 1..=max_k.min(n) {
+        observed += seq.windows(k).collect::<HashSet<_>>().len();
+        possible += alphabet_size
+            .checked_pow(k as u32)
+            .map_or(n - k + 1, |m| m.min(n - k + 1));
+    }
+    if possible == 0 {
+        1.0
+    } else {
+        observed as f64 / possible as f64
+    }
+}
+
+/// Complexity of Wootton and Federhen (Computers & Chemistry, 1993) of the sequence, ignoring
+/// case, i.e. log_N(L! / prod_i n_i!) / L for a sequence of length L over an alphabet of size N,
+/// with n_i occurrences of symbol i. This is the logarithm of the number of sequences with the
+/// same composition per symbol, ranging from 0 (a homopolymer) to below 1. The complexity of an
+/// empty sequence is zero.
+///
+/// # Example
+///
+/// ```
+/// use approx::assert_relative_eq;
+/// use bio::seq_analysis::complexity::wootton_federhen;
+///
+/// assert_eq!(wootton_federhen(b"AAAA", 4), 0.0);
+/// // log_4(4!) / 4
+/// assert_relative_eq!(wootton_federhen(b"ACGT", 4), 0.5731203125901446, epsilon = 1e-12);
+/// ```
+pub fn wootton_federhen(seq: &[u8], alphabet_size: usize) -> f64 {
+    if seq.is_empty() {
+        return 0.0;
+    }
+    let counts: Vec<u64> = symbol_counts(seq)
+        .iter()
+        .cloned()
+        .filter(|&c| c > 0)
+        .collect();
+    (ln_multinomial(&counts) / (alphabet_size as f64).ln() / seq.len() as f64).max(0.0)
+}
+
+/// Apply the given complexity measure to windows of the given size, shifted by the given step,
+/// returning the start position of each window and its complexity. Windows extending beyond
+/// the end of the sequence are omitted.
+///
+/// # Panics
+///
+/// If the window or step are zero.
+///
+/// # Example
+///
+/// ```
+/// use bio::seq_analysis::complexity::{shannon_entropy, windowed};
+///
+/// let entropy: Vec<(usize, f64)> = windowed(b"AAAAACGTAC", 4, 3, shannon_entropy).collect();
+/// assert_eq!(entropy, [(0, 0.0), (3, 1.5), (6, 2.0)]);
+/// ```
+pub fn windowed<'a, F>(
+    seq: &'a [u8],
+    window: usize,
+    step: usize,
+    measure: F,
+) -> impl Iterator<Item = (usize, f64)> + 'a
+where
+    F: Fn(&[u8]) -> f64 + 'a,
+{
+    assert!(window > 0 && step > 0, "window and step must be positive");
+    seq.windows(window)
+        .step_by(step)
+        .enumerate()
+        .map(move |(i, w)| (i * step, measure(w)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::random_seq;
+    use rand::prelude::*;
+
+    #[test]
+    fn test_dust_homopolymer() {
+        // a score of 10 * l / 2 for l triplets
+        assert!(dust(&[b'A'; 6], DUST_WINDOW, DUST_THRESHOLD).is_empty());
+        assert_eq!(dust(&[b'A'; 7], DUST_WINDOW, DUST_THRESHOLD), vec![0..7]);
+        assert_eq!(
+            dust(&[b'a'; 100], DUST_WINDOW, DUST_THRESHOLD),
+            vec![0..100]
+        );
+        assert!(dust(&[b'N'; 100], DUST_WINDOW, DUST_THRESHOLD).is_empty());
+        assert!(dust(b"AC", DUST_WINDOW, DUST_THRESHOLD).is_empty());
+    }
+
+    #[test]
+    fn test_dust() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let background = random_seq(&mut rng, b"ACGT", 2000);
+        assert!(dust(&background, DUST_WINDOW, DUST_THRESHOLD).is_empty());
+
+        let mut seq = background[..500].to_vec();
+        seq.extend(b"CAG".repeat(30));
+        seq.extend(&background[500..1000]);
+        seq.extend(&[b'T'; 60]);
+        seq.extend(&background[1000..1500]);
+        let intervals = dust(&seq, DUST_WINDOW, DUST_THRESHOLD);
+        assert_eq!(intervals.len(), 2);
+        assert!(intervals[0].start <= 505 && intervals[0].end >= 585);
+        assert!(intervals[0].start >= 490 && intervals[0].end <= 610);
+        assert!(intervals[1].start <= 1095 && intervals[1].end >= 1145);
+        assert!(intervals[1].start >= 1080 && intervals[1].end <= 1160);
+    }
+
+    #[test]
+    fn test_seg() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let background = random_seq(&mut rng, b"ACDEFGHIKLMNPQRSTVWY", 400);
+        assert!(seg(&background, SEG_WINDOW, SEG_TRIGGER, SEG_EXTENSION).is_empty());
+
+        let mut seq = background[..200].to_vec();
+        seq.extend(b"PEPE".repeat(8));
+        seq.extend(&background[200..]);
+        let intervals = seg(&seq, SEG_WINDOW, SEG_TRIGGER, SEG_EXTENSION);
+        assert_eq!(intervals.len(), 1);
+        assert!(intervals[0].start <= 200 && intervals[0].end >= 232);
+        assert!(intervals[0].start >= 200 - SEG_WINDOW && intervals[0].end <= 232 + SEG_WINDOW);
+
+        // low complexity up to the end of the sequence
+        let seq = b"MKVLAWDEFGHIKQQQQQQQQQQQQQ";
+        let intervals = seg(seq, SEG_WINDOW, SEG_TRIGGER, SEG_EXTENSION);
+        assert_eq!(intervals.len(), 1);
+        assert!(intervals[0].start <= 13 && intervals[0].end == seq.len());
+        assert!(seg(b"QQQ", SEG_WINDOW, SEG_TRIGGER, SEG_EXTENSION).is_empty());
+    }
+
+    #[test]
+    fn test_soft_masked() {
+        assert_eq!(soft_masked(b"ACGTACGT", &[1..3, 6..10]), b"AcgTACgt");
+    }
+    #[test]
+    fn test_complexity_measures() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let seq = random_seq(&mut rng, b"ACGT", 1000);
+        assert!(shannon_entropy(&seq) > 1.99);
+        assert!(linguistic_complexity(&seq, 4, 8) > 0.8);
+        assert!(wootton_federhen(&seq, 4) > 0.98);
+
+        let repeat = b"CA".repeat(500);
+        assert_relative_eq!(shannon_entropy(&repeat), 1.0, epsilon = 1e-12);
+        assert!(linguistic_complexity(&repeat, 4, 8) < 0.01);
+        assert!(wootton_federhen(&repeat, 4) < 0.5);
+
+        // the measures only depend on the composition (and order for k-mers), not on case
+        assert_eq!(
+            linguistic_complexity(b"ACGTTGCA", 4, 3),
+            linguistic_complexity(b"acgtTGCA", 4, 3)
+        );
+        // A2 C2 G1 T1, reference calculated as ln(6! / (2! 2!)) / (6 ln 4)
+        assert_relative_eq!(
+            wootton_federhen(b"ACGTAC", 4),
+            0.6243210913608063,
+            epsilon = 1e-12
+        );
+        assert_eq!(shannon_entropy(b""), 0.0);
+        assert_eq!(linguistic_complexity(b"", 4, 3), 1.0);
+        assert_eq!(wootton_federhen(b"", 4), 0.0);
+        assert_eq!(windowed(b"ACG", 4, 1, shannon_entropy).count(), 0);
+    }
+}
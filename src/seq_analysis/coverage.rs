@@ -0,0 +1,331 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Coverage (read depth) computation from intervals, e.g. BED records or aligned reads.
+//!
+//! * [`depth`](fn.depth.html) computes per-base depth along a contig.
+//! * [`bedgraph`](fn.bedgraph.html) computes run-length encoded depth from a sorted stream of
+//!   intervals, like `bedtools genomecov -bg`, without ever holding per-base values in memory.
+//! * [`summarize`](fn.summarize.html) reduces per-base depth to means and medians over windows,
+//!   e.g. for plotting.
+//!
+//! Any type implementing
+//! [`GenomicInterval`](../interval_ops/trait.GenomicInterval.html) can be used as input,
+//! so that alignment records (e.g. from rust-htslib) can be supported by implementing that
+//! trait for them.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::seq_analysis::coverage::{bedgraph, depth, summarize};
+//! use bio::seq_analysis::interval_ops::Region;
+//!
+//! let reads = vec![
+//!     Region::new("chr1", 0, 4),
+//!     Region::new("chr1", 2, 6),
+//!     Region::new("chr1", 2, 3),
+//! ];
+//! assert_eq!(depth(&reads, "chr1", 8), vec![1, 1, 3, 2, 1, 1, 0, 0]);
+//!
+//! let runs: Vec<(Region, u32)> = bedgraph(&reads).collect::<Result<_, _>>().unwrap();
+//! assert_eq!(
+//!     runs,
+//!     vec![
+//!         (Region::new("chr1", 0, 2), 1),
+//!         (Region::new("chr1", 2, 3), 3),
+//!         (Region::new("chr1", 3, 4), 2),
+//!         (Region::new("chr1", 4, 6), 1),
+//!     ]
+//! );
+//!
+//! let windows = summarize(&depth(&reads, "chr1", 8), 4);
+//! assert_eq!(windows[0].mean, 1.75);
+//! assert_eq!(windows[1].median, 0.5);
+//! ```
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::seq_analysis::interval_ops::{Checked, GenomicInterval, Order, Region, Result};
+
+/// Compute the per-base depth of the first `len` positions of the given contig.
+/// Intervals on other contigs are ignored, parts of intervals beyond `len` are clipped.
+/// Intervals need not be sorted.
+pub fn depth<I>(intervals: I, contig: &str, len: u64) -> Vec<u32>
+where
+    I: IntoIterator,
+    I::Item: GenomicInterval,
+{
+    // accumulate depth changes at interval boundaries
+    let mut changes = vec![0i64; len as usize + 1];
+    for interval in intervals {
+        if interval.contig() != contig {
+            continue;
+        }
+        let start = interval.start().min(len) as usize;
+        let end = interval.end().min(len) as usize;
+        if start < end {
+            changes[start] += 1;
+            changes[end] -= 1;
+        }
+    }
+    let mut current = 0;
+    changes[..len as usize]
+        .iter()
+        .map(|change| {
+            current += change;
+            current as u32
+        })
+        .collect()
+}
+
+/// Iterator over runs of constant depth, see [`bedgraph`](fn.bedgraph.html).
+pub struct BedGraph<I: Iterator> {
+    inner: Checked<'static, I>,
+    lookahead: Option<I::Item>,
+    contig: String,
+    pos: u64,
+    /// Ends of the intervals covering `pos`.
+    ends: BinaryHeap<Reverse<u64>>,
+    pending: Option<(Region, u32)>,
+    error_has_occured: bool,
+}
+
+impl<I> BedGraph<I>
+where
+    I: Iterator,
+    I::Item: GenomicInterval,
+{
+    fn fill(&mut self) -> Result<()> {
+        if self.lookahead.is_none() {
+            self.lookahead = self.inner.next().transpose()?;
+        }
+        Ok(())
+    }
+
+    /// Compute the next run of constant, non-zero depth. Adjacent runs may have equal depth.
+    fn next_run(&mut self) -> Result<Option<(Region, u32)>> {
+        loop {
+            self.fill()?;
+            let next_start = self
+                .lookahead
+                .as_ref()
+                .filter(|interval| interval.contig() == self.contig)
+                .map(|interval| interval.start());
+            let next_end = self.ends.peek().map(|&Reverse(end)| end);
+            let event = match (next_start, next_end) {
+                (Some(start), Some(end)) => start.min(end),
+                (Some(start), None) => start,
+                (None, Some(end)) => end,
+                (None, None) => match self.lookahead.as_ref() {
+                    // the current contig is finished
+                    Some(interval) => {
+                        self.contig = interval.contig().to_owned();
+                        self.pos = interval.start();
+                        continue;
+                    }
+                    None => return Ok(None),
+                },
+            };
+
+            let depth = self.ends.len() as u32;
+            let run = if event > self.pos && depth > 0 {
+                Some((Region::new(&self.contig, self.pos, event), depth))
+            } else {
+                None
+            };
+            self.pos = event;
+            while self.ends.peek() == Some(&Reverse(event)) {
+                self.ends.pop();
+            }
+            if next_start == Some(event) {
+                let interval = self.lookahead.take().unwrap();
+                if interval.end() > interval.start() {
+                    self.ends.push(Reverse(interval.end()));
+                }
+            }
+            if run.is_some() {
+                return Ok(run);
+            }
+        }
+    }
+}
+
+impl<I> Iterator for BedGraph<I>
+where
+    I: Iterator,
+    I::Item: GenomicInterval,
+{
+    type Item = Result<(Region, u32)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.error_has_occured {
+            return None;
+        }
+        loop {
+            match self.next_run() {
+                Ok(Some((region, depth))) => match self.pending.as_mut() {
+                    Some((pending, pending_depth))
+                        if *pending_depth == depth
+                            && pending.end == region.start
+                            && pending.contig == region.contig =>
+                    {
+                        pending.end = region.end;
+                    }
+                    _ => {
+                        let previous = self.pending.replace((region, depth));
+                        if previous.is_some() {
+                            return previous.map(Ok);
+                        }
+                    }
+                },
+                Ok(None) => return self.pending.take().map(Ok),
+                Err(e) => {
+                    self.error_has_occured = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+/// Compute runs of constant depth (like `bedtools genomecov -bg`), reported as regions
+/// together with their depth. Regions of zero depth are omitted.
+///
+/// Intervals of the same contig have to be consecutive and sorted by start position.
+pub fn bedgraph<I>(intervals: I) -> BedGraph<I::IntoIter>
+where
+    I: IntoIterator,
+    I::Item: GenomicInterval,
+{
+    BedGraph {
+        inner: Checked::new(intervals.into_iter(), Order::Grouped),
+        lookahead: None,
+        contig: String::new(),
+        pos: 0,
+        ends: BinaryHeap::new(),
+        pending: None,
+        error_has_occured: false,
+    }
+}
+
+/// Mean and median depth in a window.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WindowSummary {
+    /// Start of the window (0-based).
+    pub start: u64,
+    /// End of the window (0-based, not included).
+    pub end: u64,
+    pub mean: f64,
+    pub median: f64,
+}
+
+/// Summarize per-base depth in consecutive, non-overlapping windows of the given size.
+/// The last window is shorter if the length of `depth` is not a multiple of `window`.
+///
+/// # Panics
+///
+/// If `window` is zero.
+pub fn summarize(depth: &[u32], window: usize) -> Vec<WindowSummary> {
+    assert!(window > 0, "window size must be positive");
+    depth
+        .chunks(window)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let mut sorted = chunk.to_vec();
+            sorted.sort_unstable();
+            let n = sorted.len();
+            let median = if n % 2 == 1 {
+                sorted[n / 2] as f64
+            } else {
+                (sorted[n / 2 - 1] as f64 + sorted[n / 2] as f64) / 2.0
+            };
+            let start = (i * window) as u64;
+            WindowSummary {
+                start,
+                end: start + n as u64,
+                mean: chunk.iter().map(|&d| d as f64).sum::<f64>() / n as f64,
+                median,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::seq_analysis::interval_ops::Error;
+    use rand::prelude::*;
+
+    #[test]
+    fn test_depth_clips_and_filters() {
+        let intervals = vec![
+            Region::new("chr1", 3, 100),
+            Region::new("chr2", 0, 5),
+            Region::new("chr1", 1, 1),
+        ];
+        assert_eq!(depth(&intervals, "chr1", 5), vec![0, 0, 0, 1, 1]);
+    }
+
+    #[test]
+    fn test_bedgraph_contigs_and_gaps() {
+        let intervals = vec![
+            Region::new("chr1", 0, 10),
+            Region::new("chr1", 5, 10),
+            Region::new("chr1", 10, 20),
+            Region::new("chr1", 30, 40),
+            Region::new("chr2", 5, 6),
+        ];
+        let runs: Vec<_> = bedgraph(&intervals).map(Result::unwrap).collect();
+        assert_eq!(
+            runs,
+            vec![
+                (Region::new("chr1", 0, 5), 1),
+                (Region::new("chr1", 5, 10), 2),
+                (Region::new("chr1", 10, 20), 1),
+                (Region::new("chr1", 30, 40), 1),
+                (Region::new("chr2", 5, 6), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bedgraph_matches_depth() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut intervals: Vec<Region> = (0..200)
+            .map(|_| {
+                let start = rng.gen_range(0..1000);
+                Region::new("chr1", start, start + rng.gen_range(0..50))
+            })
+            .collect();
+        intervals.sort_by_key(|interval| interval.start);
+
+        let mut expected = vec![0; 1100];
+        for (region, d) in bedgraph(&intervals).map(Result::unwrap) {
+            for pos in region.start..region.end {
+                expected[pos as usize] = d;
+            }
+        }
+        assert_eq!(depth(&intervals, "chr1", 1100), expected);
+    }
+
+    #[test]
+    fn test_bedgraph_unsorted() {
+        let intervals = vec![Region::new("chr1", 10, 20), Region::new("chr1", 5, 8)];
+        let result: Result<Vec<_>> = bedgraph(intervals).collect();
+        assert!(matches!(result, Err(Error::Unsorted { .. })));
+    }
+
+    #[test]
+    fn test_summarize() {
+        let summary = summarize(&[1, 2, 3, 10, 4], 2);
+        assert_eq!(summary.len(), 3);
+        assert_eq!((summary[1].start, summary[1].end), (2, 4));
+        assert_eq!(summary[1].mean, 6.5);
+        assert_eq!(summary[1].median, 6.5);
+        assert_eq!((summary[2].start, summary[2].end), (4, 5));
+        assert_eq!(summary[2].median, 4.0);
+    }
+}
@@ -0,0 +1,271 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Detection of CpG islands, i.e. regions with high GC content and high frequency of CpG
+//! dinucleotides, which are often found at promoters and are a main target of methylation
+//! analyses.
+//!
+//! Windows of the minimum island length are slid over the sequence by one base, and overlapping
+//! or adjacent windows that satisfy the criteria are merged. The merged regions are trimmed to
+//! their first and last CpG and kept if they still satisfy the criteria. Finally, islands that
+//! are separated by at most the given distance are merged if the combined region satisfies the
+//! criteria. The criteria of Gardiner-Garden and Frommer (Journal of Molecular Biology, 1987)
+//! and the stricter ones of Takai and Jones (PNAS, 2002), which exclude most Alu repeats, are
+//! provided.
+//!
+//! Complexity: O(n), where n is the length of the sequence.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::seq_analysis::cpg::{find_islands, Criteria};
+//!
+//! let mut seq = b"AT".repeat(200);
+//! seq.extend(b"CGGC".repeat(60));
+//! seq.extend(b"TA".repeat(200));
+//!
+//! let islands = find_islands(&seq, &Criteria::gardiner_garden());
+//! assert_eq!(islands.len(), 1);
+//! assert_eq!((islands[0].start, islands[0].end), (400, 638));
+//! assert_eq!(islands[0].cpg_count, 60);
+//! ```
+
+/// Criteria for CpG islands.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Criteria {
+    /// Minimum length of an island.
+    pub min_length: usize,
+    /// Minimum fraction of guanines and cytosines.
+    pub min_gc_content: f32,
+    /// Minimum ratio of observed to expected CpG dinucleotides.
+    pub min_obs_exp: f32,
+    /// Maximum distance of islands to merge.
+    pub merge_distance: usize,
+}
+
+impl Criteria {
+    /// The criteria of Gardiner-Garden and Frommer: at least 200 bases, a GC content of at least
+    /// 50% and an observed/expected CpG ratio of at least 0.6.
+    pub fn gardiner_garden() -> Self {
+        Criteria {
+            min_length: 200,
+            min_gc_content: 0.5,
+            min_obs_exp: 0.6,
+            merge_distance: 0,
+        }
+    }
+
+    /// The criteria of Takai and Jones: at least 500 bases, a GC content of at least 55% and an
+    /// observed/expected CpG ratio of at least 0.65, merging islands at most 100 bases apart.
+    pub fn takai_jones() -> Self {
+        Criteria {
+            min_length: 500,
+            min_gc_content: 0.55,
+            min_obs_exp: 0.65,
+            merge_distance: 100,
+        }
+    }
+}
+
+/// A CpG island.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Island {
+    /// Start position of the island.
+    pub start: usize,
+    /// End position of the island (exclusive).
+    pub end: usize,
+    /// Fraction of bases which are guanine or cytosine.
+    pub gc_content: f32,
+    /// Ratio of observed to expected CpG dinucleotides, i.e. CpG * length / (C * G).
+    pub obs_exp: f32,
+    /// Number of CpG dinucleotides.
+    pub cpg_count: usize,
+}
+
+/// Prefix sums of cytosines, guanines and CpG dinucleotides, ignoring case.
+struct Counts {
+    c: Vec<usize>,
+    g: Vec<usize>,
+    /// Number of CpGs starting before each position.
+    cpg: Vec<usize>,
+}
+
+impl Counts {
+    fn new(sequence: &[u8]) -> Self {
+        let n = sequence.len();
+        let mut counts = Counts {
+            c: Vec::with_capacity(n + 1),
+            g: Vec::with_capacity(n + 1),
+            cpg: Vec::with_capacity(n + 1),
+        };
+        let (mut c, mut g, mut cpg) = (0, 0, 0);
+        for i in 0..=n {
+            counts.c.push(c);
+            counts.g.push(g);
+            counts.cpg.push(cpg);
+            if i < n {
+                match sequence[i].to_ascii_uppercase() {
+                    b'C' => {
+                        c += 1;
+                        if i + 1 < n && sequence[i + 1].eq_ignore_ascii_case(&b'G') {
+                            cpg += 1;
+                        }
+                    }
+                    b'G' => g += 1,
+                    _ => (),
+                }
+            }
+        }
+        counts
+    }
+
+    /// The island spanning the given interval.
+    fn island(&self, start: usize, end: usize) -> Island {
+        let (c, g) = (self.c[end] - self.c[start], self.g[end] - self.g[start]);
+        // only count CpGs that lie completely within the interval
+        let cpg_count = self.cpg[end.saturating_sub(1).max(start)] - self.cpg[start];
+        let len = (end - start) as f32;
+        Island {
+            start,
+            end,
+            gc_content: (c + g) as f32 / len,
+            obs_exp: if c == 0 || g == 0 {
+                0.0
+            } else {
+                cpg_count as f32 * len / (c as f32 * g as f32)
+            },
+            cpg_count,
+        }
+    }
+
+    fn is_island(&self, start: usize, end: usize, criteria: &Criteria) -> bool {
+        let island = self.island(start, end);
+        end - start >= criteria.min_length
+            && island.gc_content >= criteria.min_gc_content
+            && island.obs_exp >= criteria.min_obs_exp
+    }
+}
+
+/// Find the CpG islands in the given sequence that satisfy the given criteria, ordered by
+/// position.
+///
+/// # Panics
+///
+/// If the minimum length is zero.
+pub fn find_islands(sequence: &[u8], criteria: &Criteria) -> Vec<Island> {
+    assert!(criteria.min_length > 0, "minimum length must be positive");
+    let n = sequence.len();
+    let counts = Counts::new(sequence);
+    let window = criteria.min_length;
+    if n < window {
+        return Vec::new();
+    }
+
+    // merge overlapping or adjacent windows satisfying the criteria
+    let mut regions: Vec<(usize, usize)> = Vec::new();
+    for start in 0..=n - window {
+        let end = start + window;
+        if counts.is_island(start, end, criteria) {
+            match regions.last_mut() {
+                Some(last) if last.1 >= start => last.1 = end,
+                _ => regions.push((start, end)),
+            }
+        }
+    }
+
+    // trim to the first and last CpG
+    let mut islands: Vec<(usize, usize)> = Vec::new();
+    for (start, end) in regions {
+        let first = (start..end).find(|&i| counts.cpg[i + 1] > counts.cpg[i]);
+        let last = (start..end - 1)
+            .rev()
+            .find(|&i| counts.cpg[i + 1] > counts.cpg[i]);
+        if let (Some(first), Some(last)) = (first, last) {
+            let (start, end) = (first, last + 2);
+            if !counts.is_island(start, end, criteria) {
+                continue;
+            }
+            match islands.last_mut() {
+                Some(prev)
+                    if start - prev.1 <= criteria.merge_distance
+                        && counts.is_island(prev.0, end, criteria) =>
+                {
+                    prev.1 = end
+                }
+                _ => islands.push((start, end)),
+            }
+        }
+    }
+
+    islands
+        .into_iter()
+        .map(|(start, end)| counts.island(start, end))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::random_seq;
+    use rand::prelude::*;
+
+    #[test]
+    fn test_counts() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let seq = random_seq(&mut rng, b"ACGTcg", 300);
+        let counts = Counts::new(&seq);
+        for _ in 0..100 {
+            let start = rng.gen_range(0..299);
+            let end = rng.gen_range(start + 1..=300);
+            let region = seq[start..end].to_ascii_uppercase();
+            let c = region.iter().filter(|&&b| b == b'C').count();
+            let g = region.iter().filter(|&&b| b == b'G').count();
+            let cpg = region.windows(2).filter(|w| w == b"CG").count();
+            let island = counts.island(start, end);
+            assert_eq!(island.cpg_count, cpg);
+            assert_eq!(island.gc_content, (c + g) as f32 / (end - start) as f32);
+        }
+    }
+
+    #[test]
+    fn test_find_islands() {
+        let mut rng = StdRng::seed_from_u64(42);
+        // a background with low GC content
+        let mut seq = random_seq(&mut rng, b"ACGTAT", 1000);
+        seq.extend(random_seq(&mut rng, b"CG", 600));
+        seq.extend(random_seq(&mut rng, b"AT", 1000));
+
+        for criteria in &[Criteria::gardiner_garden(), Criteria::takai_jones()] {
+            let islands = find_islands(&seq, criteria);
+            assert_eq!(islands.len(), 1);
+            let island = islands[0];
+            assert!(island.start >= 1000 - criteria.min_length && island.start < 1010);
+            assert!(island.end > 1590 && island.end <= 1600);
+            assert_eq!(&seq[island.start..island.start + 2], b"CG");
+            assert_eq!(&seq[island.end - 2..island.end], b"CG");
+            assert!(island.obs_exp >= criteria.min_obs_exp);
+        }
+        assert!(find_islands(&seq[..1000], &Criteria::gardiner_garden()).is_empty());
+        assert!(find_islands(b"CG", &Criteria::gardiner_garden()).is_empty());
+    }
+
+    #[test]
+    fn test_merge_distance() {
+        let mut seq = b"CG".repeat(125);
+        seq.extend(b"AT".repeat(125));
+        seq.extend(b"CG".repeat(125));
+
+        let mut criteria = Criteria::gardiner_garden();
+        let islands = find_islands(&seq, &criteria);
+        assert_eq!(islands.len(), 2);
+        assert_eq!((islands[1].start, islands[1].end), (500, 750));
+
+        criteria.merge_distance = 250;
+        let islands = find_islands(&seq, &criteria);
+        assert_eq!(islands.len(), 1);
+        assert_eq!((islands[0].start, islands[0].end), (0, 750));
+        assert_eq!(islands[0].cpg_count, 250);
+    }
+}
@@ -0,0 +1,534 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Design of CRISPR guide RNAs: enumeration of protospacers adjacent to a PAM (protospacer
+//! adjacent motif) on both strands, and genome-wide search for off-target sites.
+//!
+//! The PAM may contain IUPAC ambiguity codes and is located either 3' of the protospacer (e.g.
+//! `NGG` for SpCas9) or 5' of it (e.g. `TTTV` for Cas12a). Guides are annotated with their GC
+//! content and flagged if it lies outside a given range, if they contain long homopolymers, or
+//! if they contain `TTTT`, which terminates transcription from Pol III promoters like U6.
+//!
+//! Off-target sites are sites followed (or preceded) by a PAM that differ from the protospacer
+//! by at most a given number of mismatches. They are found by backtracking in an
+//! [FM-index](../../data_structures/fmindex/index.html) of the genome, branching into
+//! mismatches as long as the budget allows. Note that the on-target site itself is reported as
+//! an off-target site without mismatches.
+//!
+//! Coordinates are 0-based and half-open on the forward strand of the sequence.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::seq_analysis::crispr::{Finder, OffTargetSearch, Pam};
+//! use bio_types::strand::ReqStrand;
+//!
+//! let seq = b"TTGACCGATTACAGGCTATGCATCCGGTTCCAAT";
+//! let finder = Finder::new(Pam::spcas9(), 20);
+//! let guides = finder.find_all(seq);
+//! // a guide on each strand
+//! assert_eq!(guides.len(), 2);
+//! assert_eq!(guides[0].protospacer, b"CCGATTACAGGCTATGCATC");
+//! assert_eq!(guides[0].pam, b"CGG");
+//! assert_eq!(guides[0].strand, ReqStrand::Forward);
+//! assert_eq!(guides[1].strand, ReqStrand::Reverse);
+//!
+//! let search = OffTargetSearch::new(vec![("chr1", &seq[..])], Pam::spcas9());
+//! let sites = search.search(&guides[0].protospacer, 2);
+//! assert_eq!(sites.len(), 1);
+//! assert_eq!((sites[0].start, sites[0].end, sites[0].mismatches), (4, 24, 0));
+//! ```
+
+use std::ops::Range;
+
+use bio_types::strand::ReqStrand;
+
+use crate::alphabets::{dna, iupac};
+use crate::data_structures::bwt::{bwt, less, Less, Occ, BWT};
+use crate::data_structures::fmindex::{FMIndex, FMIndexable};
+use crate::data_structures::suffix_array::{suffix_array, RawSuffixArray};
+
+/// Sampling rate of the occurrence array of the FM-index.
+const OCC_SAMPLING: u32 = 32;
+
+/// The side of the protospacer the PAM is located at, on the strand of the protospacer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PamSide {
+    FivePrime,
+    ThreePrime,
+}
+
+/// A protospacer adjacent motif.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pam {
+    /// The motif, possibly containing IUPAC ambiguity codes.
+    pub sequence: Vec<u8>,
+    pub side: PamSide,
+}
+
+impl Pam {
+    /// Create a new PAM.
+    pub fn new(sequence: &[u8], side: PamSide) -> Self {
+        Pam {
+            sequence: sequence.to_vec(),
+            side,
+        }
+    }
+
+    /// The PAM of SpCas9, `NGG` 3' of the protospacer.
+    pub fn spcas9() -> Self {
+        Pam::new(b"NGG", PamSide::ThreePrime)
+    }
+
+    /// The PAM of Cas12a (Cpf1), `TTTV` 5' of the protospacer.
+    pub fn cas12a() -> Self {
+        Pam::new(b"TTTV", PamSide::FivePrime)
+    }
+
+    /// The location of the PAM of the protospacer at the given interval and strand of a
+    /// sequence of length `len`, or `None` if it exceeds the sequence.
+    fn location(
+        &self,
+        protospacer: &Range<usize>,
+        strand: ReqStrand,
+        len: usize,
+    ) -> Option<Range<usize>> {
+        let n = self.sequence.len();
+        match (self.side, strand) {
+            (PamSide::ThreePrime, ReqStrand::Forward)
+            | (PamSide::FivePrime, ReqStrand::Reverse) => {
+                Some(protospacer.end..protospacer.end + n).filter(|pam| pam.end <= len)
+            }
+            _ => protospacer
+                .start
+                .checked_sub(n)
+                .map(|start| start..protospacer.start),
+        }
+    }
+
+    /// The PAM of the protospacer at the given interval and strand of the sequence, if the
+    /// sequence there matches the motif.
+    fn find(&self, seq: &[u8], protospacer: &Range<usize>, strand: ReqStrand) -> Option<Vec<u8>> {
+        let location = self.location(protospacer, strand, seq.len())?;
+        let pam = oriented(&seq[location], strand);
+        if self
+            .sequence
+            .iter()
+            .zip(&pam)
+            .all(|(&motif, &base)| iupac::contains(motif, base))
+        {
+            Some(pam)
+        } else {
+            None
+        }
+    }
+}
+
+/// A guide RNA, given by its protospacer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Guide {
+    /// Start position of the protospacer.
+    pub start: usize,
+    /// End position of the protospacer (exclusive).
+    pub end: usize,
+    pub strand: ReqStrand,
+    /// The protospacer 5' to 3', i.e. reverse complemented for the reverse strand.
+    pub protospacer: Vec<u8>,
+    /// The PAM 5' to 3' on the strand of the protospacer.
+    pub pam: Vec<u8>,
+    /// Fraction of guanines and cytosines in the protospacer.
+    pub gc_content: f64,
+    /// Length of the longest run of a single base in the protospacer.
+    pub longest_homopolymer: usize,
+    /// Whether the GC content is outside the range of the finder.
+    pub gc_flag: bool,
+    /// Whether the longest homopolymer exceeds the maximum of the finder.
+    pub homopolymer_flag: bool,
+    /// Whether the protospacer contains `TTTT`, a Pol III termination signal.
+    pub poly_t_flag: bool,
+}
+
+impl Guide {
+    /// Whether the guide has none of the flags set.
+    pub fn passes(&self) -> bool {
+        !(self.gc_flag || self.homopolymer_flag || self.poly_t_flag)
+    }
+}
+
+/// A finder for guide RNAs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finder {
+    pam: Pam,
+    length: usize,
+    min_gc: f64,
+    max_gc: f64,
+    max_homopolymer: usize,
+}
+
+impl Finder {
+    /// Create a new finder for protospacers of the given length, adjacent to the given PAM. By
+    /// default, guides with a GC content outside of 0.4 to 0.8 or homopolymers longer than 4
+    /// bases are flagged.
+    ///
+    /// # Panics
+    ///
+    /// If the protospacer length is zero.
+    pub fn new(pam: Pam, length: usize) -> Self {
+        assert!(length > 0, "protospacer length must be positive");
+        Finder {
+            pam,
+            length,
+            min_gc: 0.4,
+            max_gc: 0.8,
+            max_homopolymer: 4,
+        }
+    }
+
+    /// Set the range of GC contents that are not flagged.
+    pub fn gc_range(mut self, min_gc: f64, max_gc: f64) -> Self {
+        self.min_gc = min_gc;
+        self.max_gc = max_gc;
+        self
+    }
+
+    /// Set the maximal length of homopolymers that are not flagged.
+    pub fn max_homopolymer(mut self, max_homopolymer: usize) -> Self {
+        self.max_homopolymer = max_homopolymer;
+        self
+    }
+
+    /// Find all guides in the given sequence (ignoring case), ordered by position and strand
+    /// (forward first). Protospacers containing bases other than A, C, G, T are skipped.
+    pub fn find_all(&self, seq: &[u8]) -> Vec<Guide> {
+        let mut guides = Vec::new();
+        if seq.len() < self.length {
+            return guides;
+        }
+        for start in 0..=seq.len() - self.length {
+            let location = start..start + self.length;
+            if !seq[location.clone()].iter().all(|&b| is_base(b)) {
+                continue;
+            }
+            for &strand in &[ReqStrand::Forward, ReqStrand::Reverse] {
+                if let Some(pam) = self.pam.find(seq, &location, strand) {
+                    guides.push(self.guide(seq, location.clone(), strand, pam));
+                }
+            }
+        }
+        guides
+    }
+
+    fn guide(&self, seq: &[u8], location: Range<usize>, strand: ReqStrand, pam: Vec<u8>) -> Guide {
+        let protospacer = oriented(&seq[location.clone()], strand).to_ascii_uppercase();
+        let gc_content = protospacer
+            .iter()
+            .filter(|&&b| b == b'G' || b == b'C')
+            .count() as f64
+            / protospacer.len() as f64;
+        let longest_homopolymer = protospacer
+            .iter()
+            .enumerate()
+            .map(|(i, &b)| protospacer[i..].iter().take_while(|&&c| c == b).count())
+            .max()
+            .unwrap();
+        Guide {
+            start: location.start,
+            end: location.end,
+            strand,
+            gc_flag: gc_content < self.min_gc || gc_content > self.max_gc,
+            homopolymer_flag: longest_homopolymer > self.max_homopolymer,
+            poly_t_flag: protospacer.windows(4).any(|w| w == b"TTTT"),
+            protospacer,
+            pam,
+            gc_content,
+            longest_homopolymer,
+        }
+    }
+}
+
+/// A potential off-target site of a guide.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OffTarget {
+    /// Name of the sequence the site is located on.
+    pub contig: String,
+    /// Start position of the site.
+    pub start: usize,
+    /// End position of the site (exclusive).
+    pub end: usize,
+    pub strand: ReqStrand,
+    /// Number of mismatches to the protospacer.
+    pub mismatches: usize,
+    /// The site 5' to 3' on its strand.
+    pub sequence: Vec<u8>,
+    /// The PAM of the site 5' to 3' on its strand.
+    pub pam: Vec<u8>,
+}
+
+/// An FM-index of a genome for the search of off-target sites of guides.
+pub struct OffTargetSearch {
+    names: Vec<String>,
+    /// Intervals of the sequences in the text.
+    locations: Vec<Range<usize>>,
+    text: Vec<u8>,
+    sa: RawSuffixArray,
+    fmindex: FMIndex<BWT, Less, Occ>,
+    pam: Pam,
+}
+
+impl OffTargetSearch {
+    /// Build the index from the given named sequences, for sites adjacent to the given PAM.
+    /// Sequences are concatenated, separated by `N`, and bases other than A, C, G, T are
+    /// replaced by `N`.
+    pub fn new<'a, I>(sequences: I, pam: Pam) -> Self
+    where
+        I: IntoIterator<Item = (&'a str, &'a [u8])>,
+    {
+        let (mut names, mut locations, mut text) = (Vec::new(), Vec::new(), Vec::new());
+        for (name, seq) in sequences {
+            names.push(name.to_owned());
+            locations.push(text.len()..text.len() + seq.len());
+            text.extend(seq.iter().map(|&b| {
+                if is_base(b) {
+                    b.to_ascii_uppercase()
+                } else {
+                    b'N'
+                }
+            }));
+            text.push(b'N');
+        }
+        text.push(b'$');
+
+        let alphabet = dna::n_alphabet();
+        let sa = suffix_array(&text);
+        let bwt = bwt(&text, &sa);
+        let less = less(&bwt, &alphabet);
+        let occ = Occ::new(&bwt, OCC_SAMPLING, &alphabet);
+        OffTargetSearch {
+            names,
+            locations,
+            text,
+            sa,
+            fmindex: FMIndex::new(bwt, less, occ),
+            pam,
+        }
+    }
+
+    /// Find all sites with at most `max_mismatches` mismatches to the given protospacer
+    /// (given 5' to 3'), followed or preceded by the PAM, ordered by sequence, position and
+    /// strand (forward first).
+    pub fn search(&self, protospacer: &[u8], max_mismatches: usize) -> Vec<OffTarget> {
+        let protospacer = protospacer.to_ascii_uppercase();
+        let mut sites = Vec::new();
+        for &strand in &[ReqStrand::Forward, ReqStrand::Reverse] {
+            let pattern = oriented(&protospacer, strand);
+            for (lower, upper, mismatches) in self.backtrack(&pattern, max_mismatches) {
+                for &pos in &self.sa[lower..upper] {
+                    if let Some(site) = self.site(pos, pattern.len(), strand, mismatches) {
+                        sites.push(site);
+                    }
+                }
+            }
+        }
+        sites.sort_by(|a, b| {
+            let key = |site: &OffTarget| {
+                let contig = self.names.iter().position(|name| *name == site.contig);
+                (contig, site.start, site.strand == ReqStrand::Reverse)
+            };
+            key(a).cmp(&key(b))
+        });
+        sites
+    }
+
+    /// Suffix array intervals of the text matching the pattern with at most `max_mismatches`
+    /// mismatches, with their number of mismatches.
+    fn backtrack(&self, pattern: &[u8], max_mismatches: usize) -> Vec<(usize, usize, usize)> {
+        let mut intervals = Vec::new();
+        // unmatched prefix length, suffix array interval and number of mismatches
+        let mut stack = vec![(pattern.len(), 0, self.text.len(), 0)];
+        while let Some((remaining, lower, upper, mismatches)) = stack.pop() {
+            if remaining == 0 {
+                intervals.push((lower, upper, mismatches));
+                continue;
+            }
+            for &a in b"ACGT" {
+                let mismatches = mismatches + (a != pattern[remaining - 1]) as usize;
+                if mismatches > max_mismatches {
+                    continue;
+                }
+                let less = self.fmindex.less(a);
+                let lower = less
+                    + if lower > 0 {
+                        self.fmindex.occ(lower - 1, a)
+                    } else {
+                        0
+                    };
+                let upper = less + self.fmindex.occ(upper - 1, a);
+                if lower < upper {
+                    stack.push((remaining - 1, lower, upper, mismatches));
+                }
+            }
+        }
+        intervals
+    }
+
+    /// The site at the given position of the text, if it is adjacent to a PAM.
+    fn site(
+        &self,
+        pos: usize,
+        len: usize,
+        strand: ReqStrand,
+        mismatches: usize,
+    ) -> Option<OffTarget> {
+        // sites never span separators, since these are not matched
+        let contig = self
+            .locations
+            .iter()
+            .position(|location| location.contains(&pos))?;
+        let location = &self.locations[contig];
+        let seq = &self.text[location.clone()];
+        let site = pos - location.start..pos - location.start + len;
+        let pam = self.pam.find(seq, &site, strand)?;
+        Some(OffTarget {
+            contig: self.names[contig].clone(),
+            sequence: oriented(&seq[site.clone()], strand),
+            start: site.start,
+            end: site.end,
+            strand,
+            mismatches,
+            pam,
+        })
+    }
+}
+
+/// The sequence 5' to 3' on the given strand.
+fn oriented(seq: &[u8], strand: ReqStrand) -> Vec<u8> {
+    match strand {
+        ReqStrand::Forward => seq.to_vec(),
+        ReqStrand::Reverse => dna::revcomp(seq),
+    }
+}
+
+fn is_base(base: u8) -> bool {
+    matches!(base.to_ascii_uppercase(), b'A' | b'C' | b'G' | b'T')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::random_seq;
+    use rand::prelude::*;
+
+    #[test]
+    fn test_find_all() {
+        // a guide on the reverse strand, with a PAM (CCN) on the forward strand
+        let seq = b"AACCTGATCGATCGATCGATCGATCAA";
+        let guides = Finder::new(Pam::spcas9(), 20).find_all(seq);
+        assert_eq!(guides.len(), 1);
+        let guide = &guides[0];
+        assert_eq!((guide.start, guide.end), (5, 25));
+        assert_eq!(guide.strand, ReqStrand::Reverse);
+        assert_eq!(guide.protospacer, dna::revcomp(&seq[5..25]));
+        assert_eq!(guide.pam, b"AGG");
+        assert_relative_eq!(guide.gc_content, 0.5);
+        assert!(guide.passes());
+
+        // Cas12a with a 5' PAM
+        let seq = b"GGTTTACCCCCCTTTTGGATAGCAAT";
+        let guides = Finder::new(Pam::cas12a(), 20).find_all(seq);
+        assert_eq!(guides.len(), 1);
+        assert_eq!((guides[0].start, guides[0].end), (6, 26));
+        assert_eq!(guides[0].pam, b"TTTA");
+        assert_eq!(guides[0].longest_homopolymer, 6);
+        assert!(guides[0].homopolymer_flag && guides[0].poly_t_flag && !guides[0].gc_flag);
+        assert!(!guides[0].passes());
+        let guides = Finder::new(Pam::cas12a(), 20)
+            .max_homopolymer(6)
+            .gc_range(0.55, 0.8)
+            .find_all(seq);
+        assert!(!guides[0].homopolymer_flag && guides[0].gc_flag);
+
+        assert!(Finder::new(Pam::spcas9(), 20).find_all(b"ACGG").is_empty());
+        // no guides across ambiguous bases
+        assert!(Finder::new(Pam::spcas9(), 5)
+            .find_all(b"ACNGTAGG")
+            .is_empty());
+    }
+
+    #[test]
+    fn test_search() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let pam = Pam::spcas9();
+        let protospacer = random_seq(&mut rng, b"ACGT", 20);
+        let mut chr1 = random_seq(&mut rng, b"ACGT", 500);
+        // an exact site and a site with two mismatches on the reverse strand
+        chr1.splice(100..123, [&protospacer[..], b"TGG"].concat());
+        let mut site = [&protospacer[..], b"AGG"].concat();
+        site[3] = dna::complement(site[3]);
+        site[15] = dna::complement(site[15]);
+        chr1.splice(300..323, dna::revcomp(&site));
+        // a site with a mismatch on a second sequence, and one without PAM
+        let mut chr2 = random_seq(&mut rng, b"ACGT", 300);
+        let mut site = [&protospacer[..], b"CGG"].concat();
+        site[19] = dna::complement(site[19]);
+        chr2.splice(50..73, site);
+        chr2.splice(200..223, [&protospacer[..], b"TAA"].concat());
+
+        let search = OffTargetSearch::new(vec![("chr1", &chr1[..]), ("chr2", &chr2[..])], pam);
+        let summary = |max_mismatches| -> Vec<(String, usize, ReqStrand, usize)> {
+            search
+                .search(&protospacer, max_mismatches)
+                .into_iter()
+                .map(|site| (site.contig, site.start, site.strand, site.mismatches))
+                .collect()
+        };
+        assert_eq!(
+            summary(0),
+            [("chr1".to_owned(), 100, ReqStrand::Forward, 0)]
+        );
+        assert_eq!(
+            summary(2),
+            [
+                ("chr1".to_owned(), 100, ReqStrand::Forward, 0),
+                ("chr1".to_owned(), 303, ReqStrand::Reverse, 2),
+                ("chr2".to_owned(), 50, ReqStrand::Forward, 1),
+            ]
+        );
+        let sites = search.search(&protospacer, 2);
+        assert_eq!(sites[1].pam, b"AGG");
+        assert_eq!(sites[2].sequence[..19], protospacer[..19]);
+    }
+
+    #[test]
+    fn test_search_exhaustive() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let seq = random_seq(&mut rng, b"ACGT", 3000);
+        let pam = Pam::new(b"NRG", PamSide::ThreePrime);
+        let search = OffTargetSearch::new(vec![("chr1", &seq[..])], pam.clone());
+        for _ in 0..5 {
+            let protospacer = random_seq(&mut rng, b"ACGT", 8);
+            let sites = search.search(&protospacer, 2);
+            // compare with a naive search over all windows
+            let mut expected = Vec::new();
+            for start in 0..=seq.len() - 8 {
+                for &strand in &[ReqStrand::Forward, ReqStrand::Reverse] {
+                    let window = oriented(&seq[start..start + 8], strand);
+                    let mismatches = window
+                        .iter()
+                        .zip(&protospacer)
+                        .filter(|(a, b)| a != b)
+                        .count();
+                    if mismatches <= 2 && pam.find(&seq, &(start..start + 8), strand).is_some() {
+                        expected.push((start, strand, mismatches));
+                    }
+                }
+            }
+            let found: Vec<(usize, ReqStrand, usize)> = sites
+                .iter()
+                .map(|site| (site.start, site.strand, site.mismatches))
+                .collect();
+            assert_eq!(found, expected);
+        }
+    }
+}
@@ -3,11 +3,14 @@
 // This file may not be copied, modified, or distributed
 // except according to those terms.
 
-//! GC counter over an `IntoTextIterator` object.
+//! GC counter over an `IntoTextIterator` object, GC content and skews over sliding windows, and
+//! the cumulative GC skew, whose minimum estimates the origin of replication of bacterial
+//! genomes (Grigoriev, Nucleic Acids Research, 1998).
 
 //! Complexity: O(n), where n is the length of the sequence.
 
 use std::borrow::Borrow;
+use std::iter;
 
 /// Base gc content counter
 fn gcn_content<C: Borrow<u8>, T: IntoIterator<Item = C>>(sequence: T, step: usize) -> f32 {
@@ -60,6 +63,170 @@ pub fn gc3_content<C: Borrow<u8>, T: IntoIterator<Item = C>>(sequence: T) -> f32
     gcn_content(sequence, 3usize)
 }
 
+/// Base composition of a window, ignoring case.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Counts {
+    a: usize,
+    c: usize,
+    g: usize,
+    t: usize,
+}
+
+impl Counts {
+    fn update(&mut self, base: u8, delta: isize) {
+        let count = match base {
+            b'A' | b'a' => &mut self.a,
+            b'C' | b'c' => &mut self.c,
+            b'G' | b'g' => &mut self.g,
+            b'T' | b't' => &mut self.t,
+            _ => return,
+        };
+        *count = (*count as isize + delta) as usize;
+    }
+}
+
+/// (x - y) / (x + y), or 0 if both are zero.
+fn skew(x: usize, y: usize) -> f32 {
+    if x + y == 0 {
+        0.0
+    } else {
+        (x as f32 - y as f32) / (x + y) as f32
+    }
+}
+
+/// GC content and skews of a window of a sequence.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Window {
+    /// Start position of the window.
+    pub start: usize,
+    /// End position of the window (exclusive).
+    pub end: usize,
+    /// Fraction of bases which are guanine or cytosine.
+    pub gc_content: f32,
+    /// GC skew, i.e. (G - C) / (G + C).
+    pub gc_skew: f32,
+    /// AT skew, i.e. (A - T) / (A + T).
+    pub at_skew: f32,
+}
+
+/// Iterator over GC content and skews in windows of a sequence.
+pub struct Windows<'a> {
+    sequence: &'a [u8],
+    window: usize,
+    step: usize,
+    start: usize,
+    /// Counts of the previous window and its start position.
+    counts: Option<(Counts, usize)>,
+}
+
+impl<'a> Iterator for Windows<'a> {
+    type Item = Window;
+
+    fn next(&mut self) -> Option<Window> {
+        let (start, end) = (self.start, self.start + self.window);
+        if end > self.sequence.len() {
+            return None;
+        }
+        let counts = match self.counts {
+            // slide the previous window if it overlaps
+            Some((mut counts, prev_start)) if prev_start + self.window > start => {
+                for &base in &self.sequence[prev_start..start] {
+                    counts.update(base, -1);
+                }
+                for &base in &self.sequence[prev_start + self.window..end] {
+                    counts.update(base, 1);
+                }
+                counts
+            }
+            _ => {
+                let mut counts = Counts::default();
+                for &base in &self.sequence[start..end] {
+                    counts.update(base, 1);
+                }
+                counts
+            }
+        };
+        self.counts = Some((counts, start));
+        self.start += self.step;
+
+        Some(Window {
+            start,
+            end,
+            gc_content: (counts.g + counts.c) as f32 / self.window as f32,
+            gc_skew: skew(counts.g, counts.c),
+            at_skew: skew(counts.a, counts.t),
+        })
+    }
+}
+
+/// Returns an iterator over the GC content, GC skew and AT skew of the given sequence in
+/// windows of the given size, shifted by the given step. Windows extending beyond the end of the
+/// sequence are omitted.
+///
+/// Complexity: O(n) if the step is not larger than the window, O(n * window / step) otherwise.
+///
+/// # Panics
+///
+/// If the window or step are zero.
+///
+/// # Example
+///
+/// ```
+/// use bio::seq_analysis::gc::windows;
+///
+/// let seq = b"GGGGCCAATTTT";
+/// let gc: Vec<f32> = windows(seq, 4, 4).map(|w| w.gc_content).collect();
+/// assert_eq!(gc, [1.0, 0.5, 0.0]);
+/// let skew: Vec<f32> = windows(seq, 6, 6).map(|w| w.gc_skew).collect();
+/// assert_eq!(skew, [(4. - 2.) / 6., 0.0]);
+/// ```
+pub fn windows(sequence: &[u8], window: usize, step: usize) -> Windows<'_> {
+    assert!(window > 0 && step > 0, "window and step must be positive");
+    Windows {
+        sequence,
+        window,
+        step,
+        start: 0,
+        counts: None,
+    }
+}
+
+/// Returns an iterator over the cumulative GC skew, i.e. the number of guanines minus the number
+/// of cytosines in each prefix of the sequence (of length 1 to n).
+pub fn cumulative_gc_skew<C: Borrow<u8>, T: IntoIterator<Item = C>>(
+    sequence: T,
+) -> impl Iterator<Item = i64> {
+    sequence.into_iter().scan(0, |skew, base| {
+        match *base.borrow() {
+            b'G' | b'g' => *skew += 1,
+            b'C' | b'c' => *skew -= 1,
+            _ => (),
+        }
+        Some(*skew)
+    })
+}
+
+/// Returns the length of the first prefix of the sequence with minimum cumulative GC skew,
+/// including the empty prefix. On the leading strand of bacterial chromosomes, guanine is
+/// enriched over cytosine, such that this position estimates the origin of replication.
+///
+/// # Example
+///
+/// ```
+/// use bio::seq_analysis::gc::gc_skew_minimum;
+///
+/// // the skew drops until position 5 and rises afterwards
+/// assert_eq!(gc_skew_minimum(b"CCACCGGTGGAG"), 5);
+/// assert_eq!(gc_skew_minimum(b"GGA"), 0);
+/// ```
+pub fn gc_skew_minimum<C: Borrow<u8>, T: IntoIterator<Item = C>>(sequence: T) -> usize {
+    iter::once(0)
+        .chain(cumulative_gc_skew(sequence))
+        .enumerate()
+        .min_by_key(|&(i, skew)| (skew, i))
+        .map_or(0, |(i, _)| i)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -82,4 +249,35 @@ mod tests {
         let gc_content = gc_content(s);
         assert_relative_eq!(gc_content, 0.5, epsilon = f32::EPSILON);
     }
+
+    #[test]
+    fn test_windows_against_naive() {
+        use crate::utils::testing::random_seq;
+        use rand::prelude::*;
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let seq = random_seq(&mut rng, b"ACGTacgtN", 500);
+        for &(window, step) in &[(1, 1), (10, 1), (10, 3), (10, 10), (10, 25), (500, 1)] {
+            let result: Vec<Window> = windows(&seq, window, step).collect();
+            assert_eq!(result.len(), (seq.len() - window) / step + 1);
+            for w in result {
+                let slice = &seq[w.start..w.end];
+                let count = |bases: &[u8]| slice.iter().filter(|b| bases.contains(b)).count();
+                let (a, c, g, t) = (count(b"Aa"), count(b"Cc"), count(b"Gg"), count(b"Tt"));
+                assert_eq!(w.end - w.start, window);
+                assert_relative_eq!(w.gc_content, gc_content(slice), epsilon = 1e-6);
+                assert_relative_eq!(w.gc_skew, skew(g, c), epsilon = 1e-6);
+                assert_relative_eq!(w.at_skew, skew(a, t), epsilon = 1e-6);
+            }
+        }
+        assert_eq!(windows(b"ACG", 4, 1).count(), 0);
+    }
+
+    #[test]
+    fn test_cumulative_gc_skew() {
+        let skews: Vec<i64> = cumulative_gc_skew(b"GGCAcCg").collect();
+        assert_eq!(skews, [1, 2, 1, 1, 0, -1, 0]);
+        assert_eq!(gc_skew_minimum(b""), 0);
+        assert_eq!(gc_skew_minimum(b"GGCAcCg"), 6);
+    }
 }
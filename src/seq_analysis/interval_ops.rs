@@ -0,0 +1,1124 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Set operations on sorted streams of genomic intervals, in the style of
+//! [bedtools](https://bedtools.readthedocs.io).
+//!
+//! All operations consume intervals that are sorted by contig and start position, and
+//! process them in a single pass, keeping only the intervals of the second input that
+//! can still overlap the current interval of the first input in memory.
+//! Sortedness is checked on the fly; violations are reported as
+//! [`Error::Unsorted`](enum.Error.html#variant.Unsorted).
+//!
+//! Operations on a single input ([`merge`](fn.merge.html)) only require the intervals of
+//! a contig to be consecutive. Operations on two inputs have to know the order of contigs
+//! in order to advance both inputs in lockstep: contigs are either sorted lexicographically
+//! (as with `sort -k1,1 -k2,2n`), or in the order given by a [`Genome`](struct.Genome.html).
+//!
+//! Any type implementing [`GenomicInterval`](trait.GenomicInterval.html) can be used as
+//! input, in particular BED and GFF records. Coordinates are 0-based and half-open
+//! (GFF records are converted accordingly).
+//!
+//! # Example
+//!
+//! ```
+//! use bio::io::bed;
+//! use bio::seq_analysis::interval_ops::{intersect, merge, Region};
+//!
+//! let genes = b"chr1\t100\t200\tgeneA\nchr1\t150\t300\tgeneB\nchr2\t50\t80\tgeneC\n";
+//! let peaks = b"chr1\t180\t190\nchr2\t0\t60\n";
+//! let genes: Vec<bed::Record> = bed::Reader::new(&genes[..])
+//!     .records()
+//!     .collect::<Result<_, _>>()
+//!     .unwrap();
+//! let peaks: Vec<bed::Record> = bed::Reader::new(&peaks[..])
+//!     .records()
+//!     .collect::<Result<_, _>>()
+//!     .unwrap();
+//!
+//! let merged: Vec<Region> = merge(genes.iter().cloned(), 0)
+//!     .collect::<Result<_, _>>()
+//!     .unwrap();
+//! assert_eq!(
+//!     merged,
+//!     vec![Region::new("chr1", 100, 300), Region::new("chr2", 50, 80)]
+//! );
+//!
+//! let hits: Vec<(bed::Record, bed::Record)> = intersect(genes, peaks, None)
+//!     .collect::<Result<_, _>>()
+//!     .unwrap();
+//! let names: Vec<_> = hits.iter().map(|(gene, _)| gene.name().unwrap()).collect();
+//! assert_eq!(names, vec!["geneA", "geneB", "geneC"]);
+//! ```
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::convert::AsRef;
+use std::fs;
+use std::io;
+use std::io::prelude::*;
+use std::path::Path;
+
+use anyhow::Context;
+use thiserror::Error;
+
+use crate::io::{bed, gff};
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("can't read genome file")]
+    ReadError(#[from] io::Error),
+
+    #[error("invalid line in genome file: '{0}'")]
+    InvalidGenomeLine(String),
+
+    #[error("input is not sorted: {contig}:{start} follows {previous_contig}:{previous_start}")]
+    Unsorted {
+        contig: String,
+        start: u64,
+        previous_contig: String,
+        previous_start: u64,
+    },
+
+    #[error("contig {0} is not contained in the genome")]
+    UnknownContig(String),
+}
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// An interval on a contig, given by 0-based, half-open coordinates.
+pub trait GenomicInterval {
+    /// Name of the contig.
+    fn contig(&self) -> &str;
+
+    /// Start position (0-based).
+    fn start(&self) -> u64;
+
+    /// End position (0-based, not included).
+    fn end(&self) -> u64;
+}
+
+impl GenomicInterval for bed::Record {
+    fn contig(&self) -> &str {
+        self.chrom()
+    }
+
+    fn start(&self) -> u64 {
+        self.start()
+    }
+
+    fn end(&self) -> u64 {
+        self.end()
+    }
+}
+
+impl GenomicInterval for gff::Record {
+    fn contig(&self) -> &str {
+        self.seqname()
+    }
+
+    fn start(&self) -> u64 {
+        self.start().saturating_sub(1)
+    }
+
+    fn end(&self) -> u64 {
+        *self.end()
+    }
+}
+
+impl<T: GenomicInterval> GenomicInterval for &T {
+    fn contig(&self) -> &str {
+        (*self).contig()
+    }
+
+    fn start(&self) -> u64 {
+        (*self).start()
+    }
+
+    fn end(&self) -> u64 {
+        (*self).end()
+    }
+}
+
+/// A plain genomic interval, as produced by operations that create new intervals.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Region {
+    pub contig: String,
+    pub start: u64,
+    pub end: u64,
+}
+
+impl Region {
+    /// Create a new region from 0-based, half-open coordinates.
+    pub fn new(contig: &str, start: u64, end: u64) -> Self {
+        Region {
+            contig: contig.to_owned(),
+            start,
+            end,
+        }
+    }
+
+    fn of<T: GenomicInterval>(interval: &T) -> Self {
+        Region::new(interval.contig(), interval.start(), interval.end())
+    }
+}
+
+impl GenomicInterval for Region {
+    fn contig(&self) -> &str {
+        &self.contig
+    }
+
+    fn start(&self) -> u64 {
+        self.start
+    }
+
+    fn end(&self) -> u64 {
+        self.end
+    }
+}
+
+impl From<Region> for bed::Record {
+    fn from(region: Region) -> Self {
+        let mut record = bed::Record::new();
+        record.set_chrom(&region.contig);
+        record.set_start(region.start);
+        record.set_end(region.end);
+        record
+    }
+}
+
+/// Contig names and lengths, defining the order of contigs, as given by a
+/// bedtools genome file (also known as "chrom sizes" file, or the first two columns
+/// of a FASTA index).
+#[derive(Debug, Clone, Default)]
+pub struct Genome {
+    contigs: Vec<(String, u64)>,
+    ranks: HashMap<String, usize>,
+}
+
+impl Genome {
+    /// Read a genome file: one contig per line, given by its name and length,
+    /// separated by a tab. Further columns are ignored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::seq_analysis::interval_ops::Genome;
+    ///
+    /// let genome = Genome::new(&b"chr1\t248956422\nchr2\t242193529\n"[..]).unwrap();
+    /// assert_eq!(genome.contig_len("chr2"), Some(242193529));
+    /// assert_eq!(genome.contig_len("chrM"), None);
+    /// ```
+    pub fn new<R: io::Read>(reader: R) -> Result<Self> {
+        let mut contigs = Vec::new();
+        for line in io::BufReader::new(reader).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let mut fields = line.split('\t');
+            match (
+                fields.next(),
+                fields.next().map(|l| l.trim().parse::<u64>()),
+            ) {
+                (Some(name), Some(Ok(len))) => contigs.push((name.to_owned(), len)),
+                _ => return Err(Error::InvalidGenomeLine(line)),
+            }
+        }
+        Ok(contigs.into_iter().collect())
+    }
+
+    /// Read a genome file from a given path.
+    pub fn from_file<P: AsRef<Path> + std::fmt::Debug>(path: P) -> anyhow::Result<Self> {
+        let file = fs::File::open(&path)
+            .with_context(|| format!("Failed to read genome from {:#?}", path))?;
+        Ok(Genome::new(file)?)
+    }
+
+    /// Length of the given contig.
+    pub fn contig_len(&self, contig: &str) -> Option<u64> {
+        self.ranks.get(contig).map(|&rank| self.contigs[rank].1)
+    }
+
+    /// Iterate over contig names and lengths, in order.
+    pub fn contigs(&self) -> impl Iterator<Item = (&str, u64)> {
+        self.contigs.iter().map(|(name, len)| (name.as_str(), *len))
+    }
+
+    fn rank(&self, contig: &str) -> Result<usize> {
+        self.ranks
+            .get(contig)
+            .copied()
+            .ok_or_else(|| Error::UnknownContig(contig.to_owned()))
+    }
+}
+
+impl std::iter::FromIterator<(String, u64)> for Genome {
+    fn from_iter<I: IntoIterator<Item = (String, u64)>>(contigs: I) -> Self {
+        let contigs: Vec<_> = contigs.into_iter().collect();
+        let ranks = contigs
+            .iter()
+            .enumerate()
+            .map(|(rank, (name, _))| (name.clone(), rank))
+            .collect();
+        Genome { contigs, ranks }
+    }
+}
+
+/// The required order of contigs in an input.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Order<'a> {
+    /// Intervals of a contig are consecutive, contigs can be in any order.
+    Grouped,
+    Lexicographic,
+    Genome(&'a Genome),
+}
+
+impl<'a> Order<'a> {
+    fn new(genome: Option<&'a Genome>) -> Self {
+        genome.map_or(Order::Lexicographic, Order::Genome)
+    }
+
+    /// Compare two contigs. With `Grouped`, contigs are unordered and only equality
+    /// can be determined.
+    fn cmp(self, a: &str, b: &str) -> Result<Ordering> {
+        Ok(match self {
+            Order::Grouped if a == b => Ordering::Equal,
+            Order::Grouped => Ordering::Greater,
+            Order::Lexicographic => a.cmp(b),
+            Order::Genome(genome) => genome.rank(a)?.cmp(&genome.rank(b)?),
+        })
+    }
+}
+
+/// An iterator checking that intervals are sorted.
+pub(crate) struct Checked<'a, I: Iterator> {
+    inner: I,
+    order: Order<'a>,
+    previous: Option<(String, u64)>,
+    seen: HashSet<String>,
+}
+
+impl<'a, I> Checked<'a, I>
+where
+    I: Iterator,
+    I::Item: GenomicInterval,
+{
+    pub(crate) fn new(inner: I, order: Order<'a>) -> Self {
+        Checked {
+            inner,
+            order,
+            previous: None,
+            seen: HashSet::new(),
+        }
+    }
+
+    fn unsorted(&self, interval: &I::Item) -> Error {
+        let (previous_contig, previous_start) = self.previous.clone().unwrap();
+        Error::Unsorted {
+            contig: interval.contig().to_owned(),
+            start: interval.start(),
+            previous_contig,
+            previous_start,
+        }
+    }
+
+    fn check(&mut self, interval: &I::Item) -> Result<()> {
+        let contig = interval.contig();
+        if let Order::Genome(genome) = self.order {
+            genome.rank(contig)?;
+        }
+        let new_contig = match &self.previous {
+            Some((previous_contig, previous_start)) if previous_contig == contig => {
+                if interval.start() < *previous_start {
+                    return Err(self.unsorted(interval));
+                }
+                false
+            }
+            Some((previous_contig, _)) => {
+                let sorted = match self.order {
+                    Order::Grouped => !self.seen.contains(contig),
+                    order => order.cmp(contig, previous_contig)? == Ordering::Greater,
+                };
+                if !sorted {
+                    return Err(self.unsorted(interval));
+                }
+                true
+            }
+            None => true,
+        };
+        if new_contig {
+            if let Order::Grouped = self.order {
+                self.seen.insert(contig.to_owned());
+            }
+            self.previous = Some((contig.to_owned(), interval.start()));
+        } else if let Some((_, previous_start)) = self.previous.as_mut() {
+            *previous_start = interval.start();
+        }
+        Ok(())
+    }
+}
+
+impl<'a, I> Iterator for Checked<'a, I>
+where
+    I: Iterator,
+    I::Item: GenomicInterval,
+{
+    type Item = Result<I::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let interval = self.inner.next()?;
+        Some(self.check(&interval).map(|_| interval))
+    }
+}
+
+/// The second input of a two-input operation: a window of the intervals on the current
+/// contig, which is advanced along with the first input.
+struct Sweep<'a, I: Iterator> {
+    inner: Checked<'a, I>,
+    lookahead: Option<I::Item>,
+    contig: String,
+    /// Intervals on the current contig that may overlap the current query, sorted by start.
+    buffer: Vec<I::Item>,
+    /// The interval with the largest end among those that were dropped from the buffer.
+    upstream: Option<I::Item>,
+}
+
+impl<'a, I> Sweep<'a, I>
+where
+    I: Iterator,
+    I::Item: GenomicInterval,
+{
+    fn new(inner: I, order: Order<'a>) -> Self {
+        Sweep {
+            inner: Checked::new(inner, order),
+            lookahead: None,
+            contig: String::new(),
+            buffer: Vec::new(),
+            upstream: None,
+        }
+    }
+
+    fn peek(&mut self) -> Result<Option<&I::Item>> {
+        if self.lookahead.is_none() {
+            self.lookahead = self.inner.next().transpose()?;
+        }
+        Ok(self.lookahead.as_ref())
+    }
+
+    /// Move to the given contig, skipping all intervals on preceding contigs.
+    fn seek(&mut self, contig: &str) -> Result<()> {
+        if self.contig != contig {
+            self.contig = contig.to_owned();
+            self.buffer.clear();
+            self.upstream = None;
+        }
+        let order = self.inner.order;
+        while let Some(interval) = self.peek()? {
+            if order.cmp(interval.contig(), contig)? != Ordering::Less {
+                break;
+            }
+            self.lookahead = None;
+        }
+        Ok(())
+    }
+
+    /// Buffer all intervals on the current contig that start before `until`.
+    fn load(&mut self, until: u64) -> Result<()> {
+        loop {
+            self.peek()?;
+            match self.lookahead.take() {
+                Some(interval) if interval.start() < until && interval.contig() == self.contig => {
+                    self.buffer.push(interval)
+                }
+                interval => {
+                    self.lookahead = interval;
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Drop buffered intervals that end at or before `from`.
+    fn prune(&mut self, from: u64) {
+        // the dropped interval with the largest end becomes the upstream neighbor
+        let last = self
+            .buffer
+            .iter()
+            .enumerate()
+            .filter(|(_, interval)| interval.end() <= from)
+            .max_by_key(|(_, interval)| interval.end())
+            .map(|(i, _)| i);
+        if let Some(i) = last {
+            if self
+                .upstream
+                .as_ref()
+                .is_none_or(|upstream| self.buffer[i].end() >= upstream.end())
+            {
+                self.upstream = Some(self.buffer.remove(i));
+            }
+        }
+        self.buffer.retain(|interval| interval.end() > from);
+    }
+
+    /// Advance to the given query range on the given contig.
+    fn advance(&mut self, contig: &str, start: u64, end: u64) -> Result<()> {
+        self.seek(contig)?;
+        self.load(end)?;
+        self.prune(start);
+        Ok(())
+    }
+}
+
+/// Iterator over merged intervals, see [`merge`](fn.merge.html).
+pub struct Merge<I: Iterator> {
+    inner: Checked<'static, I>,
+    distance: u64,
+    current: Option<Region>,
+    error_has_occured: bool,
+}
+
+impl<I> Iterator for Merge<I>
+where
+    I: Iterator,
+    I::Item: GenomicInterval,
+{
+    type Item = Result<Region>;
+
+    fn next(&mut self) -> Option<Result<Region>> {
+        if self.error_has_occured {
+            return None;
+        }
+        loop {
+            let interval = match self.inner.next() {
+                Some(Ok(interval)) => interval,
+                Some(Err(e)) => {
+                    self.error_has_occured = true;
+                    return Some(Err(e));
+                }
+                None => return self.current.take().map(Ok),
+            };
+            match self.current.as_mut() {
+                Some(current)
+                    if current.contig == interval.contig()
+                        && interval.start() <= current.end + self.distance =>
+                {
+                    current.end = current.end.max(interval.end());
+                }
+                _ => {
+                    let previous = self.current.replace(Region::of(&interval));
+                    if previous.is_some() {
+                        return previous.map(Ok);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Merge overlapping or book-ended intervals, as well as intervals that are at most
+/// `distance` bases apart (like `bedtools merge -d`).
+///
+/// Intervals of the same contig have to be consecutive and sorted by start position.
+///
+/// # Example
+///
+/// ```
+/// use bio::seq_analysis::interval_ops::{merge, Region};
+///
+/// let intervals = vec![
+///     Region::new("chr1", 0, 10),
+///     Region::new("chr1", 5, 20),
+///     Region::new("chr1", 22, 30),
+///     Region::new("chr1", 40, 50),
+/// ];
+/// let merged: Vec<Region> = merge(intervals, 2).collect::<Result<_, _>>().unwrap();
+/// assert_eq!(
+///     merged,
+///     vec![Region::new("chr1", 0, 30), Region::new("chr1", 40, 50)]
+/// );
+/// ```
+pub fn merge<I>(intervals: I, distance: u64) -> Merge<I::IntoIter>
+where
+    I: IntoIterator,
+    I::Item: GenomicInterval,
+{
+    Merge {
+        inner: Checked::new(intervals.into_iter(), Order::Grouped),
+        distance,
+        current: None,
+        error_has_occured: false,
+    }
+}
+
+/// Iterator over the complement of intervals, see [`complement`](fn.complement.html).
+pub struct Complement<'a, I: Iterator> {
+    inner: Checked<'a, I>,
+    genome: &'a Genome,
+    /// Rank of the current contig, and end of the covered part of it.
+    rank: usize,
+    covered: u64,
+    pending: VecDeque<Region>,
+    done: bool,
+}
+
+impl<'a, I> Complement<'a, I>
+where
+    I: Iterator,
+    I::Item: GenomicInterval,
+{
+    /// Emit the uncovered remainder of the current contig and move to the next one.
+    fn finish_contig(&mut self) {
+        let (contig, len) = &self.genome.contigs[self.rank];
+        if self.covered < *len {
+            self.pending
+                .push_back(Region::new(contig, self.covered, *len));
+        }
+        self.rank += 1;
+        self.covered = 0;
+    }
+
+    fn advance(&mut self) -> Result<()> {
+        match self.inner.next().transpose()? {
+            Some(interval) => {
+                let rank = self.genome.rank(interval.contig())?;
+                while self.rank < rank {
+                    self.finish_contig();
+                }
+                let len = self.genome.contigs[rank].1;
+                let start = interval.start().min(len);
+                if start > self.covered {
+                    self.pending
+                        .push_back(Region::new(interval.contig(), self.covered, start));
+                }
+                self.covered = self.covered.max(interval.end().min(len));
+            }
+            None => {
+                while self.rank < self.genome.contigs.len() {
+                    self.finish_contig();
+                }
+                self.done = true;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a, I> Iterator for Complement<'a, I>
+where
+    I: Iterator,
+    I::Item: GenomicInterval,
+{
+    type Item = Result<Region>;
+
+    fn next(&mut self) -> Option<Result<Region>> {
+        loop {
+            if let Some(region) = self.pending.pop_front() {
+                return Some(Ok(region));
+            }
+            if self.done {
+                return None;
+            }
+            if let Err(e) = self.advance() {
+                self.done = true;
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
+/// Report all parts of the genome that are not covered by any interval (like
+/// `bedtools complement`).
+///
+/// Intervals have to be sorted in the order of contigs in the genome.
+///
+/// # Example
+///
+/// ```
+/// use bio::seq_analysis::interval_ops::{complement, Genome, Region};
+///
+/// let genome = Genome::new(&b"chr1\t100\nchr2\t50\n"[..]).unwrap();
+/// let intervals = vec![Region::new("chr1", 10, 20), Region::new("chr1", 15, 100)];
+/// let gaps: Vec<Region> = complement(intervals, &genome)
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+/// assert_eq!(
+///     gaps,
+///     vec![Region::new("chr1", 0, 10), Region::new("chr2", 0, 50)]
+/// );
+/// ```
+pub fn complement<I>(intervals: I, genome: &Genome) -> Complement<'_, I::IntoIter>
+where
+    I: IntoIterator,
+    I::Item: GenomicInterval,
+{
+    Complement {
+        inner: Checked::new(intervals.into_iter(), Order::Genome(genome)),
+        genome,
+        rank: 0,
+        covered: 0,
+        pending: VecDeque::new(),
+        done: false,
+    }
+}
+
+/// Shared state of the two-input operations: the first input, the sweep over the second
+/// input, and the results for the current interval of the first input.
+struct TwoInputs<'a, A: Iterator, B: Iterator, T> {
+    a: Checked<'a, A>,
+    b: Sweep<'a, B>,
+    pending: VecDeque<T>,
+    done: bool,
+}
+
+impl<'a, A, B, T> TwoInputs<'a, A, B, T>
+where
+    A: Iterator,
+    A::Item: GenomicInterval,
+    B: Iterator,
+    B::Item: GenomicInterval,
+{
+    fn new(a: A, b: B, genome: Option<&'a Genome>) -> Self {
+        let order = Order::new(genome);
+        TwoInputs {
+            a: Checked::new(a, order),
+            b: Sweep::new(b, order),
+            pending: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// Yield pending results, calling `process` for each interval of the first input
+    /// until there are new results.
+    fn next_with<F>(&mut self, mut process: F) -> Option<Result<T>>
+    where
+        F: FnMut(A::Item, &mut Sweep<'a, B>, &mut VecDeque<T>) -> Result<()>,
+    {
+        loop {
+            if let Some(result) = self.pending.pop_front() {
+                return Some(Ok(result));
+            }
+            if self.done {
+                return None;
+            }
+            let processed = match self.a.next() {
+                Some(Ok(interval)) => process(interval, &mut self.b, &mut self.pending),
+                Some(Err(e)) => Err(e),
+                None => {
+                    self.done = true;
+                    Ok(())
+                }
+            };
+            if let Err(e) = processed {
+                self.done = true;
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
+/// Iterator over pairs of intervals, see [`window`](fn.window.html) and
+/// [`intersect`](fn.intersect.html).
+pub struct Window<'a, A: Iterator, B: Iterator> {
+    inputs: TwoInputs<'a, A, B, (A::Item, B::Item)>,
+    left: u64,
+    right: u64,
+}
+
+impl<'a, A, B> Iterator for Window<'a, A, B>
+where
+    A: Iterator,
+    A::Item: GenomicInterval + Clone,
+    B: Iterator,
+    B::Item: GenomicInterval + Clone,
+{
+    type Item = Result<(A::Item, B::Item)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (left, right) = (self.left, self.right);
+        self.inputs.next_with(|a, b, pending| {
+            let start = a.start().saturating_sub(left);
+            let end = a.end().saturating_add(right);
+            b.advance(a.contig(), start, end)?;
+            for hit in &b.buffer {
+                if hit.start() < end && hit.end() > start {
+                    pending.push_back((a.clone(), hit.clone()));
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Report all pairs of intervals from `a` and `b` that overlap by at least one base
+/// (like `bedtools intersect -wa -wb`).
+///
+/// Both inputs have to be sorted by contig and start position. Contigs are expected
+/// in the order of the given genome, or lexicographically sorted if `None`.
+/// Pairs are reported in the order of `a`, and for each interval of `a`, in the order
+/// of `b`.
+pub fn intersect<'a, A, B>(
+    a: A,
+    b: B,
+    genome: Option<&'a Genome>,
+) -> Window<'a, A::IntoIter, B::IntoIter>
+where
+    A: IntoIterator,
+    A::Item: GenomicInterval + Clone,
+    B: IntoIterator,
+    B::Item: GenomicInterval + Clone,
+{
+    window(a, b, 0, 0, genome)
+}
+
+/// Report all pairs of intervals from `a` and `b` where the interval from `b` overlaps
+/// the interval from `a`, extended by `left` bases to the left and `right` bases to the
+/// right (like `bedtools window -l left -r right`).
+///
+/// Input requirements are the same as for [`intersect`](fn.intersect.html).
+///
+/// # Example
+///
+/// ```
+/// use bio::seq_analysis::interval_ops::{window, Region};
+///
+/// let genes = vec![Region::new("chr1", 1000, 2000)];
+/// let snps = vec![
+///     Region::new("chr1", 400, 401),
+///     Region::new("chr1", 900, 901),
+///     Region::new("chr1", 2050, 2051),
+/// ];
+/// let hits: Vec<_> = window(genes, snps, 500, 100, None)
+///     .map(|hit| hit.unwrap().1.start)
+///     .collect();
+/// assert_eq!(hits, vec![900, 2050]);
+/// ```
+pub fn window<'a, A, B>(
+    a: A,
+    b: B,
+    left: u64,
+    right: u64,
+    genome: Option<&'a Genome>,
+) -> Window<'a, A::IntoIter, B::IntoIter>
+where
+    A: IntoIterator,
+    A::Item: GenomicInterval + Clone,
+    B: IntoIterator,
+    B::Item: GenomicInterval + Clone,
+{
+    Window {
+        inputs: TwoInputs::new(a.into_iter(), b.into_iter(), genome),
+        left,
+        right,
+    }
+}
+
+/// Iterator over the parts of intervals not covered by other intervals,
+/// see [`subtract`](fn.subtract.html).
+pub struct Subtract<'a, A: Iterator, B: Iterator> {
+    inputs: TwoInputs<'a, A, B, Region>,
+}
+
+impl<'a, A, B> Iterator for Subtract<'a, A, B>
+where
+    A: Iterator,
+    A::Item: GenomicInterval,
+    B: Iterator,
+    B::Item: GenomicInterval,
+{
+    type Item = Result<Region>;
+
+    fn next(&mut self) -> Option<Result<Region>> {
+        self.inputs.next_with(|a, b, pending| {
+            b.advance(a.contig(), a.start(), a.end())?;
+            let mut covered = a.start();
+            // the buffer may still hold intervals behind a previous, longer query
+            for hit in b.buffer.iter().take_while(|hit| hit.start() < a.end()) {
+                if hit.start() > covered {
+                    pending.push_back(Region::new(a.contig(), covered, hit.start()));
+                }
+                covered = covered.max(hit.end());
+            }
+            if covered < a.end() {
+                pending.push_back(Region::new(a.contig(), covered, a.end()));
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Report the parts of the intervals in `a` that are not covered by any interval in `b`
+/// (like `bedtools subtract`). An interval of `a` can yield zero, one or several parts.
+///
+/// Input requirements are the same as for [`intersect`](fn.intersect.html).
+///
+/// # Example
+///
+/// ```
+/// use bio::seq_analysis::interval_ops::{subtract, Region};
+///
+/// let a = vec![Region::new("chr1", 0, 100), Region::new("chr2", 0, 10)];
+/// let b = vec![Region::new("chr1", 10, 20), Region::new("chr1", 50, 60)];
+/// let rest: Vec<Region> = subtract(a, b, None).collect::<Result<_, _>>().unwrap();
+/// assert_eq!(
+///     rest,
+///     vec![
+///         Region::new("chr1", 0, 10),
+///         Region::new("chr1", 20, 50),
+///         Region::new("chr1", 60, 100),
+///         Region::new("chr2", 0, 10),
+///     ]
+/// );
+/// ```
+pub fn subtract<'a, A, B>(
+    a: A,
+    b: B,
+    genome: Option<&'a Genome>,
+) -> Subtract<'a, A::IntoIter, B::IntoIter>
+where
+    A: IntoIterator,
+    A::Item: GenomicInterval,
+    B: IntoIterator,
+    B::Item: GenomicInterval,
+{
+    Subtract {
+        inputs: TwoInputs::new(a.into_iter(), b.into_iter(), genome),
+    }
+}
+
+/// An interval together with the closest other interval and the distance between them,
+/// see [`closest`](fn.closest.html).
+pub type ClosestHit<A, B> = (A, Option<(B, u64)>);
+
+/// Iterator over closest intervals, see [`closest`](fn.closest.html).
+pub struct Closest<'a, A: Iterator, B: Iterator> {
+    inputs: TwoInputs<'a, A, B, ClosestHit<A::Item, B::Item>>,
+}
+
+impl<'a, A, B> Iterator for Closest<'a, A, B>
+where
+    A: Iterator,
+    A::Item: GenomicInterval,
+    B: Iterator,
+    B::Item: GenomicInterval + Clone,
+{
+    type Item = Result<ClosestHit<A::Item, B::Item>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inputs.next_with(|a, b, pending| {
+            b.advance(a.contig(), a.start(), a.end())?;
+            // after advancing, buffered intervals overlap the query unless they start at or
+            // after its end, which happens behind a previous, longer query
+            let overlapping = b.buffer.first().filter(|hit| hit.start() < a.end());
+            let closest = match overlapping {
+                Some(hit) => Some((hit.clone(), 0)),
+                None => {
+                    let upstream = b
+                        .upstream
+                        .as_ref()
+                        .map(|hit| (hit.clone(), a.start() - hit.end()));
+                    let downstream = match b.buffer.first() {
+                        Some(hit) => Some(hit.clone()),
+                        None => {
+                            let contig = b.contig.clone();
+                            b.peek()?.filter(|hit| hit.contig() == contig).cloned()
+                        }
+                    }
+                    .map(|hit| {
+                        let distance = hit.start() - a.end();
+                        (hit, distance)
+                    });
+                    match (upstream, downstream) {
+                        (Some(up), Some(down)) if down.1 < up.1 => Some(down),
+                        (Some(up), _) => Some(up),
+                        (None, down) => down,
+                    }
+                }
+            };
+            pending.push_back((a, closest));
+            Ok(())
+        })
+    }
+}
+
+/// For each interval in `a`, report the closest interval in `b` on the same contig,
+/// together with the number of bases between the two (like `bedtools closest -d -t first`).
+/// Overlapping and book-ended intervals have a distance of zero. Ties are resolved in
+/// favor of overlapping, then upstream intervals.
+///
+/// Input requirements are the same as for [`intersect`](fn.intersect.html).
+///
+/// # Example
+///
+/// ```
+/// use bio::seq_analysis::interval_ops::{closest, Region};
+///
+/// let a = vec![Region::new("chr1", 100, 200), Region::new("chr2", 0, 10)];
+/// let b = vec![Region::new("chr1", 0, 50), Region::new("chr1", 220, 300)];
+/// let hits: Vec<_> = closest(a, b, None).map(|hit| hit.unwrap().1).collect();
+/// assert_eq!(hits, vec![Some((Region::new("chr1", 220, 300), 20)), None]);
+/// ```
+pub fn closest<'a, A, B>(
+    a: A,
+    b: B,
+    genome: Option<&'a Genome>,
+) -> Closest<'a, A::IntoIter, B::IntoIter>
+where
+    A: IntoIterator,
+    A::Item: GenomicInterval,
+    B: IntoIterator,
+    B::Item: GenomicInterval + Clone,
+{
+    Closest {
+        inputs: TwoInputs::new(a.into_iter(), b.into_iter(), genome),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn regions(intervals: &[(&str, u64, u64)]) -> Vec<Region> {
+        intervals
+            .iter()
+            .map(|&(contig, start, end)| Region::new(contig, start, end))
+            .collect()
+    }
+
+    fn genome() -> Genome {
+        vec![("chr2".to_owned(), 100), ("chr10".to_owned(), 50)]
+            .into_iter()
+            .collect()
+    }
+
+    #[test]
+    fn test_merge_unsorted() {
+        let unsorted = regions(&[("chr1", 10, 20), ("chr1", 5, 8)]);
+        let result: Result<Vec<_>> = merge(unsorted, 0).collect();
+        assert!(matches!(result, Err(Error::Unsorted { start: 5, .. })));
+
+        let split = regions(&[("chr1", 10, 20), ("chr2", 5, 8), ("chr1", 30, 40)]);
+        let result: Result<Vec<_>> = merge(split, 0).collect();
+        assert!(matches!(result, Err(Error::Unsorted { .. })));
+
+        // contigs may be in any order, as long as they are not interleaved
+        let grouped = regions(&[("chr2", 10, 20), ("chr1", 5, 8)]);
+        assert_eq!(merge(grouped, 0).count(), 2);
+    }
+
+    #[test]
+    fn test_merge_bookended_and_contained() {
+        let intervals = regions(&[("chr1", 0, 10), ("chr1", 2, 5), ("chr1", 10, 12)]);
+        let merged: Vec<_> = merge(&intervals, 0).map(Result::unwrap).collect();
+        assert_eq!(merged, regions(&[("chr1", 0, 12)]));
+    }
+
+    #[test]
+    fn test_intersect_genome_order() {
+        let a = regions(&[("chr2", 10, 20), ("chr10", 0, 10)]);
+        let b = regions(&[("chr2", 15, 16), ("chr10", 5, 6)]);
+        let genome = genome();
+        let hits: Vec<_> = intersect(&a, &b, Some(&genome))
+            .map(|hit| Region::of(hit.unwrap().1))
+            .collect();
+        assert_eq!(hits, b);
+
+        // chr10 sorts before chr2 lexicographically
+        let result: Result<Vec<_>> = intersect(&a, &b, None).collect();
+        assert!(matches!(result, Err(Error::Unsorted { .. })));
+
+        let unknown = regions(&[("chrX", 0, 10)]);
+        let result: Result<Vec<_>> = intersect(&unknown, &b, Some(&genome)).collect();
+        assert!(matches!(result, Err(Error::UnknownContig(_))));
+    }
+
+    #[test]
+    fn test_intersect_long_intervals() {
+        // a long interval in b has to be kept while later intervals of a are processed
+        let a = regions(&[("chr1", 0, 10), ("chr1", 50, 60), ("chr1", 500, 510)]);
+        let b = regions(&[("chr1", 5, 300), ("chr1", 55, 56), ("chr3", 0, 10)]);
+        let hits: Vec<_> = intersect(&a, &b, None)
+            .map(|hit| {
+                let (a, b) = hit.unwrap();
+                (a.start, b.start)
+            })
+            .collect();
+        assert_eq!(hits, vec![(0, 5), (50, 5), (50, 55)]);
+    }
+
+    #[test]
+    fn test_intersect_skips_contigs() {
+        let a = regions(&[("chr3", 0, 10)]);
+        let b = regions(&[("chr1", 0, 10), ("chr2", 0, 10), ("chr3", 5, 6)]);
+        assert_eq!(intersect(&a, &b, None).count(), 1);
+        assert_eq!(intersect(&b, &a, None).count(), 1);
+    }
+
+    #[test]
+    fn test_subtract_overlapping_b() {
+        let a = regions(&[("chr1", 0, 100)]);
+        let b = regions(&[("chr1", 0, 30), ("chr1", 10, 20), ("chr1", 90, 120)]);
+        let rest: Vec<_> = subtract(&a, &b, None).map(Result::unwrap).collect();
+        assert_eq!(rest, regions(&[("chr1", 30, 90)]));
+    }
+
+    #[test]
+    fn test_closest() {
+        let a = regions(&[("chr1", 100, 110), ("chr1", 120, 130), ("chr1", 400, 410)]);
+        let b = regions(&[("chr1", 0, 90), ("chr1", 125, 126), ("chr1", 135, 140)]);
+        let hits: Vec<_> = closest(&a, &b, None)
+            .map(|hit| hit.unwrap().1.map(|(b, d)| (b.start, d)))
+            .collect();
+        assert_eq!(hits, vec![Some((0, 10)), Some((125, 0)), Some((135, 260))]);
+    }
+
+    #[test]
+    fn test_short_query_after_long_query() {
+        let a = regions(&[("chr1", 0, 100), ("chr1", 10, 20)]);
+        let b = regions(&[("chr1", 50, 60)]);
+        let rest: Vec<_> = subtract(&a, &b, None).map(Result::unwrap).collect();
+        assert_eq!(
+            rest,
+            regions(&[("chr1", 0, 50), ("chr1", 60, 100), ("chr1", 10, 20)])
+        );
+        let hits: Vec<_> = closest(&a, &b, None)
+            .map(|hit| hit.unwrap().1.map(|(b, d)| (b.start, d)))
+            .collect();
+        assert_eq!(hits, vec![Some((50, 0)), Some((50, 30))]);
+    }
+
+    #[test]
+    fn test_complement() {
+        let genome = genome();
+        let intervals = regions(&[("chr2", 0, 10), ("chr2", 90, 200)]);
+        let gaps: Vec<_> = complement(&intervals, &genome)
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(gaps, regions(&[("chr2", 10, 90), ("chr10", 0, 50)]));
+
+        let empty: Vec<Region> = Vec::new();
+        assert_eq!(complement(empty, &genome).count(), 2);
+    }
+
+    #[test]
+    fn test_gff_coordinates() {
+        let mut record = gff::Record::new();
+        *record.seqname_mut() = "chr1".to_owned();
+        *record.start_mut() = 11;
+        *record.end_mut() = 20;
+        assert_eq!(Region::of(&record), Region::new("chr1", 10, 20));
+    }
+
+    #[test]
+    fn test_genome_file() {
+        let genome = Genome::new(&b"chr1\t1000\textra\n\nchr2\t500\n"[..]).unwrap();
+        let contigs: Vec<_> = genome.contigs().collect();
+        assert_eq!(contigs, vec![("chr1", 1000), ("chr2", 500)]);
+        assert!(matches!(
+            Genome::new(&b"chr1 1000\n"[..]),
+            Err(Error::InvalidGenomeLine(_))
+        ));
+    }
+}
@@ -0,0 +1,201 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Detection of inverted repeats, i.e. two arms (the stem) that are reverse complementary to
+//! each other, separated by a loop, e.g. hairpins, CRISPR repeats or, without a loop,
+//! reverse-complement palindromes like restriction sites.
+//!
+//! For each position and loop length, the stem is extended outward from the innermost pair of
+//! bases, which has to be complementary, allowing for a maximum number of mismatches. Trailing
+//! mismatches are removed from the stem. Of inverted repeats spanning the same interval, only the
+//! one with the longest stem is reported. Bases other than A, C, G, T never pair.
+//!
+//! Complexity: O(n * l * s) for a sequence of length n, l loop lengths and stems of length s.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::seq_analysis::inverted_repeats::Finder;
+//!
+//! // a hairpin with a stem of 10 bases and a loop of 3 bases
+//! let seq = b"TTGCATCCGGAAACCGGATGCAA";
+//! let finder = Finder::new(5, 3, 6, 0);
+//! let repeats = finder.find_all(seq);
+//! assert_eq!(repeats.len(), 1);
+//! let repeat = &repeats[0];
+//! assert_eq!((repeat.start, repeat.end), (0, 23));
+//! assert_eq!((repeat.stem, repeat.loop_length), (10, 3));
+//!
+//! // EcoRI sites are palindromes without a loop
+//! let sites = Finder::new(3, 0, 0, 0).find_all(b"ATGAATTCGGC");
+//! assert_eq!((sites[0].start, sites[0].end), (2, 8));
+//! ```
+
+use std::collections::HashMap;
+
+/// An inverted repeat.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InvertedRepeat {
+    /// Start position of the left arm.
+    pub start: usize,
+    /// End position of the right arm (exclusive).
+    pub end: usize,
+    /// Length of each arm.
+    pub stem: usize,
+    /// Length of the loop between the arms.
+    pub loop_length: usize,
+    /// Number of mismatching pairs in the stem.
+    pub mismatches: usize,
+}
+
+impl InvertedRepeat {
+    /// Start position of the loop, i.e. the end of the left arm.
+    pub fn loop_start(&self) -> usize {
+        self.start + self.stem
+    }
+
+    /// End position of the loop, i.e. the start of the right arm.
+    pub fn loop_end(&self) -> usize {
+        self.loop_start() + self.loop_length
+    }
+}
+
+/// A finder for inverted repeats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Finder {
+    min_stem: usize,
+    min_loop: usize,
+    max_loop: usize,
+    max_mismatches: usize,
+}
+
+impl Finder {
+    /// Create a new finder for inverted repeats with a stem of at least `min_stem` bases, a loop
+    /// between `min_loop` and `max_loop` bases and at most `max_mismatches` mismatching pairs
+    /// in the stem.
+    ///
+    /// # Panics
+    ///
+    /// If the minimum stem length is zero or `min_loop` exceeds `max_loop`.
+    pub fn new(min_stem: usize, min_loop: usize, max_loop: usize, max_mismatches: usize) -> Self {
+        assert!(min_stem > 0, "minimum stem length must be positive");
+        assert!(min_loop <= max_loop, "invalid range of loop lengths");
+        Finder {
+            min_stem,
+            min_loop,
+            max_loop,
+            max_mismatches,
+        }
+    }
+
+    /// Find all inverted repeats in the given sequence (ignoring case), ordered by position.
+    pub fn find_all(&self, seq: &[u8]) -> Vec<InvertedRepeat> {
+        let n = seq.len();
+        // the best inverted repeat for each interval
+        let mut best: HashMap<(usize, usize), InvertedRepeat> = HashMap::new();
+        for loop_start in 1..n {
+            for loop_length in self.min_loop..=self.max_loop {
+                let loop_end = loop_start + loop_length;
+                if loop_end >= n {
+                    break;
+                }
+                if let Some(repeat) = self.extend(seq, loop_start, loop_end) {
+                    let entry = best.entry((repeat.start, repeat.end)).or_insert(repeat);
+                    if repeat.stem > entry.stem {
+                        *entry = repeat;
+                    }
+                }
+            }
+        }
+        let mut repeats: Vec<InvertedRepeat> = best.into_values().collect();
+        repeats.sort_by_key(|r| (r.start, r.end));
+        repeats
+    }
+
+    /// Extend the stem outward from the given loop.
+    fn extend(&self, seq: &[u8], loop_start: usize, loop_end: usize) -> Option<InvertedRepeat> {
+        if !pairs(seq[loop_start - 1], seq[loop_end]) {
+            return None;
+        }
+        let max_stem = loop_start.min(seq.len() - loop_end);
+        let (mut stem, mut mismatches, mut stem_mismatches) = (0, 0, 0);
+        for k in 0..max_stem {
+            if pairs(seq[loop_start - 1 - k], seq[loop_end + k]) {
+                stem = k + 1;
+                stem_mismatches = mismatches;
+            } else {
+                mismatches += 1;
+                if mismatches > self.max_mismatches {
+                    break;
+                }
+            }
+        }
+        if stem < self.min_stem {
+            return None;
+        }
+        Some(InvertedRepeat {
+            start: loop_start - stem,
+            end: loop_end + stem,
+            stem,
+            loop_length: loop_end - loop_start,
+            mismatches: stem_mismatches,
+        })
+    }
+}
+
+/// Whether the given bases are complementary (ignoring case).
+fn pairs(a: u8, b: u8) -> bool {
+    matches!(
+        (a.to_ascii_uppercase(), b.to_ascii_uppercase()),
+        (b'A', b'T') | (b'T', b'A') | (b'C', b'G') | (b'G', b'C')
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alphabets::dna;
+    use crate::utils::testing::random_seq;
+    use rand::prelude::*;
+
+    #[test]
+    fn test_hairpin() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let stem = random_seq(&mut rng, b"ACGT", 12);
+        let mut seq = random_seq(&mut rng, b"ACGT", 100);
+        seq.extend(&stem);
+        seq.extend(b"TTTTT");
+        seq.extend(dna::revcomp(&stem));
+        seq.extend(random_seq(&mut rng, b"ACGT", 100));
+
+        let repeats = Finder::new(10, 3, 8, 0).find_all(&seq);
+        assert_eq!(repeats.len(), 1);
+        let repeat = repeats[0];
+        assert!(repeat.start <= 100 && repeat.end >= 129);
+        assert_eq!(repeat.loop_start(), 112);
+        assert_eq!(repeat.loop_end(), 117);
+        assert_eq!(repeat.mismatches, 0);
+
+        // a mismatch in the stem
+        seq[103] = dna::complement(seq[103]);
+        assert!(Finder::new(10, 3, 8, 0)
+            .find_all(&seq)
+            .iter()
+            .all(|r| r.stem < 10));
+        let repeats = Finder::new(10, 3, 8, 1).find_all(&seq);
+        assert_eq!(repeats.len(), 1);
+        assert_eq!(repeats[0].mismatches, 1);
+        assert!(repeats[0].stem >= 12);
+    }
+
+    #[test]
+    fn test_palindromes() {
+        let repeats = Finder::new(2, 0, 0, 0).find_all(b"GGATCCNNGAATTCAtgcat");
+        let bounds: Vec<(usize, usize)> = repeats.iter().map(|r| (r.start, r.end)).collect();
+        assert_eq!(bounds, [(0, 6), (8, 14), (13, 17), (14, 20)]);
+        assert!(Finder::new(2, 0, 3, 0).find_all(b"NNNNNN").is_empty());
+        assert!(Finder::new(1, 0, 3, 0).find_all(b"A").is_empty());
+    }
+}
@@ -5,5 +5,16 @@
 
 //! Sequence analysis algorithms.
 
+pub mod codon_usage;
+pub mod complexity;
+pub mod coverage;
+pub mod cpg;
+pub mod crispr;
 pub mod gc;
+pub mod interval_ops;
+pub mod inverted_repeats;
 pub mod orf;
+pub mod protein_properties;
+pub mod restriction;
+pub mod rna_structure;
+pub mod tandem_repeats;
@@ -24,15 +24,28 @@
 //! }
 //! ```
 //!
-//! Right now the only way to check the reverse strand for ORF is to use
-//! the `alphabet::dna::RevComp` struct and to check for both sequences.
-//! But that's not so performance friendly, as the reverse complementation and the ORF research
-//! could go on at the same time.
+//! The above only checks the forward strand. All six reading frames of both strands are searched
+//! with `Finder::find_all_six_frames`, which can also report nested ORFs and treat the sequence
+//! as circular, e.g. for plasmids.
+//!
+//! ```
+//! use bio::seq_analysis::orf::{Finder, Nested, StrandedOrf};
+//! let finder = Finder::new(vec![b"ATG"], vec![b"TGA", b"TAG", b"TAA"], 5)
+//!     .nested(Nested::All)
+//!     .circular(true);
+//!
+//! // an ORF spanning the origin of a plasmid
+//! let plasmid = b"CCCTGAGGGGATGCCC";
+//! let orfs: Vec<StrandedOrf> = finder.find_all_six_frames(plasmid).collect();
+//! assert_eq!(orfs, [StrandedOrf { start: 10, end: 22, frame: 2 }]);
+//! ```
 
 use std::borrow::Borrow;
 use std::collections::VecDeque;
 use std::iter;
 
+use crate::alphabets::dna;
+
 /// An implementation of a naive algorithm finder
 // Implementation note:
 //
@@ -44,6 +57,17 @@ pub struct Finder {
     start_codons: Vec<VecDeque<u8>>,
     stop_codons: Vec<VecDeque<u8>>,
     min_len: usize,
+    nested: Nested,
+    circular: bool,
+}
+
+/// Which ORFs to report if a reading frame contains multiple start codons before a stop codon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Nested {
+    /// Report only the longest ORF, beginning at the first start codon.
+    Outermost,
+    /// Report an ORF for each start codon.
+    All,
 }
 
 impl Finder {
@@ -64,9 +88,53 @@ impl Finder {
                 .map(|x| x.iter().map(|&x| x as u8).collect::<VecDeque<u8>>())
                 .collect(),
             min_len,
+            nested: Nested::Outermost,
+            circular: false,
         }
     }
 
+    /// Set which ORFs are reported by `find_all_six_frames` if there are multiple start codons
+    /// before a stop codon (default: `Nested::Outermost`).
+    pub fn nested(mut self, nested: Nested) -> Self {
+        self.nested = nested;
+        self
+    }
+
+    /// Set whether `find_all_six_frames` treats sequences as circular (default: false), such
+    /// that ORFs may span the origin.
+    pub fn circular(mut self, circular: bool) -> Self {
+        self.circular = circular;
+        self
+    }
+
+    /// Find all ORFs in all six reading frames of the given sequence.
+    /// ORFs are reported per frame, in the order +1, +2, +3, -1, -2, -3.
+    pub fn find_all_six_frames<'b>(&self, seq: &'b [u8]) -> SixFrameMatches<'_, 'b> {
+        SixFrameMatches {
+            finder: self,
+            forward: seq,
+            reverse: dna::revcomp(seq),
+            reverse_strand: false,
+            frame: 0,
+            pos: 0,
+            starts: Vec::new(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    fn is_start(&self, codon: &[u8]) -> bool {
+        self.start_codons.iter().any(|c| c.iter().eq(codon))
+    }
+
+    fn is_stop(&self, codon: &[u8]) -> bool {
+        self.stop_codons.iter().any(|c| c.iter().eq(codon))
+    }
+
+    /// Length criterion of `find_all`, with the ORF given by its start and end position.
+    fn is_long_enough(&self, start: usize, end: usize) -> bool {
+        end - start - 2 > self.min_len
+    }
+
     /// Find all ORFs in the given sequence
     pub fn find_all<C, T>(&self, seq: T) -> Matches<'_, C, T::IntoIter>
     where
@@ -165,6 +233,128 @@ where
     }
 }
 
+/// An ORF on either strand, given by its start and end position on the forward strand.
+///
+/// The frame is 1, 2 or 3 on the forward strand and -1, -2 or -3 on the reverse strand,
+/// counting from the start of the respective strand. For circular sequences, ORFs spanning the
+/// origin have an end position beyond the sequence length, i.e. positions have to be taken
+/// modulo the sequence length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StrandedOrf {
+    pub start: usize,
+    pub end: usize,
+    pub frame: i8,
+}
+
+/// Iterator over the ORFs in the six reading frames of a sequence.
+pub struct SixFrameMatches<'a, 'b> {
+    finder: &'a Finder,
+    forward: &'b [u8],
+    reverse: Vec<u8>,
+    reverse_strand: bool,
+    /// Current frame (0, 1 or 2).
+    frame: usize,
+    /// Position of the next codon.
+    pos: usize,
+    /// Start codon positions since the last stop codon.
+    starts: Vec<usize>,
+    pending: VecDeque<StrandedOrf>,
+}
+
+impl<'a, 'b> SixFrameMatches<'a, 'b> {
+    /// Record the ORFs ending with the stop codon at the current position.
+    ///
+    /// Circular sequences are scanned three times, which covers all frames of all ORFs of at most
+    /// the sequence length. ORFs are reported if they start in the second pass, in which case
+    /// any upstream start codon of the same ORF has already been seen.
+    fn stop(&mut self, n: usize) {
+        let end = self.pos + 3;
+        let finder = self.finder;
+        let circular = finder.circular;
+        let reachable = |&&start: &&usize| !circular || end - start <= n;
+        let canonical = |start: usize| !circular || (n..2 * n).contains(&start);
+
+        let starts: Vec<usize> = match finder.nested {
+            Nested::Outermost => self
+                .starts
+                .iter()
+                .find(reachable)
+                .cloned()
+                .into_iter()
+                .collect(),
+            Nested::All => self.starts.iter().filter(reachable).cloned().collect(),
+        };
+        for start in starts {
+            if !canonical(start) || !finder.is_long_enough(start, end) {
+                continue;
+            }
+            let (start, end) = if circular {
+                (start - n, end - n)
+            } else {
+                (start, end)
+            };
+            let frame = (start % 3 + 1) as i8;
+            self.pending.push_back(if self.reverse_strand {
+                // map back to the forward strand, wrapping around the origin if needed
+                let offset = if end > n { 2 * n } else { n };
+                StrandedOrf {
+                    start: offset - end,
+                    end: offset - start,
+                    frame: -frame,
+                }
+            } else {
+                StrandedOrf { start, end, frame }
+            });
+        }
+        self.starts.clear();
+    }
+}
+
+impl<'a, 'b> Iterator for SixFrameMatches<'a, 'b> {
+    type Item = StrandedOrf;
+
+    fn next(&mut self) -> Option<StrandedOrf> {
+        let n = self.forward.len();
+        let len = if self.finder.circular { 3 * n } else { n };
+        loop {
+            if let Some(orf) = self.pending.pop_front() {
+                return Some(orf);
+            }
+            if self.pos + 3 > len {
+                // next frame
+                self.frame += 1;
+                if self.frame == 3 {
+                    if self.reverse_strand {
+                        return None;
+                    }
+                    self.frame = 0;
+                    self.reverse_strand = true;
+                }
+                self.pos = self.frame;
+                self.starts.clear();
+                continue;
+            }
+
+            let seq = if self.reverse_strand {
+                &self.reverse[..]
+            } else {
+                self.forward
+            };
+            let codon = [
+                seq[self.pos % n],
+                seq[(self.pos + 1) % n],
+                seq[(self.pos + 2) % n],
+            ];
+            if self.finder.is_stop(&codon) {
+                self.stop(n);
+            } else if self.finder.is_start(&codon) {
+                self.starts.push(self.pos);
+            }
+            self.pos += 3;
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -225,4 +415,112 @@ mod tests {
         ];
         assert_eq!(expected, finder.find_all(sequence).collect::<Vec<Orf>>());
     }
+
+    #[test]
+    fn test_six_frames_against_find_all() {
+        let finder = basic_finder();
+        let sequence = b"ATGGGGTGAGGGGGATGGAAAAATAAGCCATGCCCCCCCATCATTTAGGCTTAGGGGGGCAT";
+        let orfs: Vec<StrandedOrf> = finder.find_all_six_frames(sequence).collect();
+
+        let mut forward: Vec<StrandedOrf> = finder
+            .find_all(sequence)
+            .map(|orf| StrandedOrf {
+                start: orf.start,
+                end: orf.end,
+                frame: orf.offset + 1,
+            })
+            .collect();
+        forward.sort_by_key(|orf| (orf.frame, orf.end));
+        assert_eq!(&orfs[..forward.len()], &forward[..]);
+
+        let revcomp = dna::revcomp(&sequence[..]);
+        let reverse: Vec<StrandedOrf> = orfs[forward.len()..]
+            .iter()
+            .map(|orf| StrandedOrf {
+                start: sequence.len() - orf.end,
+                end: sequence.len() - orf.start,
+                frame: -orf.frame,
+            })
+            .collect();
+        let mut expected: Vec<StrandedOrf> = finder
+            .find_all_six_frames(&revcomp)
+            .filter(|orf| orf.frame > 0)
+            .collect();
+        expected.sort_by_key(|orf| (orf.frame, orf.end));
+        assert_eq!(reverse, expected);
+        assert!(!reverse.is_empty());
+    }
+
+    #[test]
+    fn test_nested() {
+        let sequence = b"ATGATGCCCCCCATGTAA";
+        let finder = basic_finder();
+        let outermost: Vec<StrandedOrf> = finder.find_all_six_frames(sequence).collect();
+        assert_eq!(
+            outermost,
+            vec![StrandedOrf {
+                start: 0,
+                end: 18,
+                frame: 1
+            }]
+        );
+        let finder = basic_finder().nested(Nested::All);
+        let all: Vec<(usize, usize)> = finder
+            .find_all_six_frames(sequence)
+            .map(|orf| (orf.start, orf.end))
+            .collect();
+        // the last one is too short
+        assert_eq!(all, vec![(0, 18), (3, 18)]);
+    }
+
+    #[test]
+    fn test_circular() {
+        use crate::utils::testing::random_seq;
+        use rand::prelude::*;
+
+        let mut rng = StdRng::seed_from_u64(42);
+        for nested in &[Nested::Outermost, Nested::All] {
+            let finder = basic_finder().nested(*nested).circular(true);
+            for len in 20..60 {
+                let sequence = random_seq(&mut rng, b"ACGT", len);
+                let shift = rng.gen_range(0..len);
+                let mut rotated = sequence[shift..].to_vec();
+                rotated.extend_from_slice(&sequence[..shift]);
+
+                // ORFs do not depend on the position of the origin
+                let normalize = |orfs: Vec<StrandedOrf>, shift: usize| {
+                    let mut orfs: Vec<(usize, usize, bool)> = orfs
+                        .iter()
+                        .map(|orf| {
+                            assert!(orf.start < len && orf.end - orf.start <= len);
+                            let start = (orf.start + shift) % len;
+                            (start, start + orf.end - orf.start, orf.frame > 0)
+                        })
+                        .collect();
+                    orfs.sort_unstable();
+                    orfs
+                };
+                assert_eq!(
+                    normalize(finder.find_all_six_frames(&sequence).collect(), 0),
+                    normalize(finder.find_all_six_frames(&rotated).collect(), shift)
+                );
+            }
+        }
+
+        // an ORF spanning the origin
+        let finder = basic_finder().circular(true);
+        let sequence = b"CCCTGAGGGGATGCCC";
+        let orfs: Vec<StrandedOrf> = finder
+            .find_all_six_frames(sequence)
+            .filter(|orf| orf.frame > 0)
+            .collect();
+        assert_eq!(
+            orfs,
+            vec![StrandedOrf {
+                start: 10,
+                end: 22,
+                frame: 2
+            }]
+        );
+    }
 }
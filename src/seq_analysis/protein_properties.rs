@@ -0,0 +1,203 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Physicochemical properties of proteins, similar to those reported by ExPASy ProtParam:
+//! molecular weight, isoelectric point, GRAVY score, instability index and extinction
+//! coefficient. The properties are computed from the amino acid property tables in
+//! [`alphabets::protein`](../../alphabets/protein/index.html). Sequences are accepted in upper-
+//! or lowercase.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::seq_analysis::protein_properties;
+//!
+//! let peptide = b"MKWVTFISLLLLFSSAYS";
+//! let weight = protein_properties::molecular_weight(peptide).unwrap();
+//! assert!((weight - 2106.55).abs() < 0.01);
+//! let pi = protein_properties::isoelectric_point(peptide);
+//! assert!((pi - 9.30).abs() < 0.01);
+//! let gravy = protein_properties::gravy(peptide).unwrap();
+//! assert!((gravy - 1.289).abs() < 0.001);
+//! assert_eq!(protein_properties::extinction_coefficient(peptide, false), 6990);
+//! ```
+
+use crate::alphabets::protein;
+
+/// Precision of the isoelectric point.
+const PI_PRECISION: f64 = 1e-4;
+
+/// Molar extinction coefficients at 280 nm of tryptophan, tyrosine and cystine (Pace et al.,
+/// Protein Science, 1995).
+const EXTINCTION_TRP: u32 = 5500;
+const EXTINCTION_TYR: u32 = 1490;
+const EXTINCTION_CYSTINE: u32 = 125;
+
+/// Average molecular weight of the given protein in Dalton, or `None` if it contains residues
+/// other than the 20 common amino acids and selenocysteine.
+pub fn molecular_weight(protein: &[u8]) -> Option<f64> {
+    weight(protein, protein::average_mass, protein::WATER_AVERAGE_MASS)
+}
+
+/// Monoisotopic molecular weight of the given protein in Dalton, or `None` if it contains
+/// residues other than the 20 common amino acids and selenocysteine.
+pub fn monoisotopic_weight(protein: &[u8]) -> Option<f64> {
+    weight(
+        protein,
+        protein::monoisotopic_mass,
+        protein::WATER_MONOISOTOPIC_MASS,
+    )
+}
+
+fn weight(protein: &[u8], mass: fn(u8) -> Option<f64>, water: f64) -> Option<f64> {
+    protein
+        .iter()
+        .map(|&aa| mass(aa))
+        .sum::<Option<f64>>()
+        .map(|residues| residues + water)
+}
+
+/// Net charge of the given protein at the given pH, following the Henderson-Hasselbalch
+/// equation for the termini and the ionizable side chains. Residues without an ionizable side
+/// chain (including unknown ones) do not contribute.
+pub fn charge(protein: &[u8], ph: f64) -> f64 {
+    let positive = |pka: f64| 1.0 / (1.0 + 10f64.powf(ph - pka));
+    let negative = |pka: f64| 1.0 / (1.0 + 10f64.powf(pka - ph));
+    protein.iter().fold(
+        positive(protein::N_TERMINUS_PKA) - negative(protein::C_TERMINUS_PKA),
+        |charge, &aa| match protein::side_chain_pka(aa) {
+            Some(pka) if protein::is_basic(aa) => charge + positive(pka),
+            Some(pka) => charge - negative(pka),
+            None => charge,
+        },
+    )
+}
+
+/// Isoelectric point of the given protein, i.e. the pH at which its net
+/// [`charge`](fn.charge.html) is zero, found by bisection between pH 0 and 14.
+pub fn isoelectric_point(protein: &[u8]) -> f64 {
+    let (mut lower, mut upper) = (0.0, 14.0);
+    while upper - lower > PI_PRECISION {
+        let ph = (lower + upper) / 2.0;
+        if charge(protein, ph) > 0.0 {
+            lower = ph;
+        } else {
+            upper = ph;
+        }
+    }
+    (lower + upper) / 2.0
+}
+
+/// Grand average of hydropathy (GRAVY) of the given protein, i.e. the mean Kyte-Doolittle
+/// hydropathy of its residues, or `None` if it is empty or contains residues other than the
+/// 20 common amino acids.
+pub fn gravy(protein: &[u8]) -> Option<f64> {
+    if protein.is_empty() {
+        return None;
+    }
+    protein
+        .iter()
+        .map(|&aa| protein::hydrophobicity(aa))
+        .sum::<Option<f64>>()
+        .map(|sum| sum / protein.len() as f64)
+}
+
+/// Instability index of Guruprasad et al. (Protein Engineering, 1990) of the given protein,
+/// or `None` if it is empty or contains residues other than the 20 common amino acids.
+/// Proteins with an index above 40 are predicted to be unstable in vitro.
+pub fn instability_index(protein: &[u8]) -> Option<f64> {
+    if protein.is_empty() {
+        return None;
+    }
+    protein
+        .windows(2)
+        .map(|dipeptide| protein::instability_weight(dipeptide[0], dipeptide[1]))
+        .sum::<Option<f64>>()
+        .map(|sum| 10.0 * sum / protein.len() as f64)
+}
+
+/// Molar extinction coefficient of the given protein at 280 nm in M<sup>-1</sup>
+/// cm<sup>-1</sup> (Pace et al., 1995). If `cystines` is true, all pairs of cysteines are
+/// assumed to form cystines, otherwise all cysteines are assumed to be reduced.
+pub fn extinction_coefficient(protein: &[u8], cystines: bool) -> u32 {
+    let count = |residue: u8| {
+        protein
+            .iter()
+            .filter(|aa| aa.eq_ignore_ascii_case(&residue))
+            .count() as u32
+    };
+    let mut coefficient = count(b'W') * EXTINCTION_TRP + count(b'Y') * EXTINCTION_TYR;
+    if cystines {
+        coefficient += count(b'C') / 2 * EXTINCTION_CYSTINE;
+    }
+    coefficient
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_molecular_weight() {
+        assert_relative_eq!(molecular_weight(b"GG").unwrap(), 132.11904, epsilon = 1e-6);
+        assert_relative_eq!(
+            molecular_weight(b"ACDEFGHIKLMNPQRSTVWY").unwrap(),
+            2395.73584,
+            epsilon = 1e-6
+        );
+        assert_eq!(molecular_weight(b"gg"), molecular_weight(b"GG"));
+        assert!(monoisotopic_weight(b"GG").unwrap() < molecular_weight(b"GG").unwrap());
+        assert_eq!(molecular_weight(b""), Some(protein::WATER_AVERAGE_MASS));
+        assert_eq!(molecular_weight(b"GXG"), None);
+    }
+
+    #[test]
+    fn test_isoelectric_point() {
+        assert!((isoelectric_point(b"KKKK") - 11.278).abs() < 0.001);
+        assert!((isoelectric_point(b"DDDD") - 3.228).abs() < 0.001);
+        assert!((isoelectric_point(b"GG") - 6.100).abs() < 0.001);
+        assert!((isoelectric_point(b"ACDEFGHIKLMNPQRSTVWY") - 7.357).abs() < 0.001);
+        assert!(charge(b"KKKK", 7.0) > 3.9);
+        assert!(charge(b"DDDD", 7.0) < -4.0);
+        assert!(charge(b"GG", 7.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_gravy() {
+        assert_relative_eq!(gravy(b"KKKK").unwrap(), -3.9);
+        assert_relative_eq!(
+            gravy(b"ACDEFGHIKLMNPQRSTVWY").unwrap(),
+            -0.49,
+            epsilon = 1e-9
+        );
+        assert_eq!(gravy(b""), None);
+        assert_eq!(gravy(b"AUA"), None);
+    }
+
+    #[test]
+    fn test_instability_index() {
+        assert_relative_eq!(
+            instability_index(b"MKWVTFISLLLLFSSAYS").unwrap(),
+            17.566666666666666,
+            epsilon = 1e-9
+        );
+        assert_relative_eq!(
+            instability_index(b"ACDEFGHIKLMNPQRSTVWY").unwrap(),
+            84.74,
+            epsilon = 1e-9
+        );
+        // a single residue has no dipeptides
+        assert_eq!(instability_index(b"A"), Some(0.0));
+        assert_eq!(instability_index(b""), None);
+        assert_eq!(instability_index(b"AXA"), None);
+    }
+
+    #[test]
+    fn test_extinction_coefficient() {
+        assert_eq!(extinction_coefficient(b"WYC", false), 6990);
+        assert_eq!(extinction_coefficient(b"wyCCC", true), 7115);
+        assert_eq!(extinction_coefficient(b"GG", true), 0);
+    }
+}
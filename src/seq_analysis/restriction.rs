@@ -0,0 +1,444 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Restriction enzymes: recognition site search on both strands and simulated digests of linear
+//! and circular sequences.
+//!
+//! An enzyme is given by its recognition site, which may contain IUPAC ambiguity codes, and the
+//! positions at which it cuts the top and the bottom strand, relative to the start of the
+//! site on the top strand. E.g., EcoRI recognizes `GAATTC` and cuts the top strand after the
+//! first and the bottom strand after the fifth base (`G^AATTC`), leaving a 5' overhang of four
+//! bases. Enzymes can be created from the site notation of REBASE (http://rebase.neb.com),
+//! e.g. `G^AATTC` or `GGTCTC(1/5)`, or parsed from REBASE files in EMBOSS format
+//! (`emboss_e.###`). A set of common enzymes is bundled.
+//!
+//! Coordinates are 0-based on the forward strand. A cut at position i separates the bases
+//! i - 1 and i.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::seq_analysis::restriction::{common_enzymes, digest, Enzyme, Overhang};
+//!
+//! let ecori = Enzyme::from_rebase("EcoRI", "G^AATTC").unwrap();
+//! assert_eq!(ecori.overhang(), Overhang::FivePrime(4));
+//!
+//! let seq = b"CCGAATTCAAAAAAGGATCCAA";
+//! let enzymes: Vec<Enzyme> = common_enzymes()
+//!     .into_iter()
+//!     .filter(|e| e.name == "EcoRI" || e.name == "BamHI")
+//!     .collect();
+//! let result = digest(&enzymes, seq, false);
+//! assert_eq!(result.fragments, vec![0..3, 3..15, 15..22]);
+//! assert_eq!(result.cuts[0].enzyme.name, "EcoRI");
+//! assert_eq!((result.cuts[0].top, result.cuts[0].bottom), (3, 7));
+//! ```
+
+use std::io;
+use std::io::prelude::*;
+use std::ops::Range;
+
+use bio_types::strand::ReqStrand;
+use thiserror::Error;
+
+use crate::alphabets::{dna, iupac};
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("can't read REBASE file")]
+    ReadError(#[from] io::Error),
+
+    #[error("invalid recognition site '{0}'")]
+    InvalidSite(String),
+
+    #[error("invalid record in line {0} of REBASE file")]
+    InvalidRecord(usize),
+}
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Common restriction enzymes in REBASE notation.
+const COMMON_ENZYMES: &[(&str, &str)] = &[
+    ("AluI", "AG^CT"),
+    ("ApaI", "GGGCC^C"),
+    ("AvaI", "C^YCGRG"),
+    ("BamHI", "G^GATCC"),
+    ("BglII", "A^GATCT"),
+    ("BsaI", "GGTCTC(1/5)"),
+    ("BsmBI", "CGTCTC(1/5)"),
+    ("DpnII", "^GATC"),
+    ("EcoRI", "G^AATTC"),
+    ("EcoRV", "GAT^ATC"),
+    ("HaeIII", "GG^CC"),
+    ("HindIII", "A^AGCTT"),
+    ("HinfI", "G^ANTC"),
+    ("KpnI", "GGTAC^C"),
+    ("MluI", "A^CGCGT"),
+    ("MspI", "C^CGG"),
+    ("NcoI", "C^CATGG"),
+    ("NdeI", "CA^TATG"),
+    ("NheI", "G^CTAGC"),
+    ("NotI", "GC^GGCCGC"),
+    ("PstI", "CTGCA^G"),
+    ("SacI", "GAGCT^C"),
+    ("SalI", "G^TCGAC"),
+    ("SapI", "GCTCTTC(1/4)"),
+    ("SmaI", "CCC^GGG"),
+    ("SpeI", "A^CTAGT"),
+    ("XbaI", "T^CTAGA"),
+    ("XhoI", "C^TCGAG"),
+];
+
+/// Ends left by an enzyme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Overhang {
+    Blunt,
+    /// The top strand is cut before the bottom strand, leaving single-stranded 5' ends of the
+    /// given length.
+    FivePrime(usize),
+    /// The bottom strand is cut before the top strand, leaving single-stranded 3' ends of the
+    /// given length.
+    ThreePrime(usize),
+}
+
+/// A restriction enzyme.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Enzyme {
+    pub name: String,
+    /// The recognition site (uppercase, IUPAC codes).
+    pub site: Vec<u8>,
+    /// The cut position on the top strand, relative to the start of the site.
+    pub cut: isize,
+    /// The cut position on the bottom strand, relative to the start of the site on the top
+    /// strand.
+    pub complement_cut: isize,
+}
+
+impl Enzyme {
+    /// Create a new enzyme. Fails if the site is empty or contains symbols other than IUPAC
+    /// nucleotide codes.
+    pub fn new(name: &str, site: &[u8], cut: isize, complement_cut: isize) -> Result<Self> {
+        if site.is_empty() || site.iter().any(|&a| iupac::bits(a).is_none()) {
+            return Err(Error::InvalidSite(
+                String::from_utf8_lossy(site).into_owned(),
+            ));
+        }
+        Ok(Enzyme {
+            name: name.to_owned(),
+            site: site.to_ascii_uppercase(),
+            cut,
+            complement_cut,
+        })
+    }
+
+    /// Create an enzyme from the REBASE site notation. Either the cut position on the top strand
+    /// is marked with `^` (e.g. `G^AATTC`), with the bottom strand cut at the symmetric position,
+    /// or the cut positions downstream or upstream of the site are given in parentheses (e.g.
+    /// `GGTCTC(1/5)` or `(8/10)GCAGC`). Notations with cuts on both sides of the site are not
+    /// supported.
+    pub fn from_rebase(name: &str, notation: &str) -> Result<Self> {
+        let invalid = || Error::InvalidSite(notation.to_owned());
+        let parse_cuts = |cuts: &str| -> Result<(isize, isize)> {
+            let mut cuts = cuts.split('/').map(|c| c.parse::<isize>());
+            match (cuts.next(), cuts.next(), cuts.next()) {
+                (Some(Ok(cut)), Some(Ok(complement_cut)), None) => Ok((cut, complement_cut)),
+                _ => Err(invalid()),
+            }
+        };
+
+        if let Some(pos) = notation.find('^') {
+            let site = notation.replacen('^', "", 1);
+            let cut = pos as isize;
+            Enzyme::new(name, site.as_bytes(), cut, site.len() as isize - cut)
+        } else if let Some(site) = notation.strip_suffix(')') {
+            // cuts downstream of the site
+            let open = site.find('(').ok_or_else(invalid)?;
+            let (cut, complement_cut) = parse_cuts(&site[open + 1..])?;
+            let len = open as isize;
+            Enzyme::new(
+                name,
+                &site.as_bytes()[..open],
+                len + cut,
+                len + complement_cut,
+            )
+        } else if let Some(site) = notation.strip_prefix('(') {
+            // cuts upstream of the site
+            let close = site.find(')').ok_or_else(invalid)?;
+            let (cut, complement_cut) = parse_cuts(&site[..close])?;
+            Enzyme::new(name, &site.as_bytes()[close + 1..], -cut, -complement_cut)
+        } else {
+            Err(invalid())
+        }
+    }
+
+    /// The ends left by this enzyme.
+    pub fn overhang(&self) -> Overhang {
+        let len = (self.complement_cut - self.cut).unsigned_abs();
+        match self.cut.cmp(&self.complement_cut) {
+            std::cmp::Ordering::Equal => Overhang::Blunt,
+            std::cmp::Ordering::Less => Overhang::FivePrime(len),
+            std::cmp::Ordering::Greater => Overhang::ThreePrime(len),
+        }
+    }
+
+    /// Whether the recognition site equals its reverse complement, such that it is recognized
+    /// on both strands at the same position.
+    pub fn is_palindromic(&self) -> bool {
+        dna::revcomp(&self.site) == self.site
+    }
+
+    fn matches(site: &[u8], seq: &[u8], pos: usize) -> bool {
+        let n = seq.len();
+        site.iter()
+            .enumerate()
+            .all(|(i, &code)| iupac::contains(code, seq[(pos + i) % n]))
+    }
+
+    /// All cuts of this enzyme in the given sequence, ordered by site position and strand. If the
+    /// sequence is circular, sites and cuts may span the origin. Otherwise, sites with a cut
+    /// outside of the sequence are ignored.
+    pub fn cuts(&self, seq: &[u8], circular: bool) -> Vec<Cut<'_>> {
+        let (n, len) = (seq.len(), self.site.len());
+        let mut cuts = Vec::new();
+        if n == 0 || (!circular && len > n) {
+            return cuts;
+        }
+        let last = if circular { n } else { n - len + 1 };
+        let reverse_site = dna::revcomp(&self.site);
+        let palindromic = reverse_site == self.site;
+
+        for pos in 0..last {
+            let mut add = |strand, top: isize, bottom: isize| {
+                let (top, bottom) = if circular {
+                    (
+                        top.rem_euclid(n as isize) as usize,
+                        bottom.rem_euclid(n as isize) as usize,
+                    )
+                } else if top > 0 && bottom > 0 && top < n as isize && bottom < n as isize {
+                    (top as usize, bottom as usize)
+                } else {
+                    return;
+                };
+                cuts.push(Cut {
+                    enzyme: self,
+                    site: pos,
+                    strand,
+                    top,
+                    bottom,
+                });
+            };
+
+            let pos_ = pos as isize;
+            if Self::matches(&self.site, seq, pos) {
+                add(
+                    ReqStrand::Forward,
+                    pos_ + self.cut,
+                    pos_ + self.complement_cut,
+                );
+            }
+            if !palindromic && Self::matches(&reverse_site, seq, pos) {
+                // the site on the bottom strand, whose cuts are mirrored
+                let end = pos_ + len as isize;
+                add(
+                    ReqStrand::Reverse,
+                    end - self.complement_cut,
+                    end - self.cut,
+                );
+            }
+        }
+        cuts
+    }
+}
+
+/// Create the bundled set of common restriction enzymes.
+pub fn common_enzymes() -> Vec<Enzyme> {
+    COMMON_ENZYMES
+        .iter()
+        .map(|(name, notation)| Enzyme::from_rebase(name, notation).unwrap())
+        .collect()
+}
+
+/// Parse enzymes from a REBASE file in EMBOSS format (`emboss_e.###`), with one tab-separated
+/// record per enzyme: name, site, site length, number of cuts, whether the enzyme cuts bluntly,
+/// and up to four cut positions. Cut positions count from 1 at the first base of the site and
+/// denote the base after which the strand is cut, with negative positions upstream of the site.
+/// Enzymes with unknown cut positions are skipped, and of enzymes cutting on both sides of the
+/// site, only the first pair of cuts is used. Comment lines start with `#`.
+pub fn read_emboss<R: BufRead>(reader: R) -> Result<Vec<Enzyme>> {
+    let mut enzymes = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let invalid = || Error::InvalidRecord(i + 1);
+        if fields.len() < 5 {
+            return Err(invalid());
+        }
+        let num_cuts: usize = fields[3].parse().map_err(|_| invalid())?;
+        if num_cuts == 0 {
+            continue;
+        }
+        if fields.len() < 7 {
+            return Err(invalid());
+        }
+        let position = |field: &str| -> Result<isize> {
+            let pos: isize = field.parse().map_err(|_| invalid())?;
+            // there is no position 0, -1 denotes the base before the site
+            Ok(if pos < 0 { pos + 1 } else { pos })
+        };
+        let (cut, complement_cut) = (position(fields[5])?, position(fields[6])?);
+        enzymes.push(Enzyme::new(
+            fields[0],
+            fields[1].as_bytes(),
+            cut,
+            complement_cut,
+        )?);
+    }
+    Ok(enzymes)
+}
+
+/// A cut of both strands by an enzyme.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cut<'a> {
+    pub enzyme: &'a Enzyme,
+    /// Start of the recognition site.
+    pub site: usize,
+    /// The strand the recognition site is found on.
+    pub strand: ReqStrand,
+    /// Cut position on the top strand.
+    pub top: usize,
+    /// Cut position on the bottom strand.
+    pub bottom: usize,
+}
+
+/// The result of a digest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Digest<'a> {
+    /// All cuts, ordered by the position of the top strand cut.
+    pub cuts: Vec<Cut<'a>>,
+    /// The fragments, given by their top strand. For circular sequences, the fragment spanning
+    /// the origin ends beyond the sequence length, and an uncut sequence is returned as a single
+    /// fragment.
+    pub fragments: Vec<Range<usize>>,
+}
+
+/// Simulate the digest of the given sequence with all of the given enzymes.
+pub fn digest<'a>(enzymes: &'a [Enzyme], seq: &[u8], circular: bool) -> Digest<'a> {
+    let mut cuts: Vec<Cut<'a>> = enzymes
+        .iter()
+        .flat_map(|enzyme| enzyme.cuts(seq, circular))
+        .collect();
+    cuts.sort_by_key(|cut| (cut.top, cut.site));
+
+    let mut positions: Vec<usize> = cuts.iter().map(|cut| cut.top).collect();
+    positions.dedup();
+    let fragments = if circular {
+        match (positions.first(), positions.last()) {
+            (Some(&first), Some(&last)) => positions
+                .windows(2)
+                .map(|w| w[0]..w[1])
+                .chain(std::iter::once(last..first + seq.len()))
+                .collect(),
+            _ => std::iter::once(0..seq.len()).collect(),
+        }
+    } else {
+        let mut bounds = vec![0];
+        bounds.extend(positions);
+        bounds.push(seq.len());
+        bounds.windows(2).map(|w| w[0]..w[1]).collect()
+    };
+
+    Digest { cuts, fragments }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_rebase() {
+        let ecori = Enzyme::from_rebase("EcoRI", "G^AATTC").unwrap();
+        assert_eq!((ecori.cut, ecori.complement_cut), (1, 5));
+        assert!(ecori.is_palindromic());
+        let bsai = Enzyme::from_rebase("BsaI", "GGTCTC(1/5)").unwrap();
+        assert_eq!((bsai.cut, bsai.complement_cut), (7, 11));
+        assert_eq!(bsai.overhang(), Overhang::FivePrime(4));
+        assert!(!bsai.is_palindromic());
+        let upstream = Enzyme::from_rebase("BceAI", "(12/14)ACGGC").unwrap();
+        assert_eq!(upstream.site, b"ACGGC");
+        assert_eq!((upstream.cut, upstream.complement_cut), (-12, -14));
+        assert_eq!(
+            Enzyme::from_rebase("PstI", "CTGCA^G").unwrap().overhang(),
+            Overhang::ThreePrime(4)
+        );
+        assert_eq!(
+            Enzyme::from_rebase("SmaI", "CCC^GGG").unwrap().overhang(),
+            Overhang::Blunt
+        );
+        assert!(Enzyme::from_rebase("X", "GAATTC").is_err());
+        assert!(Enzyme::from_rebase("X", "GAXTTC(1/5)").is_err());
+        assert!(Enzyme::from_rebase("X", "GGTCTC(1/5").is_err());
+        assert_eq!(common_enzymes().len(), COMMON_ENZYMES.len());
+    }
+
+    #[test]
+    fn test_reverse_strand() {
+        let bsai = Enzyme::from_rebase("BsaI", "GGTCTC(1/5)").unwrap();
+        // the site on the bottom strand, cutting upstream on the top strand
+        let seq = b"AAAAAAAAAAGAGACCAAAA";
+        let cuts = bsai.cuts(seq, false);
+        assert_eq!(cuts.len(), 1);
+        assert_eq!(cuts[0].strand, ReqStrand::Reverse);
+        assert_eq!(cuts[0].site, 10);
+        assert_eq!((cuts[0].top, cuts[0].bottom), (5, 9));
+
+        // the same cuts as on the forward strand of the reverse complement
+        let revcomp = dna::revcomp(&seq[..]);
+        let forward = bsai.cuts(&revcomp, false);
+        assert_eq!(forward[0].strand, ReqStrand::Forward);
+        assert_eq!(
+            (seq.len() - forward[0].bottom, seq.len() - forward[0].top),
+            (cuts[0].top, cuts[0].bottom)
+        );
+
+        // cuts outside of a linear sequence are ignored
+        assert!(bsai.cuts(&seq[5..], false).is_empty());
+    }
+
+    #[test]
+    fn test_circular_digest() {
+        let enzymes = vec![Enzyme::from_rebase("EcoRI", "G^AATTC").unwrap()];
+        // sites spanning the origin
+        let seq = b"ATTCAAAAGAATTCAAAAGA";
+        let result = digest(&enzymes, seq, true);
+        assert_eq!(result.cuts.len(), 2);
+        assert_eq!(result.fragments, vec![9..19, 19..29]);
+        assert_eq!(result.cuts[1].site, 18);
+        assert_eq!(result.cuts[1].bottom, 3);
+
+        let linear = digest(&enzymes, seq, false);
+        assert_eq!(linear.fragments, vec![0..9, 9..20]);
+        let circular = digest(&enzymes, b"AAAA", true);
+        assert_eq!(circular.fragments.len(), 1);
+        assert_eq!(circular.fragments[0], 0..4);
+    }
+
+    #[test]
+    fn test_read_emboss() {
+        let data = b"# REBASE version 104
+#
+AatII\tGACGTC\t6\t2\t0\t5\t1\t0\t0
+BaeI\tACNNNNGTAYC\t11\t4\t0\t-11\t-16\t23\t28
+AbaCIII\tCTATCAV\t7\t0\t0\t0\t0\t0\t0
+";
+        let enzymes = read_emboss(&data[..]).unwrap();
+        assert_eq!(enzymes.len(), 2);
+        assert_eq!(enzymes[0].name, "AatII");
+        assert_eq!(enzymes[0].overhang(), Overhang::ThreePrime(4));
+        assert_eq!((enzymes[1].cut, enzymes[1].complement_cut), (-10, -15));
+        assert!(read_emboss(&b"AatII\tGACGTC\t6\tx\t0\t5\t1"[..]).is_err());
+    }
+}
@@ -0,0 +1,790 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! RNA secondary structure prediction, e.g. for checking sgRNAs or primers for hairpins.
+//!
+//! Two algorithms are provided, both allowing Watson-Crick and G-U wobble pairs (DNA is folded
+//! like RNA, treating `T` as `U`), and both returning structures without pseudoknots:
+//!
+//! * [`nussinov`](fn.nussinov.html) maximizes the number of base pairs (Nussinov and Jacobson,
+//!   PNAS, 1980).
+//! * [`mfe`](fn.mfe.html) minimizes the free energy with the loop decomposition of Zuker and
+//!   Stiegler (Nucleic Acids Research, 1981). The energy model is a simplified version of the
+//!   Turner 2004 nearest-neighbor model: helices are scored with the stacking energies, hairpin,
+//!   bulge and interior loops with their length-dependent initiation energies, an asymmetry
+//!   penalty and penalties for terminal A-U and G-U pairs, and multiloops with a linear
+//!   function of the number of branches. Sequence-dependent mismatch, dangling end and special
+//!   loop energies are not considered, hence energies differ slightly from those of e.g.
+//!   ViennaRNA. Interior loops are limited to 30 unpaired bases.
+//!
+//! Complexity: O(n^3) for a sequence of length n.
+//!
+//! Structures can be parsed from and written in dot-bracket notation, with pseudoknots given by
+//! further types of brackets, i.e. `[]`, `{}`, `<>` and pairs of upper- and lowercase letters
+//! (e.g. `Aa`). For benchmarking predictions, structures can be compared by their base pair
+//! distance and their mountain distance.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::seq_analysis::rna_structure::{mfe, nussinov, Structure};
+//!
+//! let seq = b"GGGGAAACCCC";
+//! let (structure, energy) = mfe(seq);
+//! assert_eq!(structure.dot_bracket(), "((((...))))");
+//! assert!((energy - -4.5).abs() < 1e-9);
+//!
+//! let structure = nussinov(b"GGGAAAUCC", 3);
+//! assert_eq!(structure.dot_bracket(), "(((...)))");
+//!
+//! // compare with a reference structure containing a pseudoknot
+//! let reference = Structure::from_dot_bracket("(((.[[)))]]").unwrap();
+//! assert!(reference.has_pseudoknots());
+//! let predicted = mfe(b"GGGAGGCCCCC").0;
+//! assert_eq!(predicted.base_pair_distance(&reference), 2);
+//! ```
+
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum Error {
+    #[error("unbalanced bracket at position {0} of dot-bracket string")]
+    UnbalancedBracket(usize),
+
+    #[error("invalid character '{character}' at position {pos} of dot-bracket string")]
+    InvalidCharacter { pos: usize, character: char },
+}
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Bracket types of dot-bracket notation, in the order they are used for pseudoknots, with
+/// further pseudoknots given by pairs of upper- and lowercase letters.
+const BRACKETS: [(u8, u8); 4] = [(b'(', b')'), (b'[', b']'), (b'{', b'}'), (b'<', b'>')];
+
+/// Minimal number of unpaired bases in a hairpin loop.
+const MIN_HAIRPIN: usize = 3;
+/// Maximal number of unpaired bases in an interior loop.
+const MAX_LOOP: usize = 30;
+const INF: i32 = i32::MAX / 4;
+
+// Energies are given in dcal/mol.
+
+/// Stacking energies of pair (i, j) on pair (i + 1, j - 1), indexed by the type of (i, j) and
+/// the type of (j - 1, i + 1), with the types CG, GC, GU, UG, AU, UA.
+const STACK: [[i32; 6]; 6] = [
+    [-240, -330, -210, -140, -210, -210],
+    [-330, -340, -250, -150, -220, -240],
+    [-210, -250, 130, -50, -140, -130],
+    [-140, -150, -50, 30, -60, -100],
+    [-210, -220, -140, -60, -110, -90],
+    [-210, -240, -130, -100, -90, -130],
+];
+/// Initiation energies of loops by number of unpaired bases, extrapolated logarithmically
+/// beyond the table.
+const HAIRPIN: [i32; 10] = [INF, INF, INF, 540, 560, 570, 540, 600, 550, 640];
+const BULGE: [i32; 7] = [INF, 380, 280, 320, 360, 400, 440];
+const INTERIOR: [i32; 7] = [INF, INF, 50, 160, 110, 200, 200];
+/// Factor of the logarithmic extrapolation of loop initiation energies (1.75 RT).
+const LOOP_EXTRAPOLATION: f64 = 107.856;
+/// Penalty per unpaired base of difference between the sides of an interior loop.
+const ASYMMETRY: i32 = 60;
+const MAX_ASYMMETRY: i32 = 300;
+/// Penalty for A-U and G-U pairs terminating a helix.
+const TERMINAL_AU: i32 = 50;
+/// Penalty for A-U and G-U pairs closing an interior loop.
+const INTERIOR_AU: i32 = 70;
+/// Multiloop penalties for closing the loop and per branch (including the closing pair).
+const MULTILOOP_CLOSING: i32 = 340;
+const MULTILOOP_BRANCH: i32 = 40;
+
+/// A secondary structure of a sequence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Structure {
+    /// Length of the sequence.
+    pub len: usize,
+    /// Base pairs (i, j) with i < j, ordered by i.
+    pub pairs: Vec<(usize, usize)>,
+}
+
+/// A base pair of a pseudoknot-free structure with the base pairs it directly encloses, i.e.
+/// the branches of its loop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    /// The base pair.
+    pub pair: (usize, usize),
+    /// The enclosed base pairs, ordered by position.
+    pub children: Vec<Node>,
+}
+
+impl Structure {
+    /// Parse a structure in dot-bracket notation. Unpaired bases are given by `.`, pairs by
+    /// `()`, `[]`, `{}`, `<>` or an upper- and the respective lowercase letter.
+    pub fn from_dot_bracket(dot_bracket: &str) -> Result<Self> {
+        // positions of unclosed brackets of each type
+        let mut open: Vec<Vec<usize>> = vec![Vec::new(); BRACKETS.len() + 26];
+        let mut pairs = Vec::new();
+        for (pos, &c) in dot_bracket.as_bytes().iter().enumerate() {
+            if c == b'.' {
+                continue;
+            }
+            match bracket_type(c) {
+                Some((level, true)) => open[level].push(pos),
+                Some((level, false)) => {
+                    let i = open[level].pop().ok_or(Error::UnbalancedBracket(pos))?;
+                    pairs.push((i, pos));
+                }
+                None => {
+                    return Err(Error::InvalidCharacter {
+                        pos,
+                        character: dot_bracket[pos..].chars().next().unwrap(),
+                    })
+                }
+            }
+        }
+        if let Some(&pos) = open.iter().filter_map(|positions| positions.first()).min() {
+            return Err(Error::UnbalancedBracket(pos));
+        }
+        Ok(Structure::new(dot_bracket.len(), pairs))
+    }
+
+    /// The structure in dot-bracket notation, e.g. `((((...))))`. Pairs crossing other pairs are
+    /// written with further types of brackets, choosing the first type for which they do not
+    /// cross a previous pair.
+    ///
+    /// # Panics
+    ///
+    /// If more than 30 types of brackets are needed.
+    pub fn dot_bracket(&self) -> String {
+        let mut dot_bracket = vec![b'.'; self.len];
+        let mut levels: Vec<Vec<(usize, usize)>> = Vec::new();
+        for &pair in &self.pairs {
+            let level = match levels
+                .iter()
+                .position(|level| level.iter().all(|&other| !crosses(pair, other)))
+            {
+                Some(level) => level,
+                None => {
+                    levels.push(Vec::new());
+                    levels.len() - 1
+                }
+            };
+            levels[level].push(pair);
+            let (open, close) = brackets(level);
+            dot_bracket[pair.0] = open;
+            dot_bracket[pair.1] = close;
+        }
+        String::from_utf8(dot_bracket).unwrap()
+    }
+
+    /// The partner of each position, or `None` if it is unpaired.
+    pub fn partners(&self) -> Vec<Option<usize>> {
+        let mut partners = vec![None; self.len];
+        for &(i, j) in &self.pairs {
+            partners[i] = Some(j);
+            partners[j] = Some(i);
+        }
+        partners
+    }
+
+    /// Whether the structure contains crossing base pairs.
+    pub fn has_pseudoknots(&self) -> bool {
+        let mut open = Vec::new();
+        for (pos, partner) in self.partners().into_iter().enumerate() {
+            match partner {
+                Some(j) if j > pos => open.push(pos),
+                Some(i) => {
+                    // the partner has to be the innermost unclosed position
+                    let innermost = open.pop();
+                    if innermost != Some(i) {
+                        return true;
+                    }
+                }
+                None => (),
+            }
+        }
+        false
+    }
+
+    /// The tree of base pairs, given by the base pairs of the exterior loop, or `None` if the
+    /// structure contains pseudoknots.
+    pub fn tree(&self) -> Option<Vec<Node>> {
+        if self.has_pseudoknots() {
+            return None;
+        }
+        // the children of the enclosing pairs of the current position
+        let mut stack: Vec<Vec<Node>> = vec![Vec::new()];
+        for (pos, partner) in self.partners().into_iter().enumerate() {
+            match partner {
+                Some(j) if j > pos => stack.push(Vec::new()),
+                Some(i) => {
+                    let children = stack.pop().unwrap();
+                    stack.last_mut().unwrap().push(Node {
+                        pair: (i, pos),
+                        children,
+                    });
+                }
+                None => (),
+            }
+        }
+        stack.pop()
+    }
+
+    /// The mountain representation of the structure, i.e. the number of base pairs (i, j) with
+    /// i <= k < j for each position k.
+    pub fn mountain(&self) -> Vec<usize> {
+        let mut mountain = vec![0; self.len];
+        for &(i, j) in &self.pairs {
+            for height in &mut mountain[i..j] {
+                *height += 1;
+            }
+        }
+        mountain
+    }
+
+    /// The base pair distance to the given structure, i.e. the number of base pairs contained
+    /// in only one of both structures.
+    pub fn base_pair_distance(&self, other: &Structure) -> usize {
+        let shared = self
+            .pairs
+            .iter()
+            .filter(|pair| other.pairs.binary_search(pair).is_ok())
+            .count();
+        self.pairs.len() + other.pairs.len() - 2 * shared
+    }
+
+    /// The mountain distance to the given structure of the same length, i.e. the sum of the
+    /// absolute differences of the mountain representations.
+    ///
+    /// # Panics
+    ///
+    /// If the structures have different lengths.
+    pub fn mountain_distance(&self, other: &Structure) -> usize {
+        assert_eq!(self.len, other.len, "structures have different lengths");
+        self.mountain()
+            .into_iter()
+            .zip(other.mountain())
+            .map(|(a, b)| a.abs_diff(b))
+            .sum()
+    }
+
+    fn new(len: usize, mut pairs: Vec<(usize, usize)>) -> Self {
+        pairs.sort_unstable();
+        Structure { len, pairs }
+    }
+}
+
+/// Whether the given base pairs cross each other.
+fn crosses((i, j): (usize, usize), (k, l): (usize, usize)) -> bool {
+    (i < k && k < j && j < l) || (k < i && i < l && l < j)
+}
+
+/// The type of the given bracket and whether it is opening.
+fn bracket_type(c: u8) -> Option<(usize, bool)> {
+    if let Some(level) = BRACKETS.iter().position(|&(open, _)| open == c) {
+        return Some((level, true));
+    }
+    if let Some(level) = BRACKETS.iter().position(|&(_, close)| close == c) {
+        return Some((level, false));
+    }
+    match c {
+        b'A'..=b'Z' => Some((BRACKETS.len() + (c - b'A') as usize, true)),
+        b'a'..=b'z' => Some((BRACKETS.len() + (c - b'a') as usize, false)),
+        _ => None,
+    }
+}
+
+/// The opening and closing bracket of the given type.
+fn brackets(level: usize) -> (u8, u8) {
+    match BRACKETS.get(level) {
+        Some(&brackets) => brackets,
+        None => {
+            let letter = level - BRACKETS.len();
+            assert!(letter < 26, "too many types of brackets needed");
+            (b'A' + letter as u8, b'a' + letter as u8)
+        }
+    }
+}
+
+/// Predict the structure of the given sequence with the maximal number of base pairs, with at
+/// least `min_loop` unpaired bases in each hairpin loop.
+pub fn nussinov(seq: &[u8], min_loop: usize) -> Structure {
+    let n = seq.len();
+    // maximal number of pairs in seq[i..j]
+    let mut pairs = vec![vec![0usize; n + 1]; n + 1];
+    // pairs of i with k, followed by seq[k + 1..j]
+    let paired =
+        |pairs: &[Vec<usize>], i: usize, k: usize, j: usize| pairs[i + 1][k] + 1 + pairs[k + 1][j];
+    for i in (0..n).rev() {
+        for j in i + 1..=n {
+            let mut best = pairs[i + 1][j];
+            for k in i + min_loop + 1..j {
+                if pair_type(seq[i], seq[k]).is_some() {
+                    best = best.max(paired(&pairs, i, k, j));
+                }
+            }
+            pairs[i][j] = best;
+        }
+    }
+
+    let mut structure = Vec::new();
+    let mut intervals = vec![(0, n)];
+    while let Some((i, j)) = intervals.pop() {
+        if i >= j || pairs[i][j] == 0 {
+            continue;
+        }
+        if pairs[i][j] == pairs[i + 1][j] {
+            intervals.push((i + 1, j));
+            continue;
+        }
+        let k = (i + min_loop + 1..j)
+            .find(|&k| {
+                pair_type(seq[i], seq[k]).is_some() && paired(&pairs, i, k, j) == pairs[i][j]
+            })
+            .unwrap();
+        structure.push((i, k));
+        intervals.push((i + 1, k));
+        intervals.push((k + 1, j));
+    }
+    Structure::new(n, structure)
+}
+
+/// Predict the structure of the given sequence with minimum free energy. Returns the structure
+/// and its free energy in kcal/mol.
+pub fn mfe(seq: &[u8]) -> (Structure, f64) {
+    let n = seq.len();
+    // minimal energy of seq[i..=j], given that i and j pair
+    let mut v = vec![vec![INF; n]; n];
+    // minimal energy of seq[i..=j] within a multiloop, with at least one branch
+    let mut wm = vec![vec![INF; n]; n];
+    for span in MIN_HAIRPIN + 1..n {
+        for i in 0..n - span {
+            let j = i + span;
+            if let Some(t) = pair_type(seq[i], seq[j]) {
+                let mut e = hairpin(seq, i, j);
+                for (k, l) in interior_pairs(i, j) {
+                    if v[k][l] < INF {
+                        e = e.min(interior(seq, i, j, k, l) + v[k][l]);
+                    }
+                }
+                for u in i + 2..j - 1 {
+                    if wm[i + 1][u] < INF && wm[u + 1][j - 1] < INF {
+                        e = e.min(multiloop(t) + wm[i + 1][u] + wm[u + 1][j - 1]);
+                    }
+                }
+                v[i][j] = e;
+            }
+            let mut e = wm[i + 1][j].min(wm[i][j - 1]);
+            if v[i][j] < INF {
+                e = e.min(v[i][j] + branch(seq, i, j));
+            }
+            for k in i + 1..j {
+                if wm[i][k - 1] < INF && wm[k][j] < INF {
+                    e = e.min(wm[i][k - 1] + wm[k][j]);
+                }
+            }
+            wm[i][j] = e;
+        }
+    }
+
+    // minimal energy of the prefix of length j
+    let mut w = vec![0; n + 1];
+    for j in 1..=n {
+        w[j] = w[j - 1];
+        for i in 0..j {
+            if v[i][j - 1] < INF {
+                w[j] = w[j].min(w[i] + v[i][j - 1] + exterior(seq, i, j - 1));
+            }
+        }
+    }
+
+    enum Task {
+        Exterior(usize),
+        Pair(usize, usize),
+        Multiloop(usize, usize),
+    }
+    let mut pairs = Vec::new();
+    let mut tasks = vec![Task::Exterior(n)];
+    while let Some(task) = tasks.pop() {
+        match task {
+            Task::Exterior(0) => (),
+            Task::Exterior(j) => {
+                if w[j] == w[j - 1] {
+                    tasks.push(Task::Exterior(j - 1));
+                    continue;
+                }
+                let i = (0..j)
+                    .find(|&i| {
+                        v[i][j - 1] < INF && w[i] + v[i][j - 1] + exterior(seq, i, j - 1) == w[j]
+                    })
+                    .unwrap();
+                tasks.push(Task::Pair(i, j - 1));
+                tasks.push(Task::Exterior(i));
+            }
+            Task::Pair(i, j) => {
+                pairs.push((i, j));
+                let e = v[i][j];
+                if e == hairpin(seq, i, j) {
+                    continue;
+                }
+                if let Some((k, l)) = interior_pairs(i, j)
+                    .find(|&(k, l)| v[k][l] < INF && interior(seq, i, j, k, l) + v[k][l] == e)
+                {
+                    tasks.push(Task::Pair(k, l));
+                    continue;
+                }
+                let t = pair_type(seq[i], seq[j]).unwrap();
+                let u = (i + 2..j - 1)
+                    .find(|&u| {
+                        wm[i + 1][u] < INF
+                            && wm[u + 1][j - 1] < INF
+                            && multiloop(t) + wm[i + 1][u] + wm[u + 1][j - 1] == e
+                    })
+                    .unwrap();
+                tasks.push(Task::Multiloop(i + 1, u));
+                tasks.push(Task::Multiloop(u + 1, j - 1));
+            }
+            Task::Multiloop(i, j) => {
+                let e = wm[i][j];
+                if v[i][j] < INF && v[i][j] + branch(seq, i, j) == e {
+                    tasks.push(Task::Pair(i, j));
+                } else if wm[i + 1][j] == e {
+                    tasks.push(Task::Multiloop(i + 1, j));
+                } else if wm[i][j - 1] == e {
+                    tasks.push(Task::Multiloop(i, j - 1));
+                } else {
+                    let k = (i + 1..j)
+                        .find(|&k| {
+                            wm[i][k - 1] < INF && wm[k][j] < INF && wm[i][k - 1] + wm[k][j] == e
+                        })
+                        .unwrap();
+                    tasks.push(Task::Multiloop(i, k - 1));
+                    tasks.push(Task::Multiloop(k, j));
+                }
+            }
+        }
+    }
+
+    (Structure::new(n, pairs), w[n] as f64 / 100.0)
+}
+
+/// Free energy of the given structure of the given sequence in kcal/mol under the energy model
+/// of [`mfe`](fn.mfe.html), or `None` if the structure contains pseudoknots, pairs that are not
+/// Watson-Crick or G-U pairs, or hairpin loops with less than three unpaired bases.
+pub fn energy(seq: &[u8], structure: &Structure) -> Option<f64> {
+    let n = seq.len();
+    let mut partner = vec![None; n];
+    for &(i, j) in &structure.pairs {
+        if i >= j || j >= n || partner[i].is_some() || partner[j].is_some() {
+            return None;
+        }
+        pair_type(seq[i], seq[j])?;
+        partner[i] = Some(j);
+        partner[j] = Some(i);
+    }
+    // pairs directly enclosed by the given interval
+    let children = |start: usize, end: usize| {
+        let mut children = Vec::new();
+        let mut k = start;
+        while k < end {
+            match partner[k] {
+                Some(l) if l > k && l < end => {
+                    children.push((k, l));
+                    k = l + 1;
+                }
+                Some(_) => return None,
+                None => k += 1,
+            }
+        }
+        Some(children)
+    };
+
+    let mut e = 0;
+    for (i, j) in children(0, n)? {
+        e += exterior(seq, i, j);
+    }
+    for &(i, j) in &structure.pairs {
+        e += match children(i + 1, j)?[..] {
+            [] if j - i > MIN_HAIRPIN => hairpin(seq, i, j),
+            [] => return None,
+            [(k, l)] => interior(seq, i, j, k, l),
+            ref branches => {
+                multiloop(pair_type(seq[i], seq[j]).unwrap())
+                    + branches
+                        .iter()
+                        .map(|&(k, l)| branch(seq, k, l))
+                        .sum::<i32>()
+            }
+        };
+    }
+    Some(e as f64 / 100.0)
+}
+
+/// Type of the pair of the given bases (ignoring case, `T` is treated as `U`), i.e. an index
+/// into the energy tables.
+fn pair_type(a: u8, b: u8) -> Option<usize> {
+    let base = |base: u8| match base.to_ascii_uppercase() {
+        b'T' => b'U',
+        base => base,
+    };
+    match (base(a), base(b)) {
+        (b'C', b'G') => Some(0),
+        (b'G', b'C') => Some(1),
+        (b'G', b'U') => Some(2),
+        (b'U', b'G') => Some(3),
+        (b'A', b'U') => Some(4),
+        (b'U', b'A') => Some(5),
+        _ => None,
+    }
+}
+
+/// Whether the pair type is an A-U or G-U pair.
+fn is_weak(pair_type: usize) -> bool {
+    pair_type >= 2
+}
+
+fn terminal_penalty(pair_type: usize) -> i32 {
+    if is_weak(pair_type) {
+        TERMINAL_AU
+    } else {
+        0
+    }
+}
+
+/// Initiation energy of a loop with the given number of unpaired bases.
+fn initiation(table: &[i32], unpaired: usize) -> i32 {
+    match table.get(unpaired) {
+        Some(&e) => e,
+        None => {
+            let max = table.len() - 1;
+            table[max] + (LOOP_EXTRAPOLATION * (unpaired as f64 / max as f64).ln()).round() as i32
+        }
+    }
+}
+
+/// Energy of the hairpin loop closed by the pair (i, j).
+fn hairpin(seq: &[u8], i: usize, j: usize) -> i32 {
+    let unpaired = j - i - 1;
+    if unpaired < MIN_HAIRPIN {
+        return INF;
+    }
+    initiation(&HAIRPIN, unpaired) + terminal_penalty(pair_type(seq[i], seq[j]).unwrap())
+}
+
+/// Energy of the stack, bulge or interior loop closed by the pairs (i, j) and (k, l).
+fn interior(seq: &[u8], i: usize, j: usize, k: usize, l: usize) -> i32 {
+    let outer = pair_type(seq[i], seq[j]).unwrap();
+    let inner = pair_type(seq[l], seq[k]).unwrap();
+    let (left, right) = (k - i - 1, j - l - 1);
+    match (left.min(right), left.max(right)) {
+        (0, 0) => STACK[outer][inner],
+        (0, 1) => initiation(&BULGE, 1) + STACK[outer][inner],
+        (0, bulge) => initiation(&BULGE, bulge) + terminal_penalty(outer) + terminal_penalty(inner),
+        (shorter, longer) => {
+            let closing = [outer, inner].iter().filter(|&&t| is_weak(t)).count() as i32;
+            initiation(&INTERIOR, shorter + longer)
+                + (ASYMMETRY * (longer - shorter) as i32).min(MAX_ASYMMETRY)
+                + INTERIOR_AU * closing
+        }
+    }
+}
+
+/// Candidate inner pairs (k, l) of interior loops closed by (i, j).
+fn interior_pairs(i: usize, j: usize) -> impl Iterator<Item = (usize, usize)> {
+    (i + 1..j.min(i + MAX_LOOP + 2)).flat_map(move |k| {
+        let left = k - i - 1;
+        (k + MIN_HAIRPIN + 1..j)
+            .rev()
+            .take_while(move |&l| left + j - l - 1 <= MAX_LOOP)
+            .map(move |l| (k, l))
+    })
+}
+
+/// Energy of closing a multiloop with a pair of the given type.
+fn multiloop(pair_type: usize) -> i32 {
+    MULTILOOP_CLOSING + MULTILOOP_BRANCH + terminal_penalty(pair_type)
+}
+
+/// Energy of the pair (i, j) as a branch of a multiloop.
+fn branch(seq: &[u8], i: usize, j: usize) -> i32 {
+    MULTILOOP_BRANCH + terminal_penalty(pair_type(seq[i], seq[j]).unwrap())
+}
+
+/// Energy of the pair (i, j) in the exterior loop.
+fn exterior(seq: &[u8], i: usize, j: usize) -> i32 {
+    terminal_penalty(pair_type(seq[i], seq[j]).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::random_seq;
+    use rand::prelude::*;
+
+    fn is_valid(seq: &[u8], structure: &Structure, min_loop: usize) -> bool {
+        structure.pairs.iter().all(|&(i, j)| {
+            j - i > min_loop
+                && pair_type(seq[i], seq[j]).is_some()
+                && structure.pairs.iter().all(|&(k, l)| {
+                    (k, l) == (i, j) || l < i || k > j || (i < k && l < j) || (k < i && j < l)
+                })
+        })
+    }
+
+    #[test]
+    fn test_nussinov() {
+        let structure = nussinov(b"GGGAAAUCC", 3);
+        assert_eq!(structure.dot_bracket(), "(((...)))");
+        // a longer minimal loop excludes the innermost pair
+        assert_eq!(nussinov(b"GGGAAAUCC", 4).pairs.len(), 2);
+        assert!(nussinov(b"AAAAAA", 0).pairs.is_empty());
+        assert!(nussinov(b"", 3).pairs.is_empty());
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let seq = random_seq(&mut rng, b"ACGU", 60);
+        let structure = nussinov(&seq, 3);
+        assert!(is_valid(&seq, &structure, 3));
+        assert!(structure.pairs.len() >= nussinov(&seq[1..], 3).pairs.len());
+    }
+
+    #[test]
+    fn test_mfe() {
+        let (structure, e) = mfe(b"GGGGAAACCCC");
+        assert_eq!(structure.dot_bracket(), "((((...))))");
+        assert_relative_eq!(e, -4.5);
+        // DNA is folded like RNA
+        assert_eq!(mfe(b"ggggtttcccc").0.dot_bracket(), "((((...))))");
+
+        // a bulge
+        let (structure, e) = mfe(b"GGGAGGAAACCCCC");
+        assert_eq!(structure.dot_bracket(), "(((.((...)))))");
+        assert_relative_eq!(e, 5.4 + 3.8 - 3.3 - 3.3 - 3.3 - 3.3, epsilon = 1e-9);
+
+        let (structure, e) = mfe(b"AAAAAAA");
+        assert!(structure.pairs.is_empty());
+        assert_eq!(e, 0.0);
+        assert_eq!(mfe(b"").0.len, 0);
+    }
+
+    #[test]
+    fn test_multiloop() {
+        let seq = b"GCGCAGGGGAAACCCCAACAGCAGAAACUGCUGAAGCGC";
+        let (structure, e) = mfe(seq);
+        assert_eq!(
+            structure.dot_bracket(),
+            "((((.((((...))))..((((((...))))))..))))"
+        );
+        assert_relative_eq!(e, -15.5, epsilon = 1e-9);
+        assert_eq!(energy(seq, &structure), Some(e));
+    }
+
+    #[test]
+    fn test_dot_bracket() {
+        for dot_bracket in &["((..((...))..))..", "..((..[[..))..]]", "(([[{{))]]}}", ""] {
+            let structure = Structure::from_dot_bracket(dot_bracket).unwrap();
+            assert_eq!(structure.dot_bracket(), *dot_bracket);
+        }
+        let structure = Structure::from_dot_bracket(".(.[).]").unwrap();
+        assert_eq!(structure.len, 7);
+        assert_eq!(structure.pairs, [(1, 4), (3, 6)]);
+        assert_eq!(
+            structure.partners(),
+            [None, Some(4), None, Some(6), Some(1), None, Some(3)]
+        );
+        // letters are written as the first free type of bracket
+        let structure = Structure::from_dot_bracket("((AA))aa").unwrap();
+        assert_eq!(structure.pairs, [(0, 5), (1, 4), (2, 7), (3, 6)]);
+        assert_eq!(structure.dot_bracket(), "(([[))]]");
+        // brackets are assigned in order of the pairs
+        let structure = Structure::from_dot_bracket("[(])").unwrap();
+        assert_eq!(structure.dot_bracket(), "([)]");
+
+        assert_eq!(
+            Structure::from_dot_bracket("((.)"),
+            Err(Error::UnbalancedBracket(0))
+        );
+        assert_eq!(
+            Structure::from_dot_bracket("(.)]"),
+            Err(Error::UnbalancedBracket(3))
+        );
+        assert_eq!(
+            Structure::from_dot_bracket("(.#)"),
+            Err(Error::InvalidCharacter {
+                pos: 2,
+                character: '#'
+            })
+        );
+    }
+
+    #[test]
+    fn test_tree() {
+        let structure = Structure::from_dot_bracket("((.(..).(..)))..(..)").unwrap();
+        assert!(!structure.has_pseudoknots());
+        let tree = structure.tree().unwrap();
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree[0].pair, (0, 13));
+        assert_eq!(tree[0].children.len(), 1);
+        let multiloop = &tree[0].children[0];
+        assert_eq!(multiloop.pair, (1, 12));
+        let branches: Vec<(usize, usize)> = multiloop.children.iter().map(|c| c.pair).collect();
+        assert_eq!(branches, [(3, 6), (8, 11)]);
+        assert!(multiloop.children.iter().all(|c| c.children.is_empty()));
+        assert_eq!(tree[1].pair, (16, 19));
+
+        let pseudoknot = Structure::from_dot_bracket("((..[[..))..]]").unwrap();
+        assert!(pseudoknot.has_pseudoknots());
+        assert_eq!(pseudoknot.tree(), None);
+        assert_eq!(
+            Structure::from_dot_bracket("...").unwrap().tree(),
+            Some(vec![])
+        );
+    }
+
+    #[test]
+    fn test_distances() {
+        let a = Structure::from_dot_bracket("((((...))))").unwrap();
+        let b = Structure::from_dot_bracket(".(((...))).").unwrap();
+        let c = Structure::from_dot_bracket("...........").unwrap();
+        assert_eq!(a.mountain(), [1, 2, 3, 4, 4, 4, 4, 3, 2, 1, 0]);
+        assert_eq!(a.base_pair_distance(&b), 1);
+        assert_eq!(b.base_pair_distance(&a), 1);
+        assert_eq!(a.base_pair_distance(&c), 4);
+        assert_eq!(a.base_pair_distance(&a), 0);
+        assert_eq!(a.mountain_distance(&b), 10);
+        assert_eq!(a.mountain_distance(&c), 28);
+        assert_eq!(b.mountain_distance(&b), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_mountain_distance_lengths() {
+        let a = Structure::from_dot_bracket("(...)").unwrap();
+        let b = Structure::from_dot_bracket("(....)").unwrap();
+        a.mountain_distance(&b);
+    }
+
+    #[test]
+    fn test_energy() {
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..20 {
+            let seq = random_seq(&mut rng, b"ACGU", 80);
+            let (structure, e) = mfe(&seq);
+            assert!(is_valid(&seq, &structure, MIN_HAIRPIN));
+            assert!(e <= 0.0);
+            assert_relative_eq!(energy(&seq, &structure).unwrap(), e, epsilon = 1e-9);
+            // the Nussinov structure cannot have a lower energy
+            let structure = nussinov(&seq, MIN_HAIRPIN);
+            assert!(energy(&seq, &structure).unwrap() >= e - 1e-9);
+        }
+
+        let seq = b"GGGGAAACCCC";
+        let crossing = Structure::new(11, vec![(0, 6), (2, 10)]);
+        assert_eq!(energy(seq, &crossing), None);
+        let short = Structure::new(11, vec![(3, 6)]);
+        assert_eq!(energy(seq, &short), None);
+        let mismatch = Structure::new(11, vec![(0, 4)]);
+        assert_eq!(energy(seq, &mismatch), None);
+        let open = Structure::new(11, vec![]);
+        assert_eq!(energy(seq, &open), Some(0.0));
+    }
+}
@@ -0,0 +1,262 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Detection of tandem repeats, e.g. short tandem repeats (STRs) for genotyping.
+//!
+//! For each period p, the sequence is compared with itself shifted by p bases. Runs of matches,
+//! allowing for mismatches, are found as maximal scoring segments, with a score of 1 per match
+//! and a configurable penalty per mismatch. A segment is dropped as soon as its score falls too
+//! far below the maximum, such that repeats separated by unrelated sequence are not joined. A
+//! repeat is reported with its period, copy number and purity (the fraction of bases matching
+//! the base one period before). Repeats with a non-primitive unit (e.g. `ACAC`) or that are
+//! covered by a repeat with a period dividing their period are omitted.
+//!
+//! Complexity: O(n * p) for a sequence of length n and a maximal period p.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::seq_analysis::tandem_repeats::Finder;
+//!
+//! let seq = b"GATTCGATGCCAGCAGCAGCAGCAGCAGCAGTTGACCTAGTCA";
+//! let finder = Finder::new(1, 6, 12);
+//! let repeats = finder.find_all(seq);
+//! assert_eq!(repeats.len(), 1);
+//! let repeat = &repeats[0];
+//! assert_eq!((repeat.start, repeat.end, repeat.period), (10, 31, 3));
+//! assert_eq!(repeat.unit, b"CAG");
+//! assert_eq!(repeat.copies, 7.0);
+//! assert_eq!(repeat.purity, 1.0);
+//! ```
+
+/// A tandem repeat.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TandemRepeat {
+    /// Start position of the repeat.
+    pub start: usize,
+    /// End position of the repeat (exclusive).
+    pub end: usize,
+    /// Length of the repeat unit.
+    pub period: usize,
+    /// The first copy of the repeat unit.
+    pub unit: Vec<u8>,
+    /// Number of copies of the unit, i.e. the length of the repeat divided by its period.
+    pub copies: f64,
+    /// Fraction of the bases after the first copy that equal the base one period before.
+    pub purity: f64,
+}
+
+/// A finder for tandem repeats.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finder {
+    min_period: usize,
+    max_period: usize,
+    min_length: usize,
+    min_copies: f64,
+    min_purity: f64,
+    mismatch_penalty: u32,
+}
+
+impl Finder {
+    /// Create a new finder for repeats with a period between `min_period` and `max_period`
+    /// (inclusive) and a length of at least `min_length`. By default, repeats need at least two
+    /// copies and a purity of 0.8, and mismatches are penalized with 3.
+    ///
+    /// # Panics
+    ///
+    /// If the minimum period is zero or larger than the maximum period.
+    pub fn new(min_period: usize, max_period: usize, min_length: usize) -> Self {
+        assert!(
+            min_period > 0 && min_period <= max_period,
+            "invalid range of periods"
+        );
+        Finder {
+            min_period,
+            max_period,
+            min_length,
+            min_copies: 2.0,
+            min_purity: 0.8,
+            mismatch_penalty: 3,
+        }
+    }
+
+    /// Set the minimal number of copies of the unit.
+    pub fn min_copies(mut self, min_copies: f64) -> Self {
+        self.min_copies = min_copies;
+        self
+    }
+
+    /// Set the minimal purity.
+    pub fn min_purity(mut self, min_purity: f64) -> Self {
+        self.min_purity = min_purity;
+        self
+    }
+
+    /// Set the penalty of a mismatch, relative to a score of 1 per match. Higher penalties split
+    /// repeats at mismatches.
+    pub fn mismatch_penalty(mut self, mismatch_penalty: u32) -> Self {
+        self.mismatch_penalty = mismatch_penalty;
+        self
+    }
+
+    /// Find all tandem repeats in the given sequence (ignoring case), ordered by position and
+    /// period.
+    pub fn find_all(&self, seq: &[u8]) -> Vec<TandemRepeat> {
+        let mut repeats: Vec<TandemRepeat> = Vec::new();
+        for period in self.min_period..=self.max_period.min(seq.len() / 2) {
+            for (start, end, matches) in self.segments(seq, period) {
+                let len = end - start + period;
+                let repeat = TandemRepeat {
+                    start,
+                    end: start + len,
+                    period,
+                    unit: seq[start..start + period].to_vec(),
+                    copies: len as f64 / period as f64,
+                    purity: matches as f64 / (end - start) as f64,
+                };
+                if len >= self.min_length
+                    && repeat.copies >= self.min_copies
+                    && repeat.purity >= self.min_purity
+                    && is_primitive(&repeat.unit)
+                    && !is_covered(&repeat, &repeats)
+                {
+                    repeats.push(repeat);
+                }
+            }
+        }
+        repeats.sort_by_key(|repeat| (repeat.start, repeat.period));
+        repeats
+    }
+
+    /// Maximal scoring segments of positions matching the position one period later, given as
+    /// start, end (exclusive) and number of matches.
+    fn segments(&self, seq: &[u8], period: usize) -> Vec<(usize, usize, usize)> {
+        let is_match = |i: usize| seq[i].eq_ignore_ascii_case(&seq[i + period]);
+        let penalty = self.mismatch_penalty as i64;
+        // the score may drop by this amount below the maximum before the segment ends
+        let max_drop = 3 * penalty;
+        let n = seq.len() - period;
+        let mut segments = Vec::new();
+
+        let mut start = 0;
+        while start < n {
+            if !is_match(start) {
+                start += 1;
+                continue;
+            }
+            let (mut score, mut best, mut best_end) = (0, 0, start);
+            let (mut matches, mut best_matches) = (0, 0);
+            let mut i = start;
+            while i < n {
+                if is_match(i) {
+                    score += 1;
+                    matches += 1;
+                } else {
+                    score -= penalty;
+                }
+                if score > best {
+                    best = score;
+                    best_end = i + 1;
+                    best_matches = matches;
+                } else if score <= 0 || best - score > max_drop {
+                    break;
+                }
+                i += 1;
+            }
+            segments.push((start, best_end, best_matches));
+            start = best_end;
+        }
+        segments
+    }
+}
+
+/// Whether the unit is not a repetition of a shorter unit, i.e. whether it only occurs twice
+/// in the unit concatenated with itself.
+fn is_primitive(unit: &[u8]) -> bool {
+    let p = unit.len();
+    let unit = unit.to_ascii_uppercase();
+    let doubled = [&unit[..], &unit[..]].concat();
+    !doubled[1..2 * p - 1].windows(p).any(|w| w == &unit[..])
+}
+
+/// Whether the repeat is mostly covered by a repeat with a period dividing its period.
+fn is_covered(repeat: &TandemRepeat, repeats: &[TandemRepeat]) -> bool {
+    let len = repeat.end - repeat.start;
+    repeats.iter().any(|other| {
+        let overlap = repeat
+            .end
+            .min(other.end)
+            .saturating_sub(repeat.start.max(other.start));
+        matches!(repeat.period.checked_rem(other.period), Some(0)) && overlap * 10 >= len * 9
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::random_seq;
+    use rand::prelude::*;
+
+    #[test]
+    fn test_find_all() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut seq = random_seq(&mut rng, b"ACGT", 200);
+        seq.extend(b"CA".repeat(15));
+        seq.extend(random_seq(&mut rng, b"ACGT", 200));
+        // an imperfect repeat
+        let mut imperfect = b"AATG".repeat(10);
+        imperfect[21] = b'C';
+        seq.extend(&imperfect);
+        seq.extend(random_seq(&mut rng, b"ACGT", 200));
+        seq.extend(&[b'T'; 20]);
+        seq.extend(random_seq(&mut rng, b"ACGT", 200));
+
+        let repeats = Finder::new(1, 6, 15).find_all(&seq);
+        assert_eq!(repeats.len(), 3);
+        let expected = [
+            (200, 230, 2, 15.0),
+            (430, 470, 4, 10.0),
+            (670, 690, 1, 20.0),
+        ];
+        for (repeat, &(start, end, period, copies)) in repeats.iter().zip(&expected) {
+            assert_eq!(repeat.period, period);
+            assert!(repeat.start <= start && repeat.start + 3 >= start);
+            assert!(repeat.end >= end && repeat.end <= end + 3);
+            assert!(repeat.copies >= copies);
+        }
+        assert!(repeats[0].purity == 1.0 && repeats[2].purity == 1.0);
+        assert!(repeats[1].purity < 1.0 && repeats[1].purity > 0.9);
+
+        // a high mismatch penalty splits the imperfect repeat
+        let repeats = Finder::new(4, 4, 15)
+            .mismatch_penalty(20)
+            .find_all(&imperfect);
+        let bounds: Vec<(usize, usize)> = repeats.iter().map(|r| (r.start, r.end)).collect();
+        assert_eq!(bounds, [(0, 21), (22, 40)]);
+    }
+
+    #[test]
+    fn test_non_primitive() {
+        let repeats = Finder::new(2, 6, 10).find_all(b"ATATATATATATATAT");
+        assert_eq!(repeats.len(), 1);
+        assert_eq!(repeats[0].period, 2);
+        // periods that are multiples are not reported, even if the smaller period is not searched
+        assert!(Finder::new(2, 4, 10).find_all(&[b'G'; 20]).is_empty());
+        assert!(Finder::new(1, 4, 10).find_all(b"ACG").is_empty());
+        assert_eq!(
+            Finder::new(1, 1, 5).min_copies(10.0).find_all(b"AAAAAAAAA"),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_is_primitive() {
+        assert!(is_primitive(b"A"));
+        assert!(is_primitive(b"AAT"));
+        assert!(!is_primitive(b"ATAT"));
+        assert!(!is_primitive(b"AAA"));
+        assert!(is_primitive(b"ATATA"));
+    }
+}
@@ -48,6 +48,96 @@ pub trait Posterior {
     ) -> LogProb;
 }
 
+/// Posterior model that marginalizes over a discrete set of base events per event, e.g. over the
+/// points of a grid of allele frequencies that are consistent with a genotype. The base events
+/// are enumerated by the given function.
+///
+/// # Example
+///
+/// ```
+/// use bio::stats::bayesian::model::{Enumeration, Likelihood, Model, Prior};
+/// use bio::stats::{LogProb, Prob};
+///
+/// /// Likelihood of observing the given number of heads and tails for the given bias (in
+/// /// percent) of a coin.
+/// struct Coin;
+///
+/// impl Likelihood for Coin {
+///     type Event = u32;
+///     type Data = (u32, u32);
+///
+///     fn compute(&self, bias: &u32, &(heads, tails): &(u32, u32), _: &mut ()) -> LogProb {
+///         let p = *bias as f64 / 100.0;
+///         LogProb(heads as f64 * p.ln() + tails as f64 * (1.0 - p).ln())
+///     }
+/// }
+///
+/// /// Uniform prior over the bias grid.
+/// struct Uniform;
+///
+/// impl Prior for Uniform {
+///     type Event = u32;
+///
+///     fn compute(&self, _: &u32) -> LogProb {
+///         LogProb::from(Prob(1.0 / 99.0))
+///     }
+/// }
+///
+/// // whether the coin is fair (bias between 40% and 60%) or not
+/// let posterior = Enumeration::new(|&fair: &bool| {
+///     (1..100).filter(|bias| (40..=60).contains(bias) == fair).collect()
+/// });
+/// let model = Model::new(Coin, Uniform, posterior);
+///
+/// let instance = model.compute(vec![true, false], &(16, 4));
+/// assert!(instance.posterior(&false).unwrap() > instance.posterior(&true).unwrap());
+/// assert_eq!(instance.maximum_posterior_event(), Some(&false));
+/// assert_eq!(instance.maximum_posterior(), Some(&80));
+/// ```
+pub struct Enumeration<F, Event, BaseEvent, Data>
+where
+    F: Fn(&Event) -> Vec<BaseEvent>,
+{
+    base_events: F,
+    phantom: PhantomData<fn(&Event, &Data) -> BaseEvent>,
+}
+
+impl<F, Event, BaseEvent, Data> Enumeration<F, Event, BaseEvent, Data>
+where
+    F: Fn(&Event) -> Vec<BaseEvent>,
+{
+    /// Create a new instance, enumerating the base events of each event with the given
+    /// function.
+    pub fn new(base_events: F) -> Self {
+        Enumeration {
+            base_events,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<F, Event, BaseEvent, Data> Posterior for Enumeration<F, Event, BaseEvent, Data>
+where
+    F: Fn(&Event) -> Vec<BaseEvent>,
+{
+    type Event = Event;
+    type BaseEvent = BaseEvent;
+    type Data = Data;
+
+    fn compute<J: FnMut(&BaseEvent, &Data) -> LogProb>(
+        &self,
+        event: &Event,
+        data: &Data,
+        joint_prob: &mut J,
+    ) -> LogProb {
+        let probs = (self.base_events)(event)
+            .iter()
+            .map(|base_event| joint_prob(base_event, data))
+            .collect_vec();
+        LogProb::ln_sum_exp(&probs)
+    }
+}
+
 /// Bayesian model, consisting of a prior, a posterior and a likelihood model.
 /// Thereby, `Payload` is a custom payload of the model instance.
 /// This can be used to define custom caching mechanisms. See
@@ -166,6 +256,21 @@ where
             .max_by_key(|(_, prob)| NotNan::new(***prob).unwrap())
             .map(|(event, _)| event)
     }
+
+    /// Posterior probabilities of all events of the universe, in arbitrary order.
+    pub fn posteriors(&self) -> impl Iterator<Item = (&PosteriorEvent, LogProb)> {
+        self.posterior_probs
+            .iter()
+            .map(move |(event, p)| (event, p - self.marginal))
+    }
+
+    /// Maximum a posteriori estimate among the events of the universe.
+    pub fn maximum_posterior_event(&self) -> Option<&PosteriorEvent> {
+        self.posterior_probs
+            .iter()
+            .max_by_key(|(_, prob)| NotNan::new(***prob).unwrap())
+            .map(|(event, _)| event)
+    }
 }
 
 impl<PosteriorEvent> ModelInstance<NotNan<f64>, PosteriorEvent>
@@ -180,4 +285,77 @@ where
     }
 }
 
-mod tests {}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::Prob;
+
+    /// Likelihood of the number of alternative allele observations, given the allele frequency.
+    struct Observations;
+
+    impl Likelihood for Observations {
+        type Event = NotNan<f64>;
+        type Data = (u32, u32);
+
+        fn compute(&self, af: &NotNan<f64>, &(alt, reference): &(u32, u32), _: &mut ()) -> LogProb {
+            let p = af.into_inner() * 0.99 + 0.005;
+            LogProb(alt as f64 * p.ln() + reference as f64 * (1.0 - p).ln())
+        }
+    }
+
+    struct Uniform;
+
+    impl Prior for Uniform {
+        type Event = NotNan<f64>;
+
+        fn compute(&self, _: &NotNan<f64>) -> LogProb {
+            LogProb::from(Prob(1.0 / 11.0))
+        }
+    }
+
+    fn grid() -> Vec<NotNan<f64>> {
+        (0..=10)
+            .map(|i| NotNan::new(i as f64 / 10.0).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_enumeration() {
+        // present or absent variant
+        let posterior = Enumeration::new(|&present: &bool| {
+            grid()
+                .into_iter()
+                .filter(|af| (af.into_inner() > 0.0) == present)
+                .collect()
+        });
+        let model = Model::new(Observations, Uniform, posterior);
+
+        let instance = model.compute(vec![true, false], &(5, 15));
+        let probs: Vec<LogProb> = instance.posteriors().map(|(_, p)| p).collect();
+        assert_eq!(probs.len(), 2);
+        assert_relative_eq!(
+            *Prob::from(LogProb::ln_sum_exp(&probs)),
+            1.0,
+            epsilon = 1e-6
+        );
+        assert_eq!(instance.maximum_posterior_event(), Some(&true));
+        assert_eq!(
+            instance.maximum_posterior(),
+            Some(&NotNan::new(0.2).unwrap())
+        );
+
+        // the marginal is the sum over the whole grid
+        let joint = grid()
+            .iter()
+            .map(|af| Uniform.compute(af) + Observations.compute(af, &(5, 15), &mut ()))
+            .collect_vec();
+        assert_relative_eq!(
+            *instance.marginal(),
+            *LogProb::ln_sum_exp(&joint),
+            epsilon = 1e-6
+        );
+
+        let instance = model.compute(vec![true, false], &(0, 30));
+        assert_eq!(instance.maximum_posterior_event(), Some(&false));
+    }
+}
@@ -3,9 +3,14 @@
 // This file may not be copied, modified, or distributed
 // except according to those terms.
 
-//! Combinations with and without replacement.
+//! Combinations with and without replacement, multinomial coefficients, and the logarithms of
+//! the gamma function, factorials and binomial coefficients. The latter are needed when
+//! coefficients exceed the range of `f64`, e.g. in exact tests on large counts.
 
 use std::cmp;
+use std::f64;
+
+use statrs::function::{factorial, gamma};
 
 /// Calculate the number of combinations when choosing
 /// k elements from n elements without replacement, multiplied by a scaling factor.
@@ -59,6 +64,90 @@ pub fn combinations_with_repl(n: u64, k: u64) -> f64 {
     combinations(n + k - 1, k)
 }
 
+/// Natural logarithm of the gamma function, using the Lanczos approximation.
+///
+/// # Examples
+/// ```
+/// use approx::assert_relative_eq;
+/// use bio::stats::combinatorics::ln_gamma;
+/// assert_relative_eq!(ln_gamma(5.0), 24f64.ln(), epsilon = 1e-10);
+/// ```
+pub fn ln_gamma(x: f64) -> f64 {
+    gamma::ln_gamma(x)
+}
+
+/// Natural logarithm of n!. This is exact (up to floating point precision) for n <= 170, and
+/// uses the gamma function otherwise.
+///
+/// # Examples
+/// ```
+/// use approx::assert_relative_eq;
+/// use bio::stats::combinatorics::ln_factorial;
+/// assert_relative_eq!(ln_factorial(5), 120f64.ln(), epsilon = f64::EPSILON);
+/// ```
+pub fn ln_factorial(n: u64) -> f64 {
+    factorial::ln_factorial(n)
+}
+
+/// Natural logarithm of the number of combinations when choosing k elements from n elements
+/// without replacement (the binomial coefficient). This is exact (up to floating point
+/// precision) for small n, and uses factorials in log-space otherwise.
+///
+/// # Examples
+/// ```
+/// use approx::assert_relative_eq;
+/// use bio::stats::combinatorics::ln_combinations;
+/// assert_relative_eq!(ln_combinations(5, 3), 10f64.ln(), epsilon = f64::EPSILON);
+/// assert_eq!(ln_combinations(3, 5), f64::NEG_INFINITY);
+/// ```
+pub fn ln_combinations(n: u64, k: u64) -> f64 {
+    if k > n {
+        f64::NEG_INFINITY
+    } else if n <= 60 {
+        combinations(n, k).ln()
+    } else {
+        ln_factorial(n) - ln_factorial(k) - ln_factorial(n - k)
+    }
+}
+
+/// Calculate the multinomial coefficient, i.e. the number of ways to distribute
+/// n = k_1 + ... + k_m elements into m groups of sizes k_1, ..., k_m, as a product of binomial
+/// coefficients.
+///
+/// # Examples
+/// ```
+/// use approx::assert_relative_eq;
+/// use bio::stats::combinatorics::multinomial;
+/// assert_relative_eq!(multinomial(&[2, 1, 1]), 12., epsilon = f64::EPSILON);
+/// ```
+pub fn multinomial(ks: &[u64]) -> f64 {
+    let mut n = 0;
+    let mut comb = 1.0;
+    for &k in ks {
+        n += k;
+        comb *= combinations(n, k);
+    }
+    comb
+}
+
+/// Natural logarithm of the multinomial coefficient (see `multinomial`).
+///
+/// # Examples
+/// ```
+/// use approx::assert_relative_eq;
+/// use bio::stats::combinatorics::ln_multinomial;
+/// assert_relative_eq!(ln_multinomial(&[2, 1, 1]), 12f64.ln(), epsilon = f64::EPSILON);
+/// ```
+pub fn ln_multinomial(ks: &[u64]) -> f64 {
+    let mut n = 0;
+    ks.iter()
+        .map(|&k| {
+            n += k;
+            ln_combinations(n, k)
+        })
+        .sum()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -82,4 +171,41 @@ mod tests {
             epsilon = f64::EPSILON
         );
     }
+
+    #[test]
+    fn test_ln_comb() {
+        for n in 0..100 {
+            for k in 0..=n {
+                assert_relative_eq!(
+                    ln_combinations(n, k),
+                    combinations(n, k).ln(),
+                    max_relative = 1e-12
+                );
+            }
+        }
+        // the coefficient exceeds the range of f64
+        assert!(combinations(2000, 1000).is_infinite());
+        assert_relative_eq!(
+            ln_combinations(2000, 1000),
+            1382.267_993_537_48,
+            max_relative = 1e-12
+        );
+    }
+
+    #[test]
+    fn test_multinomial() {
+        assert_relative_eq!(multinomial(&[]), 1.0);
+        assert_relative_eq!(multinomial(&[3, 0, 2]), 10.0);
+        assert_relative_eq!(
+            multinomial(&[5, 10, 15]),
+            ln_multinomial(&[5, 10, 15]).exp(),
+            max_relative = 1e-10
+        );
+        assert_relative_eq!(
+            ln_multinomial(&[5, 10, 15]),
+            ln_factorial(30) - ln_factorial(5) - ln_factorial(10) - ln_factorial(15),
+            max_relative = 1e-12
+        );
+        assert_relative_eq!(ln_gamma(0.5), f64::consts::PI.sqrt().ln(), epsilon = 1e-10);
+    }
 }
@@ -0,0 +1,384 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Discrete distributions for modelling coverage and allele counts, with probability mass,
+//! cumulative distribution and quantile functions in log-space.
+//!
+//! Tail probabilities are calculated exactly, by summing the probability masses from the
+//! boundary of the tail outwards until they become negligible. If this would take more than
+//! `EXACT_LIMIT` terms, e.g. for a binomial distribution with a very large number of trials,
+//! the normal approximation with continuity correction is used instead.
+//!
+//! # Example
+//!
+//! ```
+//! use approx::assert_relative_eq;
+//! use bio::stats::distributions::{Binomial, DiscreteDistribution, Poisson};
+//! use bio::stats::Prob;
+//!
+//! // probability of observing at most 3 alternative reads at a heterozygous site with 30 reads
+//! let het = Binomial::new(30, Prob(0.5));
+//! assert_relative_eq!(het.ln_cdf(3).exp(), 4.22e-6, max_relative = 1e-2);
+//!
+//! // coverage at a position
+//! let coverage = Poisson::new(30.0);
+//! assert_eq!(coverage.quantile(Prob(0.5)), 30);
+//! assert!(coverage.ln_sf(60) < coverage.ln_sf(50));
+//! ```
+
+use std::f64;
+
+use statrs::function::erf;
+
+use crate::stats::combinatorics::{ln_combinations, ln_factorial, ln_gamma};
+use crate::stats::probs::ln_sum_exp_exact;
+use crate::stats::{LogProb, Prob};
+
+/// Maximum number of probability masses summed up for a tail probability, before falling back
+/// to the normal approximation.
+pub const EXACT_LIMIT: u64 = 10_000;
+
+/// Probability masses below this fraction of the largest mass of a tail are neglected.
+const LN_NEGLIGIBLE: f64 = -40.0;
+
+/// A discrete distribution over the non-negative integers.
+pub trait DiscreteDistribution {
+    /// Probability of observing exactly k, in log-space.
+    fn ln_pmf(&self, k: u64) -> LogProb;
+
+    /// Expected value.
+    fn mean(&self) -> f64;
+
+    /// Variance.
+    fn variance(&self) -> f64;
+
+    /// Largest value with non-zero probability, if the support is bounded.
+    fn max(&self) -> Option<u64>;
+
+    /// Probability of observing at most k, in log-space.
+    fn ln_cdf(&self, k: u64) -> LogProb {
+        if matches!(self.max(), Some(max) if k >= max) {
+            LogProb::ln_one()
+        } else if (k as f64) < self.mean() {
+            // sum the lower tail downwards
+            ln_tail_sum(self, (0..=k).rev())
+                .unwrap_or_else(|| LogProb(ln_normal_cdf(z_score(self, k))))
+        } else {
+            complement(self.ln_sf(k))
+        }
+    }
+
+    /// Probability of observing more than k (the survival function), in log-space.
+    fn ln_sf(&self, k: u64) -> LogProb {
+        if matches!(self.max(), Some(max) if k >= max) {
+            LogProb::ln_zero()
+        } else if (k as f64) < self.mean() {
+            complement(self.ln_cdf(k))
+        } else {
+            // sum the upper tail upwards
+            let max = self.max().unwrap_or(u64::MAX);
+            ln_tail_sum(self, k + 1..=max)
+                .unwrap_or_else(|| LogProb(ln_normal_cdf(-z_score(self, k))))
+        }
+    }
+
+    /// The smallest k with a cumulative probability of at least p.
+    fn quantile(&self, p: Prob) -> u64 {
+        let max = self.max().unwrap_or(u64::MAX);
+        if *p <= 0.0 {
+            return 0;
+        } else if *p >= 1.0 {
+            return max;
+        }
+        // start from the normal approximation
+        let z = -f64::consts::SQRT_2 * erf::erfc_inv(2.0 * *p);
+        let guess = (self.mean() + z * self.variance().sqrt()).round();
+        let mut k = if guess <= 0.0 {
+            0
+        } else {
+            (guess as u64).min(max)
+        };
+        if self.ln_cdf(k).exp() >= *p {
+            while k > 0 && self.ln_cdf(k - 1).exp() >= *p {
+                k -= 1;
+            }
+        } else {
+            while k < max && self.ln_cdf(k).exp() < *p {
+                k += 1;
+            }
+        }
+        k
+    }
+}
+
+/// Sum the probability masses of a tail, given from its boundary outwards, until they become
+/// negligible. Returns `None` if this exceeds `EXACT_LIMIT` terms.
+fn ln_tail_sum<D, I>(dist: &D, tail: I) -> Option<LogProb>
+where
+    D: DiscreteDistribution + ?Sized,
+    I: Iterator<Item = u64>,
+{
+    let mut probs = Vec::new();
+    let mut best = f64::NEG_INFINITY;
+    for k in tail {
+        if probs.len() as u64 >= EXACT_LIMIT {
+            return None;
+        }
+        let p = *dist.ln_pmf(k);
+        if p < best + LN_NEGLIGIBLE || (p == f64::NEG_INFINITY && best > p) {
+            break;
+        }
+        best = best.max(p);
+        probs.push(p);
+    }
+    Some(cap(ln_sum_exp_exact(&probs)))
+}
+
+/// Standard score of k, with continuity correction.
+fn z_score<D: DiscreteDistribution + ?Sized>(dist: &D, k: u64) -> f64 {
+    (k as f64 + 0.5 - dist.mean()) / dist.variance().sqrt()
+}
+
+/// Binomial distribution of the number of successes in n trials.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Binomial {
+    n: u64,
+    p: Prob,
+}
+
+impl Binomial {
+    /// Create a binomial distribution with n trials and success probability p.
+    pub fn new(n: u64, p: Prob) -> Self {
+        Binomial { n, p }
+    }
+}
+
+impl DiscreteDistribution for Binomial {
+    fn ln_pmf(&self, k: u64) -> LogProb {
+        if k > self.n {
+            return LogProb::ln_zero();
+        }
+        LogProb(
+            ln_combinations(self.n, k)
+                + xlny(k as f64, *self.p)
+                + xlny((self.n - k) as f64, 1.0 - *self.p),
+        )
+    }
+
+    fn mean(&self) -> f64 {
+        self.n as f64 * *self.p
+    }
+
+    fn variance(&self) -> f64 {
+        self.n as f64 * *self.p * (1.0 - *self.p)
+    }
+
+    fn max(&self) -> Option<u64> {
+        Some(self.n)
+    }
+}
+
+/// Poisson distribution with rate λ.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Poisson {
+    lambda: f64,
+}
+
+impl Poisson {
+    /// Create a Poisson distribution with the given rate.
+    ///
+    /// # Panics
+    ///
+    /// If the rate is negative.
+    pub fn new(lambda: f64) -> Self {
+        assert!(lambda >= 0.0, "rate must not be negative");
+        Poisson { lambda }
+    }
+}
+
+impl DiscreteDistribution for Poisson {
+    fn ln_pmf(&self, k: u64) -> LogProb {
+        LogProb(xlny(k as f64, self.lambda) - self.lambda - ln_factorial(k))
+    }
+
+    fn mean(&self) -> f64 {
+        self.lambda
+    }
+
+    fn variance(&self) -> f64 {
+        self.lambda
+    }
+
+    fn max(&self) -> Option<u64> {
+        if self.lambda == 0.0 {
+            Some(0)
+        } else {
+            None
+        }
+    }
+}
+
+/// Beta-binomial distribution of the number of successes in n trials, with a success
+/// probability following a beta distribution with shape parameters α and β. This models
+/// allele counts that are overdispersed compared to the binomial distribution.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BetaBinomial {
+    n: u64,
+    alpha: f64,
+    beta: f64,
+}
+
+impl BetaBinomial {
+    /// Create a beta-binomial distribution with n trials and shape parameters α and β.
+    ///
+    /// # Panics
+    ///
+    /// If α or β are not positive.
+    pub fn new(n: u64, alpha: f64, beta: f64) -> Self {
+        assert!(
+            alpha > 0.0 && beta > 0.0,
+            "shape parameters must be positive"
+        );
+        BetaBinomial { n, alpha, beta }
+    }
+}
+
+impl DiscreteDistribution for BetaBinomial {
+    fn ln_pmf(&self, k: u64) -> LogProb {
+        if k > self.n {
+            return LogProb::ln_zero();
+        }
+        LogProb(
+            ln_combinations(self.n, k)
+                + ln_beta(k as f64 + self.alpha, (self.n - k) as f64 + self.beta)
+                - ln_beta(self.alpha, self.beta),
+        )
+    }
+
+    fn mean(&self) -> f64 {
+        self.n as f64 * self.alpha / (self.alpha + self.beta)
+    }
+
+    fn variance(&self) -> f64 {
+        let (n, s) = (self.n as f64, self.alpha + self.beta);
+        n * self.alpha * self.beta * (s + n) / (s * s * (s + 1.0))
+    }
+
+    fn max(&self) -> Option<u64> {
+        Some(self.n)
+    }
+}
+
+/// x * ln(y), defined as 0 for x = 0.
+fn xlny(x: f64, y: f64) -> f64 {
+    if x == 0.0 {
+        0.0
+    } else {
+        x * y.ln()
+    }
+}
+
+fn ln_beta(a: f64, b: f64) -> f64 {
+    ln_gamma(a) + ln_gamma(b) - ln_gamma(a + b)
+}
+
+/// Cumulative distribution function of the standard normal distribution, in log-space.
+fn ln_normal_cdf(z: f64) -> f64 {
+    (0.5 * erf::erfc(-z / f64::consts::SQRT_2)).ln()
+}
+
+/// 1 - p in log-space, without approximating the exponential function.
+fn complement(p: LogProb) -> LogProb {
+    LogProb((-p.exp()).ln_1p())
+}
+
+/// Cap numerical overshoots of sums of probabilities at 1.
+fn cap(p: LogProb) -> LogProb {
+    if p > LogProb::ln_one() {
+        LogProb::ln_one()
+    } else {
+        p
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check<D: DiscreteDistribution>(dist: &D, max: u64) {
+        let pmf: Vec<f64> = (0..=max).map(|k| dist.ln_pmf(k).exp()).collect();
+        assert_relative_eq!(pmf.iter().sum::<f64>(), 1.0, max_relative = 1e-10);
+        let mut cdf = 0.0;
+        // only check where the tail beyond max is negligible
+        for k in 0..max * 3 / 4 {
+            cdf += pmf[k as usize];
+            assert_relative_eq!(dist.ln_cdf(k).exp(), cdf, max_relative = 1e-8);
+            assert_relative_eq!(
+                dist.ln_sf(k).exp(),
+                pmf[k as usize + 1..].iter().sum::<f64>(),
+                max_relative = 1e-8,
+                epsilon = 1e-300
+            );
+        }
+        for &p in &[0.001, 0.1, 0.5, 0.9, 0.999] {
+            let k = dist.quantile(Prob(p));
+            assert!(dist.ln_cdf(k).exp() >= p);
+            assert!(k == 0 || dist.ln_cdf(k - 1).exp() < p);
+        }
+    }
+
+    #[test]
+    fn test_binomial() {
+        check(&Binomial::new(50, Prob(0.3)), 50);
+        check(&Binomial::new(10, Prob(0.0)), 10);
+        check(&Binomial::new(10, Prob(1.0)), 10);
+        assert_eq!(Binomial::new(10, Prob(0.5)).ln_cdf(10), LogProb::ln_one());
+        // far tails do not underflow
+        assert_relative_eq!(
+            *Binomial::new(1000, Prob(0.5)).ln_cdf(10),
+            -639.209_051_910_708_5,
+            max_relative = 1e-8
+        );
+    }
+
+    #[test]
+    fn test_poisson() {
+        check(&Poisson::new(4.5), 200);
+        check(&Poisson::new(0.0), 10);
+        assert_relative_eq!(
+            Poisson::new(3.0).ln_pmf(2).exp(),
+            0.224_041_807_655_388_3,
+            max_relative = 1e-12
+        );
+    }
+
+    #[test]
+    fn test_beta_binomial() {
+        let dist = BetaBinomial::new(40, 2.0, 3.0);
+        check(&dist, 40);
+        assert_relative_eq!(dist.mean(), 16.0);
+        // with large shape parameters, the beta-binomial approaches the binomial
+        let dist = BetaBinomial::new(40, 2e6, 3e6);
+        let binomial = Binomial::new(40, Prob(0.4));
+        for k in 10..=30 {
+            assert_relative_eq!(
+                dist.ln_pmf(k).exp(),
+                binomial.ln_pmf(k).exp(),
+                max_relative = 1e-4
+            );
+        }
+    }
+
+    #[test]
+    fn test_normal_approximation() {
+        let dist = Binomial::new(100_000_000, Prob(0.5));
+        // the tail is too wide for summation
+        assert!(ln_tail_sum(&dist, 0..100_000_000).is_none());
+        assert_relative_eq!(dist.ln_cdf(50_000_000).exp(), 0.5, epsilon = 1e-3);
+        assert_relative_eq!(dist.ln_sf(50_010_000).exp(), 0.02275, epsilon = 1e-3);
+        let k = dist.quantile(Prob(0.5));
+        assert!((49_999_000..=50_001_000).contains(&k));
+    }
+}
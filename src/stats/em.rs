@@ -0,0 +1,263 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A generic expectation–maximization (EM) driver (Dempster, Laird and Rubin, Journal of the
+//! Royal Statistical Society, Series B, 1977), e.g. for estimating haplotype frequencies or
+//! contamination. A model only has to provide the E-step and the M-step, while iterating until
+//! convergence and restarting from multiple random initial parameters is handled here.
+//!
+//! A mixture of binomial distributions, e.g. for allele counts of a mixture of clones, is
+//! provided as an example model.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::stats::em::{run_with_restarts, BinomialMixture};
+//! use rand::prelude::*;
+//!
+//! // alternative allele counts and depths at sites of two clones
+//! let observations = [(2, 40), (19, 40), (3, 38), (22, 41), (18, 39), (1, 40), (21, 42)];
+//! let model = BinomialMixture::new(&observations, 2);
+//!
+//! let mut rng = StdRng::seed_from_u64(42);
+//! let estimate = run_with_restarts(&model, 5, |_| model.random_params(&mut rng), 100, 1e-8);
+//! assert!(estimate.converged);
+//! let mut probs = estimate.params.probs.clone();
+//! probs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+//! assert!((probs[0] - 0.05).abs() < 0.02 && (probs[1] - 0.5).abs() < 0.05);
+//! ```
+
+use rand::Rng;
+
+use crate::stats::distributions::{Binomial, DiscreteDistribution};
+use crate::stats::probs::ln_sum_exp_exact;
+use crate::stats::{LogProb, Prob};
+
+/// A model that can be fitted with expectation–maximization.
+pub trait Model {
+    /// The parameters of the model.
+    type Params;
+    /// Expected sufficient statistics of the data given parameters, e.g. the responsibilities of
+    /// mixture components.
+    type Stats;
+
+    /// E-step: calculate the expected sufficient statistics and the log-likelihood of the data
+    /// given the parameters.
+    fn expectation(&self, params: &Self::Params) -> (Self::Stats, LogProb);
+
+    /// M-step: calculate the parameters maximizing the expected log-likelihood.
+    fn maximization(&self, stats: &Self::Stats) -> Self::Params;
+}
+
+/// Result of fitting a model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Estimate<P> {
+    /// The estimated parameters.
+    pub params: P,
+    /// The log-likelihood of the data given the estimated parameters.
+    pub log_likelihood: LogProb,
+    /// The number of performed iterations.
+    pub iterations: usize,
+    /// Whether the log-likelihood converged before reaching the maximum number of iterations.
+    pub converged: bool,
+}
+
+/// Fit the model, starting from the given initial parameters, until the log-likelihood changes
+/// by less than `tolerance` or `max_iterations` is reached.
+pub fn run<M: Model>(
+    model: &M,
+    init: M::Params,
+    max_iterations: usize,
+    tolerance: f64,
+) -> Estimate<M::Params> {
+    let mut params = init;
+    let (mut stats, mut log_likelihood) = model.expectation(&params);
+    let mut iterations = 0;
+    let mut converged = false;
+    while iterations < max_iterations {
+        params = model.maximization(&stats);
+        let (new_stats, new_log_likelihood) = model.expectation(&params);
+        let change = *new_log_likelihood - *log_likelihood;
+        stats = new_stats;
+        log_likelihood = new_log_likelihood;
+        iterations += 1;
+        if change.abs() < tolerance {
+            converged = true;
+            break;
+        }
+    }
+    Estimate {
+        params,
+        log_likelihood,
+        iterations,
+        converged,
+    }
+}
+
+/// Fit the model from the given number of initial parameters (at least one), e.g. chosen at
+/// random, and return the estimate with the highest log-likelihood. This reduces the chance of
+/// ending in a poor local optimum.
+pub fn run_with_restarts<M, F>(
+    model: &M,
+    restarts: usize,
+    mut init: F,
+    max_iterations: usize,
+    tolerance: f64,
+) -> Estimate<M::Params>
+where
+    M: Model,
+    F: FnMut(usize) -> M::Params,
+{
+    let mut best = run(model, init(0), max_iterations, tolerance);
+    for restart in 1..restarts {
+        let estimate = run(model, init(restart), max_iterations, tolerance);
+        if estimate.log_likelihood > best.log_likelihood {
+            best = estimate;
+        }
+    }
+    best
+}
+
+/// Parameters of a mixture of binomial distributions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BinomialMixtureParams {
+    /// Weight of each component.
+    pub weights: Vec<f64>,
+    /// Success probability of each component.
+    pub probs: Vec<f64>,
+}
+
+/// A mixture of binomial distributions over observed numbers of successes and trials, e.g.
+/// alternative allele counts and read depths.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BinomialMixture<'a> {
+    observations: &'a [(u64, u64)],
+    components: usize,
+}
+
+impl<'a> BinomialMixture<'a> {
+    /// Create a mixture with the given number of components over the given pairs of successes
+    /// and trials.
+    pub fn new(observations: &'a [(u64, u64)], components: usize) -> Self {
+        BinomialMixture {
+            observations,
+            components,
+        }
+    }
+
+    /// Random initial parameters, with uniform weights.
+    pub fn random_params<R: Rng>(&self, rng: &mut R) -> BinomialMixtureParams {
+        BinomialMixtureParams {
+            weights: vec![1.0 / self.components as f64; self.components],
+            probs: (0..self.components)
+                .map(|_| rng.gen_range(0.01..0.99))
+                .collect(),
+        }
+    }
+}
+
+impl<'a> Model for BinomialMixture<'a> {
+    type Params = BinomialMixtureParams;
+    /// Responsibilities of the components for each observation.
+    type Stats = Vec<Vec<f64>>;
+
+    fn expectation(&self, params: &BinomialMixtureParams) -> (Vec<Vec<f64>>, LogProb) {
+        let mut log_likelihood = 0.0;
+        let responsibilities = self
+            .observations
+            .iter()
+            .map(|&(k, n)| {
+                let joint: Vec<f64> = params
+                    .weights
+                    .iter()
+                    .zip(&params.probs)
+                    .map(|(&w, &p)| w.ln() + *Binomial::new(n, Prob(p)).ln_pmf(k))
+                    .collect();
+                let marginal = *ln_sum_exp_exact(&joint);
+                log_likelihood += marginal;
+                joint.iter().map(|p| (p - marginal).exp()).collect()
+            })
+            .collect();
+        (responsibilities, LogProb(log_likelihood))
+    }
+
+    fn maximization(&self, responsibilities: &Vec<Vec<f64>>) -> BinomialMixtureParams {
+        let mut weights = vec![0.0; self.components];
+        let mut successes = vec![0.0; self.components];
+        let mut trials = vec![0.0; self.components];
+        for (r, &(k, n)) in responsibilities.iter().zip(self.observations) {
+            for c in 0..self.components {
+                weights[c] += r[c];
+                successes[c] += r[c] * k as f64;
+                trials[c] += r[c] * n as f64;
+            }
+        }
+        let total: f64 = weights.iter().sum();
+        BinomialMixtureParams {
+            weights: weights.iter().map(|w| w / total).collect(),
+            probs: successes
+                .iter()
+                .zip(&trials)
+                .map(|(s, t)| if *t > 0.0 { s / t } else { 0.5 })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+
+    #[test]
+    fn test_binomial_mixture() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let observations: Vec<(u64, u64)> = (0..500)
+            .map(|_| {
+                let p = if rng.gen_bool(0.3) { 0.1 } else { 0.6 };
+                let k = (0..50).filter(|_| rng.gen_bool(p)).count() as u64;
+                (k, 50)
+            })
+            .collect();
+        let model = BinomialMixture::new(&observations, 2);
+
+        let estimate = run_with_restarts(&model, 10, |_| model.random_params(&mut rng), 500, 1e-10);
+        assert!(estimate.converged);
+        let (low, high) = if estimate.params.probs[0] < estimate.params.probs[1] {
+            (0, 1)
+        } else {
+            (1, 0)
+        };
+        assert_relative_eq!(estimate.params.probs[low], 0.1, epsilon = 0.01);
+        assert_relative_eq!(estimate.params.probs[high], 0.6, epsilon = 0.01);
+        assert_relative_eq!(estimate.params.weights[low], 0.3, epsilon = 0.05);
+
+        // the log-likelihood never decreases
+        let mut params = model.random_params(&mut rng);
+        let mut last = LogProb::ln_zero();
+        for _ in 0..20 {
+            let (stats, log_likelihood) = model.expectation(&params);
+            assert!(*log_likelihood >= *last - 1e-9);
+            last = log_likelihood;
+            params = model.maximization(&stats);
+        }
+    }
+
+    #[test]
+    fn test_max_iterations() {
+        let observations = [(1, 10), (9, 10)];
+        let model = BinomialMixture::new(&observations, 2);
+        let init = BinomialMixtureParams {
+            weights: vec![0.5, 0.5],
+            probs: vec![0.4, 0.6],
+        };
+        let estimate = run(&model, init.clone(), 1, 1e-12);
+        assert_eq!(estimate.iterations, 1);
+        assert!(!estimate.converged);
+        let estimate = run(&model, init, 0, 1e-12);
+        assert_eq!(estimate.iterations, 0);
+        assert_eq!(estimate.params.probs, vec![0.4, 0.6]);
+    }
+}
@@ -0,0 +1,526 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Hidden Markov Models with user-defined emission distributions.
+//!
+//! Any type implementing the `Emission` trait can be used as the emission distribution of the
+//! states of a `Model`, which can then be decoded with `viterbi()`, `forward()` and
+//! `posterior()`, and trained with the Baum-Welch algorithm. Emissions that implement
+//! `Emission::fit` are re-estimated during training, others are kept fixed.
+//! Categorical, Gaussian and Gaussian mixture emissions are provided.
+//!
+//! # Example
+//!
+//! Segmenting the read depth log-ratios of consecutive genomic bins into copy number states
+//! (loss, neutral, gain):
+//!
+//! ```rust
+//! use bio::stats::hmm::emission::{Gaussian, Model};
+//! use bio::stats::hmm::viterbi;
+//! use ndarray::array;
+//!
+//! let transition = array![[0.9, 0.05, 0.05], [0.05, 0.9, 0.05], [0.05, 0.05, 0.9]];
+//! let emissions = vec![
+//!     Gaussian::new(-0.5, 0.3),
+//!     Gaussian::new(0.0, 0.3),
+//!     Gaussian::new(0.5, 0.3),
+//! ];
+//! let initial = array![0.1, 0.8, 0.1];
+//! let hmm = Model::with_float(&transition, emissions, &initial).unwrap();
+//!
+//! let log_ratios = vec![0.1, -0.1, 0.0, -0.9, -1.1, -0.8, -1.0, 0.1, 0.0, 0.7, 0.5, 0.6];
+//! let (trained, _) = hmm.baum_welch(&[&log_ratios], 20, 1e-6).unwrap();
+//! // the loss is deeper than expected
+//! assert!(trained.emissions()[0].mean < -0.8);
+//!
+//! let (path, _) = viterbi(&trained, &log_ratios);
+//! let path: Vec<usize> = path.iter().map(|s| **s).collect();
+//! assert_eq!(path, [1, 1, 1, 0, 0, 0, 0, 1, 1, 2, 2, 2]);
+//! ```
+
+use ndarray::prelude::*;
+use num_traits::Zero;
+use statrs::distribution::{Continuous, Normal};
+
+use super::super::{LogProb, Prob};
+use super::{Error, Result, State, StateIter, StateTransitionIter};
+
+/// An emission distribution over observations of type `O`.
+pub trait Emission<O> {
+    /// Probability of the given observation.
+    fn prob(&self, observation: &O) -> LogProb;
+
+    /// Re-estimate the parameters from the given observations, weighted by the posterior
+    /// probability of the state emitting them (the M-step of the Baum-Welch algorithm).
+    /// The default implementation keeps the parameters fixed.
+    fn fit(&mut self, _observations: &[&O], _weights: &[f64]) {}
+}
+
+/// A categorical distribution over the symbols `0..M`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Categorical {
+    pub probs: Vec<LogProb>,
+}
+
+impl Categorical {
+    /// Create a categorical distribution with the given symbol probabilities.
+    pub fn new(probs: &[f64]) -> Self {
+        Categorical {
+            probs: probs.iter().map(|&p| LogProb::from(Prob(p))).collect(),
+        }
+    }
+}
+
+impl Emission<usize> for Categorical {
+    fn prob(&self, observation: &usize) -> LogProb {
+        self.probs[*observation]
+    }
+
+    fn fit(&mut self, observations: &[&usize], weights: &[f64]) {
+        let mut counts = vec![0.0; self.probs.len()];
+        for (&&o, &w) in observations.iter().zip(weights) {
+            counts[o] += w;
+        }
+        let total: f64 = counts.iter().sum();
+        if total > 0.0 {
+            self.probs = counts
+                .iter()
+                .map(|&c| LogProb::from(Prob(c / total)))
+                .collect();
+        }
+    }
+}
+
+/// A Gaussian distribution.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gaussian {
+    pub mean: f64,
+    pub sd: f64,
+}
+
+impl Gaussian {
+    /// Create a Gaussian distribution with the given mean and standard deviation.
+    ///
+    /// # Panics
+    ///
+    /// If the standard deviation is not positive.
+    pub fn new(mean: f64, sd: f64) -> Self {
+        assert!(sd > 0.0, "standard deviation must be positive");
+        Gaussian { mean, sd }
+    }
+
+    /// The density is the one of `statrs::distribution::Normal`, as used by
+    /// `univariate_continuous_emission::GaussianModel`.
+    fn ln_pdf(&self, x: f64) -> f64 {
+        Normal::new(self.mean, self.sd)
+            .expect("standard deviation must be positive")
+            .ln_pdf(x)
+    }
+
+    /// Weighted maximum likelihood estimate. Keeps the current parameters if the observations
+    /// have no weight, and the current standard deviation if they have no variance.
+    fn fit_weighted<I: Iterator<Item = (f64, f64)> + Clone>(&mut self, observations: I) {
+        let total: f64 = observations.clone().map(|(_, w)| w).sum();
+        if total <= 0.0 {
+            return;
+        }
+        let mean = observations.clone().map(|(x, w)| w * x).sum::<f64>() / total;
+        let var = observations
+            .map(|(x, w)| w * (x - mean) * (x - mean))
+            .sum::<f64>()
+            / total;
+        self.mean = mean;
+        if var > 0.0 {
+            self.sd = var.sqrt();
+        }
+    }
+}
+
+impl Emission<f64> for Gaussian {
+    fn prob(&self, observation: &f64) -> LogProb {
+        LogProb(self.ln_pdf(*observation))
+    }
+
+    fn fit(&mut self, observations: &[&f64], weights: &[f64]) {
+        self.fit_weighted(
+            observations
+                .iter()
+                .map(|&&x| x)
+                .zip(weights.iter().copied()),
+        );
+    }
+}
+
+/// A mixture of Gaussian distributions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GaussianMixture {
+    /// Mixture weights (summing up to one) and components.
+    pub components: Vec<(f64, Gaussian)>,
+}
+
+impl GaussianMixture {
+    /// Create a mixture of the given weighted components. The weights are normalized.
+    ///
+    /// # Panics
+    ///
+    /// If there are no components or the weights do not have a positive sum.
+    pub fn new(components: Vec<(f64, Gaussian)>) -> Self {
+        let total: f64 = components.iter().map(|(w, _)| w).sum();
+        assert!(total > 0.0, "mixture weights must have a positive sum");
+        GaussianMixture {
+            components: components
+                .into_iter()
+                .map(|(w, component)| (w / total, component))
+                .collect(),
+        }
+    }
+
+    /// Probability of each component, given the observation.
+    fn responsibilities(&self, x: f64) -> Vec<f64> {
+        let joint: Vec<LogProb> = self
+            .components
+            .iter()
+            .map(|(w, component)| LogProb(w.ln() + component.ln_pdf(x)))
+            .collect();
+        let total = LogProb::ln_sum_exp(&joint);
+        joint
+            .into_iter()
+            .map(|p| {
+                if total.is_zero() {
+                    0.0
+                } else {
+                    (p - total).exp()
+                }
+            })
+            .collect()
+    }
+}
+
+impl Emission<f64> for GaussianMixture {
+    fn prob(&self, observation: &f64) -> LogProb {
+        let joint: Vec<LogProb> = self
+            .components
+            .iter()
+            .map(|(w, component)| LogProb(w.ln() + component.ln_pdf(*observation)))
+            .collect();
+        LogProb::ln_sum_exp(&joint)
+    }
+
+    /// Perform a single EM step on the mixture.
+    fn fit(&mut self, observations: &[&f64], weights: &[f64]) {
+        let responsibilities: Vec<Vec<f64>> = observations
+            .iter()
+            .map(|&&x| self.responsibilities(x))
+            .collect();
+        let total: f64 = weights.iter().sum();
+        if total <= 0.0 {
+            return;
+        }
+        for (c, (weight, component)) in self.components.iter_mut().enumerate() {
+            let weighted = observations
+                .iter()
+                .zip(weights)
+                .zip(&responsibilities)
+                .map(|((&&x, &w), r)| (x, w * r[c]));
+            let component_total: f64 = weighted.clone().map(|(_, w)| w).sum();
+            *weight = component_total / total;
+            component.fit_weighted(weighted);
+        }
+    }
+}
+
+/// Implementation of a `hmm::Model` with an emission distribution of type `E` per state.
+///
+/// Log-scale probabilities are used for numeric stability.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Model<E> {
+    /// The state transition matrix (size `NxN`), `A` in Rabiner's tutorial.
+    transition: Array2<LogProb>,
+
+    /// The emission distribution of each state.
+    emissions: Vec<E>,
+
+    /// The initial state distribution (size `N`), `pi` in Rabiner's tutorial.
+    initial: Array1<LogProb>,
+}
+
+impl<E> Model<E> {
+    /// Construct new Hidden MarkovModel with the given transition matrix, emissions, and initial
+    /// state vector already in log-probability space.
+    pub fn new(
+        transition: Array2<LogProb>,
+        emissions: Vec<E>,
+        initial: Array1<LogProb>,
+    ) -> Result<Self> {
+        let (an0, an1) = transition.dim();
+        let bn = emissions.len();
+        let pin = initial.dim();
+
+        if an0 != an1 || an0 != bn || an0 != pin {
+            Err(Error::InvalidDimension {
+                an0,
+                an1,
+                bn,
+                bm: bn,
+                pin,
+            })
+        } else {
+            Ok(Self {
+                transition,
+                emissions,
+                initial,
+            })
+        }
+    }
+
+    /// Construct new Hidden MarkovModel with the given transition matrix, emissions, and initial
+    /// state vector with probabilities as `f64` values.
+    pub fn with_float(
+        transition: &Array2<f64>,
+        emissions: Vec<E>,
+        initial: &Array1<f64>,
+    ) -> Result<Self> {
+        Self::new(
+            transition.map(|x| LogProb::from(Prob(*x))),
+            emissions,
+            initial.map(|x| LogProb::from(Prob(*x))),
+        )
+    }
+
+    /// The state transition matrix (size `NxN`).
+    pub fn transition(&self) -> &Array2<LogProb> {
+        &self.transition
+    }
+
+    /// The emission distribution of each state.
+    pub fn emissions(&self) -> &[E] {
+        &self.emissions
+    }
+
+    /// The initial state distribution (size `N`).
+    pub fn initial(&self) -> &Array1<LogProb> {
+        &self.initial
+    }
+
+    /// Train the model on the given observation sequences with the Baum-Welch algorithm,
+    /// starting from this model. Iterates until the overall probability of the sequences
+    /// improves by less than `tolerance` (in log-space) or `max_iterations` is reached. Empty
+    /// sequences are ignored.
+    ///
+    /// ## Result
+    ///
+    /// The trained model and the overall probability of the sequences under it.
+    pub fn baum_welch<O, S: AsRef<[O]>>(
+        &self,
+        sequences: &[S],
+        max_iterations: usize,
+        tolerance: f64,
+    ) -> Result<(Self, LogProb)>
+    where
+        E: Emission<O> + Clone,
+    {
+        let observations: Vec<&O> = sequences.iter().flat_map(|seq| seq.as_ref()).collect();
+        super::baum_welch(
+            self,
+            sequences,
+            max_iterations,
+            tolerance,
+            |model, transition, initial, exps| {
+                let mut emissions = model.emissions.clone();
+                for (j, emission) in emissions.iter_mut().enumerate() {
+                    let weights: Vec<f64> = exps
+                        .iter()
+                        .flat_map(|exp| exp.states.column(j).to_vec())
+                        .map(|p| p.exp())
+                        .collect();
+                    emission.fit(&observations, &weights);
+                }
+                Model {
+                    transition,
+                    emissions,
+                    initial,
+                }
+            },
+        )
+    }
+}
+
+impl<O, E: Emission<O>> super::Model<O> for Model<E> {
+    fn num_states(&self) -> usize {
+        self.transition.dim().0
+    }
+
+    fn states(&self) -> StateIter {
+        StateIter {
+            nxt: 0,
+            max: self.num_states(),
+        }
+    }
+
+    fn transitions(&self) -> StateTransitionIter {
+        StateTransitionIter {
+            nxt_a: 0,
+            nxt_b: 0,
+            max: self.num_states(),
+        }
+    }
+
+    fn transition_prob(&self, from: State, to: State) -> LogProb {
+        self.transition[[*from, *to]]
+    }
+
+    fn initial_prob(&self, state: State) -> LogProb {
+        self.initial[[*state]]
+    }
+
+    fn observation_prob(&self, state: State, observation: &O) -> LogProb {
+        self.emissions[*state].prob(observation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::hmm::discrete_emission::Model as DiscreteEmissionHMM;
+    use crate::stats::hmm::univariate_continuous_emission::GaussianModel;
+    use crate::stats::hmm::{forward, posterior, viterbi};
+    use rand::prelude::*;
+    use statrs::distribution::Normal;
+
+    #[test]
+    fn test_categorical_equals_discrete() {
+        let transition = array![[0.6, 0.4], [0.3, 0.7]];
+        let observation = array![[0.2, 0.3, 0.3, 0.2], [0.3, 0.2, 0.2, 0.3]];
+        let initial = array![0.5, 0.5];
+        let discrete = DiscreteEmissionHMM::with_float(&transition, &observation, &initial)
+            .expect("Dimensions should be consistent");
+        let emissions = observation
+            .outer_iter()
+            .map(|row| Categorical::new(row.as_slice().unwrap()))
+            .collect();
+        let hmm = Model::with_float(&transition, emissions, &initial)
+            .expect("Dimensions should be consistent");
+
+        let sequences = vec![
+            vec![1, 2, 2, 1, 2, 1, 0, 3, 0, 0, 3, 3, 0],
+            vec![0, 3, 3, 0, 1, 2, 1, 1, 2],
+        ];
+        assert_eq!(
+            viterbi(&hmm, &sequences[0]),
+            viterbi(&discrete, &sequences[0])
+        );
+        assert_eq!(
+            posterior(&hmm, &sequences[1]),
+            posterior(&discrete, &sequences[1])
+        );
+
+        let (trained, prob) = hmm.baum_welch(&sequences, 5, 0.0).unwrap();
+        let (expected, expected_prob) = discrete.baum_welch(&sequences, 5, 0.0).unwrap();
+        assert_relative_eq!(*prob, *expected_prob, epsilon = 1e-4);
+        for (emission, row) in trained
+            .emissions()
+            .iter()
+            .zip(expected.observation().outer_iter())
+        {
+            for (p, q) in emission.probs.iter().zip(row) {
+                assert_relative_eq!(**p, **q, epsilon = 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn test_gaussian_equals_univariate_continuous() {
+        let transition = array![[0.8, 0.2], [0.3, 0.7]];
+        let initial = array![0.6, 0.4];
+        let hmm = Model::with_float(
+            &transition,
+            vec![Gaussian::new(0.0, 1.0), Gaussian::new(2.0, 0.5)],
+            &initial,
+        )
+        .expect("Dimensions should be consistent");
+        let continuous = GaussianModel::with_float(
+            &transition,
+            vec![
+                Normal::new(0.0, 1.0).unwrap(),
+                Normal::new(2.0, 0.5).unwrap(),
+            ],
+            &initial,
+        )
+        .expect("Dimensions should be consistent");
+
+        let observations = vec![0.1, -0.4, 1.8, 2.3, 2.1, 0.2];
+        assert_eq!(
+            viterbi(&hmm, &observations),
+            viterbi(&continuous, &observations)
+        );
+        assert_relative_eq!(
+            *forward(&hmm, &observations).1,
+            *forward(&continuous, &observations).1,
+            epsilon = 1e-10
+        );
+    }
+
+    #[test]
+    fn test_gaussian_baum_welch() {
+        // alternating segments drawn from two Gaussians
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut observations = Vec::new();
+        for segment in 0..10 {
+            let dist = if segment % 2 == 0 {
+                Normal::new(0.0, 0.5).unwrap()
+            } else {
+                Normal::new(3.0, 1.0).unwrap()
+            };
+            observations.extend((0..50).map(|_| dist.sample(&mut rng)));
+        }
+
+        let transition = array![[0.5, 0.5], [0.5, 0.5]];
+        let emissions = vec![Gaussian::new(-1.0, 2.0), Gaussian::new(1.0, 2.0)];
+        let initial = array![0.5, 0.5];
+        let hmm = Model::with_float(&transition, emissions, &initial)
+            .expect("Dimensions should be consistent");
+        let (trained, prob) = hmm.baum_welch(&[&observations], 100, 1e-8).unwrap();
+        assert!(*prob > *forward(&hmm, &observations).1);
+
+        let (low, high) = (trained.emissions()[0], trained.emissions()[1]);
+        assert_relative_eq!(low.mean, 0.0, epsilon = 0.2);
+        assert_relative_eq!(low.sd, 0.5, epsilon = 0.1);
+        assert_relative_eq!(high.mean, 3.0, epsilon = 0.2);
+        assert_relative_eq!(high.sd, 1.0, epsilon = 0.2);
+        assert!(*trained.transition()[[0, 0]] > Prob(0.9).ln());
+    }
+
+    #[test]
+    fn test_gaussian_mixture() {
+        let mixture = GaussianMixture::new(vec![
+            (1.0, Gaussian::new(0.0, 1.0)),
+            (3.0, Gaussian::new(4.0, 1.0)),
+        ]);
+        let expected = 0.25 * Gaussian::new(0.0, 1.0).prob(&1.0).exp()
+            + 0.75 * Gaussian::new(4.0, 1.0).prob(&1.0).exp();
+        assert_relative_eq!(mixture.prob(&1.0).exp(), expected, epsilon = 1e-6);
+
+        // a single EM step moves the components towards the data
+        let mut rng = StdRng::seed_from_u64(42);
+        let data: Vec<f64> = (0..1000)
+            .map(|i| {
+                let dist = if i % 2 == 0 {
+                    Normal::new(-1.0, 0.5).unwrap()
+                } else {
+                    Normal::new(5.0, 0.5).unwrap()
+                };
+                dist.sample(&mut rng)
+            })
+            .collect();
+        let refs: Vec<&f64> = data.iter().collect();
+        let mut fitted = mixture.clone();
+        for _ in 0..20 {
+            fitted.fit(&refs, &vec![1.0; data.len()]);
+        }
+        assert_relative_eq!(fitted.components[0].0, 0.5, epsilon = 0.05);
+        assert_relative_eq!(fitted.components[0].1.mean, -1.0, epsilon = 0.1);
+        assert_relative_eq!(fitted.components[1].1.mean, 5.0, epsilon = 0.1);
+    }
+}
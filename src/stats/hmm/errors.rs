@@ -23,5 +23,9 @@ pub enum Error {
         bm: usize,
         pin: usize,
     },
+    #[error("at least one non-empty observation sequence is required for training")]
+    NoObservations,
+    #[error("observation sequence {index} is impossible under the model")]
+    ImpossibleObservations { index: usize },
 }
 pub type Result<T, E = Error> = std::result::Result<T, E>;
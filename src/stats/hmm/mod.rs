@@ -57,20 +57,28 @@
 //! assert_relative_eq!(2.64e-8_f64, *prob, epsilon = 1e-9_f64);
 //! ```
 //!
+//! ## Posterior Decoding and Training
+//!
+//! Posterior state probabilities are computed with the forward-backward algorithm by
+//! `posterior()`. Discrete emission models can be trained on unlabeled observation sequences
+//! with the Baum-Welch algorithm (see `discrete_emission::Model::baum_welch()`).
+//!
 //! ## Numeric Stability
 //!
 //! The implementation uses log-scale probabilities for numeric stability.
 //!
 //! ## Limitations
 //!
-//! Currently, only discrete and single-variate Gaussian continuous HMMs are implemented.
-//! Also, only dense transition matrices are supported.
+//! Discrete, univariate continuous, and user-defined emission distributions (see the `emission`
+//! module, which also provides Gaussian mixtures) are implemented.
+//! Only dense transition matrices are supported.
 //!
 //! ## References
 //!
 //! - Rabiner, Lawrence R. "A tutorial on hidden Markov models and selected applications
 //!   in speech recognition." Proceedings of the IEEE 77, no. 2 (1989): 257-286.
 
+pub mod emission;
 pub mod errors;
 
 use std::cmp::Ordering;
@@ -438,6 +446,199 @@ pub fn backward<O, M: Model<O>>(hmm: &M, observations: &[O]) -> (Array2<LogProb>
     (vals, prob)
 }
 
+/// Compute the backward probabilities, i.e. `P[[t, s]]` is the probability of the
+/// observations after `t`, given state `s` at `t`.
+fn backward_probs<O, M: Model<O>>(hmm: &M, observations: &[O]) -> Array2<LogProb> {
+    let n = observations.len();
+    let mut vals = Array2::<LogProb>::zeros((n, hmm.num_states()));
+    for j in hmm.states() {
+        vals[[n - 1, *j]] = LogProb::ln_one();
+    }
+    for i in (0..n - 1).rev() {
+        for j in hmm.states() {
+            let xs = hmm
+                .states()
+                .map(|k| {
+                    hmm.transition_prob_idx(j, k, i + 1)
+                        + hmm.observation_prob(k, &observations[i + 1])
+                        + vals[[i + 1, *k]]
+                })
+                .collect::<Vec<LogProb>>();
+            vals[[i, *j]] = LogProb::ln_sum_exp(&xs);
+        }
+    }
+    vals
+}
+
+/// Execute the forward-backward algorithm and return the posterior state probabilities as
+/// `LogProb` values and the overall probability of the observations.
+///
+/// ## Arguments
+///
+/// - `hmm` - the `Model` to run the forward-backward algorithm on
+/// - `observations` - a non-empty slice of observation values to use in the algorithm
+///
+/// ## Result
+///
+/// The resulting pair (*P*, *p*) is the posterior probability table (`P[[o, s]]` is the
+/// probability of state `s` at observation `o`, given all observations) and the overall
+/// probability for `observations` (as `LogProb`).
+///
+/// ## Example
+///
+/// ```rust
+/// use approx::assert_relative_eq;
+/// use bio::stats::hmm::discrete_emission::Model as DiscreteEmissionHMM;
+/// use bio::stats::hmm::posterior;
+/// use bio::stats::Prob;
+/// use ndarray::array;
+///
+/// let transition = array![[0.5, 0.5], [0.4, 0.6]];
+/// let observation = array![[0.2, 0.3, 0.3, 0.2], [0.3, 0.2, 0.2, 0.3]];
+/// let initial = array![0.5, 0.5];
+/// let hmm = DiscreteEmissionHMM::with_float(&transition, &observation, &initial).unwrap();
+///
+/// let (probs, log_prob) = posterior(&hmm, &[2, 2, 1, 0]);
+/// assert_relative_eq!(*Prob::from(log_prob), 0.0038432, epsilon = 0.0001);
+/// for row in probs.outer_iter() {
+///     let total: f64 = row.iter().map(|p| *Prob::from(*p)).sum();
+///     assert_relative_eq!(total, 1.0, epsilon = 1e-5);
+/// }
+/// ```
+pub fn posterior<O, M: Model<O>>(hmm: &M, observations: &[O]) -> (Array2<LogProb>, LogProb) {
+    let (fwd, prob) = forward(hmm, observations);
+    let bwd = backward_probs(hmm, observations);
+    let vals = Array2::from_shape_fn(fwd.dim(), |(i, j)| fwd[[i, j]] + bwd[[i, j]] - prob);
+    (vals, prob)
+}
+
+/// Expected state occupancies and transitions of an observation sequence, as needed for
+/// Baum-Welch training.
+struct Expectations {
+    /// Posterior probability of each state at each observation.
+    states: Array2<LogProb>,
+    /// Expected number of transitions between each pair of states.
+    transitions: Array2<LogProb>,
+    prob: LogProb,
+}
+
+/// Compute the expectations of the given non-empty observations under the model.
+fn expectations<O, M: Model<O>>(hmm: &M, observations: &[O]) -> Expectations {
+    let (fwd, prob) = forward(hmm, observations);
+    let bwd = backward_probs(hmm, observations);
+    let states = Array2::from_shape_fn(fwd.dim(), |(i, j)| fwd[[i, j]] + bwd[[i, j]] - prob);
+    let n = hmm.num_states();
+    let transitions = Array2::from_shape_fn((n, n), |(j, k)| {
+        let xs = (1..observations.len())
+            .map(|i| {
+                fwd[[i - 1, j]]
+                    + hmm.transition_prob_idx(State(j), State(k), i)
+                    + hmm.observation_prob(State(k), &observations[i])
+                    + bwd[[i, k]]
+                    - prob
+            })
+            .collect::<Vec<LogProb>>();
+        LogProb::ln_sum_exp(&xs)
+    });
+    Expectations {
+        states,
+        transitions,
+        prob,
+    }
+}
+
+/// Compute the expectations of all non-empty observation sequences, and their overall
+/// probability.
+fn all_expectations<O, M: Model<O>, S: AsRef<[O]>>(
+    hmm: &M,
+    sequences: &[S],
+) -> Result<(Vec<Expectations>, LogProb)> {
+    let mut all = Vec::with_capacity(sequences.len());
+    for (index, observations) in sequences.iter().enumerate() {
+        let observations = observations.as_ref();
+        if observations.is_empty() {
+            continue;
+        }
+        let exp = expectations(hmm, observations);
+        if exp.prob.is_zero() {
+            return Err(Error::ImpossibleObservations { index });
+        }
+        all.push(exp);
+    }
+    if all.is_empty() {
+        return Err(Error::NoObservations);
+    }
+    let prob = all.iter().map(|exp| exp.prob).sum();
+    Ok((all, prob))
+}
+
+/// Re-estimate the initial state distribution and the transition matrix from the given
+/// expectations. Rows of states that are never left keep their previous values.
+fn reestimate_transitions<O, M: Model<O>>(
+    hmm: &M,
+    expectations: &[Expectations],
+) -> (Array2<LogProb>, Array1<LogProb>) {
+    let n = hmm.num_states();
+    let num_seqs = LogProb((expectations.len() as f64).ln());
+    let initial = Array1::from_shape_fn(n, |j| {
+        let xs = expectations
+            .iter()
+            .map(|exp| exp.states[[0, j]])
+            .collect::<Vec<LogProb>>();
+        LogProb::ln_sum_exp(&xs) - num_seqs
+    });
+    let counts = Array2::from_shape_fn((n, n), |(j, k)| {
+        let xs = expectations
+            .iter()
+            .map(|exp| exp.transitions[[j, k]])
+            .collect::<Vec<LogProb>>();
+        LogProb::ln_sum_exp(&xs)
+    });
+    let mut transition =
+        Array2::from_shape_fn((n, n), |(j, k)| hmm.transition_prob(State(j), State(k)));
+    for (j, row) in counts.outer_iter().enumerate() {
+        let total = LogProb::ln_sum_exp(row.to_slice().unwrap());
+        if !total.is_zero() {
+            for k in 0..n {
+                transition[[j, k]] = row[k] - total;
+            }
+        }
+    }
+    (transition, initial)
+}
+
+/// Train the given model on the observation sequences with the Baum-Welch algorithm. In each
+/// iteration, `reestimate` is called with the current model, the re-estimated transition
+/// matrix and initial state distribution, and the expectations of the non-empty sequences (in
+/// order), and returns the model with updated emissions.
+fn baum_welch<O, M, S, F>(
+    hmm: &M,
+    sequences: &[S],
+    max_iterations: usize,
+    tolerance: f64,
+    mut reestimate: F,
+) -> Result<(M, LogProb)>
+where
+    M: Model<O> + Clone,
+    S: AsRef<[O]>,
+    F: FnMut(&M, Array2<LogProb>, Array1<LogProb>, &[Expectations]) -> M,
+{
+    let mut model = hmm.clone();
+    let (mut exps, mut prob) = all_expectations(&model, sequences)?;
+    for _ in 0..max_iterations {
+        let (transition, initial) = reestimate_transitions(&model, &exps);
+        model = reestimate(&model, transition, initial, &exps);
+        let (next_exps, next_prob) = all_expectations(&model, sequences)?;
+        let improvement = *next_prob - *prob;
+        exps = next_exps;
+        prob = next_prob;
+        if improvement < tolerance {
+            break;
+        }
+    }
+    Ok((model, prob))
+}
+
 /// Implementation of Hidden Markov Model with emission values from discrete distributions.
 pub mod discrete_emission {
     use super::super::{LogProb, Prob};
@@ -451,7 +652,7 @@ pub mod discrete_emission {
     /// The state transition matrix with dimensions `NxN` is `A`, the observation probability
     /// distribution is the matrix `B` with dimensions `NxM` and the initial state distribution `pi`
     /// has length `N`.
-    #[derive(Debug, PartialEq)]
+    #[derive(Debug, Clone, PartialEq)]
     pub struct Model {
         /// The state transition matrix (size `NxN`), `A` in Rabiner's tutorial.
         transition: Array2<LogProb>,
@@ -519,6 +720,89 @@ pub mod discrete_emission {
                 initial.map(|x| LogProb::from(Prob(*x))),
             )
         }
+
+        /// The state transition matrix (size `NxN`).
+        pub fn transition(&self) -> &Array2<LogProb> {
+            &self.transition
+        }
+
+        /// The observation symbol probability distribution (size `NxM`).
+        pub fn observation(&self) -> &Array2<LogProb> {
+            &self.observation
+        }
+
+        /// The initial state distribution (size `N`).
+        pub fn initial(&self) -> &Array1<LogProb> {
+            &self.initial
+        }
+
+        /// Train the model on the given observation sequences with the Baum-Welch algorithm,
+        /// starting from this model. Iterates until the overall probability of the
+        /// sequences improves by less than `tolerance` (in log-space) or `max_iterations` is
+        /// reached. Empty sequences are ignored.
+        ///
+        /// ## Result
+        ///
+        /// The trained model and the overall probability of the sequences under it.
+        ///
+        /// ## Example
+        ///
+        /// ```rust
+        /// use bio::stats::hmm::discrete_emission::Model as DiscreteEmissionHMM;
+        /// use bio::stats::hmm::{forward, Model};
+        /// use ndarray::array;
+        ///
+        /// let transition = array![[0.6, 0.4], [0.3, 0.7]];
+        /// let observation = array![[0.4, 0.6], [0.5, 0.5]];
+        /// let initial = array![0.5, 0.5];
+        /// let hmm = DiscreteEmissionHMM::with_float(&transition, &observation, &initial).unwrap();
+        ///
+        /// let sequences = [vec![0, 0, 0, 1, 1, 1, 1, 0, 0, 0], vec![1, 1, 1, 0, 0, 0, 0]];
+        /// let (trained, log_prob) = hmm.baum_welch(&sequences, 100, 1e-6).unwrap();
+        /// let before: f64 = sequences.iter().map(|seq| *forward(&hmm, seq).1).sum();
+        /// assert!(*log_prob > before);
+        /// ```
+        pub fn baum_welch<S: AsRef<[usize]>>(
+            &self,
+            sequences: &[S],
+            max_iterations: usize,
+            tolerance: f64,
+        ) -> Result<(Self, LogProb)> {
+            super::baum_welch(
+                self,
+                sequences,
+                max_iterations,
+                tolerance,
+                |model, transition, initial, exps| {
+                    let mut observation = model.observation.clone();
+                    for (j, mut row) in observation.outer_iter_mut().enumerate() {
+                        let mut counts = vec![Vec::new(); row.len()];
+                        let seqs = sequences
+                            .iter()
+                            .map(|seq| seq.as_ref())
+                            .filter(|seq| !seq.is_empty());
+                        for (exp, observations) in exps.iter().zip(seqs) {
+                            for (i, &o) in observations.iter().enumerate() {
+                                counts[o].push(exp.states[[i, j]]);
+                            }
+                        }
+                        let counts: Vec<LogProb> =
+                            counts.iter().map(|xs| LogProb::ln_sum_exp(xs)).collect();
+                        let total = LogProb::ln_sum_exp(&counts);
+                        if !total.is_zero() {
+                            for (p, count) in row.iter_mut().zip(counts) {
+                                *p = count - total;
+                            }
+                        }
+                    }
+                    Model {
+                        transition,
+                        observation,
+                        initial,
+                    }
+                },
+            )
+        }
     }
 
     impl super::Model<usize> for Model {
@@ -679,6 +963,7 @@ mod tests {
     use super::discrete_emission::Model as DiscreteEmissionHMM;
     use super::univariate_continuous_emission::GaussianModel as GaussianHMM;
     use super::*;
+    use std::iter;
 
     #[test]
     fn test_discrete_viterbi_toy_example() {
@@ -763,6 +1048,96 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_discrete_posterior_against_naive() {
+        let transition = array![[0.5, 0.5], [0.4, 0.6]];
+        let observation = array![[0.2, 0.3, 0.3, 0.2], [0.3, 0.2, 0.2, 0.3]];
+        let initial = array![0.5, 0.5];
+        let hmm = DiscreteEmissionHMM::with_float(&transition, &observation, &initial)
+            .expect("Dimensions should be consistent");
+        let seq = [2, 2, 1, 0, 3];
+
+        // sum over all state paths
+        let mut joint = Array2::<f64>::zeros((seq.len(), 2));
+        let mut total = 0.0;
+        for path in 0..1usize << seq.len() {
+            let state = |i: usize| (path >> i) & 1;
+            let mut p = initial[state(0)] * observation[[state(0), seq[0]]];
+            for i in 1..seq.len() {
+                p *= transition[[state(i - 1), state(i)]] * observation[[state(i), seq[i]]];
+            }
+            for i in 0..seq.len() {
+                joint[[i, state(i)]] += p;
+            }
+            total += p;
+        }
+
+        let (probs, log_prob) = posterior(&hmm, &seq);
+        assert_relative_eq!(*Prob::from(log_prob), total, max_relative = 1e-5);
+        for i in 0..seq.len() {
+            for j in 0..2 {
+                assert_relative_eq!(
+                    *Prob::from(probs[[i, j]]),
+                    joint[[i, j]] / total,
+                    epsilon = 1e-5
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_discrete_baum_welch() {
+        let transition = array![[0.6, 0.4], [0.3, 0.7]];
+        let observation = array![[0.2, 0.3, 0.3, 0.2], [0.3, 0.2, 0.2, 0.3]];
+        let initial = array![0.5, 0.5];
+        let hmm = DiscreteEmissionHMM::with_float(&transition, &observation, &initial)
+            .expect("Dimensions should be consistent");
+        let sequences = vec![
+            vec![1, 2, 2, 1, 2, 1, 0, 3, 0, 0, 3, 3, 0],
+            vec![],
+            vec![0, 3, 3, 0, 1, 2, 1, 1, 2],
+        ];
+
+        // the likelihood never decreases
+        let mut last = f64::NEG_INFINITY;
+        for iterations in 0..10 {
+            let (trained, log_prob) = hmm.baum_welch(&sequences, iterations, 0.0).unwrap();
+            assert!(*log_prob >= last - 1e-9);
+            last = *log_prob;
+
+            let total: f64 = sequences
+                .iter()
+                .filter(|seq| !seq.is_empty())
+                .map(|seq| *forward(&trained, seq).1)
+                .sum();
+            assert_relative_eq!(*log_prob, total, epsilon = 1e-9);
+            let rows = trained
+                .transition()
+                .outer_iter()
+                .chain(trained.observation().outer_iter())
+                .chain(iter::once(trained.initial().view()));
+            for row in rows {
+                let sum: f64 = row.iter().map(|p| *Prob::from(*p)).sum();
+                assert_relative_eq!(sum, 1.0, epsilon = 1e-5);
+            }
+        }
+
+        assert_eq!(
+            hmm.baum_welch(&[Vec::<usize>::new()], 10, 0.0),
+            Err(Error::NoObservations)
+        );
+        let impossible = DiscreteEmissionHMM::with_float(
+            &transition,
+            &array![[1.0, 0.0, 0.0, 0.0], [1.0, 0.0, 0.0, 0.0]],
+            &initial,
+        )
+        .unwrap();
+        assert_eq!(
+            impossible.baum_welch(&sequences, 10, 0.0),
+            Err(Error::ImpossibleObservations { index: 0 })
+        );
+    }
+
     #[test]
     fn test_gaussian_viterbi_simple_example() {
         let transition = array![[0.5, 0.5], [0.4, 0.6]];
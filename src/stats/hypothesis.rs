@@ -0,0 +1,337 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Statistical tests on contingency tables, e.g. for strand bias of variant calls or for
+//! enrichment of reads in a region, the hypergeometric test for enrichment of e.g. a gene set,
+//! and correction for multiple testing. P-values are given in log-space, such that very small
+//! p-values do not underflow.
+//!
+//! # Example
+//!
+//! ```
+//! use approx::assert_relative_eq;
+//! use bio::stats::hypothesis::{benjamini_hochberg, chi_square, fisher_exact, hypergeometric, Alternative};
+//!
+//! // forward and reverse reads supporting the reference and the alternative allele
+//! let table = [[3, 1], [1, 3]];
+//! let pvalue = fisher_exact(table, Alternative::TwoSided);
+//! assert_relative_eq!(pvalue.exp(), 0.4857142857142857, epsilon = 1e-12);
+//!
+//! let test = chi_square(table, true);
+//! assert_relative_eq!(test.statistic, 0.5);
+//!
+//! // 8 of 20 differentially expressed genes are in a gene set of size 50, out of 1000 genes
+//! let enrichment = hypergeometric(8, 1000, 50, 20);
+//! let pvalues = [enrichment, fisher_exact(table, Alternative::Greater)];
+//! let qvalues = benjamini_hochberg(&pvalues);
+//! assert!(qvalues[0] < qvalues[1]);
+//! ```
+
+use std::cmp;
+
+use itertools::Itertools;
+use ordered_float::OrderedFloat;
+use statrs::function::erf;
+
+use crate::stats::combinatorics::ln_combinations;
+use crate::stats::probs::ln_sum_exp_exact;
+use crate::stats::LogProb;
+
+/// A 2×2 contingency table, given as rows.
+pub type Table = [[u64; 2]; 2];
+
+/// Alternative hypothesis of a test, regarding the upper left cell of a 2×2 table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Alternative {
+    TwoSided,
+    /// The upper left cell is smaller than expected (odds ratio < 1).
+    Less,
+    /// The upper left cell is larger than expected (odds ratio > 1).
+    Greater,
+}
+
+/// Relative tolerance when comparing table probabilities in the two-sided Fisher's exact test,
+/// as used by R.
+const RELATIVE_TOLERANCE: f64 = 1e-7;
+
+/// Probability of drawing exactly k successes in n draws without replacement from a population
+/// with the given number of successes, in log-space. Invalid parameters, i.e. more successes
+/// or draws than the population size, yield a probability of zero.
+pub fn hypergeometric_pmf(k: u64, population: u64, successes: u64, draws: u64) -> LogProb {
+    if successes > population || k > successes || k > draws || draws - k > population - successes {
+        return LogProb::ln_zero();
+    }
+    LogProb(
+        ln_combinations(successes, k) + ln_combinations(population - successes, draws - k)
+            - ln_combinations(population, draws),
+    )
+}
+
+/// Hypergeometric test for enrichment, i.e. the probability of drawing at least k successes
+/// in n draws without replacement from a population with the given number of successes.
+/// Time complexity: O(n)
+pub fn hypergeometric(k: u64, population: u64, successes: u64, draws: u64) -> LogProb {
+    if k == 0 {
+        return LogProb::ln_one();
+    }
+    let max = cmp::min(successes, draws);
+    let probs = (k..=max)
+        .map(|x| *hypergeometric_pmf(x, population, successes, draws))
+        .collect_vec();
+    cap(ln_sum_exp_exact(&probs))
+}
+
+/// Fisher's exact test on a 2×2 table, conditioning on the row and column sums.
+/// Time complexity: O(n) for a table with n entries.
+pub fn fisher_exact(table: Table, alternative: Alternative) -> LogProb {
+    let [[a, b], [c, d]] = table;
+    let (row1, row2, col1) = (a + b, c + d, a + c);
+    // the upper left cell follows a hypergeometric distribution, given the margins
+    let ln_pmf = |x: u64| *hypergeometric_pmf(x, row1 + row2, row1, col1);
+    let (min, max) = (col1.saturating_sub(row2), cmp::min(row1, col1));
+
+    let probs: Vec<f64> = match alternative {
+        Alternative::Less => (min..=a).map(ln_pmf).collect(),
+        Alternative::Greater => (a..=max).map(ln_pmf).collect(),
+        Alternative::TwoSided => {
+            let observed = ln_pmf(a) + RELATIVE_TOLERANCE.ln_1p();
+            (min..=max).map(ln_pmf).filter(|&p| p <= observed).collect()
+        }
+    };
+    cap(ln_sum_exp_exact(&probs))
+}
+
+/// Bonferroni correction of the given p-values for multiple testing, controlling the
+/// family-wise error rate.
+pub fn bonferroni(pvalues: &[LogProb]) -> Vec<LogProb> {
+    let m = (pvalues.len() as f64).ln();
+    pvalues.iter().map(|&p| cap(p + LogProb(m))).collect()
+}
+
+/// Benjamini–Hochberg correction of the given p-values for multiple testing, controlling the
+/// false discovery rate (Benjamini and Hochberg, Journal of the Royal Statistical Society,
+/// Series B, 1995). Returns the adjusted p-values (q-values) in the order of the given p-values.
+/// Time complexity: O(m log m) for m p-values.
+pub fn benjamini_hochberg(pvalues: &[LogProb]) -> Vec<LogProb> {
+    let m = pvalues.len();
+    let sorted_idx = (0..m).sorted_by_key(|&i| OrderedFloat(*pvalues[i]));
+    let mut qvalues = vec![LogProb::ln_one(); m];
+    let mut min = LogProb::ln_one();
+    // the q-value is the minimum of p * m / rank over all p-values of at least the same rank
+    for (rank, i) in sorted_idx.enumerate().rev() {
+        let q = pvalues[i] + LogProb((m as f64).ln() - ((rank + 1) as f64).ln());
+        if q < min {
+            min = q;
+        }
+        qvalues[i] = min;
+    }
+    qvalues
+}
+
+/// Result of a chi-square test.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChiSquare {
+    /// The test statistic.
+    pub statistic: f64,
+    /// The p-value of the statistic, with one degree of freedom.
+    pub pvalue: LogProb,
+}
+
+/// Pearson's chi-square test of independence on a 2×2 table, optionally with Yates' continuity
+/// correction. If a row or column sum is zero, the statistic is zero.
+pub fn chi_square(table: Table, yates: bool) -> ChiSquare {
+    // compute in floating point, such that products of large counts cannot overflow
+    let [[a, b], [c, d]] = table.map(|row| row.map(|count| count as f64));
+    let n = a + b + c + d;
+    let margins = [a + b, c + d, a + c, b + d];
+    if margins.contains(&0.0) {
+        return ChiSquare {
+            statistic: 0.0,
+            pvalue: LogProb::ln_one(),
+        };
+    }
+    let mut diff = (a * d - b * c).abs();
+    if yates {
+        diff = (diff - n / 2.0).max(0.0);
+    }
+    let statistic = n * diff * diff / margins.iter().product::<f64>();
+    ChiSquare {
+        statistic,
+        pvalue: chi_square_sf(statistic),
+    }
+}
+
+/// Survival function of the chi-square distribution with one degree of freedom in log-space.
+fn chi_square_sf(statistic: f64) -> LogProb {
+    LogProb(ln_erfc((statistic / 2.0).sqrt()))
+}
+
+/// Argument above which `ln_erfc` uses the asymptotic expansion, well before `erfc`
+/// underflows (at about 26.5).
+const ERFC_ASYMPTOTIC: f64 = 20.0;
+
+/// Logarithm of the complementary error function for non-negative arguments. For large
+/// arguments, the asymptotic expansion
+/// `erfc(x) = exp(-x²) / (x √π) * (1 - 1/(2x²) + 3/(2x²)² - 15/(2x²)³ + ...)`
+/// is used, whose first omitted term is below 1e-9 relative to the result.
+fn ln_erfc(x: f64) -> f64 {
+    if x < ERFC_ASYMPTOTIC {
+        return erf::erfc(x).ln();
+    }
+    let y = 1.0 / (2.0 * x * x);
+    let series = 1.0 - y * (1.0 - 3.0 * y * (1.0 - 5.0 * y * (1.0 - 7.0 * y)));
+    -x * x - x.ln() - 0.5 * std::f64::consts::PI.ln() + series.ln()
+}
+
+/// Cap numerical overshoots of sums of probabilities at 1.
+fn cap(p: LogProb) -> LogProb {
+    if p > LogProb::ln_one() {
+        LogProb::ln_one()
+    } else {
+        p
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fisher_exact() {
+        // reference values calculated with exact arithmetic
+        let cases = [
+            (
+                [[3, 1], [1, 3]],
+                [0.4857142857142857, 0.9857142857142857, 0.24285714285714285],
+            ),
+            (
+                [[1, 9], [11, 3]],
+                [
+                    0.002759456185220083,
+                    0.0013797280926100416,
+                    0.9999663480953022,
+                ],
+            ),
+            (
+                [[10, 20], [30, 40]],
+                [0.5044757698516281, 0.2533310713617557, 0.8676419647894411],
+            ),
+            (
+                [[500, 10], [400, 90]],
+                [9.498849741189535e-20, 1.0, 7.401905838061512e-20],
+            ),
+        ];
+        for (table, expected) in &cases {
+            let alternatives = [
+                Alternative::TwoSided,
+                Alternative::Less,
+                Alternative::Greater,
+            ];
+            for (&alternative, &p) in alternatives.iter().zip(expected) {
+                let pvalue = fisher_exact(*table, alternative);
+                assert_relative_eq!(pvalue.exp(), p, max_relative = 1e-10);
+            }
+        }
+        assert_eq!(
+            fisher_exact([[0, 0], [0, 5]], Alternative::TwoSided),
+            LogProb::ln_one()
+        );
+    }
+
+    #[test]
+    fn test_hypergeometric() {
+        // reference values calculated with exact arithmetic
+        assert_relative_eq!(
+            hypergeometric(8, 1000, 50, 20).exp(),
+            1.7680127327682835e-06,
+            max_relative = 1e-10
+        );
+        assert_relative_eq!(
+            hypergeometric_pmf(2, 1000, 50, 20).exp(),
+            0.19038136022410357,
+            max_relative = 1e-10
+        );
+        assert_eq!(hypergeometric(0, 1000, 50, 20), LogProb::ln_one());
+        assert_eq!(hypergeometric(21, 1000, 50, 20), LogProb::ln_zero());
+        assert_eq!(hypergeometric_pmf(0, 10, 8, 5), LogProb::ln_zero());
+        assert_eq!(hypergeometric(5, 10, 5, 5), hypergeometric_pmf(5, 10, 5, 5));
+        // more successes or draws than the population size
+        assert_eq!(hypergeometric_pmf(2, 10, 12, 5), LogProb::ln_zero());
+        assert_eq!(hypergeometric_pmf(2, 10, 5, 12), LogProb::ln_zero());
+        assert_eq!(hypergeometric(2, 10, 12, 5), LogProb::ln_zero());
+    }
+
+    #[test]
+    fn test_multiple_testing() {
+        let pvalues = [0.01, 0.04, 0.03, 0.005, 0.5]
+            .iter()
+            .map(|p: &f64| LogProb(p.ln()))
+            .collect_vec();
+        let expected = [0.025, 0.05, 0.05, 0.025, 0.5];
+        for (q, e) in benjamini_hochberg(&pvalues).iter().zip(&expected) {
+            assert_relative_eq!(q.exp(), e, max_relative = 1e-12);
+        }
+        let expected = [0.05, 0.2, 0.15, 0.025, 1.0];
+        for (q, e) in bonferroni(&pvalues).iter().zip(&expected) {
+            assert_relative_eq!(q.exp(), e, max_relative = 1e-12);
+        }
+        assert!(benjamini_hochberg(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_chi_square() {
+        let test = chi_square([[1, 9], [11, 3]], false);
+        assert_relative_eq!(test.statistic, 10.971428571428572, max_relative = 1e-12);
+        assert_relative_eq!(
+            test.pvalue.exp(),
+            0.0009252740371197613,
+            max_relative = 1e-10
+        );
+        let test = chi_square([[1, 9], [11, 3]], true);
+        assert_relative_eq!(test.statistic, 8.4, max_relative = 1e-12);
+        assert_relative_eq!(
+            test.pvalue.exp(),
+            0.0037522101008738433,
+            max_relative = 1e-10
+        );
+        // products of the counts exceed u64
+        let test = chi_square(
+            [
+                [1_000_000_000, 9_000_000_000],
+                [11_000_000_000, 3_000_000_000],
+            ],
+            false,
+        );
+        assert_relative_eq!(test.statistic, 10.971428571428572e9, max_relative = 1e-12);
+        assert!(test.pvalue.is_finite());
+        let test = chi_square([[500, 10], [400, 90]], true);
+        assert_relative_eq!(
+            test.pvalue.exp(),
+            1.343870821806481e-17,
+            max_relative = 1e-8
+        );
+        assert_eq!(
+            chi_square([[0, 3], [0, 5]], false).pvalue,
+            LogProb::ln_one()
+        );
+    }
+
+    #[test]
+    fn test_chi_square_huge_statistic() {
+        // reference values from the asymptotic series with more terms
+        assert_relative_eq!(
+            *chi_square_sf(10_000.0),
+            -5004.831061513645,
+            max_relative = 1e-12
+        );
+        // both branches agree at the switch to the asymptotic expansion
+        let x = ERFC_ASYMPTOTIC;
+        assert_relative_eq!(ln_erfc(x), -403.56934333410425, max_relative = 1e-12);
+        assert_relative_eq!(ln_erfc(x), erf::erfc(x).ln(), max_relative = 1e-12);
+        let test = chi_square([[10_000, 0], [0, 10_000]], false);
+        assert_relative_eq!(test.statistic, 20_000.0);
+        assert!(test.pvalue.is_finite() && *test.pvalue < -9000.0);
+    }
+}
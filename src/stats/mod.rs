@@ -7,8 +7,12 @@
 
 pub mod bayesian;
 pub mod combinatorics;
+pub mod distributions;
+pub mod em;
 pub mod hmm;
+pub mod hypothesis;
 pub mod pairhmm;
 pub mod probs;
+pub mod streaming;
 
 pub use crate::stats::probs::{LogProb, PHREDProb, Prob};
@@ -99,11 +99,13 @@
 //! ```
 pub use homopolypairhmm::{HomopolyPairHMM, HopParameters};
 pub use pairhmm::PairHMM;
+pub use read_likelihood::{PhredGapParameters, ReadEmission, ReadLikelihood};
 
 use crate::stats::LogProb;
 
 mod homopolypairhmm;
 mod pairhmm;
+mod read_likelihood;
 
 // traits common to pairhmm implementations
 
@@ -32,6 +32,10 @@ fn ln_sum3_exp_approx(mut p0: LogProb, mut p1: LogProb, mut p2: LogProb) -> LogP
     if p1 > p0 {
         mem::swap(&mut p1, &mut p0);
     }
+    // p0 is the largest, p1 has to be the second largest
+    if p1 < p2 {
+        mem::swap(&mut p1, &mut p2);
+    }
     if *(p0 - p1) > 10.0 {
         // if p0 is strong enough compared to second, just return the maximum
         p0
@@ -288,6 +292,19 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_ln_sum3_exp_approx() {
+        // the second largest probability is passed last, and must not be dropped
+        let (p0, p1, p2) = (LogProb(-20.0), LogProb(-1.0), LogProb(-2.0));
+        let expected = LogProb::ln_sum_exp(&[p0, p1, p2]);
+        for &(a, b, c) in &[(p0, p1, p2), (p0, p2, p1), (p1, p0, p2), (p2, p1, p0)] {
+            assert_relative_eq!(*ln_sum3_exp_approx(a, b, c), *expected, epsilon = 1e-12);
+        }
+        // a negligible second largest probability is dropped
+        let (p0, p1, p2) = (LogProb(-1.0), LogProb(-20.0), LogProb(-30.0));
+        assert_eq!(ln_sum3_exp_approx(p2, p0, p1), p0);
+    }
+
     // Single base insertion and deletion rates for R1 according to Schirmer et al.
     // BMC Bioinformatics 2016, 10.1186/s12859-016-0976-y
     static PROB_ILLUMINA_INS: Prob = Prob(2.8e-6);
@@ -0,0 +1,499 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Likelihoods of sequencing reads given candidate haplotypes, as used by haplotype-based
+//! variant callers (e.g. GATK HaplotypeCaller). The read is aligned globally against a local
+//! part of the haplotype, with emission probabilities derived from the base qualities of the
+//! read and gap probabilities given as PHRED scaled qualities.
+//!
+//! Besides the log-space `PairHMM` (optionally banded), a linear-space kernel is provided,
+//! which avoids the expensive log-space additions. Like GATK, it scales the initial
+//! probability by 2^1020 to prevent underflows, and falls back to the log-space `PairHMM` if
+//! the likelihood still underflows.
+//!
+//! The emission probabilities of the read are precomputed for each symbol of the haplotype,
+//! such that the match and deletion states of a haplotype position are updated by a loop over
+//! contiguous arrays without branches. With the `simd` feature, this loop uses AVX on x86_64
+//! CPUs supporting it (detected at runtime). The insertion states are a recurrence along the
+//! read, and are computed sequentially.
+
+use crate::stats::pairhmm::{
+    EmissionParameters, GapParameters, PairHMM, StartEndGapParameters, XYEmission,
+};
+use crate::stats::{LogProb, PHREDProb, Prob};
+
+/// Scaling of the initial probability of the linear-space kernel.
+const SCALE_EXPONENT: i32 = 1020;
+
+/// Symmetric gap parameters given as PHRED scaled qualities.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhredGapParameters {
+    open: LogProb,
+    extend: LogProb,
+}
+
+impl PhredGapParameters {
+    /// Create gap parameters from the PHRED scaled probabilities of opening and extending a gap.
+    pub fn new(open: PHREDProb, extend: PHREDProb) -> Self {
+        PhredGapParameters {
+            open: LogProb::from(open),
+            extend: LogProb::from(extend),
+        }
+    }
+}
+
+impl Default for PhredGapParameters {
+    /// The defaults of GATK: gap open quality 45, and gap extension quality 10.
+    fn default() -> Self {
+        PhredGapParameters::new(PHREDProb(45.0), PHREDProb(10.0))
+    }
+}
+
+impl GapParameters for PhredGapParameters {
+    fn prob_gap_x(&self) -> LogProb {
+        self.open
+    }
+
+    fn prob_gap_y(&self) -> LogProb {
+        self.open
+    }
+
+    fn prob_gap_x_extend(&self) -> LogProb {
+        self.extend
+    }
+
+    fn prob_gap_y_extend(&self) -> LogProb {
+        self.extend
+    }
+}
+
+/// Emission parameters of a read (y) against a haplotype (x). A base is emitted correctly
+/// with probability `1 - e`, and as each of the three other bases with probability `e / 3`,
+/// where `e` is the error probability given by its base quality. `N`s match any base.
+/// Gaps are emitted with probability one.
+#[derive(Debug, Clone)]
+pub struct ReadEmission<'a> {
+    haplotype: &'a [u8],
+    read: &'a [u8],
+    prob_match: Vec<LogProb>,
+    prob_mismatch: Vec<LogProb>,
+}
+
+impl<'a> ReadEmission<'a> {
+    /// Create emission parameters for the given read with base qualities (PHRED scaled,
+    /// without ASCII offset) and haplotype.
+    ///
+    /// # Panics
+    ///
+    /// If the read and its qualities differ in length.
+    pub fn new(read: &'a [u8], quals: &[u8], haplotype: &'a [u8]) -> Self {
+        assert_eq!(
+            read.len(),
+            quals.len(),
+            "read and qualities must have the same length"
+        );
+        let errors: Vec<Prob> = quals
+            .iter()
+            .map(|&q| Prob::from(PHREDProb(q as f64)))
+            .collect();
+        ReadEmission {
+            haplotype,
+            read,
+            prob_match: errors
+                .iter()
+                .map(|&e| LogProb::from(Prob(1.0) - e))
+                .collect(),
+            prob_mismatch: errors
+                .iter()
+                .map(|&e| LogProb::from(e / Prob(3.0)))
+                .collect(),
+        }
+    }
+
+    fn is_match(&self, i: usize, j: usize) -> bool {
+        is_match(self.haplotype[i], self.read[j])
+    }
+}
+
+fn is_match(a: u8, b: u8) -> bool {
+    a.eq_ignore_ascii_case(&b) || a == b'N' || b == b'N'
+}
+
+impl<'a> EmissionParameters for ReadEmission<'a> {
+    fn prob_emit_xy(&self, i: usize, j: usize) -> XYEmission {
+        if self.is_match(i, j) {
+            XYEmission::Match(self.prob_match[j])
+        } else {
+            XYEmission::Mismatch(self.prob_mismatch[j])
+        }
+    }
+
+    fn prob_emit_x(&self, _: usize) -> LogProb {
+        LogProb::ln_one()
+    }
+
+    fn prob_emit_y(&self, _: usize) -> LogProb {
+        LogProb::ln_one()
+    }
+
+    fn len_x(&self) -> usize {
+        self.haplotype.len()
+    }
+
+    fn len_y(&self) -> usize {
+        self.read.len()
+    }
+}
+
+/// Alignment mode with free start and end gaps in the haplotype.
+struct LocalHaplotype;
+
+impl StartEndGapParameters for LocalHaplotype {
+    fn free_start_gap_x(&self) -> bool {
+        true
+    }
+
+    fn free_end_gap_x(&self) -> bool {
+        true
+    }
+}
+
+/// Calculates likelihoods of reads given haplotypes.
+///
+/// # Example
+///
+/// ```
+/// use bio::stats::pairhmm::{PhredGapParameters, ReadLikelihood};
+///
+/// let haplotypes: [&[u8]; 2] = [b"ACGTTGACCAGTAGGCATCA", b"ACGTTGACCCGTAGGCATCA"];
+/// let read = b"TGACCAGTAGG";
+/// let quals = [30; 11];
+///
+/// let mut engine = ReadLikelihood::new(PhredGapParameters::default());
+/// let p0 = engine.prob(read, &quals, haplotypes[0]);
+/// let p1 = engine.prob(read, &quals, haplotypes[1]);
+/// assert!(p0 > p1);
+/// // the linear-space kernel yields the same likelihoods
+/// assert!((*engine.prob_fast(read, &quals, haplotypes[1]) - *p1).abs() < 1e-3);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReadLikelihood {
+    gap_params: PhredGapParameters,
+    pairhmm: PairHMM,
+    max_edit_dist: Option<usize>,
+    /// Match, deletion (gap in the read) and insertion (gap in the haplotype) probabilities of
+    /// the previous and current haplotype position, for the linear-space kernel.
+    fm: [Vec<f64>; 2],
+    fx: [Vec<f64>; 2],
+    fy: Vec<f64>,
+}
+
+impl ReadLikelihood {
+    /// Create a new engine with the given gap parameters.
+    pub fn new(gap_params: PhredGapParameters) -> Self {
+        ReadLikelihood {
+            gap_params,
+            pairhmm: PairHMM::new(&gap_params),
+            max_edit_dist: None,
+            fm: [Vec::new(), Vec::new()],
+            fx: [Vec::new(), Vec::new()],
+            fy: Vec::new(),
+        }
+    }
+
+    /// Restrict the log-space calculation to alignments with at most the given edit distance
+    /// (banded alignment).
+    pub fn max_edit_dist(mut self, max_edit_dist: Option<usize>) -> Self {
+        self.max_edit_dist = max_edit_dist;
+        self
+    }
+
+    /// Likelihood of the read with the given base qualities (PHRED scaled, without ASCII
+    /// offset), given the haplotype, calculated with the log-space `PairHMM`.
+    pub fn prob(&mut self, read: &[u8], quals: &[u8], haplotype: &[u8]) -> LogProb {
+        let emission = ReadEmission::new(read, quals, haplotype);
+        self.pairhmm
+            .prob_related(&emission, &LocalHaplotype, self.max_edit_dist)
+    }
+
+    /// Likelihood of the read with the given base qualities (PHRED scaled, without ASCII
+    /// offset), given the haplotype, calculated with the linear-space kernel. Banding is not
+    /// applied.
+    pub fn prob_fast(&mut self, read: &[u8], quals: &[u8], haplotype: &[u8]) -> LogProb {
+        let emission = ReadEmission::new(read, quals, haplotype);
+        let n = read.len();
+        let prob_match: Vec<f64> = emission.prob_match.iter().map(|p| p.exp()).collect();
+        let prob_mismatch: Vec<f64> = emission.prob_mismatch.iter().map(|p| p.exp()).collect();
+        let open = self.gap_params.open.exp();
+        let extend = self.gap_params.extend.exp();
+        let transitions = Transitions {
+            open,
+            extend,
+            no_gap: 1.0 - 2.0 * open,
+            no_extend: 1.0 - extend,
+        };
+        let scale = 2f64.powi(SCALE_EXPONENT);
+
+        // emission probabilities of the read for each symbol of the haplotype
+        let mut rows = [None; 256];
+        let mut emissions: Vec<Vec<f64>> = Vec::new();
+        for &a in haplotype {
+            if rows[a as usize].is_none() {
+                rows[a as usize] = Some(emissions.len());
+                emissions.push(
+                    read.iter()
+                        .enumerate()
+                        .map(|(j, &b)| {
+                            if is_match(a, b) {
+                                prob_match[j]
+                            } else {
+                                prob_mismatch[j]
+                            }
+                        })
+                        .collect(),
+                );
+            }
+        }
+
+        for k in 0..2 {
+            self.fm[k].clear();
+            self.fm[k].resize(n + 1, 0.0);
+            self.fx[k].clear();
+            self.fx[k].resize(n + 1, 0.0);
+        }
+        self.fy.clear();
+        self.fy.resize(n + 1, 0.0);
+
+        let (mut prev, mut curr) = (0, 1);
+        let mut total = 0.0;
+        // initialized like in the log-space implementation
+        self.fm[prev][0] = scale;
+        for &a in haplotype {
+            // the alignment may start at any position of the haplotype
+            self.fm[prev][0] += scale;
+            self.fm[curr][0] = 0.0;
+            let (fm_prev, fm_curr) = split(&mut self.fm, prev);
+            let (fx_prev, fx_curr) = split(&mut self.fx, prev);
+            // match and deletion states only depend on the previous haplotype position
+            let emission = &emissions[rows[a as usize].unwrap()];
+            transitions.match_deletion(emission, fm_prev, fx_prev, &self.fy, fm_curr, fx_curr);
+            // insertion states depend on the current haplotype position
+            let mut fy_left = 0.0;
+            for j in 1..=n {
+                self.fy[j] = open * fm_curr[j - 1] + extend * fy_left;
+                fy_left = self.fy[j];
+            }
+            total += fm_curr[n] + fx_curr[n] + self.fy[n];
+            std::mem::swap(&mut prev, &mut curr);
+        }
+
+        if total > 0.0 && total.is_finite() {
+            // the sum of paths can exceed probability 1.0, especially in case of repeats
+            LogProb((total.ln() - SCALE_EXPONENT as f64 * 2f64.ln()).min(0.0))
+        } else {
+            // underflow, use the log-space implementation
+            self.pairhmm.prob_related(&emission, &LocalHaplotype, None)
+        }
+    }
+}
+
+/// Transition probabilities of the linear-space kernel.
+#[derive(Debug, Clone, Copy)]
+struct Transitions {
+    open: f64,
+    extend: f64,
+    no_gap: f64,
+    no_extend: f64,
+}
+
+impl Transitions {
+    /// Update the match and deletion states of the current haplotype position (`fm_curr`,
+    /// `fx_curr`) from those of the previous one, given the emission probabilities of the read
+    /// at the current haplotype position.
+    fn match_deletion(
+        &self,
+        emission: &[f64],
+        fm_prev: &[f64],
+        fx_prev: &[f64],
+        fy: &[f64],
+        fm_curr: &mut [f64],
+        fx_curr: &mut [f64],
+    ) {
+        #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+        {
+            if is_x86_feature_detected!("avx") {
+                // Safety: the CPU supports AVX.
+                unsafe {
+                    self.match_deletion_avx(emission, fm_prev, fx_prev, fy, fm_curr, fx_curr)
+                };
+                return;
+            }
+        }
+        self.match_deletion_from(0, emission, fm_prev, fx_prev, fy, fm_curr, fx_curr);
+    }
+
+    /// Update the states of read positions `start + 1..=n`.
+    #[allow(clippy::too_many_arguments)]
+    #[inline(always)]
+    fn match_deletion_from(
+        &self,
+        start: usize,
+        emission: &[f64],
+        fm_prev: &[f64],
+        fx_prev: &[f64],
+        fy: &[f64],
+        fm_curr: &mut [f64],
+        fx_curr: &mut [f64],
+    ) {
+        let n = emission.len();
+        let (fm_diag, fx_diag, fy_diag) = (&fm_prev[start..n], &fx_prev[start..n], &fy[start..n]);
+        let (fm_up, fx_up) = (&fm_prev[start + 1..=n], &fx_prev[start + 1..=n]);
+        for (k, (fm, fx)) in fm_curr[start + 1..=n]
+            .iter_mut()
+            .zip(&mut fx_curr[start + 1..=n])
+            .enumerate()
+        {
+            *fm = emission[start + k]
+                * (self.no_gap * fm_diag[k] + self.no_extend * (fx_diag[k] + fy_diag[k]));
+            *fx = self.open * fm_up[k] + self.extend * fx_up[k];
+        }
+    }
+
+    /// Like `match_deletion_from`, processing four read positions at once with AVX. Results
+    /// are identical, since the same operations are applied in the same order.
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    #[target_feature(enable = "avx")]
+    unsafe fn match_deletion_avx(
+        &self,
+        emission: &[f64],
+        fm_prev: &[f64],
+        fx_prev: &[f64],
+        fy: &[f64],
+        fm_curr: &mut [f64],
+        fx_curr: &mut [f64],
+    ) {
+        use std::arch::x86_64::*;
+
+        let n = emission.len();
+        assert!(fm_prev.len() > n && fx_prev.len() > n && fy.len() > n);
+        assert!(fm_curr.len() > n && fx_curr.len() > n);
+        let open = _mm256_set1_pd(self.open);
+        let extend = _mm256_set1_pd(self.extend);
+        let no_gap = _mm256_set1_pd(self.no_gap);
+        let no_extend = _mm256_set1_pd(self.no_extend);
+        let vectorized = n - n % 4;
+        for j in (0..vectorized).step_by(4) {
+            let fm_diag = _mm256_loadu_pd(fm_prev.as_ptr().add(j));
+            let fx_diag = _mm256_loadu_pd(fx_prev.as_ptr().add(j));
+            let fy_diag = _mm256_loadu_pd(fy.as_ptr().add(j));
+            let fm_up = _mm256_loadu_pd(fm_prev.as_ptr().add(j + 1));
+            let fx_up = _mm256_loadu_pd(fx_prev.as_ptr().add(j + 1));
+            let fm = _mm256_mul_pd(
+                _mm256_loadu_pd(emission.as_ptr().add(j)),
+                _mm256_add_pd(
+                    _mm256_mul_pd(no_gap, fm_diag),
+                    _mm256_mul_pd(no_extend, _mm256_add_pd(fx_diag, fy_diag)),
+                ),
+            );
+            let fx = _mm256_add_pd(_mm256_mul_pd(open, fm_up), _mm256_mul_pd(extend, fx_up));
+            _mm256_storeu_pd(fm_curr.as_mut_ptr().add(j + 1), fm);
+            _mm256_storeu_pd(fx_curr.as_mut_ptr().add(j + 1), fx);
+        }
+        self.match_deletion_from(vectorized, emission, fm_prev, fx_prev, fy, fm_curr, fx_curr);
+    }
+}
+
+/// Split the given pair of vectors into the one at `prev` and the other one.
+fn split(vecs: &mut [Vec<f64>; 2], prev: usize) -> (&[f64], &mut [f64]) {
+    let (first, second) = vecs.split_at_mut(1);
+    if prev == 0 {
+        (&first[0], &mut second[0])
+    } else {
+        (&second[0], &mut first[0])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testing::random_seq;
+    use rand::prelude::*;
+
+    #[test]
+    fn test_fast_against_pairhmm() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut engine = ReadLikelihood::new(PhredGapParameters::default());
+        for _ in 0..100 {
+            let len = rng.gen_range(1..60);
+            let haplotype = random_seq(&mut rng, b"ACGT", len);
+            let start = rng.gen_range(0..haplotype.len());
+            let end = rng.gen_range(start + 1..=haplotype.len());
+            let mut read = haplotype[start..end].to_vec();
+            for _ in 0..rng.gen_range(0..3) {
+                let i = rng.gen_range(0..read.len());
+                match rng.gen_range(0..3) {
+                    0 => read[i] = *b"ACGTN".choose(&mut rng).unwrap(),
+                    1 => read.insert(i, b'A'),
+                    _ if read.len() > 1 => {
+                        read.remove(i);
+                    }
+                    _ => (),
+                }
+            }
+            let quals: Vec<u8> = (0..read.len()).map(|_| rng.gen_range(2..41)).collect();
+            let p = engine.prob(&read, &quals, &haplotype);
+            let p_fast = engine.prob_fast(&read, &quals, &haplotype);
+            assert!((*p - *p_fast).abs() < 1e-3, "{} != {}", *p, *p_fast);
+        }
+    }
+
+    #[test]
+    fn test_match_deletion() {
+        // the dispatching kernel (e.g. with AVX) agrees exactly with the sequential one
+        let mut rng = StdRng::seed_from_u64(42);
+        let transitions = Transitions {
+            open: 0.1,
+            extend: 0.2,
+            no_gap: 0.8,
+            no_extend: 0.8,
+        };
+        for n in 0..20 {
+            let mut random = |len| (0..len).map(|_| rng.gen::<f64>()).collect::<Vec<_>>();
+            let (emission, fm_prev, fx_prev, fy) =
+                (random(n), random(n + 1), random(n + 1), random(n + 1));
+            let mut curr = [vec![0.0; n + 1], vec![0.0; n + 1]];
+            let mut expected = curr.clone();
+            let [fm_curr, fx_curr] = &mut curr;
+            transitions.match_deletion(&emission, &fm_prev, &fx_prev, &fy, fm_curr, fx_curr);
+            let [fm_curr, fx_curr] = &mut expected;
+            transitions
+                .match_deletion_from(0, &emission, &fm_prev, &fx_prev, &fy, fm_curr, fx_curr);
+            assert_eq!(curr, expected);
+        }
+    }
+
+    #[test]
+    fn test_underflow() {
+        // a long read that does not fit the haplotype at all
+        let haplotype = b"A".repeat(500);
+        let read = b"C".repeat(1000);
+        let quals = vec![60; 1000];
+        let mut engine = ReadLikelihood::new(PhredGapParameters::default());
+        let p = engine.prob(&read, &quals, &haplotype);
+        assert!(*p < -1500.0);
+        assert_eq!(engine.prob_fast(&read, &quals, &haplotype), p);
+    }
+
+    #[test]
+    fn test_banded() {
+        let haplotype = b"ACGTTGACCAGTAGGCATCAGGATTACA";
+        let read = b"GACCAGTAGGCATC";
+        let quals = [25; 14];
+        let mut engine = ReadLikelihood::new(PhredGapParameters::default());
+        let p = engine.prob(read, &quals, haplotype);
+        let mut banded = ReadLikelihood::new(PhredGapParameters::default()).max_edit_dist(Some(2));
+        assert!((*banded.prob(read, &quals, haplotype) - *p).abs() < 1e-3);
+    }
+}
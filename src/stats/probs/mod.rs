@@ -40,6 +40,16 @@ fn ln_1m_exp(p: f64) -> f64 {
     }
 }
 
+/// Exact sum of probabilities in log-space. Unlike `LogProb::ln_sum_exp`, this does not
+/// approximate the exponential function, which matters e.g. for p-values close to 1.
+pub(crate) fn ln_sum_exp_exact(probs: &[f64]) -> LogProb {
+    let max = probs.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    if max == f64::NEG_INFINITY {
+        return LogProb::ln_zero();
+    }
+    LogProb(max + probs.iter().map(|p| (p - max).exp()).sum::<f64>().ln())
+}
+
 custom_derive! {
     /// A newtype for probabilities.
     ///
@@ -84,6 +94,26 @@ impl Prob {
             Err(Error::InvalidProb { prob: p })
         }
     }
+
+    /// Normalize the given probabilities such that they sum up to 1. If all probabilities are
+    /// zero, they are returned unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bio::stats::Prob;
+    ///
+    /// let probs = Prob::normalize(&[Prob(0.5), Prob(1.5)]);
+    /// assert_eq!(probs, [Prob(0.25), Prob(0.75)]);
+    /// ```
+    pub fn normalize(probs: &[Prob]) -> Vec<Prob> {
+        let total: f64 = probs.iter().map(|p| **p).sum();
+        if total == 0.0 {
+            probs.to_vec()
+        } else {
+            probs.iter().map(|&p| Prob(*p / total)).collect()
+        }
+    }
 }
 
 custom_derive! {
@@ -292,6 +322,34 @@ impl LogProb {
         }
     }
 
+    /// Numerically stable normalization of probabilities in log-space, such that they sum up to
+    /// 1. If all probabilities are zero, they are returned unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// #[macro_use]
+    /// extern crate approx;
+    /// # extern crate bio;
+    /// # fn main() {
+    /// use bio::stats::{LogProb, Prob};
+    ///
+    /// // unnormalized likelihoods, e.g. of the genotypes of a variant
+    /// let likelihoods = [LogProb(-1000.0), LogProb(-1001.0), LogProb::ln_zero()];
+    /// let posteriors = LogProb::ln_normalize(&likelihoods);
+    /// assert_relative_eq!(*Prob::from(LogProb::ln_sum_exp(&posteriors)), 1.0, epsilon = 1e-6);
+    /// assert_eq!(posteriors[2], LogProb::ln_zero());
+    /// # }
+    /// ```
+    pub fn ln_normalize(probs: &[LogProb]) -> Vec<LogProb> {
+        let total = Self::ln_sum_exp(probs);
+        if total == Self::ln_zero() {
+            probs.to_vec()
+        } else {
+            probs.iter().map(|&p| p - total).collect()
+        }
+    }
+
     /// Calculate the cumulative sum of the given probabilities in a numerically stable way (Durbin 1998).
     pub fn ln_cumsum_exp<I: IntoIterator<Item = LogProb>>(probs: I) -> ScanIter<I> {
         probs
@@ -585,6 +643,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_normalize() {
+        let probs = [
+            LogProb(0.2f64.ln()),
+            LogProb(0.6f64.ln()),
+            LogProb::ln_zero(),
+        ];
+        let normalized = LogProb::ln_normalize(&probs);
+        assert_relative_eq!(*normalized[0], 0.25f64.ln(), epsilon = 0.000001);
+        assert_relative_eq!(*normalized[1], 0.75f64.ln(), epsilon = 0.000001);
+        assert_eq!(normalized[2], LogProb::ln_zero());
+
+        let zeros = [LogProb::ln_zero(); 2];
+        assert_eq!(LogProb::ln_normalize(&zeros), zeros);
+        assert_eq!(Prob::normalize(&[Prob(0.0); 2]), [Prob(0.0); 2]);
+        assert!(LogProb::ln_normalize(&[]).is_empty());
+    }
+
     #[test]
     fn test_zero() {
         assert!(LogProb::zero().is_zero());
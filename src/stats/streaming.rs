@@ -0,0 +1,435 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Descriptive statistics over streams of values in constant memory, e.g. for QC metrics like
+//! read lengths, qualities or insert sizes over unbounded record streams.
+//!
+//! * `Moments` calculates count, mean, variance, minimum and maximum with Welford's algorithm.
+//! * `P2Quantile` estimates a quantile with the P² algorithm (Jain and Chlamtac, Communications
+//!   of the ACM, 1985), using five markers.
+//! * `Histogram` counts values in bins of equal width.
+//!
+//! `Moments` and `Histogram` can be merged, such that a stream can be processed in chunks by
+//! multiple threads.
+//!
+//! # Example
+//!
+//! ```
+//! use approx::assert_relative_eq;
+//! use bio::stats::streaming::{Histogram, Moments, P2Quantile};
+//!
+//! let read_lengths = [151.0, 149.0, 150.0, 151.0, 98.0, 151.0, 151.0, 120.0];
+//!
+//! let mut moments = Moments::new();
+//! let mut median = P2Quantile::new(0.5);
+//! let mut histogram = Histogram::new(0.0, 200.0, 4);
+//! for &len in &read_lengths {
+//!     moments.insert(len);
+//!     median.insert(len);
+//!     histogram.insert(len);
+//! }
+//! assert_relative_eq!(moments.mean().unwrap(), 140.125);
+//! assert_eq!(moments.max(), Some(151.0));
+//! assert_eq!(median.quantile(), Some(150.0));
+//! assert_eq!(histogram.bins(), &[0, 1, 2, 5]);
+//! ```
+
+use std::f64;
+use std::iter::FromIterator;
+
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum Error {
+    #[error("histograms with different ranges or numbers of bins cannot be merged")]
+    IncompatibleHistograms,
+}
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Count, mean, variance, minimum and maximum of a stream of values.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct Moments {
+    count: u64,
+    mean: f64,
+    /// Sum of squared differences from the mean.
+    m2: f64,
+    min: f64,
+    max: f64,
+}
+
+impl Moments {
+    /// Create empty statistics.
+    pub fn new() -> Self {
+        Moments::default()
+    }
+
+    /// Add a value.
+    pub fn insert(&mut self, value: f64) {
+        if self.count == 0 {
+            self.min = value;
+            self.max = value;
+        } else {
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    /// Merge the statistics of another stream into these (Chan et al., 1979).
+    pub fn merge(&mut self, other: &Moments) {
+        if other.count == 0 {
+            return;
+        }
+        if self.count == 0 {
+            *self = *other;
+            return;
+        }
+        let count = self.count + other.count;
+        let delta = other.mean - self.mean;
+        self.mean += delta * other.count as f64 / count as f64;
+        self.m2 += other.m2 + delta * delta * self.count as f64 * other.count as f64 / count as f64;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        self.count = count;
+    }
+
+    /// Number of values.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Mean of the values, if any.
+    pub fn mean(&self) -> Option<f64> {
+        if self.count > 0 {
+            Some(self.mean)
+        } else {
+            None
+        }
+    }
+
+    /// Sample variance of the values, if there are at least two.
+    pub fn variance(&self) -> Option<f64> {
+        if self.count > 1 {
+            Some(self.m2 / (self.count - 1) as f64)
+        } else {
+            None
+        }
+    }
+
+    /// Sample standard deviation of the values, if there are at least two.
+    pub fn std_dev(&self) -> Option<f64> {
+        self.variance().map(f64::sqrt)
+    }
+
+    /// Minimum of the values, if any.
+    pub fn min(&self) -> Option<f64> {
+        self.mean().map(|_| self.min)
+    }
+
+    /// Maximum of the values, if any.
+    pub fn max(&self) -> Option<f64> {
+        self.mean().map(|_| self.max)
+    }
+}
+
+impl Extend<f64> for Moments {
+    fn extend<I: IntoIterator<Item = f64>>(&mut self, values: I) {
+        for value in values {
+            self.insert(value);
+        }
+    }
+}
+
+impl FromIterator<f64> for Moments {
+    fn from_iter<I: IntoIterator<Item = f64>>(values: I) -> Self {
+        let mut moments = Moments::new();
+        moments.extend(values);
+        moments
+    }
+}
+
+/// Estimator of a quantile of a stream of values with the P² algorithm. Until five values have
+/// been seen, the quantile is exact.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct P2Quantile {
+    p: f64,
+    count: u64,
+    /// Heights of the markers, i.e. the estimated minimum, p/2, p, (1+p)/2 quantiles and
+    /// maximum. Until five values have been seen, the values themselves.
+    heights: [f64; 5],
+    /// Actual positions of the markers.
+    positions: [f64; 5],
+    /// Desired positions of the markers.
+    desired: [f64; 5],
+    /// Increments of the desired positions per value.
+    increments: [f64; 5],
+}
+
+impl P2Quantile {
+    /// Create an estimator for the p-quantile.
+    ///
+    /// # Panics
+    ///
+    /// If p is not within [0, 1].
+    pub fn new(p: f64) -> Self {
+        assert!((0.0..=1.0).contains(&p), "p must be within [0, 1]");
+        P2Quantile {
+            p,
+            count: 0,
+            heights: [0.0; 5],
+            positions: [1.0, 2.0, 3.0, 4.0, 5.0],
+            desired: [1.0, 1.0 + 2.0 * p, 1.0 + 4.0 * p, 3.0 + 2.0 * p, 5.0],
+            increments: [0.0, p / 2.0, p, (1.0 + p) / 2.0, 1.0],
+        }
+    }
+
+    /// Number of values.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Add a value.
+    pub fn insert(&mut self, value: f64) {
+        if self.count < 5 {
+            self.heights[self.count as usize] = value;
+            self.count += 1;
+            if self.count == 5 {
+                self.heights
+                    .sort_by(|a, b| a.partial_cmp(b).expect("NaN values are not supported"));
+            }
+            return;
+        }
+        self.count += 1;
+
+        // find the cell of the value, and extend the extreme markers if necessary
+        let q = &mut self.heights;
+        let k = if value < q[0] {
+            q[0] = value;
+            0
+        } else if value >= q[4] {
+            q[4] = value;
+            3
+        } else {
+            (1..5).find(|&i| value < q[i]).unwrap() - 1
+        };
+        for position in &mut self.positions[k + 1..] {
+            *position += 1.0;
+        }
+        for (desired, increment) in self.desired.iter_mut().zip(&self.increments) {
+            *desired += increment;
+        }
+
+        // adjust the heights of the inner markers
+        for i in 1..4 {
+            let n = &mut self.positions;
+            let d = self.desired[i] - n[i];
+            if (d >= 1.0 && n[i + 1] - n[i] > 1.0) || (d <= -1.0 && n[i - 1] - n[i] < -1.0) {
+                let d = d.signum();
+                let parabolic = q[i]
+                    + d / (n[i + 1] - n[i - 1])
+                        * ((n[i] - n[i - 1] + d) * (q[i + 1] - q[i]) / (n[i + 1] - n[i])
+                            + (n[i + 1] - n[i] - d) * (q[i] - q[i - 1]) / (n[i] - n[i - 1]));
+                q[i] = if q[i - 1] < parabolic && parabolic < q[i + 1] {
+                    parabolic
+                } else {
+                    // linear prediction
+                    let j = if d > 0.0 { i + 1 } else { i - 1 };
+                    q[i] + d * (q[j] - q[i]) / (n[j] - n[i])
+                };
+                n[i] += d;
+            }
+        }
+    }
+
+    /// The estimated quantile, if any values have been seen.
+    pub fn quantile(&self) -> Option<f64> {
+        match self.count {
+            0 => None,
+            count if count < 5 => {
+                let mut values = self.heights[..count as usize].to_vec();
+                values.sort_by(|a, b| a.partial_cmp(b).expect("NaN values are not supported"));
+                // nearest rank
+                let rank = (self.p * count as f64).ceil().max(1.0) as usize;
+                Some(values[rank - 1])
+            }
+            _ => Some(self.heights[2]),
+        }
+    }
+}
+
+/// Histogram with bins of equal width over the interval [min, max), counting values outside of
+/// the interval separately.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Histogram {
+    min: f64,
+    max: f64,
+    bins: Vec<u64>,
+    underflow: u64,
+    overflow: u64,
+}
+
+impl Histogram {
+    /// Create a histogram with the given number of bins over the interval [min, max).
+    ///
+    /// # Panics
+    ///
+    /// If the interval is empty or there are no bins.
+    pub fn new(min: f64, max: f64, bins: usize) -> Self {
+        assert!(min < max, "interval must not be empty");
+        assert!(bins > 0, "there must be at least one bin");
+        Histogram {
+            min,
+            max,
+            bins: vec![0; bins],
+            underflow: 0,
+            overflow: 0,
+        }
+    }
+
+    /// Add a value.
+    pub fn insert(&mut self, value: f64) {
+        if value < self.min {
+            self.underflow += 1;
+        } else if value >= self.max {
+            self.overflow += 1;
+        } else {
+            let width = (self.max - self.min) / self.bins.len() as f64;
+            // guard against rounding up at the upper end
+            let bin = (((value - self.min) / width) as usize).min(self.bins.len() - 1);
+            self.bins[bin] += 1;
+        }
+    }
+
+    /// Add the counts of another histogram with the same bins to this one.
+    pub fn merge(&mut self, other: &Histogram) -> Result<()> {
+        if self.min != other.min || self.max != other.max || self.bins.len() != other.bins.len() {
+            return Err(Error::IncompatibleHistograms);
+        }
+        for (count, other) in self.bins.iter_mut().zip(&other.bins) {
+            *count += other;
+        }
+        self.underflow += other.underflow;
+        self.overflow += other.overflow;
+        Ok(())
+    }
+
+    /// The counts of the bins.
+    pub fn bins(&self) -> &[u64] {
+        &self.bins
+    }
+
+    /// The interval [start, end) of the given bin.
+    pub fn bin_range(&self, bin: usize) -> (f64, f64) {
+        let width = (self.max - self.min) / self.bins.len() as f64;
+        (
+            self.min + bin as f64 * width,
+            self.min + (bin + 1) as f64 * width,
+        )
+    }
+
+    /// Number of values below the interval.
+    pub fn underflow(&self) -> u64 {
+        self.underflow
+    }
+
+    /// Number of values at or above the end of the interval.
+    pub fn overflow(&self) -> u64 {
+        self.overflow
+    }
+
+    /// Total number of values, including those outside of the interval.
+    pub fn count(&self) -> u64 {
+        self.bins.iter().sum::<u64>() + self.underflow + self.overflow
+    }
+}
+
+impl Extend<f64> for Histogram {
+    fn extend<I: IntoIterator<Item = f64>>(&mut self, values: I) {
+        for value in values {
+            self.insert(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::prelude::*;
+
+    #[test]
+    fn test_moments() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let values: Vec<f64> = (0..1000).map(|_| rng.gen_range(-5.0..20.0)).collect();
+        let n = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / n;
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0);
+
+        let moments: Moments = values.iter().copied().collect();
+        assert_eq!(moments.count(), 1000);
+        assert_relative_eq!(moments.mean().unwrap(), mean, max_relative = 1e-12);
+        assert_relative_eq!(moments.variance().unwrap(), variance, max_relative = 1e-12);
+
+        // merging chunks yields the same statistics
+        let mut merged = Moments::new();
+        for chunk in values.chunks(77) {
+            merged.merge(&chunk.iter().copied().collect());
+        }
+        merged.merge(&Moments::new());
+        assert_eq!(merged.count(), 1000);
+        assert_relative_eq!(merged.mean().unwrap(), mean, max_relative = 1e-12);
+        assert_relative_eq!(merged.variance().unwrap(), variance, max_relative = 1e-12);
+        assert_eq!(merged.min(), moments.min());
+        assert_eq!(merged.max(), moments.max());
+
+        let mut single = Moments::new();
+        assert_eq!(single.mean(), None);
+        single.insert(3.0);
+        assert_eq!(single.mean(), Some(3.0));
+        assert_eq!(single.variance(), None);
+    }
+
+    #[test]
+    fn test_p2_quantile() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let values: Vec<f64> = (0..10_000).map(|_| rng.gen_range(0.0..100.0)).collect();
+        for &p in &[0.1, 0.5, 0.9, 0.99] {
+            let mut estimator = P2Quantile::new(p);
+            for &value in &values {
+                estimator.insert(value);
+            }
+            assert_relative_eq!(estimator.quantile().unwrap(), p * 100.0, epsilon = 1.0);
+        }
+
+        let mut estimator = P2Quantile::new(0.5);
+        assert_eq!(estimator.quantile(), None);
+        for &value in &[5.0, 1.0, 3.0] {
+            estimator.insert(value);
+        }
+        assert_eq!(estimator.quantile(), Some(3.0));
+    }
+
+    #[test]
+    fn test_histogram() {
+        let mut histogram = Histogram::new(0.0, 1.0, 10);
+        histogram.extend(vec![-0.5, 0.0, 0.05, 0.15, 0.999, 1.0, 2.0]);
+        assert_eq!(histogram.bins(), &[2, 1, 0, 0, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(histogram.underflow(), 1);
+        assert_eq!(histogram.overflow(), 2);
+        assert_eq!(histogram.count(), 7);
+        assert_relative_eq!(histogram.bin_range(1).0, 0.1);
+
+        let mut other = Histogram::new(0.0, 1.0, 10);
+        other.insert(0.55);
+        histogram.merge(&other).unwrap();
+        assert_eq!(histogram.bins()[5], 1);
+        assert_eq!(
+            histogram.merge(&Histogram::new(0.0, 1.0, 5)),
+            Err(Error::IncompatibleHistograms)
+        );
+    }
+}
@@ -0,0 +1,13 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Helpers shared by the unit tests.
+
+use rand::prelude::*;
+
+/// A random sequence of the given length over the given alphabet.
+pub(crate) fn random_seq<R: Rng>(rng: &mut R, alphabet: &[u8], len: usize) -> Vec<u8> {
+    (0..len).map(|_| *alphabet.choose(rng).unwrap()).collect()
+}
@@ -0,0 +1,45 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Sequence variants in the representation of VCF records, i.e. a position with a reference
+//! allele and one or more alternative alleles, and their normalization against a reference
+//! genome. For reading and writing VCF/BCF files, have a look at
+//! [rust-htslib](https://docs.rs/rust-htslib).
+
+pub mod normalize;
+
+/// A variant given by its position and alleles, as in a VCF record. In contrast to VCF, the
+/// position is 0-based.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Variant {
+    /// Name of the reference sequence.
+    pub contig: String,
+    /// Position of the first base of the reference allele.
+    pub pos: u64,
+    pub ref_allele: Vec<u8>,
+    pub alt_alleles: Vec<Vec<u8>>,
+}
+
+impl Variant {
+    /// Create a new variant.
+    pub fn new(contig: &str, pos: u64, ref_allele: &[u8], alt_alleles: &[&[u8]]) -> Self {
+        Variant {
+            contig: contig.to_owned(),
+            pos,
+            ref_allele: ref_allele.to_vec(),
+            alt_alleles: alt_alleles.iter().map(|allele| allele.to_vec()).collect(),
+        }
+    }
+
+    /// Whether the variant has exactly one alternative allele.
+    pub fn is_biallelic(&self) -> bool {
+        self.alt_alleles.len() == 1
+    }
+
+    /// End position of the reference allele (exclusive).
+    pub fn end(&self) -> u64 {
+        self.pos + self.ref_allele.len() as u64
+    }
+}
@@ -0,0 +1,465 @@
+// Copyright 2021 Johannes Köster.
+// Licensed under the MIT license (http://opensource.org/licenses/MIT)
+// This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Normalization of variants against a reference genome, such that each variant has a unique
+//! representation: a variant is normalized if it is parsimonious, i.e. represented with as few
+//! bases as possible, and left-aligned, i.e. shifted as far to the left as possible without
+//! changing the alleles it represents (Tan et al., Unified representation of genetic variants,
+//! Bioinformatics, 2015). This is the representation used by most variant callers and
+//! databases, and a prerequisite for comparing variants by position and alleles.
+//!
+//! As in VCF, alleles are kept non-empty: indels include the base preceding the event, or the
+//! base following it if the event starts at the beginning of the reference sequence.
+//! Multi-allelic variants are normalized jointly, they can be split into bi-allelic ones with
+//! [`split_multiallelic`](fn.split_multiallelic.html) before.
+//!
+//! The reference is read from an indexed FASTA file. Only the bases around the variant are
+//! fetched, extending the window as needed while shifting through repeats.
+//!
+//! # Example
+//!
+//! ```
+//! use bio::io::fasta;
+//! use bio::variant::normalize;
+//! use bio::variant::Variant;
+//! use std::io;
+//!
+//! const FASTA: &[u8] = b">chr1\nTTGCACACACAGTTAAAAACG\n";
+//! const FAI: &[u8] = b"chr1\t21\t6\t21\t22\n";
+//! let mut reader = fasta::IndexedReader::new(io::Cursor::new(FASTA), FAI).unwrap();
+//!
+//! // deletion of CA at the end of the CACACACA repeat
+//! let variant = Variant::new("chr1", 9, b"CAG", &[b"G"]);
+//! assert!(normalize::is_parsimonious(&variant));
+//! assert_eq!(
+//!     normalize::normalize(&variant, &mut reader).unwrap(),
+//!     Variant::new("chr1", 2, b"GCA", &[b"G"])
+//! );
+//! ```
+
+use std::io;
+
+use thiserror::Error;
+
+use crate::io::fasta;
+use crate::variant::Variant;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("can't read reference sequence")]
+    ReadError(#[from] io::Error),
+
+    #[error("unknown reference sequence '{0}'")]
+    UnknownContig(String),
+
+    #[error(
+        "reference allele {ref_allele} at {contig}:{pos} does not match reference sequence {reference}"
+    )]
+    ReferenceMismatch {
+        contig: String,
+        pos: u64,
+        ref_allele: String,
+        reference: String,
+    },
+
+    #[error("unsupported allele '{0}', only sequences of bases can be normalized")]
+    UnsupportedAllele(String),
+
+    #[error("variant at {contig}:{pos} has no alternative allele")]
+    MissingAlternativeAllele { contig: String, pos: u64 },
+
+    #[error(
+        "alternative alleles of variant at {contig}:{pos} are identical to the reference allele"
+    )]
+    NoVariation { contig: String, pos: u64 },
+}
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Normalize the given variant, i.e. left-align and trim it against the reference, following
+/// the algorithm of Tan et al. (2015). The reference allele has to match the reference
+/// sequence (case-insensitive), and at least one alternative allele has to differ from it. Bases
+/// added from the reference are given as in the FASTA file.
+pub fn normalize<R: io::Read + io::Seek>(
+    variant: &Variant,
+    reader: &mut fasta::IndexedReader<R>,
+) -> Result<Variant> {
+    if variant.alt_alleles.is_empty() {
+        return Err(Error::MissingAlternativeAllele {
+            contig: variant.contig.clone(),
+            pos: variant.pos,
+        });
+    }
+    for allele in Some(&variant.ref_allele)
+        .into_iter()
+        .chain(&variant.alt_alleles)
+    {
+        if allele.is_empty() || !allele.iter().all(u8::is_ascii_alphabetic) {
+            return Err(Error::UnsupportedAllele(
+                String::from_utf8_lossy(allele).into_owned(),
+            ));
+        }
+    }
+
+    // such variants could be shifted through the entire reference sequence
+    if variant
+        .alt_alleles
+        .iter()
+        .all(|allele| allele.eq_ignore_ascii_case(&variant.ref_allele))
+    {
+        return Err(Error::NoVariation {
+            contig: variant.contig.clone(),
+            pos: variant.pos,
+        });
+    }
+
+    let mut reference = Reference::new(reader, &variant.contig, variant.pos, variant.end())?;
+    if !reference.seq.eq_ignore_ascii_case(&variant.ref_allele) {
+        return Err(Error::ReferenceMismatch {
+            contig: variant.contig.clone(),
+            pos: variant.pos,
+            ref_allele: String::from_utf8_lossy(&variant.ref_allele).into_owned(),
+            reference: String::from_utf8_lossy(&reference.seq).into_owned(),
+        });
+    }
+
+    let mut pos = variant.pos;
+    let mut alleles: Vec<Vec<u8>> = Some(&variant.ref_allele)
+        .into_iter()
+        .chain(&variant.alt_alleles)
+        .cloned()
+        .collect();
+
+    // trim the last base while it is shared by all alleles, extending to the left whenever an
+    // allele becomes empty
+    loop {
+        if alleles.iter().all(|allele| !allele.is_empty()) && shared_last_base(&alleles) {
+            for allele in &mut alleles {
+                allele.pop();
+            }
+        } else if alleles.iter().any(|allele| allele.is_empty()) {
+            if pos > 0 {
+                pos -= 1;
+                let base = reference.base(pos)?;
+                for allele in &mut alleles {
+                    allele.insert(0, base);
+                }
+            } else {
+                // at the start of the reference, use the following base instead
+                let base = reference.base(alleles[0].len() as u64)?;
+                for allele in &mut alleles {
+                    allele.push(base);
+                }
+                break;
+            }
+        } else {
+            break;
+        }
+    }
+
+    // trim the first base while it is shared by all alleles
+    while alleles.iter().all(|allele| allele.len() >= 2) && shared_first_base(&alleles) {
+        for allele in &mut alleles {
+            allele.remove(0);
+        }
+        pos += 1;
+    }
+
+    let alt_alleles = alleles.split_off(1);
+    Ok(Variant {
+        contig: variant.contig.clone(),
+        pos,
+        ref_allele: alleles.pop().unwrap(),
+        alt_alleles,
+    })
+}
+
+/// Check whether the given variant is normalized, i.e. parsimonious and left-aligned.
+pub fn is_normalized<R: io::Read + io::Seek>(
+    variant: &Variant,
+    reader: &mut fasta::IndexedReader<R>,
+) -> Result<bool> {
+    Ok(normalize(variant, reader)? == *variant)
+}
+
+/// Check whether the given variant is parsimonious, i.e. it cannot be represented with fewer
+/// bases without an allele becoming empty: unless one of the alleles consists of a single base,
+/// the alleles do neither all end nor all start with the same base. In contrast to
+/// [`is_normalized`](fn.is_normalized.html), this does not require the reference.
+pub fn is_parsimonious(variant: &Variant) -> bool {
+    let alleles: Vec<Vec<u8>> = Some(&variant.ref_allele)
+        .into_iter()
+        .chain(&variant.alt_alleles)
+        .cloned()
+        .collect();
+    if alleles.iter().any(|allele| allele.is_empty()) {
+        return false;
+    }
+    alleles.iter().any(|allele| allele.len() == 1)
+        || !(shared_last_base(&alleles) || shared_first_base(&alleles))
+}
+
+/// Split the given variant into one bi-allelic variant per alternative allele. The resulting
+/// variants are not trimmed, they can be made parsimonious with
+/// [`normalize`](fn.normalize.html).
+pub fn split_multiallelic(variant: &Variant) -> Vec<Variant> {
+    variant
+        .alt_alleles
+        .iter()
+        .map(|alt_allele| Variant {
+            contig: variant.contig.clone(),
+            pos: variant.pos,
+            ref_allele: variant.ref_allele.clone(),
+            alt_alleles: vec![alt_allele.clone()],
+        })
+        .collect()
+}
+
+fn shared_last_base(alleles: &[Vec<u8>]) -> bool {
+    let last = alleles[0][alleles[0].len() - 1];
+    alleles
+        .iter()
+        .all(|allele| allele[allele.len() - 1].eq_ignore_ascii_case(&last))
+}
+
+fn shared_first_base(alleles: &[Vec<u8>]) -> bool {
+    alleles
+        .iter()
+        .all(|allele| allele[0].eq_ignore_ascii_case(&alleles[0][0]))
+}
+
+/// A window of a reference sequence that is extended on demand.
+struct Reference<'a, R: io::Read + io::Seek> {
+    reader: &'a mut fasta::IndexedReader<R>,
+    contig: &'a str,
+    len: u64,
+    start: u64,
+    seq: Vec<u8>,
+}
+
+impl<'a, R: io::Read + io::Seek> Reference<'a, R> {
+    /// Minimal number of bases fetched when extending the window.
+    const CHUNK: u64 = 64;
+
+    fn new(
+        reader: &'a mut fasta::IndexedReader<R>,
+        contig: &'a str,
+        start: u64,
+        end: u64,
+    ) -> Result<Self> {
+        let len = reader
+            .index
+            .sequences()
+            .into_iter()
+            .find(|sequence| sequence.name == contig)
+            .ok_or_else(|| Error::UnknownContig(contig.to_owned()))?
+            .len;
+        let mut reference = Reference {
+            reader,
+            contig,
+            len,
+            start,
+            seq: Vec::new(),
+        };
+        reference.seq = reference.fetch(start, end.min(len))?;
+        Ok(reference)
+    }
+
+    fn fetch(&mut self, start: u64, end: u64) -> Result<Vec<u8>> {
+        let mut seq = Vec::new();
+        self.reader.fetch(self.contig, start, end)?;
+        self.reader.read(&mut seq)?;
+        Ok(seq)
+    }
+
+    /// The base at the given position, which has to be within the reference sequence.
+    fn base(&mut self, pos: u64) -> Result<u8> {
+        let end = self.start + self.seq.len() as u64;
+        if pos < self.start {
+            // grow exponentially, so that long repeats need few reads
+            let chunk = Self::CHUNK.max(self.seq.len() as u64).max(self.start - pos);
+            let start = self.start.saturating_sub(chunk);
+            let mut seq = self.fetch(start, self.start)?;
+            seq.extend_from_slice(&self.seq);
+            self.seq = seq;
+            self.start = start;
+        } else if pos >= end {
+            let stop = (pos + Self::CHUNK).min(self.len);
+            let seq = self.fetch(end, stop)?;
+            self.seq.extend_from_slice(&seq);
+        }
+        Ok(self.seq[(pos - self.start) as usize])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FASTA: &[u8] = b">chr1\nTTGCACACACAGTTAAAAACG\n>chr2\nAAAACGT\n";
+    const FAI: &[u8] = b"chr1\t21\t6\t21\t22\nchr2\t7\t34\t7\t8\n";
+
+    fn reader() -> fasta::IndexedReader<io::Cursor<&'static [u8]>> {
+        fasta::IndexedReader::new(io::Cursor::new(FASTA), FAI).unwrap()
+    }
+
+    fn normalized(variant: Variant) -> Variant {
+        normalize(&variant, &mut reader()).unwrap()
+    }
+
+    #[test]
+    fn test_normalize_deletion() {
+        let expected = Variant::new("chr1", 2, b"GCA", &[b"G"]);
+        assert_eq!(
+            normalized(Variant::new("chr1", 9, b"CAG", &[b"G"])),
+            expected
+        );
+        // right-shifted and not parsimonious
+        assert_eq!(
+            normalized(Variant::new("chr1", 7, b"CACAGT", &[b"CAGT"])),
+            expected
+        );
+        assert_eq!(normalized(expected.clone()), expected);
+        assert!(is_normalized(&expected, &mut reader()).unwrap());
+        assert!(!is_normalized(&Variant::new("chr1", 9, b"CAG", &[b"G"]), &mut reader()).unwrap());
+    }
+
+    #[test]
+    fn test_normalize_insertion() {
+        assert_eq!(
+            normalized(Variant::new("chr1", 17, b"A", &[b"AA"])),
+            Variant::new("chr1", 13, b"T", &[b"TA"])
+        );
+        // insertion of CA at the end of the repeat
+        assert_eq!(
+            normalized(Variant::new("chr1", 10, b"A", &[b"ACA"])),
+            Variant::new("chr1", 2, b"G", &[b"GCA"])
+        );
+    }
+
+    #[test]
+    fn test_normalize_substitution() {
+        assert_eq!(
+            normalized(Variant::new("chr1", 3, b"CAC", &[b"CTC"])),
+            Variant::new("chr1", 4, b"A", &[b"T"])
+        );
+        let mnp = Variant::new("chr1", 3, b"CA", &[b"GT"]);
+        assert_eq!(normalized(mnp.clone()), mnp);
+    }
+
+    #[test]
+    fn test_normalize_multiallelic() {
+        let variant = Variant::new("chr1", 9, b"CAG", &[b"G", b"CACAG"]);
+        assert_eq!(
+            normalized(variant.clone()),
+            Variant::new("chr1", 2, b"GCA", &[b"G", b"GCACA"])
+        );
+        let split = split_multiallelic(&variant);
+        assert_eq!(
+            split,
+            [
+                Variant::new("chr1", 9, b"CAG", &[b"G"]),
+                Variant::new("chr1", 9, b"CAG", &[b"CACAG"]),
+            ]
+        );
+        assert!(split.iter().all(Variant::is_biallelic));
+        assert_eq!(
+            normalized(split[1].clone()),
+            Variant::new("chr1", 2, b"G", &[b"GCA"])
+        );
+    }
+
+    #[test]
+    fn test_normalize_start() {
+        // deletions within a repeat at the start of the reference use the following base
+        let expected = Variant::new("chr2", 0, b"AA", &[b"A"]);
+        assert_eq!(
+            normalized(Variant::new("chr2", 2, b"AA", &[b"A"])),
+            expected
+        );
+        assert_eq!(
+            normalized(Variant::new("chr2", 0, b"AAAAC", &[b"AAAC"])),
+            expected
+        );
+        assert_eq!(normalized(expected.clone()), expected);
+    }
+
+    #[test]
+    fn test_normalize_long_repeat() {
+        // repeats longer than the initially fetched window
+        let repeat = b"CA".repeat(100);
+        let fasta = [b">chr1\nG".as_ref(), &repeat, b"T\n"].concat();
+        let fai = b"chr1\t202\t6\t202\t203\n";
+        let mut reader = fasta::IndexedReader::new(io::Cursor::new(fasta), fai.as_ref()).unwrap();
+        let variant = Variant::new("chr1", 199, b"CAT", &[b"T"]);
+        assert_eq!(
+            normalize(&variant, &mut reader).unwrap(),
+            Variant::new("chr1", 0, b"GCA", &[b"G"])
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut reader = reader();
+        assert!(matches!(
+            normalize(&Variant::new("chr1", 9, b"CTG", &[b"G"]), &mut reader),
+            Err(Error::ReferenceMismatch { .. })
+        ));
+        assert!(matches!(
+            normalize(&Variant::new("chr3", 9, b"CAG", &[b"G"]), &mut reader),
+            Err(Error::UnknownContig(_))
+        ));
+        assert!(matches!(
+            normalize(&Variant::new("chr1", 9, b"C", &[b"<DEL>"]), &mut reader),
+            Err(Error::UnsupportedAllele(_))
+        ));
+        assert!(matches!(
+            normalize(&Variant::new("chr1", 9, b"C", &[]), &mut reader),
+            Err(Error::MissingAlternativeAllele { .. })
+        ));
+        assert!(matches!(
+            normalize(
+                &Variant::new("chr1", 9, b"CAG", &[b"CAG", b"cag"]),
+                &mut reader
+            ),
+            Err(Error::NoVariation { .. })
+        ));
+        // lowercase reference alleles are accepted
+        assert_eq!(
+            normalize(&Variant::new("chr1", 9, b"cag", &[b"g"]), &mut reader).unwrap(),
+            Variant::new("chr1", 2, b"GCA", &[b"G"])
+        );
+    }
+
+    #[test]
+    fn test_is_parsimonious() {
+        assert!(is_parsimonious(&Variant::new("chr1", 0, b"A", &[b"T"])));
+        assert!(is_parsimonious(&Variant::new("chr1", 0, b"GCA", &[b"G"])));
+        assert!(is_parsimonious(&Variant::new("chr1", 0, b"AC", &[b"GT"])));
+        assert!(!is_parsimonious(&Variant::new(
+            "chr1",
+            0,
+            b"CAG",
+            &[b"CTG"]
+        )));
+        assert!(!is_parsimonious(&Variant::new(
+            "chr1",
+            0,
+            b"GCA",
+            &[b"GCT", b"GA"]
+        )));
+        assert!(!is_parsimonious(&Variant::new(
+            "chr1",
+            0,
+            b"GA",
+            &[b"GCA", b"GT"]
+        )));
+        assert!(is_parsimonious(&Variant::new(
+            "chr1",
+            0,
+            b"G",
+            &[b"GA", b"T"]
+        )));
+    }
+}